@@ -53,8 +53,16 @@ enum AppColors {
         case .command: return .blue
         case .fileAccess: return .purple
         case .network: return .cyan
+        case .networkClose: return .cyan
         case .process: return .indigo
         case .session: return .gray
+        case .configReload: return .teal
+        case .resource: return .pink
+        case .anomaly: return .mint
+        case .fileSummary: return .purple
+        case .listen: return .orange
+        case .reverseShell: return .red
+        case .proxyRequest(_, _, _, let blocked): return blocked ? .red : .cyan
         }
     }
 
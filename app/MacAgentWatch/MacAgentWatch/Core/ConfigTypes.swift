@@ -17,7 +17,13 @@ struct MonitoringConfig {
     var trackChildren: Bool = true
     var trackingPollMs: UInt64 = 100
     var fsDebounceMs: UInt64 = 100
+    var fsBurstThreshold: UInt32 = 50
+    var fsIgnore: [String] = []
+    var fsSnapshot: Bool = false
     var netPollMs: UInt64 = 500
+    var netUploadAlertMb: UInt64 = 10
+    var netProxy: Bool = false
+    var netProxyBlock: Bool = false
     var watchPaths: [String] = []
     var sensitivePatterns: [String] = [".env", ".env.*", "*.pem", "*.key", "*credential*", "*secret*"]
     var networkWhitelist: [String] = ["api.anthropic.com", "github.com", "api.github.com"]
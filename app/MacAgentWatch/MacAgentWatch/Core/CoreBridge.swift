@@ -157,6 +157,50 @@ final class CoreBridge {
         }
     }
 
+    // MARK: - Live Session Subscription
+
+    /// Push appended session events instead of polling `getLatestEvents`.
+    /// Callbacks run on the main actor; keep the returned subscription alive
+    /// for as long as updates are wanted.
+    func subscribeToSession(
+        path: String,
+        sinceIndex: UInt32,
+        onEvents: @escaping @MainActor ([MonitoringEvent]) -> Void,
+        onReset: @escaping @MainActor () -> Void
+    ) -> FfiSessionSubscription {
+        let listener = SessionListener(onEvents: onEvents, onReset: onReset)
+        return FfiSessionSubscription(path: path, sinceIndex: sinceIndex, listener: listener)
+    }
+
+    private final class SessionListener: FfiSessionListener, @unchecked Sendable {
+        private let onEventsHandler: @MainActor ([MonitoringEvent]) -> Void
+        private let onResetHandler: @MainActor () -> Void
+
+        init(
+            onEvents: @escaping @MainActor ([MonitoringEvent]) -> Void,
+            onReset: @escaping @MainActor () -> Void
+        ) {
+            self.onEventsHandler = onEvents
+            self.onResetHandler = onReset
+        }
+
+        func onEvents(events: [FfiEvent]) {
+            Task { @MainActor in
+                self.onEventsHandler(events.map { CoreBridge.convertEvent($0) })
+            }
+        }
+
+        func onReset() {
+            Task { @MainActor in
+                self.onResetHandler()
+            }
+        }
+
+        func onError(message: String) {
+            print("[CoreBridge] Warning: session subscription stopped: \(message)")
+        }
+    }
+
     // MARK: - v0.5.0 Config Save
 
     func saveConfig(_ config: AppConfig) {
@@ -232,6 +276,7 @@ final class CoreBridge {
         case .start: return .start
         case .exit: return .exit
         case .fork: return .fork
+        case .detach: return .detach
         }
     }
 
@@ -242,6 +287,27 @@ final class CoreBridge {
         }
     }
 
+    private static func convertResourceMetric(_ ffiMetric: FfiResourceMetric) -> ResourceMetric {
+        switch ffiMetric {
+        case .processes: return .processes
+        case .spawnRate: return .spawnRate
+        case .cpu: return .cpu
+        case .memory: return .memory
+        case .memoryGrowth: return .memoryGrowth
+        case .threads: return .threads
+        case .openFiles: return .openFiles
+        }
+    }
+
+    private static func convertAnomalyKind(_ ffiKind: FfiAnomalyKind) -> AnomalyKind {
+        switch ffiKind {
+        case .newHosts: return .newHosts
+        case .newCommands: return .newCommands
+        case .fileRate: return .fileRate
+        case .sessionLength: return .sessionLength
+        }
+    }
+
     private static func convertEventType(_ ffiEventType: FfiEventType) -> EventType {
         switch ffiEventType {
         case .command(let command, let args, let exitCode):
@@ -250,10 +316,34 @@ final class CoreBridge {
             return .fileAccess(path: path, action: convertFileAction(action))
         case .network(let host, let port, let proto):
             return .network(host: host, port: port, protocol: proto)
+        case .networkClose(let host, let port, let proto, let durationMs, let bytesSent, let bytesReceived):
+            return .networkClose(
+                host: host, port: port, protocol: proto,
+                durationMs: durationMs, bytesSent: bytesSent, bytesReceived: bytesReceived
+            )
         case .process(let pid, let ppid, let action):
             return .process(pid: pid, ppid: ppid, action: convertProcessAction(action))
         case .session(let action):
             return .session(action: convertSessionAction(action))
+        case .configReload(let path, let changes):
+            return .configReload(
+                path: path,
+                changes: changes.map {
+                    ConfigChange(key: $0.key, oldValue: $0.oldValue, newValue: $0.newValue)
+                }
+            )
+        case .resource(let metric, let value, let limit):
+            return .resource(metric: convertResourceMetric(metric), value: value, limit: limit)
+        case .anomaly(let kind, let score, let explanation):
+            return .anomaly(kind: convertAnomalyKind(kind), score: score, explanation: explanation)
+        case .fileSummary(let path, let action, let count):
+            return .fileSummary(path: path, action: convertFileAction(action), count: count)
+        case .listen(let address, let port, let proto):
+            return .listen(address: address, port: port, protocol: proto)
+        case .reverseShell(let host, let port, let proto):
+            return .reverseShell(host: host, port: port, protocol: proto)
+        case .proxyRequest(let method, let host, let port, let blocked):
+            return .proxyRequest(method: method, host: host, port: port, blocked: blocked)
         }
     }
 
@@ -282,7 +372,13 @@ final class CoreBridge {
         config.monitoring.trackChildren = ffiConfig.monitoring.trackChildren
         config.monitoring.trackingPollMs = ffiConfig.monitoring.trackingPollMs
         config.monitoring.fsDebounceMs = ffiConfig.monitoring.fsDebounceMs
+        config.monitoring.fsBurstThreshold = ffiConfig.monitoring.fsBurstThreshold
+        config.monitoring.fsIgnore = ffiConfig.monitoring.fsIgnore
+        config.monitoring.fsSnapshot = ffiConfig.monitoring.fsSnapshot
         config.monitoring.netPollMs = ffiConfig.monitoring.netPollMs
+        config.monitoring.netUploadAlertMb = ffiConfig.monitoring.netUploadAlertMb
+        config.monitoring.netProxy = ffiConfig.monitoring.netProxy
+        config.monitoring.netProxyBlock = ffiConfig.monitoring.netProxyBlock
         config.monitoring.watchPaths = ffiConfig.monitoring.watchPaths
         config.monitoring.sensitivePatterns = ffiConfig.monitoring.sensitivePatterns
         config.monitoring.networkWhitelist = ffiConfig.monitoring.networkWhitelist
@@ -317,9 +413,33 @@ final class CoreBridge {
         summary.highCount = Int(ffiSummary.highCount)
         summary.mediumCount = Int(ffiSummary.mediumCount)
         summary.lowCount = Int(ffiSummary.lowCount)
+        summary.traffic = ffiSummary.traffic.map(convertCategoryTraffic)
         return summary
     }
 
+    private static func convertCategoryTraffic(_ ffiTraffic: FfiCategoryTraffic) -> CategoryTraffic {
+        let category: ProviderCategory
+        switch ffiTraffic.category {
+        case .llm: category = .llm
+        case .packageRegistry: category = .packageRegistry
+        case .vcs: category = .vcs
+        case .other: category = .other
+        }
+        return CategoryTraffic(
+            category: category,
+            requests: Int(ffiTraffic.requests),
+            closed: Int(ffiTraffic.closed),
+            durationMs: ffiTraffic.durationMs,
+            bytesSent: ffiTraffic.bytesSent,
+            bytesReceived: ffiTraffic.bytesReceived,
+            first: ffiTraffic.firstMs.map { Date(timeIntervalSince1970: TimeInterval($0) / 1000.0) },
+            last: ffiTraffic.lastMs.map { Date(timeIntervalSince1970: TimeInterval($0) / 1000.0) },
+            providers: ffiTraffic.providers
+                .map { (name: $0.name, requests: Int($0.requests)) }
+                .sorted { $0.requests > $1.requests }
+        )
+    }
+
     private static func convertChartDataPoint(_ ffiPoint: FfiChartDataPoint) -> ChartDataPoint {
         let timestamp = Date(timeIntervalSince1970: TimeInterval(ffiPoint.timestampMs) / 1000.0)
         return ChartDataPoint(
@@ -358,6 +478,7 @@ final class CoreBridge {
         case .start: return .start
         case .exit: return .exit
         case .fork: return .fork
+        case .detach: return .detach
         }
     }
 
@@ -368,6 +489,27 @@ final class CoreBridge {
         }
     }
 
+    private static func convertToFfiResourceMetric(_ metric: ResourceMetric) -> FfiResourceMetric {
+        switch metric {
+        case .processes: return .processes
+        case .spawnRate: return .spawnRate
+        case .cpu: return .cpu
+        case .memory: return .memory
+        case .memoryGrowth: return .memoryGrowth
+        case .threads: return .threads
+        case .openFiles: return .openFiles
+        }
+    }
+
+    private static func convertToFfiAnomalyKind(_ kind: AnomalyKind) -> FfiAnomalyKind {
+        switch kind {
+        case .newHosts: return .newHosts
+        case .newCommands: return .newCommands
+        case .fileRate: return .fileRate
+        case .sessionLength: return .sessionLength
+        }
+    }
+
     private static func convertToFfiEventType(_ eventType: EventType) -> FfiEventType {
         switch eventType {
         case .command(let command, let args, let exitCode):
@@ -376,10 +518,34 @@ final class CoreBridge {
             return .fileAccess(path: path, action: convertToFfiFileAction(action))
         case .network(let host, let port, let proto):
             return .network(host: host, port: port, protocol: proto)
+        case .networkClose(let host, let port, let proto, let durationMs, let bytesSent, let bytesReceived):
+            return .networkClose(
+                host: host, port: port, protocol: proto,
+                durationMs: durationMs, bytesSent: bytesSent, bytesReceived: bytesReceived
+            )
         case .process(let pid, let ppid, let action):
             return .process(pid: pid, ppid: ppid, action: convertToFfiProcessAction(action))
         case .session(let action):
             return .session(action: convertToFfiSessionAction(action))
+        case .configReload(let path, let changes):
+            return .configReload(
+                path: path,
+                changes: changes.map {
+                    FfiConfigChange(key: $0.key, oldValue: $0.oldValue, newValue: $0.newValue)
+                }
+            )
+        case .resource(let metric, let value, let limit):
+            return .resource(metric: convertToFfiResourceMetric(metric), value: value, limit: limit)
+        case .anomaly(let kind, let score, let explanation):
+            return .anomaly(kind: convertToFfiAnomalyKind(kind), score: score, explanation: explanation)
+        case .fileSummary(let path, let action, let count):
+            return .fileSummary(path: path, action: convertToFfiFileAction(action), count: count)
+        case .listen(let address, let port, let proto):
+            return .listen(address: address, port: port, protocol: proto)
+        case .reverseShell(let host, let port, let proto):
+            return .reverseShell(host: host, port: port, protocol: proto)
+        case .proxyRequest(let method, let host, let port, let blocked):
+            return .proxyRequest(method: method, host: host, port: port, blocked: blocked)
         }
     }
 
@@ -418,7 +584,13 @@ final class CoreBridge {
             trackChildren: config.monitoring.trackChildren,
             trackingPollMs: config.monitoring.trackingPollMs,
             fsDebounceMs: config.monitoring.fsDebounceMs,
+            fsBurstThreshold: config.monitoring.fsBurstThreshold,
+            fsIgnore: config.monitoring.fsIgnore,
+            fsSnapshot: config.monitoring.fsSnapshot,
             netPollMs: config.monitoring.netPollMs,
+            netUploadAlertMb: config.monitoring.netUploadAlertMb,
+            netProxy: config.monitoring.netProxy,
+            netProxyBlock: config.monitoring.netProxyBlock,
             watchPaths: config.monitoring.watchPaths,
             sensitivePatterns: config.monitoring.sensitivePatterns,
             networkWhitelist: config.monitoring.networkWhitelist
@@ -49,27 +49,79 @@ enum FileAction: String, Codable {
 }
 
 enum ProcessAction: String, Codable {
-    case start, exit, fork
+    case start, exit, fork, detach
 }
 
 enum SessionAction: String, Codable {
     case start, end
 }
 
+enum ResourceMetric: String, Codable {
+    case processes
+    case spawnRate = "spawn_rate"
+    case cpu, memory
+    case memoryGrowth = "memory_growth"
+    case threads
+    case openFiles = "open_files"
+
+    func format(_ value: UInt64) -> String {
+        switch self {
+        case .cpu:
+            return "\(value)%"
+        case .memory, .memoryGrowth:
+            return ByteCountFormatter.string(fromByteCount: Int64(value), countStyle: .memory)
+        default:
+            return "\(value)"
+        }
+    }
+}
+
+enum AnomalyKind: String, Codable {
+    case newHosts = "new_hosts"
+    case newCommands = "new_commands"
+    case fileRate = "file_rate"
+    case sessionLength = "session_length"
+}
+
+struct ConfigChange: Hashable {
+    let key: String
+    let oldValue: String?
+    let newValue: String?
+}
+
 enum EventType {
     case command(command: String, args: [String], exitCode: Int32?)
     case fileAccess(path: String, action: FileAction)
     case network(host: String, port: UInt16, protocol: String)
+    case networkClose(
+        host: String, port: UInt16, protocol: String,
+        durationMs: UInt64, bytesSent: UInt64?, bytesReceived: UInt64?
+    )
     case process(pid: UInt32, ppid: UInt32?, action: ProcessAction)
     case session(action: SessionAction)
+    case configReload(path: String, changes: [ConfigChange])
+    case resource(metric: ResourceMetric, value: UInt64, limit: UInt64)
+    case anomaly(kind: AnomalyKind, score: UInt32, explanation: String)
+    case fileSummary(path: String, action: FileAction, count: UInt64)
+    case listen(address: String, port: UInt16, protocol: String)
+    case reverseShell(host: String, port: UInt16, protocol: String)
+    case proxyRequest(method: String, host: String, port: UInt16, blocked: Bool)
 
     var icon: String {
         switch self {
         case .command: return "terminal"
         case .fileAccess: return "doc"
         case .network: return "network"
+        case .networkClose: return "network.slash"
         case .process: return "gearshape.2"
         case .session: return "play.circle"
+        case .configReload: return "arrow.triangle.2.circlepath"
+        case .resource: return "gauge.with.dots.needle.67percent"
+        case .anomaly: return "waveform.path.ecg"
+        case .fileSummary: return "doc.on.doc"
+        case .listen: return "antenna.radiowaves.left.and.right"
+        case .reverseShell: return "exclamationmark.triangle"
+        case .proxyRequest(_, _, _, let blocked): return blocked ? "hand.raised" : "arrow.up.forward"
         }
     }
 
@@ -81,10 +133,30 @@ enum EventType {
             return "\(action.rawValue): \(path)"
         case .network(let host, let port, let proto):
             return "\(proto)://\(host):\(port)"
+        case .networkClose(let host, let port, let proto, let durationMs, let bytesSent, _):
+            let seconds = String(format: "%.1fs", Double(durationMs) / 1000)
+            let sent = bytesSent.map {
+                ", sent " + ByteCountFormatter.string(fromByteCount: Int64($0), countStyle: .binary)
+            } ?? ""
+            return "Closed \(proto)://\(host):\(port) after \(seconds)\(sent)"
         case .process(let pid, _, let action):
             return "\(action.rawValue) (PID: \(pid))"
         case .session(let action):
             return "Session \(action.rawValue)"
+        case .configReload(_, let changes):
+            return "Config reloaded: \(changes.map(\.key).joined(separator: ", "))"
+        case .resource(let metric, let value, let limit):
+            return "\(metric.rawValue): \(metric.format(value)) (limit \(metric.format(limit)))"
+        case .anomaly(_, _, let explanation):
+            return explanation
+        case .fileSummary(let path, let action, let count):
+            return "\(action.rawValue): \(count) files under \(path)/"
+        case .listen(let address, let port, let proto):
+            return "Listening on \(proto)://\(address):\(port)"
+        case .reverseShell(let host, let port, let proto):
+            return "Shell stdio connected to \(proto)://\(host):\(port)"
+        case .proxyRequest(let method, let host, let port, let blocked):
+            return "\(method) \(host):\(port)" + (blocked ? " (blocked)" : "")
         }
     }
 
@@ -99,8 +171,16 @@ enum EventType {
         case .command: return "[CMD]"
         case .fileAccess: return "[FILE]"
         case .network: return "[NET]"
+        case .networkClose: return "[NET]"
         case .process: return "[PROC]"
         case .session: return "[SES]"
+        case .configReload: return "[CFG]"
+        case .resource: return "[RES]"
+        case .anomaly: return "[ANOM]"
+        case .fileSummary: return "[FILES]"
+        case .listen: return "[LISTEN]"
+        case .reverseShell: return "[SHELL]"
+        case .proxyRequest: return "[PROXY]"
         }
     }
 
@@ -109,8 +189,16 @@ enum EventType {
         case .command: return String(localized: "event.type.command")
         case .fileAccess: return String(localized: "event.type.fileAccess")
         case .network: return String(localized: "event.type.network")
+        case .networkClose: return String(localized: "event.type.networkClose")
         case .process: return String(localized: "event.type.process")
         case .session: return String(localized: "event.type.session")
+        case .configReload: return String(localized: "event.type.configReload")
+        case .resource: return String(localized: "event.type.resource")
+        case .anomaly: return String(localized: "event.type.anomaly")
+        case .fileSummary: return String(localized: "event.type.fileSummary")
+        case .listen: return String(localized: "event.type.listen")
+        case .reverseShell: return String(localized: "event.type.reverseShell")
+        case .proxyRequest: return String(localized: "event.type.proxyRequest")
         }
     }
 }
@@ -125,12 +213,33 @@ struct MonitoringEvent: Identifiable {
     let alert: Bool
 }
 
+enum ProviderCategory: String, CaseIterable {
+    case llm
+    case packageRegistry
+    case vcs
+    case other
+}
+
+struct CategoryTraffic {
+    let category: ProviderCategory
+    let requests: Int
+    let closed: Int
+    let durationMs: UInt64
+    let bytesSent: UInt64
+    let bytesReceived: UInt64
+    let first: Date?
+    let last: Date?
+    /// Requests per provider name, most requested first
+    let providers: [(name: String, requests: Int)]
+}
+
 struct ActivitySummary {
     var totalEvents: Int = 0
     var criticalCount: Int = 0
     var highCount: Int = 0
     var mediumCount: Int = 0
     var lowCount: Int = 0
+    var traffic: [CategoryTraffic] = []
 
     static let empty = ActivitySummary()
 }
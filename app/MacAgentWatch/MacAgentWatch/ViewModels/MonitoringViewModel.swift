@@ -41,6 +41,7 @@ final class MonitoringViewModel {
     }
 
     private var autoRetryTimer: Timer?
+    private var liveSubscription: FfiSessionSubscription?
 
     var selectedTheme: AppThemeMode {
         get {
@@ -223,16 +224,43 @@ final class MonitoringViewModel {
     func pollLatestEvents() {
         guard let session = selectedSession else { return }
         let newEvents = bridge.getLatestEvents(path: session.filePath, sinceIndex: liveEventIndex)
-        if !newEvents.isEmpty {
-            events.append(contentsOf: newEvents)
-            liveEventIndex += UInt32(newEvents.count)
-            trimEvents()
-            activitySummary = bridge.getActivitySummary(events: events)
-            recentAlerts = events.filter { $0.alert }.prefix(5).map { $0 }
-
-            for event in newEvents {
-                notificationManager.sendNotification(for: event)
+        appendLiveEvents(newEvents)
+    }
+
+    /// Receive appended events for the selected session as they are written,
+    /// replacing `pollLatestEvents`.
+    func startLiveSubscription() {
+        stopLiveSubscription()
+        guard let session = selectedSession else { return }
+        liveSubscription = bridge.subscribeToSession(
+            path: session.filePath,
+            sinceIndex: liveEventIndex,
+            onEvents: { [weak self] newEvents in
+                self?.appendLiveEvents(newEvents)
+            },
+            onReset: { [weak self] in
+                // The subscription redelivers the file from its first event
+                self?.events = []
+                self?.liveEventIndex = 0
             }
+        )
+    }
+
+    func stopLiveSubscription() {
+        liveSubscription?.cancel()
+        liveSubscription = nil
+    }
+
+    private func appendLiveEvents(_ newEvents: [MonitoringEvent]) {
+        guard !newEvents.isEmpty else { return }
+        events.append(contentsOf: newEvents)
+        liveEventIndex += UInt32(newEvents.count)
+        trimEvents()
+        activitySummary = bridge.getActivitySummary(events: events)
+        recentAlerts = events.filter { $0.alert }.prefix(5).map { $0 }
+
+        for event in newEvents {
+            notificationManager.sendNotification(for: event)
         }
     }
 
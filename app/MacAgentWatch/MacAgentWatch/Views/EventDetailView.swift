@@ -117,10 +117,29 @@ struct EventDetailView: View {
             fileAccessSection(path: path, action: action)
         case .network(let host, let port, let proto):
             networkSection(host: host, port: port, proto: proto)
+        case .networkClose(let host, let port, let proto, let durationMs, let bytesSent, let bytesReceived):
+            networkCloseSection(
+                host: host, port: port, proto: proto,
+                durationMs: durationMs, bytesSent: bytesSent, bytesReceived: bytesReceived
+            )
         case .process(let pid, let ppid, let action):
             processSection(pid: pid, ppid: ppid, action: action)
         case .session(let action):
             sessionSection(action: action)
+        case .configReload(let path, let changes):
+            configReloadSection(path: path, changes: changes)
+        case .resource(let metric, let value, let limit):
+            resourceSection(metric: metric, value: value, limit: limit)
+        case .anomaly(let kind, let score, let explanation):
+            anomalySection(kind: kind, score: score, explanation: explanation)
+        case .fileSummary(let path, let action, let count):
+            fileSummarySection(path: path, action: action, count: count)
+        case .listen(let address, let port, let proto):
+            listenSection(address: address, port: port, proto: proto)
+        case .reverseShell(let host, let port, let proto):
+            reverseShellSection(host: host, port: port, proto: proto)
+        case .proxyRequest(let method, let host, let port, let blocked):
+            proxyRequestSection(method: method, host: host, port: port, blocked: blocked)
         }
     }
 
@@ -173,6 +192,27 @@ struct EventDetailView: View {
         }
     }
 
+    // MARK: - File Summary Details
+
+    private func fileSummarySection(path: String, action: FileAction, count: UInt64) -> some View {
+        VStack(alignment: .leading, spacing: fieldSpacing) {
+            Text(String(localized: "detail.section.fileSummary"))
+                .font(.subheadline.weight(.semibold))
+                .foregroundStyle(.secondary)
+
+            detailRow(
+                label: String(localized: "detail.directory"),
+                value: path,
+                copyable: true
+            )
+            detailRow(
+                label: String(localized: "detail.action"),
+                value: action.rawValue.capitalized
+            )
+            detailRow(label: String(localized: "detail.count"), value: "\(count)")
+        }
+    }
+
     // MARK: - Network Details
 
     private func networkSection(host: String, port: UInt16, proto: String) -> some View {
@@ -197,6 +237,127 @@ struct EventDetailView: View {
         }
     }
 
+    // MARK: - Network Close Details
+
+    private func networkCloseSection(
+        host: String, port: UInt16, proto: String,
+        durationMs: UInt64, bytesSent: UInt64?, bytesReceived: UInt64?
+    ) -> some View {
+        VStack(alignment: .leading, spacing: fieldSpacing) {
+            Text(String(localized: "detail.section.networkClose"))
+                .font(.subheadline.weight(.semibold))
+                .foregroundStyle(.secondary)
+
+            detailRow(
+                label: String(localized: "detail.host"),
+                value: host,
+                copyable: true
+            )
+            detailRow(
+                label: String(localized: "detail.port"),
+                value: String(port)
+            )
+            detailRow(
+                label: String(localized: "detail.protocol"),
+                value: proto
+            )
+            detailRow(
+                label: String(localized: "detail.duration"),
+                value: String(format: "%.2fs", Double(durationMs) / 1000)
+            )
+            if let bytesSent {
+                detailRow(
+                    label: String(localized: "detail.bytesSent"),
+                    value: ByteCountFormatter.string(fromByteCount: Int64(bytesSent), countStyle: .binary)
+                )
+            }
+            if let bytesReceived {
+                detailRow(
+                    label: String(localized: "detail.bytesReceived"),
+                    value: ByteCountFormatter.string(fromByteCount: Int64(bytesReceived), countStyle: .binary)
+                )
+            }
+        }
+    }
+
+    // MARK: - Listen Details
+
+    private func listenSection(address: String, port: UInt16, proto: String) -> some View {
+        VStack(alignment: .leading, spacing: fieldSpacing) {
+            Text(String(localized: "detail.section.listen"))
+                .font(.subheadline.weight(.semibold))
+                .foregroundStyle(.secondary)
+
+            detailRow(
+                label: String(localized: "detail.address"),
+                value: address,
+                copyable: true
+            )
+            detailRow(
+                label: String(localized: "detail.port"),
+                value: String(port)
+            )
+            detailRow(
+                label: String(localized: "detail.protocol"),
+                value: proto
+            )
+        }
+    }
+
+    // MARK: - Reverse Shell Details
+
+    private func reverseShellSection(host: String, port: UInt16, proto: String) -> some View {
+        VStack(alignment: .leading, spacing: fieldSpacing) {
+            Text(String(localized: "detail.section.reverseShell"))
+                .font(.subheadline.weight(.semibold))
+                .foregroundStyle(.secondary)
+
+            detailRow(
+                label: String(localized: "detail.remoteHost"),
+                value: host,
+                copyable: true
+            )
+            detailRow(
+                label: String(localized: "detail.port"),
+                value: String(port)
+            )
+            detailRow(
+                label: String(localized: "detail.protocol"),
+                value: proto
+            )
+        }
+    }
+
+    // MARK: - Proxy Request Details
+
+    private func proxyRequestSection(method: String, host: String, port: UInt16, blocked: Bool) -> some View {
+        VStack(alignment: .leading, spacing: fieldSpacing) {
+            Text(String(localized: "detail.section.proxyRequest"))
+                .font(.subheadline.weight(.semibold))
+                .foregroundStyle(.secondary)
+
+            detailRow(
+                label: String(localized: "detail.method"),
+                value: method
+            )
+            detailRow(
+                label: String(localized: "detail.host"),
+                value: host,
+                copyable: true
+            )
+            detailRow(
+                label: String(localized: "detail.port"),
+                value: String(port)
+            )
+            detailRow(
+                label: String(localized: "detail.blocked"),
+                value: blocked
+                    ? String(localized: "detail.alert.yes")
+                    : String(localized: "detail.alert.no")
+            )
+        }
+    }
+
     // MARK: - Process Details
 
     private func processSection(pid: UInt32, ppid: UInt32?, action: ProcessAction) -> some View {
@@ -237,6 +398,56 @@ struct EventDetailView: View {
         }
     }
 
+    // MARK: - Config Reload Details
+
+    private func configReloadSection(path: String, changes: [ConfigChange]) -> some View {
+        VStack(alignment: .leading, spacing: fieldSpacing) {
+            Text(String(localized: "detail.section.configReload"))
+                .font(.subheadline.weight(.semibold))
+                .foregroundStyle(.secondary)
+
+            detailRow(
+                label: String(localized: "detail.path"),
+                value: path,
+                copyable: true
+            )
+            ForEach(changes, id: \.key) { change in
+                detailRow(
+                    label: change.key,
+                    value: "\(change.oldValue ?? String(localized: "detail.none")) → \(change.newValue ?? String(localized: "detail.none"))"
+                )
+            }
+        }
+    }
+
+    // MARK: - Resource Details
+
+    private func resourceSection(metric: ResourceMetric, value: UInt64, limit: UInt64) -> some View {
+        VStack(alignment: .leading, spacing: fieldSpacing) {
+            Text(String(localized: "detail.section.resource"))
+                .font(.subheadline.weight(.semibold))
+                .foregroundStyle(.secondary)
+
+            detailRow(label: String(localized: "detail.metric"), value: metric.rawValue)
+            detailRow(label: String(localized: "detail.value"), value: metric.format(value))
+            detailRow(label: String(localized: "detail.limit"), value: metric.format(limit))
+        }
+    }
+
+    // MARK: - Anomaly Details
+
+    private func anomalySection(kind: AnomalyKind, score: UInt32, explanation: String) -> some View {
+        VStack(alignment: .leading, spacing: fieldSpacing) {
+            Text(String(localized: "detail.section.anomaly"))
+                .font(.subheadline.weight(.semibold))
+                .foregroundStyle(.secondary)
+
+            detailRow(label: String(localized: "detail.kind"), value: kind.rawValue)
+            detailRow(label: String(localized: "detail.score"), value: "\(score)")
+            detailRow(label: String(localized: "detail.explanation"), value: explanation)
+        }
+    }
+
     // MARK: - Detail Row Helper
 
     private func detailRow(label: String, value: String, copyable: Bool = false) -> some View {
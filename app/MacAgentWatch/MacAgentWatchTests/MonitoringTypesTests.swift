@@ -88,6 +88,7 @@ final class MonitoringTypesTests: XCTestCase {
         XCTAssertEqual(ProcessAction.start.rawValue, "start")
         XCTAssertEqual(ProcessAction.exit.rawValue, "exit")
         XCTAssertEqual(ProcessAction.fork.rawValue, "fork")
+        XCTAssertEqual(ProcessAction.detach.rawValue, "detach")
     }
 
     // MARK: - SessionAction
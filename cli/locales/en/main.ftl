@@ -11,16 +11,46 @@ usage-subtitle = AI Agent Monitoring Tool
 usage-label = USAGE:
 usage-line1 = macagentwatch [OPTIONS] -- <COMMAND> [ARGS]...
 usage-line2 = macagentwatch analyze <COMMAND> [ARGS]...
+usage-line3 = macagentwatch config <init|validate|show|set KEY=VALUE>
+usage-line4 = macagentwatch attach <PID|AGENT>
+usage-line5 = macagentwatch diff <SESSION>
 examples-label = EXAMPLES:
 example-claude = macagentwatch -- claude-code "help me with this project"
 example-json = macagentwatch --format json -- cursor
 example-analyze = macagentwatch analyze rm -rf /tmp/cache
 example-watch = macagentwatch --watch ~/projects -- your-agent
+example-attach = macagentwatch --enable-netmon attach claude
 usage-help-hint = Run 'macagentwatch --help' for more options.
 
 ## Recording banner / footer
 banner-recording = MacAgentWatch Recording
 session-ended = Session ended (exit: { $exit_code })
+banner-attached = MacAgentWatch Attached to { $name } ({ $pid })
+attach-detach-hint = Press Ctrl-C to detach; the process keeps running.
+attach-detached = Detached from { $name } ({ $pid })
+attach-exited = { $name } ({ $pid }) exited
+
+## Session traffic, printed under the footer
+traffic-line = { $category }: { $requests } connections, { $first } to { $last }
+traffic-providers = ({ $providers })
+traffic-mean-duration = { $duration } average over { $closed } closed
+traffic-llm = LLM APIs
+traffic-package-registry = Package registries
+traffic-vcs = Code hosting
+traffic-other = Other hosts
+
+## Workspace changes, printed under the footer and by the diff command
+workspace-summary = Workspace: { $added } added, { $deleted } deleted, { $modified } modified, { $binary } binary or unrecorded
+workspace-unchanged = Workspace: no files changed
+workspace-diff-hint = Review with: macagentwatch diff { $session }
+diff-not-found = No workspace changes recorded for session '{ $session }' (record them with --snapshot or monitoring.fs_snapshot)
+diff-title = Workspace changes in session { $session }, { $start } to { $end }
+diff-truncated = The snapshot reached its file limit; some changes may be missing.
+diff-no-changes = No files changed
+diff-added = Added
+diff-deleted = Deleted
+diff-modified = Modified
+diff-binary = Binary or unrecorded
 
 ## Analyze command
 analyze-title = Command Analysis
@@ -29,6 +59,36 @@ analyze-risk-label = Risk Level:
 analyze-reason-label = Reason:
 analyze-danger-warning = This command may be dangerous!
 
+## Config command
+config-init-created = Created config file at { $path }
+config-init-exists = Config file already exists at { $path } (use --force to overwrite)
+config-init-failed = Failed to create config file at { $path }
+config-missing = No config file at { $path }; defaults are in use
+config-read-failed = Failed to read config file { $path }
+config-valid = { $path }: configuration is valid
+config-summary = { $errors } error(s), { $warnings } warning(s)
+config-severity-error = error
+config-severity-warning = warning
+config-set-format = Expected KEY=VALUE, got '{ $input }'
+config-set-done = Set { $key } = { $value } in { $path }
+
+## Cross-session queries
+query-no-database = No event database at { $path }. Run `macagentwatch import` to load existing session logs.
+query-no-results = No matching events
+query-invalid-time = Invalid time '{ $input }' (expected e.g. 30m, 12h, 7d, 2w, 2025-01-31 or an RFC 3339 timestamp)
+query-session-events = { $count } matching event(s)
+top-col-events = EVENTS
+top-col-sessions = SESSIONS
+trends-col-period = PERIOD
+import-dir = Importing session logs from { $path }
+import-summary = Imported { $imported } session(s) with { $events } event(s); { $skipped } already imported, { $failed } failed
+import-failed = Failed to import { $path }: { $error }
+
+## Sanitizer
+sanitize-input-label = Input:
+sanitize-output-label = Output:
+sanitize-nothing-masked = Nothing would be masked
+
 ## Risk levels
 risk-low = LOW
 risk-medium = MEDIUM
@@ -46,6 +106,11 @@ risk-curl-to-bash = Piping remote script to shell (curl | bash)
 risk-wget-to-bash = Piping remote script to shell (wget | bash)
 risk-curl-to-sh = Piping remote script to shell (curl | sh)
 risk-fork-bomb = Fork bomb detected
+risk-dev-tcp = Shell redirected to a TCP socket (reverse shell)
+risk-dev-udp = Shell redirected to a UDP socket (reverse shell)
+risk-netcat-exec = Netcat running a program on a connection (reverse or bind shell)
+risk-shell-to-netcat = Interactive shell piped through netcat (reverse shell)
+risk-pty-spawn = Spawning an interactive terminal shell
 risk-rm-rf = Recursive force delete
 risk-rm-recursive = Recursive delete
 risk-sudo = Privilege escalation
@@ -71,3 +136,4 @@ risk-custom-high = Custom high-risk command
 ## Errors
 error-no-command = No command specified
 error-pty-fallback = PTY failed ({ $error }), using simple mode
+error-ctrlc-handler = Failed to install the Ctrl-C handler
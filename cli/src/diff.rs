@@ -0,0 +1,185 @@
+//! `diff` subcommand: the workspace changes stored for a session
+
+use crate::i18n::{t, t_args};
+use crate::OutputFormat;
+use anyhow::{bail, Result};
+use chrono::{DateTime, Local, Utc};
+use colored::Colorize;
+use macagentwatch_core::{SegmentName, WorkspaceChanges};
+use std::path::{Path, PathBuf};
+
+/// Log directory and session ID of `session`, which is either a session ID
+/// or the path of one of the session's files
+fn locate(session: &str, log_dirs: &[PathBuf]) -> Option<(PathBuf, String)> {
+    let path = Path::new(session);
+    if path.is_file() {
+        let name = path.file_name()?.to_str()?;
+        let id = match SegmentName::parse(name) {
+            Some(segment) => segment.session_id,
+            None => {
+                let rest = name.strip_prefix("session-")?;
+                rest.strip_suffix(".patch")
+                    .or_else(|| rest.strip_suffix(".changes.json"))?
+                    .to_string()
+            }
+        };
+        let dir = path.parent().unwrap_or(Path::new(".")).to_path_buf();
+        return Some((dir, id));
+    }
+
+    let id = session.strip_prefix("session-").unwrap_or(session);
+    log_dirs.iter().find_map(|dir| {
+        let (list, _) = WorkspaceChanges::artifact_paths(dir, id);
+        list.exists().then(|| (dir.clone(), id.to_string()))
+    })
+}
+
+fn format_time(time: DateTime<Utc>) -> String {
+    time.with_timezone(&Local)
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string()
+}
+
+/// `diff`: the files a session added, deleted and changed, and the patch
+/// of the text changes unless `stat` is set
+pub fn diff_command(
+    session: &str,
+    log_dirs: &[PathBuf],
+    stat: bool,
+    format: OutputFormat,
+    no_color: bool,
+) -> Result<()> {
+    let Some((dir, id)) = locate(session, log_dirs) else {
+        bail!(t_args("diff-not-found", &[("session", session)]));
+    };
+    let changes = WorkspaceChanges::load(&dir, &id)?;
+
+    match format {
+        OutputFormat::Json => {
+            let mut value = serde_json::to_value(&changes)?;
+            value["session_id"] = serde_json::Value::String(id);
+            if !stat {
+                value["patch"] = serde_json::Value::String(changes.patch.clone());
+            }
+            println!("{}", serde_json::to_string_pretty(&value)?);
+        }
+        OutputFormat::Compact => {
+            let sections = [
+                ("A", &changes.added),
+                ("D", &changes.deleted),
+                ("M", &changes.modified),
+                ("B", &changes.binary_changed),
+            ];
+            for (tag, paths) in sections {
+                for path in paths {
+                    println!("{} {}", tag, path.display());
+                }
+            }
+            if !stat {
+                print!("{}", changes.patch);
+            }
+        }
+        OutputFormat::Pretty => print_pretty(&changes, &id, stat, no_color),
+    }
+    Ok(())
+}
+
+fn print_pretty(changes: &WorkspaceChanges, id: &str, stat: bool, no_color: bool) {
+    let title = t_args(
+        "diff-title",
+        &[
+            ("session", id),
+            ("start", &format_time(changes.started)),
+            ("end", &format_time(changes.ended)),
+        ],
+    );
+    if no_color {
+        println!("{}", title);
+    } else {
+        println!("{}", title.cyan().bold());
+    }
+    if changes.truncated {
+        println!("{}", t("diff-truncated"));
+    }
+    if changes.is_empty() {
+        println!("{}", t("diff-no-changes"));
+        return;
+    }
+
+    let sections = [
+        ("diff-added", '+', &changes.added),
+        ("diff-deleted", '-', &changes.deleted),
+        ("diff-modified", '~', &changes.modified),
+        ("diff-binary", '*', &changes.binary_changed),
+    ];
+    for (label, marker, paths) in sections {
+        if paths.is_empty() {
+            continue;
+        }
+        println!();
+        println!("{} ({})", t(label), paths.len());
+        for path in paths {
+            let line = format!("  {} {}", marker, path.display());
+            let line = match (no_color, marker) {
+                (true, _) => line,
+                (false, '+') => line.green().to_string(),
+                (false, '-') => line.red().to_string(),
+                (false, _) => line.yellow().to_string(),
+            };
+            println!("{}", line);
+        }
+    }
+
+    if stat || changes.patch.is_empty() {
+        return;
+    }
+    println!();
+    for line in changes.patch.lines() {
+        if no_color {
+            println!("{}", line);
+        } else {
+            println!("{}", patch_line_color(line));
+        }
+    }
+}
+
+fn patch_line_color(line: &str) -> String {
+    if line.starts_with("--- ") || line.starts_with("+++ ") {
+        line.bold().to_string()
+    } else if line.starts_with("@@") {
+        line.cyan().to_string()
+    } else if line.starts_with('+') {
+        line.green().to_string()
+    } else if line.starts_with('-') {
+        line.red().to_string()
+    } else {
+        line.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_locate_by_id_and_path() {
+        let first = TempDir::new().unwrap();
+        let second = TempDir::new().unwrap();
+        WorkspaceChanges::default()
+            .save(second.path(), "20250101-120000")
+            .unwrap();
+        let dirs = vec![first.path().to_path_buf(), second.path().to_path_buf()];
+
+        let expected = Some((second.path().to_path_buf(), "20250101-120000".to_string()));
+        assert_eq!(locate("20250101-120000", &dirs), expected);
+        assert_eq!(locate("session-20250101-120000", &dirs), expected);
+        assert_eq!(locate("20250102-090000", &dirs), None);
+
+        let (_, patch) = WorkspaceChanges::artifact_paths(second.path(), "20250101-120000");
+        assert_eq!(locate(&patch.to_string_lossy(), &[]), expected);
+        let log = second.path().join("session-20250101-120000.jsonl");
+        std::fs::write(&log, "").unwrap();
+        assert_eq!(locate(&log.to_string_lossy(), &[]), expected);
+    }
+}
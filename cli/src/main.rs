@@ -2,17 +2,24 @@
 //!
 //! Command-line interface for monitoring AI agents.
 
+mod diff;
 mod i18n;
+mod query;
 
 use anyhow::{Context, Result};
 use clap::{Parser, Subcommand, ValueEnum};
 use colored::Colorize;
 use i18n::{t, t_args};
 use macagentwatch_core::{
-    Config, LogFormat, LoggerConfig, NetworkWhitelist, ProcessWrapper, RiskLevel, RiskScorer,
-    WrapperConfig,
+    sanitize, AgentDetector, AgentProfile, AggregationConfig, AttachTarget, CategoryTraffic,
+    Config, ConfigDiagnostic, DiagnosticSeverity, LogFormat, LoggerConfig, NetworkWhitelist,
+    ProcessWrapper, ProviderCategory, RetentionPolicy, RiskLevel, RiskScorer, Sanitizer,
+    SensitiveFileDetector, SqliteStorage, TrafficSummary, WorkspaceChanges, WrapperConfig,
 };
+use query::{GroupByArg, QueryArgs, TrendBucket};
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 /// MacAgentWatch - AI Agent Monitoring Tool
 #[derive(Parser)]
@@ -48,6 +55,22 @@ struct Cli {
     #[arg(long)]
     no_track_children: bool,
 
+    /// Disable exec capture (fall back to detecting commands from shell prompts)
+    #[arg(long)]
+    no_exec_capture: bool,
+
+    /// Inject hooks into a wrapped bash, zsh or fish shell to log each command line with its exit code
+    #[arg(long)]
+    shell_hooks: bool,
+
+    /// Disable resource monitoring (CPU, memory, threads, open files, process spawns)
+    #[arg(long)]
+    no_resource_monitor: bool,
+
+    /// Don't compare the session against earlier sessions of the same agent
+    #[arg(long)]
+    no_baseline: bool,
+
     /// Polling interval for child process tracking (milliseconds)
     #[arg(long, default_value = "100")]
     tracking_poll_ms: u64,
@@ -56,10 +79,18 @@ struct Cli {
     #[arg(long)]
     enable_fswatch: bool,
 
+    /// Record the watched paths (or working directory) and save a patch of what the session changed
+    #[arg(long)]
+    snapshot: bool,
+
     /// Enable network monitoring
     #[arg(long)]
     enable_netmon: bool,
 
+    /// Route the wrapped command's HTTP(S) traffic through a local proxy that logs each hostname
+    #[arg(long)]
+    net_proxy: bool,
+
     /// Directory to save session logs
     #[arg(long)]
     log_dir: Option<PathBuf>,
@@ -88,6 +119,86 @@ enum Commands {
         #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
         args: Vec<String>,
     },
+    /// Manage the configuration file
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Search events across all recorded sessions
+    Search {
+        #[command(flatten)]
+        filter: QueryArgs,
+        /// List matching sessions instead of events
+        #[arg(long)]
+        sessions: bool,
+    },
+    /// Show the most frequent agents, hosts, commands or paths
+    Top {
+        /// What to group events by
+        #[arg(value_enum)]
+        by: GroupByArg,
+        #[command(flatten)]
+        filter: QueryArgs,
+    },
+    /// Show per-agent activity over time
+    Trends {
+        #[command(flatten)]
+        filter: QueryArgs,
+        /// Length of each period
+        #[arg(long, value_enum, default_value = "day")]
+        bucket: TrendBucket,
+    },
+    /// Import existing JSONL session logs into the event database
+    Import {
+        /// Log directories to import (default: the configured log directory)
+        dirs: Vec<PathBuf>,
+    },
+    /// Check the sensitive data masking rules
+    Sanitize {
+        #[command(subcommand)]
+        action: SanitizeAction,
+    },
+    /// Monitor an agent that is already running, until it exits or Ctrl-C
+    Attach {
+        /// PID or agent name (e.g. claude)
+        target: String,
+    },
+    /// Show the files a session changed, recorded with --snapshot
+    Diff {
+        /// Session ID, or the path of the session's log or patch
+        session: String,
+        /// List the changed files without the patch
+        #[arg(long)]
+        stat: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum SanitizeAction {
+    /// Show what would be masked in a command line, and by which rule
+    Test {
+        /// Text to sanitize, e.g. "deploy --vault-token s3cr3t"
+        input: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Create a commented sample config file
+    Init {
+        /// Overwrite an existing config file
+        #[arg(long)]
+        force: bool,
+    },
+    /// Check the config file and report problems with line numbers
+    Validate,
+    /// Print the effective configuration
+    Show,
+    /// Change a single setting (e.g. alerts.min_level=medium)
+    Set {
+        /// Setting as section.key=value
+        assignment: String,
+    },
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
@@ -137,6 +248,43 @@ fn main() -> Result<()> {
         Some(Commands::Analyze { command, args }) => {
             analyze_command(&command, &args, cli.format, cli.no_color)
         }
+        Some(Commands::Config { action }) => {
+            config_command(action, cli.config, cli.format, cli.no_color)
+        }
+        Some(Commands::Search {
+            ref filter,
+            sessions,
+        }) => {
+            let config = load_app_config(cli.config.as_deref())?;
+            query::search_command(&config, filter, sessions, logger_config(&cli))
+        }
+        Some(Commands::Top { by, ref filter }) => {
+            let config = load_app_config(cli.config.as_deref())?;
+            query::top_command(&config, by, filter, cli.format)
+        }
+        Some(Commands::Trends { ref filter, bucket }) => {
+            let config = load_app_config(cli.config.as_deref())?;
+            query::trends_command(&config, filter, bucket, cli.format)
+        }
+        Some(Commands::Import { ref dirs }) => {
+            let config = load_app_config(cli.config.as_deref())?;
+            let dirs = if dirs.is_empty() {
+                query::default_import_dirs(&config, cli.log_dir.as_deref())
+            } else {
+                dirs.clone()
+            };
+            query::import_command(&config, &dirs)
+        }
+        Some(Commands::Sanitize { action }) => {
+            let config = load_app_config(cli.config.as_deref())?;
+            sanitize_command(action, &config, cli.format, cli.no_color)
+        }
+        Some(Commands::Attach { ref target }) => attach_command(&cli, target),
+        Some(Commands::Diff { ref session, stat }) => {
+            let config = load_app_config(cli.config.as_deref())?;
+            let dirs = query::default_import_dirs(&config, cli.log_dir.as_deref());
+            diff::diff_command(session, &dirs, stat, cli.format, cli.no_color)
+        }
         None => {
             if cli.cmd.is_empty() {
                 print_usage();
@@ -166,12 +314,16 @@ fn print_usage() {
     println!("{}", t("usage-label").yellow());
     println!("    {}", t("usage-line1"));
     println!("    {}", t("usage-line2"));
+    println!("    {}", t("usage-line3"));
+    println!("    {}", t("usage-line4"));
+    println!("    {}", t("usage-line5"));
     println!();
     println!("{}", t("examples-label").yellow());
     println!("    {}", t("example-claude"));
     println!("    {}", t("example-json"));
     println!("    {}", t("example-analyze"));
     println!("    {}", t("example-watch"));
+    println!("    {}", t("example-attach"));
     println!();
     println!("{}", t("usage-help-hint"));
 }
@@ -278,10 +430,286 @@ fn analyze_command(
     Ok(())
 }
 
+fn config_command(
+    action: ConfigAction,
+    path: Option<PathBuf>,
+    format: OutputFormat,
+    no_color: bool,
+) -> Result<()> {
+    let path = match path {
+        Some(path) => path,
+        None => Config::default_path()?,
+    };
+    let path_str = path.display().to_string();
+
+    match action {
+        ConfigAction::Init { force } => {
+            Config::write_sample(&path, force).with_context(|| {
+                if path.exists() && !force {
+                    t_args("config-init-exists", &[("path", &path_str)])
+                } else {
+                    t_args("config-init-failed", &[("path", &path_str)])
+                }
+            })?;
+            println!("{}", t_args("config-init-created", &[("path", &path_str)]));
+        }
+        ConfigAction::Validate => {
+            if !path.exists() {
+                println!("{}", t_args("config-missing", &[("path", &path_str)]));
+                return Ok(());
+            }
+            let content = std::fs::read_to_string(&path)
+                .with_context(|| t_args("config-read-failed", &[("path", &path_str)]))?;
+            let diagnostics = Config::validate_toml(&content);
+            print_diagnostics(&diagnostics, &path_str, format, no_color)?;
+            if diagnostics.iter().any(ConfigDiagnostic::is_error) {
+                std::process::exit(1);
+            }
+        }
+        ConfigAction::Show => {
+            let config = load_config_file(&path)?;
+            match format {
+                OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&config)?),
+                _ => print!("{}", config.to_toml()?),
+            }
+        }
+        ConfigAction::Set { assignment } => {
+            let (key, value) = assignment
+                .split_once('=')
+                .map(|(k, v)| (k.trim(), v.trim()))
+                .with_context(|| t_args("config-set-format", &[("input", &assignment)]))?;
+            let mut config = load_config_file(&path)?;
+            config.set_value(key, value)?;
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            config.save(&path)?;
+            println!(
+                "{}",
+                t_args(
+                    "config-set-done",
+                    &[("key", key), ("value", value), ("path", &path_str)]
+                )
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn sanitize_command(
+    action: SanitizeAction,
+    config: &Config,
+    format: OutputFormat,
+    no_color: bool,
+) -> Result<()> {
+    let SanitizeAction::Test { input } = action;
+    let sanitizer = Sanitizer::from_config(&config.sanitize)?;
+    let output = sanitizer.sanitize_command_string(&input);
+    let redactions = sanitizer.explain(&input);
+
+    match format {
+        OutputFormat::Json => {
+            let masked: Vec<_> = redactions
+                .iter()
+                .map(|r| {
+                    serde_json::json!({
+                        "rule": r.rule,
+                        "trigger": r.trigger,
+                        "value": r.secret,
+                    })
+                })
+                .collect();
+            let result = serde_json::json!({
+                "input": input,
+                "output": output,
+                "masked": masked,
+            });
+            println!("{}", serde_json::to_string_pretty(&result)?);
+        }
+        OutputFormat::Compact => {
+            println!("{}", output);
+            for r in &redactions {
+                println!("[{}] {}", r.rule, r.secret);
+            }
+        }
+        OutputFormat::Pretty => {
+            println!("  {} {}", t("sanitize-input-label").dimmed(), input);
+            println!("  {} {}", t("sanitize-output-label").dimmed(), output);
+            println!();
+            if redactions.is_empty() {
+                println!("  {}", t("sanitize-nothing-masked"));
+            }
+            for r in &redactions {
+                let rule = match r.trigger {
+                    Some(ref trigger) => format!("{} ({})", r.rule, trigger),
+                    None => r.rule.clone(),
+                };
+                let rule = if no_color {
+                    rule
+                } else {
+                    rule.yellow().to_string()
+                };
+                println!("  {} {}", rule, r.secret);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Load the config given with `-c`, or the default config file if present
+fn load_app_config(path: Option<&std::path::Path>) -> Result<Config> {
+    match path {
+        Some(path) => Ok(Config::load_from_path(path)?),
+        None => load_config_file(&Config::default_path()?),
+    }
+}
+
+/// Event output settings from the global CLI flags
+fn logger_config(cli: &Cli) -> LoggerConfig {
+    LoggerConfig {
+        format: cli.format.into(),
+        min_level: cli.min_level.into(),
+        show_timestamps: !cli.no_timestamps,
+        use_colors: !cli.no_color,
+    }
+}
+
+/// Load a config file, falling back to defaults when it does not exist yet
+fn load_config_file(path: &std::path::Path) -> Result<Config> {
+    if path.exists() {
+        Ok(Config::load_from_path(path)?)
+    } else {
+        Ok(Config::default())
+    }
+}
+
+fn print_diagnostics(
+    diagnostics: &[ConfigDiagnostic],
+    path: &str,
+    format: OutputFormat,
+    no_color: bool,
+) -> Result<()> {
+    if format == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(diagnostics)?);
+        return Ok(());
+    }
+
+    if diagnostics.is_empty() {
+        println!("{}", t_args("config-valid", &[("path", path)]));
+        return Ok(());
+    }
+
+    for diag in diagnostics {
+        let severity = match diag.severity {
+            DiagnosticSeverity::Error => t("config-severity-error"),
+            DiagnosticSeverity::Warning => t("config-severity-warning"),
+        };
+        let severity = match (no_color, diag.severity) {
+            (true, _) => severity,
+            (false, DiagnosticSeverity::Error) => severity.red().bold().to_string(),
+            (false, DiagnosticSeverity::Warning) => severity.yellow().to_string(),
+        };
+        let location = match diag.line {
+            Some(line) => format!("{}:{}", path, line),
+            None => path.to_string(),
+        };
+        println!("{}: {}: {}: {}", location, severity, diag.key, diag.message);
+    }
+
+    let errors = diagnostics.iter().filter(|d| d.is_error()).count();
+    let warnings = diagnostics.len() - errors;
+    println!();
+    println!(
+        "{}",
+        t_args(
+            "config-summary",
+            &[
+                ("errors", &errors.to_string()),
+                ("warnings", &warnings.to_string())
+            ]
+        )
+    );
+    Ok(())
+}
+
+/// Number of recent sessions an agent's baseline is learned from
+const BASELINE_SESSIONS: usize = 50;
+
+/// Learn what `agent` usually does from the event database, if it has
+/// enough earlier sessions there
+fn load_baseline(config: &Config, agent: &str) -> Result<Option<AgentProfile>> {
+    let db_path = config.logging.effective_db_path()?;
+    if !db_path.exists() {
+        return Ok(None);
+    }
+    let storage = SqliteStorage::new(&db_path)?;
+    Ok(AgentProfile::load(&storage, agent, BASELINE_SESSIONS)?)
+}
+
 fn run_wrapper(cli: Cli) -> Result<()> {
     let command = cli.cmd.first().context(t("error-no-command"))?;
     let args: Vec<String> = cli.cmd.iter().skip(1).cloned().collect();
+    let wrapper = build_wrapper(&cli, command, args);
+
+    print_banner(&t("banner-recording"), cli.no_color);
+
+    let exit_code = if cli.headless {
+        wrapper.run_simple()?
+    } else {
+        // Try PTY first, fall back to simple if it fails
+        wrapper.run().unwrap_or_else(|e| {
+            eprintln!(
+                "{}",
+                t_args("error-pty-fallback", &[("error", &e.to_string())])
+            );
+            wrapper.run_simple().unwrap_or(-1)
+        })
+    };
+
+    let exit_str = exit_code.to_string();
+    print_footer(
+        &t_args("session-ended", &[("exit_code", &exit_str)]),
+        cli.no_color,
+    );
+    print_traffic(&wrapper.traffic_summary());
+    print_workspace_changes(&wrapper);
+
+    std::process::exit(exit_code);
+}
+
+/// Monitor a running process until it exits or Ctrl-C detaches from it
+fn attach_command(cli: &Cli, target: &str) -> Result<()> {
+    let (pid, name) = AttachTarget::parse(target).resolve(&AgentDetector::new())?;
+    let wrapper = build_wrapper(cli, &name, Vec::new());
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let handler_stop = Arc::clone(&stop);
+    ctrlc::set_handler(move || handler_stop.store(true, Ordering::Relaxed))
+        .context(t("error-ctrlc-handler"))?;
+
+    let pid_str = pid.to_string();
+    let args = [("name", name.as_str()), ("pid", pid_str.as_str())];
+    print_banner(&t_args("banner-attached", &args), cli.no_color);
+    println!("{}", t("attach-detach-hint"));
+    println!();
 
+    wrapper.attach(pid, &stop)?;
+
+    let footer_id = if stop.load(Ordering::Relaxed) {
+        "attach-detached"
+    } else {
+        "attach-exited"
+    };
+    print_footer(&t_args(footer_id, &args), cli.no_color);
+    print_traffic(&wrapper.traffic_summary());
+    print_workspace_changes(&wrapper);
+    Ok(())
+}
+
+/// Wrapper for `command` set up from the CLI flags and the config file
+fn build_wrapper(cli: &Cli, command: &str, args: Vec<String>) -> ProcessWrapper {
     // Load config file if specified or use default
     let app_config = if let Some(ref path) = cli.config {
         Config::load_from_path(path).unwrap_or_else(|e| {
@@ -302,13 +730,16 @@ fn run_wrapper(cli: Cli) -> Result<()> {
         })
     };
 
+    // Secrets are masked with the built-in rules plus those from [sanitize]
+    if let Err(e) = sanitize::configure(&app_config.sanitize) {
+        eprintln!(
+            "[agent-watch] Warning: Invalid [sanitize] rules: {}, using built-in rules",
+            e
+        );
+    }
+
     // Build logger config
-    let logger_config = LoggerConfig {
-        format: cli.format.into(),
-        min_level: cli.min_level.into(),
-        show_timestamps: !cli.no_timestamps,
-        use_colors: !cli.no_color,
-    };
+    let logger_config = logger_config(cli);
 
     // Determine watch paths from CLI and config
     let mut watch_paths: Vec<PathBuf> = cli.watch.iter().map(PathBuf::from).collect();
@@ -319,30 +750,69 @@ fn run_wrapper(cli: Cli) -> Result<()> {
     // Determine log directory
     let log_dir = cli
         .log_dir
+        .clone()
         .or_else(|| dirs::data_local_dir().map(|d| d.join("macagentwatch").join("logs")));
 
-    // Build network whitelist
+    // Build detection rules; these are hot reloaded if the config file changes
     let network_whitelist =
         NetworkWhitelist::new(app_config.monitoring.network_whitelist.clone(), vec![]);
+    let sensitive_detector =
+        SensitiveFileDetector::new(app_config.monitoring.sensitive_patterns.clone());
+    let mut risk_scorer = RiskScorer::new();
+    risk_scorer.add_custom_high_risk(app_config.alerts.custom_high_risk.clone());
+    let config_path = cli
+        .config
+        .clone()
+        .or_else(|| Config::default_path().ok().filter(|p| p.exists()));
 
     // Build wrapper config
     let mut config = WrapperConfig::new(command)
         .args(args)
         .logger_config(logger_config)
         .track_children(!cli.no_track_children)
+        .capture_exec(!cli.no_exec_capture)
+        .shell_hooks(cli.shell_hooks)
+        .monitor_resources(!cli.no_resource_monitor)
         .tracking_poll_ms(cli.tracking_poll_ms)
         .enable_fswatch(cli.enable_fswatch)
         .watch_paths(watch_paths)
+        .fs_aggregation(AggregationConfig::from_config(&app_config.monitoring))
+        .workspace_snapshot(cli.snapshot || app_config.monitoring.fs_snapshot)
         .enable_netmon(cli.enable_netmon)
-        .network_whitelist(network_whitelist);
+        .network_whitelist(network_whitelist)
+        .upload_alert_mb(app_config.monitoring.net_upload_alert_mb)
+        .egress_proxy(cli.net_proxy || app_config.monitoring.net_proxy)
+        .proxy_block_unlisted(app_config.monitoring.net_proxy_block)
+        .sensitive_detector(sensitive_detector);
 
     if let Some(dir) = log_dir {
-        config = config.session_log_dir(dir);
+        config = config
+            .session_log_dir(dir)
+            .retention(RetentionPolicy::from_config(&app_config.logging));
+        if app_config.logging.uses_sqlite() {
+            if let Ok(db_path) = app_config.logging.effective_db_path() {
+                config = config.retention_db_path(db_path);
+            }
+        }
+    }
+    if let Some(path) = config_path {
+        config = config.config_path(path);
     }
+    if !cli.no_baseline {
+        match load_baseline(&app_config, command) {
+            Ok(Some(profile)) => config = config.baseline(profile),
+            Ok(None) => {}
+            Err(e) => eprintln!("[agent-watch] Warning: Failed to load baseline: {}", e),
+        }
+    }
+
+    ProcessWrapper::new(config).with_risk_scorer(risk_scorer)
+}
 
-    // Print banner
-    let banner_text = format!("\u{25c9} {}", t("banner-recording"));
-    if !cli.no_color {
+/// Boxed banner printed when monitoring starts
+fn print_banner(text: &str, no_color: bool) {
+    let banner_text = format!("\u{25c9} {}", text);
+    if !no_color {
         println!();
         println!("{}", "\u{256d}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{256e}".cyan());
         println!(
@@ -358,27 +828,11 @@ fn run_wrapper(cli: Cli) -> Result<()> {
         println!("{}", banner_text);
         println!();
     }
+}
 
-    // Create and run wrapper
-    let wrapper = ProcessWrapper::new(config);
-
-    let exit_code = if cli.headless {
-        wrapper.run_simple()?
-    } else {
-        // Try PTY first, fall back to simple if it fails
-        wrapper.run().unwrap_or_else(|e| {
-            eprintln!(
-                "{}",
-                t_args("error-pty-fallback", &[("error", &e.to_string())])
-            );
-            wrapper.run_simple().unwrap_or(-1)
-        })
-    };
-
-    // Print footer
-    let exit_str = exit_code.to_string();
-    let footer_text = t_args("session-ended", &[("exit_code", &exit_str)]);
-    if !cli.no_color {
+/// Boxed footer printed when monitoring ends
+fn print_footer(footer_text: &str, no_color: bool) {
+    if !no_color {
         println!();
         println!("{}", "\u{256d}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{2500}\u{256e}".cyan());
         println!(
@@ -392,8 +846,99 @@ fn run_wrapper(cli: Cli) -> Result<()> {
         println!();
         println!("{}", footer_text);
     }
+}
+
+/// Network traffic of the session by provider category, printed under
+/// the footer
+fn print_traffic(summary: &TrafficSummary) {
+    for (category, traffic) in summary.iter() {
+        println!("  {}", traffic_line(category, traffic));
+    }
+}
 
-    std::process::exit(exit_code);
+/// Counts of the files the session changed, printed under the footer,
+/// with how to see the patch
+fn print_workspace_changes(wrapper: &ProcessWrapper) {
+    let Some(changes) = wrapper.workspace_changes() else {
+        return;
+    };
+    if changes.is_empty() {
+        println!("  {}", t("workspace-unchanged"));
+        return;
+    }
+    println!("  {}", workspace_line(&changes));
+    if let Some(session) = wrapper.session_id() {
+        println!(
+            "  {}",
+            t_args("workspace-diff-hint", &[("session", &session)])
+        );
+    }
+}
+
+fn workspace_line(changes: &WorkspaceChanges) -> String {
+    t_args(
+        "workspace-summary",
+        &[
+            ("added", &changes.added.len().to_string()),
+            ("deleted", &changes.deleted.len().to_string()),
+            ("modified", &changes.modified.len().to_string()),
+            ("binary", &changes.binary_changed.len().to_string()),
+        ],
+    )
+}
+
+fn traffic_line(category: ProviderCategory, traffic: &CategoryTraffic) -> String {
+    let label = match category {
+        ProviderCategory::Llm => t("traffic-llm"),
+        ProviderCategory::PackageRegistry => t("traffic-package-registry"),
+        ProviderCategory::Vcs => t("traffic-vcs"),
+        ProviderCategory::Other => t("traffic-other"),
+    };
+    let clock = |time: Option<chrono::DateTime<chrono::Utc>>| {
+        time.map(|at| {
+            at.with_timezone(&chrono::Local)
+                .format("%H:%M:%S")
+                .to_string()
+        })
+        .unwrap_or_default()
+    };
+    let mut line = t_args(
+        "traffic-line",
+        &[
+            ("category", &label),
+            ("requests", &traffic.requests.to_string()),
+            ("first", &clock(traffic.first)),
+            ("last", &clock(traffic.last)),
+        ],
+    );
+
+    if !traffic.providers.is_empty() {
+        let mut providers: Vec<(&String, &u64)> = traffic.providers.iter().collect();
+        providers.sort_by(|a, b| b.1.cmp(a.1));
+        let providers = providers
+            .iter()
+            .map(|(name, requests)| format!("{} {}", name, requests))
+            .collect::<Vec<_>>()
+            .join(", ");
+        line.push(' ');
+        line.push_str(&t_args("traffic-providers", &[("providers", &providers)]));
+    }
+    if let Some(mean) = traffic.mean_duration_ms() {
+        let duration = if mean < 1000 {
+            format!("{}ms", mean)
+        } else {
+            format!("{:.1}s", mean as f64 / 1000.0)
+        };
+        line.push_str(", ");
+        line.push_str(&t_args(
+            "traffic-mean-duration",
+            &[
+                ("duration", &duration),
+                ("closed", &traffic.closed.to_string()),
+            ],
+        ));
+    }
+    line
 }
 
 #[cfg(test)]
@@ -456,6 +1001,46 @@ mod tests {
         assert!(cli.no_track_children);
     }
 
+    #[test]
+    fn test_cli_parse_no_exec_capture() {
+        let cli = Cli::parse_from(["macagentwatch", "--no-exec-capture", "--", "cmd"]);
+        assert!(cli.no_exec_capture);
+        let cli = Cli::parse_from(["macagentwatch", "--", "cmd"]);
+        assert!(!cli.no_exec_capture);
+    }
+
+    #[test]
+    fn test_cli_parse_shell_hooks() {
+        let cli = Cli::parse_from(["macagentwatch", "--shell-hooks", "--", "zsh"]);
+        assert!(cli.shell_hooks);
+        let cli = Cli::parse_from(["macagentwatch", "--", "zsh"]);
+        assert!(!cli.shell_hooks);
+    }
+
+    #[test]
+    fn test_cli_parse_net_proxy() {
+        let cli = Cli::parse_from(["macagentwatch", "--net-proxy", "--", "claude"]);
+        assert!(cli.net_proxy);
+        let cli = Cli::parse_from(["macagentwatch", "--", "claude"]);
+        assert!(!cli.net_proxy);
+    }
+
+    #[test]
+    fn test_cli_parse_no_resource_monitor() {
+        let cli = Cli::parse_from(["macagentwatch", "--no-resource-monitor", "--", "cmd"]);
+        assert!(cli.no_resource_monitor);
+        let cli = Cli::parse_from(["macagentwatch", "--", "cmd"]);
+        assert!(!cli.no_resource_monitor);
+    }
+
+    #[test]
+    fn test_cli_parse_no_baseline() {
+        let cli = Cli::parse_from(["macagentwatch", "--no-baseline", "--", "cmd"]);
+        assert!(cli.no_baseline);
+        let cli = Cli::parse_from(["macagentwatch", "--", "cmd"]);
+        assert!(!cli.no_baseline);
+    }
+
     #[test]
     fn test_cli_parse_tracking_poll_ms() {
         let cli = Cli::parse_from(["macagentwatch", "--tracking-poll-ms", "50", "--", "cmd"]);
@@ -469,10 +1054,205 @@ mod tests {
         assert!(!cli.no_track_children);
     }
 
+    #[test]
+    fn test_cli_parse_config_set() {
+        let cli = Cli::parse_from(["macagentwatch", "config", "set", "alerts.min_level=medium"]);
+        match cli.command {
+            Some(Commands::Config {
+                action: ConfigAction::Set { assignment },
+            }) => assert_eq!(assignment, "alerts.min_level=medium"),
+            _ => panic!("Expected Config Set command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parse_config_init_force() {
+        let cli = Cli::parse_from(["macagentwatch", "config", "init", "--force"]);
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Config {
+                action: ConfigAction::Init { force: true }
+            })
+        ));
+    }
+
+    #[test]
+    fn test_config_command_set_and_validate() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("config.toml");
+
+        config_command(
+            ConfigAction::Set {
+                assignment: "monitoring.net_poll_ms=250".to_string(),
+            },
+            Some(path.clone()),
+            OutputFormat::Pretty,
+            true,
+        )
+        .unwrap();
+        let loaded = Config::load_from_path(&path).unwrap();
+        assert_eq!(loaded.monitoring.net_poll_ms, 250);
+
+        let result = config_command(
+            ConfigAction::Set {
+                assignment: "alerts.min_level".to_string(),
+            },
+            Some(path.clone()),
+            OutputFormat::Pretty,
+            true,
+        );
+        assert!(result.is_err());
+
+        let result = config_command(
+            ConfigAction::Set {
+                assignment: "alerts.min_level=severe".to_string(),
+            },
+            Some(path.clone()),
+            OutputFormat::Pretty,
+            true,
+        );
+        assert!(result.is_err());
+        assert!(Config::validate_toml(&std::fs::read_to_string(&path).unwrap()).is_empty());
+    }
+
+    #[test]
+    fn test_cli_parse_search_sessions() {
+        let cli = Cli::parse_from([
+            "macagentwatch",
+            "search",
+            "--path",
+            "~/.ssh",
+            "--since",
+            "7d",
+            "--sessions",
+        ]);
+        match cli.command {
+            Some(Commands::Search { filter, sessions }) => {
+                assert!(sessions);
+                assert_eq!(filter.path.as_deref(), Some("~/.ssh"));
+                assert!(filter.since.is_some());
+            }
+            _ => panic!("Expected Search command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parse_top_and_subject_conflict() {
+        let cli = Cli::parse_from(["macagentwatch", "top", "host", "--agent", "Cursor"]);
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Top {
+                by: GroupByArg::Host,
+                ..
+            })
+        ));
+        assert!(Cli::try_parse_from([
+            "macagentwatch",
+            "search",
+            "--host",
+            "github.com",
+            "--path",
+            "/tmp"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_attach() {
+        let cli = Cli::parse_from(["macagentwatch", "--enable-netmon", "attach", "4242"]);
+        assert!(cli.enable_netmon);
+        match cli.command {
+            Some(Commands::Attach { ref target }) => {
+                assert_eq!(AttachTarget::parse(target), AttachTarget::Pid(4242));
+            }
+            _ => panic!("Expected Attach command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parse_sanitize_test() {
+        let cli = Cli::parse_from(["macagentwatch", "sanitize", "test", "deploy --token abc"]);
+        let Some(Commands::Sanitize { action }) = cli.command else {
+            panic!("Expected Sanitize command");
+        };
+        let SanitizeAction::Test { ref input } = action;
+        assert_eq!(input, "deploy --token abc");
+
+        let mut config = Config::default();
+        config
+            .sanitize
+            .extra_flags
+            .push("--vault-token".to_string());
+        assert!(sanitize_command(action, &config, OutputFormat::Json, true).is_ok());
+
+        config.sanitize.allowlist.push("(".to_string());
+        let action = SanitizeAction::Test {
+            input: "x".to_string(),
+        };
+        assert!(sanitize_command(action, &config, OutputFormat::Pretty, true).is_err());
+    }
+
     #[test]
     fn test_i18n_messages_loaded() {
         assert_eq!(t("version-title"), "MacAgentWatch");
         assert_eq!(t("risk-low"), "LOW");
         assert_eq!(t("risk-critical"), "CRITICAL");
     }
+
+    #[test]
+    fn test_traffic_line() {
+        let traffic = CategoryTraffic {
+            requests: 12,
+            closed: 4,
+            duration_ms: 10_000,
+            first: Some(chrono::Utc::now()),
+            last: Some(chrono::Utc::now()),
+            providers: [("OpenAI".to_string(), 2), ("Anthropic".to_string(), 10)]
+                .into_iter()
+                .collect(),
+            ..Default::default()
+        };
+        let line = traffic_line(ProviderCategory::Llm, &traffic);
+        assert!(line.starts_with("\u{2068}LLM APIs\u{2069}: \u{2068}12\u{2069} connections"));
+        assert!(line.contains("Anthropic 10, OpenAI 2"));
+        assert!(line.contains("2.5s"));
+
+        let quiet = CategoryTraffic {
+            requests: 1,
+            ..Default::default()
+        };
+        let line = traffic_line(ProviderCategory::Other, &quiet);
+        assert!(line.contains("Other hosts"));
+        assert!(!line.contains("average"));
+    }
+
+    #[test]
+    fn test_cli_parse_snapshot_and_diff() {
+        let cli = Cli::parse_from(["macagentwatch", "--snapshot", "--", "claude"]);
+        assert!(cli.snapshot);
+        let cli = Cli::parse_from(["macagentwatch", "--", "claude"]);
+        assert!(!cli.snapshot);
+
+        let cli = Cli::parse_from(["macagentwatch", "diff", "20250101-120000", "--stat"]);
+        match cli.command {
+            Some(Commands::Diff { session, stat }) => {
+                assert_eq!(session, "20250101-120000");
+                assert!(stat);
+            }
+            _ => panic!("Expected Diff command"),
+        }
+    }
+
+    #[test]
+    fn test_workspace_line() {
+        let changes = WorkspaceChanges {
+            added: vec![PathBuf::from("a"), PathBuf::from("b")],
+            modified: vec![PathBuf::from("c")],
+            ..Default::default()
+        };
+        let line = workspace_line(&changes);
+        assert!(line.contains("\u{2068}2\u{2069} added"));
+        assert!(line.contains("\u{2068}0\u{2069} deleted"));
+        assert!(line.contains("\u{2068}1\u{2069} modified"));
+    }
 }
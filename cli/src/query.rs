@@ -0,0 +1,450 @@
+//! Cross-session queries over the SQLite event database.
+//!
+//! Implements the `search`, `top`, `trends` and `import` subcommands.
+
+use crate::i18n::{t, t_args};
+use crate::{OutputFormat, RiskLevelArg};
+use anyhow::Result;
+use chrono::{DateTime, Duration, Local, NaiveDate, Utc};
+use clap::{ArgGroup, Args, ValueEnum};
+use colored::Colorize;
+use macagentwatch_core::{
+    Config, EventQuery, GroupBy, LogFormat, Logger, LoggerConfig, RiskLevel, SqliteStorage,
+};
+use std::path::{Path, PathBuf};
+
+/// Filters shared by the cross-session subcommands
+#[derive(Args, Debug, Default)]
+#[command(group(ArgGroup::new("subject").args(["path", "host", "command", "text"])))]
+pub struct QueryArgs {
+    /// Only sessions of this agent (e.g. Claude, Cursor)
+    #[arg(long)]
+    pub agent: Option<String>,
+
+    /// File accesses whose path contains this text (~ is expanded)
+    #[arg(long)]
+    pub path: Option<String>,
+
+    /// Network connections to hosts containing this text
+    #[arg(long)]
+    pub host: Option<String>,
+
+    /// Commands containing this text
+    #[arg(long)]
+    pub command: Option<String>,
+
+    /// Commands, paths or hosts containing this text
+    #[arg(long)]
+    pub text: Option<String>,
+
+    /// Only events with this risk level
+    #[arg(long, value_enum)]
+    pub risk: Option<RiskLevelArg>,
+
+    /// Start of the time range: a duration ago (30m, 12h, 7d, 2w) or a date
+    #[arg(long, value_parser = parse_time)]
+    pub since: Option<DateTime<Utc>>,
+
+    /// End of the time range, in the same format as --since
+    #[arg(long, value_parser = parse_time)]
+    pub until: Option<DateTime<Utc>>,
+
+    /// Maximum number of results
+    #[arg(long)]
+    pub limit: Option<usize>,
+}
+
+impl QueryArgs {
+    /// Build the storage query for these filters
+    pub fn to_query(&self) -> EventQuery {
+        let (event_type, text) = if let Some(ref path) = self.path {
+            (Some("file_access"), Some(expand_home(path)))
+        } else if let Some(ref host) = self.host {
+            (Some("network"), Some(host.clone()))
+        } else if let Some(ref command) = self.command {
+            (Some("command"), Some(command.clone()))
+        } else {
+            (None, self.text.clone())
+        };
+
+        EventQuery {
+            agent: self.agent.clone(),
+            event_type: event_type.map(String::from),
+            text,
+            risk_level: self.risk.map(RiskLevel::from),
+            start_time: self.since,
+            end_time: self.until,
+            limit: self.limit,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum GroupByArg {
+    Agent,
+    Host,
+    Command,
+    Path,
+}
+
+impl From<GroupByArg> for GroupBy {
+    fn from(g: GroupByArg) -> Self {
+        match g {
+            GroupByArg::Agent => GroupBy::Agent,
+            GroupByArg::Host => GroupBy::Host,
+            GroupByArg::Command => GroupBy::Command,
+            GroupByArg::Path => GroupBy::Path,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum TrendBucket {
+    Hour,
+    Day,
+    Week,
+}
+
+impl TrendBucket {
+    fn millis(self) -> i64 {
+        match self {
+            TrendBucket::Hour => Duration::hours(1),
+            TrendBucket::Day => Duration::days(1),
+            TrendBucket::Week => Duration::weeks(1),
+        }
+        .num_milliseconds()
+    }
+}
+
+/// Parse `--since`/`--until`: a duration before now (`30m`, `12h`, `7d`,
+/// `2w`), a date (`2025-01-31`, local midnight) or an RFC 3339 timestamp
+pub fn parse_time(input: &str) -> Result<DateTime<Utc>, String> {
+    let input = input.trim();
+    if let Some(unit) = input.chars().last().filter(char::is_ascii_alphabetic) {
+        if let Ok(n) = input[..input.len() - 1].parse::<i64>() {
+            let ago = match unit {
+                'm' => Duration::minutes(n),
+                'h' => Duration::hours(n),
+                'd' => Duration::days(n),
+                'w' => Duration::weeks(n),
+                _ => return Err(t_args("query-invalid-time", &[("input", input)])),
+            };
+            return Ok(Utc::now() - ago);
+        }
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(input, "%Y-%m-%d") {
+        return date
+            .and_hms_opt(0, 0, 0)
+            .and_then(|t| t.and_local_timezone(Local).earliest())
+            .map(|t| t.with_timezone(&Utc))
+            .ok_or_else(|| t_args("query-invalid-time", &[("input", input)]));
+    }
+    DateTime::parse_from_rfc3339(input)
+        .map(|t| t.with_timezone(&Utc))
+        .map_err(|_| t_args("query-invalid-time", &[("input", input)]))
+}
+
+fn expand_home(path: &str) -> String {
+    match (path.strip_prefix('~'), dirs::home_dir()) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+            format!("{}{}", home.display(), rest)
+        }
+        _ => path.to_string(),
+    }
+}
+
+/// Open the event database, or print a hint and return `None` if there
+/// is nothing to query yet
+fn open_existing_db(config: &Config) -> Result<Option<SqliteStorage>> {
+    let db_path = config.logging.effective_db_path()?;
+    if !db_path.exists() {
+        println!(
+            "{}",
+            t_args(
+                "query-no-database",
+                &[("path", &db_path.display().to_string())]
+            )
+        );
+        return Ok(None);
+    }
+    Ok(Some(SqliteStorage::new(&db_path)?))
+}
+
+fn format_time(time: DateTime<Utc>) -> String {
+    time.with_timezone(&Local)
+        .format("%Y-%m-%d %H:%M")
+        .to_string()
+}
+
+fn risk_label(level: RiskLevel, no_color: bool) -> String {
+    let label = match level {
+        RiskLevel::Low => t("risk-low"),
+        RiskLevel::Medium => t("risk-medium"),
+        RiskLevel::High => t("risk-high"),
+        RiskLevel::Critical => t("risk-critical"),
+    };
+    if no_color {
+        return label;
+    }
+    match level {
+        RiskLevel::Low => label.green().to_string(),
+        RiskLevel::Medium => label.yellow().to_string(),
+        RiskLevel::High => label.bright_yellow().bold().to_string(),
+        RiskLevel::Critical => label.red().bold().to_string(),
+    }
+}
+
+/// `search`: matching events, or matching sessions with `--sessions`.
+/// Events are printed with the same formatting as a live session.
+pub fn search_command(
+    config: &Config,
+    filter: &QueryArgs,
+    sessions: bool,
+    logger_config: LoggerConfig,
+) -> Result<()> {
+    let Some(storage) = open_existing_db(config)? else {
+        return Ok(());
+    };
+    let query = filter.to_query();
+    let json = logger_config.format == LogFormat::JsonLines;
+    let no_color = !logger_config.use_colors;
+
+    if sessions {
+        let matches = storage.sessions_matching(&query)?;
+        if json {
+            println!("{}", serde_json::to_string_pretty(&matches)?);
+            return Ok(());
+        }
+        if matches.is_empty() {
+            println!("{}", t("query-no-results"));
+        }
+        for m in &matches {
+            let agent = m
+                .agent_name
+                .as_deref()
+                .or(m.process_name.as_deref())
+                .unwrap_or("-");
+            println!(
+                "{}  {}  {}  {}  {}",
+                format_time(m.last_match),
+                m.session_id,
+                agent,
+                t_args(
+                    "query-session-events",
+                    &[("count", &m.matching_events.to_string())]
+                ),
+                risk_label(m.max_risk, no_color)
+            );
+        }
+        return Ok(());
+    }
+
+    let events = storage.query_events(&query)?;
+    if events.is_empty() && !json {
+        println!("{}", t("query-no-results"));
+        return Ok(());
+    }
+    let logger = Logger::new(logger_config);
+    for event in &events {
+        logger.log_stdout(event)?;
+    }
+    Ok(())
+}
+
+/// `top`: most frequent agents, hosts, commands or paths
+pub fn top_command(
+    config: &Config,
+    by: GroupByArg,
+    filter: &QueryArgs,
+    format: OutputFormat,
+) -> Result<()> {
+    let Some(storage) = open_existing_db(config)? else {
+        return Ok(());
+    };
+    let mut query = filter.to_query();
+    query.limit = Some(filter.limit.unwrap_or(10));
+    let groups = storage.group_counts(&query, by.into())?;
+
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&groups)?),
+        _ if groups.is_empty() => println!("{}", t("query-no-results")),
+        OutputFormat::Compact => {
+            for g in &groups {
+                println!("{}\t{}\t{}", g.total, g.sessions, g.key);
+            }
+        }
+        OutputFormat::Pretty => {
+            println!(
+                "{:>8} {:>8} {:>6} {:>6}  {}",
+                t("top-col-events"),
+                t("top-col-sessions"),
+                t("risk-crit-compact"),
+                t("risk-high"),
+                GroupBy::from(by).to_string().to_uppercase()
+            );
+            for g in &groups {
+                println!(
+                    "{:>8} {:>8} {:>6} {:>6}  {}",
+                    g.total, g.sessions, g.critical, g.high, g.key
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// `trends`: per-agent sessions and events over time
+pub fn trends_command(
+    config: &Config,
+    filter: &QueryArgs,
+    bucket: TrendBucket,
+    format: OutputFormat,
+) -> Result<()> {
+    let Some(storage) = open_existing_db(config)? else {
+        return Ok(());
+    };
+    let trends = storage.agent_trends(&filter.to_query(), bucket.millis())?;
+
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&trends)?),
+        _ if trends.is_empty() => println!("{}", t("query-no-results")),
+        OutputFormat::Compact => {
+            for p in &trends {
+                println!(
+                    "{}\t{}\t{}\t{}\t{}\t{}",
+                    p.agent, p.start_ms, p.sessions, p.total, p.critical, p.high
+                );
+            }
+        }
+        OutputFormat::Pretty => {
+            let mut current: Option<&str> = None;
+            for p in &trends {
+                if current != Some(p.agent.as_str()) {
+                    if current.is_some() {
+                        println!();
+                    }
+                    println!("{}", p.agent.cyan().bold());
+                    println!(
+                        "  {:<16} {:>8} {:>8} {:>6} {:>6}",
+                        t("trends-col-period"),
+                        t("top-col-sessions"),
+                        t("top-col-events"),
+                        t("risk-crit-compact"),
+                        t("risk-high")
+                    );
+                    current = Some(&p.agent);
+                }
+                let start = DateTime::from_timestamp_millis(p.start_ms).unwrap_or_default();
+                println!(
+                    "  {:<16} {:>8} {:>8} {:>6} {:>6}",
+                    format_time(start),
+                    p.sessions,
+                    p.total,
+                    p.critical,
+                    p.high
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// `import`: backfill JSONL session logs into the event database
+pub fn import_command(config: &Config, dirs: &[PathBuf]) -> Result<()> {
+    let db_path = config.logging.effective_db_path()?;
+    let mut storage = SqliteStorage::new(&db_path)?;
+
+    for dir in dirs {
+        println!(
+            "{}",
+            t_args("import-dir", &[("path", &dir.display().to_string())])
+        );
+        let result = storage.backfill_from_dir(dir)?;
+        for (path, error) in &result.failed {
+            eprintln!(
+                "[agent-watch] Warning: {}",
+                t_args(
+                    "import-failed",
+                    &[("path", &path.display().to_string()), ("error", error)]
+                )
+            );
+        }
+        println!(
+            "{}",
+            t_args(
+                "import-summary",
+                &[
+                    ("imported", &result.imported.to_string()),
+                    ("events", &result.events.to_string()),
+                    ("skipped", &result.skipped.to_string()),
+                    ("failed", &result.failed.len().to_string()),
+                ]
+            )
+        );
+    }
+    Ok(())
+}
+
+/// Log directories to import when none is given: the configured log
+/// directory and the directory the CLI wrapper writes to by default
+pub fn default_import_dirs(config: &Config, cli_log_dir: Option<&Path>) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    let candidates = [
+        config.logging.effective_log_dir().ok(),
+        cli_log_dir
+            .map(Path::to_path_buf)
+            .or_else(|| dirs::data_local_dir().map(|d| d.join("macagentwatch").join("logs"))),
+    ];
+    for dir in candidates.into_iter().flatten() {
+        if dir.exists() && !dirs.contains(&dir) {
+            dirs.push(dir);
+        }
+    }
+    dirs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_time_relative() {
+        let before = Utc::now();
+        let parsed = parse_time("7d").unwrap();
+        let expected = before - Duration::days(7);
+        assert!((parsed - expected).num_seconds().abs() < 5);
+        assert!(parse_time("3x").is_err());
+    }
+
+    #[test]
+    fn test_parse_time_absolute() {
+        let parsed = parse_time("2025-01-31T12:00:00Z").unwrap();
+        assert_eq!(parsed.timestamp(), 1_738_324_800);
+        assert!(parse_time("2025-01-31").is_ok());
+        assert!(parse_time("yesterday").is_err());
+    }
+
+    #[test]
+    fn test_query_args_subject_filters() {
+        let args = QueryArgs {
+            host: Some("github".to_string()),
+            agent: Some("Cursor".to_string()),
+            ..Default::default()
+        };
+        let query = args.to_query();
+        assert_eq!(query.event_type.as_deref(), Some("network"));
+        assert_eq!(query.text.as_deref(), Some("github"));
+        assert_eq!(query.agent.as_deref(), Some("Cursor"));
+
+        let args = QueryArgs {
+            path: Some("~/.ssh".to_string()),
+            ..Default::default()
+        };
+        let query = args.to_query();
+        assert_eq!(query.event_type.as_deref(), Some("file_access"));
+        if let Some(home) = dirs::home_dir() {
+            assert_eq!(query.text, Some(format!("{}/.ssh", home.display())));
+        }
+    }
+}
@@ -0,0 +1,477 @@
+//! Aggregation of noisy file events
+//!
+//! Sits between a file event source and logging/storage. Low-risk file
+//! events are debounced per path, dropped when they match an ignore glob and
+//! held for a short window, so that a burst of them (a package install, a
+//! build) is collapsed into one summary event per directory. Events above
+//! Low risk, and events other than file access, always pass straight
+//! through.
+
+use crate::config::MonitoringConfig;
+use crate::event::{Event, EventType, FileAction, RiskLevel};
+use glob::Pattern;
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Aggregation settings
+#[derive(Debug, Clone, PartialEq)]
+pub struct AggregationConfig {
+    /// Repeats of the same action on the same path within this interval are
+    /// dropped
+    pub debounce: Duration,
+    /// How long low-risk file events are held to detect bursts
+    pub window: Duration,
+    /// Events of one action within a window at which they are collapsed
+    /// into summaries (0 disables summaries)
+    pub burst_threshold: usize,
+    /// Globs for paths whose low-risk events are dropped. A glob matches the
+    /// full path, the file name or any directory on the path, so
+    /// `node_modules` ignores everything beneath such a directory.
+    pub ignore: Vec<String>,
+}
+
+impl Default for AggregationConfig {
+    fn default() -> Self {
+        Self {
+            debounce: Duration::from_millis(100),
+            window: Duration::from_secs(1),
+            burst_threshold: 50,
+            ignore: Vec::new(),
+        }
+    }
+}
+
+impl AggregationConfig {
+    /// Aggregation settings from the `[monitoring]` config section
+    pub fn from_config(config: &MonitoringConfig) -> Self {
+        Self {
+            debounce: config.fs_debounce_duration(),
+            burst_threshold: config.fs_burst_threshold,
+            ignore: config.fs_ignore.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// Set the per-path debounce interval
+    pub fn debounce(mut self, debounce: Duration) -> Self {
+        self.debounce = debounce;
+        self
+    }
+
+    /// Set how long events are held to detect bursts
+    pub fn window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Set the number of events collapsed into summaries
+    pub fn burst_threshold(mut self, threshold: usize) -> Self {
+        self.burst_threshold = threshold;
+        self
+    }
+
+    /// Set the ignore globs
+    pub fn ignore(mut self, globs: Vec<String>) -> Self {
+        self.ignore = globs;
+        self
+    }
+}
+
+/// Debounces, filters and collapses low-risk file events.
+///
+/// Feed events to [`Self::push`] and call [`Self::flush`] periodically;
+/// both return the events ready to be logged. [`Self::finish`] releases
+/// everything still held.
+pub struct EventAggregator {
+    config: AggregationConfig,
+    ignore: Vec<Pattern>,
+    /// When each path and action last passed the debounce
+    last_seen: HashMap<(PathBuf, FileAction), Instant>,
+    /// Low-risk file events held for the current window
+    pending: Vec<Event>,
+    window_start: Option<Instant>,
+}
+
+impl EventAggregator {
+    /// Share of a directory's events that large subdirectories must hold for
+    /// a summary to move down into them
+    const DOMINANT_SHARE: f64 = 0.9;
+
+    /// Create an aggregator. Invalid ignore globs are skipped.
+    pub fn new(config: AggregationConfig) -> Self {
+        let ignore = config
+            .ignore
+            .iter()
+            .filter_map(|glob| Pattern::new(glob).ok())
+            .collect();
+        Self {
+            config,
+            ignore,
+            last_seen: HashMap::new(),
+            pending: Vec::new(),
+            window_start: None,
+        }
+    }
+
+    /// Add an event, returning the events ready to be logged
+    pub fn push(&mut self, event: Event, now: Instant) -> Vec<Event> {
+        let key = match &event.event_type {
+            EventType::FileAccess { path, action } if event.risk_level == RiskLevel::Low => {
+                (path.clone(), *action)
+            }
+            _ => {
+                let mut ready = self.flush(now);
+                ready.push(event);
+                return ready;
+            }
+        };
+
+        if self.is_ignored(&key.0) {
+            return self.flush(now);
+        }
+        if let Some(last) = self.last_seen.get(&key) {
+            if now.duration_since(*last) < self.config.debounce {
+                return self.flush(now);
+            }
+        }
+        self.last_seen.insert(key, now);
+        self.pending.push(event);
+        self.window_start.get_or_insert(now);
+        self.flush(now)
+    }
+
+    /// Release held events once their window has passed
+    pub fn flush(&mut self, now: Instant) -> Vec<Event> {
+        match self.window_start {
+            Some(start) if now.duration_since(start) >= self.config.window => {
+                let debounce = self.config.debounce;
+                self.last_seen
+                    .retain(|_, last| now.duration_since(*last) < debounce);
+                self.finish()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Release every held event, collapsing bursts
+    pub fn finish(&mut self) -> Vec<Event> {
+        self.window_start = None;
+        let pending = std::mem::take(&mut self.pending);
+        if self.config.burst_threshold == 0 || pending.len() < self.config.burst_threshold {
+            return pending;
+        }
+
+        // Group by action, keeping the order actions first appeared in
+        let mut groups: Vec<(FileAction, Vec<Event>)> = Vec::new();
+        for event in pending {
+            let EventType::FileAccess { action, .. } = event.event_type else {
+                continue;
+            };
+            match groups.iter_mut().find(|(a, _)| *a == action) {
+                Some((_, events)) => events.push(event),
+                None => groups.push((action, vec![event])),
+            }
+        }
+
+        let mut ready = Vec::new();
+        for (action, events) in groups {
+            ready.extend(self.collapse(action, events));
+        }
+        ready.sort_by_key(|event| event.timestamp);
+        ready
+    }
+
+    /// Replace bursts among `events` (all of one action) with summaries
+    fn collapse(&self, action: FileAction, events: Vec<Event>) -> Vec<Event> {
+        let threshold = self.config.burst_threshold;
+        let paths: Vec<&Path> = events
+            .iter()
+            .map(|event| match &event.event_type {
+                EventType::FileAccess { path, .. } => path.as_path(),
+                _ => Path::new(""),
+            })
+            .collect();
+
+        let mut summaries = Vec::new();
+        let mut summarized = vec![false; events.len()];
+        let mut rest: Vec<usize> = (0..events.len()).collect();
+        while rest.len() >= threshold {
+            let Some((dir, members)) = Self::burst_root(&paths, &rest, threshold) else {
+                break;
+            };
+            let first = &events[members[0]];
+            let mut summary = Event::file_summary(
+                dir,
+                action,
+                members.len() as u64,
+                first.process.clone(),
+                first.pid,
+            );
+            summary.timestamp = first.timestamp;
+            summaries.push(summary);
+            for &i in &members {
+                summarized[i] = true;
+            }
+            rest.retain(|i| !summarized[*i]);
+        }
+
+        let mut ready: Vec<Event> = events
+            .into_iter()
+            .zip(summarized)
+            .filter_map(|(event, summarized)| (!summarized).then_some(event))
+            .collect();
+        ready.extend(summaries);
+        ready
+    }
+
+    /// The directory to summarize a burst under and the events beneath it.
+    ///
+    /// Starting from the root, moves into the subdirectory holding most of
+    /// the events while it holds at least `threshold` of them, and either a
+    /// dominant share or the events are split between a few such large
+    /// subdirectories (which the caller summarizes one at a time). A
+    /// directory whose events are spread thinly over many subdirectories,
+    /// like `node_modules`, is where the burst is summarized.
+    fn burst_root(
+        paths: &[&Path],
+        indices: &[usize],
+        threshold: usize,
+    ) -> Option<(PathBuf, Vec<usize>)> {
+        let mut dir = PathBuf::new();
+        let mut members = indices.to_vec();
+        for depth in 0.. {
+            let mut by_child: HashMap<&OsStr, Vec<usize>> = HashMap::new();
+            for &i in &members {
+                let mut components = paths[i].components();
+                // Only directories above the file itself
+                if let Some(child) = components.nth(depth) {
+                    if components.next().is_some() {
+                        by_child.entry(child.as_os_str()).or_default().push(i);
+                    }
+                }
+            }
+            let in_large: usize = by_child
+                .values()
+                .map(Vec::len)
+                .filter(|len| *len >= threshold)
+                .sum();
+            let Some((child, sub)) = by_child.into_iter().max_by_key(|(_, sub)| sub.len()) else {
+                break;
+            };
+            let dominant = members.len() as f64 * Self::DOMINANT_SHARE;
+            if sub.len() < threshold || (in_large as f64) < dominant {
+                break;
+            }
+            dir.push(child);
+            members = sub;
+        }
+        (!dir.as_os_str().is_empty()).then_some((dir, members))
+    }
+
+    fn is_ignored(&self, path: &Path) -> bool {
+        self.ignore.iter().any(|pattern| {
+            pattern.matches_path(path)
+                || path
+                    .components()
+                    .any(|c| pattern.matches(&c.as_os_str().to_string_lossy()))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file_event(path: &str, action: FileAction, risk_level: RiskLevel) -> Event {
+        Event::new(
+            EventType::FileAccess {
+                path: PathBuf::from(path),
+                action,
+            },
+            "fswatch".to_string(),
+            1,
+            risk_level,
+        )
+    }
+
+    fn low(path: &str) -> Event {
+        file_event(path, FileAction::Create, RiskLevel::Low)
+    }
+
+    fn summaries(events: &[Event]) -> Vec<(PathBuf, FileAction, u64)> {
+        events
+            .iter()
+            .filter_map(|e| match &e.event_type {
+                EventType::FileSummary {
+                    path,
+                    action,
+                    count,
+                } => Some((path.clone(), *action, *count)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_config_from_monitoring() {
+        let monitoring = MonitoringConfig {
+            fs_debounce_ms: 250,
+            fs_burst_threshold: 10,
+            fs_ignore: vec!["*.swp".to_string()],
+            ..Default::default()
+        };
+        let config = AggregationConfig::from_config(&monitoring);
+        assert_eq!(config.debounce, Duration::from_millis(250));
+        assert_eq!(config.burst_threshold, 10);
+        assert_eq!(config.ignore, vec!["*.swp".to_string()]);
+        assert_eq!(config.window, Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_low_events_held_for_window() {
+        let mut aggregator = EventAggregator::new(AggregationConfig::default());
+        let start = Instant::now();
+        assert!(aggregator.push(low("/work/a.txt"), start).is_empty());
+        assert!(aggregator
+            .flush(start + Duration::from_millis(500))
+            .is_empty());
+        let ready = aggregator.flush(start + Duration::from_secs(1));
+        assert_eq!(ready.len(), 1);
+        assert!(aggregator.finish().is_empty());
+    }
+
+    #[test]
+    fn test_higher_risk_events_pass_through() {
+        let config = AggregationConfig::default().ignore(vec!["*.pem".to_string()]);
+        let mut aggregator = EventAggregator::new(config);
+        let now = Instant::now();
+        for _ in 0..3 {
+            let ready = aggregator.push(
+                file_event("/work/key.pem", FileAction::Read, RiskLevel::Critical),
+                now,
+            );
+            assert_eq!(ready.len(), 1);
+        }
+        let command = Event::command("ls".into(), vec![], "ls".into(), 1, RiskLevel::Low);
+        assert_eq!(aggregator.push(command, now).len(), 1);
+    }
+
+    #[test]
+    fn test_debounce_per_path() {
+        let mut aggregator = EventAggregator::new(AggregationConfig::default());
+        let start = Instant::now();
+        aggregator.push(low("/work/a.txt"), start);
+        aggregator.push(low("/work/a.txt"), start + Duration::from_millis(50));
+        aggregator.push(low("/work/b.txt"), start + Duration::from_millis(50));
+        aggregator.push(
+            file_event("/work/a.txt", FileAction::Delete, RiskLevel::Low),
+            start + Duration::from_millis(60),
+        );
+        aggregator.push(low("/work/a.txt"), start + Duration::from_millis(150));
+        assert_eq!(aggregator.finish().len(), 4);
+    }
+
+    #[test]
+    fn test_ignore_globs() {
+        let config = AggregationConfig::default().ignore(vec![
+            "node_modules".to_string(),
+            "*.swp".to_string(),
+            "/work/tmp/*".to_string(),
+            "[".to_string(),
+        ]);
+        let mut aggregator = EventAggregator::new(config);
+        let now = Instant::now();
+        for path in [
+            "/work/node_modules/lodash/index.js",
+            "/work/.main.rs.swp",
+            "/work/tmp/out.log",
+        ] {
+            aggregator.push(low(path), now);
+        }
+        aggregator.push(low("/work/src/main.rs"), now);
+        let ready = aggregator.finish();
+        assert_eq!(ready.len(), 1);
+        assert!(matches!(
+            &ready[0].event_type,
+            EventType::FileAccess { path, .. } if path == Path::new("/work/src/main.rs")
+        ));
+    }
+
+    #[test]
+    fn test_burst_collapsed_under_common_directory() {
+        let mut aggregator = EventAggregator::new(AggregationConfig::default());
+        let now = Instant::now();
+        for i in 0..1204 {
+            aggregator.push(
+                low(&format!("/work/node_modules/pkg{}/lib/f{i}.js", i % 100)),
+                now,
+            );
+        }
+        // Stragglers elsewhere stay individual events
+        aggregator.push(low("/work/src/main.rs"), now);
+        aggregator.push(low("/work/README.md"), now);
+
+        let ready = aggregator.finish();
+        assert_eq!(
+            summaries(&ready),
+            vec![(
+                PathBuf::from("/work/node_modules"),
+                FileAction::Create,
+                1204
+            )]
+        );
+        assert_eq!(ready.len(), 3);
+    }
+
+    #[test]
+    fn test_bursts_split_by_action_and_directory() {
+        let config = AggregationConfig::default().burst_threshold(10);
+        let mut aggregator = EventAggregator::new(config);
+        let now = Instant::now();
+        for i in 0..30 {
+            aggregator.push(low(&format!("/work/build/obj{i}.o")), now);
+            aggregator.push(
+                file_event(
+                    &format!("/work/cache/{i}"),
+                    FileAction::Delete,
+                    RiskLevel::Low,
+                ),
+                now,
+            );
+        }
+        for i in 0..20 {
+            aggregator.push(low(&format!("/tmp/scratch/{i}")), now);
+        }
+
+        let mut found = summaries(&aggregator.finish());
+        found.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            found,
+            vec![
+                (PathBuf::from("/tmp/scratch"), FileAction::Create, 20),
+                (PathBuf::from("/work/build"), FileAction::Create, 30),
+                (PathBuf::from("/work/cache"), FileAction::Delete, 30),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_small_batches_not_collapsed() {
+        let mut aggregator = EventAggregator::new(AggregationConfig::default());
+        let now = Instant::now();
+        for i in 0..49 {
+            aggregator.push(low(&format!("/work/src/{i}.rs")), now);
+        }
+        let ready = aggregator.finish();
+        assert_eq!(ready.len(), 49);
+        assert!(summaries(&ready).is_empty());
+
+        let mut disabled = EventAggregator::new(AggregationConfig::default().burst_threshold(0));
+        for i in 0..100 {
+            disabled.push(low(&format!("/work/src/{i}.rs")), now);
+        }
+        assert_eq!(disabled.finish().len(), 100);
+    }
+}
@@ -0,0 +1,571 @@
+//! Attach monitoring to a process tree
+//!
+//! [`Attachment`] runs the process tracker, network monitor and file system
+//! watcher for one root process and merges their output into a single stream
+//! of [`Event`]s. Children reported by the tracker are added to the network
+//! monitor as they start, so each connection names the process that made it.
+//!
+//! The root can be a process the wrapper has just spawned or one that was
+//! already running, such as an agent picked by name with [`AttachTarget`].
+
+use crate::agent_detector::AgentDetector;
+use crate::aggregate::AggregationConfig;
+use crate::bus::{EventBus, SubscribeOptions};
+use crate::config::MonitoringConfig;
+use crate::error::CoreError;
+use crate::event::Event;
+use crate::fswatch::{FileSystemWatcher, FsWatchConfig};
+use crate::netmon::{NetMonConfig, NetworkMonitor, TrackedPids};
+use crate::process_tracker::{ProcessTracker, TrackerConfig, TrackerEvent};
+use crate::reload::LiveRules;
+use crate::types::MonitoringSubsystem;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::mpsc::Receiver;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Process to attach to, as given on the command line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AttachTarget {
+    /// A process ID
+    Pid(u32),
+    /// Name of a running agent, matched case-insensitively
+    Agent(String),
+}
+
+impl AttachTarget {
+    /// Parse a PID or an agent name
+    pub fn parse(target: &str) -> Self {
+        match target.trim().parse::<u32>() {
+            Ok(pid) => AttachTarget::Pid(pid),
+            Err(_) => AttachTarget::Agent(target.trim().to_string()),
+        }
+    }
+
+    /// Find the PID and process name of the target among running processes
+    pub fn resolve(&self, detector: &AgentDetector) -> Result<(u32, String), CoreError> {
+        match self {
+            AttachTarget::Pid(pid) => {
+                let name = ProcessTracker::process_name(*pid)
+                    .ok_or_else(|| CoreError::Attach(format!("No process with PID {}", pid)))?;
+                Ok((*pid, name))
+            }
+            AttachTarget::Agent(name) => {
+                let wanted = name.to_lowercase();
+                let agents: Vec<_> = detector
+                    .scan_for_agents()
+                    .into_iter()
+                    .filter(|agent| agent.name.to_lowercase().contains(&wanted))
+                    .collect();
+                match agents.as_slice() {
+                    [] => Err(CoreError::Attach(format!(
+                        "No running agent matches '{}'",
+                        name
+                    ))),
+                    [agent] => Ok((agent.pid, agent.name.clone())),
+                    several => {
+                        let found: Vec<String> = several
+                            .iter()
+                            .map(|agent| format!("{} ({})", agent.name, agent.pid))
+                            .collect();
+                        Err(CoreError::Attach(format!(
+                            "'{}' matches several agents: {}; attach by PID instead",
+                            name,
+                            found.join(", ")
+                        )))
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Which subsystems to attach to a process tree
+#[derive(Debug, Clone)]
+pub struct AttachConfig {
+    /// Root process of the tree
+    pub pid: u32,
+    /// Name reported for the root process
+    pub name: String,
+    /// Track child processes
+    pub track_children: bool,
+    /// Polling interval for child process tracking (milliseconds)
+    pub tracking_poll_ms: u64,
+    /// Monitor network connections of the tree
+    pub enable_netmon: bool,
+    /// Polling interval for network monitoring (milliseconds)
+    pub net_poll_ms: u64,
+    /// Megabytes sent to a non-whitelisted host over one connection at
+    /// which its close is Critical (0 = never)
+    pub upload_alert_mb: u64,
+    /// Watch the file system
+    pub enable_fswatch: bool,
+    /// Paths to watch
+    pub watch_paths: Vec<PathBuf>,
+    /// How bursts of file events are summarized
+    pub fs_aggregation: AggregationConfig,
+    /// cgroup holding the tree, if the caller created one
+    pub cgroup: Option<PathBuf>,
+}
+
+impl AttachConfig {
+    /// Track children and network connections of `pid`
+    pub fn new(pid: u32, name: impl Into<String>) -> Self {
+        Self {
+            pid,
+            name: name.into(),
+            track_children: true,
+            tracking_poll_ms: 100,
+            enable_netmon: true,
+            net_poll_ms: 1000,
+            upload_alert_mb: 10,
+            enable_fswatch: false,
+            watch_paths: Vec::new(),
+            fs_aggregation: AggregationConfig::default(),
+            cgroup: None,
+        }
+    }
+
+    /// Subsystems and intervals from the `[monitoring]` section
+    pub fn from_config(pid: u32, name: impl Into<String>, monitoring: &MonitoringConfig) -> Self {
+        Self::new(pid, name)
+            .track_children(monitoring.track_children)
+            .tracking_poll_ms(monitoring.tracking_poll_ms)
+            .enable_netmon(monitoring.net_enabled)
+            .net_poll_ms(monitoring.net_poll_ms)
+            .upload_alert_mb(monitoring.net_upload_alert_mb)
+            .enable_fswatch(monitoring.fs_enabled)
+            .watch_paths(monitoring.watch_paths.clone())
+            .fs_aggregation(AggregationConfig::from_config(monitoring))
+    }
+
+    /// Enable/disable child process tracking
+    pub fn track_children(mut self, enabled: bool) -> Self {
+        self.track_children = enabled;
+        self
+    }
+
+    /// Set the child process polling interval (milliseconds)
+    pub fn tracking_poll_ms(mut self, ms: u64) -> Self {
+        self.tracking_poll_ms = ms;
+        self
+    }
+
+    /// Enable/disable network monitoring
+    pub fn enable_netmon(mut self, enabled: bool) -> Self {
+        self.enable_netmon = enabled;
+        self
+    }
+
+    /// Set the network polling interval (milliseconds)
+    pub fn net_poll_ms(mut self, ms: u64) -> Self {
+        self.net_poll_ms = ms;
+        self
+    }
+
+    /// Set the upload size that makes a connection Critical (0 = never)
+    pub fn upload_alert_mb(mut self, mb: u64) -> Self {
+        self.upload_alert_mb = mb;
+        self
+    }
+
+    /// Enable/disable file system watching
+    pub fn enable_fswatch(mut self, enabled: bool) -> Self {
+        self.enable_fswatch = enabled;
+        self
+    }
+
+    /// Set paths to watch
+    pub fn watch_paths(mut self, paths: Vec<PathBuf>) -> Self {
+        self.watch_paths = paths;
+        self
+    }
+
+    /// Set how bursts of file events are summarized
+    pub fn fs_aggregation(mut self, aggregation: AggregationConfig) -> Self {
+        self.fs_aggregation = aggregation;
+        self
+    }
+
+    /// Set the cgroup holding the tree
+    pub fn cgroup(mut self, cgroup: Option<PathBuf>) -> Self {
+        self.cgroup = cgroup;
+        self
+    }
+
+    /// Process tracker for the tree, if child tracking is enabled
+    pub(crate) fn build_tracker(&self, rules: &LiveRules) -> Option<ProcessTracker> {
+        if !self.track_children || self.pid == 0 {
+            return None;
+        }
+        let config = TrackerConfig::new(self.pid)
+            .poll_interval(Duration::from_millis(self.tracking_poll_ms))
+            .cgroup(self.cgroup.clone());
+        Some(ProcessTracker::new(config).with_shared_risk_scorer(rules.risk_scorer.clone()))
+    }
+
+    /// Network monitor for the root process, if network monitoring is enabled
+    pub(crate) fn build_net_monitor(&self, rules: &LiveRules) -> Option<NetworkMonitor> {
+        if !self.enable_netmon || self.pid == 0 {
+            return None;
+        }
+        let config = NetMonConfig::new(self.pid)
+            .poll_interval(Duration::from_millis(self.net_poll_ms))
+            .upload_alert_bytes(self.upload_alert_mb.saturating_mul(1024 * 1024));
+        let monitor =
+            NetworkMonitor::new(config).with_shared_whitelist(rules.network_whitelist.clone());
+        monitor.add_process(self.pid, self.name.clone());
+        Some(monitor)
+    }
+
+    /// File system watcher, if watching is enabled and there is something to watch
+    pub(crate) fn build_fs_watcher(&self, rules: &LiveRules) -> Option<FileSystemWatcher> {
+        if !self.enable_fswatch || self.watch_paths.is_empty() {
+            return None;
+        }
+        let config =
+            FsWatchConfig::new(self.watch_paths.clone()).aggregation(self.fs_aggregation.clone());
+        Some(FileSystemWatcher::new(config).with_shared_detector(rules.file_detector.clone()))
+    }
+}
+
+/// Keeps a network monitor's PID set in step with the process tree.
+///
+/// Called for each tracker event; children are named after their own
+/// executable so their connections are not reported as the root's.
+pub(crate) fn follow_child(net_pids: Option<&TrackedPids>, event: &TrackerEvent) {
+    let Some(net_pids) = net_pids else {
+        return;
+    };
+    match event {
+        TrackerEvent::ChildStarted { pid, name, .. } => net_pids.add_process(*pid, name.clone()),
+        TrackerEvent::ChildExited { pid, .. } => net_pids.remove(*pid),
+        // Still part of the tree, and still watched
+        TrackerEvent::ChildDetached { .. } => {}
+    }
+}
+
+/// Monitoring subsystems attached to one process tree
+pub struct Attachment {
+    config: AttachConfig,
+    rules: LiveRules,
+    events: EventBus<Event>,
+    tracker: Option<ProcessTracker>,
+    net_monitor: Option<NetworkMonitor>,
+    fs_watcher: Option<FileSystemWatcher>,
+    forwarders: Vec<JoinHandle<()>>,
+}
+
+impl Attachment {
+    /// Create an attachment; nothing runs until [`Attachment::start`]
+    pub fn new(config: AttachConfig, rules: LiveRules) -> Self {
+        Self {
+            config,
+            rules,
+            events: EventBus::new(),
+            tracker: None,
+            net_monitor: None,
+            fs_watcher: None,
+            forwarders: Vec::new(),
+        }
+    }
+
+    /// Subscribe to events of all attached subsystems
+    pub fn subscribe(&self) -> Receiver<Event> {
+        self.events.subscribe()
+    }
+
+    /// Subscribe with a custom buffer, lag policy and filter
+    pub fn subscribe_with(&self, options: SubscribeOptions) -> Receiver<Event> {
+        self.events.subscribe_with(options)
+    }
+
+    /// Root process of the tree
+    pub fn pid(&self) -> u32 {
+        self.config.pid
+    }
+
+    /// Name reported for the root process
+    pub fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    /// Whether the root process is still running
+    pub fn target_alive(&self) -> bool {
+        ProcessTracker::process_name(self.config.pid).is_some()
+    }
+
+    /// Start the configured subsystems.
+    ///
+    /// Fails if the root process does not exist. A subsystem that cannot
+    /// start is skipped with a warning, as the wrapper does.
+    pub fn start(&mut self) -> Result<(), CoreError> {
+        if !self.target_alive() {
+            return Err(CoreError::Attach(format!(
+                "No process with PID {}",
+                self.config.pid
+            )));
+        }
+
+        if let Some(mut monitor) = self.config.build_net_monitor(&self.rules) {
+            let rx = monitor.subscribe_with(SubscribeOptions::blocking());
+            match monitor.start() {
+                Ok(()) => {
+                    self.forwarders.push(self.forward(rx));
+                    self.net_monitor = Some(monitor);
+                }
+                Err(e) => {
+                    eprintln!("[agent-watch] Warning: Failed to start network monitor: {e}")
+                }
+            }
+        }
+
+        if let Some(mut watcher) = self.config.build_fs_watcher(&self.rules) {
+            let rx = watcher.subscribe_with(SubscribeOptions::blocking());
+            match watcher.start() {
+                Ok(()) => {
+                    self.forwarders.push(self.forward(rx));
+                    self.fs_watcher = Some(watcher);
+                }
+                Err(e) => {
+                    eprintln!("[agent-watch] Warning: Failed to start file system watcher: {e}")
+                }
+            }
+        }
+
+        if let Some(mut tracker) = self.config.build_tracker(&self.rules) {
+            let rx = tracker.subscribe_with(SubscribeOptions::blocking());
+            tracker.start();
+            let net_pids = self.net_monitor.as_ref().map(|m| m.tracked_pids());
+            let events = self.events.clone();
+            let root_name = self.config.name.clone();
+            self.forwarders.push(thread::spawn(move || {
+                // Names of running children, for their exit events
+                let mut running: HashMap<u32, String> = HashMap::new();
+                while let Ok(tracker_event) = rx.recv() {
+                    follow_child(net_pids.as_ref(), &tracker_event);
+                    let event = match tracker_event {
+                        TrackerEvent::ChildStarted {
+                            pid,
+                            ppid,
+                            name,
+                            risk_level,
+                            backend,
+                            ..
+                        } => {
+                            running.insert(pid, name.clone());
+                            Event::process_start(name, pid, Some(ppid), risk_level)
+                                .with_backend(backend)
+                        }
+                        TrackerEvent::ChildExited { pid, exit, backend } => {
+                            let name = running.remove(&pid).unwrap_or_else(|| root_name.clone());
+                            Event::process_exit(name, pid, None, exit).with_backend(backend)
+                        }
+                        TrackerEvent::ChildDetached {
+                            pid,
+                            ppid,
+                            name,
+                            backend,
+                        } => Event::process_detach(name, pid, Some(ppid)).with_backend(backend),
+                    };
+                    events.publish(event);
+                }
+            }));
+            self.tracker = Some(tracker);
+        }
+
+        Ok(())
+    }
+
+    /// Republish a subsystem's events on the attachment's bus
+    fn forward(&self, rx: Receiver<Event>) -> JoinHandle<()> {
+        let events = self.events.clone();
+        thread::spawn(move || {
+            while let Ok(event) = rx.recv() {
+                events.publish(event);
+            }
+        })
+    }
+
+    /// Signal all subsystems to stop without waiting for them
+    pub fn signal_stop(&self) {
+        if let Some(ref tracker) = self.tracker {
+            MonitoringSubsystem::signal_stop(tracker);
+        }
+        if let Some(ref monitor) = self.net_monitor {
+            MonitoringSubsystem::signal_stop(monitor);
+        }
+        if let Some(ref watcher) = self.fs_watcher {
+            MonitoringSubsystem::signal_stop(watcher);
+        }
+    }
+
+    /// Stop all subsystems and wait for their events to be delivered
+    pub fn stop(&mut self) {
+        self.signal_stop();
+        if let Some(mut tracker) = self.tracker.take() {
+            MonitoringSubsystem::stop(&mut tracker);
+        }
+        if let Some(mut monitor) = self.net_monitor.take() {
+            MonitoringSubsystem::stop(&mut monitor);
+        }
+        if let Some(mut watcher) = self.fs_watcher.take() {
+            MonitoringSubsystem::stop(&mut watcher);
+        }
+        for handle in self.forwarders.drain(..) {
+            let _ = handle.join();
+        }
+        // Subscribers see a disconnect once no more events can arrive
+        self.events.close();
+    }
+
+    /// Whether any subsystem is running
+    pub fn is_running(&self) -> bool {
+        self.tracker.as_ref().is_some_and(|t| t.is_running())
+            || self.net_monitor.as_ref().is_some_and(|m| m.is_running())
+            || self.fs_watcher.as_ref().is_some_and(|w| w.is_running())
+    }
+}
+
+impl MonitoringSubsystem for Attachment {
+    fn start(&mut self) -> Result<(), CoreError> {
+        Attachment::start(self)
+    }
+
+    fn stop(&mut self) {
+        Attachment::stop(self)
+    }
+
+    fn signal_stop(&self) {
+        Attachment::signal_stop(self)
+    }
+
+    fn is_running(&self) -> bool {
+        Attachment::is_running(self)
+    }
+}
+
+impl Drop for Attachment {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::{EventType, ProcessAction, TrackerBackend};
+
+    #[test]
+    fn test_parse_target() {
+        assert_eq!(AttachTarget::parse("1234"), AttachTarget::Pid(1234));
+        assert_eq!(
+            AttachTarget::parse(" claude "),
+            AttachTarget::Agent("claude".to_string())
+        );
+    }
+
+    #[test]
+    fn test_resolve_missing_agent() {
+        let detector = AgentDetector::with_patterns(vec!["no-such-agent-xyz".to_string()]);
+        let err = AttachTarget::Agent("no-such-agent-xyz".to_string())
+            .resolve(&detector)
+            .unwrap_err();
+        assert!(err.to_string().contains("No running agent matches"));
+    }
+
+    #[test]
+    fn test_config_from_monitoring() {
+        let monitoring = MonitoringConfig {
+            net_enabled: false,
+            track_children: false,
+            tracking_poll_ms: 250,
+            net_upload_alert_mb: 25,
+            ..MonitoringConfig::default()
+        };
+        let config = AttachConfig::from_config(42, "claude", &monitoring);
+        assert_eq!(config.name, "claude");
+        assert_eq!(config.upload_alert_mb, 25);
+        assert!(!config.enable_netmon);
+        assert!(!config.track_children);
+        assert_eq!(config.tracking_poll_ms, 250);
+        assert!(config.build_tracker(&LiveRules::default()).is_none());
+        assert!(config.build_net_monitor(&LiveRules::default()).is_none());
+    }
+
+    #[test]
+    fn test_built_net_monitor_names_root() {
+        let config = AttachConfig::new(42, "claude");
+        let monitor = config.build_net_monitor(&LiveRules::default()).unwrap();
+        assert_eq!(monitor.tracked_pids().process_name(42), "claude");
+    }
+
+    #[test]
+    fn test_follow_child_updates_net_pids() {
+        let net_pids = TrackedPids::default();
+        follow_child(
+            Some(&net_pids),
+            &TrackerEvent::ChildStarted {
+                pid: 7,
+                ppid: 1,
+                name: "curl".to_string(),
+                path: None,
+                risk_level: crate::event::RiskLevel::Low,
+                backend: TrackerBackend::Polling,
+            },
+        );
+        assert_eq!(net_pids.process_name(7), "curl");
+        follow_child(
+            Some(&net_pids),
+            &TrackerEvent::ChildExited {
+                pid: 7,
+                exit: Default::default(),
+                backend: TrackerBackend::Polling,
+            },
+        );
+        assert!(!net_pids.contains(7));
+    }
+
+    #[test]
+    fn test_start_fails_for_missing_process() {
+        let mut attachment = Attachment::new(
+            AttachConfig::new(u32::MAX - 1, "ghost"),
+            LiveRules::default(),
+        );
+        assert!(matches!(attachment.start(), Err(CoreError::Attach(_))));
+        assert!(!attachment.is_running());
+    }
+
+    #[test]
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    fn test_attach_reports_existing_process_children() {
+        let mut root = std::process::Command::new("sh")
+            .args(["-c", "sleep 0.3; sleep 0.3"])
+            .spawn()
+            .unwrap();
+        let config = AttachConfig::new(root.id(), "sh")
+            .tracking_poll_ms(20)
+            .enable_netmon(false);
+        let mut attachment = Attachment::new(config, LiveRules::default());
+        let rx = attachment.subscribe();
+        attachment.start().unwrap();
+        assert!(attachment.is_running());
+
+        let event = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(matches!(
+            event.event_type,
+            EventType::Process {
+                action: ProcessAction::Start,
+                ..
+            }
+        ));
+        assert_eq!(event.process, "sleep");
+
+        root.wait().unwrap();
+        attachment.stop();
+        assert!(!attachment.is_running());
+        // Remaining events are delivered, then the stream ends
+        while rx.recv().is_ok() {}
+    }
+}
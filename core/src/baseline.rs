@@ -0,0 +1,652 @@
+//! Behavioural baselines for agents
+//!
+//! Learns what an agent usually does from its earlier sessions in the event
+//! database: the hosts it contacts, the commands it runs, how fast it touches
+//! files and how long its sessions last. An [`AnomalyDetector`] compares a
+//! live session against that [`AgentProfile`] and reports Medium or High
+//! anomaly events, each with a score and an explanation, when the session
+//! deviates significantly.
+//!
+//! Scores are distances from the baseline mean in standard deviations. A
+//! short history of near-identical sessions has almost no spread, so the
+//! deviation used is never less than a quarter of the mean, and each kind
+//! also needs a minimum absolute count before it is reported.
+
+use crate::error::CoreError;
+use crate::event::{AnomalyKind, Event, RiskLevel};
+use crate::sqlite_storage::SqliteStorage;
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::path::Path;
+use std::time::Duration;
+
+/// What one earlier session of an agent did
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionActivity {
+    pub session_id: String,
+    /// Distinct hosts contacted
+    pub hosts: BTreeSet<String>,
+    /// Distinct commands run
+    pub commands: BTreeSet<String>,
+    /// Most file events within one minute
+    pub peak_file_rate: u32,
+    /// Session length, if the session ended
+    pub duration: Option<Duration>,
+}
+
+/// Mean and standard deviation of a per-session measure
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Stats {
+    pub mean: f64,
+    pub stddev: f64,
+}
+
+impl Stats {
+    fn of(values: &[f64]) -> Self {
+        if values.is_empty() {
+            return Self::default();
+        }
+        let n = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / n;
+        let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n;
+        Self {
+            mean,
+            stddev: variance.sqrt(),
+        }
+    }
+
+    /// How many deviations `value` lies above the mean
+    fn score(&self, value: f64) -> f64 {
+        let spread = self.stddev.max(self.mean * 0.25).max(1.0);
+        (value - self.mean) / spread
+    }
+}
+
+/// What an agent usually does, learned from its earlier sessions
+#[derive(Debug, Clone, PartialEq)]
+pub struct AgentProfile {
+    /// Agent name, or the wrapped command for sessions without a detected agent
+    pub agent: String,
+    /// Number of sessions learned from
+    pub sessions: usize,
+    /// Number of sessions that contacted each host
+    pub hosts: HashMap<String, usize>,
+    /// Number of sessions that ran each command
+    pub commands: HashMap<String, usize>,
+    /// Hosts per session that no other session contacted
+    pub new_hosts: Stats,
+    /// Commands per session that no other session ran
+    pub new_commands: Stats,
+    /// Most file events within one minute, per session
+    pub file_rate: Stats,
+    /// Length in seconds of the sessions that ended
+    pub session_secs: Stats,
+}
+
+impl AgentProfile {
+    /// Sessions needed before a profile is used for detection
+    pub const MIN_SESSIONS: usize = 5;
+
+    /// Build a profile from earlier sessions of `agent`
+    pub fn learn(agent: impl Into<String>, sessions: &[SessionActivity]) -> Self {
+        let mut hosts: HashMap<String, usize> = HashMap::new();
+        let mut commands: HashMap<String, usize> = HashMap::new();
+        for session in sessions {
+            for host in &session.hosts {
+                *hosts.entry(host.clone()).or_default() += 1;
+            }
+            for command in session.commands.iter().map(|c| command_name(c)) {
+                *commands.entry(command).or_default() += 1;
+            }
+        }
+
+        // Novelty of each session relative to all the others
+        let new_hosts: Vec<f64> = sessions
+            .iter()
+            .map(|s| s.hosts.iter().filter(|h| hosts[*h] == 1).count() as f64)
+            .collect();
+        let new_commands: Vec<f64> = sessions
+            .iter()
+            .map(|s| {
+                let names: BTreeSet<String> = s.commands.iter().map(|c| command_name(c)).collect();
+                names.iter().filter(|c| commands[*c] == 1).count() as f64
+            })
+            .collect();
+        let file_rate: Vec<f64> = sessions
+            .iter()
+            .map(|s| f64::from(s.peak_file_rate))
+            .collect();
+        let session_secs: Vec<f64> = sessions
+            .iter()
+            .filter_map(|s| s.duration)
+            .map(|d| d.as_secs_f64())
+            .collect();
+
+        Self {
+            agent: agent.into(),
+            sessions: sessions.len(),
+            hosts,
+            commands,
+            new_hosts: Stats::of(&new_hosts),
+            new_commands: Stats::of(&new_commands),
+            file_rate: Stats::of(&file_rate),
+            session_secs: Stats::of(&session_secs),
+        }
+    }
+
+    /// Learn the profile of `agent` from its most recent `max_sessions`
+    /// sessions in `storage`. Returns `None` while the agent has fewer than
+    /// [`Self::MIN_SESSIONS`] sessions.
+    pub fn load(
+        storage: &SqliteStorage,
+        agent: &str,
+        max_sessions: usize,
+    ) -> Result<Option<Self>, CoreError> {
+        let sessions = storage.session_activity(agent, max_sessions)?;
+        if sessions.len() < Self::MIN_SESSIONS {
+            return Ok(None);
+        }
+        Ok(Some(Self::learn(agent, &sessions)))
+    }
+}
+
+/// When deviations are reported
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnomalyThresholds {
+    /// Score at which a deviation is reported as Medium
+    pub medium_score: f64,
+    /// Score at which a deviation is reported as High
+    pub high_score: f64,
+    /// New hosts needed before they can be reported
+    pub min_new_hosts: usize,
+    /// New commands needed before they can be reported
+    pub min_new_commands: usize,
+    /// File events within a minute needed before the rate can be reported
+    pub min_file_rate: usize,
+    /// Session length needed before it can be reported
+    pub min_session_length: Duration,
+}
+
+impl Default for AnomalyThresholds {
+    fn default() -> Self {
+        Self {
+            medium_score: 3.0,
+            high_score: 6.0,
+            min_new_hosts: 5,
+            min_new_commands: 5,
+            min_file_rate: 100,
+            min_session_length: Duration::from_secs(10 * 60),
+        }
+    }
+}
+
+impl AnomalyThresholds {
+    /// Set the score reported as Medium
+    pub fn medium_score(mut self, score: f64) -> Self {
+        self.medium_score = score;
+        self
+    }
+
+    /// Set the score reported as High
+    pub fn high_score(mut self, score: f64) -> Self {
+        self.high_score = score;
+        self
+    }
+
+    /// Set the new hosts needed before they can be reported
+    pub fn min_new_hosts(mut self, count: usize) -> Self {
+        self.min_new_hosts = count;
+        self
+    }
+
+    /// Set the new commands needed before they can be reported
+    pub fn min_new_commands(mut self, count: usize) -> Self {
+        self.min_new_commands = count;
+        self
+    }
+
+    /// Set the file events within a minute needed before the rate can be reported
+    pub fn min_file_rate(mut self, count: usize) -> Self {
+        self.min_file_rate = count;
+        self
+    }
+
+    /// Set the session length needed before it can be reported
+    pub fn min_session_length(mut self, length: Duration) -> Self {
+        self.min_session_length = length;
+        self
+    }
+}
+
+/// Compares a live session against an agent's profile.
+///
+/// Each kind of anomaly is reported once at Medium and once more if it
+/// escalates to High.
+pub struct AnomalyDetector {
+    profile: AgentProfile,
+    thresholds: AnomalyThresholds,
+    /// Root PID of the live session
+    pid: u32,
+    started_at: DateTime<Utc>,
+    /// Hosts and commands outside the profile, in the order first seen
+    new_hosts: Vec<String>,
+    new_commands: Vec<String>,
+    /// File events within the last minute
+    file_events: VecDeque<DateTime<Utc>>,
+    reported: HashMap<AnomalyKind, RiskLevel>,
+}
+
+impl AnomalyDetector {
+    /// Window over which the file event rate is measured
+    const FILE_WINDOW: chrono::TimeDelta = chrono::TimeDelta::minutes(1);
+
+    /// Create a detector for a session rooted at `pid` that started at `started_at`
+    pub fn new(profile: AgentProfile, pid: u32, started_at: DateTime<Utc>) -> Self {
+        Self {
+            profile,
+            thresholds: AnomalyThresholds::default(),
+            pid,
+            started_at,
+            new_hosts: Vec::new(),
+            new_commands: Vec::new(),
+            file_events: VecDeque::new(),
+            reported: HashMap::new(),
+        }
+    }
+
+    /// Set custom thresholds
+    pub fn with_thresholds(mut self, thresholds: AnomalyThresholds) -> Self {
+        self.thresholds = thresholds;
+        self
+    }
+
+    /// The profile the session is compared against
+    pub fn profile(&self) -> &AgentProfile {
+        &self.profile
+    }
+
+    /// Record a connection to `host`
+    pub fn observe_host(&mut self, host: &str) -> Option<Event> {
+        if self.profile.hosts.contains_key(host) || self.new_hosts.iter().any(|h| h == host) {
+            return None;
+        }
+        self.new_hosts.push(host.to_string());
+        let count = self.new_hosts.len();
+        if count < self.thresholds.min_new_hosts {
+            return None;
+        }
+        let stats = self.profile.new_hosts;
+        let explanation = format!(
+            "contacted {} hosts not seen in {} earlier sessions (usually {:.1} per session): {}",
+            count,
+            self.profile.sessions,
+            stats.mean,
+            examples(&self.new_hosts)
+        );
+        self.report(
+            AnomalyKind::NewHosts,
+            stats.score(count as f64),
+            explanation,
+        )
+    }
+
+    /// Record a run of `command`
+    pub fn observe_command(&mut self, command: &str) -> Option<Event> {
+        let name = command_name(command);
+        if name.is_empty()
+            || self.profile.commands.contains_key(&name)
+            || self.new_commands.contains(&name)
+        {
+            return None;
+        }
+        self.new_commands.push(name);
+        let count = self.new_commands.len();
+        if count < self.thresholds.min_new_commands {
+            return None;
+        }
+        let stats = self.profile.new_commands;
+        let explanation = format!(
+            "ran {} commands not seen in {} earlier sessions (usually {:.1} per session): {}",
+            count,
+            self.profile.sessions,
+            stats.mean,
+            examples(&self.new_commands)
+        );
+        self.report(
+            AnomalyKind::NewCommands,
+            stats.score(count as f64),
+            explanation,
+        )
+    }
+
+    /// Record a file event at `at`
+    pub fn observe_file(&mut self, at: DateTime<Utc>) -> Option<Event> {
+        self.observe_files(at, 1)
+    }
+
+    /// Record `count` file events at `at`, e.g. from a burst summary
+    pub fn observe_files(&mut self, at: DateTime<Utc>, count: usize) -> Option<Event> {
+        self.file_events.extend(std::iter::repeat_n(at, count));
+        while self
+            .file_events
+            .front()
+            .is_some_and(|t| *t + Self::FILE_WINDOW < at)
+        {
+            self.file_events.pop_front();
+        }
+        let count = self.file_events.len();
+        if count < self.thresholds.min_file_rate {
+            return None;
+        }
+        let stats = self.profile.file_rate;
+        let explanation = format!(
+            "touched {} files within a minute (usually at most {:.0} ± {:.0})",
+            count, stats.mean, stats.stddev
+        );
+        self.report(
+            AnomalyKind::FileRate,
+            stats.score(count as f64),
+            explanation,
+        )
+    }
+
+    /// Check how long the session has been running as of `now`
+    pub fn check_duration(&mut self, now: DateTime<Utc>) -> Option<Event> {
+        let elapsed = (now - self.started_at).to_std().ok()?;
+        // Nothing to compare against until a session of this agent has ended
+        if elapsed < self.thresholds.min_session_length || self.profile.session_secs.mean == 0.0 {
+            return None;
+        }
+        let stats = self.profile.session_secs;
+        let explanation = format!(
+            "running for {} (usually {} ± {})",
+            format_secs(elapsed.as_secs_f64()),
+            format_secs(stats.mean),
+            format_secs(stats.stddev)
+        );
+        self.report(
+            AnomalyKind::SessionLength,
+            stats.score(elapsed.as_secs_f64()),
+            explanation,
+        )
+    }
+
+    /// Build an anomaly event if `score` reaches a level not yet reported
+    /// for `kind`
+    fn report(&mut self, kind: AnomalyKind, score: f64, explanation: String) -> Option<Event> {
+        let level = if score >= self.thresholds.high_score {
+            RiskLevel::High
+        } else if score >= self.thresholds.medium_score {
+            RiskLevel::Medium
+        } else {
+            return None;
+        };
+        if self.reported.get(&kind).is_some_and(|r| *r >= level) {
+            return None;
+        }
+        self.reported.insert(kind, level);
+        Some(Event::anomaly(
+            self.profile.agent.clone(),
+            self.pid,
+            kind,
+            score.round() as u32,
+            explanation,
+            level,
+        ))
+    }
+}
+
+/// Program name of a command, without its directory
+fn command_name(command: &str) -> String {
+    Path::new(command.trim())
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// The first few items of a list, for an explanation
+fn examples(items: &[String]) -> String {
+    const SHOWN: usize = 5;
+    let mut text = items
+        .iter()
+        .take(SHOWN)
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .join(", ");
+    if items.len() > SHOWN {
+        text.push_str(", …");
+    }
+    text
+}
+
+/// Format seconds as `1h 05m`, `12m` or `40s`
+fn format_secs(secs: f64) -> String {
+    let secs = secs.round() as u64;
+    match (secs / 3600, secs % 3600 / 60) {
+        (0, 0) => format!("{}s", secs),
+        (0, m) => format!("{}m", m),
+        (h, m) => format!("{}h {:02}m", h, m),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::EventType;
+
+    fn session(
+        id: &str,
+        hosts: &[&str],
+        commands: &[&str],
+        rate: u32,
+        mins: u64,
+    ) -> SessionActivity {
+        SessionActivity {
+            session_id: id.to_string(),
+            hosts: hosts.iter().map(|h| h.to_string()).collect(),
+            commands: commands.iter().map(|c| c.to_string()).collect(),
+            peak_file_rate: rate,
+            duration: Some(Duration::from_secs(mins * 60)),
+        }
+    }
+
+    /// Five sessions that talk to the same API, run git and cargo, touch
+    /// about 20 files a minute and last 20 to 30 minutes
+    fn history() -> Vec<SessionActivity> {
+        vec![
+            session("s1", &["api.openai.com"], &["git", "cargo"], 20, 20),
+            session(
+                "s2",
+                &["api.openai.com"],
+                &["git", "/usr/bin/cargo"],
+                25,
+                30,
+            ),
+            session("s3", &["api.openai.com", "github.com"], &["git"], 15, 25),
+            session("s4", &["api.openai.com"], &["git", "cargo", "ls"], 20, 20),
+            session("s5", &["api.openai.com"], &["git", "cargo"], 30, 30),
+        ]
+    }
+
+    fn detector() -> AnomalyDetector {
+        let profile = AgentProfile::learn("aider", &history());
+        AnomalyDetector::new(profile, 4321, Utc::now())
+    }
+
+    #[test]
+    fn test_stats() {
+        let stats = Stats::of(&[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]);
+        assert_eq!(stats.mean, 5.0);
+        assert_eq!(stats.stddev, 2.0);
+        assert_eq!(stats.score(11.0), 3.0);
+        // The spread never drops below one, or a quarter of the mean
+        assert_eq!(Stats::of(&[0.0, 0.0]).score(3.0), 3.0);
+        assert_eq!(Stats::of(&[100.0, 100.0]).score(150.0), 2.0);
+        assert_eq!(Stats::of(&[]), Stats::default());
+    }
+
+    #[test]
+    fn test_learn_profile() {
+        let profile = AgentProfile::learn("aider", &history());
+        assert_eq!(profile.agent, "aider");
+        assert_eq!(profile.sessions, 5);
+        assert_eq!(profile.hosts["api.openai.com"], 5);
+        assert_eq!(profile.hosts["github.com"], 1);
+        // Commands are compared by program name
+        assert_eq!(profile.commands["cargo"], 4);
+        assert_eq!(profile.commands["ls"], 1);
+        // github.com and ls each appeared in one session only
+        assert!((profile.new_hosts.mean - 0.2).abs() < 1e-9);
+        assert!((profile.new_commands.mean - 0.2).abs() < 1e-9);
+        assert_eq!(profile.file_rate.mean, 22.0);
+        assert_eq!(profile.session_secs.mean, 25.0 * 60.0);
+    }
+
+    #[test]
+    fn test_known_hosts_are_not_anomalies() {
+        let mut detector = detector();
+        for _ in 0..100 {
+            assert!(detector.observe_host("api.openai.com").is_none());
+        }
+        assert!(detector.observe_host("github.com").is_none());
+    }
+
+    #[test]
+    fn test_many_new_hosts_escalate() {
+        let mut detector = detector();
+        let mut events = Vec::new();
+        for i in 0..40 {
+            events.extend(detector.observe_host(&format!("host{i}.example.com")));
+            // Repeats of a host already counted change nothing
+            assert!(detector.observe_host("host0.example.com").is_none());
+        }
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].risk_level, RiskLevel::Medium);
+        assert_eq!(events[1].risk_level, RiskLevel::High);
+        assert_eq!(events[0].process, "aider");
+        assert_eq!(events[0].pid, 4321);
+        match &events[0].event_type {
+            EventType::Anomaly {
+                kind,
+                score,
+                explanation,
+            } => {
+                assert_eq!(*kind, AnomalyKind::NewHosts);
+                assert_eq!(*score, 5);
+                assert!(explanation.starts_with(
+                    "contacted 5 hosts not seen in 5 earlier sessions (usually 0.2 per session): \
+                     host0.example.com, host1.example.com"
+                ));
+            }
+            other => panic!("expected anomaly, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_new_commands() {
+        let mut detector = detector();
+        for command in ["git", "cargo", "/usr/local/bin/git", "ls"] {
+            assert!(detector.observe_command(command).is_none());
+        }
+        let events: Vec<Event> = ["nc", "socat", "nmap", "base64", "curl", "/usr/bin/dig"]
+            .iter()
+            .filter_map(|c| detector.observe_command(c))
+            .collect();
+        assert_eq!(events.len(), 1);
+        assert!(matches!(
+            &events[0].event_type,
+            EventType::Anomaly { kind: AnomalyKind::NewCommands, explanation, .. }
+                if explanation.ends_with("nc, socat, nmap, base64, curl")
+        ));
+    }
+
+    #[test]
+    fn test_file_rate() {
+        let thresholds = AnomalyThresholds::default().min_file_rate(30);
+        let mut detector = detector().with_thresholds(thresholds);
+        let start = Utc::now();
+
+        // A steady pace close to the usual rate is not reported
+        for i in 0..200 {
+            let at = start + chrono::TimeDelta::seconds(i * 2);
+            assert!(detector.observe_file(at).is_none());
+        }
+
+        let burst = start + chrono::TimeDelta::hours(1);
+        let events: Vec<Event> = (0..500)
+            .filter_map(|i| detector.observe_file(burst + chrono::TimeDelta::milliseconds(i * 100)))
+            .collect();
+        let levels: Vec<RiskLevel> = events.iter().map(|e| e.risk_level).collect();
+        assert_eq!(levels, vec![RiskLevel::Medium, RiskLevel::High]);
+        assert!(matches!(
+            &events[0].event_type,
+            EventType::Anomaly { kind: AnomalyKind::FileRate, explanation, .. }
+                if explanation.starts_with("touched 39 files within a minute (usually at most 22")
+        ));
+    }
+
+    #[test]
+    fn test_file_rate_from_summary() {
+        let mut detector = detector();
+        let at = Utc::now();
+
+        assert!(detector.observe_files(at, 20).is_none());
+        let event = detector
+            .observe_files(at, 1184)
+            .expect("no anomaly for a summarized burst");
+        assert_eq!(event.risk_level, RiskLevel::High);
+        assert!(matches!(
+            &event.event_type,
+            EventType::Anomaly { kind: AnomalyKind::FileRate, explanation, .. }
+                if explanation.starts_with("touched 1204 files within a minute")
+        ));
+    }
+
+    #[test]
+    fn test_session_length() {
+        let started = Utc::now();
+        let profile = AgentProfile::learn("aider", &history());
+        let mut detector = AnomalyDetector::new(profile, 1, started);
+        let at = |mins: i64| started + chrono::TimeDelta::minutes(mins);
+
+        assert!(detector.check_duration(at(30)).is_none());
+        let event = detector
+            .check_duration(at(60))
+            .expect("no anomaly after an hour");
+        assert_eq!(event.risk_level, RiskLevel::Medium);
+        assert!(matches!(
+            &event.event_type,
+            EventType::Anomaly { kind: AnomalyKind::SessionLength, explanation, .. }
+                if explanation == "running for 1h 00m (usually 25m ± 4m)"
+        ));
+        assert!(detector.check_duration(at(61)).is_none());
+        assert!(detector.check_duration(at(120)).is_some());
+    }
+
+    #[test]
+    fn test_thresholds() {
+        let profile = AgentProfile::learn("aider", &history());
+        let thresholds = AnomalyThresholds::default()
+            .min_new_hosts(1)
+            .medium_score(1.0)
+            .high_score(100.0);
+        let mut detector = AnomalyDetector::new(profile, 1, Utc::now()).with_thresholds(thresholds);
+        assert!(detector.observe_host("a.example").is_none());
+        let event = detector.observe_host("b.example").unwrap();
+        assert_eq!(event.risk_level, RiskLevel::Medium);
+    }
+
+    #[test]
+    fn test_load_needs_enough_sessions() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        assert_eq!(AgentProfile::load(&storage, "aider", 50).unwrap(), None);
+    }
+
+    #[test]
+    fn test_format_secs() {
+        assert_eq!(format_secs(40.0), "40s");
+        assert_eq!(format_secs(12.0 * 60.0), "12m");
+        assert_eq!(format_secs(3900.0), "1h 05m");
+    }
+}
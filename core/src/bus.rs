@@ -0,0 +1,465 @@
+//! Broadcast event bus
+//!
+//! Subsystems publish each value once and every subscriber gets its own copy.
+//! Subscribers have their own bounded buffer and filter, so a departed
+//! consumer doesn't affect the others. Subscriptions are lossless by
+//! default: a full buffer makes the publisher wait. Consumers that would
+//! rather miss values than hold up monitoring opt into
+//! [`LagPolicy::DropNewest`], and [`EventBus::dropped`] counts what they
+//! missed.
+
+use crate::event::{Event, RiskLevel};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+
+/// Default number of values buffered per subscriber
+pub const DEFAULT_CAPACITY: usize = 1024;
+
+/// A value that can be published on an [`EventBus`]
+pub trait BusEvent: Clone + Send + 'static {
+    /// Short name of the value's type, e.g. `"network"` or `"file_access"`
+    fn kind(&self) -> &'static str;
+
+    /// Risk level of the value, if it carries one
+    fn risk_level(&self) -> Option<RiskLevel> {
+        None
+    }
+}
+
+impl BusEvent for Event {
+    fn kind(&self) -> &'static str {
+        self.event_type.tag()
+    }
+
+    fn risk_level(&self) -> Option<RiskLevel> {
+        Some(self.risk_level)
+    }
+}
+
+/// What to do when a subscriber's buffer is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LagPolicy {
+    /// Wait until the subscriber makes room, so it sees every value. The
+    /// publisher stalls while the subscriber falls behind.
+    #[default]
+    Block,
+    /// Drop the value for this subscriber and count it as lagged
+    DropNewest,
+}
+
+/// Which values a subscriber receives
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EventFilter {
+    /// Kinds to deliver (see [`BusEvent::kind`]); all kinds if empty
+    pub kinds: Vec<String>,
+    /// Lowest risk level to deliver. Values without a risk level are not
+    /// delivered when this is set.
+    pub min_risk: Option<RiskLevel>,
+}
+
+impl EventFilter {
+    /// Deliver only values of the given kinds
+    pub fn kinds<I, S>(mut self, kinds: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.kinds = kinds.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Deliver only values at or above `level`
+    pub fn min_risk(mut self, level: RiskLevel) -> Self {
+        self.min_risk = Some(level);
+        self
+    }
+
+    /// Check whether `value` passes the filter
+    pub fn matches<T: BusEvent>(&self, value: &T) -> bool {
+        if !self.kinds.is_empty() && !self.kinds.iter().any(|k| k == value.kind()) {
+            return false;
+        }
+        match self.min_risk {
+            Some(min) => value.risk_level().is_some_and(|level| level >= min),
+            None => true,
+        }
+    }
+}
+
+/// Options for a single subscription
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubscribeOptions {
+    /// Values buffered before the lag policy applies
+    pub capacity: usize,
+    /// What happens when the buffer is full
+    pub lag: LagPolicy,
+    /// Which values are delivered
+    pub filter: EventFilter,
+}
+
+impl Default for SubscribeOptions {
+    fn default() -> Self {
+        Self {
+            capacity: DEFAULT_CAPACITY,
+            lag: LagPolicy::default(),
+            filter: EventFilter::default(),
+        }
+    }
+}
+
+impl SubscribeOptions {
+    /// Options for a consumer that must see every value, such as a
+    /// forwarder to storage: the publisher waits instead of dropping. The
+    /// same as the default, spelled out where losing values would be a bug.
+    pub fn blocking() -> Self {
+        Self::default().lag(LagPolicy::Block)
+    }
+
+    /// Options for a consumer that would rather miss values than hold up
+    /// the publisher, such as a UI listener
+    pub fn dropping() -> Self {
+        Self::default().lag(LagPolicy::DropNewest)
+    }
+
+    /// Set the buffer size
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    /// Set the lag policy
+    pub fn lag(mut self, lag: LagPolicy) -> Self {
+        self.lag = lag;
+        self
+    }
+
+    /// Set the filter
+    pub fn filter(mut self, filter: EventFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+}
+
+struct Subscriber<T> {
+    id: u64,
+    tx: SyncSender<T>,
+    lag: LagPolicy,
+    filter: EventFilter,
+    dropped: u64,
+}
+
+struct Shared<T> {
+    subscribers: Mutex<Vec<Subscriber<T>>>,
+    next_id: AtomicU64,
+    dropped: AtomicU64,
+}
+
+/// Delivers published values to any number of subscribers.
+///
+/// Clones share the same subscribers. Receivers see a disconnect once every
+/// clone of the bus is dropped or [`EventBus::close`] is called.
+pub struct EventBus<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Clone for EventBus<T> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<T> std::fmt::Debug for EventBus<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventBus")
+            .field("subscribers", &self.subscriber_count())
+            .field("dropped", &self.dropped())
+            .finish()
+    }
+}
+
+impl<T: BusEvent> Default for EventBus<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> EventBus<T> {
+    /// Number of live subscribers
+    pub fn subscriber_count(&self) -> usize {
+        self.shared
+            .subscribers
+            .lock()
+            .map(|s| s.len())
+            .unwrap_or_default()
+    }
+
+    /// Values dropped across all subscribers because their buffer was full
+    pub fn dropped(&self) -> u64 {
+        self.shared.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Disconnect all subscribers. Later subscriptions work as usual.
+    pub fn close(&self) {
+        if let Ok(mut subscribers) = self.shared.subscribers.lock() {
+            subscribers.clear();
+        }
+    }
+}
+
+impl<T: BusEvent> EventBus<T> {
+    /// Create a bus without subscribers
+    pub fn new() -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                subscribers: Mutex::new(Vec::new()),
+                next_id: AtomicU64::new(0),
+                dropped: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// Subscribe to every value with the default buffer. Lossless: the
+    /// receiver must be drained or dropped, or publishing stalls.
+    pub fn subscribe(&self) -> Receiver<T> {
+        self.subscribe_with(SubscribeOptions::default())
+    }
+
+    /// Subscribe with a custom buffer, lag policy and filter
+    pub fn subscribe_with(&self, options: SubscribeOptions) -> Receiver<T> {
+        let (tx, rx) = mpsc::sync_channel(options.capacity.max(1));
+        let subscriber = Subscriber {
+            id: self.shared.next_id.fetch_add(1, Ordering::Relaxed),
+            tx,
+            lag: options.lag,
+            filter: options.filter,
+            dropped: 0,
+        };
+        if let Ok(mut subscribers) = self.shared.subscribers.lock() {
+            subscribers.push(subscriber);
+        }
+        rx
+    }
+
+    /// Deliver `value` to every subscriber whose filter it passes, returning
+    /// how many received it. Subscribers whose receiver is gone are removed.
+    pub fn publish(&self, value: T) -> usize {
+        let mut delivered = 0;
+        let mut blocking = Vec::new();
+        {
+            let Ok(mut subscribers) = self.shared.subscribers.lock() else {
+                return 0;
+            };
+            subscribers.retain_mut(|sub| {
+                if !sub.filter.matches(&value) {
+                    return true;
+                }
+                if sub.lag == LagPolicy::Block {
+                    // Sent after the lock is released so a full buffer
+                    // doesn't hold up other publishers
+                    blocking.push((sub.id, sub.tx.clone()));
+                    return true;
+                }
+                match sub.tx.try_send(value.clone()) {
+                    Ok(()) => {
+                        delivered += 1;
+                        true
+                    }
+                    Err(TrySendError::Full(_)) => {
+                        if sub.dropped == 0 {
+                            eprintln!(
+                                "[agent-watch] Warning: Event subscriber {} is lagging, dropping events",
+                                sub.id
+                            );
+                        }
+                        sub.dropped += 1;
+                        self.shared.dropped.fetch_add(1, Ordering::Relaxed);
+                        true
+                    }
+                    Err(TrySendError::Disconnected(_)) => false,
+                }
+            });
+        }
+
+        let mut gone = Vec::new();
+        for (id, tx) in blocking {
+            match tx.send(value.clone()) {
+                Ok(()) => delivered += 1,
+                Err(_) => gone.push(id),
+            }
+        }
+        if !gone.is_empty() {
+            if let Ok(mut subscribers) = self.shared.subscribers.lock() {
+                subscribers.retain(|sub| !gone.contains(&sub.id));
+            }
+        }
+        delivered
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::{EventType, FileAction};
+    use std::path::PathBuf;
+    use std::sync::mpsc::TryRecvError;
+    use std::thread;
+    use std::time::Duration;
+
+    fn network(risk: RiskLevel) -> Event {
+        Event::new(
+            EventType::Network {
+                host: "example.com".into(),
+                port: 443,
+                protocol: "tcp".into(),
+                provider: None,
+            },
+            "curl".into(),
+            1,
+            risk,
+        )
+    }
+
+    fn file() -> Event {
+        Event::new(
+            EventType::FileAccess {
+                path: PathBuf::from("/tmp/a"),
+                action: FileAction::Write,
+            },
+            "fswatch".into(),
+            1,
+            RiskLevel::Low,
+        )
+    }
+
+    #[test]
+    fn test_every_subscriber_gets_each_event() {
+        let bus = EventBus::new();
+        let first = bus.subscribe();
+        let second = bus.subscribe();
+
+        assert_eq!(bus.publish(network(RiskLevel::Low)), 2);
+        assert!(first.try_recv().is_ok());
+        assert!(second.try_recv().is_ok());
+
+        // A later subscriber doesn't disconnect the earlier ones
+        let third = bus.subscribe();
+        assert_eq!(bus.publish(file()), 3);
+        for rx in [&first, &second, &third] {
+            assert_eq!(rx.try_recv().unwrap().kind(), "file_access");
+        }
+    }
+
+    #[test]
+    fn test_filters() {
+        let bus = EventBus::new();
+        let risky = bus.subscribe_with(
+            SubscribeOptions::default().filter(EventFilter::default().min_risk(RiskLevel::High)),
+        );
+        let files = bus.subscribe_with(
+            SubscribeOptions::default().filter(EventFilter::default().kinds(["file_access"])),
+        );
+
+        bus.publish(network(RiskLevel::Low));
+        bus.publish(network(RiskLevel::Critical));
+        bus.publish(file());
+
+        assert_eq!(risky.try_recv().unwrap().risk_level, RiskLevel::Critical);
+        assert!(risky.try_recv().is_err());
+        assert_eq!(files.try_recv().unwrap().kind(), "file_access");
+        assert!(files.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_lagging_subscriber_drops_without_affecting_others() {
+        let bus = EventBus::new();
+        let slow = bus.subscribe_with(SubscribeOptions::dropping().capacity(2));
+        let fast = bus.subscribe_with(SubscribeOptions::dropping());
+
+        for _ in 0..5 {
+            bus.publish(file());
+        }
+        assert_eq!(bus.dropped(), 3);
+        assert_eq!(slow.try_iter().count(), 2);
+        assert_eq!(fast.try_iter().count(), 5);
+
+        // Room again once the subscriber catches up
+        assert_eq!(bus.publish(file()), 2);
+    }
+
+    #[test]
+    fn test_blocking_subscriber_waits_for_room() {
+        let bus = EventBus::new();
+        let rx = bus.subscribe_with(SubscribeOptions::blocking().capacity(1));
+
+        let publisher = bus.clone();
+        let handle = thread::spawn(move || {
+            for _ in 0..10 {
+                publisher.publish(file());
+            }
+        });
+        let received: Vec<Event> = (0..10)
+            .map(|_| rx.recv_timeout(Duration::from_secs(5)).unwrap())
+            .collect();
+        handle.join().unwrap();
+
+        assert_eq!(received.len(), 10);
+        assert_eq!(bus.dropped(), 0);
+    }
+
+    #[test]
+    fn test_default_subscription_is_lossless() {
+        let bus = EventBus::new();
+        let rx = bus.subscribe_with(SubscribeOptions::default().capacity(1));
+
+        let publisher = bus.clone();
+        let handle = thread::spawn(move || {
+            for risk in [RiskLevel::Low, RiskLevel::Critical, RiskLevel::High] {
+                publisher.publish(network(risk));
+            }
+        });
+        thread::sleep(Duration::from_millis(50));
+        let received: Vec<RiskLevel> = (0..3)
+            .map(|_| rx.recv_timeout(Duration::from_secs(5)).unwrap().risk_level)
+            .collect();
+        handle.join().unwrap();
+
+        assert_eq!(
+            received,
+            vec![RiskLevel::Low, RiskLevel::Critical, RiskLevel::High]
+        );
+        assert_eq!(bus.dropped(), 0);
+    }
+
+    #[test]
+    fn test_departed_subscribers_are_removed() {
+        let bus = EventBus::new();
+        let kept = bus.subscribe();
+        drop(bus.subscribe());
+        drop(bus.subscribe_with(SubscribeOptions::blocking()));
+        assert_eq!(bus.subscriber_count(), 3);
+
+        assert_eq!(bus.publish(file()), 1);
+        assert_eq!(bus.subscriber_count(), 1);
+        assert!(kept.try_recv().is_ok());
+    }
+
+    #[test]
+    fn test_receivers_disconnect_when_bus_goes_away() {
+        let bus: EventBus<Event> = EventBus::new();
+        let rx = bus.subscribe();
+        let clone = bus.clone();
+        drop(bus);
+        assert!(matches!(rx.try_recv(), Err(TryRecvError::Empty)));
+        drop(clone);
+        assert!(matches!(rx.try_recv(), Err(TryRecvError::Disconnected)));
+
+        let bus: EventBus<Event> = EventBus::new();
+        let rx = bus.subscribe();
+        bus.close();
+        assert!(matches!(rx.try_recv(), Err(TryRecvError::Disconnected)));
+        assert_eq!(bus.subscriber_count(), 0);
+    }
+}
@@ -0,0 +1,284 @@
+//! Dedicated cgroup for a wrapped process tree
+//!
+//! A child that double-forks, calls `setsid` or is `nohup`ed gets
+//! reparented to init and falls out of a parent-PID walk from the root.
+//! cgroup membership is inherited across fork and survives reparenting, so
+//! on Linux the wrapped process is moved into its own cgroup v2 and the
+//! tracker reads the members back to keep escaped descendants attributed.
+//! The wrapper spawns the process from inside the cgroup, so it is born a
+//! member and children it forks before its first instruction are too.
+//!
+//! Creating the cgroup needs write access to the wrapper's own cgroup, as
+//! under a delegated systemd subtree or when running as root. Without it
+//! the tracker falls back to session and process group membership.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+/// Held while this process is inside a tree cgroup, so concurrent spawns
+/// from another wrapper don't move it out again early
+static ENTERED: Mutex<()> = Mutex::new(());
+
+/// cgroup v2 holding one wrapped process tree, removed on drop
+#[derive(Debug)]
+pub struct TreeCgroup {
+    path: PathBuf,
+}
+
+impl TreeCgroup {
+    /// Create an empty cgroup below this process's own cgroup. Processes
+    /// spawned while it is [entered](Self::enter) are born inside.
+    #[cfg(target_os = "linux")]
+    pub fn create() -> io::Result<Self> {
+        use std::sync::atomic::{AtomicU32, Ordering};
+        static NEXT: AtomicU32 = AtomicU32::new(0);
+
+        let mountinfo = std::fs::read_to_string("/proc/self/mountinfo")?;
+        let mount = parse_cgroup2_mount(&mountinfo)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "cgroup v2 is not mounted"))?;
+        let own = std::fs::read_to_string("/proc/self/cgroup")?;
+        let own = parse_unified_cgroup(&own).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "process is not in a cgroup v2")
+        })?;
+
+        let path = mount.join(own.trim_start_matches('/')).join(format!(
+            "agent-watch-{}-{}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir(&path)?;
+        Ok(Self { path })
+    }
+
+    /// cgroups are Linux-only
+    #[cfg(not(target_os = "linux"))]
+    pub fn create() -> io::Result<Self> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "cgroups are only available on Linux",
+        ))
+    }
+
+    /// Move this process into the cgroup until the returned guard is
+    /// dropped. Anything it forks meanwhile starts out a member.
+    pub fn enter(&self) -> io::Result<EnteredCgroup> {
+        let lock = ENTERED.lock().unwrap_or_else(|e| e.into_inner());
+        let parent = self
+            .path
+            .parent()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "cgroup has no parent"))?;
+        std::fs::write(
+            self.path.join("cgroup.procs"),
+            std::process::id().to_string(),
+        )?;
+        Ok(EnteredCgroup {
+            parent: parent.to_path_buf(),
+            _lock: lock,
+        })
+    }
+
+    /// Move an already running process into the cgroup
+    pub fn add(&self, pid: u32) -> io::Result<()> {
+        std::fs::write(self.path.join("cgroup.procs"), pid.to_string())
+    }
+
+    /// Directory of the cgroup
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Processes currently in the cgroup
+    pub fn members(&self) -> Vec<u32> {
+        read_members(&self.path)
+    }
+}
+
+/// This process's stay in a [`TreeCgroup`], ended on drop
+#[derive(Debug)]
+pub struct EnteredCgroup {
+    parent: PathBuf,
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl Drop for EnteredCgroup {
+    fn drop(&mut self) {
+        let pid = std::process::id();
+        if let Err(e) = std::fs::write(self.parent.join("cgroup.procs"), pid.to_string()) {
+            eprintln!(
+                "[agent-watch] Warning: Failed to leave the process tree cgroup: {}",
+                e
+            );
+        }
+    }
+}
+
+impl Drop for TreeCgroup {
+    fn drop(&mut self) {
+        // A cgroup can only be removed once empty; hand processes that
+        // outlived the session back to the parent cgroup
+        if let Some(parent) = self.path.parent() {
+            for pid in self.members() {
+                let _ = std::fs::write(parent.join("cgroup.procs"), pid.to_string());
+            }
+        }
+        let _ = std::fs::remove_dir(&self.path);
+    }
+}
+
+/// Processes in the cgroup at `path`, empty if it cannot be read. This
+/// process is left out in case it failed to leave the cgroup.
+pub(crate) fn read_members(path: &Path) -> Vec<u32> {
+    let own = std::process::id();
+    std::fs::read_to_string(path.join("cgroup.procs"))
+        .map(|procs| {
+            procs
+                .lines()
+                .filter_map(|l| l.trim().parse().ok())
+                .filter(|&pid| pid != own)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Mount point of the cgroup v2 hierarchy from `/proc/self/mountinfo`
+#[cfg(any(target_os = "linux", test))]
+fn parse_cgroup2_mount(mountinfo: &str) -> Option<PathBuf> {
+    mountinfo.lines().find_map(|line| {
+        // Optional fields end at " - ", followed by the filesystem type
+        let (mount, fs) = line.split_once(" - ")?;
+        (fs.split_whitespace().next()? == "cgroup2")
+            .then(|| mount.split_whitespace().nth(4).map(PathBuf::from))?
+    })
+}
+
+/// Path of the process in the cgroup v2 hierarchy from `/proc/<pid>/cgroup`
+#[cfg(any(target_os = "linux", test))]
+fn parse_unified_cgroup(cgroup: &str) -> Option<&str> {
+    cgroup
+        .lines()
+        .find_map(|line| line.strip_prefix("0::"))
+        .map(str::trim)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cgroup2_mount() {
+        let hybrid = "\
+25 30 0:23 / /sys rw,nosuid shared:7 - sysfs sysfs rw
+33 25 0:28 / /sys/fs/cgroup ro,nosuid shared:9 - tmpfs tmpfs ro,mode=755
+34 33 0:29 / /sys/fs/cgroup/unified rw,nosuid shared:10 - cgroup2 cgroup2 rw
+35 33 0:30 / /sys/fs/cgroup/memory rw shared:11 - cgroup cgroup rw,memory
+";
+        assert_eq!(
+            parse_cgroup2_mount(hybrid),
+            Some(PathBuf::from("/sys/fs/cgroup/unified"))
+        );
+        let unified = "30 23 0:26 / /sys/fs/cgroup rw,nosuid - cgroup2 cgroup2 rw,nsdelegate\n";
+        assert_eq!(
+            parse_cgroup2_mount(unified),
+            Some(PathBuf::from("/sys/fs/cgroup"))
+        );
+        assert_eq!(
+            parse_cgroup2_mount("25 30 0:23 / /sys rw - sysfs sysfs rw\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_unified_cgroup() {
+        let hybrid = "4:memory:/user.slice\n0::/user.slice/user-1000.slice/session-2.scope\n";
+        assert_eq!(
+            parse_unified_cgroup(hybrid),
+            Some("/user.slice/user-1000.slice/session-2.scope")
+        );
+        assert_eq!(parse_unified_cgroup("4:memory:/\n"), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_tree_cgroup_create_and_remove() {
+        use std::process::Command;
+
+        let mut child = Command::new("sleep")
+            .arg("5")
+            .spawn()
+            .expect("Failed to spawn sleep");
+        let Ok(cgroup) = TreeCgroup::create().and_then(|c| c.add(child.id()).map(|_| c)) else {
+            // No writable cgroup v2 in this environment
+            let _ = child.kill();
+            let _ = child.wait();
+            return;
+        };
+        let path = cgroup.path().to_path_buf();
+        assert!(cgroup.members().contains(&child.id()));
+
+        // A member that is still running is handed back on drop
+        drop(cgroup);
+        assert!(!path.exists());
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_tree_cgroup_holds_child_forked_before_exec() {
+        use std::process::Command;
+        use std::time::{Duration, Instant};
+
+        let own_cgroup = std::fs::read_to_string("/proc/self/cgroup").unwrap();
+        let Ok(cgroup) = TreeCgroup::create() else {
+            return;
+        };
+        let Ok(entered) = cgroup.enter() else {
+            return;
+        };
+        // Forks as its first act, before anyone could move it afterwards
+        let spawned = Command::new("sh")
+            .args(["-c", "sleep 5 & exec sleep 5"])
+            .spawn();
+        drop(entered);
+        let mut child = spawned.expect("Failed to spawn sh");
+        assert_eq!(
+            std::fs::read_to_string("/proc/self/cgroup").unwrap(),
+            own_cgroup
+        );
+
+        // Other tests may fork while this process is inside; only the
+        // child and what it forked count
+        let ppid = |pid: u32| {
+            std::fs::read_to_string(format!("/proc/{pid}/stat"))
+                .ok()
+                .and_then(|stat| {
+                    let (_, rest) = stat.rsplit_once(')')?;
+                    rest.split_whitespace().nth(1)?.parse::<u32>().ok()
+                })
+        };
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let members = loop {
+            let members: Vec<u32> = cgroup
+                .members()
+                .into_iter()
+                .filter(|&pid| pid == child.id() || ppid(pid) == Some(child.id()))
+                .collect();
+            if members.len() == 2 || Instant::now() > deadline {
+                break members;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        };
+        for &pid in &members {
+            // SAFETY: kill has no memory safety preconditions; the pids are
+            // the processes spawned above
+            unsafe {
+                libc::kill(pid as libc::pid_t, libc::SIGKILL);
+            }
+        }
+        let _ = child.wait();
+
+        assert!(members.contains(&child.id()), "{members:?}");
+        assert_eq!(members.len(), 2, "{members:?}");
+    }
+}
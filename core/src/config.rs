@@ -4,6 +4,7 @@
 //! Default configuration path: `~/.macagentwatch/config.toml`
 
 use crate::error::{ConfigError, CoreError};
+use crate::types::RiskLevel;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::time::Duration;
@@ -23,6 +24,9 @@ pub struct Config {
     /// Notification settings
     #[serde(default)]
     pub notifications: NotificationConfig,
+    /// Sensitive data masking settings
+    #[serde(default)]
+    pub sanitize: SanitizeConfig,
 }
 
 impl Config {
@@ -40,14 +44,23 @@ impl Config {
 # log_dir = \"~/.macagentwatch/logs\"
 # retention_days = 30
 # storage_backend = \"jsonl\"   # jsonl, sqlite, both
+# max_session_mb = 100        # roll over to a new log segment at this size (0 = no limit)
+# max_total_mb = 2048         # remove the oldest closed sessions above this, per log dir and database (0 = no limit)
+# compression = \"zstd\"       # zstd, gzip, none (applied to closed sessions)
 
 # [monitoring]
 # fs_enabled = false
 # net_enabled = false
 # track_children = true
 # tracking_poll_ms = 100
-# fs_debounce_ms = 100
+# fs_debounce_ms = 100        # drop repeats of a file event on the same path within this time
+# fs_burst_threshold = 50     # collapse this many low-risk file events per second into a summary (0 = off)
+# fs_ignore = []              # e.g. [\"node_modules\", \".git\", \"*.swp\"]: drop low-risk file events on matching paths
+# fs_snapshot = false         # record watch_paths at session start and store a patch of the changes next to the session log
 # net_poll_ms = 500
+# net_upload_alert_mb = 10    # sent over one connection to a non-whitelisted host at which its close is Critical (0 = off)
+# net_proxy = false           # route the agent's HTTP(S) through a local proxy that logs each hostname
+# net_proxy_block = false     # with net_proxy, refuse hosts not in network_whitelist
 # watch_paths = []
 # sensitive_patterns = [\".env\", \".env.*\", \"*.pem\", \"*.key\", \"*credential*\", \"*secret*\"]
 # network_whitelist = [\"api.anthropic.com\", \"github.com\", \"api.github.com\"]
@@ -55,6 +68,12 @@ impl Config {
 # [alerts]
 # min_level = \"high\"
 # custom_high_risk = []
+
+# [sanitize]
+# extra_flags = []            # e.g. [\"--vault-token\"]: masks the next argument and --vault-token=...
+# extra_env = []              # e.g. [\"ACME_DEPLOY_KEY\"]: masks ACME_DEPLOY_KEY=...
+# patterns = []               # e.g. [{ name = \"vault-token\", regex = 'hvs\\.[A-Za-z0-9]{24,}' }]
+# allowlist = []              # regexes for values that are never masked, e.g. ['sk_test_\\w+']
 ";
 
     /// Load configuration from default path (~/.macagentwatch/config.toml)
@@ -71,11 +90,31 @@ impl Config {
 
     /// Create a commented-out sample config file if the parent directory exists
     fn create_sample_config(path: &std::path::Path) {
-        if let Some(parent) = path.parent() {
-            if std::fs::create_dir_all(parent).is_ok() {
-                let _ = std::fs::write(path, Self::SAMPLE_CONFIG);
+        let _ = Self::write_sample(path, false);
+    }
+
+    /// Write the commented-out sample config to `path`.
+    ///
+    /// Fails with [`ConfigError::AlreadyExists`] if the file exists and
+    /// `overwrite` is false.
+    pub fn write_sample(path: &std::path::Path, overwrite: bool) -> Result<(), CoreError> {
+        if path.exists() && !overwrite {
+            return Err(ConfigError::AlreadyExists {
+                path: path.to_path_buf(),
             }
+            .into());
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| ConfigError::CreateDir {
+                path: parent.to_path_buf(),
+                source: e,
+            })?;
         }
+        std::fs::write(path, Self::SAMPLE_CONFIG).map_err(|e| ConfigError::WriteFile {
+            path: path.to_path_buf(),
+            source: e,
+        })?;
+        Ok(())
     }
 
     /// Load configuration from a specific path
@@ -122,9 +161,14 @@ impl Config {
         Ok(config_dir)
     }
 
+    /// Serialize configuration to a TOML string
+    pub fn to_toml(&self) -> Result<String, CoreError> {
+        Ok(toml::to_string_pretty(self).map_err(ConfigError::SerializeToml)?)
+    }
+
     /// Save configuration to file
     pub fn save(&self, path: &std::path::Path) -> Result<(), CoreError> {
-        let content = toml::to_string_pretty(self).map_err(ConfigError::SerializeToml)?;
+        let content = self.to_toml()?;
         std::fs::write(path, content).map_err(|e| ConfigError::WriteFile {
             path: path.to_path_buf(),
             source: e,
@@ -133,6 +177,496 @@ impl Config {
     }
 }
 
+/// Severity of a configuration diagnostic
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiagnosticSeverity {
+    /// The setting is invalid and will be ignored or misbehave
+    Error,
+    /// The setting is accepted but probably not what the user intended
+    Warning,
+}
+
+impl std::fmt::Display for DiagnosticSeverity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DiagnosticSeverity::Error => write!(f, "error"),
+            DiagnosticSeverity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// A single finding from config validation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfigDiagnostic {
+    /// Error or warning
+    pub severity: DiagnosticSeverity,
+    /// Dotted key the diagnostic refers to (e.g. `alerts.min_level`)
+    pub key: String,
+    /// Offending value, for array entries or enum-like strings
+    pub value: Option<String>,
+    /// Human-readable description
+    pub message: String,
+    /// 1-based line in the TOML source, when known
+    pub line: Option<usize>,
+}
+
+impl ConfigDiagnostic {
+    fn error(key: &str, value: Option<String>, message: impl Into<String>) -> Self {
+        Self {
+            severity: DiagnosticSeverity::Error,
+            key: key.to_string(),
+            value,
+            message: message.into(),
+            line: None,
+        }
+    }
+
+    fn warning(key: &str, value: Option<String>, message: impl Into<String>) -> Self {
+        Self {
+            severity: DiagnosticSeverity::Warning,
+            ..Self::error(key, value, message)
+        }
+    }
+
+    /// Whether this diagnostic is an error
+    pub fn is_error(&self) -> bool {
+        self.severity == DiagnosticSeverity::Error
+    }
+}
+
+impl std::fmt::Display for ConfigDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(line) = self.line {
+            write!(f, "line {}: ", line)?;
+        }
+        write!(f, "{}: {}: {}", self.severity, self.key, self.message)
+    }
+}
+
+/// Accepted values for `general.default_format`
+const OUTPUT_FORMATS: &[&str] = &["pretty", "json", "compact"];
+
+impl Config {
+    /// Check semantic constraints that deserialization does not enforce.
+    ///
+    /// Returned diagnostics carry no line numbers; use [`Config::validate_toml`]
+    /// to validate a config file with source locations.
+    pub fn validate(&self) -> Vec<ConfigDiagnostic> {
+        let mut diags = Vec::new();
+
+        if !OUTPUT_FORMATS.contains(&self.general.default_format.as_str()) {
+            diags.push(ConfigDiagnostic::error(
+                "general.default_format",
+                Some(self.general.default_format.clone()),
+                format!(
+                    "unknown output format '{}' (expected pretty, json or compact)",
+                    self.general.default_format
+                ),
+            ));
+        }
+
+        for (key, value) in [
+            ("alerts.min_level", &self.alerts.min_level),
+            (
+                "notifications.min_risk_level",
+                &self.notifications.min_risk_level,
+            ),
+        ] {
+            if let Err(e) = value.parse::<RiskLevel>() {
+                diags.push(ConfigDiagnostic::error(key, Some(value.clone()), e));
+            }
+        }
+
+        if let Some(ref dir) = self.logging.log_dir {
+            if dir.exists() && !dir.is_dir() {
+                diags.push(ConfigDiagnostic::error(
+                    "logging.log_dir",
+                    Some(dir.display().to_string()),
+                    format!("{} is not a directory", dir.display()),
+                ));
+            }
+        }
+
+        for (key, value) in [
+            (
+                "monitoring.tracking_poll_ms",
+                self.monitoring.tracking_poll_ms,
+            ),
+            ("monitoring.net_poll_ms", self.monitoring.net_poll_ms),
+        ] {
+            if value == 0 {
+                diags.push(ConfigDiagnostic::error(
+                    key,
+                    None,
+                    "polling interval must be greater than 0",
+                ));
+            }
+        }
+
+        let logging = &self.logging;
+        if logging.max_total_mb > 0 && logging.max_total_mb < logging.max_session_mb {
+            diags.push(ConfigDiagnostic::warning(
+                "logging.max_total_mb",
+                Some(logging.max_total_mb.to_string()),
+                format!(
+                    "total cap of {} MB is below the per-session cap of {} MB; \
+                     a single session can exceed it",
+                    logging.max_total_mb, logging.max_session_mb
+                ),
+            ));
+        }
+
+        for path in &self.monitoring.watch_paths {
+            let shown = path.display().to_string();
+            if !path.exists() {
+                diags.push(ConfigDiagnostic::warning(
+                    "monitoring.watch_paths",
+                    Some(shown.clone()),
+                    format!("watch path {} does not exist", shown),
+                ));
+                continue;
+            }
+            let readable = if path.is_dir() {
+                std::fs::read_dir(path).map(|_| ())
+            } else {
+                std::fs::File::open(path).map(|_| ())
+            };
+            if let Err(e) = readable {
+                diags.push(ConfigDiagnostic::warning(
+                    "monitoring.watch_paths",
+                    Some(shown.clone()),
+                    format!("watch path {} is not readable: {}", shown, e),
+                ));
+            }
+        }
+
+        for pattern in &self.monitoring.sensitive_patterns {
+            if let Err(e) = glob::Pattern::new(pattern) {
+                diags.push(ConfigDiagnostic::error(
+                    "monitoring.sensitive_patterns",
+                    Some(pattern.clone()),
+                    format!("invalid glob pattern '{}': {}", pattern, e),
+                ));
+            }
+        }
+
+        for pattern in &self.monitoring.fs_ignore {
+            if let Err(e) = glob::Pattern::new(pattern) {
+                diags.push(ConfigDiagnostic::error(
+                    "monitoring.fs_ignore",
+                    Some(pattern.clone()),
+                    format!("invalid glob pattern '{}': {}", pattern, e),
+                ));
+            }
+        }
+
+        let sanitize = &self.sanitize;
+        for pattern in &sanitize.patterns {
+            let message = if pattern.name.trim().is_empty() {
+                Some("secret pattern needs a name".to_string())
+            } else {
+                match regex::Regex::new(&pattern.regex) {
+                    Err(e) => Some(format!("invalid regex '{}': {}", pattern.regex, e)),
+                    Ok(re) if pattern.group >= re.captures_len() => Some(format!(
+                        "regex '{}' has no capture group {}",
+                        pattern.regex, pattern.group
+                    )),
+                    Ok(_) => None,
+                }
+            };
+            if let Some(message) = message {
+                diags.push(ConfigDiagnostic::error(
+                    "sanitize.patterns",
+                    Some(pattern.regex.clone()),
+                    message,
+                ));
+            }
+        }
+        for allowed in &sanitize.allowlist {
+            if let Err(e) = regex::Regex::new(allowed) {
+                diags.push(ConfigDiagnostic::error(
+                    "sanitize.allowlist",
+                    Some(allowed.clone()),
+                    format!("invalid regex '{}': {}", allowed, e),
+                ));
+            }
+        }
+        for flag in &sanitize.extra_flags {
+            if !flag.starts_with('-') {
+                diags.push(ConfigDiagnostic::warning(
+                    "sanitize.extra_flags",
+                    Some(flag.clone()),
+                    format!("'{}' is not a flag and will never match", flag),
+                ));
+            }
+        }
+
+        for host in &self.monitoring.network_whitelist {
+            if host.trim().is_empty() || host.contains("://") || host.contains('/') {
+                diags.push(ConfigDiagnostic::warning(
+                    "monitoring.network_whitelist",
+                    Some(host.clone()),
+                    format!("'{}' is not a bare host name and will never match", host),
+                ));
+            }
+        }
+
+        diags
+    }
+
+    /// Parse and validate a TOML config source.
+    ///
+    /// Parse failures are reported as a single error diagnostic. Otherwise
+    /// unknown keys are reported as warnings alongside the results of
+    /// [`Config::validate`], each annotated with its line in `content`.
+    pub fn validate_toml(content: &str) -> Vec<ConfigDiagnostic> {
+        let config: Config = match toml::from_str(content) {
+            Ok(config) => config,
+            Err(e) => {
+                let mut diag = ConfigDiagnostic::error("(file)", None, e.message().to_string());
+                diag.line = e.span().map(|span| line_of_offset(content, span.start));
+                return vec![diag];
+            }
+        };
+
+        let mut diags = unknown_keys(content, &config);
+        diags.extend(config.validate());
+
+        for diag in &mut diags {
+            if diag.line.is_none() {
+                diag.line = locate_key(content, &diag.key, diag.value.as_deref());
+            }
+        }
+        diags.sort_by_key(|d| d.line.unwrap_or(usize::MAX));
+        diags
+    }
+
+    /// Set a single `section.key` value from its string form.
+    ///
+    /// The value is parsed as a TOML literal when possible (`true`, `100`,
+    /// `["a", "b"]`) and as a plain string otherwise. For list settings a
+    /// comma-separated string is also accepted. The updated config must pass
+    /// [`Config::validate`] for that key or the change is rejected.
+    pub fn set_value(&mut self, key: &str, value: &str) -> Result<(), CoreError> {
+        let (section, field) = key
+            .split_once('.')
+            .filter(|(s, f)| !s.is_empty() && !f.is_empty() && !f.contains('.'))
+            .ok_or_else(|| ConfigError::UnknownKey(key.to_string()))?;
+
+        let mut doc = toml::Table::try_from(&*self).map_err(ConfigError::SerializeToml)?;
+        let table = doc
+            .get_mut(section)
+            .and_then(toml::Value::as_table_mut)
+            .ok_or_else(|| ConfigError::UnknownKey(key.to_string()))?;
+
+        let existing = table.get(field).cloned();
+        let mut parsed = toml::from_str::<toml::Table>(&format!("v = {}", value))
+            .ok()
+            .and_then(|mut t| t.remove("v"))
+            .unwrap_or_else(|| toml::Value::String(value.to_string()));
+        if matches!(existing, Some(toml::Value::Array(_))) && !parsed.is_array() {
+            parsed = toml::Value::Array(
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .map(|s| toml::Value::String(s.to_string()))
+                    .collect(),
+            );
+        } else if matches!(existing, Some(toml::Value::String(_))) && !parsed.is_str() {
+            parsed = toml::Value::String(value.to_string());
+        }
+        table.insert(field.to_string(), parsed);
+
+        let updated: Config =
+            toml::Value::Table(doc)
+                .try_into()
+                .map_err(|e: toml::de::Error| ConfigError::InvalidValue {
+                    key: key.to_string(),
+                    message: e.message().to_string(),
+                })?;
+
+        // Keys serde does not know are silently dropped on deserialization
+        let roundtrip = toml::Table::try_from(&updated).map_err(ConfigError::SerializeToml)?;
+        let known = roundtrip
+            .get(section)
+            .and_then(toml::Value::as_table)
+            .is_some_and(|t| t.contains_key(field));
+        if existing.is_none() && !known {
+            return Err(ConfigError::UnknownKey(key.to_string()).into());
+        }
+
+        if let Some(diag) = updated
+            .validate()
+            .into_iter()
+            .find(|d| d.is_error() && d.key == key)
+        {
+            return Err(ConfigError::InvalidValue {
+                key: key.to_string(),
+                message: diag.message,
+            }
+            .into());
+        }
+
+        *self = updated;
+        Ok(())
+    }
+}
+
+/// A single setting that differs between two configs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfigChange {
+    /// Dotted key (e.g. `monitoring.network_whitelist`)
+    pub key: String,
+    /// Previous value as TOML, `None` if the key was unset
+    pub old: Option<String>,
+    /// New value as TOML, `None` if the key was removed
+    pub new: Option<String>,
+}
+
+impl Config {
+    /// List the settings that differ between `self` and `other`, sorted by key
+    pub fn diff(&self, other: &Config) -> Vec<ConfigChange> {
+        let (Ok(old), Ok(new)) = (toml::Table::try_from(self), toml::Table::try_from(other)) else {
+            return Vec::new();
+        };
+
+        let mut old_flat = std::collections::BTreeMap::new();
+        let mut new_flat = std::collections::BTreeMap::new();
+        flatten_table("", &old, &mut old_flat);
+        flatten_table("", &new, &mut new_flat);
+
+        let keys: std::collections::BTreeSet<&String> =
+            old_flat.keys().chain(new_flat.keys()).collect();
+        keys.into_iter()
+            .filter_map(|key| {
+                let (o, n) = (old_flat.get(key), new_flat.get(key));
+                (o != n).then(|| ConfigChange {
+                    key: key.clone(),
+                    old: o.cloned(),
+                    new: n.cloned(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Flatten nested TOML tables into `section.key -> value` pairs
+fn flatten_table(
+    prefix: &str,
+    table: &toml::Table,
+    out: &mut std::collections::BTreeMap<String, String>,
+) {
+    for (key, value) in table {
+        let full_key = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+        match value {
+            toml::Value::Table(inner) => flatten_table(&full_key, inner, out),
+            other => {
+                out.insert(full_key, other.to_string());
+            }
+        }
+    }
+}
+
+/// Convert a byte offset into a 1-based line number
+fn line_of_offset(content: &str, offset: usize) -> usize {
+    content[..offset.min(content.len())].matches('\n').count() + 1
+}
+
+/// Report keys present in the source that the config schema does not know.
+///
+/// Serde ignores unknown fields, so a typo like `min_levle` would otherwise
+/// go unnoticed. Known keys are those that survive a parse/serialize round trip.
+fn unknown_keys(content: &str, config: &Config) -> Vec<ConfigDiagnostic> {
+    let (Ok(source), Ok(known)) = (
+        toml::from_str::<toml::Table>(content),
+        toml::Table::try_from(config),
+    ) else {
+        return Vec::new();
+    };
+
+    let mut diags = Vec::new();
+    for (section, value) in &source {
+        let Some(known_section) = known.get(section).and_then(toml::Value::as_table) else {
+            diags.push(ConfigDiagnostic::warning(
+                section,
+                None,
+                format!("unknown section '{}'", section),
+            ));
+            continue;
+        };
+        let Some(table) = value.as_table() else {
+            continue;
+        };
+        for field in table.keys() {
+            if !known_section.contains_key(field) {
+                diags.push(ConfigDiagnostic::warning(
+                    &format!("{}.{}", section, field),
+                    None,
+                    format!("unknown key '{}' in [{}]", field, section),
+                ));
+            }
+        }
+    }
+    diags
+}
+
+/// Find the line where `key` (dotted `section.field`) is set in a TOML source.
+///
+/// When `value` is given, the search continues past the key line to the first
+/// line containing that value as a quoted string, which pinpoints entries of
+/// multi-line arrays.
+fn locate_key(content: &str, key: &str, value: Option<&str>) -> Option<usize> {
+    let (section, field) = key.split_once('.').unwrap_or((key, ""));
+    let mut current_section = String::new();
+    let mut key_line = None;
+
+    for (idx, line) in content.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') && !trimmed.starts_with("[[") && trimmed.ends_with(']') {
+            if key_line.is_some() {
+                break;
+            }
+            current_section = trimmed[1..trimmed.len() - 1].trim().to_string();
+            if field.is_empty() && current_section == section {
+                return Some(idx + 1);
+            }
+            continue;
+        }
+        if current_section != section || trimmed.starts_with('#') {
+            continue;
+        }
+
+        if key_line.is_none() {
+            let is_key = trimmed
+                .strip_prefix(field)
+                .is_some_and(|rest| rest.trim_start().starts_with('='));
+            if !field.is_empty() && is_key {
+                key_line = Some(idx + 1);
+            } else {
+                continue;
+            }
+        }
+
+        match value {
+            Some(v)
+                if line.contains(&format!("\"{}\"", v)) || line.contains(&format!("'{}'", v)) =>
+            {
+                return Some(idx + 1);
+            }
+            Some(_) => {}
+            None => return key_line,
+        }
+    }
+
+    key_line
+}
+
 /// General configuration settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
@@ -165,6 +699,39 @@ pub enum StorageBackend {
     Both,
 }
 
+/// Compression applied to closed session logs
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    /// Leave session logs uncompressed
+    None,
+    /// gzip (`.jsonl.gz`)
+    Gzip,
+    /// Zstandard (`.jsonl.zst`, default)
+    #[default]
+    Zstd,
+}
+
+impl Compression {
+    /// File name suffix appended to `.jsonl`, without the dot
+    pub fn extension(self) -> Option<&'static str> {
+        match self {
+            Compression::None => None,
+            Compression::Gzip => Some("gz"),
+            Compression::Zstd => Some("zst"),
+        }
+    }
+
+    /// Compression indicated by a file name suffix
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        match ext {
+            "gz" => Some(Compression::Gzip),
+            "zst" => Some(Compression::Zstd),
+            _ => None,
+        }
+    }
+}
+
 /// Logging configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
@@ -177,6 +744,14 @@ pub struct LoggingConfig {
     pub retention_days: u32,
     /// Storage backend selection
     pub storage_backend: StorageBackend,
+    /// Size cap for a single session log file in MB; when reached the
+    /// session rolls over to a new segment (0 = no limit)
+    pub max_session_mb: u64,
+    /// Size cap in MB for all session logs, and separately for the SQLite
+    /// database; the oldest closed sessions are removed first (0 = no limit)
+    pub max_total_mb: u64,
+    /// Compression applied to session logs once the session has ended
+    pub compression: Compression,
 }
 
 impl Default for LoggingConfig {
@@ -186,6 +761,9 @@ impl Default for LoggingConfig {
             log_dir: None,
             retention_days: 30,
             storage_backend: StorageBackend::default(),
+            max_session_mb: 100,
+            max_total_mb: 2048,
+            compression: Compression::default(),
         }
     }
 }
@@ -198,6 +776,27 @@ impl LoggingConfig {
             None => Config::default_log_dir(),
         }
     }
+
+    /// Get the SQLite database path used by the `sqlite` and `both` backends
+    pub fn effective_db_path(&self) -> Result<PathBuf, CoreError> {
+        self.effective_log_dir().map(|dir| dir.join("events.db"))
+    }
+
+    /// Whether events are written to the SQLite database
+    pub fn uses_sqlite(&self) -> bool {
+        matches!(
+            self.storage_backend,
+            StorageBackend::Sqlite | StorageBackend::Both
+        )
+    }
+
+    /// Whether events are written to JSONL session files
+    pub fn uses_jsonl(&self) -> bool {
+        matches!(
+            self.storage_backend,
+            StorageBackend::Jsonl | StorageBackend::Both
+        )
+    }
 }
 
 /// Monitoring configuration
@@ -212,10 +811,28 @@ pub struct MonitoringConfig {
     pub track_children: bool,
     /// Child process tracking poll interval in milliseconds
     pub tracking_poll_ms: u64,
-    /// FSEvents debounce time in milliseconds
+    /// Low-risk file events on the same path and action within this time
+    /// (milliseconds) are dropped
     pub fs_debounce_ms: u64,
+    /// Low-risk file events of one action within a second at which they are
+    /// collapsed into summary events per directory (0 disables summaries)
+    pub fs_burst_threshold: usize,
+    /// Globs for paths whose low-risk file events are dropped, matched
+    /// against the full path, the file name and each directory name
+    pub fs_ignore: Vec<String>,
+    /// Snapshot the watched paths at session start and store what changed
+    /// by the end next to the session log
+    pub fs_snapshot: bool,
     /// Network polling interval in milliseconds
     pub net_poll_ms: u64,
+    /// Megabytes sent over one connection to a non-whitelisted host at
+    /// which its close event is Critical (0 disables the rule)
+    pub net_upload_alert_mb: u64,
+    /// Point the wrapped process at a local HTTP proxy that reports the
+    /// hostname of each request
+    pub net_proxy: bool,
+    /// Refuse proxied requests to hosts outside the network whitelist
+    pub net_proxy_block: bool,
     /// Paths to watch for file system events
     pub watch_paths: Vec<PathBuf>,
     /// Sensitive file patterns (glob patterns)
@@ -232,7 +849,13 @@ impl Default for MonitoringConfig {
             track_children: true,
             tracking_poll_ms: 100,
             fs_debounce_ms: 100,
+            fs_burst_threshold: 50,
+            fs_ignore: Vec::new(),
+            fs_snapshot: false,
             net_poll_ms: 500,
+            net_upload_alert_mb: 10,
+            net_proxy: false,
+            net_proxy_block: false,
             watch_paths: Vec::new(),
             sensitive_patterns: vec![
                 ".env".to_string(),
@@ -252,7 +875,7 @@ impl Default for MonitoringConfig {
 }
 
 impl MonitoringConfig {
-    /// Get FSEvents debounce duration
+    /// Get the file event debounce duration
     pub fn fs_debounce_duration(&self) -> Duration {
         Duration::from_millis(self.fs_debounce_ms)
     }
@@ -312,6 +935,32 @@ impl Default for AlertConfig {
     }
 }
 
+/// Sensitive data masking settings, added to the built-in rules
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SanitizeConfig {
+    /// Flags whose value is masked, as the next argument or `--flag=value`
+    pub extra_flags: Vec<String>,
+    /// Environment variables whose value is masked in `NAME=value`
+    pub extra_env: Vec<String>,
+    /// Additional secret formats, reported under their own name
+    pub patterns: Vec<SecretPatternConfig>,
+    /// Regexes for values that are never masked (matched against the whole value)
+    pub allowlist: Vec<String>,
+}
+
+/// A custom secret format in the `[sanitize]` section
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SecretPatternConfig {
+    /// Rule name shown in redactions, e.g. `***[vault-token]***`
+    pub name: String,
+    /// Regex matching the secret
+    pub regex: String,
+    /// Capture group holding the secret (0 = the whole match)
+    #[serde(default)]
+    pub group: usize,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -473,4 +1122,262 @@ verbose = true
         let result = Config::from_toml("invalid { toml content");
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_validate_default_config_is_clean() {
+        assert!(Config::default().validate().is_empty());
+        assert!(Config::validate_toml(Config::SAMPLE_CONFIG).is_empty());
+    }
+
+    #[test]
+    fn test_validate_toml_reports_invalid_levels_with_lines() {
+        let content = r#"
+[general]
+default_format = "yaml"
+
+[alerts]
+min_level = "severe"
+
+[notifications]
+min_risk_level = "hgh"
+"#;
+        let diags = Config::validate_toml(content);
+        assert_eq!(diags.len(), 3);
+        assert!(diags.iter().all(ConfigDiagnostic::is_error));
+        assert_eq!(diags[0].key, "general.default_format");
+        assert_eq!(diags[0].line, Some(3));
+        assert_eq!(diags[1].key, "alerts.min_level");
+        assert_eq!(diags[1].line, Some(6));
+        assert_eq!(diags[2].key, "notifications.min_risk_level");
+        assert_eq!(diags[2].line, Some(9));
+    }
+
+    #[test]
+    fn test_validate_toml_bad_glob_in_multiline_array() {
+        let content = r#"[monitoring]
+sensitive_patterns = [
+    ".env",
+    "[unclosed",
+]
+"#;
+        let diags = Config::validate_toml(content);
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].key, "monitoring.sensitive_patterns");
+        assert_eq!(diags[0].value.as_deref(), Some("[unclosed"));
+        assert_eq!(diags[0].line, Some(4));
+    }
+
+    #[test]
+    fn test_validate_toml_bad_fs_ignore_glob() {
+        let content =
+            "[monitoring]\nfs_burst_threshold = 20\nfs_ignore = [\"node_modules\", \"[tmp\"]\n";
+        let diags = Config::validate_toml(content);
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].key, "monitoring.fs_ignore");
+        assert_eq!(diags[0].value.as_deref(), Some("[tmp"));
+        assert_eq!(diags[0].line, Some(3));
+
+        let config = Config::from_toml(content).unwrap();
+        assert_eq!(config.monitoring.fs_burst_threshold, 20);
+        assert_eq!(config.monitoring.fs_ignore.len(), 2);
+    }
+
+    #[test]
+    fn test_validate_toml_parse_error_has_line() {
+        let diags = Config::validate_toml("[general]\nverbose = \n");
+        assert_eq!(diags.len(), 1);
+        assert!(diags[0].is_error());
+        assert_eq!(diags[0].line, Some(2));
+    }
+
+    #[test]
+    fn test_validate_toml_unknown_key_warning() {
+        let content = "[alerts]\nmin_levle = \"high\"\n";
+        let diags = Config::validate_toml(content);
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].severity, DiagnosticSeverity::Warning);
+        assert_eq!(diags[0].key, "alerts.min_levle");
+        assert_eq!(diags[0].line, Some(2));
+    }
+
+    #[test]
+    fn test_validate_missing_watch_path_and_zero_poll() {
+        let mut config = Config::default();
+        config.monitoring.watch_paths = vec![PathBuf::from("/nonexistent/macagentwatch/path")];
+        config.monitoring.net_poll_ms = 0;
+
+        let diags = config.validate();
+        assert_eq!(diags.len(), 2);
+        assert!(diags
+            .iter()
+            .any(|d| d.key == "monitoring.watch_paths" && !d.is_error()));
+        assert!(diags
+            .iter()
+            .any(|d| d.key == "monitoring.net_poll_ms" && d.is_error()));
+    }
+
+    #[test]
+    fn test_diagnostic_display() {
+        let mut diag = ConfigDiagnostic::error("alerts.min_level", None, "bad level");
+        assert_eq!(diag.to_string(), "error: alerts.min_level: bad level");
+        diag.line = Some(4);
+        assert_eq!(
+            diag.to_string(),
+            "line 4: error: alerts.min_level: bad level"
+        );
+    }
+
+    #[test]
+    fn test_set_value_scalars() {
+        let mut config = Config::default();
+        config.set_value("general.verbose", "true").unwrap();
+        config.set_value("monitoring.net_poll_ms", "250").unwrap();
+        config.set_value("alerts.min_level", "medium").unwrap();
+        config
+            .set_value("logging.storage_backend", "sqlite")
+            .unwrap();
+
+        assert!(config.general.verbose);
+        assert_eq!(config.monitoring.net_poll_ms, 250);
+        assert_eq!(config.alerts.min_level, "medium");
+        assert_eq!(config.logging.storage_backend, StorageBackend::Sqlite);
+    }
+
+    #[test]
+    fn test_set_value_lists_and_optional() {
+        let mut config = Config::default();
+        config
+            .set_value(
+                "monitoring.network_whitelist",
+                "example.com, api.example.com",
+            )
+            .unwrap();
+        assert_eq!(
+            config.monitoring.network_whitelist,
+            vec!["example.com", "api.example.com"]
+        );
+
+        config
+            .set_value("monitoring.sensitive_patterns", r#"["*.pem", ".env"]"#)
+            .unwrap();
+        assert_eq!(config.monitoring.sensitive_patterns, vec!["*.pem", ".env"]);
+
+        config
+            .set_value("logging.log_dir", "/tmp/agent-logs")
+            .unwrap();
+        assert_eq!(
+            config.logging.log_dir,
+            Some(PathBuf::from("/tmp/agent-logs"))
+        );
+    }
+
+    #[test]
+    fn test_set_value_rejects_invalid() {
+        let mut config = Config::default();
+        assert!(matches!(
+            config.set_value("alerts.min_level", "severe"),
+            Err(CoreError::Config(ConfigError::InvalidValue { .. }))
+        ));
+        assert!(matches!(
+            config.set_value("monitoring.net_poll_ms", "soon"),
+            Err(CoreError::Config(ConfigError::InvalidValue { .. }))
+        ));
+        assert!(matches!(
+            config.set_value("alerts.nonexistent", "1"),
+            Err(CoreError::Config(ConfigError::UnknownKey(_)))
+        ));
+        assert!(matches!(
+            config.set_value("verbose", "true"),
+            Err(CoreError::Config(ConfigError::UnknownKey(_)))
+        ));
+        assert_eq!(config.alerts.min_level, "high");
+    }
+
+    #[test]
+    fn test_config_diff() {
+        let old = Config::default();
+        let mut new = Config::default();
+        assert!(old.diff(&new).is_empty());
+
+        new.monitoring
+            .network_whitelist
+            .push("example.com".to_string());
+        new.alerts.min_level = "medium".to_string();
+        new.logging.log_dir = Some(PathBuf::from("/tmp/logs"));
+
+        let changes = old.diff(&new);
+        let keys: Vec<&str> = changes.iter().map(|c| c.key.as_str()).collect();
+        assert_eq!(
+            keys,
+            vec![
+                "alerts.min_level",
+                "logging.log_dir",
+                "monitoring.network_whitelist"
+            ]
+        );
+        assert_eq!(changes[0].old.as_deref(), Some("\"high\""));
+        assert_eq!(changes[0].new.as_deref(), Some("\"medium\""));
+        assert_eq!(changes[1].old, None);
+        assert!(changes[2].new.as_ref().unwrap().contains("example.com"));
+    }
+
+    #[test]
+    fn test_write_sample_refuses_overwrite() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("nested").join("config.toml");
+
+        Config::write_sample(&path, false).unwrap();
+        assert!(path.exists());
+        assert!(matches!(
+            Config::write_sample(&path, false),
+            Err(CoreError::Config(ConfigError::AlreadyExists { .. }))
+        ));
+        Config::write_sample(&path, true).unwrap();
+    }
+
+    #[test]
+    fn test_sanitize_section() {
+        let config = Config::from_toml(
+            r#"
+[sanitize]
+extra_flags = ["--vault-token"]
+extra_env = ["ACME_DEPLOY_KEY"]
+patterns = [{ name = "vault-token", regex = 'hvs\.[A-Za-z0-9]{24,}' }]
+allowlist = ['sk_test_\w+']
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.sanitize.extra_flags, vec!["--vault-token"]);
+        assert_eq!(config.sanitize.extra_env, vec!["ACME_DEPLOY_KEY"]);
+        assert_eq!(config.sanitize.patterns[0].name, "vault-token");
+        assert_eq!(config.sanitize.patterns[0].group, 0);
+        assert!(config.validate().is_empty());
+        assert!(Config::default().sanitize.extra_flags.is_empty());
+    }
+
+    #[test]
+    fn test_validate_sanitize_section() {
+        let content = r#"
+[sanitize]
+extra_flags = ["vault-token"]
+patterns = [
+    { name = "bad", regex = "(" },
+    { name = "no-group", regex = "abc", group = 1 },
+]
+allowlist = ["["]
+"#;
+        let diags = Config::validate_toml(content);
+        let found: Vec<(&str, DiagnosticSeverity)> =
+            diags.iter().map(|d| (d.key.as_str(), d.severity)).collect();
+        assert_eq!(
+            found,
+            vec![
+                ("sanitize.extra_flags", DiagnosticSeverity::Warning),
+                ("sanitize.patterns", DiagnosticSeverity::Error),
+                ("sanitize.patterns", DiagnosticSeverity::Error),
+                ("sanitize.allowlist", DiagnosticSeverity::Error),
+            ]
+        );
+        assert_eq!(diags[1].line, Some(5));
+    }
 }
@@ -1,12 +1,17 @@
 //! Detection module for MacAgentWatch
 //!
-//! Provides pattern-based detection for sensitive files and network connections.
+//! Provides pattern-based detection for sensitive files and network connections,
+//! and a catalogue of well-known network providers.
 
 use crate::event::RiskLevel;
 use glob::Pattern;
-use std::collections::HashSet;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::net::{IpAddr, ToSocketAddrs};
 use std::path::{Path, PathBuf};
-use std::sync::LazyLock;
+use std::sync::{mpsc, LazyLock, Once, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
 
 /// Pre-computed lowercase sensitive directory patterns for efficient matching
 static SENSITIVE_DIRS_LOWER: LazyLock<Vec<&'static str>> =
@@ -234,6 +239,228 @@ impl Detector<NetworkConnection> for NetworkWhitelist {
     }
 }
 
+/// Kind of service a network destination belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProviderCategory {
+    /// Hosted LLM APIs
+    Llm,
+    /// Package registries and their download mirrors
+    PackageRegistry,
+    /// Source code hosting
+    Vcs,
+    /// Anything not in the catalogue
+    Other,
+}
+
+impl ProviderCategory {
+    /// All categories, in display order
+    pub const ALL: [ProviderCategory; 4] = [
+        ProviderCategory::Llm,
+        ProviderCategory::PackageRegistry,
+        ProviderCategory::Vcs,
+        ProviderCategory::Other,
+    ];
+
+    /// Stable identifier, as used in session footers
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProviderCategory::Llm => "llm",
+            ProviderCategory::PackageRegistry => "package_registry",
+            ProviderCategory::Vcs => "vcs",
+            ProviderCategory::Other => "other",
+        }
+    }
+}
+
+impl std::fmt::Display for ProviderCategory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A well-known network service, matched by host and its subdomains
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Provider {
+    /// Lowercase hostname; its subdomains match too
+    pub host: String,
+    /// Display name, e.g. "OpenAI"
+    pub name: String,
+    /// Kind of service
+    pub category: ProviderCategory,
+}
+
+impl Provider {
+    /// Create a provider for `host`, which is lowercased
+    pub fn new(host: &str, name: &str, category: ProviderCategory) -> Self {
+        Self {
+            host: host.to_ascii_lowercase(),
+            name: name.to_string(),
+            category,
+        }
+    }
+}
+
+/// How long [`ProviderCatalogue::resolved_default`] waits for DNS answers
+const PROVIDER_RESOLVE_TIMEOUT: Duration = Duration::from_secs(5);
+
+static RESOLVED_CATALOGUE: OnceLock<ProviderCatalogue> = OnceLock::new();
+static DEFAULT_CATALOGUE: LazyLock<ProviderCatalogue> = LazyLock::new(ProviderCatalogue::default);
+static RESOLVE_DEFAULT: Once = Once::new();
+
+/// Classifies network destinations as LLM APIs, package registries or code hosts
+///
+/// Hostnames match a provider's host or any of its subdomains. Connections seen
+/// by the network monitor only carry an address, so the catalogue can also
+/// match the addresses its hosts resolve to (see [`Self::resolve_addresses`]).
+#[derive(Debug, Clone)]
+pub struct ProviderCatalogue {
+    providers: Vec<Provider>,
+    /// Resolved address to index into `providers`
+    addresses: HashMap<IpAddr, usize>,
+}
+
+impl Default for ProviderCatalogue {
+    fn default() -> Self {
+        Self::new(default_providers())
+    }
+}
+
+impl ProviderCatalogue {
+    /// Create a catalogue from the given providers
+    pub fn new(providers: Vec<Provider>) -> Self {
+        Self {
+            providers,
+            addresses: HashMap::new(),
+        }
+    }
+
+    /// The built-in catalogue, matching hostnames only
+    pub fn builtin() -> &'static Self {
+        &DEFAULT_CATALOGUE
+    }
+
+    /// The built-in catalogue, including resolved addresses once known
+    ///
+    /// The first call starts resolving the built-in hosts on a background
+    /// thread and returns the hostname-only catalogue; later calls return the
+    /// resolved one as soon as resolution has finished. Used when events are
+    /// recorded, so they keep the classification of that moment.
+    pub fn resolved_default() -> &'static Self {
+        RESOLVE_DEFAULT.call_once(|| {
+            let spawned = thread::Builder::new()
+                .name("provider-resolve".into())
+                .spawn(|| {
+                    let mut catalogue = ProviderCatalogue::default();
+                    catalogue.resolve_addresses(PROVIDER_RESOLVE_TIMEOUT);
+                    let _ = RESOLVED_CATALOGUE.set(catalogue);
+                });
+            if let Err(e) = spawned {
+                eprintln!(
+                    "[agent-watch] Warning: cannot resolve provider hosts: {}",
+                    e
+                );
+            }
+        });
+        RESOLVED_CATALOGUE.get().unwrap_or(&DEFAULT_CATALOGUE)
+    }
+
+    /// Add a provider; earlier entries win for identical hosts
+    pub fn add(&mut self, provider: Provider) {
+        self.providers.push(provider);
+    }
+
+    /// Providers in the catalogue
+    pub fn providers(&self) -> &[Provider] {
+        &self.providers
+    }
+
+    /// Number of addresses learned by [`Self::resolve_addresses`]
+    pub fn resolved_count(&self) -> usize {
+        self.addresses.len()
+    }
+
+    /// Resolve every provider host so connections to bare addresses can be
+    /// classified
+    ///
+    /// Lookups run in parallel; answers arriving after `timeout` are dropped.
+    /// Returns the number of addresses learned.
+    pub fn resolve_addresses(&mut self, timeout: Duration) -> usize {
+        let (tx, rx) = mpsc::channel();
+        let mut pending = 0;
+        for (index, provider) in self.providers.iter().enumerate() {
+            if provider.host.parse::<IpAddr>().is_ok() {
+                continue;
+            }
+            let tx = tx.clone();
+            let host = provider.host.clone();
+            let spawned = thread::Builder::new()
+                .name("provider-lookup".into())
+                .spawn(move || {
+                    let addrs: Vec<IpAddr> = (host.as_str(), 443)
+                        .to_socket_addrs()
+                        .map(|addrs| addrs.map(|a| a.ip()).collect())
+                        .unwrap_or_default();
+                    let _ = tx.send((index, addrs));
+                });
+            if spawned.is_ok() {
+                pending += 1;
+            }
+        }
+        drop(tx);
+
+        let deadline = Instant::now() + timeout;
+        let mut learned = 0;
+        while pending > 0 {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let Ok((index, addrs)) = rx.recv_timeout(remaining) else {
+                break;
+            };
+            pending -= 1;
+            for addr in addrs {
+                if let std::collections::hash_map::Entry::Vacant(entry) = self.addresses.entry(addr)
+                {
+                    entry.insert(index);
+                    learned += 1;
+                }
+            }
+        }
+        learned
+    }
+
+    /// Find the provider for a hostname or address
+    ///
+    /// The most specific host wins, so `api.github.com` can be listed apart
+    /// from `github.com`.
+    pub fn classify(&self, host: &str) -> Option<&Provider> {
+        let host = host.trim_end_matches('.').to_ascii_lowercase();
+        let bare = host.trim_start_matches('[').trim_end_matches(']');
+        if let Ok(addr) = bare.parse::<IpAddr>() {
+            if let Some(provider) = self.providers.iter().find(|p| p.host == bare) {
+                return Some(provider);
+            }
+            return self.addresses.get(&addr).map(|&i| &self.providers[i]);
+        }
+
+        self.providers
+            .iter()
+            .filter(|p| {
+                host == p.host
+                    || host
+                        .strip_suffix(p.host.as_str())
+                        .is_some_and(|rest| rest.ends_with('.'))
+            })
+            .max_by_key(|p| p.host.len())
+    }
+
+    /// Category of a hostname or address
+    pub fn category(&self, host: &str) -> ProviderCategory {
+        self.classify(host)
+            .map(|p| p.category)
+            .unwrap_or(ProviderCategory::Other)
+    }
+}
+
 /// Default sensitive file patterns
 pub fn default_sensitive_patterns() -> Vec<String> {
     vec![
@@ -285,6 +512,51 @@ pub fn default_network_whitelist() -> Vec<String> {
     ]
 }
 
+/// Default provider catalogue entries
+pub fn default_providers() -> Vec<Provider> {
+    use ProviderCategory::*;
+    [
+        ("api.anthropic.com", "Anthropic", Llm),
+        ("api.openai.com", "OpenAI", Llm),
+        ("openai.azure.com", "Azure OpenAI", Llm),
+        ("generativelanguage.googleapis.com", "Google Gemini", Llm),
+        ("aiplatform.googleapis.com", "Google Vertex AI", Llm),
+        ("api.mistral.ai", "Mistral", Llm),
+        ("api.cohere.com", "Cohere", Llm),
+        ("api.cohere.ai", "Cohere", Llm),
+        ("api.groq.com", "Groq", Llm),
+        ("openrouter.ai", "OpenRouter", Llm),
+        ("api.deepseek.com", "DeepSeek", Llm),
+        ("api.x.ai", "xAI", Llm),
+        ("api.together.xyz", "Together AI", Llm),
+        ("api.perplexity.ai", "Perplexity", Llm),
+        ("api.fireworks.ai", "Fireworks AI", Llm),
+        ("registry.npmjs.org", "npm", PackageRegistry),
+        ("registry.yarnpkg.com", "Yarn", PackageRegistry),
+        ("pypi.org", "PyPI", PackageRegistry),
+        ("files.pythonhosted.org", "PyPI", PackageRegistry),
+        ("crates.io", "crates.io", PackageRegistry),
+        ("rubygems.org", "RubyGems", PackageRegistry),
+        ("proxy.golang.org", "Go modules", PackageRegistry),
+        ("sum.golang.org", "Go modules", PackageRegistry),
+        ("repo.maven.apache.org", "Maven Central", PackageRegistry),
+        ("repo1.maven.org", "Maven Central", PackageRegistry),
+        ("api.nuget.org", "NuGet", PackageRegistry),
+        ("packagist.org", "Packagist", PackageRegistry),
+        ("repo.packagist.org", "Packagist", PackageRegistry),
+        ("hex.pm", "Hex", PackageRegistry),
+        ("pub.dev", "pub.dev", PackageRegistry),
+        ("github.com", "GitHub", Vcs),
+        ("githubusercontent.com", "GitHub", Vcs),
+        ("gitlab.com", "GitLab", Vcs),
+        ("bitbucket.org", "Bitbucket", Vcs),
+        ("codeberg.org", "Codeberg", Vcs),
+    ]
+    .into_iter()
+    .map(|(host, name, category)| Provider::new(host, name, category))
+    .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -539,4 +811,82 @@ mod tests {
             let _ = result;
         }
     }
+
+    // Provider catalogue tests
+
+    #[test]
+    fn test_provider_catalogue_classifies_hosts() {
+        let catalogue = ProviderCatalogue::default();
+
+        assert_eq!(
+            catalogue.category("api.anthropic.com"),
+            ProviderCategory::Llm
+        );
+        assert_eq!(catalogue.category("API.OpenAI.com."), ProviderCategory::Llm);
+        assert_eq!(
+            catalogue.category("registry.npmjs.org"),
+            ProviderCategory::PackageRegistry
+        );
+        assert_eq!(
+            catalogue.category("index.crates.io"),
+            ProviderCategory::PackageRegistry
+        );
+        assert_eq!(
+            catalogue.category("raw.githubusercontent.com"),
+            ProviderCategory::Vcs
+        );
+        assert_eq!(catalogue.category("example.com"), ProviderCategory::Other);
+        assert_eq!(
+            catalogue.classify("myteam.openai.azure.com").unwrap().name,
+            "Azure OpenAI"
+        );
+    }
+
+    #[test]
+    fn test_provider_catalogue_requires_label_boundary() {
+        let catalogue = ProviderCatalogue::default();
+
+        assert!(catalogue.classify("evilgithub.com").is_none());
+        assert!(catalogue.classify("github.com.evil.net").is_none());
+    }
+
+    #[test]
+    fn test_provider_catalogue_prefers_most_specific_host() {
+        let mut catalogue = ProviderCatalogue::default();
+        catalogue.add(Provider::new(
+            "models.github.com",
+            "GitHub Models",
+            ProviderCategory::Llm,
+        ));
+
+        assert_eq!(
+            catalogue.category("models.github.com"),
+            ProviderCategory::Llm
+        );
+        assert_eq!(catalogue.category("api.github.com"), ProviderCategory::Vcs);
+    }
+
+    #[test]
+    fn test_provider_catalogue_matches_resolved_addresses() {
+        let mut catalogue = ProviderCatalogue::new(vec![
+            Provider::new("localhost", "Local model", ProviderCategory::Llm),
+            Provider::new("10.1.2.3", "Gateway", ProviderCategory::Llm),
+        ]);
+
+        assert_eq!(catalogue.category("10.1.2.3"), ProviderCategory::Llm);
+        assert_eq!(catalogue.category("127.0.0.1"), ProviderCategory::Other);
+
+        assert!(catalogue.resolve_addresses(Duration::from_secs(5)) > 0);
+        assert_eq!(catalogue.classify("127.0.0.1").unwrap().name, "Local model");
+        assert_eq!(catalogue.category("192.0.2.1"), ProviderCategory::Other);
+    }
+
+    #[test]
+    fn test_provider_category_serialization() {
+        assert_eq!(
+            serde_json::to_string(&ProviderCategory::PackageRegistry).unwrap(),
+            "\"package_registry\""
+        );
+        assert_eq!(ProviderCategory::Llm.to_string(), "llm");
+    }
 }
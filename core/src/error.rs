@@ -33,6 +33,14 @@ pub enum CoreError {
     #[error("Network monitor error: {0}")]
     NetMon(String),
 
+    /// Attaching to a running process failed
+    #[error("Attach error: {0}")]
+    Attach(String),
+
+    /// Egress proxy errors
+    #[error("Egress proxy error: {0}")]
+    Proxy(String),
+
     /// Generic I/O error
     #[error("I/O error: {0}")]
     Io(#[from] std::io::Error),
@@ -73,6 +81,18 @@ pub enum ConfigError {
         path: PathBuf,
         source: std::io::Error,
     },
+
+    /// Config file already exists and overwrite was not requested
+    #[error("Config file already exists: {path}")]
+    AlreadyExists { path: PathBuf },
+
+    /// Setting key does not exist
+    #[error("Unknown config key: {0}")]
+    UnknownKey(String),
+
+    /// Value rejected for a setting key
+    #[error("Invalid value for {key}: {message}")]
+    InvalidValue { key: String, message: String },
 }
 
 /// Storage-specific errors
@@ -107,6 +127,10 @@ pub enum StorageError {
     /// SQLite error
     #[error("SQLite error: {0}")]
     Sqlite(#[from] rusqlite::Error),
+
+    /// Session log has no header and no `session-{id}.jsonl` file name
+    #[error("Cannot determine session ID for {path}")]
+    MissingSessionId { path: PathBuf },
 }
 
 /// Convenience type alias
@@ -2,12 +2,35 @@
 //!
 //! Defines all event types that can be captured during agent monitoring.
 
+use crate::config::ConfigChange;
+use crate::detector::{Provider, ProviderCatalogue, ProviderCategory};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
 // Re-export shared types for backward compatibility
-pub use crate::types::{FileAction, ProcessAction, RiskLevel, SessionAction};
+pub use crate::types::{
+    AnomalyKind, FileAction, ProcessAction, ResourceMetric, RiskLevel, SessionAction,
+    TrackerBackend,
+};
+
+/// Provider a network destination matched when the event was recorded
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ProviderTag {
+    /// Provider name, e.g. "OpenAI"
+    pub name: String,
+    /// Kind of service
+    pub category: ProviderCategory,
+}
+
+impl From<&Provider> for ProviderTag {
+    fn from(provider: &Provider) -> Self {
+        Self {
+            name: provider.name.clone(),
+            category: provider.category,
+        }
+    }
+}
 
 /// Type of event captured
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -37,21 +60,164 @@ pub enum EventType {
         port: u16,
         /// Protocol (tcp, udp)
         protocol: String,
+        /// Provider the host matched when the connection was seen
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        provider: Option<ProviderTag>,
+    },
+    /// Network connection closed, with how long it was open and how much
+    /// data moved over it
+    NetworkClose {
+        /// Remote host
+        host: String,
+        /// Remote port
+        port: u16,
+        /// Protocol (tcp, udp)
+        protocol: String,
+        /// Time from when the connection was first seen until it was gone,
+        /// accurate to the network poll interval
+        duration_ms: u64,
+        /// Payload bytes sent, where the platform reports per-socket counters
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        bytes_sent: Option<u64>,
+        /// Payload bytes received, where the platform reports per-socket
+        /// counters
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        bytes_received: Option<u64>,
+        /// Provider the host matched when the connection was seen
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        provider: Option<ProviderTag>,
     },
     /// Process lifecycle
     Process {
-        /// Process ID
+        /// Process ID. Serialized as `process_pid` because the event's own
+        /// `pid` shares the flattened object.
+        #[serde(rename = "process_pid")]
         pid: u32,
         /// Parent process ID
         ppid: Option<u32>,
         /// Process action
         action: ProcessAction,
+        /// Outcome and resource usage, on exit events
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        exit: Option<ExitStats>,
+        /// How the process tracker observed the process
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        backend: Option<TrackerBackend>,
     },
     /// Session lifecycle
     Session {
         /// Session action
         action: SessionAction,
     },
+    /// Configuration reloaded during a running session
+    ConfigReload {
+        /// Config file that changed
+        path: PathBuf,
+        /// Settings that differ from the previously active config
+        changes: Vec<ConfigChange>,
+    },
+    /// Resource usage of a process tree crossed a limit
+    Resource {
+        /// What was measured
+        metric: ResourceMetric,
+        /// Measured value, in the metric's unit
+        value: u64,
+        /// Limit the value exceeded
+        limit: u64,
+    },
+    /// Behaviour deviated from the agent's baseline of earlier sessions
+    Anomaly {
+        /// What deviated
+        kind: AnomalyKind,
+        /// Distance from the baseline, in standard deviations
+        score: u32,
+        /// What was observed compared with what is usual
+        explanation: String,
+    },
+    /// A burst of low-risk file events collapsed into one
+    FileSummary {
+        /// Directory the files are under
+        path: PathBuf,
+        /// Type of access
+        action: FileAction,
+        /// Number of file events collapsed
+        count: u64,
+    },
+    /// A process started accepting inbound connections
+    Listen {
+        /// Bound local address (`0.0.0.0` or `::` for all interfaces)
+        address: String,
+        /// Bound local port
+        port: u16,
+        /// Protocol (tcp)
+        protocol: String,
+    },
+    /// A shell whose standard input or output is a network socket
+    ReverseShell {
+        /// Remote host the socket is connected to
+        host: String,
+        /// Remote port
+        port: u16,
+        /// Protocol (tcp, udp)
+        protocol: String,
+    },
+    /// A request made through the egress proxy, named by hostname rather
+    /// than the IP address socket polling sees
+    ProxyRequest {
+        /// HTTP method (`CONNECT` for tunnels)
+        method: String,
+        /// Destination hostname. For tunnels that start a TLS handshake,
+        /// the server name it asks for.
+        host: String,
+        /// Destination port
+        port: u16,
+        /// Whether the proxy refused the request
+        blocked: bool,
+        /// Provider the hostname matched when the request was made
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        provider: Option<ProviderTag>,
+    },
+}
+
+impl EventType {
+    /// Snake-case name of the variant, as used for `type` in serialized events
+    pub fn tag(&self) -> &'static str {
+        match self {
+            EventType::Command { .. } => "command",
+            EventType::FileAccess { .. } => "file_access",
+            EventType::Network { .. } => "network",
+            EventType::NetworkClose { .. } => "network_close",
+            EventType::Process { .. } => "process",
+            EventType::Session { .. } => "session",
+            EventType::ConfigReload { .. } => "config_reload",
+            EventType::Resource { .. } => "resource",
+            EventType::Anomaly { .. } => "anomaly",
+            EventType::FileSummary { .. } => "file_summary",
+            EventType::Listen { .. } => "listen",
+            EventType::ReverseShell { .. } => "reverse_shell",
+            EventType::ProxyRequest { .. } => "proxy_request",
+        }
+    }
+}
+
+/// Outcome and resource usage of a process that exited
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExitStats {
+    /// Exit code, if the process was seen before it was reaped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    /// Wall-clock run time from when the process was first seen
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    /// User plus system CPU time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_time_ms: Option<u64>,
+    /// Highest resident set size observed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub peak_rss_bytes: Option<u64>,
+    /// ID of the command event for the program the process ran
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command_id: Option<uuid::Uuid>,
 }
 
 /// A monitoring event captured by MacAgentWatch
@@ -144,6 +310,8 @@ impl Event {
                 pid,
                 ppid,
                 action: ProcessAction::Start,
+                exit: None,
+                backend: None,
             },
             process,
             pid,
@@ -151,19 +319,165 @@ impl Event {
         )
     }
 
+    /// Create a config reload event
+    pub fn config_reload(path: PathBuf, changes: Vec<ConfigChange>, risk_level: RiskLevel) -> Self {
+        Self::new(
+            EventType::ConfigReload { path, changes },
+            "config".to_string(),
+            std::process::id(),
+            risk_level,
+        )
+    }
+
+    /// Create a resource limit event for the tree rooted at `pid`
+    pub fn resource(
+        process: String,
+        pid: u32,
+        metric: ResourceMetric,
+        value: u64,
+        limit: u64,
+        risk_level: RiskLevel,
+    ) -> Self {
+        Self::new(
+            EventType::Resource {
+                metric,
+                value,
+                limit,
+            },
+            process,
+            pid,
+            risk_level,
+        )
+    }
+
+    /// Create an anomaly event for the agent whose tree is rooted at `pid`
+    pub fn anomaly(
+        agent: String,
+        pid: u32,
+        kind: AnomalyKind,
+        score: u32,
+        explanation: String,
+        risk_level: RiskLevel,
+    ) -> Self {
+        Self::new(
+            EventType::Anomaly {
+                kind,
+                score,
+                explanation,
+            },
+            agent,
+            pid,
+            risk_level,
+        )
+    }
+
+    /// Create a low-risk event summarizing `count` file events under `path`
+    pub fn file_summary(
+        path: PathBuf,
+        action: FileAction,
+        count: u64,
+        process: String,
+        pid: u32,
+    ) -> Self {
+        Self::new(
+            EventType::FileSummary {
+                path,
+                action,
+                count,
+            },
+            process,
+            pid,
+            RiskLevel::Low,
+        )
+    }
+
     /// Create a process exit event
-    pub fn process_exit(process: String, pid: u32, ppid: Option<u32>) -> Self {
+    pub fn process_exit(process: String, pid: u32, ppid: Option<u32>, exit: ExitStats) -> Self {
         Self::new(
             EventType::Process {
                 pid,
                 ppid,
                 action: ProcessAction::Exit,
+                exit: Some(exit),
+                backend: None,
             },
             process,
             pid,
             RiskLevel::Low,
         )
     }
+
+    /// Create an event for a process that left the monitored tree, now a
+    /// child of `ppid`
+    pub fn process_detach(process: String, pid: u32, ppid: Option<u32>) -> Self {
+        Self::new(
+            EventType::Process {
+                pid,
+                ppid,
+                action: ProcessAction::Detach,
+                exit: None,
+                backend: None,
+            },
+            process,
+            pid,
+            RiskLevel::High,
+        )
+    }
+
+    /// Create an event for a process that started listening on a local port
+    pub fn listen(process: String, pid: u32, address: String, port: u16, protocol: String) -> Self {
+        Self::new(
+            EventType::Listen {
+                address,
+                port,
+                protocol,
+            },
+            process,
+            pid,
+            RiskLevel::Critical,
+        )
+    }
+
+    /// Create an event for a shell whose stdio is connected to a remote host
+    pub fn reverse_shell(
+        shell: String,
+        pid: u32,
+        host: String,
+        port: u16,
+        protocol: String,
+    ) -> Self {
+        Self::new(
+            EventType::ReverseShell {
+                host,
+                port,
+                protocol,
+            },
+            shell,
+            pid,
+            RiskLevel::Critical,
+        )
+    }
+
+    /// Record the provider `catalogue` matches the destination of a network
+    /// or proxy event to. Other event types are returned unchanged.
+    pub fn with_provider(mut self, catalogue: &ProviderCatalogue) -> Self {
+        if let EventType::Network { host, provider, .. }
+        | EventType::NetworkClose { host, provider, .. }
+        | EventType::ProxyRequest { host, provider, .. } = &mut self.event_type
+        {
+            *provider = catalogue.classify(host).map(ProviderTag::from);
+        }
+        self
+    }
+
+    /// Record which tracker backend observed a process event. Other event
+    /// types are returned unchanged.
+    pub fn with_backend(mut self, tracker_backend: TrackerBackend) -> Self {
+        if let EventType::Process { backend, .. } = &mut self.event_type {
+            *backend = Some(tracker_backend);
+        }
+        self
+    }
 }
 
 #[cfg(test)]
@@ -199,6 +513,13 @@ mod tests {
         assert_eq!(RiskLevel::Critical.to_string(), "critical");
     }
 
+    #[test]
+    fn test_risk_level_from_str() {
+        assert_eq!("high".parse::<RiskLevel>(), Ok(RiskLevel::High));
+        assert_eq!(" Medium ".parse::<RiskLevel>(), Ok(RiskLevel::Medium));
+        assert!("severe".parse::<RiskLevel>().is_err());
+    }
+
     #[test]
     fn test_event_creation() {
         let event = Event::command(
@@ -268,4 +589,221 @@ mod tests {
             }
         ));
     }
+
+    #[test]
+    fn test_process_exit_stats_roundtrip() {
+        let exit = ExitStats {
+            exit_code: Some(1),
+            duration_ms: Some(1500),
+            cpu_time_ms: Some(320),
+            peak_rss_bytes: Some(64 * 1024 * 1024),
+            command_id: Some(uuid::Uuid::new_v4()),
+        };
+        let event = Event::process_exit("npm".to_string(), 4321, Some(1), exit.clone());
+        let json = serde_json::to_string(&event).unwrap();
+        assert!(json.contains("\"exit_code\":1"));
+
+        let parsed: Event = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.event_type, event.event_type);
+    }
+
+    #[test]
+    fn test_process_event_without_exit_stats() {
+        let event = Event::process_start("npm".to_string(), 4321, Some(1), RiskLevel::Low);
+        let json = serde_json::to_string(&event).unwrap();
+        // Without an exit field, like logs written before exit stats existed
+        assert!(!json.contains("\"exit\""));
+        let parsed: Event = serde_json::from_str(&json).unwrap();
+        assert!(matches!(
+            parsed.event_type,
+            EventType::Process { exit: None, .. }
+        ));
+    }
+
+    #[test]
+    fn test_process_detach_event() {
+        let event = Event::process_detach("node".to_string(), 4321, Some(1));
+        assert_eq!(event.risk_level, RiskLevel::High);
+        let json = serde_json::to_string(&event).unwrap();
+        assert!(json.contains("\"action\":\"detach\""));
+        let parsed: Event = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.event_type, event.event_type);
+    }
+
+    #[test]
+    fn test_with_provider_records_classification() {
+        let event = Event::new(
+            EventType::ProxyRequest {
+                method: "CONNECT".to_string(),
+                host: "api.openai.com".to_string(),
+                port: 443,
+                blocked: false,
+                provider: None,
+            },
+            "codex".to_string(),
+            4321,
+            RiskLevel::Medium,
+        )
+        .with_provider(ProviderCatalogue::builtin());
+        let EventType::ProxyRequest { provider, .. } = &event.event_type else {
+            unreachable!()
+        };
+        assert_eq!(
+            provider,
+            &Some(ProviderTag {
+                name: "OpenAI".to_string(),
+                category: ProviderCategory::Llm,
+            })
+        );
+
+        let json = serde_json::to_string(&event).unwrap();
+        assert!(json.contains("\"provider\":{\"name\":\"OpenAI\",\"category\":\"llm\"}"));
+        let parsed: Event = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.event_type, event.event_type);
+
+        // Events recorded before providers were stored still parse
+        let legacy = json.replace(
+            ",\"provider\":{\"name\":\"OpenAI\",\"category\":\"llm\"}",
+            "",
+        );
+        let parsed: Event = serde_json::from_str(&legacy).unwrap();
+        assert!(matches!(
+            parsed.event_type,
+            EventType::ProxyRequest { provider: None, .. }
+        ));
+    }
+
+    #[test]
+    fn test_network_close_roundtrip() {
+        let event = Event::new(
+            EventType::NetworkClose {
+                host: "203.0.113.9".to_string(),
+                port: 443,
+                protocol: "tcp".to_string(),
+                duration_ms: 12_000,
+                bytes_sent: Some(4096),
+                bytes_received: None,
+                provider: None,
+            },
+            "curl".to_string(),
+            4321,
+            RiskLevel::Medium,
+        );
+        let json = serde_json::to_string(&event).unwrap();
+        assert!(json.contains("\"type\":\"network_close\""));
+        assert!(json.contains("\"bytes_sent\":4096"));
+        assert!(!json.contains("bytes_received"));
+        let parsed: Event = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.event_type, event.event_type);
+    }
+
+    #[test]
+    fn test_listen_and_reverse_shell_events() {
+        let listen = Event::listen(
+            "python3".to_string(),
+            4321,
+            "0.0.0.0".to_string(),
+            4444,
+            "tcp".to_string(),
+        );
+        assert_eq!(listen.risk_level, RiskLevel::Critical);
+        assert!(listen.alert);
+        let json = serde_json::to_string(&listen).unwrap();
+        assert!(json.contains("\"type\":\"listen\""));
+        let parsed: Event = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.event_type, listen.event_type);
+
+        let shell = Event::reverse_shell(
+            "bash".to_string(),
+            4322,
+            "10.0.0.5".to_string(),
+            9001,
+            "tcp".to_string(),
+        );
+        assert_eq!(shell.risk_level, RiskLevel::Critical);
+        assert_eq!(shell.event_type.tag(), "reverse_shell");
+        let json = serde_json::to_string(&shell).unwrap();
+        let parsed: Event = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.event_type, shell.event_type);
+        assert_eq!(parsed.process, "bash");
+    }
+
+    #[test]
+    fn test_process_event_backend_roundtrip() {
+        let event = Event::process_start("curl".to_string(), 4321, Some(1), RiskLevel::Low)
+            .with_backend(TrackerBackend::ProcConnector);
+        let json = serde_json::to_string(&event).unwrap();
+        assert!(json.contains("\"backend\":\"proc_connector\""));
+        let parsed: Event = serde_json::from_str(&json).unwrap();
+        assert!(matches!(
+            parsed.event_type,
+            EventType::Process {
+                backend: Some(TrackerBackend::ProcConnector),
+                ..
+            }
+        ));
+
+        // Other event types ignore the backend
+        let session =
+            Event::session_start("claude".to_string(), 1).with_backend(TrackerBackend::Ptrace);
+        assert!(!serde_json::to_string(&session).unwrap().contains("backend"));
+    }
+
+    #[test]
+    fn test_resource_event_roundtrip() {
+        let event = Event::resource(
+            "claude".to_string(),
+            4321,
+            ResourceMetric::SpawnRate,
+            250,
+            200,
+            RiskLevel::Critical,
+        );
+        assert!(event.alert);
+        let json = serde_json::to_string(&event).unwrap();
+        assert!(json.contains("\"type\":\"resource\""));
+        assert!(json.contains("\"metric\":\"spawn_rate\""));
+
+        let parsed: Event = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.event_type, event.event_type);
+    }
+
+    #[test]
+    fn test_anomaly_event_roundtrip() {
+        let event = Event::anomaly(
+            "aider".to_string(),
+            4321,
+            AnomalyKind::NewHosts,
+            7,
+            "contacted 40 hosts not seen before".to_string(),
+            RiskLevel::High,
+        );
+        let json = serde_json::to_string(&event).unwrap();
+        assert!(json.contains("\"type\":\"anomaly\""));
+        assert!(json.contains("\"kind\":\"new_hosts\""));
+
+        let parsed: Event = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.event_type, event.event_type);
+        assert!(parsed.alert);
+    }
+
+    #[test]
+    fn test_file_summary_event_roundtrip() {
+        let event = Event::file_summary(
+            PathBuf::from("/work/node_modules"),
+            FileAction::Create,
+            1204,
+            "fswatch".to_string(),
+            1,
+        );
+        assert_eq!(event.risk_level, RiskLevel::Low);
+        assert!(!event.alert);
+
+        let json = serde_json::to_string(&event).unwrap();
+        assert!(json.contains("\"type\":\"file_summary\""));
+        assert_eq!(event.event_type.tag(), "file_summary");
+        assert!(json.contains("\"count\":1204"));
+        let parsed: Event = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.event_type, event.event_type);
+    }
 }
@@ -0,0 +1,776 @@
+//! Exec capture module
+//!
+//! Reports the real argv of every program executed in the wrapped process
+//! tree, including those started by agents through `child_process.spawn`
+//! and similar APIs that never show up at a shell prompt.
+//!
+//! The tree is polled at a configurable interval. A new PID, or a known PID
+//! whose command line changed (`execve` keeps the PID), is reported as an
+//! exec. On Linux processes are read from `/proc`; on macOS from libproc and
+//! the `KERN_PROCARGS2` sysctl.
+
+use crate::bus::{BusEvent, EventBus, SubscribeOptions};
+use crate::error::CoreError;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+#[cfg(target_os = "macos")]
+use libproc::bsd_info::BSDInfo;
+#[cfg(target_os = "macos")]
+use libproc::proc_pid::{pidinfo, pidpath};
+#[cfg(target_os = "macos")]
+use libproc::processes::{pids_by_type, ProcFilter};
+
+/// Event emitted by the exec monitor
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExecEvent {
+    /// A program was executed
+    Exec {
+        pid: u32,
+        ppid: u32,
+        /// Exact argument vector, `argv[0]` first
+        argv: Vec<String>,
+        /// Path of the executable, if readable
+        path: Option<String>,
+    },
+    /// A process that executed a program exited
+    Exit {
+        pid: u32,
+        /// Exit code, if the process was seen after exiting but before its
+        /// parent reaped it
+        exit_code: Option<i32>,
+    },
+}
+
+impl BusEvent for ExecEvent {
+    fn kind(&self) -> &'static str {
+        match self {
+            ExecEvent::Exec { .. } => "exec",
+            ExecEvent::Exit { .. } => "exec_exit",
+        }
+    }
+}
+
+/// Configuration for the exec monitor
+#[derive(Debug, Clone)]
+pub struct ExecMonConfig {
+    /// Root process ID of the tree to capture
+    pub root_pid: u32,
+    /// Polling interval
+    pub poll_interval: Duration,
+    /// Maximum tree depth (None for unlimited)
+    pub max_depth: Option<usize>,
+    /// Also report execs of the root process itself
+    pub include_root: bool,
+}
+
+impl Default for ExecMonConfig {
+    fn default() -> Self {
+        Self {
+            root_pid: 0,
+            poll_interval: Duration::from_millis(50),
+            max_depth: Some(10),
+            include_root: true,
+        }
+    }
+}
+
+impl ExecMonConfig {
+    /// Create a new exec monitor config for a root process
+    pub fn new(root_pid: u32) -> Self {
+        Self {
+            root_pid,
+            ..Default::default()
+        }
+    }
+
+    /// Set the polling interval
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Set the maximum tree depth
+    pub fn max_depth(mut self, depth: Option<usize>) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Set whether execs of the root process are reported
+    pub fn include_root(mut self, include: bool) -> Self {
+        self.include_root = include;
+        self
+    }
+}
+
+/// A process as seen in one scan. Shared with the process tracker and the
+/// resource monitor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ProcEntry {
+    pub(crate) pid: u32,
+    pub(crate) ppid: u32,
+    /// Process group ID
+    pub(crate) pgid: u32,
+    /// Session ID
+    pub(crate) sid: u32,
+    /// Start time in platform ticks; a different value means the PID was reused
+    pub(crate) start_time: u64,
+    /// `Some` once the process has exited and is waiting to be reaped
+    pub(crate) exited: Option<Option<i32>>,
+}
+
+/// What the monitor remembers about a process between scans
+#[derive(Debug, Clone)]
+struct Seen {
+    start_time: u64,
+    argv: Vec<String>,
+    exit_code: Option<i32>,
+}
+
+/// Captures program executions in a process tree
+pub struct ExecMonitor {
+    config: ExecMonConfig,
+    /// Processes seen in the last scan (pid -> state)
+    seen: Arc<Mutex<HashMap<u32, Seen>>>,
+    /// Subscribers to exec events
+    events: EventBus<ExecEvent>,
+    /// Stop flag
+    stop_flag: Arc<AtomicBool>,
+    /// Worker thread handle
+    thread_handle: Option<JoinHandle<()>>,
+}
+
+impl ExecMonitor {
+    /// Create a new exec monitor
+    pub fn new(config: ExecMonConfig) -> Self {
+        Self {
+            config,
+            seen: Arc::new(Mutex::new(HashMap::new())),
+            events: EventBus::new(),
+            stop_flag: Arc::new(AtomicBool::new(false)),
+            thread_handle: None,
+        }
+    }
+
+    /// Whether exec capture works on this platform
+    pub fn is_supported() -> bool {
+        cfg!(any(target_os = "linux", target_os = "macos"))
+    }
+
+    /// Subscribe to exec events
+    pub fn subscribe(&self) -> Receiver<ExecEvent> {
+        self.events.subscribe()
+    }
+
+    /// Subscribe with a custom buffer, lag policy and filter
+    pub fn subscribe_with(&self, options: SubscribeOptions) -> Receiver<ExecEvent> {
+        self.events.subscribe_with(options)
+    }
+
+    /// Start the capture thread
+    pub fn start(&mut self) -> std::result::Result<(), CoreError> {
+        if !Self::is_supported() {
+            return Err(CoreError::Wrapper(
+                "exec capture is not supported on this platform".to_string(),
+            ));
+        }
+        if self.thread_handle.is_some() {
+            return Ok(());
+        }
+        self.stop_flag.store(false, Ordering::Relaxed);
+
+        let config = self.config.clone();
+        let seen = Arc::clone(&self.seen);
+        let stop_flag = Arc::clone(&self.stop_flag);
+        let events = self.events.clone();
+
+        let handle = thread::spawn(move || {
+            while !stop_flag.load(Ordering::Relaxed) {
+                Self::scan(&config, &seen, &events);
+                thread::sleep(config.poll_interval);
+            }
+            // Catch processes that ran since the last poll
+            Self::scan(&config, &seen, &events);
+        });
+
+        self.thread_handle = Some(handle);
+        Ok(())
+    }
+
+    /// Stop the capture thread. Subscribers see a disconnect once the final
+    /// scan has been sent.
+    pub fn stop(&mut self) {
+        self.stop_flag.store(true, Ordering::Relaxed);
+
+        if let Some(handle) = self.thread_handle.take() {
+            let _ = handle.join();
+        }
+        self.events.close();
+    }
+
+    /// Signal the monitor to stop without waiting for the thread to finish
+    pub fn signal_stop(&self) {
+        self.stop_flag.store(true, Ordering::Relaxed);
+    }
+
+    /// Check if the monitor is running
+    pub fn is_running(&self) -> bool {
+        self.thread_handle.is_some() && !self.stop_flag.load(Ordering::Relaxed)
+    }
+
+    /// Scan the tree once and emit events for what changed
+    fn scan(
+        config: &ExecMonConfig,
+        seen: &Arc<Mutex<HashMap<u32, Seen>>>,
+        events: &EventBus<ExecEvent>,
+    ) {
+        let processes = list_processes();
+        let tree = descendants(&processes, config);
+        let Ok(mut seen) = seen.lock() else {
+            return;
+        };
+        for event in diff_scan(&tree, &mut seen, read_argv, read_path) {
+            events.publish(event);
+        }
+    }
+}
+
+/// Processes in the tree under `config.root_pid`, plus the root itself if
+/// `include_root` is set
+fn descendants(processes: &[ProcEntry], config: &ExecMonConfig) -> Vec<ProcEntry> {
+    process_tree(
+        processes,
+        config.root_pid,
+        config.max_depth,
+        config.include_root,
+    )
+}
+
+/// Processes in the tree under `root_pid`, plus the root itself if
+/// `include_root` is set. Shared with the resource monitor.
+pub(crate) fn process_tree(
+    processes: &[ProcEntry],
+    root_pid: u32,
+    max_depth: Option<usize>,
+    include_root: bool,
+) -> Vec<ProcEntry> {
+    let mut children: HashMap<u32, Vec<&ProcEntry>> = HashMap::new();
+    for entry in processes {
+        children.entry(entry.ppid).or_default().push(entry);
+    }
+
+    let mut tree = Vec::new();
+    if include_root {
+        tree.extend(processes.iter().filter(|p| p.pid == root_pid).cloned());
+    }
+    let mut to_visit = vec![(root_pid, 0usize)];
+    while let Some((pid, depth)) = to_visit.pop() {
+        if max_depth.is_some_and(|max| depth > max) {
+            continue;
+        }
+        for child in children.get(&pid).into_iter().flatten() {
+            // pid 0 is the parent of the first processes on some systems
+            if child.pid == pid {
+                continue;
+            }
+            tree.push((*child).clone());
+            to_visit.push((child.pid, depth + 1));
+        }
+    }
+    tree
+}
+
+/// Compare one scan against the previous state and return exec and exit
+/// events, updating `seen`
+fn diff_scan(
+    tree: &[ProcEntry],
+    seen: &mut HashMap<u32, Seen>,
+    read_argv: impl Fn(u32) -> Option<Vec<String>>,
+    read_path: impl Fn(u32) -> Option<String>,
+) -> Vec<ExecEvent> {
+    let mut events = Vec::new();
+
+    for entry in tree {
+        let known = seen
+            .get(&entry.pid)
+            .is_some_and(|s| s.start_time == entry.start_time);
+
+        if let Some(exit_code) = entry.exited {
+            // A zombie has no command line left; only remember its status
+            if let Some(s) = seen.get_mut(&entry.pid) {
+                s.exit_code = exit_code;
+            }
+            continue;
+        }
+
+        // The PID was reused by an unrelated process
+        if !known {
+            if let Some(old) = seen.remove(&entry.pid) {
+                events.push(ExecEvent::Exit {
+                    pid: entry.pid,
+                    exit_code: old.exit_code,
+                });
+            }
+        }
+
+        // Kernel threads and processes that exited mid-scan have no argv
+        let Some(argv) = read_argv(entry.pid).filter(|argv| !argv.is_empty()) else {
+            continue;
+        };
+        if known && seen.get(&entry.pid).is_some_and(|s| s.argv == argv) {
+            continue;
+        }
+
+        events.push(ExecEvent::Exec {
+            pid: entry.pid,
+            ppid: entry.ppid,
+            argv: argv.clone(),
+            path: read_path(entry.pid),
+        });
+        seen.insert(
+            entry.pid,
+            Seen {
+                start_time: entry.start_time,
+                argv,
+                exit_code: None,
+            },
+        );
+    }
+
+    let gone: Vec<u32> = seen
+        .keys()
+        .filter(|pid| !tree.iter().any(|e| e.pid == **pid))
+        .copied()
+        .collect();
+    for pid in gone {
+        if let Some(old) = seen.remove(&pid) {
+            events.push(ExecEvent::Exit {
+                pid,
+                exit_code: old.exit_code,
+            });
+        }
+    }
+
+    events
+}
+
+/// Parse the fields of `/proc/<pid>/stat` the monitor needs
+#[cfg(any(target_os = "linux", test))]
+pub(crate) fn parse_proc_stat(pid: u32, stat: &str) -> Option<ProcEntry> {
+    // The command name may contain spaces and parentheses; fields resume
+    // after the last ')'
+    let rest = &stat[stat.rfind(')')? + 1..];
+    let fields: Vec<&str> = rest.split_whitespace().collect();
+    let state = fields.first()?;
+    let ppid = fields.get(1)?.parse().ok()?;
+    let pgid = fields.get(2)?.parse().ok()?;
+    let sid = fields.get(3)?.parse().ok()?;
+    let start_time = fields.get(19)?.parse().ok()?;
+    let exited = (*state == "Z" || *state == "X").then(|| {
+        // Field 52 holds the wait status (Linux 3.5+)
+        fields
+            .get(49)
+            .and_then(|s| s.parse::<i32>().ok())
+            .map(decode_wait_status)
+    });
+    Some(ProcEntry {
+        pid,
+        ppid,
+        pgid,
+        sid,
+        start_time,
+        exited,
+    })
+}
+
+/// Turn a wait status into an exit code, using the shell's 128 + signal
+/// convention for processes killed by a signal
+pub(crate) fn decode_wait_status(status: i32) -> i32 {
+    if status & 0x7f == 0 {
+        (status >> 8) & 0xff
+    } else {
+        128 + (status & 0x7f)
+    }
+}
+
+/// Split a NUL-separated argument block into its arguments
+#[cfg(any(target_os = "linux", test))]
+fn parse_cmdline(raw: &[u8]) -> Vec<String> {
+    let raw = raw.strip_suffix(&[0]).unwrap_or(raw);
+    if raw.is_empty() {
+        return Vec::new();
+    }
+    raw.split(|&b| b == 0)
+        .map(|arg| String::from_utf8_lossy(arg).into_owned())
+        .collect()
+}
+
+/// List every process on the system
+#[cfg(target_os = "linux")]
+pub(crate) fn list_processes() -> Vec<ProcEntry> {
+    let Ok(dir) = std::fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    dir.filter_map(Result::ok)
+        .filter_map(|entry| entry.file_name().to_str()?.parse::<u32>().ok())
+        .filter_map(|pid| {
+            let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+            parse_proc_stat(pid, &stat)
+        })
+        .collect()
+}
+
+#[cfg(target_os = "linux")]
+fn read_argv(pid: u32) -> Option<Vec<String>> {
+    let raw = std::fs::read(format!("/proc/{pid}/cmdline")).ok()?;
+    Some(parse_cmdline(&raw))
+}
+
+#[cfg(target_os = "linux")]
+fn read_path(pid: u32) -> Option<String> {
+    std::fs::read_link(format!("/proc/{pid}/exe"))
+        .ok()
+        .map(|p| p.to_string_lossy().into_owned())
+}
+
+/// Parse a `KERN_PROCARGS2` buffer: argc, the executable path, padding,
+/// then argc NUL-terminated arguments
+#[cfg(any(target_os = "macos", test))]
+fn parse_procargs2(buf: &[u8]) -> Option<Vec<String>> {
+    let argc = i32::from_ne_bytes(buf.get(..4)?.try_into().ok()?);
+    let mut rest = &buf[4..];
+    // Skip the executable path and the NUL padding after it
+    let path_end = rest.iter().position(|&b| b == 0)?;
+    rest = &rest[path_end..];
+    let args_start = rest.iter().position(|&b| b != 0)?;
+    rest = &rest[args_start..];
+
+    Some(
+        rest.split(|&b| b == 0)
+            .take(usize::try_from(argc).ok()?)
+            .map(|arg| String::from_utf8_lossy(arg).into_owned())
+            .collect(),
+    )
+}
+
+/// List every process on the system
+#[cfg(target_os = "macos")]
+pub(crate) fn list_processes() -> Vec<ProcEntry> {
+    /// `p_stat` value of a zombie process
+    const SZOMB: u32 = 5;
+
+    let Ok(pids) = pids_by_type(ProcFilter::All) else {
+        return Vec::new();
+    };
+    pids.into_iter()
+        .filter(|&pid| pid != 0)
+        .filter_map(|pid| {
+            let info = pidinfo::<BSDInfo>(pid as i32, 0).ok()?;
+            Some(ProcEntry {
+                pid,
+                ppid: info.pbi_ppid,
+                pgid: info.pbi_pgid,
+                // SAFETY: getsid has no memory preconditions
+                sid: unsafe { libc::getsid(pid as libc::pid_t) }.max(0) as u32,
+                start_time: info.pbi_start_tvsec * 1_000_000 + info.pbi_start_tvusec,
+                exited: (info.pbi_status == SZOMB)
+                    .then(|| Some(decode_wait_status(info.pbi_xstatus as i32))),
+            })
+        })
+        .collect()
+}
+
+#[cfg(target_os = "macos")]
+fn read_argv(pid: u32) -> Option<Vec<String>> {
+    let mut mib = [libc::CTL_KERN, libc::KERN_PROCARGS2, pid as libc::c_int];
+    let mut size: libc::size_t = 0;
+    // SAFETY: a null buffer asks the kernel for the required size only
+    let rc = unsafe {
+        libc::sysctl(
+            mib.as_mut_ptr(),
+            mib.len() as libc::c_uint,
+            std::ptr::null_mut(),
+            &mut size,
+            std::ptr::null_mut(),
+            0,
+        )
+    };
+    if rc != 0 || size == 0 {
+        return None;
+    }
+    let mut buf = vec![0u8; size];
+    // SAFETY: `buf` is valid for `size` bytes and the kernel writes at most that
+    let rc = unsafe {
+        libc::sysctl(
+            mib.as_mut_ptr(),
+            mib.len() as libc::c_uint,
+            buf.as_mut_ptr().cast(),
+            &mut size,
+            std::ptr::null_mut(),
+            0,
+        )
+    };
+    if rc != 0 {
+        return None;
+    }
+    buf.truncate(size);
+    parse_procargs2(&buf)
+}
+
+#[cfg(target_os = "macos")]
+fn read_path(pid: u32) -> Option<String> {
+    pidpath(pid as i32).ok()
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub(crate) fn list_processes() -> Vec<ProcEntry> {
+    Vec::new()
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn read_argv(_pid: u32) -> Option<Vec<String>> {
+    None
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn read_path(_pid: u32) -> Option<String> {
+    None
+}
+
+impl crate::types::MonitoringSubsystem for ExecMonitor {
+    fn start(&mut self) -> std::result::Result<(), CoreError> {
+        ExecMonitor::start(self)
+    }
+
+    fn stop(&mut self) {
+        ExecMonitor::stop(self)
+    }
+
+    fn signal_stop(&self) {
+        ExecMonitor::signal_stop(self)
+    }
+
+    fn is_running(&self) -> bool {
+        ExecMonitor::is_running(self)
+    }
+}
+
+impl Drop for ExecMonitor {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(pid: u32, ppid: u32, start_time: u64) -> ProcEntry {
+        ProcEntry {
+            pid,
+            ppid,
+            pgid: 0,
+            sid: 0,
+            start_time,
+            exited: None,
+        }
+    }
+
+    fn argv(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_exec_mon_config_builder() {
+        let config = ExecMonConfig::new(42)
+            .poll_interval(Duration::from_millis(10))
+            .max_depth(Some(2))
+            .include_root(false);
+        assert_eq!(config.root_pid, 42);
+        assert_eq!(config.poll_interval, Duration::from_millis(10));
+        assert_eq!(config.max_depth, Some(2));
+        assert!(!config.include_root);
+    }
+
+    #[test]
+    fn test_parse_proc_stat() {
+        let mut fields = vec!["0"; 50];
+        fields[0] = "S";
+        fields[1] = "100";
+        fields[19] = "123456";
+        let stat = format!("4242 (my (odd) cmd) {}", fields.join(" "));
+        assert_eq!(parse_proc_stat(4242, &stat), Some(entry(4242, 100, 123456)));
+
+        fields[0] = "Z";
+        fields[49] = "512";
+        let stat = format!("4242 (sh) {}", fields.join(" "));
+        assert_eq!(parse_proc_stat(4242, &stat).unwrap().exited, Some(Some(2)));
+
+        fields[49] = "9";
+        let stat = format!("4242 (sh) {}", fields.join(" "));
+        assert_eq!(
+            parse_proc_stat(4242, &stat).unwrap().exited,
+            Some(Some(137))
+        );
+        assert_eq!(parse_proc_stat(1, "garbage"), None);
+    }
+
+    #[test]
+    fn test_parse_cmdline() {
+        assert_eq!(
+            parse_cmdline(b"git\0commit\0-m\0two words\0"),
+            argv(&["git", "commit", "-m", "two words"])
+        );
+        assert_eq!(parse_cmdline(b"sh\0\0"), argv(&["sh", ""]));
+        assert!(parse_cmdline(b"").is_empty());
+    }
+
+    #[test]
+    fn test_parse_procargs2() {
+        let mut buf = 3i32.to_ne_bytes().to_vec();
+        buf.extend_from_slice(b"/usr/bin/git\0\0\0\0git\0commit\0-m\0PATH=/usr/bin\0");
+        assert_eq!(parse_procargs2(&buf), Some(argv(&["git", "commit", "-m"])));
+        assert_eq!(parse_procargs2(&[1, 0]), None);
+    }
+
+    #[test]
+    fn test_descendants() {
+        let processes = vec![
+            entry(1, 0, 0),
+            entry(10, 1, 0),
+            entry(11, 10, 0),
+            entry(12, 11, 0),
+            entry(20, 1, 0),
+        ];
+        let pids = |config: &ExecMonConfig| {
+            let mut pids: Vec<u32> = descendants(&processes, config)
+                .iter()
+                .map(|e| e.pid)
+                .collect();
+            pids.sort();
+            pids
+        };
+        assert_eq!(pids(&ExecMonConfig::new(10)), vec![10, 11, 12]);
+        assert_eq!(
+            pids(&ExecMonConfig::new(10).include_root(false)),
+            vec![11, 12]
+        );
+        assert_eq!(
+            pids(&ExecMonConfig::new(10).max_depth(Some(0))),
+            vec![10, 11]
+        );
+    }
+
+    #[test]
+    fn test_diff_scan_exec_reexec_and_exit() {
+        let mut argvs: HashMap<u32, Vec<String>> = HashMap::new();
+        argvs.insert(10, argv(&["bash", "-c", "exec git status"]));
+        let mut seen = HashMap::new();
+        let tree = vec![entry(10, 1, 5)];
+
+        let events = diff_scan(&tree, &mut seen, |pid| argvs.get(&pid).cloned(), |_| None);
+        assert_eq!(
+            events,
+            vec![ExecEvent::Exec {
+                pid: 10,
+                ppid: 1,
+                argv: argv(&["bash", "-c", "exec git status"]),
+                path: None,
+            }]
+        );
+
+        // Unchanged on the next scan
+        let events = diff_scan(&tree, &mut seen, |pid| argvs.get(&pid).cloned(), |_| None);
+        assert!(events.is_empty());
+
+        // Same PID and start time, new command line: an execve
+        argvs.insert(10, argv(&["git", "status"]));
+        let events = diff_scan(&tree, &mut seen, |pid| argvs.get(&pid).cloned(), |_| None);
+        assert!(matches!(&events[..], [ExecEvent::Exec { argv, .. }] if argv[0] == "git"));
+
+        // Seen as a zombie, then reaped
+        let zombie = vec![ProcEntry {
+            exited: Some(Some(1)),
+            ..entry(10, 1, 5)
+        }];
+        assert!(diff_scan(&zombie, &mut seen, |_| None, |_| None).is_empty());
+        let events = diff_scan(&[], &mut seen, |_| None, |_| None);
+        assert_eq!(
+            events,
+            vec![ExecEvent::Exit {
+                pid: 10,
+                exit_code: Some(1)
+            }]
+        );
+    }
+
+    #[test]
+    fn test_diff_scan_pid_reuse() {
+        let mut seen = HashMap::new();
+        let first = |_| Some(argv(&["sleep", "1"]));
+        diff_scan(&[entry(10, 1, 5)], &mut seen, first, |_| None);
+
+        let second = |_| Some(argv(&["sleep", "1"]));
+        let events = diff_scan(&[entry(10, 1, 9)], &mut seen, second, |_| None);
+        assert_eq!(events.len(), 2);
+        assert_eq!(
+            events[0],
+            ExecEvent::Exit {
+                pid: 10,
+                exit_code: None
+            }
+        );
+        assert!(matches!(events[1], ExecEvent::Exec { pid: 10, .. }));
+    }
+
+    #[test]
+    fn test_exec_monitor_subscribe() {
+        let mut monitor = ExecMonitor::new(ExecMonConfig::new(std::process::id()));
+        let first = monitor.subscribe();
+        let second = monitor.subscribe();
+        assert_eq!(monitor.events.subscriber_count(), 2);
+
+        let exit = ExecEvent::Exit {
+            pid: 42,
+            exit_code: Some(0),
+        };
+        monitor.events.publish(exit.clone());
+        assert_eq!(first.try_recv().unwrap(), exit);
+        assert_eq!(second.try_recv().unwrap(), exit);
+
+        monitor.stop();
+        assert!(first.recv().is_err());
+        assert!(second.recv().is_err());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_exec_monitor_captures_child_argv() {
+        let mut child = std::process::Command::new("sh")
+            .args(["-c", "sleep 0.3; true"])
+            .spawn()
+            .unwrap();
+        let mut monitor = ExecMonitor::new(
+            ExecMonConfig::new(child.id()).poll_interval(Duration::from_millis(10)),
+        );
+        let rx = monitor.subscribe();
+        monitor.start().unwrap();
+        child.wait().unwrap();
+        thread::sleep(Duration::from_millis(50));
+        monitor.stop();
+
+        let execs: Vec<Vec<String>> = rx
+            .try_iter()
+            .filter_map(|e| match e {
+                ExecEvent::Exec { argv, .. } => Some(argv),
+                ExecEvent::Exit { .. } => None,
+            })
+            .collect();
+        assert!(execs.contains(&argv(&["sh", "-c", "sleep 0.3; true"])));
+        assert!(execs.contains(&argv(&["sleep", "0.3"])));
+    }
+}
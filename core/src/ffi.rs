@@ -3,17 +3,32 @@
 //! Provides FFI-safe types, conversions, and exported functions for the Swift app layer.
 
 use crate::agent_detector::AgentDetector;
-use crate::config::{Config, NotificationConfig};
+use crate::attach::{AttachConfig, Attachment};
+use crate::bus::{EventBus, EventFilter, SubscribeOptions};
+use crate::config::{Compression, Config, NotificationConfig};
+use crate::detector::ProviderCategory;
 use crate::error::CoreError;
-use crate::event::{Event, EventType, FileAction, ProcessAction, RiskLevel, SessionAction};
-use crate::fswatch::{FileSystemWatcher, FsWatchConfig};
-use crate::netmon::{NetMonConfig, NetworkMonitor};
-use crate::process_tracker::{ProcessTracker, TrackerConfig, TrackerEvent};
+use crate::event::{
+    AnomalyKind, Event, EventType, FileAction, ProcessAction, ProviderTag, ResourceMetric,
+    RiskLevel, SessionAction,
+};
+use crate::reload::{ConfigWatchConfig, ConfigWatcher, LiveRules};
+use crate::retention::{compress_session, RetentionManager, RetentionPolicy};
 use crate::risk::RiskScorer;
-use crate::storage::{EventStorage, SessionLogger};
+use crate::sanitize::sanitize_event;
+use crate::sqlite_storage::{
+    AgentTrend, EventQuery, GroupBy, GroupCount, SessionMatch, SqliteStorage,
+};
+use crate::storage::{
+    open_session_log, session_segments, EventStorage, SegmentName, SessionLogger,
+};
+use crate::tail::SessionTail;
+use crate::traffic::{TrafficStats, TrafficSummary};
+use std::collections::HashMap;
 use std::io::BufRead;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, LazyLock, Mutex};
 use std::thread::{self, JoinHandle};
 
 // ─── FFI Enum Types ───────────────────────────────────────────────────────────
@@ -40,6 +55,7 @@ pub enum FfiProcessAction {
     Start,
     Exit,
     Fork,
+    Detach,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
@@ -48,6 +64,25 @@ pub enum FfiSessionAction {
     End,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum FfiResourceMetric {
+    Processes,
+    SpawnRate,
+    Cpu,
+    Memory,
+    MemoryGrowth,
+    Threads,
+    OpenFiles,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum FfiAnomalyKind {
+    NewHosts,
+    NewCommands,
+    FileRate,
+    SessionLength,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, uniffi::Enum)]
 pub enum FfiEventType {
     Command {
@@ -63,6 +98,16 @@ pub enum FfiEventType {
         host: String,
         port: u16,
         protocol: String,
+        provider: Option<FfiProviderTag>,
+    },
+    NetworkClose {
+        host: String,
+        port: u16,
+        protocol: String,
+        duration_ms: u64,
+        bytes_sent: Option<u64>,
+        bytes_received: Option<u64>,
+        provider: Option<FfiProviderTag>,
     },
     Process {
         pid: u32,
@@ -72,10 +117,61 @@ pub enum FfiEventType {
     Session {
         action: FfiSessionAction,
     },
+    ConfigReload {
+        path: String,
+        changes: Vec<FfiConfigChange>,
+    },
+    Resource {
+        metric: FfiResourceMetric,
+        value: u64,
+        limit: u64,
+    },
+    Anomaly {
+        kind: FfiAnomalyKind,
+        score: u32,
+        explanation: String,
+    },
+    FileSummary {
+        path: String,
+        action: FfiFileAction,
+        count: u64,
+    },
+    Listen {
+        address: String,
+        port: u16,
+        protocol: String,
+    },
+    ReverseShell {
+        host: String,
+        port: u16,
+        protocol: String,
+    },
+    ProxyRequest {
+        method: String,
+        host: String,
+        port: u16,
+        blocked: bool,
+        provider: Option<FfiProviderTag>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum FfiGroupBy {
+    Agent,
+    Host,
+    Command,
+    Path,
 }
 
 // ─── FFI Record Types ─────────────────────────────────────────────────────────
 
+#[derive(Debug, Clone, PartialEq, Eq, uniffi::Record)]
+pub struct FfiConfigChange {
+    pub key: String,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+}
+
 #[derive(Debug, Clone, uniffi::Record)]
 pub struct FfiEvent {
     pub id: String,
@@ -88,6 +184,29 @@ pub struct FfiEvent {
     pub alert: bool,
 }
 
+/// Which live events an engine subscription receives
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiSubscribeOptions {
+    /// Event type tags such as "network" or "file_access"; all types if empty
+    pub event_types: Vec<String>,
+    pub min_risk_level: Option<FfiRiskLevel>,
+    /// Events buffered for a slow listener before newer ones are dropped
+    pub capacity: u32,
+}
+
+impl From<FfiSubscribeOptions> for SubscribeOptions {
+    fn from(options: FfiSubscribeOptions) -> Self {
+        let mut filter = EventFilter::default().kinds(options.event_types);
+        if let Some(level) = options.min_risk_level {
+            filter = filter.min_risk(level.into());
+        }
+        // Listeners run app code; a slow one must not hold up monitoring
+        SubscribeOptions::dropping()
+            .capacity(options.capacity as usize)
+            .filter(filter)
+    }
+}
+
 #[derive(Debug, Clone, uniffi::Record)]
 pub struct FfiGeneralConfig {
     pub verbose: bool,
@@ -108,7 +227,13 @@ pub struct FfiMonitoringConfig {
     pub track_children: bool,
     pub tracking_poll_ms: u64,
     pub fs_debounce_ms: u64,
+    pub fs_burst_threshold: u32,
+    pub fs_ignore: Vec<String>,
+    pub fs_snapshot: bool,
     pub net_poll_ms: u64,
+    pub net_upload_alert_mb: u64,
+    pub net_proxy: bool,
+    pub net_proxy_block: bool,
     pub watch_paths: Vec<String>,
     pub sensitive_patterns: Vec<String>,
     pub network_whitelist: Vec<String>,
@@ -144,6 +269,110 @@ pub struct FfiActivitySummary {
     pub high_count: u32,
     pub medium_count: u32,
     pub low_count: u32,
+    /// Network traffic by provider category; categories without traffic
+    /// are left out
+    pub traffic: Vec<FfiCategoryTraffic>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum FfiProviderCategory {
+    Llm,
+    PackageRegistry,
+    Vcs,
+    Other,
+}
+
+impl From<ProviderCategory> for FfiProviderCategory {
+    fn from(category: ProviderCategory) -> Self {
+        match category {
+            ProviderCategory::Llm => FfiProviderCategory::Llm,
+            ProviderCategory::PackageRegistry => FfiProviderCategory::PackageRegistry,
+            ProviderCategory::Vcs => FfiProviderCategory::Vcs,
+            ProviderCategory::Other => FfiProviderCategory::Other,
+        }
+    }
+}
+
+impl From<FfiProviderCategory> for ProviderCategory {
+    fn from(category: FfiProviderCategory) -> Self {
+        match category {
+            FfiProviderCategory::Llm => ProviderCategory::Llm,
+            FfiProviderCategory::PackageRegistry => ProviderCategory::PackageRegistry,
+            FfiProviderCategory::Vcs => ProviderCategory::Vcs,
+            FfiProviderCategory::Other => ProviderCategory::Other,
+        }
+    }
+}
+
+/// Provider a network event's destination matched when it was recorded
+#[derive(Debug, Clone, PartialEq, Eq, uniffi::Record)]
+pub struct FfiProviderTag {
+    pub name: String,
+    pub category: FfiProviderCategory,
+}
+
+impl From<ProviderTag> for FfiProviderTag {
+    fn from(tag: ProviderTag) -> Self {
+        Self {
+            name: tag.name,
+            category: tag.category.into(),
+        }
+    }
+}
+
+impl From<&FfiProviderTag> for ProviderTag {
+    fn from(tag: &FfiProviderTag) -> Self {
+        Self {
+            name: tag.name.clone(),
+            category: tag.category.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiProviderRequests {
+    pub name: String,
+    pub requests: u64,
+}
+
+/// Traffic to one provider category over a session
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiCategoryTraffic {
+    pub category: FfiProviderCategory,
+    /// Connections opened and requests sent through the egress proxy
+    pub requests: u64,
+    /// Connections seen closing, which `duration_ms` and the byte counts cover
+    pub closed: u64,
+    pub duration_ms: u64,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    pub first_ms: Option<i64>,
+    pub last_ms: Option<i64>,
+    pub providers: Vec<FfiProviderRequests>,
+}
+
+fn ffi_traffic(summary: &TrafficSummary) -> Vec<FfiCategoryTraffic> {
+    summary
+        .iter()
+        .map(|(category, traffic)| FfiCategoryTraffic {
+            category: category.into(),
+            requests: traffic.requests,
+            closed: traffic.closed,
+            duration_ms: traffic.duration_ms,
+            bytes_sent: traffic.bytes_sent,
+            bytes_received: traffic.bytes_received,
+            first_ms: traffic.first.map(|t| t.timestamp_millis()),
+            last_ms: traffic.last.map(|t| t.timestamp_millis()),
+            providers: traffic
+                .providers
+                .iter()
+                .map(|(name, requests)| FfiProviderRequests {
+                    name: name.clone(),
+                    requests: *requests,
+                })
+                .collect(),
+        })
+        .collect()
 }
 
 #[derive(Debug, Clone, uniffi::Record)]
@@ -165,6 +394,65 @@ pub struct FfiChartDataPoint {
     pub low: u32,
 }
 
+/// Filters for cross-session queries; `None` fields match everything
+#[derive(Debug, Clone, Default, uniffi::Record)]
+pub struct FfiEventFilter {
+    pub agent: Option<String>,
+    pub text: Option<String>,
+    pub event_type: Option<String>,
+    pub risk_level: Option<FfiRiskLevel>,
+    pub start_time_ms: Option<i64>,
+    pub end_time_ms: Option<i64>,
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiGroupCount {
+    pub key: String,
+    pub total: u32,
+    pub critical: u32,
+    pub high: u32,
+    pub medium: u32,
+    pub low: u32,
+    pub sessions: u32,
+    pub first_seen_ms: i64,
+    pub last_seen_ms: i64,
+}
+
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiSessionMatch {
+    pub session_id: String,
+    pub agent_name: Option<String>,
+    pub process_name: Option<String>,
+    pub start_time_ms: Option<i64>,
+    pub end_time_ms: Option<i64>,
+    pub matching_events: u32,
+    pub max_risk_level: FfiRiskLevel,
+    pub first_match_ms: i64,
+    pub last_match_ms: i64,
+}
+
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiAgentTrend {
+    pub agent: String,
+    pub timestamp_ms: i64,
+    pub sessions: u32,
+    pub total: u32,
+    pub critical: u32,
+    pub high: u32,
+    pub medium: u32,
+    pub low: u32,
+}
+
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiBackfillResult {
+    pub imported: u32,
+    pub skipped: u32,
+    pub events: u32,
+    /// `path: error` for each session log that could not be imported
+    pub failures: Vec<String>,
+}
+
 #[derive(Debug, Clone, uniffi::Record)]
 pub struct FfiDetectedAgent {
     pub pid: u32,
@@ -199,6 +487,96 @@ impl From<RiskLevel> for FfiRiskLevel {
     }
 }
 
+impl From<FfiRiskLevel> for RiskLevel {
+    fn from(level: FfiRiskLevel) -> Self {
+        match level {
+            FfiRiskLevel::Low => RiskLevel::Low,
+            FfiRiskLevel::Medium => RiskLevel::Medium,
+            FfiRiskLevel::High => RiskLevel::High,
+            FfiRiskLevel::Critical => RiskLevel::Critical,
+        }
+    }
+}
+
+impl From<FfiGroupBy> for GroupBy {
+    fn from(group_by: FfiGroupBy) -> Self {
+        match group_by {
+            FfiGroupBy::Agent => GroupBy::Agent,
+            FfiGroupBy::Host => GroupBy::Host,
+            FfiGroupBy::Command => GroupBy::Command,
+            FfiGroupBy::Path => GroupBy::Path,
+        }
+    }
+}
+
+impl From<FfiEventFilter> for EventQuery {
+    fn from(filter: FfiEventFilter) -> Self {
+        EventQuery {
+            agent: filter.agent.filter(|a| !a.is_empty()),
+            text: filter.text.filter(|t| !t.is_empty()),
+            event_type: filter
+                .event_type
+                .filter(|t| SEARCHABLE_EVENT_TYPES.contains(&t.as_str())),
+            risk_level: filter.risk_level.map(RiskLevel::from),
+            start_time: filter
+                .start_time_ms
+                .and_then(chrono::DateTime::from_timestamp_millis),
+            end_time: filter
+                .end_time_ms
+                .and_then(chrono::DateTime::from_timestamp_millis),
+            limit: filter.limit.map(|l| l as usize),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<GroupCount> for FfiGroupCount {
+    fn from(group: GroupCount) -> Self {
+        FfiGroupCount {
+            key: group.key,
+            total: group.total,
+            critical: group.critical,
+            high: group.high,
+            medium: group.medium,
+            low: group.low,
+            sessions: group.sessions,
+            first_seen_ms: group.first_seen.timestamp_millis(),
+            last_seen_ms: group.last_seen.timestamp_millis(),
+        }
+    }
+}
+
+impl From<SessionMatch> for FfiSessionMatch {
+    fn from(session: SessionMatch) -> Self {
+        FfiSessionMatch {
+            session_id: session.session_id,
+            agent_name: session.agent_name,
+            process_name: session.process_name,
+            start_time_ms: session.start_time.map(|t| t.timestamp_millis()),
+            end_time_ms: session.end_time.map(|t| t.timestamp_millis()),
+            matching_events: session.matching_events,
+            max_risk_level: session.max_risk.into(),
+            first_match_ms: session.first_match.timestamp_millis(),
+            last_match_ms: session.last_match.timestamp_millis(),
+        }
+    }
+}
+
+impl From<AgentTrend> for FfiAgentTrend {
+    fn from(trend: AgentTrend) -> Self {
+        FfiAgentTrend {
+            agent: trend.agent,
+            timestamp_ms: trend.start_ms,
+            sessions: trend.sessions,
+            total: trend.total,
+            critical: trend.critical,
+            high: trend.high,
+            medium: trend.medium,
+            low: trend.low,
+        }
+    }
+}
+
 impl From<FileAction> for FfiFileAction {
     fn from(action: FileAction) -> Self {
         match action {
@@ -217,6 +595,7 @@ impl From<ProcessAction> for FfiProcessAction {
             ProcessAction::Start => FfiProcessAction::Start,
             ProcessAction::Exit => FfiProcessAction::Exit,
             ProcessAction::Fork => FfiProcessAction::Fork,
+            ProcessAction::Detach => FfiProcessAction::Detach,
         }
     }
 }
@@ -230,6 +609,31 @@ impl From<SessionAction> for FfiSessionAction {
     }
 }
 
+impl From<ResourceMetric> for FfiResourceMetric {
+    fn from(metric: ResourceMetric) -> Self {
+        match metric {
+            ResourceMetric::Processes => FfiResourceMetric::Processes,
+            ResourceMetric::SpawnRate => FfiResourceMetric::SpawnRate,
+            ResourceMetric::Cpu => FfiResourceMetric::Cpu,
+            ResourceMetric::Memory => FfiResourceMetric::Memory,
+            ResourceMetric::MemoryGrowth => FfiResourceMetric::MemoryGrowth,
+            ResourceMetric::Threads => FfiResourceMetric::Threads,
+            ResourceMetric::OpenFiles => FfiResourceMetric::OpenFiles,
+        }
+    }
+}
+
+impl From<AnomalyKind> for FfiAnomalyKind {
+    fn from(kind: AnomalyKind) -> Self {
+        match kind {
+            AnomalyKind::NewHosts => FfiAnomalyKind::NewHosts,
+            AnomalyKind::NewCommands => FfiAnomalyKind::NewCommands,
+            AnomalyKind::FileRate => FfiAnomalyKind::FileRate,
+            AnomalyKind::SessionLength => FfiAnomalyKind::SessionLength,
+        }
+    }
+}
+
 impl From<EventType> for FfiEventType {
     fn from(event_type: EventType) -> Self {
         match event_type {
@@ -250,12 +654,33 @@ impl From<EventType> for FfiEventType {
                 host,
                 port,
                 protocol,
+                provider,
             } => FfiEventType::Network {
                 host,
                 port,
                 protocol,
+                provider: provider.map(Into::into),
             },
-            EventType::Process { pid, ppid, action } => FfiEventType::Process {
+            EventType::NetworkClose {
+                host,
+                port,
+                protocol,
+                duration_ms,
+                bytes_sent,
+                bytes_received,
+                provider,
+            } => FfiEventType::NetworkClose {
+                host,
+                port,
+                protocol,
+                duration_ms,
+                bytes_sent,
+                bytes_received,
+                provider: provider.map(Into::into),
+            },
+            EventType::Process {
+                pid, ppid, action, ..
+            } => FfiEventType::Process {
                 pid,
                 ppid,
                 action: action.into(),
@@ -263,6 +688,75 @@ impl From<EventType> for FfiEventType {
             EventType::Session { action } => FfiEventType::Session {
                 action: action.into(),
             },
+            EventType::ConfigReload { path, changes } => FfiEventType::ConfigReload {
+                path: path.to_string_lossy().to_string(),
+                changes: changes
+                    .into_iter()
+                    .map(|c| FfiConfigChange {
+                        key: c.key,
+                        old_value: c.old,
+                        new_value: c.new,
+                    })
+                    .collect(),
+            },
+            EventType::Resource {
+                metric,
+                value,
+                limit,
+            } => FfiEventType::Resource {
+                metric: metric.into(),
+                value,
+                limit,
+            },
+            EventType::Anomaly {
+                kind,
+                score,
+                explanation,
+            } => FfiEventType::Anomaly {
+                kind: kind.into(),
+                score,
+                explanation,
+            },
+            EventType::FileSummary {
+                path,
+                action,
+                count,
+            } => FfiEventType::FileSummary {
+                path: path.to_string_lossy().to_string(),
+                action: action.into(),
+                count,
+            },
+            EventType::Listen {
+                address,
+                port,
+                protocol,
+            } => FfiEventType::Listen {
+                address,
+                port,
+                protocol,
+            },
+            EventType::ReverseShell {
+                host,
+                port,
+                protocol,
+            } => FfiEventType::ReverseShell {
+                host,
+                port,
+                protocol,
+            },
+            EventType::ProxyRequest {
+                method,
+                host,
+                port,
+                blocked,
+                provider,
+            } => FfiEventType::ProxyRequest {
+                method,
+                host,
+                port,
+                blocked,
+                provider: provider.map(Into::into),
+            },
         }
     }
 }
@@ -325,7 +819,13 @@ impl From<Config> for FfiConfig {
                 track_children: config.monitoring.track_children,
                 tracking_poll_ms: config.monitoring.tracking_poll_ms,
                 fs_debounce_ms: config.monitoring.fs_debounce_ms,
+                fs_burst_threshold: config.monitoring.fs_burst_threshold as u32,
+                fs_ignore: config.monitoring.fs_ignore,
+                fs_snapshot: config.monitoring.fs_snapshot,
                 net_poll_ms: config.monitoring.net_poll_ms,
+                net_upload_alert_mb: config.monitoring.net_upload_alert_mb,
+                net_proxy: config.monitoring.net_proxy,
+                net_proxy_block: config.monitoring.net_proxy_block,
                 watch_paths: config
                     .monitoring
                     .watch_paths
@@ -357,6 +857,7 @@ impl From<FfiConfig> for Config {
                 log_dir: ffi.logging.log_dir.map(std::path::PathBuf::from),
                 retention_days: ffi.logging.retention_days,
                 storage_backend: StorageBackend::default(),
+                ..LoggingConfig::default()
             },
             monitoring: MonitoringConfig {
                 fs_enabled: ffi.monitoring.fs_enabled,
@@ -364,7 +865,13 @@ impl From<FfiConfig> for Config {
                 track_children: ffi.monitoring.track_children,
                 tracking_poll_ms: ffi.monitoring.tracking_poll_ms,
                 fs_debounce_ms: ffi.monitoring.fs_debounce_ms,
+                fs_burst_threshold: ffi.monitoring.fs_burst_threshold as usize,
+                fs_ignore: ffi.monitoring.fs_ignore,
+                fs_snapshot: ffi.monitoring.fs_snapshot,
                 net_poll_ms: ffi.monitoring.net_poll_ms,
+                net_upload_alert_mb: ffi.monitoring.net_upload_alert_mb,
+                net_proxy: ffi.monitoring.net_proxy,
+                net_proxy_block: ffi.monitoring.net_proxy_block,
                 watch_paths: ffi
                     .monitoring
                     .watch_paths
@@ -379,6 +886,7 @@ impl From<FfiConfig> for Config {
                 custom_high_risk: ffi.alerts.custom_high_risk,
             },
             notifications: ffi.notification.into(),
+            sanitize: SanitizeConfig::default(),
         }
     }
 }
@@ -406,15 +914,21 @@ impl From<CoreError> for FfiError {
 
 #[uniffi::export]
 pub fn load_config() -> Result<FfiConfig, FfiError> {
+    invalidate_query_db();
     let config = Config::load().map_err(FfiError::from)?;
     Ok(config.into())
 }
 
 #[uniffi::export]
 pub fn save_config(config: FfiConfig) -> Result<(), FfiError> {
-    let config: Config = config.into();
+    let mut config: Config = config.into();
     let path = Config::default_path().map_err(FfiError::from)?;
+    // The app doesn't edit sanitizer rules; keep the ones on disk
+    if let Ok(existing) = Config::load_from_path(&path) {
+        config.sanitize = existing.sanitize;
+    }
     config.save(&path).map_err(FfiError::from)?;
+    invalidate_query_db();
     Ok(())
 }
 
@@ -437,13 +951,14 @@ pub fn save_notification_config(notification: FfiNotificationConfig) -> Result<(
 pub fn analyze_command(command: String, args: Vec<String>) -> Result<FfiEvent, FfiError> {
     let scorer = RiskScorer::new();
     let (risk_level, _reason) = scorer.score(&command, &args);
-    let event = Event::command(
+    let mut event = Event::command(
         command,
         args,
         "agent".to_string(),
         std::process::id(),
         risk_level,
     );
+    sanitize_event(&mut event);
     Ok(event.into())
 }
 
@@ -454,10 +969,13 @@ pub fn get_version() -> String {
 
 #[uniffi::export]
 pub fn read_session_log(path: String) -> Result<Vec<FfiEvent>, FfiError> {
-    let file = std::fs::File::open(&path).map_err(|e| FfiError::Io {
-        message: format!("Failed to open {}: {}", path, e),
-    })?;
-    let reader = std::io::BufReader::new(file);
+    if let Some((db, session_id)) = sqlite_session(&path) {
+        let storage = lock_db(&db);
+        let events = storage.query_events(&session_query(&session_id))?;
+        return Ok(events.into_iter().map(FfiEvent::from).collect());
+    }
+
+    let reader = open_log(&path)?;
     let mut events = Vec::new();
 
     for line in reader.lines() {
@@ -496,10 +1014,13 @@ pub fn list_session_logs() -> Result<Vec<FfiSessionInfo>, FfiError> {
         return Ok(Vec::new());
     }
 
-    let pattern = log_dir.join("session-*.jsonl");
+    // First segments only, plain or compressed; later segments and
+    // compressed copies belong to the same session
+    let pattern = log_dir.join("session-*");
     let pattern_str = pattern.to_string_lossy().to_string();
 
-    let mut sessions = Vec::new();
+    let mut sessions: Vec<FfiSessionInfo> = Vec::new();
+    let mut seen = std::collections::HashSet::new();
 
     for entry in glob::glob(&pattern_str).map_err(|e| FfiError::Other {
         message: format!("Invalid glob pattern: {}", e),
@@ -508,18 +1029,28 @@ pub fn list_session_logs() -> Result<Vec<FfiSessionInfo>, FfiError> {
             message: format!("Glob error: {}", e),
         })?;
 
-        let filename = path
+        let Some(segment) = path
             .file_name()
-            .unwrap_or_default()
-            .to_string_lossy()
-            .to_string();
-
-        // Extract session ID from filename: "session-{id}.jsonl"
-        let session_id = filename
-            .strip_prefix("session-")
-            .and_then(|s| s.strip_suffix(".jsonl"))
-            .unwrap_or(&filename)
-            .to_string();
+            .and_then(|n| n.to_str())
+            .and_then(SegmentName::parse)
+            .filter(|s| s.index == 0)
+        else {
+            continue;
+        };
+        if !seen.insert(segment.session_id.clone()) {
+            continue;
+        }
+        let session_id = segment.session_id.clone();
+
+        // Report the logical `session-{id}.jsonl` path, which every reader
+        // resolves to the session's segments
+        let logical_path = log_dir.join(
+            SegmentName {
+                compression: Compression::None,
+                ..segment
+            }
+            .file_name(),
+        );
 
         // Get file modification time as a fallback start time
         let start_time_str = if let Ok(metadata) = std::fs::metadata(&path) {
@@ -534,7 +1065,13 @@ pub fn list_session_logs() -> Result<Vec<FfiSessionInfo>, FfiError> {
         };
 
         // Extract agent_name from session header and compute max_risk_level
-        let (agent_name, max_risk_level) = extract_session_metadata(&path.to_string_lossy());
+        let path = logical_path;
+        let (agent_name, mut max_risk_level) = extract_session_metadata(&path.to_string_lossy());
+        if let Some((db, session_id)) = sqlite_session(&path.to_string_lossy()) {
+            if let Ok(summary) = sqlite_risk_summary(&lock_db(&db), &session_id) {
+                max_risk_level = max_risk_of(&summary);
+            }
+        }
 
         sessions.push(FfiSessionInfo {
             session_id,
@@ -559,7 +1096,9 @@ pub fn get_activity_summary(events: Vec<FfiEvent>) -> Result<FfiActivitySummary,
         high_count: 0,
         medium_count: 0,
         low_count: 0,
+        traffic: Vec::new(),
     };
+    let mut traffic = TrafficStats::new();
 
     for event in &events {
         match event.risk_level {
@@ -568,7 +1107,34 @@ pub fn get_activity_summary(events: Vec<FfiEvent>) -> Result<FfiActivitySummary,
             FfiRiskLevel::Medium => summary.medium_count += 1,
             FfiRiskLevel::Low => summary.low_count += 1,
         }
+
+        let at = chrono::DateTime::from_timestamp_millis(event.timestamp_ms).unwrap_or_default();
+        match &event.event_type {
+            FfiEventType::Network { host, provider, .. } => {
+                traffic.record_open(host, provider.as_ref().map(Into::into).as_ref(), at)
+            }
+            FfiEventType::ProxyRequest { host, provider, .. } => {
+                traffic.record_request(host, provider.as_ref().map(Into::into).as_ref(), at)
+            }
+            FfiEventType::NetworkClose {
+                host,
+                duration_ms,
+                bytes_sent,
+                bytes_received,
+                provider,
+                ..
+            } => traffic.record_close(
+                host,
+                provider.as_ref().map(Into::into).as_ref(),
+                at,
+                *duration_ms,
+                bytes_sent.unwrap_or(0),
+                bytes_received.unwrap_or(0),
+            ),
+            _ => {}
+        }
     }
+    summary.traffic = ffi_traffic(&traffic.summary());
 
     Ok(summary)
 }
@@ -578,11 +1144,10 @@ pub fn get_activity_summary(events: Vec<FfiEvent>) -> Result<FfiActivitySummary,
 /// Extract agent_name from session header and compute max_risk_level from events.
 /// Returns (Option<agent_name>, max_risk_level).
 fn extract_session_metadata(path: &str) -> (Option<String>, FfiRiskLevel) {
-    let file = match std::fs::File::open(path) {
-        Ok(f) => f,
+    let reader = match open_log(path) {
+        Ok(r) => r,
         Err(_) => return (None, FfiRiskLevel::Low),
     };
-    let reader = std::io::BufReader::new(file);
     let mut agent_name: Option<String> = None;
     let mut max_risk = RiskLevel::Low;
 
@@ -625,6 +1190,11 @@ fn extract_session_metadata(path: &str) -> (Option<String>, FfiRiskLevel) {
 
 #[uniffi::export]
 pub fn get_session_risk_summary(path: String) -> Result<FfiActivitySummary, FfiError> {
+    if let Some((db, session_id)) = sqlite_session(&path) {
+        let storage = lock_db(&db);
+        return sqlite_risk_summary(&storage, &session_id);
+    }
+
     let events = parse_events_from_file(&path)?;
     let mut summary = FfiActivitySummary {
         total_events: events.len() as u32,
@@ -632,7 +1202,9 @@ pub fn get_session_risk_summary(path: String) -> Result<FfiActivitySummary, FfiE
         high_count: 0,
         medium_count: 0,
         low_count: 0,
+        traffic: Vec::new(),
     };
+    let mut traffic = TrafficStats::new();
 
     for event in &events {
         match event.risk_level {
@@ -641,7 +1213,9 @@ pub fn get_session_risk_summary(path: String) -> Result<FfiActivitySummary, FfiE
             RiskLevel::Medium => summary.medium_count += 1,
             RiskLevel::Low => summary.low_count += 1,
         }
+        traffic.observe(event);
     }
+    summary.traffic = ffi_traffic(&traffic.summary());
 
     Ok(summary)
 }
@@ -651,10 +1225,7 @@ pub fn get_session_risk_summary(path: String) -> Result<FfiActivitySummary, FfiE
 /// Parse events from a JSONL session file, returning Event objects.
 /// Skips session metadata lines (session_start/session_end) and empty lines.
 fn parse_events_from_file(path: &str) -> Result<Vec<Event>, FfiError> {
-    let file = std::fs::File::open(path).map_err(|e| FfiError::Io {
-        message: format!("Failed to open {}: {}", path, e),
-    })?;
-    let reader = std::io::BufReader::new(file);
+    let reader = open_log(path)?;
     let mut events = Vec::new();
 
     for line in reader.lines() {
@@ -676,6 +1247,21 @@ fn parse_events_from_file(path: &str) -> Result<Vec<Event>, FfiError> {
     Ok(events)
 }
 
+/// Open a session log for reading, following rolled-over and compressed
+/// segments.
+fn open_log(path: &str) -> Result<Box<dyn BufRead + Send>, FfiError> {
+    open_session_log(std::path::Path::new(path)).map_err(|e| FfiError::Io {
+        message: format!("Failed to open {}: {}", path, e),
+    })
+}
+
+/// Whether the session at `path` only exists in compressed form, which the
+/// incremental tail reader can't follow
+fn is_compressed_session(path: &str) -> bool {
+    let path = std::path::Path::new(path);
+    !path.exists() && !session_segments(path).is_empty()
+}
+
 // ─── New Exported Functions (v0.4.0) ──────────────────────────────────────────
 
 #[uniffi::export]
@@ -684,6 +1270,17 @@ pub fn read_session_log_paginated(
     offset: u32,
     limit: u32,
 ) -> Result<Vec<FfiEvent>, FfiError> {
+    if let Some((db, session_id)) = sqlite_session(&path) {
+        let storage = lock_db(&db);
+        let query = EventQuery {
+            offset: Some(offset as usize),
+            limit: Some(limit as usize),
+            ..session_query(&session_id)
+        };
+        let events = storage.query_events(&query)?;
+        return Ok(events.into_iter().map(FfiEvent::from).collect());
+    }
+
     let events = parse_events_from_file(&path)?;
     let offset = offset as usize;
     let limit = limit as usize;
@@ -700,8 +1297,18 @@ pub fn read_session_log_paginated(
 
 #[uniffi::export]
 pub fn get_session_event_count(path: String) -> Result<u32, FfiError> {
-    let events = parse_events_from_file(&path)?;
-    Ok(events.len() as u32)
+    if let Some((db, session_id)) = sqlite_session(&path) {
+        let storage = lock_db(&db);
+        return Ok(storage.count_events(&session_query(&session_id))? as u32);
+    }
+    if is_compressed_session(&path) {
+        return Ok(parse_events_from_file(&path)?.len() as u32);
+    }
+
+    with_session_tail(&path, |tail| {
+        tail.poll()?;
+        Ok(tail.event_count() as u32)
+    })
 }
 
 #[uniffi::export]
@@ -716,6 +1323,11 @@ pub fn get_chart_data(
     };
     let bucket_ms: i64 = bucket_minutes as i64 * 60 * 1000;
 
+    if let Some((db, session_id)) = sqlite_session(&path) {
+        let storage = lock_db(&db);
+        return sqlite_chart_data(&storage, &session_id, bucket_ms);
+    }
+
     let events = parse_events_from_file(&path)?;
     if events.is_empty() {
         return Ok(Vec::new());
@@ -758,6 +1370,20 @@ pub fn search_events(
     start_time_ms: Option<i64>,
     end_time_ms: Option<i64>,
 ) -> Result<Vec<FfiEvent>, FfiError> {
+    if let Some((db, session_id)) = sqlite_session(&path) {
+        let storage = lock_db(&db);
+        let query = EventQuery {
+            text: Some(query).filter(|q| !q.is_empty()),
+            event_type: event_type_filter.filter(|t| SEARCHABLE_EVENT_TYPES.contains(&t.as_str())),
+            risk_level: risk_level_filter.map(RiskLevel::from),
+            start_time: start_time_ms.and_then(chrono::DateTime::from_timestamp_millis),
+            end_time: end_time_ms.and_then(chrono::DateTime::from_timestamp_millis),
+            ..session_query(&session_id)
+        };
+        let events = storage.query_events(&query)?;
+        return Ok(events.into_iter().map(FfiEvent::from).collect());
+    }
+
     let events = parse_events_from_file(&path)?;
     let query_lower = query.to_lowercase();
 
@@ -791,7 +1417,25 @@ pub fn search_events(
                     "command" => matches!(event.event_type, EventType::Command { .. }),
                     "file_access" => matches!(event.event_type, EventType::FileAccess { .. }),
                     "network" => matches!(event.event_type, EventType::Network { .. }),
+                    "network_close" => {
+                        matches!(event.event_type, EventType::NetworkClose { .. })
+                    }
                     "process" => matches!(event.event_type, EventType::Process { .. }),
+                    "config_reload" => {
+                        matches!(event.event_type, EventType::ConfigReload { .. })
+                    }
+                    "resource" => matches!(event.event_type, EventType::Resource { .. }),
+                    "anomaly" => matches!(event.event_type, EventType::Anomaly { .. }),
+                    "file_summary" => {
+                        matches!(event.event_type, EventType::FileSummary { .. })
+                    }
+                    "listen" => matches!(event.event_type, EventType::Listen { .. }),
+                    "reverse_shell" => {
+                        matches!(event.event_type, EventType::ReverseShell { .. })
+                    }
+                    "proxy_request" => {
+                        matches!(event.event_type, EventType::ProxyRequest { .. })
+                    }
                     _ => true,
                 };
                 if !matches_type {
@@ -808,12 +1452,25 @@ pub fn search_events(
                     command.to_lowercase().contains(&query_lower)
                         || args.iter().any(|a| a.to_lowercase().contains(&query_lower))
                 }
-                EventType::FileAccess { path, .. } => {
+                EventType::FileAccess { path, .. } | EventType::FileSummary { path, .. } => {
                     path.to_string_lossy().to_lowercase().contains(&query_lower)
                 }
-                EventType::Network { host, .. } => host.to_lowercase().contains(&query_lower),
+                EventType::Network { host, .. }
+                | EventType::NetworkClose { host, .. }
+                | EventType::ReverseShell { host, .. }
+                | EventType::ProxyRequest { host, .. } => {
+                    host.to_lowercase().contains(&query_lower)
+                }
+                EventType::Listen { address, .. } => address.to_lowercase().contains(&query_lower),
                 EventType::Process { .. } => false,
                 EventType::Session { .. } => false,
+                EventType::Resource { .. } => false,
+                EventType::Anomaly { explanation, .. } => {
+                    explanation.to_lowercase().contains(&query_lower)
+                }
+                EventType::ConfigReload { changes, .. } => changes
+                    .iter()
+                    .any(|c| c.key.to_lowercase().contains(&query_lower)),
             }
         })
         .map(FfiEvent::from)
@@ -824,12 +1481,614 @@ pub fn search_events(
 
 #[uniffi::export]
 pub fn get_latest_events(path: String, since_index: u32) -> Result<Vec<FfiEvent>, FfiError> {
-    let events = parse_events_from_file(&path)?;
-    let since = since_index as usize;
+    if let Some((db, session_id)) = sqlite_session(&path) {
+        let storage = lock_db(&db);
+        let query = EventQuery {
+            offset: Some(since_index as usize),
+            ..session_query(&session_id)
+        };
+        let events = storage.query_events(&query)?;
+        return Ok(events.into_iter().map(FfiEvent::from).collect());
+    }
+
+    if is_compressed_session(&path) {
+        let events = parse_events_from_file(&path)?;
+        return Ok(events
+            .into_iter()
+            .skip(since_index as usize)
+            .map(FfiEvent::from)
+            .collect());
+    }
+
+    let events = with_session_tail(&path, |tail| tail.events_since(since_index as usize))?;
+    Ok(events.into_iter().map(FfiEvent::from).collect())
+}
+
+// ─── SQLite-backed Session Queries ────────────────────────────────────────────
+
+/// Event type filters understood by `search_events`; anything else matches all
+const SEARCHABLE_EVENT_TYPES: &[&str] = &[
+    "command",
+    "file_access",
+    "network",
+    "process",
+    "config_reload",
+];
+
+/// Current config, or defaults if the file is missing or invalid.
+///
+/// Unlike `Config::load()` this never writes a sample config file.
+fn load_config_quiet() -> Config {
+    Config::default_path()
+        .ok()
+        .filter(|p| p.exists())
+        .and_then(|p| Config::load_from_path(&p).ok())
+        .unwrap_or_default()
+}
+
+/// Session ID encoded in a session log filename (`session-{id}.jsonl`,
+/// or any of its segments)
+fn session_id_from_path(path: &str) -> Option<String> {
+    let name = std::path::Path::new(path).file_name()?.to_str()?;
+    SegmentName::parse(name).map(|segment| segment.session_id)
+}
+
+/// Open SQLite database shared by session queries
+type SharedDb = Arc<Mutex<SqliteStorage>>;
+
+/// Query backend resolved from the config, and the databases opened for it,
+/// so polling a session doesn't re-read the config and reopen the database.
+#[derive(Default)]
+struct QueryDbCache {
+    /// Database to query (`Some(None)` when the config selects JSONL only);
+    /// `None` until resolved
+    db_path: Option<Option<std::path::PathBuf>>,
+    open: HashMap<std::path::PathBuf, SharedDb>,
+}
+
+impl QueryDbCache {
+    /// Database at the path `resolve` picks on first use, opened once per
+    /// path; `None` if no path is picked or the database doesn't exist yet
+    fn get(&mut self, resolve: impl FnOnce() -> Option<std::path::PathBuf>) -> Option<SharedDb> {
+        let db_path = self.db_path.get_or_insert_with(resolve).clone()?;
+        if let Some(db) = self.open.get(&db_path) {
+            return Some(Arc::clone(db));
+        }
+        if !db_path.exists() {
+            return None;
+        }
+        let db = Arc::new(Mutex::new(SqliteStorage::new(&db_path).ok()?));
+        self.open.insert(db_path, Arc::clone(&db));
+        Some(db)
+    }
+}
+
+static QUERY_DB: LazyLock<Mutex<QueryDbCache>> =
+    LazyLock::new(|| Mutex::new(QueryDbCache::default()));
+
+fn query_db_cache() -> std::sync::MutexGuard<'static, QueryDbCache> {
+    QUERY_DB
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// The SQLite database session queries read, or `None` if the configured
+/// backend is JSONL only or the database doesn't exist yet
+fn query_db() -> Option<SharedDb> {
+    query_db_cache().get(|| {
+        let config = load_config_quiet();
+        config
+            .logging
+            .uses_sqlite()
+            .then(|| config.logging.effective_db_path().ok())
+            .flatten()
+    })
+}
+
+/// Forget the cached query backend, so the next query re-reads the config
+fn invalidate_query_db() {
+    *query_db_cache() = QueryDbCache::default();
+}
+
+fn lock_db(db: &SharedDb) -> std::sync::MutexGuard<'_, SqliteStorage> {
+    db.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// SQLite store and session ID to query for a session log path.
+///
+/// Returns `None`, meaning the JSONL file is read instead, unless the
+/// configured backend is `sqlite` or `both` and the database holds the session.
+fn sqlite_session(path: &str) -> Option<(SharedDb, String)> {
+    let session_id = session_id_from_path(path)?;
+    let db = query_db()?;
+    let has_session = lock_db(&db).has_session(&session_id).ok()?;
+    has_session.then_some((db, session_id))
+}
+
+/// Open the configured SQLite database for a new engine session
+fn open_session_db(
+    config: &Config,
+    session_id: &str,
+    process_name: &str,
+    agent_name: Option<&str>,
+) -> Result<SqliteStorage, CoreError> {
+    let storage =
+        SqliteStorage::new(&config.logging.effective_db_path()?)?.with_session_id(session_id);
+    storage.write_session_header(
+        session_id,
+        process_name,
+        std::process::id(),
+        &chrono::Utc::now(),
+        agent_name,
+    )?;
+    Ok(storage)
+}
+
+fn session_query(session_id: &str) -> EventQuery {
+    EventQuery {
+        session_id: Some(session_id.to_string()),
+        ..Default::default()
+    }
+}
+
+fn sqlite_chart_data(
+    storage: &SqliteStorage,
+    session_id: &str,
+    bucket_ms: i64,
+) -> Result<Vec<FfiChartDataPoint>, FfiError> {
+    let buckets = storage.chart_data(&session_query(session_id), bucket_ms)?;
+    Ok(buckets
+        .into_iter()
+        .map(|b| FfiChartDataPoint {
+            timestamp_ms: b.start_ms,
+            total: b.total,
+            critical: b.critical,
+            high: b.high,
+            medium: b.medium,
+            low: b.low,
+        })
+        .collect())
+}
+
+fn sqlite_risk_summary(
+    storage: &SqliteStorage,
+    session_id: &str,
+) -> Result<FfiActivitySummary, FfiError> {
+    let mut summary = FfiActivitySummary {
+        total_events: 0,
+        critical_count: 0,
+        high_count: 0,
+        medium_count: 0,
+        low_count: 0,
+        traffic: Vec::new(),
+    };
+    for point in sqlite_chart_data(storage, session_id, i64::MAX)? {
+        summary.total_events += point.total;
+        summary.critical_count += point.critical;
+        summary.high_count += point.high;
+        summary.medium_count += point.medium;
+        summary.low_count += point.low;
+    }
+
+    let mut traffic = TrafficStats::new();
+    for event_type in ["network", "network_close", "proxy_request"] {
+        let query = EventQuery {
+            event_type: Some(event_type.to_string()),
+            ..session_query(session_id)
+        };
+        for event in storage.query_events(&query)? {
+            traffic.observe(&event);
+        }
+    }
+    summary.traffic = ffi_traffic(&traffic.summary());
+    Ok(summary)
+}
+
+fn max_risk_of(summary: &FfiActivitySummary) -> FfiRiskLevel {
+    if summary.critical_count > 0 {
+        FfiRiskLevel::Critical
+    } else if summary.high_count > 0 {
+        FfiRiskLevel::High
+    } else if summary.medium_count > 0 {
+        FfiRiskLevel::Medium
+    } else {
+        FfiRiskLevel::Low
+    }
+}
+
+// ─── Cross-Session Queries ────────────────────────────────────────────────────
+
+/// Open the event database shared by all sessions
+fn open_event_db() -> Result<SqliteStorage, FfiError> {
+    let db_path = load_config_quiet().logging.effective_db_path()?;
+    Ok(SqliteStorage::new(&db_path)?)
+}
+
+/// Events matching `filter` across all sessions in the database
+#[uniffi::export]
+pub fn search_all_sessions(filter: FfiEventFilter) -> Result<Vec<FfiEvent>, FfiError> {
+    let events = open_event_db()?.query_events(&filter.into())?;
+    Ok(events.into_iter().map(FfiEvent::from).collect())
+}
 
-    let latest: Vec<FfiEvent> = events.into_iter().skip(since).map(FfiEvent::from).collect();
+/// Sessions containing events that match `filter`, most recent first
+#[uniffi::export]
+pub fn find_sessions(filter: FfiEventFilter) -> Result<Vec<FfiSessionMatch>, FfiError> {
+    let sessions = open_event_db()?.sessions_matching(&filter.into())?;
+    Ok(sessions.into_iter().map(FfiSessionMatch::from).collect())
+}
 
-    Ok(latest)
+/// Matching events counted per agent, host, command or path, most frequent first
+#[uniffi::export]
+pub fn group_events(
+    filter: FfiEventFilter,
+    group_by: FfiGroupBy,
+) -> Result<Vec<FfiGroupCount>, FfiError> {
+    let groups = open_event_db()?.group_counts(&filter.into(), group_by.into())?;
+    Ok(groups.into_iter().map(FfiGroupCount::from).collect())
+}
+
+/// Per-agent activity in `bucket_minutes` buckets
+#[uniffi::export]
+pub fn get_agent_trends(
+    filter: FfiEventFilter,
+    bucket_minutes: u32,
+) -> Result<Vec<FfiAgentTrend>, FfiError> {
+    let bucket_ms = bucket_minutes.max(1) as i64 * 60 * 1000;
+    let trends = open_event_db()?.agent_trends(&filter.into(), bucket_ms)?;
+    Ok(trends.into_iter().map(FfiAgentTrend::from).collect())
+}
+
+/// Import JSONL session logs from `log_dir` (default: the configured log
+/// directory) into the event database. Already imported sessions are skipped.
+#[uniffi::export]
+pub fn import_session_logs(log_dir: Option<String>) -> Result<FfiBackfillResult, FfiError> {
+    let log_dir = match log_dir {
+        Some(dir) => std::path::PathBuf::from(dir),
+        None => load_config_quiet().logging.effective_log_dir()?,
+    };
+    let result = open_event_db()?.backfill_from_dir(&log_dir)?;
+    Ok(FfiBackfillResult {
+        imported: result.imported as u32,
+        skipped: result.skipped as u32,
+        events: result.events as u32,
+        failures: result
+            .failed
+            .into_iter()
+            .map(|(path, error)| format!("{}: {}", path.display(), error))
+            .collect(),
+    })
+}
+
+// ─── Session Tailing ──────────────────────────────────────────────────────────
+
+/// Most session files kept open for tailing; beyond this the least
+/// recently polled reader is dropped.
+const MAX_SESSION_TAILS: usize = 32;
+
+/// Tail readers for polled session files, keyed by path, so repeated polls
+/// only parse lines appended since the previous call.
+///
+/// Each reader has its own lock, so parsing one file never blocks polls of
+/// another; the map lock is only held to look a reader up.
+#[derive(Default)]
+struct SessionTails {
+    entries: HashMap<String, (Arc<Mutex<SessionTail>>, u64)>,
+    clock: u64,
+}
+
+impl SessionTails {
+    /// Reader for `path`, created if needed, marked as most recently used
+    fn get(&mut self, path: &str) -> Arc<Mutex<SessionTail>> {
+        self.clock += 1;
+        let clock = self.clock;
+        if let Some((tail, last_used)) = self.entries.get_mut(path) {
+            *last_used = clock;
+            return Arc::clone(tail);
+        }
+
+        if self.entries.len() >= MAX_SESSION_TAILS {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(key) = oldest {
+                self.entries.remove(&key);
+            }
+        }
+        let tail = Arc::new(Mutex::new(SessionTail::new(path)));
+        self.entries
+            .insert(path.to_string(), (Arc::clone(&tail), clock));
+        tail
+    }
+
+    /// Drop the reader for `path` if it is still `tail`
+    fn remove(&mut self, path: &str, tail: &Arc<Mutex<SessionTail>>) {
+        if self
+            .entries
+            .get(path)
+            .is_some_and(|(cached, _)| Arc::ptr_eq(cached, tail))
+        {
+            self.entries.remove(path);
+        }
+    }
+}
+
+static SESSION_TAILS: LazyLock<Mutex<SessionTails>> =
+    LazyLock::new(|| Mutex::new(SessionTails::default()));
+
+fn session_tails() -> std::sync::MutexGuard<'static, SessionTails> {
+    SESSION_TAILS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Run `f` with the cached tail reader for `path`. The reader is dropped
+/// again if reading fails (e.g. the file was deleted).
+fn with_session_tail<T>(
+    path: &str,
+    f: impl FnOnce(&mut SessionTail) -> Result<T, CoreError>,
+) -> Result<T, FfiError> {
+    let tail = session_tails().get(path);
+    let result = {
+        let mut reader = tail.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        f(&mut reader)
+    };
+    if result.is_err() {
+        session_tails().remove(path, &tail);
+    }
+    result.map_err(FfiError::from)
+}
+
+/// Drop the cached tail reader for `path`, e.g. once its session has ended
+fn forget_session_tail(path: &str) {
+    session_tails().entries.remove(path);
+}
+
+/// How often a subscription re-reads its session file where FSEvents
+/// aren't available to report changes
+const SUBSCRIPTION_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(250);
+
+/// Wakes a session file tail: a change reported by FSEvents, or `cancel`
+#[cfg(target_os = "macos")]
+type TailWake = fsevent::Event;
+#[cfg(not(target_os = "macos"))]
+struct TailWake;
+
+#[cfg(target_os = "macos")]
+fn cancel_wake() -> TailWake {
+    fsevent::Event {
+        event_id: 0,
+        flag: fsevent::StreamFlags::NONE,
+        path: String::new(),
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+fn cancel_wake() -> TailWake {
+    TailWake
+}
+
+/// FSEvents stream on the directory of a session file, stopped on drop
+struct SessionDirWatch {
+    #[cfg(target_os = "macos")]
+    stream: fsevent::FsEvent,
+}
+
+impl SessionDirWatch {
+    /// Send a wake-up on every change next to `path`. `None` if the
+    /// directory can't be watched, including on platforms without FSEvents.
+    #[cfg(target_os = "macos")]
+    fn start(path: &str, wake: mpsc::Sender<TailWake>) -> Option<Self> {
+        let dir = std::path::Path::new(path).parent()?.to_str()?;
+        let mut stream = fsevent::FsEvent::new(vec![dir.to_string()]);
+        stream.observe_async(wake).ok()?;
+        Some(Self { stream })
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    fn start(_path: &str, _wake: mpsc::Sender<TailWake>) -> Option<Self> {
+        None
+    }
+}
+
+#[cfg(target_os = "macos")]
+impl Drop for SessionDirWatch {
+    fn drop(&mut self) {
+        self.stream.shutdown_observe();
+    }
+}
+
+/// Receives events pushed by an [`FfiSessionSubscription`]
+#[uniffi::export(with_foreign)]
+pub trait FfiSessionListener: Send + Sync {
+    /// New events were appended to the session file
+    fn on_events(&self, events: Vec<FfiEvent>);
+    /// The session file was truncated or replaced; previously delivered
+    /// events are stale and delivery restarts from the first event
+    fn on_reset(&self);
+    /// Reading the session file failed; the subscription has stopped
+    fn on_error(&self, message: String);
+}
+
+/// Push-based alternative to polling `get_latest_events`.
+///
+/// A background thread tails the session file and forwards appended events
+/// to the listener until [`FfiSessionSubscription::cancel`] is called or the
+/// subscription is dropped. It re-reads the file when FSEvents reports a
+/// change in its directory, polling only where FSEvents aren't available.
+#[derive(uniffi::Object)]
+pub struct FfiSessionSubscription {
+    stop_flag: Arc<AtomicBool>,
+    /// Held while events are handed to the listener, so that none arrive
+    /// once `cancel` has returned
+    delivering: Arc<Mutex<()>>,
+    /// Wakes a session file tail so it sees `cancel` straight away. Engine
+    /// subscriptions have none and their thread exits with the next event.
+    wake: Option<mpsc::Sender<TailWake>>,
+    worker: thread::ThreadId,
+    thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+#[uniffi::export]
+impl FfiSessionSubscription {
+    /// Start delivering events from `since_index` onwards
+    #[uniffi::constructor]
+    pub fn new(path: String, since_index: u32, listener: Arc<dyn FfiSessionListener>) -> Self {
+        let stop_flag = Arc::new(AtomicBool::new(false));
+        let delivering = Arc::new(Mutex::new(()));
+        let (wake, wake_rx) = mpsc::channel();
+        let thread_stop = Arc::clone(&stop_flag);
+        let thread_delivering = Arc::clone(&delivering);
+        let thread_wake = wake.clone();
+
+        let handle = thread::spawn(move || {
+            let watch = SessionDirWatch::start(&path, thread_wake);
+            let mut tail = SessionTail::new(&path);
+            let mut since = since_index as usize;
+
+            loop {
+                let update = tail.poll();
+                {
+                    let _delivering = thread_delivering
+                        .lock()
+                        .unwrap_or_else(|poisoned| poisoned.into_inner());
+                    if thread_stop.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    match update {
+                        Ok(update) => {
+                            if update.reset {
+                                since = 0;
+                                listener.on_reset();
+                            }
+                            let skip = since.saturating_sub(update.first_index);
+                            since = since.max(tail.event_count());
+                            let events: Vec<FfiEvent> = update
+                                .events
+                                .into_iter()
+                                .skip(skip)
+                                .map(FfiEvent::from)
+                                .collect();
+                            if !events.is_empty() {
+                                listener.on_events(events);
+                            }
+                        }
+                        Err(e) => {
+                            listener.on_error(e.to_string());
+                            break;
+                        }
+                    }
+                }
+
+                // Sleep until the file changes or the subscription is cancelled
+                let woken = if watch.is_some() {
+                    wake_rx.recv().is_ok()
+                } else {
+                    !matches!(
+                        wake_rx.recv_timeout(SUBSCRIPTION_POLL_INTERVAL),
+                        Err(mpsc::RecvTimeoutError::Disconnected)
+                    )
+                };
+                if !woken {
+                    break;
+                }
+                // One read covers every change reported meanwhile
+                while wake_rx.try_recv().is_ok() {}
+            }
+        });
+
+        Self {
+            stop_flag,
+            delivering,
+            wake: Some(wake),
+            worker: handle.thread().id(),
+            thread: Mutex::new(Some(handle)),
+        }
+    }
+
+    /// Stop delivering events. No listener call starts once this returns.
+    /// Waits for a session file tail's thread to exit, unless called from a
+    /// listener callback on that thread.
+    pub fn cancel(&self) {
+        self.stop_flag.store(true, Ordering::Relaxed);
+        let on_worker = thread::current().id() == self.worker;
+        if !on_worker {
+            // Let a delivery under way finish
+            drop(
+                self.delivering
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner()),
+            );
+        }
+
+        let handle = self
+            .thread
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .take();
+        if let (Some(handle), Some(wake)) = (handle, &self.wake) {
+            let _ = wake.send(cancel_wake());
+            if !on_worker {
+                let _ = handle.join();
+            }
+        }
+    }
+
+    /// Whether events are still being delivered: false once cancelled, or
+    /// once reading the session file failed or the engine went away
+    pub fn is_active(&self) -> bool {
+        !self.stop_flag.load(Ordering::Relaxed)
+            && self
+                .thread
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .as_ref()
+                .is_some_and(|h| !h.is_finished())
+    }
+}
+
+impl FfiSessionSubscription {
+    /// Deliver events received from a bus subscription, batching those that
+    /// arrive together
+    fn from_receiver(rx: mpsc::Receiver<Event>, listener: Arc<dyn FfiSessionListener>) -> Self {
+        let stop_flag = Arc::new(AtomicBool::new(false));
+        let delivering = Arc::new(Mutex::new(()));
+        let thread_stop = Arc::clone(&stop_flag);
+        let thread_delivering = Arc::clone(&delivering);
+
+        // Dropping the receiver on exit unsubscribes from the bus
+        let handle = thread::spawn(move || {
+            while let Ok(first) = rx.recv() {
+                let events: Vec<FfiEvent> = std::iter::once(first)
+                    .chain(rx.try_iter())
+                    .map(FfiEvent::from)
+                    .collect();
+                let _delivering = thread_delivering
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner());
+                if thread_stop.load(Ordering::Relaxed) {
+                    break;
+                }
+                listener.on_events(events);
+            }
+        });
+
+        Self {
+            stop_flag,
+            delivering,
+            wake: None,
+            worker: handle.thread().id(),
+            thread: Mutex::new(Some(handle)),
+        }
+    }
+}
+
+impl Drop for FfiSessionSubscription {
+    fn drop(&mut self) {
+        self.cancel();
+    }
 }
 
 // ─── FfiMonitoringEngine Object ───────────────────────────────────────────────
@@ -845,21 +2104,26 @@ enum SessionState {
 
 struct MonitoringSession {
     logger: Arc<Mutex<SessionLogger>>,
-    trackers: Vec<ProcessTracker>,
-    fs_watcher: Option<FileSystemWatcher>,
-    net_monitors: Vec<NetworkMonitor>,
+    /// Tracker, network monitor and file watcher of each detected agent
+    attachments: Vec<Attachment>,
+    config_watcher: Option<ConfigWatcher>,
+    retention: Option<RetentionManager>,
+    /// Compression applied to the session log once the session has ended
+    compression: Compression,
     writer_thread: Option<JoinHandle<()>>,
     detected_agents: Vec<FfiDetectedAgent>,
     /// Sender side of the unified event channel. Held here so we can drop it
     /// on stop, which causes the writer thread's recv() to return Err and exit.
     unified_tx: Option<mpsc::Sender<Event>>,
-    /// Handles for forwarding threads (TrackerEvent → Event bridges)
+    /// Handles for forwarding threads (attachment and config reload → unified channel)
     forwarding_threads: Vec<JoinHandle<()>>,
 }
 
 #[derive(uniffi::Object)]
 pub struct FfiMonitoringEngine {
     state: Mutex<(SessionState, Option<MonitoringSession>)>,
+    /// Events of the active session, published once they are stored
+    events: EventBus<Event>,
 }
 
 impl Default for FfiMonitoringEngine {
@@ -874,9 +2138,27 @@ impl FfiMonitoringEngine {
     pub fn new() -> Self {
         FfiMonitoringEngine {
             state: Mutex::new((SessionState::Idle, None)),
+            events: EventBus::new(),
         }
     }
 
+    /// Push events of this and later sessions to `listener` as they are
+    /// recorded. Any number of subscriptions can be active at once; each
+    /// has its own filter and buffer.
+    pub fn subscribe(
+        &self,
+        options: FfiSubscribeOptions,
+        listener: Arc<dyn FfiSessionListener>,
+    ) -> Arc<FfiSessionSubscription> {
+        let rx = self.events.subscribe_with(options.into());
+        Arc::new(FfiSessionSubscription::from_receiver(rx, listener))
+    }
+
+    /// Events dropped for subscriptions whose listener fell behind
+    pub fn dropped_events(&self) -> u64 {
+        self.events.dropped()
+    }
+
     pub fn start_session(&self, process_name: String) -> Result<String, FfiError> {
         // Acquire lock and check state atomically
         let mut guard = self.state.lock().map_err(|e| FfiError::Other {
@@ -927,12 +2209,15 @@ impl FfiMonitoringEngine {
         let first_agent_name = detected_agents.first().map(|a| a.name.clone());
 
         // 3. Create SessionLogger with agent name
-        let mut logger = SessionLogger::new(&log_dir, None).map_err(|e| {
-            *state = SessionState::Idle;
-            FfiError::Storage {
-                message: format!("Failed to create session logger: {}", e),
-            }
-        })?;
+        let retention_policy = RetentionPolicy::from_config(&config.logging);
+        let mut logger = SessionLogger::new(&log_dir, None)
+            .map_err(|e| {
+                *state = SessionState::Idle;
+                FfiError::Storage {
+                    message: format!("Failed to create session logger: {}", e),
+                }
+            })?
+            .with_max_segment_bytes(retention_policy.max_segment_bytes);
 
         logger
             .write_session_header(
@@ -950,136 +2235,147 @@ impl FfiMonitoringEngine {
         let session_id = logger.session_id().to_string();
         let logger = Arc::new(Mutex::new(logger));
 
+        // 3b. SQLite store when the configured backend asks for it. The JSONL
+        //     header/footer are always written so the session stays listable.
+        let sqlite = if config.logging.uses_sqlite() {
+            match open_session_db(
+                &config,
+                &session_id,
+                &process_name,
+                first_agent_name.as_deref(),
+            ) {
+                Ok(storage) => Some(storage),
+                Err(e) => {
+                    eprintln!(
+                        "[agent-watch] Warning: Failed to open SQLite storage, falling back to JSONL: {}",
+                        e
+                    );
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        let write_jsonl = config.logging.uses_jsonl() || sqlite.is_none();
+
         // 4. Create unified event channel
         let (unified_tx, unified_rx) = mpsc::channel::<Event>();
 
-        // 5. For each detected agent: create ProcessTracker and NetworkMonitor
-        let mut trackers = Vec::new();
-        let mut net_monitors = Vec::new();
+        // 4b. Detection rules shared by all subsystems, swapped on config reload
+        let rules = LiveRules::from_config(&config);
+        if let Err(e) = crate::sanitize::configure(&config.sanitize) {
+            eprintln!("[agent-watch] Warning: Invalid [sanitize] rules, using built-in rules: {e}");
+        }
+
+        // 5. Attach the tracker and network monitor to each detected agent.
+        //    The file system is watched once, alongside the first agent.
+        let mut watch_paths = config.monitoring.watch_paths.clone();
+        if watch_paths.is_empty() {
+            watch_paths.extend(dirs::home_dir());
+        }
+        let mut attachments = Vec::new();
         let mut forwarding_threads = Vec::new();
 
-        for agent in &raw_agents {
-            // ProcessTracker
-            if config.monitoring.track_children {
-                let mut tracker = ProcessTracker::new(TrackerConfig::new(agent.pid).poll_interval(
-                    std::time::Duration::from_millis(config.monitoring.tracking_poll_ms),
-                ));
-                let tracker_rx = tracker.subscribe();
-                tracker.start();
+        for (i, agent) in raw_agents.iter().enumerate() {
+            let attach_config =
+                AttachConfig::from_config(agent.pid, &agent.name, &config.monitoring)
+                    .enable_fswatch(config.monitoring.fs_enabled && i == 0)
+                    .watch_paths(watch_paths.clone());
+            let mut attachment = Attachment::new(attach_config, rules.clone());
+            let attach_rx = attachment.subscribe_with(SubscribeOptions::blocking());
+            if let Err(e) = attachment.start() {
+                eprintln!(
+                    "[agent-watch] Warning: Failed to attach to {} ({}): {e}",
+                    agent.name, agent.pid
+                );
+                continue;
+            }
+
+            let fwd_tx = unified_tx.clone();
+            let fwd_handle = thread::spawn(move || {
+                while let Ok(event) = attach_rx.recv() {
+                    if fwd_tx.send(event).is_err() {
+                        break;
+                    }
+                }
+            });
+            forwarding_threads.push(fwd_handle);
+            attachments.push(attachment);
+        }
 
-                // Forwarding thread: TrackerEvent → Event
+        // 6b. ConfigWatcher: hot reload rules when the config file changes
+        let mut config_watcher = None;
+        if let Ok(config_path) = Config::default_path() {
+            let mut watcher = ConfigWatcher::new(ConfigWatchConfig::new(config_path));
+            let reload_rx = watcher.subscribe_with(SubscribeOptions::blocking());
+            if watcher.start().is_ok() {
                 let fwd_tx = unified_tx.clone();
-                let agent_name = agent.name.clone();
                 let fwd_handle = thread::spawn(move || {
-                    while let Ok(tracker_event) = tracker_rx.recv() {
-                        let event = match tracker_event {
-                            TrackerEvent::ChildStarted {
-                                pid,
-                                ppid,
-                                name,
-                                risk_level,
-                                ..
-                            } => Event::new(
-                                EventType::Process {
-                                    pid,
-                                    ppid: Some(ppid),
-                                    action: ProcessAction::Start,
-                                },
-                                name,
-                                pid,
-                                risk_level,
-                            ),
-                            TrackerEvent::ChildExited { pid } => Event::new(
-                                EventType::Process {
-                                    pid,
-                                    ppid: None,
-                                    action: ProcessAction::Exit,
-                                },
-                                agent_name.clone(),
-                                pid,
-                                RiskLevel::Low,
-                            ),
-                        };
-                        if fwd_tx.send(event).is_err() {
+                    while let Ok(reload) = reload_rx.recv() {
+                        rules.apply(&reload.config);
+                        invalidate_query_db();
+                        if fwd_tx.send(reload.to_event()).is_err() {
                             break;
                         }
                     }
                 });
                 forwarding_threads.push(fwd_handle);
-                trackers.push(tracker);
-            }
-
-            // NetworkMonitor
-            if config.monitoring.net_enabled {
-                let mut monitor = NetworkMonitor::new(NetMonConfig::new(agent.pid).poll_interval(
-                    std::time::Duration::from_millis(config.monitoring.net_poll_ms),
-                ));
-                let net_rx = monitor.subscribe();
-                if monitor.start().is_ok() {
-                    let fwd_tx = unified_tx.clone();
-                    let fwd_handle = thread::spawn(move || {
-                        while let Ok(event) = net_rx.recv() {
-                            if fwd_tx.send(event).is_err() {
-                                break;
-                            }
-                        }
-                    });
-                    forwarding_threads.push(fwd_handle);
-                    net_monitors.push(monitor);
-                }
+                config_watcher = Some(watcher);
             }
         }
 
-        // 6. FileSystemWatcher
-        let mut fs_watcher = None;
-        if config.monitoring.fs_enabled {
-            let watch_paths = if config.monitoring.watch_paths.is_empty() {
-                if let Some(home) = dirs::home_dir() {
-                    vec![home]
-                } else {
-                    Vec::new()
-                }
-            } else {
-                config.monitoring.watch_paths.clone()
-            };
-
-            if !watch_paths.is_empty() {
-                let mut watcher = FileSystemWatcher::new(FsWatchConfig::new(watch_paths));
-                let fs_rx = watcher.subscribe();
-                if watcher.start().is_ok() {
-                    let fwd_tx = unified_tx.clone();
-                    let fwd_handle = thread::spawn(move || {
-                        while let Ok(event) = fs_rx.recv() {
-                            if fwd_tx.send(event).is_err() {
-                                break;
-                            }
-                        }
-                    });
-                    forwarding_threads.push(fwd_handle);
-                    fs_watcher = Some(watcher);
-                }
+        // 6c. RetentionManager: expire, compress and cap older sessions
+        let compression = retention_policy.compression;
+        let mut retention =
+            RetentionManager::new(retention_policy, log_dir).with_active_session(&session_id);
+        if config.logging.uses_sqlite() {
+            if let Ok(db_path) = config.logging.effective_db_path() {
+                retention = retention.with_db_path(db_path);
             }
         }
+        let retention = match retention.start() {
+            Ok(()) => Some(retention),
+            Err(e) => {
+                eprintln!("[agent-watch] Warning: Failed to start retention manager: {e}");
+                None
+            }
+        };
 
         // 7. Spawn event writer thread
         let logger_clone = Arc::clone(&logger);
+        let db_session_id = session_id.clone();
+        let events = self.events.clone();
         let writer_handle = thread::spawn(move || {
-            while let Ok(event) = unified_rx.recv() {
-                if let Ok(mut l) = logger_clone.lock() {
-                    let _ = l.write_event(&event);
+            let mut sqlite = sqlite;
+            while let Ok(mut event) = unified_rx.recv() {
+                // Every subsystem's events pass through here on the way to
+                // storage, and are published to live subscribers after that
+                sanitize_event(&mut event);
+                if write_jsonl {
+                    if let Ok(mut l) = logger_clone.lock() {
+                        let _ = l.write_event(&event);
+                    }
                 }
+                if let Some(ref mut storage) = sqlite {
+                    let _ = storage.write_event(&event);
+                }
+                events.publish(event);
             }
             // Channel closed, flush
             if let Ok(mut l) = logger_clone.lock() {
                 let _ = l.flush();
             }
+            if let Some(ref storage) = sqlite {
+                let _ = storage.write_session_footer(&db_session_id, &chrono::Utc::now());
+            }
         });
 
         *session = Some(MonitoringSession {
             logger,
-            trackers,
-            fs_watcher,
-            net_monitors,
+            attachments,
+            config_watcher,
+            retention,
+            compression,
             writer_thread: Some(writer_handle),
             detected_agents,
             unified_tx: Some(unified_tx),
@@ -1108,26 +2404,26 @@ impl FfiMonitoringEngine {
 
         if let Some(mut s) = session.take() {
             // 1. Signal all subsystems to stop
-            for tracker in &mut s.trackers {
-                tracker.signal_stop();
+            for attachment in &s.attachments {
+                attachment.signal_stop();
             }
-            if let Some(ref watcher) = s.fs_watcher {
+            if let Some(ref watcher) = s.config_watcher {
                 watcher.signal_stop();
             }
-            for monitor in &s.net_monitors {
-                monitor.signal_stop();
+            if let Some(ref retention) = s.retention {
+                retention.signal_stop();
             }
 
-            // 2. Stop and drop all subsystems. Dropping them closes the
-            //    TrackerEvent / Event senders so forwarding threads unblock.
-            for tracker in s.trackers.drain(..) {
-                drop(tracker);
+            // 2. Stop and drop all subsystems. Dropping them closes their
+            //    event buses so forwarding threads unblock.
+            for attachment in s.attachments.drain(..) {
+                drop(attachment);
             }
-            if let Some(watcher) = s.fs_watcher.take() {
+            if let Some(watcher) = s.config_watcher.take() {
                 drop(watcher);
             }
-            for monitor in s.net_monitors.drain(..) {
-                drop(monitor);
+            if let Some(retention) = s.retention.take() {
+                drop(retention);
             }
 
             // 3. Wait for forwarding threads to finish (they exit once
@@ -1152,6 +2448,16 @@ impl FfiMonitoringEngine {
                         e
                     );
                 }
+
+                // 7. Compress the finished session log
+                let session_path = logger.session_path();
+                forget_session_tail(&session_path.to_string_lossy());
+                if let Err(e) = compress_session(&session_path, s.compression) {
+                    eprintln!(
+                        "[agent-watch] Warning: Failed to compress session log: {}",
+                        e
+                    );
+                }
             }
         }
 
@@ -1202,6 +2508,7 @@ impl FfiMonitoringEngine {
 mod tests {
     use super::*;
     use crate::event::{Event, EventType, FileAction, ProcessAction, RiskLevel, SessionAction};
+    use std::io::Write;
     use std::path::PathBuf;
 
     #[test]
@@ -1244,6 +2551,10 @@ mod tests {
             FfiProcessAction::from(ProcessAction::Fork),
             FfiProcessAction::Fork
         );
+        assert_eq!(
+            FfiProcessAction::from(ProcessAction::Detach),
+            FfiProcessAction::Detach
+        );
     }
 
     #[test]
@@ -1302,6 +2613,7 @@ mod tests {
             host: "example.com".to_string(),
             port: 443,
             protocol: "tcp".to_string(),
+            provider: None,
         };
         let ffi_et: FfiEventType = et.into();
         match ffi_et {
@@ -1309,6 +2621,7 @@ mod tests {
                 host,
                 port,
                 protocol,
+                ..
             } => {
                 assert_eq!(host, "example.com");
                 assert_eq!(port, 443);
@@ -1324,6 +2637,8 @@ mod tests {
             pid: 1234,
             ppid: Some(1),
             action: ProcessAction::Start,
+            exit: None,
+            backend: None,
         };
         let ffi_et: FfiEventType = et.into();
         match ffi_et {
@@ -1350,6 +2665,163 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_event_type_config_reload_conversion() {
+        let et = EventType::ConfigReload {
+            path: std::path::PathBuf::from("/tmp/config.toml"),
+            changes: vec![crate::config::ConfigChange {
+                key: "alerts.min_level".to_string(),
+                old: Some("\"high\"".to_string()),
+                new: Some("\"medium\"".to_string()),
+            }],
+        };
+        let ffi_et: FfiEventType = et.into();
+        match ffi_et {
+            FfiEventType::ConfigReload { path, changes } => {
+                assert_eq!(path, "/tmp/config.toml");
+                assert_eq!(changes.len(), 1);
+                assert_eq!(changes[0].key, "alerts.min_level");
+                assert_eq!(changes[0].new_value.as_deref(), Some("\"medium\""));
+            }
+            _ => panic!("Expected ConfigReload variant"),
+        }
+    }
+
+    #[test]
+    fn test_event_type_resource_conversion() {
+        let et = EventType::Resource {
+            metric: ResourceMetric::SpawnRate,
+            value: 250,
+            limit: 200,
+        };
+        let ffi_et: FfiEventType = et.into();
+        assert_eq!(
+            ffi_et,
+            FfiEventType::Resource {
+                metric: FfiResourceMetric::SpawnRate,
+                value: 250,
+                limit: 200,
+            }
+        );
+    }
+
+    #[test]
+    fn test_event_type_anomaly_conversion() {
+        let et = EventType::Anomaly {
+            kind: AnomalyKind::FileRate,
+            score: 4,
+            explanation: "touched 400 files within a minute".to_string(),
+        };
+        let ffi_et: FfiEventType = et.into();
+        assert_eq!(
+            ffi_et,
+            FfiEventType::Anomaly {
+                kind: FfiAnomalyKind::FileRate,
+                score: 4,
+                explanation: "touched 400 files within a minute".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_event_type_file_summary_conversion() {
+        let et = EventType::FileSummary {
+            path: PathBuf::from("/work/node_modules"),
+            action: FileAction::Create,
+            count: 1204,
+        };
+        let ffi_et: FfiEventType = et.into();
+        assert_eq!(
+            ffi_et,
+            FfiEventType::FileSummary {
+                path: "/work/node_modules".to_string(),
+                action: FfiFileAction::Create,
+                count: 1204,
+            }
+        );
+    }
+
+    #[test]
+    fn test_event_type_network_close_conversion() {
+        let et = EventType::NetworkClose {
+            host: "203.0.113.9".to_string(),
+            port: 443,
+            protocol: "tcp".to_string(),
+            duration_ms: 1500,
+            bytes_sent: Some(2048),
+            bytes_received: None,
+            provider: None,
+        };
+        let ffi_et: FfiEventType = et.into();
+        assert_eq!(
+            ffi_et,
+            FfiEventType::NetworkClose {
+                host: "203.0.113.9".to_string(),
+                port: 443,
+                protocol: "tcp".to_string(),
+                duration_ms: 1500,
+                bytes_sent: Some(2048),
+                bytes_received: None,
+                provider: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_event_type_listen_and_reverse_shell_conversion() {
+        let et = EventType::Listen {
+            address: "0.0.0.0".to_string(),
+            port: 4444,
+            protocol: "tcp".to_string(),
+        };
+        let ffi_et: FfiEventType = et.into();
+        assert_eq!(
+            ffi_et,
+            FfiEventType::Listen {
+                address: "0.0.0.0".to_string(),
+                port: 4444,
+                protocol: "tcp".to_string(),
+            }
+        );
+
+        let et = EventType::ReverseShell {
+            host: "10.0.0.5".to_string(),
+            port: 9001,
+            protocol: "tcp".to_string(),
+        };
+        let ffi_et: FfiEventType = et.into();
+        assert_eq!(
+            ffi_et,
+            FfiEventType::ReverseShell {
+                host: "10.0.0.5".to_string(),
+                port: 9001,
+                protocol: "tcp".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_event_type_proxy_request_conversion() {
+        let et = EventType::ProxyRequest {
+            method: "CONNECT".to_string(),
+            host: "github.com".to_string(),
+            port: 443,
+            blocked: false,
+            provider: None,
+        };
+        let ffi_et: FfiEventType = et.into();
+        assert_eq!(
+            ffi_et,
+            FfiEventType::ProxyRequest {
+                method: "CONNECT".to_string(),
+                host: "github.com".to_string(),
+                port: 443,
+                blocked: false,
+                provider: None,
+            }
+        );
+    }
+
     #[test]
     fn test_event_conversion() {
         let event = Event::command(
@@ -1495,6 +2967,61 @@ mod tests {
         assert_eq!(summary.high_count, 0);
         assert_eq!(summary.medium_count, 0);
         assert_eq!(summary.low_count, 0);
+        assert!(summary.traffic.is_empty());
+    }
+
+    #[test]
+    fn test_get_activity_summary_traffic() {
+        let network = |host: &str| {
+            Event::new(
+                EventType::Network {
+                    host: host.to_string(),
+                    port: 443,
+                    protocol: "tcp".to_string(),
+                    provider: None,
+                },
+                "claude".to_string(),
+                1,
+                RiskLevel::Medium,
+            )
+        };
+        let close = Event::new(
+            EventType::NetworkClose {
+                host: "api.anthropic.com".to_string(),
+                port: 443,
+                protocol: "tcp".to_string(),
+                duration_ms: 1500,
+                bytes_sent: Some(10),
+                bytes_received: None,
+                provider: None,
+            },
+            "claude".to_string(),
+            1,
+            RiskLevel::Low,
+        );
+        // Two sockets to the same endpoint: one open event, two closes
+        let events: Vec<FfiEvent> = [
+            network("api.anthropic.com"),
+            close.clone(),
+            close,
+            network("github.com"),
+        ]
+        .into_iter()
+        .map(FfiEvent::from)
+        .collect();
+
+        let summary = get_activity_summary(events).unwrap();
+        assert_eq!(summary.traffic.len(), 2);
+        let llm = &summary.traffic[0];
+        assert_eq!(llm.category, FfiProviderCategory::Llm);
+        assert_eq!(llm.requests, 2);
+        assert_eq!(llm.closed, 2);
+        assert_eq!(llm.duration_ms, 3000);
+        assert_eq!(llm.bytes_sent, 20);
+        assert!(llm.first_ms.is_some() && llm.first_ms <= llm.last_ms);
+        assert_eq!(llm.providers[0].name, "Anthropic");
+        assert_eq!(llm.providers[0].requests, 2);
+        assert_eq!(summary.traffic[1].category, FfiProviderCategory::Vcs);
     }
 
     #[test]
@@ -1933,6 +3460,224 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_get_latest_events_after_append() {
+        let events = sample_events();
+        let (_dir, path) = create_test_session_file(&events[..3]);
+
+        assert_eq!(get_latest_events(path.clone(), 0).unwrap().len(), 3);
+        assert_eq!(get_session_event_count(path.clone()).unwrap(), 3);
+
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+        for event in &events[3..] {
+            writeln!(file, "{}", serde_json::to_string(event).unwrap()).unwrap();
+        }
+
+        let results = get_latest_events(path.clone(), 3).unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].risk_level, FfiRiskLevel::Critical);
+        assert_eq!(get_session_event_count(path.clone()).unwrap(), 5);
+        assert_eq!(get_latest_events(path, 1).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_get_latest_events_after_truncation() {
+        let events = sample_events();
+        let (_dir, path) = create_test_session_file(&events);
+        assert_eq!(get_session_event_count(path.clone()).unwrap(), 5);
+
+        let line = serde_json::to_string(&events[0]).unwrap();
+        std::fs::write(&path, format!("{}\n", line)).unwrap();
+
+        assert_eq!(get_session_event_count(path.clone()).unwrap(), 1);
+        assert_eq!(get_latest_events(path, 0).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_session_tails_evict_least_recently_used() {
+        let mut tails = SessionTails::default();
+        let first = tails.get("session-0.jsonl");
+        for i in 1..MAX_SESSION_TAILS {
+            tails.get(&format!("session-{}.jsonl", i));
+        }
+        assert!(Arc::ptr_eq(&first, &tails.get("session-0.jsonl")));
+
+        tails.get("session-new.jsonl");
+        assert_eq!(tails.entries.len(), MAX_SESSION_TAILS);
+        assert!(tails.entries.contains_key("session-0.jsonl"));
+        assert!(!tails.entries.contains_key("session-1.jsonl"));
+
+        let stale = Arc::new(Mutex::new(SessionTail::new("session-0.jsonl")));
+        tails.remove("session-0.jsonl", &stale);
+        assert!(tails.entries.contains_key("session-0.jsonl"));
+        tails.remove("session-0.jsonl", &first);
+        assert!(!tails.entries.contains_key("session-0.jsonl"));
+    }
+
+    #[derive(Default)]
+    struct CollectingListener {
+        events: Mutex<Vec<FfiEvent>>,
+        resets: std::sync::atomic::AtomicUsize,
+    }
+
+    impl FfiSessionListener for CollectingListener {
+        fn on_events(&self, events: Vec<FfiEvent>) {
+            self.events.lock().unwrap().extend(events);
+        }
+
+        fn on_reset(&self) {
+            self.resets.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fn on_error(&self, _message: String) {}
+    }
+
+    fn wait_for(condition: impl Fn() -> bool) -> bool {
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while std::time::Instant::now() < deadline {
+            if condition() {
+                return true;
+            }
+            thread::sleep(std::time::Duration::from_millis(20));
+        }
+        false
+    }
+
+    #[test]
+    fn test_session_subscription_pushes_appended_events() {
+        let events = sample_events();
+        let (_dir, path) = create_test_session_file(&events[..2]);
+
+        let listener = Arc::new(CollectingListener::default());
+        let subscription = FfiSessionSubscription::new(path.clone(), 1, listener.clone());
+        assert!(wait_for(|| listener.events.lock().unwrap().len() == 1));
+        assert!(subscription.is_active());
+
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+        for event in &events[2..] {
+            writeln!(file, "{}", serde_json::to_string(event).unwrap()).unwrap();
+        }
+        assert!(wait_for(|| listener.events.lock().unwrap().len() == 4));
+
+        subscription.cancel();
+        assert!(!subscription.is_active());
+        assert_eq!(listener.resets.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn test_session_subscription_reports_reset() {
+        let events = sample_events();
+        let (_dir, path) = create_test_session_file(&events);
+
+        let listener = Arc::new(CollectingListener::default());
+        let subscription = FfiSessionSubscription::new(path.clone(), 5, listener.clone());
+        thread::sleep(SUBSCRIPTION_POLL_INTERVAL);
+
+        let line = serde_json::to_string(&events[0]).unwrap();
+        std::fs::write(&path, format!("{}\n", line)).unwrap();
+        assert!(wait_for(|| listener.resets.load(Ordering::Relaxed) == 1));
+        assert!(wait_for(|| listener.events.lock().unwrap().len() == 1));
+
+        drop(subscription);
+    }
+
+    /// Drops the subscription it was handed from its first callback
+    #[derive(Default)]
+    struct DroppingListener {
+        subscription: Mutex<Option<Arc<FfiSessionSubscription>>>,
+        calls: std::sync::atomic::AtomicUsize,
+        dropped: AtomicBool,
+    }
+
+    impl FfiSessionListener for DroppingListener {
+        fn on_events(&self, _events: Vec<FfiEvent>) {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            drop(self.subscription.lock().unwrap().take());
+            self.dropped.store(true, Ordering::Relaxed);
+        }
+
+        fn on_reset(&self) {}
+
+        fn on_error(&self, _message: String) {}
+    }
+
+    #[test]
+    fn test_session_subscription_dropped_from_its_callback() {
+        let events = sample_events();
+        let (_dir, path) = create_test_session_file(&events);
+
+        let listener = Arc::new(DroppingListener::default());
+        // Hold the listener's lock so the last reference is the one it takes
+        let mut slot = listener.subscription.lock().unwrap();
+        let subscription = Arc::new(FfiSessionSubscription::new(
+            path.clone(),
+            0,
+            listener.clone(),
+        ));
+        *slot = Some(Arc::clone(&subscription));
+        drop(slot);
+        drop(subscription);
+
+        // Cancelled from the worker itself rather than joining it
+        assert!(wait_for(|| listener.dropped.load(Ordering::Relaxed)));
+        let line = serde_json::to_string(&events[0]).unwrap();
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+        writeln!(file, "{}", line).unwrap();
+        thread::sleep(SUBSCRIPTION_POLL_INTERVAL * 2);
+        assert_eq!(listener.calls.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_engine_subscriptions_filter_independently() {
+        let engine = FfiMonitoringEngine::new();
+        let all = Arc::new(CollectingListener::default());
+        let risky = Arc::new(CollectingListener::default());
+        let _all_sub = engine.subscribe(
+            FfiSubscribeOptions {
+                event_types: Vec::new(),
+                min_risk_level: None,
+                capacity: 64,
+            },
+            all.clone(),
+        );
+        let risky_sub = engine.subscribe(
+            FfiSubscribeOptions {
+                event_types: vec!["command".to_string()],
+                min_risk_level: Some(FfiRiskLevel::Critical),
+                capacity: 64,
+            },
+            risky.clone(),
+        );
+
+        for event in sample_events() {
+            engine.events.publish(event);
+        }
+        let total = sample_events().len();
+        assert!(wait_for(|| all.events.lock().unwrap().len() == total));
+        assert!(wait_for(|| risky.events.lock().unwrap().len() == 1));
+        assert_eq!(
+            risky.events.lock().unwrap()[0].risk_level,
+            FfiRiskLevel::Critical
+        );
+
+        // Cancelling one subscription leaves the other connected
+        risky_sub.cancel();
+        assert!(!risky_sub.is_active());
+        engine
+            .events
+            .publish(Event::session_end("test".to_string(), 1));
+        assert!(wait_for(|| all.events.lock().unwrap().len() == total + 1));
+    }
+
     // ─── Tests for v0.5.0 FFI: notification config and save_config ────────────
 
     #[test]
@@ -2371,4 +4116,197 @@ mod tests {
         assert!(!content.contains("agent_name"));
         assert!(content.contains("\"type\":\"session_start\""));
     }
+
+    #[test]
+    fn test_session_id_from_path() {
+        assert_eq!(
+            session_id_from_path("/tmp/logs/session-abc-123.jsonl").as_deref(),
+            Some("abc-123")
+        );
+        assert_eq!(session_id_from_path("/tmp/logs/test-session.jsonl"), None);
+        assert_eq!(session_id_from_path("/tmp/logs/session-abc.log"), None);
+        assert_eq!(
+            session_id_from_path("/tmp/logs/session-abc.2.jsonl.zst").as_deref(),
+            Some("abc")
+        );
+    }
+
+    #[test]
+    fn test_read_compressed_segmented_session() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let log_dir = temp_dir.path().to_path_buf();
+        let mut logger = SessionLogger::new(&log_dir, Some("packed".to_string()))
+            .unwrap()
+            .with_max_segment_bytes(512);
+        logger
+            .write_session_header("bash", 1, Some("Claude Code"))
+            .unwrap();
+        for i in 0..10 {
+            let event = Event::command(
+                format!("cmd{}", i),
+                vec![],
+                "bash".to_string(),
+                1,
+                if i == 7 {
+                    RiskLevel::High
+                } else {
+                    RiskLevel::Low
+                },
+            );
+            logger.write_event(&event).unwrap();
+        }
+        logger.write_session_footer(Some(0)).unwrap();
+        assert!(logger.segment_index() > 0);
+        let path = logger.session_path();
+        drop(logger);
+
+        let compressed = crate::retention::compress_session(&path, Compression::Gzip).unwrap();
+        assert!(compressed > 1);
+        assert!(!path.exists());
+        let path = path.to_string_lossy().to_string();
+
+        assert_eq!(read_session_log(path.clone()).unwrap().len(), 10);
+        assert_eq!(get_session_event_count(path.clone()).unwrap(), 10);
+        assert_eq!(get_latest_events(path.clone(), 8).unwrap().len(), 2);
+        let (agent_name, max_risk) = extract_session_metadata(&path);
+        assert_eq!(agent_name.as_deref(), Some("Claude Code"));
+        assert_eq!(max_risk, FfiRiskLevel::High);
+    }
+
+    #[test]
+    fn test_query_db_cache_reuses_open_database() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let db_path = dir.path().join("events.db");
+        let mut cache = QueryDbCache::default();
+
+        assert!(cache.get(|| Some(db_path.clone())).is_none());
+        SqliteStorage::new(&db_path).unwrap();
+        let first = cache.get(|| unreachable!()).unwrap();
+        let second = cache.get(|| unreachable!()).unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+
+        let mut jsonl_only = QueryDbCache::default();
+        assert!(jsonl_only.get(|| None).is_none());
+        assert!(jsonl_only.get(|| Some(db_path.clone())).is_none());
+    }
+
+    #[test]
+    fn test_sqlite_risk_summary_and_chart_data() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("events.db");
+        let mut storage = SqliteStorage::new(&db_path).unwrap().with_session_id("s1");
+        for level in [RiskLevel::Low, RiskLevel::High, RiskLevel::High] {
+            storage
+                .write_event(&Event::command(
+                    "ls".to_string(),
+                    vec![],
+                    "bash".to_string(),
+                    1,
+                    level,
+                ))
+                .unwrap();
+        }
+        let mut other = SqliteStorage::new(&db_path).unwrap().with_session_id("s2");
+        other
+            .write_event(&Event::command(
+                "rm".to_string(),
+                vec![],
+                "bash".to_string(),
+                1,
+                RiskLevel::Critical,
+            ))
+            .unwrap();
+
+        let summary = sqlite_risk_summary(&storage, "s1").unwrap();
+        assert_eq!(summary.total_events, 3);
+        assert_eq!(summary.high_count, 2);
+        assert_eq!(summary.low_count, 1);
+        assert_eq!(summary.critical_count, 0);
+        assert_eq!(max_risk_of(&summary), FfiRiskLevel::High);
+
+        let points = sqlite_chart_data(&storage, "s1", 60_000).unwrap();
+        assert_eq!(points.iter().map(|p| p.total).sum::<u32>(), 3);
+        assert_eq!(
+            sqlite_risk_summary(&storage, "s2").unwrap().critical_count,
+            1
+        );
+    }
+
+    #[test]
+    fn test_sqlite_risk_summary_traffic() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("events.db");
+        let mut storage = SqliteStorage::new(&db_path).unwrap().with_session_id("s1");
+        for (method, host) in [("CONNECT", "api.openai.com"), ("GET", "pypi.org")] {
+            storage
+                .write_event(&Event::new(
+                    EventType::ProxyRequest {
+                        method: method.to_string(),
+                        host: host.to_string(),
+                        port: 443,
+                        blocked: false,
+                        provider: None,
+                    },
+                    "codex".to_string(),
+                    1,
+                    RiskLevel::Medium,
+                ))
+                .unwrap();
+        }
+
+        let summary = sqlite_risk_summary(&storage, "s1").unwrap();
+        let categories: Vec<(FfiProviderCategory, u64)> = summary
+            .traffic
+            .iter()
+            .map(|t| (t.category, t.requests))
+            .collect();
+        assert_eq!(
+            categories,
+            vec![
+                (FfiProviderCategory::Llm, 1),
+                (FfiProviderCategory::PackageRegistry, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_risk_level_roundtrip() {
+        for level in [
+            RiskLevel::Low,
+            RiskLevel::Medium,
+            RiskLevel::High,
+            RiskLevel::Critical,
+        ] {
+            assert_eq!(RiskLevel::from(FfiRiskLevel::from(level)), level);
+        }
+    }
+
+    #[test]
+    fn test_event_filter_to_query() {
+        let query = EventQuery::from(FfiEventFilter {
+            agent: Some("Cursor".to_string()),
+            text: Some(String::new()),
+            event_type: Some("unknown".to_string()),
+            risk_level: Some(FfiRiskLevel::High),
+            start_time_ms: Some(1_700_000_000_000),
+            end_time_ms: None,
+            limit: Some(10),
+        });
+        assert_eq!(query.agent.as_deref(), Some("Cursor"));
+        assert_eq!(query.text, None);
+        assert_eq!(query.event_type, None);
+        assert_eq!(query.risk_level, Some(RiskLevel::High));
+        assert_eq!(
+            query.start_time.map(|t| t.timestamp_millis()),
+            Some(1_700_000_000_000)
+        );
+        assert_eq!(query.limit, Some(10));
+        assert_eq!(query.session_id, None);
+
+        let query = EventQuery::from(FfiEventFilter {
+            event_type: Some("network".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(query.event_type.as_deref(), Some("network"));
+    }
 }
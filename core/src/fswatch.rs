@@ -2,16 +2,27 @@
 //!
 //! Uses macOS FSEvents API to monitor file system changes.
 //! Detects file access patterns and integrates with sensitive file detection.
+//! Low-risk events pass through an [`EventAggregator`] before they are sent,
+//! so bursts of them arrive as summaries.
 
+use crate::aggregate::AggregationConfig;
+#[cfg(target_os = "macos")]
+use crate::aggregate::EventAggregator;
+use crate::bus::{EventBus, SubscribeOptions};
 use crate::detector::{Detector, SensitiveFileDetector};
 use crate::error::CoreError;
 use crate::event::{Event, EventType, FileAction, RiskLevel};
+use crate::types::Swappable;
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::mpsc::{channel, Receiver, Sender};
+#[cfg(target_os = "macos")]
+use std::sync::mpsc::channel;
+use std::sync::mpsc::Receiver;
 use std::sync::Arc;
 use std::thread::{self, JoinHandle};
 use std::time::Duration;
+#[cfg(target_os = "macos")]
+use std::time::Instant;
 
 /// File system watcher configuration
 #[derive(Debug, Clone)]
@@ -20,6 +31,8 @@ pub struct FsWatchConfig {
     pub watch_paths: Vec<PathBuf>,
     /// Latency for FSEvents (how long to coalesce events)
     pub latency: Duration,
+    /// Debouncing, ignore globs and burst summaries for low-risk events
+    pub aggregation: AggregationConfig,
 }
 
 impl Default for FsWatchConfig {
@@ -27,6 +40,7 @@ impl Default for FsWatchConfig {
         Self {
             watch_paths: Vec::new(),
             latency: Duration::from_millis(100),
+            aggregation: AggregationConfig::default(),
         }
     }
 }
@@ -46,6 +60,12 @@ impl FsWatchConfig {
         self
     }
 
+    /// Set how low-risk events are aggregated
+    pub fn aggregation(mut self, aggregation: AggregationConfig) -> Self {
+        self.aggregation = aggregation;
+        self
+    }
+
     /// Add a watch path
     pub fn add_path(mut self, path: PathBuf) -> Self {
         self.watch_paths.push(path);
@@ -78,8 +98,8 @@ impl FsEvent {
 /// File system watcher using macOS FSEvents
 pub struct FileSystemWatcher {
     config: FsWatchConfig,
-    detector: SensitiveFileDetector,
-    event_tx: Option<Sender<Event>>,
+    detector: Swappable<SensitiveFileDetector>,
+    events: EventBus<Event>,
     stop_flag: Arc<AtomicBool>,
     watch_thread: Option<JoinHandle<()>>,
 }
@@ -89,8 +109,8 @@ impl FileSystemWatcher {
     pub fn new(config: FsWatchConfig) -> Self {
         Self {
             config,
-            detector: SensitiveFileDetector::default(),
-            event_tx: None,
+            detector: Swappable::default(),
+            events: EventBus::new(),
             stop_flag: Arc::new(AtomicBool::new(false)),
             watch_thread: None,
         }
@@ -98,15 +118,29 @@ impl FileSystemWatcher {
 
     /// Create with a custom detector
     pub fn with_detector(mut self, detector: SensitiveFileDetector) -> Self {
+        self.detector = Swappable::new(detector);
+        self
+    }
+
+    /// Share a detector handle that can be swapped while the watcher runs
+    pub fn with_shared_detector(mut self, detector: Swappable<SensitiveFileDetector>) -> Self {
         self.detector = detector;
         self
     }
 
+    /// Replace the detector used to classify subsequent file events
+    pub fn set_detector(&self, detector: SensitiveFileDetector) {
+        self.detector.store(detector);
+    }
+
     /// Subscribe to file system events
-    pub fn subscribe(&mut self) -> Receiver<Event> {
-        let (tx, rx) = channel();
-        self.event_tx = Some(tx);
-        rx
+    pub fn subscribe(&self) -> Receiver<Event> {
+        self.events.subscribe()
+    }
+
+    /// Subscribe with a custom buffer, lag policy and filter
+    pub fn subscribe_with(&self, options: SubscribeOptions) -> Receiver<Event> {
+        self.events.subscribe_with(options)
     }
 
     /// Check if watcher is running
@@ -131,13 +165,14 @@ impl FileSystemWatcher {
             .collect();
 
         let latency_secs = self.config.latency.as_secs_f64();
-        let event_tx = self.event_tx.clone();
+        let aggregator = EventAggregator::new(self.config.aggregation.clone());
+        let events = self.events.clone();
         let detector = self.detector.clone();
         let stop_flag = self.stop_flag.clone();
 
         // Spawn a thread that owns the FsEvent
         let handle = thread::spawn(move || {
-            Self::watch_thread(paths, latency_secs, event_tx, detector, stop_flag);
+            Self::watch_thread(paths, latency_secs, aggregator, events, detector, stop_flag);
         });
 
         self.watch_thread = Some(handle);
@@ -155,8 +190,9 @@ impl FileSystemWatcher {
     fn watch_thread(
         paths: Vec<String>,
         _latency_secs: f64,
-        event_tx: Option<Sender<Event>>,
-        detector: SensitiveFileDetector,
+        mut aggregator: EventAggregator,
+        events: EventBus<Event>,
+        detector: Swappable<SensitiveFileDetector>,
         stop_flag: Arc<AtomicBool>,
     ) {
         // Channel for FSEvents
@@ -170,18 +206,25 @@ impl FileSystemWatcher {
             return;
         }
 
+        let send = |ready: Vec<Event>| {
+            for event in ready {
+                events.publish(event);
+            }
+        };
+
         // Use catch_unwind to ensure FSEvents cleanup even on panic (C6 fix)
         let loop_result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| loop {
             if stop_flag.load(Ordering::Relaxed) {
+                send(aggregator.finish());
                 break;
             }
 
-            match fs_rx.recv_timeout(Duration::from_millis(500)) {
+            match fs_rx.recv_timeout(Duration::from_millis(250)) {
                 Ok(fse) => {
                     let path = PathBuf::from(&fse.path);
                     let action = Self::flags_to_action(fse.flag);
 
-                    let risk_level = if detector.is_sensitive(&path) {
+                    let risk_level = if detector.load().is_sensitive(&path) {
                         RiskLevel::Critical
                     } else {
                         RiskLevel::Low
@@ -197,12 +240,15 @@ impl FileSystemWatcher {
                         risk_level,
                     );
 
-                    if let Some(ref tx) = event_tx {
-                        let _ = tx.send(event);
-                    }
+                    send(aggregator.push(event, Instant::now()));
+                }
+                Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
+                    send(aggregator.flush(Instant::now()));
+                }
+                Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => {
+                    send(aggregator.finish());
+                    break;
                 }
-                Err(std::sync::mpsc::RecvTimeoutError::Timeout) => continue,
-                Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => break,
             }
         }));
 
@@ -222,6 +268,8 @@ impl FileSystemWatcher {
         if let Some(handle) = self.watch_thread.take() {
             let _ = handle.join();
         }
+        // Subscribers see a disconnect once no more events can arrive
+        self.events.close();
     }
 
     /// Signal the watcher to stop without waiting for the thread to finish.
@@ -274,7 +322,7 @@ impl FileSystemWatcher {
 
     /// Create an event from a file system change (for manual/testing use)
     pub fn create_event(&self, path: PathBuf, action: FileAction) -> Event {
-        let risk_level = if self.detector.is_sensitive(&path) {
+        let risk_level = if self.detector.load().is_sensitive(&path) {
             RiskLevel::Critical
         } else {
             RiskLevel::Low
@@ -346,6 +394,7 @@ mod tests {
         let config = FsWatchConfig::default();
         assert!(config.watch_paths.is_empty());
         assert_eq!(config.latency, Duration::from_millis(100));
+        assert_eq!(config.aggregation, AggregationConfig::default());
     }
 
     #[test]
@@ -377,10 +426,11 @@ mod tests {
     #[test]
     fn test_watcher_subscribe() {
         let config = FsWatchConfig::default();
-        let mut watcher = FileSystemWatcher::new(config);
+        let watcher = FileSystemWatcher::new(config);
 
-        let _rx = watcher.subscribe();
-        assert!(watcher.event_tx.is_some());
+        let _first = watcher.subscribe();
+        let _second = watcher.subscribe();
+        assert_eq!(watcher.events.subscriber_count(), 2);
     }
 
     #[test]
@@ -395,6 +445,26 @@ mod tests {
         assert_eq!(event.risk_level, RiskLevel::Critical);
     }
 
+    #[test]
+    fn test_set_detector_swaps_patterns() {
+        let watcher = FileSystemWatcher::new(FsWatchConfig::default())
+            .with_detector(SensitiveFileDetector::new(vec![".custom".to_string()]));
+        let path = PathBuf::from("/tmp/notes.draft");
+
+        assert_eq!(
+            watcher
+                .create_event(path.clone(), FileAction::Read)
+                .risk_level,
+            RiskLevel::Low
+        );
+
+        watcher.set_detector(SensitiveFileDetector::new(vec!["*.draft".to_string()]));
+        assert_eq!(
+            watcher.create_event(path, FileAction::Read).risk_level,
+            RiskLevel::Critical
+        );
+    }
+
     #[test]
     fn test_raw_flags_to_action() {
         assert_eq!(
@@ -483,7 +553,7 @@ mod tests {
     #[test]
     fn test_watcher_drop_stops() {
         let config = FsWatchConfig::new(vec![PathBuf::from("/tmp")]);
-        let mut watcher = FileSystemWatcher::new(config);
+        let watcher = FileSystemWatcher::new(config);
         let _ = watcher.subscribe();
 
         // Watcher should be stopped when dropped
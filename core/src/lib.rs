@@ -22,19 +22,35 @@
 //! ```
 
 pub mod agent_detector;
+pub mod aggregate;
+pub mod attach;
+pub mod baseline;
+pub mod bus;
+pub mod cgroup;
 pub mod config;
 pub mod detector;
 pub mod error;
 pub mod event;
+pub mod execmon;
 pub mod ffi;
 pub mod fswatch;
 pub mod logger;
 pub mod netmon;
 pub mod process_tracker;
+pub mod procevents;
+pub mod proxy;
+pub mod reload;
+pub mod resmon;
+pub mod retention;
 pub mod risk;
 pub mod sanitize;
+pub mod shellhook;
+pub mod snapshot;
+pub mod sockdiag;
 pub mod sqlite_storage;
 pub mod storage;
+pub mod tail;
+pub mod traffic;
 pub mod types;
 pub mod wrapper;
 
@@ -42,25 +58,54 @@ uniffi::setup_scaffolding!();
 
 // Re-export commonly used types
 pub use agent_detector::{AgentDetector, DetectedAgent};
+pub use aggregate::{AggregationConfig, EventAggregator};
+pub use attach::{AttachConfig, AttachTarget, Attachment};
+pub use baseline::{AgentProfile, AnomalyDetector, AnomalyThresholds, SessionActivity};
+pub use bus::{BusEvent, EventBus, EventFilter, LagPolicy, SubscribeOptions};
+pub use cgroup::{EnteredCgroup, TreeCgroup};
 pub use config::{
-    AlertConfig, Config, GeneralConfig, LoggingConfig, MonitoringConfig, NotificationConfig,
-    StorageBackend,
+    AlertConfig, Compression, Config, ConfigChange, ConfigDiagnostic, DiagnosticSeverity,
+    GeneralConfig, LoggingConfig, MonitoringConfig, NotificationConfig, SanitizeConfig,
+    SecretPatternConfig, StorageBackend,
 };
 pub use detector::{
-    default_network_whitelist, default_sensitive_patterns, Detector, NetworkConnection,
-    NetworkWhitelist, SensitiveFileDetector,
+    default_network_whitelist, default_providers, default_sensitive_patterns, Detector,
+    NetworkConnection, NetworkWhitelist, Provider, ProviderCatalogue, ProviderCategory,
+    SensitiveFileDetector,
 };
 pub use error::{ConfigError, CoreError, StorageError};
-pub use event::{Event, EventType};
+pub use event::{Event, EventType, ProviderTag};
+pub use execmon::{ExecEvent, ExecMonConfig, ExecMonitor};
 pub use fswatch::{FileMonitor, FileSystemWatcher, FsEvent, FsWatchConfig};
 pub use logger::{LogDestination, LogFormat, Logger, LoggerConfig};
-pub use netmon::{NetMonConfig, NetworkMonitor, NetworkTracker, TrackedConnection};
+pub use netmon::{
+    NetMonConfig, NetworkMonitor, NetworkTracker, TrackedConnection, TrackedListener, TrackedPids,
+};
 pub use process_tracker::{ProcessTracker, TrackedProcess, TrackerConfig, TrackerEvent};
+pub use proxy::{EgressProxy, ProxyConfig};
+pub use reload::{ConfigReload, ConfigWatchConfig, ConfigWatcher, LiveRules};
+pub use resmon::{ResMonConfig, ResourceLimits, ResourceMonitor, ResourceUsage};
+pub use retention::{RetentionManager, RetentionPolicy, RetentionReport};
 pub use risk::{RiskPattern, RiskRule, RiskScorer};
-pub use sanitize::{sanitize_args, sanitize_command_string};
-pub use sqlite_storage::{EventQuery, SqliteStorage};
-pub use storage::{cleanup_old_logs, CleanupResult, EventStorage, SessionLogger};
-pub use types::{FileAction, MonitoringSubsystem, ProcessAction, RiskLevel, SessionAction};
+pub use sanitize::{
+    redact_secrets, sanitize_args, sanitize_command_string, sanitize_event, sanitize_path,
+    Redaction, Sanitizer, SecretDetector, SecretMatch, SecretRule, StreamRedactor,
+};
+pub use shellhook::{HookRecord, ShellHooks, ShellKind};
+pub use snapshot::{SnapshotConfig, WorkspaceChanges, WorkspaceSnapshot};
+pub use sqlite_storage::{
+    AgentTrend, BackfillResult, ChartBucket, EventQuery, GroupBy, GroupCount, JsonlImport,
+    SessionMatch, SqliteStorage,
+};
+pub use storage::{
+    cleanup_old_logs, open_session_log, CleanupResult, EventStorage, SegmentName, SessionLogger,
+};
+pub use tail::{SessionTail, TailUpdate};
+pub use traffic::{CategoryTraffic, TrafficStats, TrafficSummary};
+pub use types::{
+    AnomalyKind, FileAction, MonitoringSubsystem, ProcessAction, ResourceMetric, RiskLevel,
+    SessionAction, Swappable, TrackerBackend,
+};
 pub use wrapper::{ProcessWrapper, WrapperConfig, WrapperEvent};
 
 /// Library version
@@ -153,11 +153,68 @@ impl Logger {
                 host,
                 port,
                 protocol,
+                ..
             } => self.format_network_details(host, *port, protocol),
-            EventType::Process { pid, ppid, action } => {
-                Self::format_process_details(*pid, ppid, action)
-            }
+            EventType::NetworkClose {
+                host,
+                port,
+                protocol,
+                duration_ms,
+                bytes_sent,
+                bytes_received,
+                ..
+            } => self.format_network_close_details(
+                host,
+                *port,
+                protocol,
+                *duration_ms,
+                *bytes_sent,
+                *bytes_received,
+                event.risk_level,
+            ),
+            EventType::Process {
+                pid,
+                ppid,
+                action,
+                exit,
+                backend,
+            } => Self::format_process_details(*pid, ppid, action, exit.as_ref(), *backend),
             EventType::Session { action } => self.format_session_details(action),
+            EventType::ConfigReload { path, changes } => {
+                self.format_config_reload_details(path, changes)
+            }
+            EventType::Resource {
+                metric,
+                value,
+                limit,
+            } => self.format_resource_details(*metric, *value, *limit),
+            EventType::Anomaly {
+                kind,
+                score,
+                explanation,
+            } => self.format_anomaly_details(*kind, *score, explanation, event.risk_level),
+            EventType::FileSummary {
+                path,
+                action,
+                count,
+            } => self.format_file_summary_details(path, action, *count),
+            EventType::Listen {
+                address,
+                port,
+                protocol,
+            } => self.format_listen_details(address, *port, protocol),
+            EventType::ReverseShell {
+                host,
+                port,
+                protocol,
+            } => self.format_reverse_shell_details(host, *port, protocol),
+            EventType::ProxyRequest {
+                method,
+                host,
+                port,
+                blocked,
+                ..
+            } => self.format_proxy_request_details(method, host, *port, *blocked),
         }
     }
 
@@ -212,13 +269,107 @@ impl Logger {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
+    fn format_network_close_details(
+        &self,
+        host: &str,
+        port: u16,
+        protocol: &str,
+        duration_ms: u64,
+        bytes_sent: Option<u64>,
+        bytes_received: Option<u64>,
+        risk_level: RiskLevel,
+    ) -> String {
+        let mut msg = format!(
+            "[net] closed {}:{} ({}) after {:.2}s",
+            host,
+            port,
+            protocol,
+            duration_ms as f64 / 1000.0
+        );
+        if let Some(sent) = bytes_sent {
+            msg.push_str(&format!(" sent:{}", format_bytes(sent)));
+        }
+        if let Some(received) = bytes_received {
+            msg.push_str(&format!(" recv:{}", format_bytes(received)));
+        }
+        if !self.config.use_colors {
+            return msg;
+        }
+        match risk_level {
+            RiskLevel::Critical => msg.red().bold().to_string(),
+            _ => msg.blue().to_string(),
+        }
+    }
+
+    fn format_listen_details(&self, address: &str, port: u16, protocol: &str) -> String {
+        let msg = format!("[listen] {}:{} ({})", address, port, protocol);
+        if self.config.use_colors {
+            msg.red().bold().to_string()
+        } else {
+            msg
+        }
+    }
+
+    fn format_reverse_shell_details(&self, host: &str, port: u16, protocol: &str) -> String {
+        let msg = format!("[revshell] stdio -> {}:{} ({})", host, port, protocol);
+        if self.config.use_colors {
+            msg.red().bold().to_string()
+        } else {
+            msg
+        }
+    }
+
+    fn format_proxy_request_details(
+        &self,
+        method: &str,
+        host: &str,
+        port: u16,
+        blocked: bool,
+    ) -> String {
+        let msg = format!(
+            "[proxy] {} {}:{}{}",
+            method,
+            host,
+            port,
+            if blocked { " (blocked)" } else { "" }
+        );
+        if !self.config.use_colors {
+            msg
+        } else if blocked {
+            msg.red().bold().to_string()
+        } else {
+            msg.blue().to_string()
+        }
+    }
+
     fn format_process_details(
         pid: u32,
         ppid: &Option<u32>,
         action: &crate::event::ProcessAction,
+        exit: Option<&crate::event::ExitStats>,
+        backend: Option<crate::event::TrackerBackend>,
     ) -> String {
         let ppid_str = ppid.map(|p| format!(" ppid:{}", p)).unwrap_or_default();
-        format!("[proc] {:?} pid:{}{}", action, pid, ppid_str)
+        let mut msg = format!("[proc] {:?} pid:{}{}", action, pid, ppid_str);
+        if let Some(exit) = exit {
+            if let Some(code) = exit.exit_code {
+                msg.push_str(&format!(" code:{}", code));
+            }
+            if let Some(ms) = exit.duration_ms {
+                msg.push_str(&format!(" took:{:.2}s", ms as f64 / 1000.0));
+            }
+            if let Some(ms) = exit.cpu_time_ms {
+                msg.push_str(&format!(" cpu:{:.2}s", ms as f64 / 1000.0));
+            }
+            if let Some(bytes) = exit.peak_rss_bytes {
+                msg.push_str(&format!(" rss:{:.1}MB", bytes as f64 / (1024.0 * 1024.0)));
+            }
+        }
+        if let Some(backend) = backend {
+            msg.push_str(&format!(" via:{}", backend));
+        }
+        msg
     }
 
     fn format_session_details(&self, action: &crate::event::SessionAction) -> String {
@@ -230,6 +381,70 @@ impl Logger {
         }
     }
 
+    fn format_config_reload_details(
+        &self,
+        path: &std::path::Path,
+        changes: &[crate::config::ConfigChange],
+    ) -> String {
+        let keys: Vec<&str> = changes.iter().map(|c| c.key.as_str()).collect();
+        let msg = format!("[config] reloaded {} ({})", path.display(), keys.join(", "));
+        if self.config.use_colors {
+            msg.magenta().to_string()
+        } else {
+            msg
+        }
+    }
+
+    fn format_resource_details(
+        &self,
+        metric: crate::event::ResourceMetric,
+        value: u64,
+        limit: u64,
+    ) -> String {
+        let msg = format!(
+            "[resource] {} {} (limit {})",
+            metric,
+            metric.format_value(value),
+            metric.format_value(limit)
+        );
+        if self.config.use_colors {
+            msg.red().to_string()
+        } else {
+            msg
+        }
+    }
+
+    fn format_anomaly_details(
+        &self,
+        kind: crate::event::AnomalyKind,
+        score: u32,
+        explanation: &str,
+        risk_level: RiskLevel,
+    ) -> String {
+        let msg = format!("[anomaly] {} (score {}): {}", kind, score, explanation);
+        if !self.config.use_colors {
+            return msg;
+        }
+        match risk_level {
+            RiskLevel::High | RiskLevel::Critical => msg.red().to_string(),
+            _ => msg.yellow().to_string(),
+        }
+    }
+
+    fn format_file_summary_details(
+        &self,
+        path: &std::path::Path,
+        action: &crate::event::FileAction,
+        count: u64,
+    ) -> String {
+        let msg = format!("[{}] {} files under {}/", action, count, path.display());
+        if self.config.use_colors {
+            msg.dimmed().to_string()
+        } else {
+            msg
+        }
+    }
+
     fn format_json(&self, event: &Event) -> String {
         serde_json::to_string(event).unwrap_or_else(|e| {
             eprintln!(
@@ -263,18 +478,71 @@ impl Logger {
             EventType::Network { host, port, .. } => {
                 format!("net:{}:{}", host, port)
             }
+            EventType::NetworkClose {
+                host,
+                port,
+                bytes_sent,
+                ..
+            } => match bytes_sent {
+                Some(sent) => format!("net:close:{}:{}:{}", host, port, sent),
+                None => format!("net:close:{}:{}", host, port),
+            },
             EventType::Process { pid, action, .. } => {
                 format!("proc:{:?}:{}", action, pid)
             }
             EventType::Session { action } => {
                 format!("session:{:?}", action)
             }
+            EventType::ConfigReload { changes, .. } => {
+                format!("config:reload:{}", changes.len())
+            }
+            EventType::Resource { metric, value, .. } => {
+                format!("res:{}:{}", metric, value)
+            }
+            EventType::Anomaly { kind, score, .. } => {
+                format!("anomaly:{}:{}", kind, score)
+            }
+            EventType::FileSummary {
+                path,
+                action,
+                count,
+            } => {
+                format!("{}:{}:{}", action, count, path.display())
+            }
+            EventType::Listen { address, port, .. } => {
+                format!("listen:{}:{}", address, port)
+            }
+            EventType::ReverseShell { host, port, .. } => {
+                format!("revshell:{}:{}", host, port)
+            }
+            EventType::ProxyRequest {
+                host,
+                port,
+                blocked,
+                ..
+            } => {
+                let outcome = if *blocked { "block" } else { "pass" };
+                format!("proxy:{}:{}:{}", outcome, host, port)
+            }
         };
 
         format!("{} [{}] {}", time, level, details)
     }
 }
 
+/// Byte count in the largest unit that keeps it at or above 1
+fn format_bytes(bytes: u64) -> String {
+    const KB: f64 = 1024.0;
+    let value = bytes as f64;
+    if value < KB {
+        format!("{}B", bytes)
+    } else if value < KB * KB {
+        format!("{:.1}KB", value / KB)
+    } else {
+        format!("{:.1}MB", value / (KB * KB))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -452,6 +720,7 @@ mod tests {
                 host: "api.anthropic.com".to_string(),
                 port: 443,
                 protocol: "tcp".to_string(),
+                provider: None,
             },
             "curl".to_string(),
             9999,
@@ -463,6 +732,191 @@ mod tests {
         assert!(output.contains("api.anthropic.com:443"));
     }
 
+    #[test]
+    fn test_network_close_format() {
+        let config = LoggerConfig {
+            format: LogFormat::Pretty,
+            use_colors: false,
+            ..Default::default()
+        };
+        let logger = Logger::new(config);
+
+        let event = Event::new(
+            EventType::NetworkClose {
+                host: "203.0.113.9".to_string(),
+                port: 443,
+                protocol: "tcp".to_string(),
+                duration_ms: 2500,
+                bytes_sent: Some(3 * 1024 * 1024),
+                bytes_received: Some(512),
+                provider: None,
+            },
+            "curl".to_string(),
+            9999,
+            RiskLevel::Medium,
+        );
+        let output = logger.format(&event);
+        assert!(
+            output.contains("[net] closed 203.0.113.9:443 (tcp) after 2.50s sent:3.0MB recv:512B")
+        );
+    }
+
+    #[test]
+    fn test_listen_and_reverse_shell_format() {
+        let config = LoggerConfig {
+            format: LogFormat::Pretty,
+            use_colors: false,
+            ..Default::default()
+        };
+        let logger = Logger::new(config);
+
+        let listen = Event::listen(
+            "python3".to_string(),
+            4321,
+            "0.0.0.0".to_string(),
+            8000,
+            "tcp".to_string(),
+        );
+        assert!(logger
+            .format(&listen)
+            .contains("[listen] 0.0.0.0:8000 (tcp)"));
+
+        let shell = Event::reverse_shell(
+            "bash".to_string(),
+            4322,
+            "10.0.0.5".to_string(),
+            9001,
+            "tcp".to_string(),
+        );
+        assert!(logger
+            .format(&shell)
+            .contains("[revshell] stdio -> 10.0.0.5:9001 (tcp)"));
+    }
+
+    #[test]
+    fn test_proxy_request_format() {
+        let config = LoggerConfig {
+            format: LogFormat::Pretty,
+            use_colors: false,
+            ..Default::default()
+        };
+        let logger = Logger::new(config);
+
+        let event = Event::new(
+            EventType::ProxyRequest {
+                method: "CONNECT".to_string(),
+                host: "pastebin.com".to_string(),
+                port: 443,
+                blocked: true,
+                provider: None,
+            },
+            "node".to_string(),
+            4321,
+            RiskLevel::High,
+        );
+        assert!(logger
+            .format(&event)
+            .contains("[proxy] CONNECT pastebin.com:443 (blocked)"));
+    }
+
+    #[test]
+    fn test_process_exit_format() {
+        let config = LoggerConfig {
+            format: LogFormat::Pretty,
+            use_colors: false,
+            ..Default::default()
+        };
+        let logger = Logger::new(config);
+
+        let exit = crate::event::ExitStats {
+            exit_code: Some(1),
+            duration_ms: Some(2500),
+            cpu_time_ms: Some(120),
+            peak_rss_bytes: Some(48 * 1024 * 1024),
+            command_id: None,
+        };
+        let event = Event::process_exit("npm".to_string(), 4321, Some(1), exit)
+            .with_backend(crate::event::TrackerBackend::ProcConnector);
+        let output = logger.format(&event);
+        assert!(output.contains("code:1 took:2.50s cpu:0.12s rss:48.0MB via:proc_connector"));
+    }
+
+    #[test]
+    fn test_resource_format() {
+        let config = LoggerConfig {
+            format: LogFormat::Pretty,
+            use_colors: false,
+            ..Default::default()
+        };
+        let logger = Logger::new(config);
+
+        let event = Event::resource(
+            "claude".to_string(),
+            4321,
+            crate::event::ResourceMetric::Memory,
+            3 * 1024 * 1024 * 1024,
+            2 * 1024 * 1024 * 1024,
+            RiskLevel::High,
+        );
+        let output = logger.format(&event);
+        assert!(output.contains("[resource] memory 3072.0MB (limit 2048.0MB)"));
+    }
+
+    #[test]
+    fn test_anomaly_format() {
+        let event = Event::anomaly(
+            "aider".to_string(),
+            4321,
+            crate::event::AnomalyKind::NewHosts,
+            7,
+            "contacted 12 hosts not seen in 8 earlier sessions".to_string(),
+            RiskLevel::High,
+        );
+
+        let pretty = Logger::new(LoggerConfig {
+            format: LogFormat::Pretty,
+            use_colors: false,
+            ..Default::default()
+        });
+        assert!(pretty.format(&event).contains(
+            "[anomaly] new_hosts (score 7): contacted 12 hosts not seen in 8 earlier sessions"
+        ));
+
+        let compact = Logger::new(LoggerConfig {
+            format: LogFormat::Compact,
+            ..Default::default()
+        });
+        assert!(compact.format(&event).contains("anomaly:new_hosts:7"));
+    }
+
+    #[test]
+    fn test_file_summary_format() {
+        let event = Event::file_summary(
+            PathBuf::from("/work/node_modules"),
+            FileAction::Create,
+            1204,
+            "fswatch".to_string(),
+            1,
+        );
+
+        let pretty = Logger::new(LoggerConfig {
+            format: LogFormat::Pretty,
+            use_colors: false,
+            ..Default::default()
+        });
+        assert!(pretty
+            .format(&event)
+            .contains("[create] 1204 files under /work/node_modules/"));
+
+        let compact = Logger::new(LoggerConfig {
+            format: LogFormat::Compact,
+            ..Default::default()
+        });
+        assert!(compact
+            .format(&event)
+            .contains("create:1204:/work/node_modules"));
+    }
+
     #[test]
     fn test_session_format() {
         let config = LoggerConfig {
@@ -1,14 +1,23 @@
 //! Network monitoring module for MacAgentWatch
 //!
-//! Uses libproc to monitor network connections from tracked processes.
-//! Detects connections to non-whitelisted hosts.
-
-use crate::detector::{Detector, NetworkConnection, NetworkWhitelist};
+//! Uses libproc on macOS and `/proc` on Linux to monitor the sockets of
+//! tracked processes. Detects connections to non-whitelisted hosts, new
+//! listening ports, and shells whose stdio is a network socket. Each
+//! connection is reported again when it closes, with how long it was open
+//! and, on Linux, how many bytes it carried. Both events record the provider
+//! the remote address belonged to when the connection was first seen.
+
+use crate::bus::{EventBus, SubscribeOptions};
+use crate::detector::{Detector, NetworkConnection, NetworkWhitelist, ProviderCatalogue};
 use crate::error::CoreError;
-use crate::event::{Event, EventType};
-use std::collections::HashSet;
+use crate::event::{Event, EventType, ProviderTag, RiskLevel};
+use crate::process_tracker::ProcessTracker;
+use crate::sockdiag::ByteCounts;
+use crate::types::Swappable;
+use std::collections::{HashMap, HashSet};
+use std::net::{IpAddr, SocketAddr};
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::mpsc::Receiver;
 use std::sync::{Arc, Mutex};
 use std::thread::{self, JoinHandle};
 use std::time::{Duration, Instant};
@@ -26,6 +35,9 @@ pub struct NetMonConfig {
     pub track_udp: bool,
     /// Maximum number of seen connections before resetting (0 = unlimited)
     pub max_seen_connections: usize,
+    /// Bytes sent over one connection to a non-whitelisted host at which
+    /// its close is reported as Critical (0 = never)
+    pub upload_alert_bytes: u64,
 }
 
 impl Default for NetMonConfig {
@@ -36,6 +48,7 @@ impl Default for NetMonConfig {
             track_tcp: true,
             track_udp: true,
             max_seen_connections: 10_000,
+            upload_alert_bytes: 10 * 1024 * 1024,
         }
     }
 }
@@ -72,6 +85,28 @@ impl NetMonConfig {
         self.max_seen_connections = max;
         self
     }
+
+    /// Set the upload size that makes a connection to a non-whitelisted
+    /// host Critical (0 = never)
+    pub fn upload_alert_bytes(mut self, bytes: u64) -> Self {
+        self.upload_alert_bytes = bytes;
+        self
+    }
+
+    /// Risk of a closed connection: Low to whitelisted hosts, Medium to
+    /// others, and Critical once it sent `upload_alert_bytes` to a
+    /// non-whitelisted host
+    fn close_risk_level(&self, allowed: bool, bytes_sent: Option<u64>) -> RiskLevel {
+        if allowed {
+            RiskLevel::Low
+        } else if self.upload_alert_bytes > 0
+            && bytes_sent.is_some_and(|sent| sent >= self.upload_alert_bytes)
+        {
+            RiskLevel::Critical
+        } else {
+            RiskLevel::Medium
+        }
+    }
 }
 
 /// Tracked network connection with metadata
@@ -144,60 +179,375 @@ impl SeenConnectionsCache {
     }
 }
 
+/// Local address a tracked process accepts connections on
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct TrackedListener {
+    /// Process ID
+    pub pid: u32,
+    /// Bound local address
+    pub address: String,
+    /// Bound local port
+    pub port: u16,
+    /// Protocol
+    pub protocol: String,
+}
+
+impl TrackedListener {
+    /// Create a new tracked listener
+    pub fn new(pid: u32, address: String, port: u16, protocol: String) -> Self {
+        Self {
+            pid,
+            address,
+            port,
+            protocol,
+        }
+    }
+}
+
+/// Listeners and reverse shells already reported, kept until the owning
+/// process is no longer tracked
+#[derive(Debug, Default)]
+struct SeenSockets {
+    listeners: HashSet<TrackedListener>,
+    shells: HashSet<u32>,
+}
+
+impl SeenSockets {
+    fn retain_pids(&mut self, pids: &HashSet<u32>) {
+        self.listeners.retain(|l| pids.contains(&l.pid));
+        self.shells.retain(|pid| pids.contains(pid));
+    }
+}
+
+/// Shell programs whose stdio on a socket indicates a reverse or bind shell
+const SHELLS: &[&str] = &[
+    "sh", "bash", "dash", "zsh", "ksh", "mksh", "fish", "tcsh", "csh", "ash",
+];
+
+/// Whether a process name is an interactive shell
+fn is_shell(name: &str) -> bool {
+    let name = name.rsplit('/').next().unwrap_or(name);
+    let name = name.strip_prefix('-').unwrap_or(name);
+    SHELLS.contains(&name)
+}
+
+/// State of an inet socket, reduced to what the monitor reports on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SocketState {
+    /// TCP socket accepting connections
+    Listen,
+    /// Connected, or connecting, to a remote endpoint
+    Connected,
+    /// Anything else (closing, unconnected UDP, ...)
+    Other,
+}
+
+/// One inet socket of a process, as read from the OS
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SocketEntry {
+    protocol: &'static str,
+    state: SocketState,
+    local: SocketAddr,
+    remote: SocketAddr,
+    /// Open as stdin, stdout or stderr
+    stdio: bool,
+    /// Socket inode, where the platform identifies sockets by one
+    inode: Option<u64>,
+}
+
+/// A connection found in one poll
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct OpenSocket {
+    conn: TrackedConnection,
+    /// Local endpoint, telling apart connections to the same remote
+    local: SocketAddr,
+    inode: Option<u64>,
+}
+
+/// Connection open as of the last poll
+#[derive(Debug)]
+struct LiveConnection {
+    process: String,
+    opened: Instant,
+    traffic: Option<ByteCounts>,
+    provider: Option<ProviderTag>,
+}
+
+/// Connection that was open at the last poll and is now gone
+#[derive(Debug, PartialEq, Eq)]
+struct ClosedConnection {
+    process: String,
+    conn: TrackedConnection,
+    duration: Duration,
+    traffic: Option<ByteCounts>,
+    /// Provider the remote address matched when the connection was first seen
+    provider: Option<ProviderTag>,
+}
+
+/// Connections of tracked processes, from the poll they are first seen in
+/// until the poll they are gone from
+#[derive(Debug, Default)]
+struct LiveConnections {
+    open: HashMap<(TrackedConnection, SocketAddr), LiveConnection>,
+}
+
+impl LiveConnections {
+    /// Record the connections found in one poll, with their latest traffic
+    /// counters, and return those no longer open. New connections are
+    /// classified with `catalogue`.
+    fn update(
+        &mut self,
+        found: Vec<(String, OpenSocket, Option<ByteCounts>)>,
+        now: Instant,
+        catalogue: &ProviderCatalogue,
+    ) -> Vec<ClosedConnection> {
+        let mut still_open = HashSet::new();
+        for (process, socket, traffic) in found {
+            let key = (socket.conn, socket.local);
+            let live = self.open.entry(key.clone()).or_insert_with(|| {
+                let provider = catalogue.classify(&key.0.host).map(ProviderTag::from);
+                LiveConnection {
+                    process,
+                    opened: now,
+                    traffic: None,
+                    provider,
+                }
+            });
+            // Keep the last counters when a poll could not read them
+            if traffic.is_some() {
+                live.traffic = traffic;
+            }
+            still_open.insert(key);
+        }
+
+        let gone: Vec<_> = self
+            .open
+            .keys()
+            .filter(|key| !still_open.contains(*key))
+            .cloned()
+            .collect();
+        gone.into_iter()
+            .filter_map(|key| {
+                let live = self.open.remove(&key)?;
+                Some(ClosedConnection {
+                    process: live.process,
+                    conn: key.0,
+                    duration: now.saturating_duration_since(live.opened),
+                    traffic: live.traffic,
+                    provider: live.provider,
+                })
+            })
+            .collect()
+    }
+
+    /// Close every open connection, when monitoring stops
+    fn close_all(&mut self, now: Instant) -> Vec<ClosedConnection> {
+        self.update(Vec::new(), now, ProviderCatalogue::builtin())
+    }
+}
+
+/// Sockets of one process found in a single poll
+#[derive(Debug, Default, PartialEq, Eq)]
+struct PidSockets {
+    /// Outbound connections to non-loopback hosts
+    connections: Vec<OpenSocket>,
+    /// Local addresses accepting connections
+    listeners: Vec<TrackedListener>,
+    /// Remote endpoint of a socket open on stdin, stdout or stderr
+    stdio_peer: Option<TrackedConnection>,
+}
+
+impl PidSockets {
+    fn classify(pid: u32, entries: Vec<SocketEntry>, config: &NetMonConfig) -> Self {
+        let mut sockets = Self::default();
+        for entry in entries {
+            let tracked = match entry.protocol {
+                "tcp" => config.track_tcp,
+                "udp" => config.track_udp,
+                _ => false,
+            };
+            if !tracked {
+                continue;
+            }
+            if entry.state == SocketState::Listen {
+                sockets.listeners.push(TrackedListener::new(
+                    pid,
+                    entry.local.ip().to_string(),
+                    entry.local.port(),
+                    entry.protocol.to_string(),
+                ));
+                continue;
+            }
+            if entry.state != SocketState::Connected
+                || entry.remote.port() == 0
+                || entry.remote.ip().is_unspecified()
+            {
+                continue;
+            }
+            let conn = TrackedConnection::new(
+                pid,
+                entry.remote.ip().to_string(),
+                entry.remote.port(),
+                entry.protocol.to_string(),
+            );
+            // A shell talking to a local port is still a shell on a socket
+            if entry.stdio && sockets.stdio_peer.is_none() {
+                sockets.stdio_peer = Some(conn.clone());
+            }
+            if !entry.remote.ip().is_loopback() {
+                sockets.connections.push(OpenSocket {
+                    conn,
+                    local: entry.local,
+                    inode: entry.inode,
+                });
+            }
+        }
+        sockets
+    }
+}
+
+/// Shared set of PIDs whose sockets are polled, with the process name to
+/// report for each.
+///
+/// Clones share the same set, so a process tracker can add children as they
+/// start while the monitor thread is polling.
+#[derive(Debug, Clone, Default)]
+pub struct TrackedPids {
+    inner: Arc<Mutex<HashMap<u32, Option<String>>>>,
+}
+
+impl TrackedPids {
+    /// Track a PID without a known process name
+    pub fn add(&self, pid: u32) {
+        if let Ok(mut pids) = self.inner.lock() {
+            pids.entry(pid).or_insert(None);
+        }
+    }
+
+    /// Track a PID and name the process that owns its sockets
+    pub fn add_process(&self, pid: u32, name: impl Into<String>) {
+        if let Ok(mut pids) = self.inner.lock() {
+            pids.insert(pid, Some(name.into()));
+        }
+    }
+
+    /// Stop tracking a PID
+    pub fn remove(&self, pid: u32) {
+        if let Ok(mut pids) = self.inner.lock() {
+            pids.remove(&pid);
+        }
+    }
+
+    /// Whether a PID is tracked
+    pub fn contains(&self, pid: u32) -> bool {
+        self.inner
+            .lock()
+            .map(|pids| pids.contains_key(&pid))
+            .unwrap_or(false)
+    }
+
+    /// Name to report for a PID, falling back to `pid:<n>` when unknown
+    pub fn process_name(&self, pid: u32) -> String {
+        self.inner
+            .lock()
+            .ok()
+            .and_then(|pids| pids.get(&pid).cloned().flatten())
+            .unwrap_or_else(|| format!("pid:{}", pid))
+    }
+
+    /// Snapshot of tracked PIDs and their reported names
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
+    fn snapshot(&self) -> Vec<(u32, String)> {
+        self.inner
+            .lock()
+            .map(|pids| {
+                pids.iter()
+                    .map(|(pid, name)| {
+                        let name = name.clone().unwrap_or_else(|| format!("pid:{}", pid));
+                        (*pid, name)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
 /// Network monitor using libproc
 pub struct NetworkMonitor {
     config: NetMonConfig,
-    whitelist: NetworkWhitelist,
-    event_tx: Option<Sender<Event>>,
+    whitelist: Swappable<NetworkWhitelist>,
+    events: EventBus<Event>,
     stop_flag: Arc<AtomicBool>,
     monitor_thread: Option<JoinHandle<()>>,
-    tracked_pids: Arc<Mutex<HashSet<u32>>>,
+    tracked_pids: TrackedPids,
     seen_connections: Arc<Mutex<SeenConnectionsCache>>,
+    seen_sockets: Arc<Mutex<SeenSockets>>,
 }
 
 impl NetworkMonitor {
     /// Create a new network monitor
     pub fn new(config: NetMonConfig) -> Self {
-        let mut tracked = HashSet::new();
-        tracked.insert(config.root_pid);
+        let tracked = TrackedPids::default();
+        tracked.add(config.root_pid);
         let max_seen = config.max_seen_connections;
 
         Self {
             config,
-            whitelist: NetworkWhitelist::default(),
-            event_tx: None,
+            whitelist: Swappable::default(),
+            events: EventBus::new(),
             stop_flag: Arc::new(AtomicBool::new(false)),
             monitor_thread: None,
-            tracked_pids: Arc::new(Mutex::new(tracked)),
+            tracked_pids: tracked,
             seen_connections: Arc::new(Mutex::new(SeenConnectionsCache::new(max_seen))),
+            seen_sockets: Arc::new(Mutex::new(SeenSockets::default())),
         }
     }
 
     /// Create with custom whitelist
     pub fn with_whitelist(mut self, whitelist: NetworkWhitelist) -> Self {
+        self.whitelist = Swappable::new(whitelist);
+        self
+    }
+
+    /// Share a whitelist handle that can be swapped while the monitor runs
+    pub fn with_shared_whitelist(mut self, whitelist: Swappable<NetworkWhitelist>) -> Self {
         self.whitelist = whitelist;
         self
     }
 
+    /// Replace the whitelist used to score subsequent connections
+    pub fn set_whitelist(&self, whitelist: NetworkWhitelist) {
+        self.whitelist.store(whitelist);
+    }
+
     /// Subscribe to network events
-    pub fn subscribe(&mut self) -> Receiver<Event> {
-        let (tx, rx) = channel();
-        self.event_tx = Some(tx);
-        rx
+    pub fn subscribe(&self) -> Receiver<Event> {
+        self.events.subscribe()
+    }
+
+    /// Subscribe with a custom buffer, lag policy and filter
+    pub fn subscribe_with(&self, options: SubscribeOptions) -> Receiver<Event> {
+        self.events.subscribe_with(options)
     }
 
     /// Add a PID to track
     pub fn add_pid(&self, pid: u32) {
-        if let Ok(mut pids) = self.tracked_pids.lock() {
-            pids.insert(pid);
-        }
+        self.tracked_pids.add(pid);
+    }
+
+    /// Add a PID to track, naming the process in its network events
+    pub fn add_process(&self, pid: u32, name: impl Into<String>) {
+        self.tracked_pids.add_process(pid, name);
     }
 
     /// Remove a PID from tracking
     pub fn remove_pid(&self, pid: u32) {
-        if let Ok(mut pids) = self.tracked_pids.lock() {
-            pids.remove(&pid);
-        }
+        self.tracked_pids.remove(pid);
+    }
+
+    /// Handle to the tracked PID set, for feeding it from a process tracker
+    pub fn tracked_pids(&self) -> TrackedPids {
+        self.tracked_pids.clone()
     }
 
     /// Clear the seen connections cache
@@ -213,25 +563,27 @@ impl NetworkMonitor {
     }
 
     /// Start monitoring
-    #[cfg(target_os = "macos")]
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
     pub fn start(&mut self) -> std::result::Result<(), CoreError> {
         self.stop_flag.store(false, Ordering::Relaxed);
 
         let config = self.config.clone();
         let whitelist = self.whitelist.clone();
-        let event_tx = self.event_tx.clone();
+        let events = self.events.clone();
         let stop_flag = self.stop_flag.clone();
         let tracked_pids = self.tracked_pids.clone();
         let seen_connections = self.seen_connections.clone();
+        let seen_sockets = self.seen_sockets.clone();
 
         let handle = thread::spawn(move || {
             Self::monitor_loop(
                 config,
                 whitelist,
-                event_tx,
+                events,
                 stop_flag,
                 tracked_pids,
                 seen_connections,
+                seen_sockets,
             );
         });
 
@@ -239,9 +591,9 @@ impl NetworkMonitor {
         Ok(())
     }
 
-    #[cfg(not(target_os = "macos"))]
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
     pub fn start(&mut self) -> std::result::Result<(), CoreError> {
-        // No-op on non-macOS
+        // No-op on other platforms
         Ok(())
     }
 
@@ -251,6 +603,8 @@ impl NetworkMonitor {
         if let Some(handle) = self.monitor_thread.take() {
             let _ = handle.join();
         }
+        // Subscribers see a disconnect once no more events can arrive
+        self.events.close();
     }
 
     /// Signal the monitor to stop without waiting for the thread to finish.
@@ -260,33 +614,47 @@ impl NetworkMonitor {
     }
 
     /// Main monitoring loop
-    #[cfg(target_os = "macos")]
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
     fn monitor_loop(
         config: NetMonConfig,
-        whitelist: NetworkWhitelist,
-        event_tx: Option<Sender<Event>>,
+        whitelist: Swappable<NetworkWhitelist>,
+        events: EventBus<Event>,
         stop_flag: Arc<AtomicBool>,
-        tracked_pids: Arc<Mutex<HashSet<u32>>>,
+        tracked_pids: TrackedPids,
         seen_connections: Arc<Mutex<SeenConnectionsCache>>,
+        seen_sockets: Arc<Mutex<SeenSockets>>,
     ) {
+        let mut live = LiveConnections::default();
+
         loop {
             if stop_flag.load(Ordering::Relaxed) {
                 break;
             }
 
             let iteration_start = Instant::now();
+            // Resolved provider addresses as of now; the first poll starts
+            // the lookup
+            let catalogue = ProviderCatalogue::resolved_default();
 
             // Get current PIDs to check
-            let pids: Vec<u32> = tracked_pids
-                .lock()
-                .map(|g| g.iter().cloned().collect())
-                .unwrap_or_default();
+            let snapshot = tracked_pids.snapshot();
+            let polled: Vec<_> = snapshot
+                .iter()
+                .map(|(pid, process)| (*pid, process, Self::get_sockets_for_pid(*pid, &config)))
+                .collect();
+            let traffic = if polled.iter().any(|(_, _, s)| !s.connections.is_empty()) {
+                socket_traffic()
+            } else {
+                HashMap::new()
+            };
 
-            for pid in pids {
-                // Get connections for this PID
-                let connections = Self::get_connections_for_pid(pid, &config);
+            let mut found = Vec::new();
+            for (pid, process, sockets) in polled {
+                for socket in sockets.connections {
+                    let conn = socket.conn.clone();
+                    let bytes = socket.inode.and_then(|inode| traffic.get(&inode).copied());
+                    found.push((process.clone(), socket, bytes));
 
-                for conn in connections {
                     // Check if we've seen this connection before
                     {
                         let Ok(mut seen) = seen_connections.lock() else {
@@ -300,7 +668,7 @@ impl NetworkMonitor {
 
                     // Determine risk level
                     let net_conn = conn.to_network_connection();
-                    let risk_level = whitelist.risk_level(&net_conn);
+                    let risk_level = whitelist.load().risk_level(&net_conn);
 
                     // Create event
                     let event = Event::new(
@@ -308,34 +676,138 @@ impl NetworkMonitor {
                             host: conn.host.clone(),
                             port: conn.port,
                             protocol: conn.protocol.clone(),
+                            provider: None,
                         },
-                        format!("pid:{}", pid),
+                        process.clone(),
                         pid,
                         risk_level,
-                    );
+                    )
+                    .with_provider(catalogue);
 
-                    if let Some(ref tx) = event_tx {
-                        let _ = tx.send(event);
+                    events.publish(event);
+                }
+
+                for listener in sockets.listeners {
+                    Self::publish_listener(&events, &seen_sockets, process.clone(), listener);
+                }
+
+                if let Some(peer) = sockets.stdio_peer {
+                    // The tracked name may be a placeholder or stale after exec
+                    let name = ProcessTracker::process_name(pid).unwrap_or_else(|| process.clone());
+                    if is_shell(&name) {
+                        Self::publish_reverse_shell(&events, &seen_sockets, name, peer);
                     }
                 }
             }
 
+            for closed in live.update(found, Instant::now(), catalogue) {
+                events.publish(Self::close_event(&config, &whitelist, closed));
+            }
+
+            // Forget what was reported for processes no longer tracked, so
+            // a reused PID is reported afresh
+            if let Ok(mut seen) = seen_sockets.lock() {
+                let pids = snapshot.iter().map(|(pid, _)| *pid).collect();
+                seen.retain_pids(&pids);
+            }
+
             // Sleep for the remaining time in the poll interval, accounting for processing time
             let elapsed = iteration_start.elapsed();
             if let Some(remaining) = config.poll_interval.checked_sub(elapsed) {
                 thread::sleep(remaining);
             }
         }
+
+        // Report what the session left open with its totals so far
+        for closed in live.close_all(Instant::now()) {
+            events.publish(Self::close_event(&config, &whitelist, closed));
+        }
+    }
+
+    /// Event for a connection that closed, scored against the whitelist and
+    /// the upload threshold
+    fn close_event(
+        config: &NetMonConfig,
+        whitelist: &Swappable<NetworkWhitelist>,
+        closed: ClosedConnection,
+    ) -> Event {
+        let allowed = !whitelist
+            .load()
+            .is_sensitive(&closed.conn.to_network_connection());
+        let bytes_sent = closed.traffic.map(|t| t.sent);
+        let risk_level = config.close_risk_level(allowed, bytes_sent);
+        Event::new(
+            EventType::NetworkClose {
+                host: closed.conn.host,
+                port: closed.conn.port,
+                protocol: closed.conn.protocol,
+                duration_ms: closed.duration.as_millis() as u64,
+                bytes_sent,
+                bytes_received: closed.traffic.map(|t| t.received),
+                provider: closed.provider,
+            },
+            closed.process,
+            closed.conn.pid,
+            risk_level,
+        )
+    }
+
+    /// Publish a listener the first time it is seen
+    fn publish_listener(
+        events: &EventBus<Event>,
+        seen_sockets: &Mutex<SeenSockets>,
+        process: String,
+        listener: TrackedListener,
+    ) {
+        {
+            let Ok(mut seen) = seen_sockets.lock() else {
+                return;
+            };
+            if !seen.listeners.insert(listener.clone()) {
+                return;
+            }
+        }
+        events.publish(Event::listen(
+            process,
+            listener.pid,
+            listener.address,
+            listener.port,
+            listener.protocol,
+        ));
+    }
+
+    /// Publish a shell with a socket on its stdio, once per process
+    fn publish_reverse_shell(
+        events: &EventBus<Event>,
+        seen_sockets: &Mutex<SeenSockets>,
+        shell: String,
+        peer: TrackedConnection,
+    ) {
+        {
+            let Ok(mut seen) = seen_sockets.lock() else {
+                return;
+            };
+            if !seen.shells.insert(peer.pid) {
+                return;
+            }
+        }
+        events.publish(Event::reverse_shell(
+            shell,
+            peer.pid,
+            peer.host,
+            peer.port,
+            peer.protocol,
+        ));
     }
 
-    /// Get network connections for a specific PID using libproc
+    /// Get the inet sockets of a specific PID using libproc
     #[cfg(target_os = "macos")]
-    fn get_connections_for_pid(pid: u32, config: &NetMonConfig) -> Vec<TrackedConnection> {
+    fn get_sockets_for_pid(pid: u32, config: &NetMonConfig) -> PidSockets {
         use libproc::libproc::file_info::{pidfdinfo, ListFDs, ProcFDType};
         use libproc::libproc::net_info::{SocketFDInfo, SocketInfoKind, TcpSIState};
         use libproc::libproc::proc_pid::listpidinfo;
 
-        let mut connections = Vec::new();
+        let mut entries = Vec::new();
 
         // Get all file descriptors for the process
         let fds = match listpidinfo::<ListFDs>(pid as i32, 256) {
@@ -352,7 +824,7 @@ impl NetworkMonitor {
                         eprintln!("[agent-watch] Failed to list FDs for pid {}: {} (errno={})", pid, e, errno);
                     }
                 }
-                return connections;
+                return PidSockets::default();
             }
         };
 
@@ -369,81 +841,172 @@ impl NetworkMonitor {
             };
 
             let kind: SocketInfoKind = socket_info.psi.soi_kind.into();
+            let stdio = (0..=2).contains(&fd_info.proc_fd);
 
-            match kind {
-                SocketInfoKind::Tcp if config.track_tcp => {
+            let (protocol, state, in_sock) = match kind {
+                SocketInfoKind::Tcp => {
                     let tcp = libproc_safe::tcp_info(&socket_info.psi);
-                    let state: TcpSIState = tcp.tcpsi_state.into();
-
-                    // Only track established connections (not listening sockets)
-                    if !matches!(
-                        state,
-                        TcpSIState::Established | TcpSIState::SynSent | TcpSIState::SynReceived
-                    ) {
-                        continue;
-                    }
-
-                    // Extract remote address and port
-                    let remote_port = tcp.tcpsi_ini.insi_fport as u16;
-                    if remote_port == 0 {
-                        continue;
-                    }
-
-                    let Some(remote_addr) =
-                        extract_ip_address(&tcp.tcpsi_ini, tcp.tcpsi_ini.insi_vflag)
-                    else {
-                        continue;
+                    let state = match tcp.tcpsi_state.into() {
+                        TcpSIState::Listen => SocketState::Listen,
+                        TcpSIState::Established | TcpSIState::SynSent | TcpSIState::SynReceived => {
+                            SocketState::Connected
+                        }
+                        _ => SocketState::Other,
                     };
-
-                    let host = remote_addr.to_string();
-
-                    // Skip loopback addresses
-                    if host == "127.0.0.1" || host == "::1" || host == "0.0.0.0" {
-                        continue;
-                    }
-
-                    connections.push(TrackedConnection::new(
-                        pid,
-                        host,
-                        remote_port,
-                        "tcp".to_string(),
-                    ));
+                    ("tcp", state, tcp.tcpsi_ini)
                 }
-                SocketInfoKind::In if config.track_udp => {
+                SocketInfoKind::In => {
                     let in_sock = libproc_safe::in_sock_info(&socket_info.psi);
+                    ("udp", SocketState::Connected, in_sock)
+                }
+                _ => continue,
+            };
 
-                    // Extract remote address and port for UDP
-                    let remote_port = in_sock.insi_fport as u16;
-                    if remote_port == 0 {
-                        continue;
-                    }
-
-                    let Some(remote_addr) = extract_ip_address(&in_sock, in_sock.insi_vflag) else {
-                        continue;
-                    };
+            let Some((local, remote)) = libproc_safe::socket_addrs(&in_sock) else {
+                continue;
+            };
+            entries.push(SocketEntry {
+                protocol,
+                state,
+                local,
+                remote,
+                stdio,
+                inode: None,
+            });
+        }
 
-                    let host = remote_addr.to_string();
+        PidSockets::classify(pid, entries, config)
+    }
 
-                    // Skip loopback addresses
-                    if host == "127.0.0.1" || host == "::1" || host == "0.0.0.0" {
-                        continue;
-                    }
+    /// Get the inet sockets of a specific PID from `/proc/<pid>/fd` and
+    /// `/proc/<pid>/net`
+    #[cfg(target_os = "linux")]
+    fn get_sockets_for_pid(pid: u32, config: &NetMonConfig) -> PidSockets {
+        // Socket inode -> whether it is open on stdin, stdout or stderr
+        let mut inodes: HashMap<u64, bool> = HashMap::new();
+        let Ok(fds) = std::fs::read_dir(format!("/proc/{pid}/fd")) else {
+            // Process exited or is not ours to inspect
+            return PidSockets::default();
+        };
+        for fd in fds.flatten() {
+            let Ok(target) = std::fs::read_link(fd.path()) else {
+                continue;
+            };
+            let Some(inode) = parse_socket_link(&target.to_string_lossy()) else {
+                continue;
+            };
+            let stdio = fd
+                .file_name()
+                .to_str()
+                .and_then(|n| n.parse::<u32>().ok())
+                .is_some_and(|n| n <= 2);
+            *inodes.entry(inode).or_default() |= stdio;
+        }
+        if inodes.is_empty() {
+            return PidSockets::default();
+        }
 
-                    connections.push(TrackedConnection::new(
-                        pid,
-                        host,
-                        remote_port,
-                        "udp".to_string(),
-                    ));
+        let mut entries = Vec::new();
+        for (table, protocol) in [
+            ("tcp", "tcp"),
+            ("tcp6", "tcp"),
+            ("udp", "udp"),
+            ("udp6", "udp"),
+        ] {
+            let Ok(contents) = std::fs::read_to_string(format!("/proc/{pid}/net/{table}")) else {
+                continue;
+            };
+            for (inode, mut entry) in parse_proc_net(&contents, protocol) {
+                if let Some(stdio) = inodes.get(&inode) {
+                    entry.stdio = *stdio;
+                    entry.inode = Some(inode);
+                    entries.push(entry);
                 }
-                _ => {}
             }
         }
 
-        connections
+        PidSockets::classify(pid, entries, config)
     }
 }
 
+/// Traffic counters of TCP sockets by inode, where the platform has them
+#[cfg(target_os = "linux")]
+fn socket_traffic() -> HashMap<u64, ByteCounts> {
+    crate::sockdiag::tcp_byte_counts().unwrap_or_default()
+}
+
+#[cfg(target_os = "macos")]
+fn socket_traffic() -> HashMap<u64, ByteCounts> {
+    // libproc has no per-socket byte counters
+    HashMap::new()
+}
+
+/// Inode of a `socket:[<inode>]` fd link target
+#[cfg(any(target_os = "linux", test))]
+fn parse_socket_link(target: &str) -> Option<u64> {
+    target
+        .strip_prefix("socket:[")?
+        .strip_suffix(']')?
+        .parse()
+        .ok()
+}
+
+/// Parse a `/proc/net/{tcp,tcp6,udp,udp6}` table into sockets keyed by
+/// inode. Addresses are hex words in host byte order; ports are plain hex.
+#[cfg(any(target_os = "linux", test))]
+fn parse_proc_net(contents: &str, protocol: &'static str) -> Vec<(u64, SocketEntry)> {
+    contents
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let local = parse_proc_net_addr(fields.get(1)?)?;
+            let remote = parse_proc_net_addr(fields.get(2)?)?;
+            let state = match u8::from_str_radix(fields.get(3)?, 16).ok()? {
+                // TCP_ESTABLISHED, TCP_SYN_SENT, TCP_SYN_RECV; a UDP socket
+                // is "established" once connect()ed
+                0x01..=0x03 => SocketState::Connected,
+                0x0A if protocol == "tcp" => SocketState::Listen,
+                _ => SocketState::Other,
+            };
+            let inode = fields.get(9)?.parse().ok()?;
+            Some((
+                inode,
+                SocketEntry {
+                    protocol,
+                    state,
+                    local,
+                    remote,
+                    stdio: false,
+                    inode: None,
+                },
+            ))
+        })
+        .collect()
+}
+
+#[cfg(any(target_os = "linux", test))]
+fn parse_proc_net_addr(field: &str) -> Option<SocketAddr> {
+    let (addr, port) = field.split_once(':')?;
+    let port = u16::from_str_radix(port, 16).ok()?;
+    let word = |i: usize| -> Option<[u8; 4]> {
+        let hex = addr.get(i * 8..i * 8 + 8)?;
+        Some(u32::from_str_radix(hex, 16).ok()?.to_ne_bytes())
+    };
+    let ip = match addr.len() {
+        8 => IpAddr::from(word(0)?),
+        32 => {
+            let mut octets = [0u8; 16];
+            for i in 0..4 {
+                octets[i * 4..i * 4 + 4].copy_from_slice(&word(i)?);
+            }
+            IpAddr::from(octets)
+        }
+        _ => return None,
+    };
+    Some(SocketAddr::new(ip, port))
+}
+
 /// Safe wrappers for libproc union field access.
 ///
 /// The libproc crate exposes socket protocol info and address info as C unions.
@@ -451,7 +1014,7 @@ impl NetworkMonitor {
 #[cfg(target_os = "macos")]
 mod libproc_safe {
     use libproc::libproc::net_info::{InSockInfo, SocketInfo};
-    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 
     /// Extract TCP info from a socket whose `soi_kind` is `Tcp`.
     ///
@@ -471,41 +1034,34 @@ mod libproc_safe {
         unsafe { socket.soi_proto.pri_in }
     }
 
-    /// Extract the remote IPv4 address from an InSockInfo whose `insi_vflag` is 1.
-    pub fn extract_ipv4(in_sock: &InSockInfo) -> Option<IpAddr> {
-        // SAFETY: vflag == 1 confirms IPv4, so ina_46.i46a_addr4 is the active variant.
-        let addr = unsafe { in_sock.insi_faddr.ina_46.i46a_addr4 };
-        let ip = Ipv4Addr::from(u32::from_be(addr.s_addr));
-        if ip.is_unspecified() {
-            None
-        } else {
-            Some(IpAddr::V4(ip))
-        }
+    /// Local and remote endpoints of an InSockInfo. Unspecified addresses
+    /// are returned as-is; `insi_vflag` 1 is IPv4 and 2 is IPv6.
+    pub fn socket_addrs(in_sock: &InSockInfo) -> Option<(SocketAddr, SocketAddr)> {
+        let local = match in_sock.insi_vflag {
+            // SAFETY: vflag == 1 confirms IPv4, so ina_46.i46a_addr4 is the active variant.
+            1 => ipv4(unsafe { in_sock.insi_laddr.ina_46.i46a_addr4 }),
+            // SAFETY: vflag == 2 confirms IPv6, so ina_6 is the active variant.
+            2 => ipv6(unsafe { in_sock.insi_laddr.ina_6 }),
+            _ => return None,
+        };
+        let remote = match in_sock.insi_vflag {
+            // SAFETY: as above, for the foreign address.
+            1 => ipv4(unsafe { in_sock.insi_faddr.ina_46.i46a_addr4 }),
+            // SAFETY: as above, for the foreign address.
+            _ => ipv6(unsafe { in_sock.insi_faddr.ina_6 }),
+        };
+        Some((
+            SocketAddr::new(local, in_sock.insi_lport as u16),
+            SocketAddr::new(remote, in_sock.insi_fport as u16),
+        ))
     }
 
-    /// Extract the remote IPv6 address from an InSockInfo whose `insi_vflag` is 2.
-    pub fn extract_ipv6(in_sock: &InSockInfo) -> Option<IpAddr> {
-        // SAFETY: vflag == 2 confirms IPv6, so ina_6 is the active variant.
-        let addr = unsafe { in_sock.insi_faddr.ina_6 };
-        let ip = Ipv6Addr::from(addr.s6_addr);
-        if ip.is_unspecified() {
-            None
-        } else {
-            Some(IpAddr::V6(ip))
-        }
+    fn ipv4(addr: libc::in_addr) -> IpAddr {
+        IpAddr::V4(Ipv4Addr::from(u32::from_be(addr.s_addr)))
     }
-}
 
-/// Extract IP address from InSockInfo based on the vflag
-#[cfg(target_os = "macos")]
-fn extract_ip_address(
-    in_sock: &libproc::libproc::net_info::InSockInfo,
-    vflag: u8,
-) -> Option<std::net::IpAddr> {
-    match vflag {
-        1 => libproc_safe::extract_ipv4(in_sock),
-        2 => libproc_safe::extract_ipv6(in_sock),
-        _ => None,
+    fn ipv6(addr: libc::in6_addr) -> IpAddr {
+        IpAddr::V6(Ipv6Addr::from(addr.s6_addr))
     }
 }
 
@@ -513,18 +1069,20 @@ impl NetworkMonitor {
     /// Create network event (for testing)
     pub fn create_event(&self, conn: &TrackedConnection) -> Event {
         let net_conn = conn.to_network_connection();
-        let risk_level = self.whitelist.risk_level(&net_conn);
+        let risk_level = self.whitelist.load().risk_level(&net_conn);
 
         Event::new(
             EventType::Network {
                 host: conn.host.clone(),
                 port: conn.port,
                 protocol: conn.protocol.clone(),
+                provider: None,
             },
-            format!("pid:{}", conn.pid),
+            self.tracked_pids.process_name(conn.pid),
             conn.pid,
             risk_level,
         )
+        .with_provider(ProviderCatalogue::builtin())
     }
 
     /// Manually report a connection (for integration with external tools)
@@ -542,22 +1100,29 @@ impl NetworkMonitor {
 
         // Determine risk level and send event
         let net_conn = conn.to_network_connection();
-        let risk_level = self.whitelist.risk_level(&net_conn);
+        let risk_level = self.whitelist.load().risk_level(&net_conn);
 
         let event = Event::new(
             EventType::Network {
                 host: conn.host.clone(),
                 port: conn.port,
                 protocol: conn.protocol.clone(),
+                provider: None,
             },
-            format!("pid:{}", conn.pid),
+            self.tracked_pids.process_name(conn.pid),
             conn.pid,
             risk_level,
-        );
+        )
+        .with_provider(ProviderCatalogue::resolved_default());
 
-        if let Some(ref tx) = self.event_tx {
-            let _ = tx.send(event);
-        }
+        self.events.publish(event);
+    }
+
+    /// Manually report a listening socket. Each listener is reported once
+    /// while its process is tracked.
+    pub fn report_listener(&self, listener: TrackedListener) {
+        let process = self.tracked_pids.process_name(listener.pid);
+        Self::publish_listener(&self.events, &self.seen_sockets, process, listener);
     }
 }
 
@@ -662,16 +1227,17 @@ mod tests {
         let monitor = NetworkMonitor::new(config);
 
         assert!(!monitor.is_running());
-        assert!(monitor.tracked_pids.lock().unwrap().contains(&1234));
+        assert!(monitor.tracked_pids.contains(1234));
     }
 
     #[test]
     fn test_monitor_subscribe() {
         let config = NetMonConfig::default();
-        let mut monitor = NetworkMonitor::new(config);
+        let monitor = NetworkMonitor::new(config);
 
-        let _rx = monitor.subscribe();
-        assert!(monitor.event_tx.is_some());
+        let _first = monitor.subscribe();
+        let _second = monitor.subscribe();
+        assert_eq!(monitor.events.subscriber_count(), 2);
     }
 
     #[test]
@@ -680,10 +1246,10 @@ mod tests {
         let monitor = NetworkMonitor::new(config);
 
         monitor.add_pid(100);
-        assert!(monitor.tracked_pids.lock().unwrap().contains(&100));
+        assert!(monitor.tracked_pids.contains(100));
 
         monitor.remove_pid(100);
-        assert!(!monitor.tracked_pids.lock().unwrap().contains(&100));
+        assert!(!monitor.tracked_pids.contains(100));
     }
 
     #[test]
@@ -693,7 +1259,7 @@ mod tests {
 
         let monitor = NetworkMonitor::new(config).with_whitelist(whitelist);
 
-        assert!(monitor.whitelist.is_host_allowed("custom.com"));
+        assert!(monitor.whitelist.load().is_host_allowed("custom.com"));
     }
 
     #[test]
@@ -736,16 +1302,16 @@ mod tests {
         assert!(!NetworkTracker::is_running(&monitor));
 
         NetworkTracker::add_pid(&mut monitor, 999);
-        assert!(monitor.tracked_pids.lock().unwrap().contains(&999));
+        assert!(monitor.tracked_pids.contains(999));
 
         NetworkTracker::remove_pid(&mut monitor, 999);
-        assert!(!monitor.tracked_pids.lock().unwrap().contains(&999));
+        assert!(!monitor.tracked_pids.contains(999));
     }
 
     #[test]
     fn test_report_connection() {
         let config = NetMonConfig::default();
-        let mut monitor = NetworkMonitor::new(config);
+        let monitor = NetworkMonitor::new(config);
         let rx = monitor.subscribe();
 
         let conn =
@@ -768,6 +1334,570 @@ mod tests {
         assert!(rx.try_recv().is_err());
     }
 
+    #[test]
+    fn test_report_connection_names_child_process() {
+        let monitor = NetworkMonitor::new(NetMonConfig::new(100));
+        let rx = monitor.subscribe();
+        let children = monitor.tracked_pids();
+        children.add_process(4321, "curl");
+
+        monitor.report_connection(TrackedConnection::new(
+            4321,
+            "example.com".to_string(),
+            443,
+            "tcp".to_string(),
+        ));
+        let event = rx.try_recv().unwrap();
+        assert_eq!(event.process, "curl");
+        assert_eq!(event.pid, 4321);
+
+        // Untracked or unnamed PIDs fall back to the numeric label
+        monitor.report_connection(TrackedConnection::new(
+            100,
+            "example.com".to_string(),
+            443,
+            "tcp".to_string(),
+        ));
+        assert_eq!(rx.try_recv().unwrap().process, "pid:100");
+
+        children.remove(4321);
+        assert!(!monitor.tracked_pids.contains(4321));
+    }
+
+    #[test]
+    fn test_tracked_pids_add_keeps_existing_name() {
+        let pids = TrackedPids::default();
+        pids.add_process(7, "node");
+        pids.add(7);
+        assert_eq!(pids.process_name(7), "node");
+        assert_eq!(pids.process_name(8), "pid:8");
+    }
+
+    #[test]
+    fn test_report_listener_once() {
+        let monitor = NetworkMonitor::new(NetMonConfig::new(100));
+        let rx = monitor.subscribe();
+        monitor.tracked_pids().add_process(4321, "python3");
+
+        let listener = TrackedListener::new(4321, "0.0.0.0".to_string(), 8000, "tcp".to_string());
+        monitor.report_listener(listener.clone());
+        let event = rx.try_recv().unwrap();
+        assert_eq!(event.process, "python3");
+        assert_eq!(event.risk_level, RiskLevel::Critical);
+        assert_eq!(
+            event.event_type,
+            EventType::Listen {
+                address: "0.0.0.0".to_string(),
+                port: 8000,
+                protocol: "tcp".to_string(),
+            }
+        );
+
+        monitor.report_listener(listener);
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_seen_sockets_forget_untracked_pids() {
+        let mut seen = SeenSockets::default();
+        seen.listeners.insert(TrackedListener::new(
+            1,
+            "::".to_string(),
+            22,
+            "tcp".to_string(),
+        ));
+        seen.listeners.insert(TrackedListener::new(
+            2,
+            "::".to_string(),
+            22,
+            "tcp".to_string(),
+        ));
+        seen.shells.insert(2);
+
+        seen.retain_pids(&HashSet::from([1]));
+        assert_eq!(seen.listeners.len(), 1);
+        assert!(seen.shells.is_empty());
+    }
+
+    #[test]
+    fn test_is_shell() {
+        assert!(is_shell("bash"));
+        assert!(is_shell("/bin/sh"));
+        assert!(is_shell("-zsh"));
+        assert!(!is_shell("node"));
+        assert!(!is_shell("bashful"));
+    }
+
+    fn entry(protocol: &'static str, state: SocketState, local: &str, remote: &str) -> SocketEntry {
+        SocketEntry {
+            protocol,
+            state,
+            local: local.parse().unwrap(),
+            remote: remote.parse().unwrap(),
+            stdio: false,
+            inode: None,
+        }
+    }
+
+    #[test]
+    fn test_classify_sockets() {
+        let stdio = SocketEntry {
+            stdio: true,
+            ..entry(
+                "tcp",
+                SocketState::Connected,
+                "127.0.0.1:51000",
+                "127.0.0.1:4444",
+            )
+        };
+        let entries = vec![
+            entry("tcp", SocketState::Listen, "0.0.0.0:8080", "0.0.0.0:0"),
+            entry(
+                "tcp",
+                SocketState::Connected,
+                "10.0.0.2:51001",
+                "93.184.216.34:443",
+            ),
+            entry(
+                "tcp",
+                SocketState::Other,
+                "10.0.0.2:51002",
+                "93.184.216.34:80",
+            ),
+            // Unconnected UDP socket
+            entry("udp", SocketState::Other, "0.0.0.0:5353", "0.0.0.0:0"),
+            stdio,
+        ];
+
+        let sockets = PidSockets::classify(7, entries.clone(), &NetMonConfig::new(7));
+        assert_eq!(
+            sockets.listeners,
+            vec![TrackedListener::new(
+                7,
+                "0.0.0.0".to_string(),
+                8080,
+                "tcp".to_string()
+            )]
+        );
+        // Loopback peers are not network connections, but still count on stdio
+        assert_eq!(
+            sockets.connections,
+            vec![OpenSocket {
+                conn: TrackedConnection::new(
+                    7,
+                    "93.184.216.34".to_string(),
+                    443,
+                    "tcp".to_string()
+                ),
+                local: "10.0.0.2:51001".parse().unwrap(),
+                inode: None,
+            }]
+        );
+        assert_eq!(
+            sockets.stdio_peer,
+            Some(TrackedConnection::new(
+                7,
+                "127.0.0.1".to_string(),
+                4444,
+                "tcp".to_string()
+            ))
+        );
+
+        let config = NetMonConfig::new(7).track_tcp(false);
+        assert_eq!(
+            PidSockets::classify(7, entries, &config),
+            PidSockets::default()
+        );
+    }
+
+    #[test]
+    fn test_parse_socket_link() {
+        assert_eq!(parse_socket_link("socket:[123456]"), Some(123456));
+        assert_eq!(parse_socket_link("pipe:[123456]"), None);
+        assert_eq!(parse_socket_link("/dev/null"), None);
+    }
+
+    #[cfg(target_endian = "little")]
+    #[test]
+    fn test_parse_proc_net() {
+        let tcp = "\
+  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 00000000:1F90 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 41001 1 0 100 0 0 10 0
+   1: 0100007F:C350 0500000A:115C 01 00000000:00000000 00:00000000 00000000  1000        0 41002 1 0 20 4 30 10 -1
+";
+        let parsed = parse_proc_net(tcp, "tcp");
+        assert_eq!(
+            parsed,
+            vec![
+                (
+                    41001,
+                    entry("tcp", SocketState::Listen, "0.0.0.0:8080", "0.0.0.0:0")
+                ),
+                (
+                    41002,
+                    entry(
+                        "tcp",
+                        SocketState::Connected,
+                        "127.0.0.1:50000",
+                        "10.0.0.5:4444"
+                    )
+                ),
+            ]
+        );
+
+        let tcp6 = "\
+  sl  local_address                         remote_address                        st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 00000000000000000000000001000000:0016 00000000000000000000000000000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 41003 1 0 100 0 0 10 0
+";
+        assert_eq!(
+            parse_proc_net(tcp6, "tcp"),
+            vec![(
+                41003,
+                entry("tcp", SocketState::Listen, "[::1]:22", "[::]:0")
+            )]
+        );
+
+        // UDP has no listen state; a TCP_LISTEN value there is just unconnected
+        let udp = "\
+  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode ref pointer drops
+   0: 00000000:14E9 00000000:0000 07 00000000:00000000 00:00000000 00000000     0        0 41004 2 0 0
+";
+        assert_eq!(
+            parse_proc_net(udp, "udp"),
+            vec![(
+                41004,
+                entry("udp", SocketState::Other, "0.0.0.0:5353", "0.0.0.0:0")
+            )]
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    fn wait_for_event(rx: &Receiver<Event>, pred: impl Fn(&Event) -> bool) -> Option<Event> {
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while std::time::Instant::now() < deadline {
+            match rx.recv_timeout(Duration::from_millis(100)) {
+                Ok(event) if pred(&event) => return Some(event),
+                _ => {}
+            }
+        }
+        None
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_monitor_reports_listener() {
+        let server = std::net::TcpListener::bind("0.0.0.0:0").unwrap();
+        let port = server.local_addr().unwrap().port();
+
+        let config = NetMonConfig::new(std::process::id()).poll_interval(Duration::from_millis(50));
+        let mut monitor = NetworkMonitor::new(config);
+        let rx = monitor.subscribe();
+        monitor.start().unwrap();
+
+        let event = wait_for_event(
+            &rx,
+            |e| matches!(&e.event_type, EventType::Listen { port: p, .. } if *p == port),
+        )
+        .expect("listener event");
+        assert_eq!(event.pid, std::process::id());
+        assert_eq!(event.risk_level, RiskLevel::Critical);
+        assert!(matches!(
+            &event.event_type,
+            EventType::Listen { address, .. } if address == "0.0.0.0"
+        ));
+        monitor.stop();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_monitor_reports_shell_with_socket_stdio() {
+        use std::os::fd::OwnedFd;
+        use std::process::{Command, Stdio};
+
+        let server = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = server.local_addr().unwrap().port();
+        let stream = std::net::TcpStream::connect(("127.0.0.1", port)).unwrap();
+        let _accepted = server.accept().unwrap();
+
+        let mut child = Command::new("sh")
+            .args(["-c", "sleep 5"])
+            .stdin(Stdio::from(OwnedFd::from(stream)))
+            .spawn()
+            .unwrap();
+
+        let config = NetMonConfig::new(child.id()).poll_interval(Duration::from_millis(50));
+        let mut monitor = NetworkMonitor::new(config);
+        let rx = monitor.subscribe();
+        monitor.start().unwrap();
+
+        let event = wait_for_event(&rx, |e| {
+            matches!(e.event_type, EventType::ReverseShell { .. })
+        });
+        monitor.stop();
+        let _ = child.kill();
+        let _ = child.wait();
+
+        let event = event.expect("reverse shell event");
+        assert_eq!(event.process, "sh");
+        assert_eq!(event.risk_level, RiskLevel::Critical);
+        assert_eq!(
+            event.event_type,
+            EventType::ReverseShell {
+                host: "127.0.0.1".to_string(),
+                port,
+                protocol: "tcp".to_string(),
+            }
+        );
+        // A loopback peer is not also reported as a network connection
+        assert!(rx.try_recv().is_err());
+    }
+
+    fn open_socket(host: &str, local_port: u16) -> OpenSocket {
+        OpenSocket {
+            conn: TrackedConnection::new(7, host.to_string(), 443, "tcp".to_string()),
+            local: SocketAddr::from(([10, 0, 0, 2], local_port)),
+            inode: None,
+        }
+    }
+
+    #[test]
+    fn test_live_connections_report_closed() {
+        let mut live = LiveConnections::default();
+        let t0 = Instant::now();
+        let traffic = |sent, received| Some(ByteCounts { sent, received });
+
+        let first = vec![
+            (
+                "curl".to_string(),
+                open_socket("203.0.113.9", 50001),
+                traffic(10, 20),
+            ),
+            ("curl".to_string(), open_socket("203.0.113.9", 50002), None),
+        ];
+        assert!(live
+            .update(first, t0, &ProviderCatalogue::default())
+            .is_empty());
+
+        // The first connection is still open but its counters could not be
+        // read; the second one is gone
+        let second = vec![("curl".to_string(), open_socket("203.0.113.9", 50001), None)];
+        let closed = live.update(
+            second,
+            t0 + Duration::from_secs(2),
+            &ProviderCatalogue::default(),
+        );
+        assert_eq!(
+            closed,
+            vec![ClosedConnection {
+                process: "curl".to_string(),
+                conn: open_socket("203.0.113.9", 50002).conn,
+                duration: Duration::from_secs(2),
+                traffic: None,
+                provider: None,
+            }]
+        );
+
+        let closed = live.close_all(t0 + Duration::from_secs(5));
+        assert_eq!(closed.len(), 1);
+        assert_eq!(closed[0].duration, Duration::from_secs(5));
+        assert_eq!(closed[0].traffic, traffic(10, 20));
+        assert!(live.open.is_empty());
+    }
+
+    #[test]
+    fn test_live_connections_keep_provider_from_first_sight() {
+        use crate::detector::{Provider, ProviderCategory};
+
+        let mut live = LiveConnections::default();
+        let t0 = Instant::now();
+        let catalogue = ProviderCatalogue::new(vec![Provider::new(
+            "203.0.113.9",
+            "Example LLM",
+            ProviderCategory::Llm,
+        )]);
+        let found = vec![("curl".to_string(), open_socket("203.0.113.9", 50001), None)];
+        assert!(live.update(found, t0, &catalogue).is_empty());
+
+        // Closed under a catalogue that no longer knows the address
+        let closed = live.close_all(t0 + Duration::from_secs(1));
+        assert_eq!(
+            closed[0].provider,
+            Some(ProviderTag {
+                name: "Example LLM".to_string(),
+                category: ProviderCategory::Llm,
+            })
+        );
+    }
+
+    #[test]
+    fn test_close_risk_level() {
+        let config = NetMonConfig::default().upload_alert_bytes(1000);
+        assert_eq!(config.close_risk_level(true, Some(5000)), RiskLevel::Low);
+        assert_eq!(config.close_risk_level(false, Some(999)), RiskLevel::Medium);
+        assert_eq!(config.close_risk_level(false, None), RiskLevel::Medium);
+        assert_eq!(
+            config.close_risk_level(false, Some(1000)),
+            RiskLevel::Critical
+        );
+
+        let config = config.upload_alert_bytes(0);
+        assert_eq!(
+            config.close_risk_level(false, Some(u64::MAX)),
+            RiskLevel::Medium
+        );
+    }
+
+    #[test]
+    fn test_close_event_fields() {
+        let config = NetMonConfig::default().upload_alert_bytes(1000);
+        let whitelist = Swappable::new(NetworkWhitelist::new(vec![], vec![]));
+        let event = NetworkMonitor::close_event(
+            &config,
+            &whitelist,
+            ClosedConnection {
+                process: "curl".to_string(),
+                conn: open_socket("203.0.113.9", 50001).conn,
+                duration: Duration::from_millis(1500),
+                traffic: Some(ByteCounts {
+                    sent: 4096,
+                    received: 12,
+                }),
+                provider: None,
+            },
+        );
+        assert_eq!(event.process, "curl");
+        assert_eq!(event.pid, 7);
+        assert_eq!(event.risk_level, RiskLevel::Critical);
+        assert_eq!(
+            event.event_type,
+            EventType::NetworkClose {
+                host: "203.0.113.9".to_string(),
+                port: 443,
+                protocol: "tcp".to_string(),
+                duration_ms: 1500,
+                bytes_sent: Some(4096),
+                bytes_received: Some(12),
+                provider: None,
+            }
+        );
+    }
+
+    /// A non-loopback address of this host, if it has one
+    #[cfg(target_os = "linux")]
+    fn local_interface_ip() -> Option<IpAddr> {
+        // Connecting a UDP socket picks a source address without sending
+        let socket = std::net::UdpSocket::bind("0.0.0.0:0").ok()?;
+        socket.connect("192.0.2.1:9").ok()?;
+        let ip = socket.local_addr().ok()?.ip();
+        (!ip.is_loopback() && !ip.is_unspecified()).then_some(ip)
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_monitor_reports_connection_close_with_traffic() {
+        use std::io::{Read, Write};
+
+        let Some(ip) = local_interface_ip() else {
+            eprintln!("skipping: no non-loopback interface");
+            return;
+        };
+        let server = std::net::TcpListener::bind((ip, 0)).unwrap();
+        let port = server.local_addr().unwrap().port();
+
+        let config = NetMonConfig::new(std::process::id())
+            .poll_interval(Duration::from_millis(50))
+            .upload_alert_bytes(4000);
+        let mut monitor =
+            NetworkMonitor::new(config).with_whitelist(NetworkWhitelist::new(vec![], vec![]));
+        let rx = monitor.subscribe();
+        monitor.start().unwrap();
+
+        let mut client = std::net::TcpStream::connect((ip, port)).unwrap();
+        let (mut accepted, _) = server.accept().unwrap();
+        client.write_all(&[1u8; 5000]).unwrap();
+        let mut buf = [0u8; 5000];
+        accepted.read_exact(&mut buf).unwrap();
+
+        let opened = wait_for_event(
+            &rx,
+            |e| matches!(&e.event_type, EventType::Network { port: p, .. } if *p == port),
+        );
+        assert!(opened.is_some(), "connection never reported");
+        // Let a poll read the counters after the upload
+        std::thread::sleep(Duration::from_millis(200));
+        drop(client);
+        drop(accepted);
+
+        let closed = wait_for_event(
+            &rx,
+            |e| matches!(&e.event_type, EventType::NetworkClose { port: p, .. } if *p == port),
+        );
+        monitor.stop();
+
+        let closed = closed.expect("close never reported");
+        assert_eq!(closed.pid, std::process::id());
+        assert_eq!(closed.risk_level, RiskLevel::Critical);
+        let EventType::NetworkClose {
+            duration_ms,
+            bytes_sent,
+            bytes_received,
+            ..
+        } = closed.event_type
+        else {
+            unreachable!()
+        };
+        assert!(duration_ms >= 200, "duration {duration_ms}");
+        assert!(
+            bytes_sent.is_some_and(|sent| sent >= 5000),
+            "{bytes_sent:?}"
+        );
+        assert_eq!(bytes_received, Some(0));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_traffic_counts_each_socket_to_an_endpoint() {
+        use crate::detector::ProviderCategory;
+        use crate::traffic::TrafficStats;
+
+        let Some(ip) = local_interface_ip() else {
+            eprintln!("skipping: no non-loopback interface");
+            return;
+        };
+        let server = std::net::TcpListener::bind((ip, 0)).unwrap();
+        let port = server.local_addr().unwrap().port();
+
+        let config = NetMonConfig::new(std::process::id()).poll_interval(Duration::from_millis(50));
+        let mut monitor =
+            NetworkMonitor::new(config).with_whitelist(NetworkWhitelist::new(vec![], vec![]));
+        let rx = monitor.subscribe();
+        monitor.start().unwrap();
+
+        let first = std::net::TcpStream::connect((ip, port)).unwrap();
+        let second = std::net::TcpStream::connect((ip, port)).unwrap();
+        let accepted = (server.accept().unwrap(), server.accept().unwrap());
+        // Let a poll see both sockets before they close
+        std::thread::sleep(Duration::from_millis(200));
+        drop((first, second, accepted));
+        std::thread::sleep(Duration::from_millis(200));
+        monitor.stop();
+
+        let mut traffic = TrafficStats::new();
+        let mut opens = 0;
+        for event in rx.try_iter() {
+            match &event.event_type {
+                EventType::Network { port: p, .. } if *p == port => opens += 1,
+                EventType::NetworkClose { port: p, .. } if *p == port => {}
+                _ => continue,
+            }
+            traffic.observe(&event);
+        }
+        // The open is reported once per destination, each socket's close once
+        assert_eq!(opens, 1);
+        let summary = traffic.summary();
+        assert_eq!(summary.get(ProviderCategory::Other).unwrap().requests, 2);
+    }
+
     #[cfg(target_os = "macos")]
     #[test]
     fn test_monitor_start_stop() {
@@ -785,7 +1915,7 @@ mod tests {
     #[test]
     fn test_monitor_drop_stops() {
         let config = NetMonConfig::default();
-        let mut monitor = NetworkMonitor::new(config);
+        let monitor = NetworkMonitor::new(config);
         let _ = monitor.subscribe();
 
         drop(monitor);
@@ -872,7 +2002,7 @@ mod tests {
     fn test_report_connection_with_whitelist_filtering() {
         let config = NetMonConfig::default();
         let whitelist = NetworkWhitelist::new(vec!["allowed.com".to_string()], vec![]);
-        let mut monitor = NetworkMonitor::new(config).with_whitelist(whitelist);
+        let monitor = NetworkMonitor::new(config).with_whitelist(whitelist);
         let rx = monitor.subscribe();
 
         // Allowed host
@@ -892,10 +2022,37 @@ mod tests {
         assert_eq!(event.risk_level, RiskLevel::High);
     }
 
+    #[test]
+    fn test_set_whitelist_applies_to_new_connections() {
+        let monitor = NetworkMonitor::new(NetMonConfig::default())
+            .with_whitelist(NetworkWhitelist::new(vec![], vec![]));
+        let rx = monitor.subscribe();
+
+        monitor.report_connection(TrackedConnection::new(
+            1,
+            "example.com".to_string(),
+            443,
+            "tcp".to_string(),
+        ));
+        assert_eq!(rx.try_recv().unwrap().risk_level, RiskLevel::High);
+
+        monitor.set_whitelist(NetworkWhitelist::new(
+            vec!["example.com".to_string()],
+            vec![],
+        ));
+        monitor.report_connection(TrackedConnection::new(
+            1,
+            "api.example.com".to_string(),
+            443,
+            "tcp".to_string(),
+        ));
+        assert_eq!(rx.try_recv().unwrap().risk_level, RiskLevel::Medium);
+    }
+
     #[test]
     fn test_report_connection_deduplication() {
         let config = NetMonConfig::default();
-        let mut monitor = NetworkMonitor::new(config);
+        let monitor = NetworkMonitor::new(config);
         let rx = monitor.subscribe();
 
         let conn =
@@ -944,7 +2101,7 @@ mod tests {
 
         // Add extra PIDs before starting
         monitor.add_pid(99999);
-        assert!(monitor.tracked_pids.lock().unwrap().contains(&99999));
+        assert!(monitor.tracked_pids.contains(99999));
 
         monitor.start().unwrap();
         std::thread::sleep(Duration::from_millis(50));
@@ -952,10 +2109,10 @@ mod tests {
 
         // Can add/remove PIDs while running
         monitor.add_pid(99998);
-        assert!(monitor.tracked_pids.lock().unwrap().contains(&99998));
+        assert!(monitor.tracked_pids.contains(99998));
 
         monitor.remove_pid(99999);
-        assert!(!monitor.tracked_pids.lock().unwrap().contains(&99999));
+        assert!(!monitor.tracked_pids.contains(99999));
 
         monitor.stop();
         assert!(!monitor.is_running());
@@ -1,13 +1,31 @@
 //! Process tree tracking module
 //!
-//! Monitors child processes spawned by the wrapped process using libproc.
-//! Polls at configurable intervals to detect new and exited processes.
-
-use crate::event::RiskLevel;
+//! Monitors child processes spawned by the wrapped process using libproc on
+//! macOS and `/proc` on Linux. Polls at configurable intervals to detect new
+//! and exited processes.
+//!
+//! Polling misses anything that finishes between two polls. On Linux the
+//! tracker therefore first tries push-based sources from
+//! [`crate::procevents`]: the netlink proc connector, then ptrace. Polling is
+//! the last resort, and every event says which backend observed it.
+//!
+//! CPU time and resident memory are sampled on every poll, so an exit event
+//! reports how long the process ran, its CPU time and its peak RSS. A process
+//! that has exited but not been reaped yet is reported as exited right away,
+//! while its exit code can still be read.
+
+use crate::bus::{BusEvent, EventBus, SubscribeOptions};
+use crate::event::{ExitStats, RiskLevel};
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+use crate::execmon::{list_processes, ProcEntry};
+#[cfg(target_os = "linux")]
+use crate::procevents::{ProcConnector, ProcEvent, PtraceSession};
 use crate::risk::RiskScorer;
-use std::collections::HashMap;
+use crate::types::{Swappable, TrackerBackend};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::mpsc::Sender;
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
 use std::sync::{Arc, Mutex};
 use std::thread::{self, JoinHandle};
 use std::time::{Duration, Instant};
@@ -15,9 +33,9 @@ use std::time::{Duration, Instant};
 #[cfg(target_os = "macos")]
 use libproc::bsd_info::BSDInfo;
 #[cfg(target_os = "macos")]
-use libproc::proc_pid::{pidinfo, pidpath};
+use libproc::pid_rusage::{pidrusage, RUsageInfoV2};
 #[cfg(target_os = "macos")]
-use libproc::processes::{pids_by_type, ProcFilter};
+use libproc::proc_pid::{pidinfo, pidpath};
 
 /// Information about a tracked process
 #[derive(Debug, Clone)]
@@ -34,6 +52,45 @@ pub struct TrackedProcess {
     pub detected_at: Instant,
     /// Risk level of the command
     pub risk_level: RiskLevel,
+    /// CPU time used so far (user plus system)
+    pub cpu_time: Option<Duration>,
+    /// Highest resident set size observed so far
+    pub peak_rss_bytes: Option<u64>,
+    /// No longer a descendant of the root by parent PID, e.g. after
+    /// daemonizing, but still attributed to the tree
+    pub detached: bool,
+}
+
+impl TrackedProcess {
+    /// Fold a new usage sample into the running totals
+    fn record_usage(&mut self, sample: UsageSample) {
+        // CPU time only grows; keep the last reading if this one failed
+        self.cpu_time = sample.cpu_time.or(self.cpu_time);
+        self.peak_rss_bytes = match (self.peak_rss_bytes, sample.rss_bytes) {
+            (Some(peak), Some(rss)) => Some(peak.max(rss)),
+            (peak, rss) => peak.or(rss),
+        };
+    }
+
+    /// Exit statistics for this process as of now
+    fn exit_stats(&self, exit_code: Option<i32>) -> ExitStats {
+        ExitStats {
+            exit_code,
+            duration_ms: Some(self.detected_at.elapsed().as_millis() as u64),
+            cpu_time_ms: self.cpu_time.map(|t| t.as_millis() as u64),
+            peak_rss_bytes: self.peak_rss_bytes,
+            command_id: None,
+        }
+    }
+}
+
+/// CPU and memory reading for one process
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct UsageSample {
+    /// User plus system CPU time
+    cpu_time: Option<Duration>,
+    /// Resident set size, or its peak where the platform reports one
+    rss_bytes: Option<u64>,
 }
 
 /// Event emitted by the process tracker
@@ -46,9 +103,40 @@ pub enum TrackerEvent {
         name: String,
         path: Option<String>,
         risk_level: RiskLevel,
+        backend: TrackerBackend,
     },
     /// Child process exited
-    ChildExited { pid: u32 },
+    ChildExited {
+        pid: u32,
+        exit: ExitStats,
+        backend: TrackerBackend,
+    },
+    /// Tracked process left the parent-PID tree of the root, now a child
+    /// of `ppid`. It stays tracked.
+    ChildDetached {
+        pid: u32,
+        ppid: u32,
+        name: String,
+        backend: TrackerBackend,
+    },
+}
+
+impl BusEvent for TrackerEvent {
+    fn kind(&self) -> &'static str {
+        match self {
+            TrackerEvent::ChildStarted { .. } => "child_started",
+            TrackerEvent::ChildExited { .. } => "child_exited",
+            TrackerEvent::ChildDetached { .. } => "child_detached",
+        }
+    }
+
+    fn risk_level(&self) -> Option<RiskLevel> {
+        match self {
+            TrackerEvent::ChildStarted { risk_level, .. } => Some(*risk_level),
+            TrackerEvent::ChildExited { .. } => None,
+            TrackerEvent::ChildDetached { .. } => Some(RiskLevel::High),
+        }
+    }
 }
 
 /// Configuration for the process tracker
@@ -60,6 +148,12 @@ pub struct TrackerConfig {
     pub poll_interval: Duration,
     /// Maximum tree depth (None for unlimited)
     pub max_depth: Option<usize>,
+    /// Push backends to try, in order, before falling back to polling
+    pub backends: Vec<TrackerBackend>,
+    /// cgroup holding the tree, used to find descendants that were
+    /// reparented away from the root. Without one, processes in the root's
+    /// session or process group are used instead.
+    pub cgroup: Option<PathBuf>,
 }
 
 impl Default for TrackerConfig {
@@ -68,6 +162,12 @@ impl Default for TrackerConfig {
             root_pid: 0,
             poll_interval: Duration::from_millis(100),
             max_depth: Some(10),
+            backends: if cfg!(target_os = "linux") {
+                vec![TrackerBackend::ProcConnector, TrackerBackend::Ptrace]
+            } else {
+                Vec::new()
+            },
+            cgroup: None,
         }
     }
 }
@@ -92,20 +192,34 @@ impl TrackerConfig {
         self.max_depth = depth;
         self
     }
+
+    /// Set the push backends to try before polling; empty to always poll
+    pub fn backends(mut self, backends: Vec<TrackerBackend>) -> Self {
+        self.backends = backends;
+        self
+    }
+
+    /// Set the cgroup holding the tree
+    pub fn cgroup(mut self, cgroup: Option<PathBuf>) -> Self {
+        self.cgroup = cgroup;
+        self
+    }
 }
 
 /// Process tree tracker using libproc
 pub struct ProcessTracker {
     config: TrackerConfig,
-    risk_scorer: RiskScorer,
+    risk_scorer: Swappable<RiskScorer>,
     /// Currently tracked processes (pid -> TrackedProcess)
     tracked: Arc<Mutex<HashMap<u32, TrackedProcess>>>,
-    /// Event sender
-    event_tx: Option<Sender<TrackerEvent>>,
+    /// Subscribers to tracker events
+    events: EventBus<TrackerEvent>,
     /// Stop flag
     stop_flag: Arc<AtomicBool>,
     /// Worker thread handle
     thread_handle: Option<JoinHandle<()>>,
+    /// Backend the running tracker settled on
+    backend: TrackerBackend,
 }
 
 impl ProcessTracker {
@@ -113,25 +227,40 @@ impl ProcessTracker {
     pub fn new(config: TrackerConfig) -> Self {
         Self {
             config,
-            risk_scorer: RiskScorer::new(),
+            risk_scorer: Swappable::new(RiskScorer::new()),
             tracked: Arc::new(Mutex::new(HashMap::new())),
-            event_tx: None,
+            events: EventBus::new(),
             stop_flag: Arc::new(AtomicBool::new(false)),
             thread_handle: None,
+            backend: TrackerBackend::Polling,
         }
     }
 
     /// Set a custom risk scorer
     pub fn with_risk_scorer(mut self, scorer: RiskScorer) -> Self {
+        self.risk_scorer = Swappable::new(scorer);
+        self
+    }
+
+    /// Share a risk scorer handle that can be swapped while the tracker runs
+    pub fn with_shared_risk_scorer(mut self, scorer: Swappable<RiskScorer>) -> Self {
         self.risk_scorer = scorer;
         self
     }
 
+    /// Replace the risk scorer used for newly detected processes
+    pub fn set_risk_scorer(&self, scorer: RiskScorer) {
+        self.risk_scorer.store(scorer);
+    }
+
     /// Subscribe to tracker events
-    pub fn subscribe(&mut self) -> std::sync::mpsc::Receiver<TrackerEvent> {
-        let (tx, rx) = std::sync::mpsc::channel();
-        self.event_tx = Some(tx);
-        rx
+    pub fn subscribe(&self) -> Receiver<TrackerEvent> {
+        self.events.subscribe()
+    }
+
+    /// Subscribe with a custom buffer, lag policy and filter
+    pub fn subscribe_with(&self, options: SubscribeOptions) -> Receiver<TrackerEvent> {
+        self.events.subscribe_with(options)
     }
 
     /// Start the tracking thread
@@ -139,16 +268,24 @@ impl ProcessTracker {
         let config = self.config.clone();
         let tracked = Arc::clone(&self.tracked);
         let stop_flag = Arc::clone(&self.stop_flag);
-        let event_tx = self.event_tx.clone();
+        let events = self.events.clone();
         let risk_scorer = self.risk_scorer.clone();
+        let (ready_tx, ready_rx) = sync_channel(1);
 
         let handle = thread::spawn(move || {
-            Self::tracking_loop(config, tracked, stop_flag, event_tx, risk_scorer);
+            Self::tracking_loop(config, tracked, stop_flag, events, risk_scorer, ready_tx);
         });
 
+        // The backend is chosen on the tracking thread, which ptrace needs
+        self.backend = ready_rx.recv().unwrap_or(TrackerBackend::Polling);
         self.thread_handle = Some(handle);
     }
 
+    /// Backend observing the tree; polling until the tracker has started
+    pub fn backend(&self) -> TrackerBackend {
+        self.backend
+    }
+
     /// Stop the tracking thread
     pub fn stop(&mut self) {
         self.stop_flag.store(true, Ordering::Relaxed);
@@ -156,6 +293,8 @@ impl ProcessTracker {
         if let Some(handle) = self.thread_handle.take() {
             let _ = handle.join();
         }
+        // Subscribers see a disconnect once no more events can arrive
+        self.events.close();
     }
 
     /// Signal the tracker to stop without waiting for the thread to finish.
@@ -169,6 +308,11 @@ impl ProcessTracker {
         self.thread_handle.is_some() && !self.stop_flag.load(Ordering::Relaxed)
     }
 
+    /// Name of a running process, or `None` if there is no such process
+    pub fn process_name(pid: u32) -> Option<String> {
+        Self::get_process_info(pid, &RiskScorer::new()).map(|process| process.name)
+    }
+
     /// Get currently tracked processes
     pub fn get_tracked(&self) -> Vec<TrackedProcess> {
         if let Ok(tracked) = self.tracked.lock() {
@@ -192,86 +336,190 @@ impl ProcessTracker {
         config: TrackerConfig,
         tracked: Arc<Mutex<HashMap<u32, TrackedProcess>>>,
         stop_flag: Arc<AtomicBool>,
-        event_tx: Option<Sender<TrackerEvent>>,
-        risk_scorer: RiskScorer,
+        events: EventBus<TrackerEvent>,
+        risk_scorer: Swappable<RiskScorer>,
+        ready: SyncSender<TrackerBackend>,
     ) {
+        #[cfg(target_os = "linux")]
+        if let Some(source) = PushSource::open(&config) {
+            Self::push_loop(
+                config,
+                source,
+                tracked,
+                stop_flag,
+                events,
+                risk_scorer,
+                ready,
+            );
+            return;
+        }
+        let _ = ready.send(TrackerBackend::Polling);
+
         loop {
             // Check stop flag
             if stop_flag.load(Ordering::Relaxed) {
                 break;
             }
 
-            // Scan for processes
-            Self::scan_processes(&config, &tracked, &event_tx, &risk_scorer);
+            // Scan for processes with the scorer current at this cycle
+            let scorer = risk_scorer.load();
+            Self::scan_processes(&config, &tracked, &events, &scorer);
 
             // Sleep for poll interval
             thread::sleep(config.poll_interval);
         }
     }
 
-    /// Build a parent→children map from all system PIDs using a single pass of
-    /// pidinfo syscalls. Returns the map keyed by parent PID.
-    #[cfg(target_os = "macos")]
-    fn build_children_map() -> HashMap<u32, Vec<u32>> {
-        let all_pids = match pids_by_type(ProcFilter::All) {
-            Ok(pids) => pids,
-            Err(_) => return HashMap::new(),
-        };
+    /// Tracking loop fed by a push source
+    #[cfg(target_os = "linux")]
+    fn push_loop(
+        config: TrackerConfig,
+        mut source: PushSource,
+        tracked: Arc<Mutex<HashMap<u32, TrackedProcess>>>,
+        stop_flag: Arc<AtomicBool>,
+        events: EventBus<TrackerEvent>,
+        risk_scorer: Swappable<RiskScorer>,
+        ready: SyncSender<TrackerBackend>,
+    ) {
+        // Pick up what is already running; anything started from now on
+        // arrives as an event
+        Self::scan_processes(&config, &tracked, &events, &risk_scorer.load());
+        if let PushSource::Ptrace(session) = &mut source {
+            let pids: Vec<u32> = tracked
+                .lock()
+                .map(|t| t.keys().copied().collect())
+                .unwrap_or_default();
+            for pid in pids {
+                session.follow(pid);
+            }
+        }
+
+        let _ = ready.send(source.backend());
+
+        let mut state = PushState::new(&config, source.backend(), &tracked, &events);
+        while !stop_flag.load(Ordering::Relaxed) {
+            let scorer = risk_scorer.load();
+            match source.next(config.poll_interval) {
+                Ok(batch) => {
+                    for event in batch {
+                        state.handle(event, &scorer);
+                    }
+                }
+                Err(e) => {
+                    // Events were lost; catch up from the process table
+                    state.pending.clear();
+                    Self::scan_processes(&config, &tracked, &events, &scorer);
+                    if e.raw_os_error() != Some(libc::ENOBUFS) {
+                        thread::sleep(config.poll_interval);
+                    }
+                }
+            }
+            state.flush();
+        }
+    }
 
+    /// Build a parent→children map from one listing of all system processes.
+    /// Returns the map keyed by parent PID.
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
+    fn build_children_map(processes: &[ProcEntry]) -> HashMap<u32, Vec<u32>> {
         let mut children_map: HashMap<u32, Vec<u32>> = HashMap::new();
-        for pid in all_pids {
-            if pid == 0 {
+        for process in processes {
+            if process.pid == 0 {
                 continue;
             }
-            if let Ok(info) = pidinfo::<BSDInfo>(pid as i32, 0) {
-                children_map.entry(info.pbi_ppid).or_default().push(pid);
-            }
+            children_map
+                .entry(process.ppid)
+                .or_default()
+                .push(process.pid);
         }
         children_map
     }
 
     /// Scan for new and exited processes
-    #[cfg(target_os = "macos")]
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
     fn scan_processes(
         config: &TrackerConfig,
         tracked: &Arc<Mutex<HashMap<u32, TrackedProcess>>>,
-        event_tx: &Option<Sender<TrackerEvent>>,
+        events: &EventBus<TrackerEvent>,
         risk_scorer: &RiskScorer,
     ) {
-        // Build the children map once per scan (single pass of pidinfo syscalls)
-        let children_map = Self::build_children_map();
+        // List processes and build the children map once per scan
+        let processes = list_processes();
+        let children_map = Self::build_children_map(&processes);
 
-        // Get all descendant PIDs using the pre-built map
-        let descendants =
-            Self::get_descendants_from_map(&children_map, config.root_pid, config.max_depth);
+        // Exited processes stay listed until reaped. Count them as gone so
+        // their exit code is reported while it can still be read.
+        let exit_codes: HashMap<u32, Option<i32>> = processes
+            .iter()
+            .filter_map(|p| p.exited.map(|code| (p.pid, code)))
+            .collect();
+
+        // Current parent of every live process
+        let parents: HashMap<u32, u32> = processes
+            .iter()
+            .filter(|p| !exit_codes.contains_key(&p.pid))
+            .map(|p| (p.pid, p.ppid))
+            .collect();
 
         // Phase 1: Find which PIDs are new and which have exited (short lock)
-        let (new_pids, exited_pids) = {
+        let (new_pids, tracked_pids, exited_pids) = {
             let tracked_guard = match tracked.lock() {
                 Ok(guard) => guard,
                 Err(_) => return,
             };
 
-            let new_pids: Vec<u32> = descendants
+            let tracked_pids: Vec<u32> = tracked_guard.keys().copied().collect();
+
+            // All live descendant PIDs using the pre-built map, plus those
+            // of processes that were reparented away but still belong to
+            // the tree
+            let root = processes.iter().find(|p| p.pid == config.root_pid);
+            let mut anchors = Membership::of(config, root).members(&processes);
+            anchors.extend(tracked_pids.iter().copied());
+            let mut attributed: HashSet<u32> =
+                Self::get_descendants_from_map(&children_map, config.root_pid, config.max_depth)
+                    .into_iter()
+                    .collect();
+            for anchor in anchors {
+                if attributed.contains(&anchor) || !parents.contains_key(&anchor) {
+                    continue;
+                }
+                attributed.insert(anchor);
+                attributed.extend(Self::get_descendants_from_map(
+                    &children_map,
+                    anchor,
+                    config.max_depth,
+                ));
+            }
+            attributed.retain(|pid| parents.contains_key(pid));
+
+            let new_pids: Vec<u32> = attributed
                 .iter()
                 .filter(|pid| !tracked_guard.contains_key(pid))
                 .copied()
                 .collect();
 
-            let exited_pids: Vec<u32> = tracked_guard
-                .keys()
-                .filter(|pid| !descendants.contains(pid))
+            let exited_pids: Vec<u32> = tracked_pids
+                .iter()
+                .filter(|pid| !attributed.contains(pid))
                 .copied()
                 .collect();
 
-            (new_pids, exited_pids)
+            (new_pids, tracked_pids, exited_pids)
         }; // Lock released here
 
-        // Phase 2: Get process info for new PIDs (slow syscalls, no lock held)
+        // Phase 2: Get process info for new PIDs and sample usage of every
+        // tracked one, including those that just exited (slow syscalls, no
+        // lock held)
         let new_processes: Vec<TrackedProcess> = new_pids
             .iter()
             .filter_map(|pid| Self::get_process_info(*pid, risk_scorer))
             .collect();
+        let samples: Vec<(u32, UsageSample)> = tracked_pids
+            .iter()
+            .chain(new_processes.iter().map(|p| &p.pid))
+            .map(|&pid| (pid, sample_usage(pid)))
+            .collect();
 
         // Phase 3: Update tracked map and emit events (short lock)
         {
@@ -281,42 +529,60 @@ impl ProcessTracker {
             };
 
             for process in new_processes {
-                if let Some(tx) = event_tx {
-                    let _ = tx.send(TrackerEvent::ChildStarted {
-                        pid: process.pid,
-                        ppid: process.ppid,
-                        name: process.name.clone(),
-                        path: process.path.clone(),
-                        risk_level: process.risk_level,
-                    });
-                }
+                events.publish(TrackerEvent::ChildStarted {
+                    pid: process.pid,
+                    ppid: process.ppid,
+                    name: process.name.clone(),
+                    path: process.path.clone(),
+                    risk_level: process.risk_level,
+                    backend: TrackerBackend::Polling,
+                });
                 tracked_guard.insert(process.pid, process);
             }
 
-            for pid in exited_pids {
-                tracked_guard.remove(&pid);
-                if let Some(tx) = event_tx {
-                    let _ = tx.send(TrackerEvent::ChildExited { pid });
+            for (pid, sample) in samples {
+                if let Some(process) = tracked_guard.get_mut(&pid) {
+                    process.record_usage(sample);
                 }
             }
+
+            for pid in exited_pids {
+                let Some(process) = tracked_guard.remove(&pid) else {
+                    continue;
+                };
+                let exit = process.exit_stats(exit_codes.get(&pid).copied().flatten());
+                events.publish(TrackerEvent::ChildExited {
+                    pid,
+                    exit,
+                    backend: TrackerBackend::Polling,
+                });
+            }
+
+            report_detached(
+                config.root_pid,
+                &mut tracked_guard,
+                &parents,
+                events,
+                TrackerBackend::Polling,
+            );
         }
     }
 
-    /// Non-macOS stub
-    #[cfg(not(target_os = "macos"))]
+    /// Stub for platforms without process listing
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
     fn scan_processes(
         _config: &TrackerConfig,
         _tracked: &Arc<Mutex<HashMap<u32, TrackedProcess>>>,
-        _event_tx: &Option<Sender<TrackerEvent>>,
+        _events: &EventBus<TrackerEvent>,
         _risk_scorer: &RiskScorer,
     ) {
-        // No-op on non-macOS platforms
+        // No-op on other platforms
     }
 
     /// Get all descendant PIDs of a process using a pre-built children map.
     /// This avoids redundant pidinfo syscalls when called from scan_processes,
     /// which already builds the map once per scan cycle.
-    #[cfg(target_os = "macos")]
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
     fn get_descendants_from_map(
         children_map: &HashMap<u32, Vec<u32>>,
         root_pid: u32,
@@ -344,14 +610,14 @@ impl ProcessTracker {
     /// Get all descendant PIDs of a process (standalone version).
     /// Builds its own children map internally. Used by tests.
     #[cfg(test)]
-    #[cfg(target_os = "macos")]
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
     fn get_descendants(root_pid: u32, max_depth: Option<usize>) -> Vec<u32> {
-        let children_map = Self::build_children_map();
+        let children_map = Self::build_children_map(&list_processes());
         Self::get_descendants_from_map(&children_map, root_pid, max_depth)
     }
 
     #[cfg(test)]
-    #[cfg(not(target_os = "macos"))]
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
     fn get_descendants(_root_pid: u32, _max_depth: Option<usize>) -> Vec<u32> {
         Vec::new()
     }
@@ -390,15 +656,542 @@ impl ProcessTracker {
             path,
             detected_at: Instant::now(),
             risk_level,
+            cpu_time: None,
+            peak_rss_bytes: None,
+            detached: false,
+        })
+    }
+
+    /// Get process information
+    #[cfg(target_os = "linux")]
+    fn get_process_info(pid: u32, risk_scorer: &RiskScorer) -> Option<TrackedProcess> {
+        let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+        let entry = crate::execmon::parse_proc_stat(pid, &stat)?;
+        let name = std::fs::read_to_string(format!("/proc/{pid}/comm"))
+            .ok()?
+            .trim_end()
+            .to_string();
+        let path = std::fs::read_link(format!("/proc/{pid}/exe"))
+            .ok()
+            .map(|p| p.to_string_lossy().into_owned());
+
+        // Score the command
+        let (risk_level, _) = risk_scorer.score(&name, &[]);
+
+        Some(TrackedProcess {
+            pid,
+            ppid: entry.ppid,
+            name,
+            path,
+            detected_at: Instant::now(),
+            risk_level,
+            cpu_time: None,
+            peak_rss_bytes: None,
+            detached: false,
         })
     }
 
-    #[cfg(not(target_os = "macos"))]
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
     fn get_process_info(_pid: u32, _risk_scorer: &RiskScorer) -> Option<TrackedProcess> {
         None
     }
 }
 
+/// What makes a process part of the tree regardless of its parent, so
+/// descendants that were reparented away are still attributed to it
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Membership {
+    /// Processes in the tree's cgroup, which holds the root too
+    Cgroup { path: PathBuf, root: u32 },
+    /// Processes in the session the root leads
+    Session(u32),
+    /// Processes in the process group the root leads
+    Group(u32),
+    /// Parent PIDs only
+    None,
+}
+
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+impl Membership {
+    /// The configured cgroup or, without one, the session or process group
+    /// of the root if it leads one. `root` is its entry in the process table.
+    fn of(config: &TrackerConfig, root: Option<&ProcEntry>) -> Self {
+        match (&config.cgroup, root) {
+            (Some(path), _) => Membership::Cgroup {
+                path: path.clone(),
+                root: config.root_pid,
+            },
+            (None, Some(root)) if root.sid == root.pid => Membership::Session(root.pid),
+            (None, Some(root)) if root.pgid == root.pid => Membership::Group(root.pid),
+            _ => Membership::None,
+        }
+    }
+
+    /// Members among `processes`, other than the root and this process
+    fn members(&self, processes: &[ProcEntry]) -> Vec<u32> {
+        let own = std::process::id();
+        let members: Vec<u32> = match self {
+            Membership::Cgroup { path, .. } => crate::cgroup::read_members(path),
+            Membership::Session(sid) => processes
+                .iter()
+                .filter(|p| p.sid == *sid)
+                .map(|p| p.pid)
+                .collect(),
+            Membership::Group(pgid) => processes
+                .iter()
+                .filter(|p| p.pgid == *pgid)
+                .map(|p| p.pid)
+                .collect(),
+            Membership::None => return Vec::new(),
+        };
+        members
+            .into_iter()
+            .filter(|&pid| pid != own && !self.is_root(pid))
+            .collect()
+    }
+
+    /// Whether a single running process is a member
+    #[cfg(target_os = "linux")]
+    fn contains(&self, pid: u32) -> bool {
+        if pid == std::process::id() || self.is_root(pid) {
+            return false;
+        }
+        let entry = || {
+            let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+            crate::execmon::parse_proc_stat(pid, &stat)
+        };
+        match self {
+            Membership::Cgroup { path, .. } => crate::cgroup::read_members(path).contains(&pid),
+            Membership::Session(sid) => entry().is_some_and(|e| e.sid == *sid),
+            Membership::Group(pgid) => entry().is_some_and(|e| e.pgid == *pgid),
+            Membership::None => false,
+        }
+    }
+
+    fn is_root(&self, pid: u32) -> bool {
+        match self {
+            Membership::Cgroup { root, .. }
+            | Membership::Session(root)
+            | Membership::Group(root) => *root == pid,
+            Membership::None => false,
+        }
+    }
+}
+
+/// Push-based source of process events
+#[cfg(target_os = "linux")]
+enum PushSource {
+    Connector(ProcConnector),
+    Ptrace(PtraceSession),
+}
+
+#[cfg(target_os = "linux")]
+impl PushSource {
+    /// First configured backend that can observe the tree, if any
+    fn open(config: &TrackerConfig) -> Option<Self> {
+        config.backends.iter().find_map(|backend| match backend {
+            TrackerBackend::ProcConnector => ProcConnector::open(config.poll_interval)
+                .map(Self::Connector)
+                .ok(),
+            TrackerBackend::Ptrace => PtraceSession::attach(config.root_pid)
+                .map(Self::Ptrace)
+                .ok(),
+            TrackerBackend::Polling => None,
+        })
+    }
+
+    fn backend(&self) -> TrackerBackend {
+        match self {
+            Self::Connector(_) => TrackerBackend::ProcConnector,
+            Self::Ptrace(_) => TrackerBackend::Ptrace,
+        }
+    }
+
+    /// Events that arrived within `timeout`; an error means some were lost
+    fn next(&mut self, timeout: Duration) -> std::io::Result<Vec<ProcEvent>> {
+        match self {
+            Self::Connector(connector) => connector.recv(),
+            Self::Ptrace(session) => Ok(session.poll(timeout)),
+        }
+    }
+}
+
+/// Tracked tree as maintained from push events
+#[cfg(target_os = "linux")]
+struct PushState<'a> {
+    config: &'a TrackerConfig,
+    backend: TrackerBackend,
+    tracked: &'a Mutex<HashMap<u32, TrackedProcess>>,
+    events: &'a EventBus<TrackerEvent>,
+    /// Forked children that have not called exec yet. They are reported
+    /// once they exec, exit or outlive a poll interval, so a fork followed
+    /// by exec shows up once, under the program it runs.
+    pending: HashMap<u32, TrackedProcess>,
+    /// How to recognize processes whose parent is outside the tree
+    membership: Membership,
+    /// Members first seen as the child of a process outside the tree. Their
+    /// own children are counted from them, as from the root.
+    anchors: HashSet<u32>,
+    /// When usage was last sampled
+    last_flush: Instant,
+}
+
+#[cfg(target_os = "linux")]
+impl<'a> PushState<'a> {
+    fn new(
+        config: &'a TrackerConfig,
+        backend: TrackerBackend,
+        tracked: &'a Mutex<HashMap<u32, TrackedProcess>>,
+        events: &'a EventBus<TrackerEvent>,
+    ) -> Self {
+        let root = std::fs::read_to_string(format!("/proc/{}/stat", config.root_pid))
+            .ok()
+            .and_then(|stat| crate::execmon::parse_proc_stat(config.root_pid, &stat));
+        Self {
+            config,
+            backend,
+            tracked,
+            events,
+            pending: HashMap::new(),
+            membership: Membership::of(config, root.as_ref()),
+            anchors: HashSet::new(),
+            last_flush: Instant::now(),
+        }
+    }
+
+    fn handle(&mut self, event: ProcEvent, risk_scorer: &RiskScorer) {
+        match event {
+            ProcEvent::Fork { ppid, pid } => self.on_fork(ppid, pid, risk_scorer),
+            ProcEvent::Exec { pid } => self.on_exec(pid, risk_scorer),
+            ProcEvent::Exit { pid, exit_code } => self.on_exit(pid, exit_code),
+        }
+    }
+
+    fn on_fork(&mut self, ppid: u32, pid: u32, risk_scorer: &RiskScorer) {
+        if self.pending.contains_key(&pid) || self.is_tracked(pid) {
+            return;
+        }
+        let parent_depth = match self.depth(ppid) {
+            Some(depth) => depth,
+            // Forked by a member that went unseen, e.g. one that was
+            // reparented before tracking started
+            None if self.membership.contains(pid) => {
+                self.anchors.insert(pid);
+                0
+            }
+            None => return,
+        };
+        if self.config.max_depth.is_some_and(|max| parent_depth > max) {
+            return;
+        }
+        // Until it calls exec the child still runs its parent's program
+        let mut process =
+            ProcessTracker::get_process_info(pid, risk_scorer).unwrap_or_else(|| TrackedProcess {
+                pid,
+                ppid,
+                name: format!("pid:{pid}"),
+                path: None,
+                detected_at: Instant::now(),
+                risk_level: RiskLevel::Low,
+                cpu_time: None,
+                peak_rss_bytes: None,
+                detached: false,
+            });
+        process.ppid = ppid;
+        self.pending.insert(pid, process);
+    }
+
+    fn on_exec(&mut self, pid: u32, risk_scorer: &RiskScorer) {
+        let fresh = ProcessTracker::get_process_info(pid, risk_scorer);
+        if let Some(mut process) = self.pending.remove(&pid) {
+            // Keep what was seen at fork if it is already gone
+            if let Some(fresh) = fresh {
+                process.name = fresh.name;
+                process.path = fresh.path;
+                process.risk_level = fresh.risk_level;
+            }
+            self.report_start(process);
+            return;
+        }
+
+        // A tracked process running a new program is reported again
+        let Some(fresh) = fresh else {
+            return;
+        };
+        let Ok(mut tracked) = self.tracked.lock() else {
+            return;
+        };
+        let Some(process) = tracked.get_mut(&pid) else {
+            return;
+        };
+        if process.name == fresh.name && process.path == fresh.path {
+            return;
+        }
+        process.name = fresh.name;
+        process.path = fresh.path;
+        process.risk_level = fresh.risk_level;
+        self.events.publish(TrackerEvent::ChildStarted {
+            pid,
+            ppid: process.ppid,
+            name: process.name.clone(),
+            path: process.path.clone(),
+            risk_level: process.risk_level,
+            backend: self.backend,
+        });
+    }
+
+    fn on_exit(&mut self, pid: u32, exit_code: Option<i32>) {
+        self.anchors.remove(&pid);
+        if let Some(process) = self.pending.remove(&pid) {
+            self.report_start(process);
+        }
+        let Some(mut process) = self.tracked.lock().ok().and_then(|mut t| t.remove(&pid)) else {
+            return;
+        };
+        // Still readable until the parent reaps it
+        process.record_usage(sample_usage(pid));
+        self.events.publish(TrackerEvent::ChildExited {
+            pid,
+            exit: process.exit_stats(exit_code),
+            backend: self.backend,
+        });
+    }
+
+    /// Report a new child and start tracking it
+    fn report_start(&self, process: TrackedProcess) {
+        let Ok(mut tracked) = self.tracked.lock() else {
+            return;
+        };
+        if tracked.contains_key(&process.pid) {
+            return;
+        }
+        self.events.publish(TrackerEvent::ChildStarted {
+            pid: process.pid,
+            ppid: process.ppid,
+            name: process.name.clone(),
+            path: process.path.clone(),
+            risk_level: process.risk_level,
+            backend: self.backend,
+        });
+        tracked.insert(process.pid, process);
+    }
+
+    /// Report children that forked without exec and sample usage, at most
+    /// once per poll interval
+    fn flush(&mut self) {
+        if self.last_flush.elapsed() < self.config.poll_interval {
+            return;
+        }
+        self.last_flush = Instant::now();
+
+        let due: Vec<u32> = self
+            .pending
+            .iter()
+            .filter(|(_, p)| p.detected_at.elapsed() >= self.config.poll_interval)
+            .map(|(&pid, _)| pid)
+            .collect();
+        for pid in due {
+            if let Some(process) = self.pending.remove(&pid) {
+                self.report_start(process);
+            }
+        }
+
+        let pids: Vec<u32> = match self.tracked.lock() {
+            Ok(tracked) => tracked.keys().copied().collect(),
+            Err(_) => return,
+        };
+        let samples: Vec<(u32, UsageSample)> =
+            pids.iter().map(|&pid| (pid, sample_usage(pid))).collect();
+        // Parents are not pushed on reparenting, so read them back
+        let parents: HashMap<u32, u32> = pids
+            .iter()
+            .chain(self.pending.keys())
+            .chain(std::iter::once(&self.config.root_pid))
+            .filter_map(|&pid| {
+                let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+                let entry = crate::execmon::parse_proc_stat(pid, &stat)?;
+                entry.exited.is_none().then_some((pid, entry.ppid))
+            })
+            .collect();
+        if let Ok(mut tracked) = self.tracked.lock() {
+            for (pid, sample) in samples {
+                if let Some(process) = tracked.get_mut(&pid) {
+                    process.record_usage(sample);
+                }
+            }
+            report_detached(
+                self.config.root_pid,
+                &mut tracked,
+                &parents,
+                self.events,
+                self.backend,
+            );
+        }
+    }
+
+    fn is_tracked(&self, pid: u32) -> bool {
+        self.tracked
+            .lock()
+            .map(|t| t.contains_key(&pid))
+            .unwrap_or(false)
+    }
+
+    /// Depth of `pid` below the root, or `None` outside the tree
+    fn depth(&self, pid: u32) -> Option<usize> {
+        let tracked = self.tracked.lock().ok()?;
+        let mut depth = 0;
+        let mut current = pid;
+        while current != self.config.root_pid && !self.anchors.contains(&current) {
+            current = self
+                .pending
+                .get(&current)
+                .or_else(|| tracked.get(&current))?
+                .ppid;
+            depth += 1;
+            // Reused PIDs could form a loop
+            if depth > tracked.len() + self.pending.len() {
+                return None;
+            }
+        }
+        Some(depth)
+    }
+}
+
+/// Mark tracked processes whose chain of current parents no longer leads
+/// to the root as detached, reporting each once. `parents` maps live
+/// processes to their parent; processes missing from it are skipped. Once
+/// the root itself is gone its orphans are reparented anyway, so nothing is
+/// reported.
+fn report_detached(
+    root_pid: u32,
+    tracked: &mut HashMap<u32, TrackedProcess>,
+    parents: &HashMap<u32, u32>,
+    events: &EventBus<TrackerEvent>,
+    backend: TrackerBackend,
+) {
+    if !parents.contains_key(&root_pid) {
+        return;
+    }
+    for process in tracked.values_mut() {
+        if process.detached {
+            continue;
+        }
+        let Some(&ppid) = parents.get(&process.pid) else {
+            continue;
+        };
+        if leads_to(ppid, root_pid, parents) {
+            continue;
+        }
+        process.detached = true;
+        events.publish(TrackerEvent::ChildDetached {
+            pid: process.pid,
+            ppid,
+            name: process.name.clone(),
+            backend,
+        });
+    }
+}
+
+/// Whether `pid` is `root_pid` or following `parents` up from it gets there
+fn leads_to(pid: u32, root_pid: u32, parents: &HashMap<u32, u32>) -> bool {
+    let mut current = pid;
+    // Bounded, as reused PIDs could form a loop
+    for _ in 0..=parents.len() {
+        if current == root_pid {
+            return true;
+        }
+        match parents.get(&current) {
+            Some(&ppid) => current = ppid,
+            None => return false,
+        }
+    }
+    false
+}
+
+/// Read CPU time and resident memory of a process
+#[cfg(target_os = "macos")]
+fn sample_usage(pid: u32) -> UsageSample {
+    // Also works for exited processes that have not been reaped yet
+    match pidrusage::<RUsageInfoV2>(pid as i32) {
+        Ok(usage) => UsageSample {
+            cpu_time: Some(mach_ticks_to_duration(
+                usage.ri_user_time + usage.ri_system_time,
+            )),
+            rss_bytes: Some(usage.ri_resident_size),
+        },
+        Err(_) => UsageSample::default(),
+    }
+}
+
+/// Convert Mach absolute time units, which are not nanoseconds on Apple
+/// Silicon, to a duration
+#[cfg(target_os = "macos")]
+#[allow(deprecated)]
+pub(crate) fn mach_ticks_to_duration(ticks: u64) -> Duration {
+    static TIMEBASE: std::sync::OnceLock<(u64, u64)> = std::sync::OnceLock::new();
+    let (numer, denom) = *TIMEBASE.get_or_init(|| {
+        let mut info = libc::mach_timebase_info { numer: 0, denom: 0 };
+        // SAFETY: `info` is a valid, writable mach_timebase_info
+        if unsafe { libc::mach_timebase_info(&mut info) } != 0 || info.denom == 0 {
+            return (1, 1);
+        }
+        (u64::from(info.numer), u64::from(info.denom))
+    });
+    Duration::from_nanos((u128::from(ticks) * u128::from(numer) / u128::from(denom)) as u64)
+}
+
+/// Read CPU time and resident memory of a process
+#[cfg(target_os = "linux")]
+fn sample_usage(pid: u32) -> UsageSample {
+    // SAFETY: sysconf has no preconditions
+    let ticks_per_sec = match unsafe { libc::sysconf(libc::_SC_CLK_TCK) } {
+        n if n > 0 => n as u64,
+        _ => 100,
+    };
+    let cpu_time = std::fs::read_to_string(format!("/proc/{pid}/stat"))
+        .ok()
+        .and_then(|stat| parse_cpu_ticks(&stat))
+        .map(|ticks| Duration::from_millis(ticks * 1000 / ticks_per_sec));
+    // Exited processes have released their memory and report no RSS
+    let rss_bytes = std::fs::read_to_string(format!("/proc/{pid}/status"))
+        .ok()
+        .and_then(|status| parse_peak_rss(&status));
+    UsageSample {
+        cpu_time,
+        rss_bytes,
+    }
+}
+
+/// User plus system CPU ticks from `/proc/<pid>/stat`
+#[cfg(any(target_os = "linux", test))]
+fn parse_cpu_ticks(stat: &str) -> Option<u64> {
+    let rest = &stat[stat.rfind(')')? + 1..];
+    let fields: Vec<&str> = rest.split_whitespace().collect();
+    // utime and stime are fields 14 and 15; the state (field 3) is first here
+    let utime: u64 = fields.get(11)?.parse().ok()?;
+    let stime: u64 = fields.get(12)?.parse().ok()?;
+    Some(utime + stime)
+}
+
+/// Peak resident set size in bytes from `/proc/<pid>/status`, falling back
+/// to the current size on kernels without `VmHWM`
+#[cfg(any(target_os = "linux", test))]
+fn parse_peak_rss(status: &str) -> Option<u64> {
+    let field = |name: &str| {
+        status.lines().find_map(|line| {
+            let value = line.strip_prefix(name)?.trim().strip_suffix("kB")?;
+            value.trim().parse::<u64>().ok().map(|kb| kb * 1024)
+        })
+    };
+    field("VmHWM:").or_else(|| field("VmRSS:"))
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+fn sample_usage(_pid: u32) -> UsageSample {
+    UsageSample::default()
+}
+
 impl crate::types::MonitoringSubsystem for ProcessTracker {
     fn start(&mut self) -> std::result::Result<(), crate::error::CoreError> {
         ProcessTracker::start(self);
@@ -435,6 +1228,11 @@ mod tests {
         assert_eq!(config.root_pid, 0);
         assert_eq!(config.poll_interval, Duration::from_millis(100));
         assert_eq!(config.max_depth, Some(10));
+        #[cfg(target_os = "linux")]
+        assert_eq!(
+            config.backends,
+            vec![TrackerBackend::ProcConnector, TrackerBackend::Ptrace]
+        );
     }
 
     #[test]
@@ -459,10 +1257,11 @@ mod tests {
     #[test]
     fn test_tracker_subscribe() {
         let config = TrackerConfig::new(std::process::id());
-        let mut tracker = ProcessTracker::new(config);
+        let tracker = ProcessTracker::new(config);
 
-        let _rx = tracker.subscribe();
-        assert!(tracker.event_tx.is_some());
+        let _first = tracker.subscribe();
+        let _second = tracker.subscribe();
+        assert_eq!(tracker.events.subscriber_count(), 2);
     }
 
     #[test]
@@ -487,6 +1286,9 @@ mod tests {
             path: Some("/usr/bin/test".to_string()),
             detected_at: Instant::now(),
             risk_level: RiskLevel::Low,
+            cpu_time: None,
+            peak_rss_bytes: None,
+            detached: false,
         };
 
         let cloned = process.clone();
@@ -502,16 +1304,21 @@ mod tests {
             name: "bash".to_string(),
             path: Some("/bin/bash".to_string()),
             risk_level: RiskLevel::Low,
+            backend: TrackerBackend::Polling,
         };
 
-        let exit_event = TrackerEvent::ChildExited { pid: 1234 };
+        let exit_event = TrackerEvent::ChildExited {
+            pid: 1234,
+            exit: ExitStats::default(),
+            backend: TrackerBackend::ProcConnector,
+        };
 
         // Just verify they can be created and cloned
         let _cloned = start_event.clone();
         let _cloned = exit_event.clone();
     }
 
-    #[cfg(target_os = "macos")]
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
     #[test]
     fn test_get_descendants_current_process() {
         // Current process should have no children in test
@@ -522,7 +1329,7 @@ mod tests {
         let _ = descendants.len();
     }
 
-    #[cfg(target_os = "macos")]
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
     #[test]
     fn test_get_process_info() {
         let scorer = RiskScorer::new();
@@ -578,7 +1385,7 @@ mod tests {
                 TrackerEvent::ChildStarted { pid, .. } if pid == child_pid => {
                     found_start = true;
                 }
-                TrackerEvent::ChildExited { pid } if pid == child_pid => {
+                TrackerEvent::ChildExited { pid, .. } if pid == child_pid => {
                     found_exit = true;
                 }
                 _ => {}
@@ -589,6 +1396,410 @@ mod tests {
         assert!(found_exit, "Should have detected child exit");
     }
 
+    #[test]
+    fn test_record_usage_keeps_peak() {
+        let mut process = TrackedProcess {
+            pid: 1234,
+            ppid: 1,
+            name: "node".to_string(),
+            path: None,
+            detected_at: Instant::now(),
+            risk_level: RiskLevel::Low,
+            cpu_time: None,
+            peak_rss_bytes: None,
+            detached: false,
+        };
+        process.record_usage(UsageSample {
+            cpu_time: Some(Duration::from_millis(40)),
+            rss_bytes: Some(300),
+        });
+        process.record_usage(UsageSample {
+            cpu_time: Some(Duration::from_millis(90)),
+            rss_bytes: Some(100),
+        });
+        // A failed read after exit keeps what was seen before
+        process.record_usage(UsageSample::default());
+
+        let exit = process.exit_stats(Some(2));
+        assert_eq!(exit.exit_code, Some(2));
+        assert_eq!(exit.cpu_time_ms, Some(90));
+        assert_eq!(exit.peak_rss_bytes, Some(300));
+        assert!(exit.duration_ms.is_some());
+    }
+
+    #[test]
+    fn test_parse_cpu_ticks() {
+        let stat = "4321 (my (odd) cmd) S 1 4321 4321 0 -1 4194560 100 0 0 0 \
+                    25 17 0 0 20 0 1 0 12345 1000000 200";
+        assert_eq!(parse_cpu_ticks(stat), Some(42));
+        assert_eq!(parse_cpu_ticks("4321 (short) S 1"), None);
+    }
+
+    #[test]
+    fn test_parse_peak_rss() {
+        let status = "Name:\tnode\nVmPeak:\t  900 kB\nVmHWM:\t  512 kB\nVmRSS:\t  256 kB\n";
+        assert_eq!(parse_peak_rss(status), Some(512 * 1024));
+        assert_eq!(parse_peak_rss("VmRSS:\t  256 kB\n"), Some(256 * 1024));
+        // Zombies have no memory fields
+        assert_eq!(parse_peak_rss("Name:\tsh\nState:\tZ (zombie)\n"), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_tracker_reports_exit_stats() {
+        use std::process::Command;
+
+        let mut child = Command::new("sh")
+            .args(["-c", "sleep 0.3; exit 3"])
+            .spawn()
+            .expect("Failed to spawn sh");
+        let child_pid = child.id();
+
+        let config =
+            TrackerConfig::new(std::process::id()).poll_interval(Duration::from_millis(20));
+        let mut tracker = ProcessTracker::new(config);
+        let rx = tracker.subscribe();
+        tracker.start();
+
+        // The child stays a zombie until waited for, so the tracker can read
+        // its exit code
+        let exit = loop {
+            match rx.recv_timeout(Duration::from_secs(5)) {
+                Ok(TrackerEvent::ChildExited { pid, exit, .. }) if pid == child_pid => break exit,
+                Ok(_) => {}
+                Err(e) => panic!("child exit not reported: {e}"),
+            }
+        };
+        let _ = child.wait();
+        tracker.stop();
+
+        assert_eq!(exit.exit_code, Some(3));
+        assert!(exit.duration_ms.unwrap() >= 200, "{exit:?}");
+        assert!(exit.cpu_time_ms.is_some());
+        assert!(exit.peak_rss_bytes.unwrap() > 0);
+    }
+
+    #[cfg(target_os = "linux")]
+    fn push_state_events(config: &TrackerConfig, events: Vec<ProcEvent>) -> Vec<TrackerEvent> {
+        let tracked = Mutex::new(HashMap::new());
+        let bus = EventBus::new();
+        let rx = bus.subscribe();
+        let mut state = PushState::new(config, TrackerBackend::ProcConnector, &tracked, &bus);
+        for event in events {
+            state.handle(event, &RiskScorer::new());
+        }
+        rx.try_iter().collect()
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_push_state_reports_fork_exec_exit() {
+        // PIDs above pid_max, so nothing can be read from /proc
+        let root = 4_000_000;
+        let config = TrackerConfig::new(root);
+        let events = push_state_events(
+            &config,
+            vec![
+                ProcEvent::Fork {
+                    ppid: root,
+                    pid: root + 1,
+                },
+                ProcEvent::Exec { pid: root + 1 },
+                ProcEvent::Fork {
+                    ppid: root + 1,
+                    pid: root + 2,
+                },
+                ProcEvent::Exit {
+                    pid: root + 2,
+                    exit_code: Some(0),
+                },
+                ProcEvent::Exit {
+                    pid: root + 1,
+                    exit_code: Some(3),
+                },
+            ],
+        );
+
+        let summary: Vec<(&str, u32)> = events
+            .iter()
+            .map(|event| match event {
+                TrackerEvent::ChildStarted { pid, backend, .. } => {
+                    assert_eq!(*backend, TrackerBackend::ProcConnector);
+                    ("start", *pid)
+                }
+                TrackerEvent::ChildExited { pid, backend, .. } => {
+                    assert_eq!(*backend, TrackerBackend::ProcConnector);
+                    ("exit", *pid)
+                }
+                other => panic!("unexpected {other:?}"),
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("start", root + 1),
+                ("start", root + 2),
+                ("exit", root + 2),
+                ("exit", root + 1),
+            ]
+        );
+        match &events[3] {
+            TrackerEvent::ChildExited { exit, .. } => assert_eq!(exit.exit_code, Some(3)),
+            other => panic!("unexpected {other:?}"),
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_push_state_ignores_outside_and_too_deep() {
+        let root = 4_000_000;
+        let config = TrackerConfig::new(root).max_depth(Some(0));
+        let events = push_state_events(
+            &config,
+            vec![
+                // Unrelated process elsewhere on the system
+                ProcEvent::Fork { ppid: 1, pid: 7 },
+                ProcEvent::Exec { pid: 7 },
+                ProcEvent::Exit {
+                    pid: 7,
+                    exit_code: Some(0),
+                },
+                // Child of the root is within depth 0, its child is not
+                ProcEvent::Fork {
+                    ppid: root,
+                    pid: root + 1,
+                },
+                ProcEvent::Exec { pid: root + 1 },
+                ProcEvent::Fork {
+                    ppid: root + 1,
+                    pid: root + 2,
+                },
+                ProcEvent::Exec { pid: root + 2 },
+            ],
+        );
+        assert_eq!(events.len(), 1, "{events:?}");
+        assert!(matches!(
+            events[0],
+            TrackerEvent::ChildStarted { pid, .. } if pid == root + 1
+        ));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_push_backend_catches_short_lived_process() {
+        use std::process::Command;
+
+        // Far longer than the child lives, so polling alone would miss it
+        let config = TrackerConfig::new(std::process::id()).poll_interval(Duration::from_secs(2));
+        let mut tracker = ProcessTracker::new(config);
+        let rx = tracker.subscribe();
+        tracker.start();
+        if tracker.backend() == TrackerBackend::Polling {
+            // No push source in this environment
+            return;
+        }
+
+        let mut child = Command::new("true").spawn().expect("Failed to spawn true");
+        let child_pid = child.id();
+        let (mut started, mut exit) = (None, None);
+        while exit.is_none() {
+            match rx.recv_timeout(Duration::from_secs(5)) {
+                Ok(TrackerEvent::ChildStarted {
+                    pid, name, backend, ..
+                }) if pid == child_pid => started = Some((name, backend)),
+                Ok(TrackerEvent::ChildExited {
+                    pid,
+                    exit: e,
+                    backend,
+                }) if pid == child_pid => {
+                    assert_eq!(backend, tracker.backend());
+                    exit = Some(e);
+                }
+                Ok(_) => {}
+                Err(e) => panic!("short-lived child not reported: {e}"),
+            }
+        }
+        let _ = child.wait();
+        tracker.stop();
+
+        assert_eq!(started, Some(("true".to_string(), tracker.backend())));
+        assert_eq!(exit.unwrap().exit_code, Some(0));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_ptrace_backend_follows_attached_tree() {
+        use std::io::{BufRead, BufReader};
+        use std::process::{Command, Stdio};
+
+        // A grandchild of this process: ptrace refuses our own children
+        let mut outer = Command::new("sh")
+            .args(["-c", "sh -c 'sleep 0.5; /bin/true; exit 4' & echo $!; wait"])
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("Failed to spawn sh");
+        let mut line = String::new();
+        BufReader::new(outer.stdout.take().unwrap())
+            .read_line(&mut line)
+            .unwrap();
+        let root: u32 = line.trim().parse().unwrap();
+
+        let config = TrackerConfig::new(root)
+            .poll_interval(Duration::from_secs(2))
+            .backends(vec![TrackerBackend::Ptrace]);
+        let mut tracker = ProcessTracker::new(config);
+        let rx = tracker.subscribe();
+        tracker.start();
+        if tracker.backend() != TrackerBackend::Ptrace {
+            // ptrace is not permitted in this environment
+            let _ = outer.wait();
+            return;
+        }
+
+        let mut true_pid = None;
+        let exit = loop {
+            match rx.recv_timeout(Duration::from_secs(5)) {
+                Ok(TrackerEvent::ChildStarted {
+                    pid, name, backend, ..
+                }) if name == "true" => {
+                    assert_eq!(backend, TrackerBackend::Ptrace);
+                    true_pid = Some(pid);
+                }
+                Ok(TrackerEvent::ChildExited { pid, exit, .. }) if Some(pid) == true_pid => {
+                    break exit
+                }
+                Ok(_) => {}
+                Err(e) => panic!("traced child not reported: {e}"),
+            }
+        };
+        // The traced root still exits normally
+        assert!(outer.wait().unwrap().success());
+        tracker.stop();
+
+        assert_eq!(exit.exit_code, Some(0));
+    }
+
+    #[test]
+    fn test_report_detached_once() {
+        let root = 4_000_000;
+        let process = |pid, ppid| TrackedProcess {
+            pid,
+            ppid,
+            name: format!("p{pid}"),
+            path: None,
+            detected_at: Instant::now(),
+            risk_level: RiskLevel::Low,
+            cpu_time: None,
+            peak_rss_bytes: None,
+            detached: false,
+        };
+        let mut tracked: HashMap<u32, TrackedProcess> = [
+            (root + 1, process(root + 1, root)),
+            (root + 2, process(root + 2, root + 1)),
+            (root + 3, process(root + 3, root + 1)),
+        ]
+        .into_iter()
+        .collect();
+        // root + 3 was reparented to init; root + 2 is unknown, e.g. gone
+        let parents: HashMap<u32, u32> = [(root, 1), (root + 1, root), (root + 3, 1)]
+            .into_iter()
+            .collect();
+
+        let events = EventBus::new();
+        let rx = events.subscribe();
+        for _ in 0..2 {
+            report_detached(
+                root,
+                &mut tracked,
+                &parents,
+                &events,
+                TrackerBackend::Polling,
+            );
+        }
+        let reported: Vec<TrackerEvent> = rx.try_iter().collect();
+        assert_eq!(reported.len(), 1, "{reported:?}");
+        assert!(matches!(
+            &reported[0],
+            TrackerEvent::ChildDetached { pid, ppid: 1, .. } if *pid == root + 3
+        ));
+        assert_eq!(reported[0].risk_level(), Some(RiskLevel::High));
+        assert!(tracked[&(root + 3)].detached);
+        assert!(!tracked[&(root + 1)].detached);
+
+        // Orphans of a root that exited are not reported
+        let mut tracked: HashMap<u32, TrackedProcess> =
+            [(root + 1, process(root + 1, root))].into_iter().collect();
+        let parents: HashMap<u32, u32> = [(root + 1, 1)].into_iter().collect();
+        report_detached(
+            root,
+            &mut tracked,
+            &parents,
+            &events,
+            TrackerBackend::Polling,
+        );
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_tracker_keeps_daemonized_child() {
+        use std::os::unix::process::CommandExt;
+        use std::process::Command;
+
+        for backends in [Vec::new(), TrackerConfig::default().backends] {
+            // The subshell exits right away, leaving its sleep to init. As
+            // the root leads its process group, the sleep is still found.
+            let mut root = Command::new("sh")
+                .args(["-c", "( sleep 5 & ) ; sleep 5"])
+                .process_group(0)
+                .spawn()
+                .expect("Failed to spawn sh");
+            let root_pid = root.id();
+
+            let config = TrackerConfig::new(root_pid)
+                .poll_interval(Duration::from_millis(50))
+                .backends(backends);
+            let mut tracker = ProcessTracker::new(config);
+            let rx = tracker.subscribe();
+            tracker.start();
+
+            let detached = loop {
+                match rx.recv_timeout(Duration::from_secs(5)) {
+                    Ok(TrackerEvent::ChildDetached {
+                        pid, ppid, name, ..
+                    }) => break (pid, ppid, name),
+                    Ok(_) => {}
+                    Err(e) => panic!("daemonized child not reported: {e}"),
+                }
+            };
+            let still_tracked = tracker.is_tracked(detached.0);
+            tracker.stop();
+            // SAFETY: kill has no memory safety preconditions; the group
+            // only holds the processes spawned above
+            unsafe {
+                libc::kill(-(root_pid as libc::pid_t), libc::SIGKILL);
+            }
+            let _ = root.wait();
+
+            assert_eq!(detached.2, "sleep");
+            assert_ne!(detached.1, root_pid);
+            assert!(still_tracked, "detached child should stay tracked");
+        }
+    }
+
+    #[test]
+    fn test_set_risk_scorer_swaps_shared_handle() {
+        let shared = Swappable::new(RiskScorer::new());
+        let tracker =
+            ProcessTracker::new(TrackerConfig::new(1)).with_shared_risk_scorer(shared.clone());
+
+        let mut scorer = RiskScorer::new();
+        scorer.add_custom_high_risk(vec!["deploy".to_string()]);
+        tracker.set_risk_scorer(scorer);
+
+        assert_eq!(shared.load().score("deploy", &[]).0, RiskLevel::High);
+    }
+
     #[test]
     fn test_is_tracked() {
         let config = TrackerConfig::new(std::process::id());
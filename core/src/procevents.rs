@@ -0,0 +1,709 @@
+//! Push-based process events
+//!
+//! Sources that report fork, exec and exit as they happen, so the process
+//! tracker sees programs that finish before the next poll, such as most
+//! `curl | sh` one-shots, `rm` and `chmod`.
+//!
+//! On Linux the netlink proc connector is preferred. The kernel pushes every
+//! fork, exec and exit on the system to the socket, but subscribing needs
+//! `CAP_NET_ADMIN` in the initial namespaces. Without it, ptrace with fork,
+//! clone, exec and exit tracing follows the tree instead. The tracer must
+//! not be the real parent of the root: the kernel would report the root's
+//! ptrace stops to our own `wait()` on it. Wrapped commands therefore fall
+//! back to polling when the proc connector is unavailable.
+
+#[cfg(target_os = "linux")]
+use std::collections::{HashMap, HashSet};
+#[cfg(target_os = "linux")]
+use std::io;
+#[cfg(target_os = "linux")]
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+#[cfg(target_os = "linux")]
+use std::time::{Duration, Instant};
+
+/// Process lifecycle event reported by a push source
+#[cfg(any(target_os = "linux", test))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ProcEvent {
+    /// `ppid` forked a new process `pid`
+    Fork { ppid: u32, pid: u32 },
+    /// `pid` replaced its program image
+    Exec { pid: u32 },
+    /// `pid` exited; codes of processes killed by a signal follow the
+    /// shell's 128 + signal convention
+    Exit { pid: u32, exit_code: Option<i32> },
+}
+
+// Layout of connector messages, from <linux/netlink.h>, <linux/connector.h>
+// and <linux/cn_proc.h>
+#[cfg(any(target_os = "linux", test))]
+const NLMSG_HDRLEN: usize = 16;
+#[cfg(any(target_os = "linux", test))]
+const NLMSG_DONE: u16 = 3;
+#[cfg(any(target_os = "linux", test))]
+const CN_MSG_LEN: usize = 20;
+#[cfg(any(target_os = "linux", test))]
+const CN_IDX_PROC: u32 = 1;
+#[cfg(any(target_os = "linux", test))]
+const CN_VAL_PROC: u32 = 1;
+/// Offset of `event_data` within `struct proc_event`
+#[cfg(any(target_os = "linux", test))]
+const PROC_EVENT_DATA: usize = 16;
+#[cfg(any(target_os = "linux", test))]
+const PROC_EVENT_NONE: u32 = 0;
+#[cfg(any(target_os = "linux", test))]
+const PROC_EVENT_FORK: u32 = 0x1;
+#[cfg(any(target_os = "linux", test))]
+const PROC_EVENT_EXEC: u32 = 0x2;
+#[cfg(any(target_os = "linux", test))]
+const PROC_EVENT_EXIT: u32 = 0x8000_0000;
+#[cfg(target_os = "linux")]
+const PROC_CN_MCAST_LISTEN: u32 = 1;
+#[cfg(target_os = "linux")]
+const PROC_CN_MCAST_IGNORE: u32 = 2;
+
+/// How long to wait for the kernel to acknowledge a subscription
+#[cfg(target_os = "linux")]
+const LISTEN_ACK_TIMEOUT: Duration = Duration::from_millis(500);
+
+#[cfg(any(target_os = "linux", test))]
+fn read_u32(buf: &[u8], offset: usize) -> Option<u32> {
+    let bytes = buf.get(offset..offset + 4)?;
+    Some(u32::from_ne_bytes(bytes.try_into().ok()?))
+}
+
+/// `struct proc_event` payloads of the proc connector messages in one
+/// datagram
+#[cfg(any(target_os = "linux", test))]
+fn connector_payloads(buf: &[u8]) -> Vec<&[u8]> {
+    let mut payloads = Vec::new();
+    let mut offset = 0;
+    while let Some(len) = read_u32(buf, offset).map(|len| len as usize) {
+        if len < NLMSG_HDRLEN || offset + len > buf.len() {
+            break;
+        }
+        let message = &buf[offset..offset + len];
+        let kind = u16::from_ne_bytes([message[4], message[5]]);
+        let cn = &message[NLMSG_HDRLEN..];
+        if kind == NLMSG_DONE
+            && read_u32(cn, 0) == Some(CN_IDX_PROC)
+            && read_u32(cn, 4) == Some(CN_VAL_PROC)
+        {
+            if let Some(payload) = cn.get(CN_MSG_LEN..) {
+                payloads.push(payload);
+            }
+        }
+        // Messages are 4-byte aligned
+        offset += (len + 3) & !3;
+    }
+    payloads
+}
+
+/// Decode one `struct proc_event`. Thread creation and exit are skipped.
+#[cfg(any(target_os = "linux", test))]
+fn parse_proc_event(payload: &[u8]) -> Option<ProcEvent> {
+    let data = |index: usize| read_u32(payload, PROC_EVENT_DATA + index * 4);
+    match read_u32(payload, 0)? {
+        PROC_EVENT_FORK => {
+            // parent_pid, parent_tgid, child_pid, child_tgid
+            let (child_pid, child_tgid) = (data(2)?, data(3)?);
+            (child_pid == child_tgid).then_some(ProcEvent::Fork {
+                ppid: data(1)?,
+                pid: child_tgid,
+            })
+        }
+        // process_pid, process_tgid
+        PROC_EVENT_EXEC => Some(ProcEvent::Exec { pid: data(1)? }),
+        PROC_EVENT_EXIT => {
+            // process_pid, process_tgid, exit_code, exit_signal
+            let (pid, tgid) = (data(0)?, data(1)?);
+            (pid == tgid).then_some(ProcEvent::Exit {
+                pid: tgid,
+                exit_code: Some(crate::execmon::decode_wait_status(data(2)? as i32)),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Decode the process events in one datagram from the proc connector
+#[cfg(any(target_os = "linux", test))]
+pub(crate) fn parse_connector_message(buf: &[u8]) -> Vec<ProcEvent> {
+    connector_payloads(buf)
+        .into_iter()
+        .filter_map(parse_proc_event)
+        .collect()
+}
+
+/// Error code of a subscription acknowledgement in the datagram, if any
+#[cfg(any(target_os = "linux", test))]
+fn listen_ack(buf: &[u8]) -> Option<u32> {
+    connector_payloads(buf)
+        .into_iter()
+        .find(|payload| read_u32(payload, 0) == Some(PROC_EVENT_NONE))
+        .and_then(|payload| read_u32(payload, PROC_EVENT_DATA))
+}
+
+/// Subscription to the kernel's proc connector
+#[cfg(target_os = "linux")]
+pub(crate) struct ProcConnector {
+    fd: OwnedFd,
+    buf: Vec<u8>,
+}
+
+#[cfg(target_os = "linux")]
+impl ProcConnector {
+    /// Subscribe to process events. Reads give up after `timeout` so the
+    /// caller can check for shutdown.
+    ///
+    /// Fails unless the kernel acknowledges the subscription, which it only
+    /// does for privileged callers in the initial namespaces; elsewhere the
+    /// socket opens fine but never receives an event.
+    pub(crate) fn open(timeout: Duration) -> io::Result<Self> {
+        // SAFETY: socket has no memory preconditions
+        let raw = unsafe {
+            libc::socket(
+                libc::AF_NETLINK,
+                libc::SOCK_DGRAM | libc::SOCK_CLOEXEC,
+                libc::NETLINK_CONNECTOR,
+            )
+        };
+        if raw < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: `raw` is a freshly opened descriptor owned by nobody else
+        let fd = unsafe { OwnedFd::from_raw_fd(raw) };
+
+        // SAFETY: sockaddr_nl is plain data; all zeroes is a valid value
+        let mut addr: libc::sockaddr_nl = unsafe { std::mem::zeroed() };
+        addr.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+        addr.nl_groups = CN_IDX_PROC;
+        // SAFETY: `addr` is a valid sockaddr_nl of the given length
+        let rc = unsafe {
+            libc::bind(
+                fd.as_raw_fd(),
+                &addr as *const libc::sockaddr_nl as *const libc::sockaddr,
+                std::mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+            )
+        };
+        if rc != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let tv = libc::timeval {
+            tv_sec: timeout.as_secs() as libc::time_t,
+            tv_usec: timeout.subsec_micros().max(1000) as libc::suseconds_t,
+        };
+        // SAFETY: `tv` is a valid timeval of the given length
+        let rc = unsafe {
+            libc::setsockopt(
+                fd.as_raw_fd(),
+                libc::SOL_SOCKET,
+                libc::SO_RCVTIMEO,
+                &tv as *const libc::timeval as *const libc::c_void,
+                std::mem::size_of::<libc::timeval>() as libc::socklen_t,
+            )
+        };
+        if rc != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let mut connector = Self {
+            fd,
+            buf: vec![0; 8192],
+        };
+        connector.send_op(PROC_CN_MCAST_LISTEN)?;
+        connector.await_ack()?;
+        Ok(connector)
+    }
+
+    /// Send a multicast control operation to the connector
+    fn send_op(&self, op: u32) -> io::Result<()> {
+        let len = NLMSG_HDRLEN + CN_MSG_LEN + 4;
+        let mut msg = Vec::with_capacity(len);
+        // nlmsghdr: len, type, flags, seq, pid
+        msg.extend_from_slice(&(len as u32).to_ne_bytes());
+        msg.extend_from_slice(&NLMSG_DONE.to_ne_bytes());
+        msg.extend_from_slice(&0u16.to_ne_bytes());
+        msg.extend_from_slice(&0u32.to_ne_bytes());
+        msg.extend_from_slice(&std::process::id().to_ne_bytes());
+        // cn_msg: idx, val, seq, ack, len, flags
+        msg.extend_from_slice(&CN_IDX_PROC.to_ne_bytes());
+        msg.extend_from_slice(&CN_VAL_PROC.to_ne_bytes());
+        msg.extend_from_slice(&0u32.to_ne_bytes());
+        msg.extend_from_slice(&0u32.to_ne_bytes());
+        msg.extend_from_slice(&4u16.to_ne_bytes());
+        msg.extend_from_slice(&0u16.to_ne_bytes());
+        msg.extend_from_slice(&op.to_ne_bytes());
+
+        // SAFETY: `msg` is valid for reads of its length
+        let sent = unsafe {
+            libc::send(
+                self.fd.as_raw_fd(),
+                msg.as_ptr() as *const libc::c_void,
+                msg.len(),
+                0,
+            )
+        };
+        if sent < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// Wait for the kernel to confirm the subscription
+    fn await_ack(&mut self) -> io::Result<()> {
+        let deadline = Instant::now() + LISTEN_ACK_TIMEOUT;
+        while Instant::now() < deadline {
+            let Some(len) = self.recv_raw()? else {
+                continue;
+            };
+            match listen_ack(&self.buf[..len]) {
+                Some(0) => return Ok(()),
+                Some(errno) => return Err(io::Error::from_raw_os_error(errno as i32)),
+                None => {}
+            }
+        }
+        Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            "proc connector did not acknowledge the subscription",
+        ))
+    }
+
+    /// Read one datagram into the buffer; `None` if the read timed out
+    fn recv_raw(&mut self) -> io::Result<Option<usize>> {
+        // SAFETY: `buf` is valid for writes of its length
+        let len = unsafe {
+            libc::recv(
+                self.fd.as_raw_fd(),
+                self.buf.as_mut_ptr() as *mut libc::c_void,
+                self.buf.len(),
+                0,
+            )
+        };
+        if len < 0 {
+            let err = io::Error::last_os_error();
+            return match err.kind() {
+                io::ErrorKind::WouldBlock
+                | io::ErrorKind::TimedOut
+                | io::ErrorKind::Interrupted => Ok(None),
+                _ => Err(err),
+            };
+        }
+        Ok(Some(len as usize))
+    }
+
+    /// Next batch of events, empty if none arrived before the timeout.
+    ///
+    /// `ENOBUFS` means the kernel dropped events because the socket buffer
+    /// overflowed; the caller should rescan the tree.
+    pub(crate) fn recv(&mut self) -> io::Result<Vec<ProcEvent>> {
+        Ok(match self.recv_raw()? {
+            Some(len) => parse_connector_message(&self.buf[..len]),
+            None => Vec::new(),
+        })
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for ProcConnector {
+    fn drop(&mut self) {
+        // The kernel skips building events while nobody listens
+        let _ = self.send_op(PROC_CN_MCAST_IGNORE);
+    }
+}
+
+/// Sleep between checks for ptrace stops while the tree is idle
+#[cfg(target_os = "linux")]
+const PTRACE_IDLE_SLEEP: Duration = Duration::from_millis(1);
+
+#[cfg(target_os = "linux")]
+const PTRACE_OPTIONS: libc::c_int = libc::PTRACE_O_TRACEFORK
+    | libc::PTRACE_O_TRACEVFORK
+    | libc::PTRACE_O_TRACECLONE
+    | libc::PTRACE_O_TRACEEXEC
+    | libc::PTRACE_O_TRACEEXIT;
+
+/// ptrace session following a process tree.
+///
+/// Every call must come from the thread that attached: ptrace ties tracees
+/// to the tracing thread, and they are released when it exits.
+#[cfg(target_os = "linux")]
+pub(crate) struct PtraceSession {
+    /// Traced thread IDs and the process each belongs to
+    threads: HashMap<u32, u32>,
+    /// Children reported before their parent's fork event arrived
+    adopted: HashSet<u32>,
+    /// Tracee held stopped while the caller handles its events, and how to
+    /// let it go
+    held: Option<(u32, Release)>,
+}
+
+/// How a stopped tracee is let go
+#[cfg(target_os = "linux")]
+#[derive(Debug, Clone, Copy)]
+enum Release {
+    /// Continue, delivering the signal unless it is 0
+    Continue(libc::c_int),
+    /// Stop tracing it
+    Detach,
+    /// Stay in group stop until SIGCONT, as if untraced
+    Listen,
+}
+
+#[cfg(target_os = "linux")]
+impl PtraceSession {
+    /// Trace `root` and, through fork, clone and exec tracing, everything
+    /// it starts from now on
+    pub(crate) fn attach(root: u32) -> io::Result<Self> {
+        let own_pid = std::process::id();
+        let parent = std::fs::read_to_string(format!("/proc/{root}/stat"))
+            .ok()
+            .and_then(|stat| crate::execmon::parse_proc_stat(root, &stat))
+            .map(|entry| entry.ppid);
+        if root == own_pid || parent == Some(own_pid) {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "cannot trace this process or its own children",
+            ));
+        }
+
+        let mut session = Self {
+            threads: HashMap::new(),
+            adopted: HashSet::new(),
+            held: None,
+        };
+        session.seize(root, root)?;
+        session.follow(root);
+        Ok(session)
+    }
+
+    /// Trace every thread of a process that was already running
+    pub(crate) fn follow(&mut self, pid: u32) {
+        let Ok(tasks) = std::fs::read_dir(format!("/proc/{pid}/task")) else {
+            return;
+        };
+        for tid in tasks
+            .flatten()
+            .filter_map(|t| t.file_name().to_str()?.parse().ok())
+        {
+            if !self.threads.contains_key(&tid) {
+                let _ = self.seize(tid, pid);
+            }
+        }
+    }
+
+    fn seize(&mut self, tid: u32, tgid: u32) -> io::Result<()> {
+        // SAFETY: PTRACE_SEIZE reads no memory; options go in `data`
+        let rc = unsafe {
+            libc::ptrace(
+                libc::PTRACE_SEIZE as _,
+                tid as libc::pid_t,
+                std::ptr::null_mut::<libc::c_void>(),
+                PTRACE_OPTIONS as usize as *mut libc::c_void,
+            )
+        };
+        if rc != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        self.threads.insert(tid, tgid);
+        Ok(())
+    }
+
+    /// Events reported within `timeout`, returned as soon as any arrive.
+    ///
+    /// The tracee they came from stays stopped until the next call, so the
+    /// caller can still read its program and usage from `/proc`, even for
+    /// processes that only live for a moment.
+    pub(crate) fn poll(&mut self, timeout: Duration) -> Vec<ProcEvent> {
+        let deadline = Instant::now() + timeout;
+        let mut events = Vec::new();
+        loop {
+            self.release();
+            let mut status = 0;
+            // Only tracees of this thread, never the host's own children
+            // SAFETY: `status` is a valid, writable int
+            let tid = unsafe {
+                libc::waitpid(
+                    -1,
+                    &mut status,
+                    libc::__WALL | libc::__WNOTHREAD | libc::WNOHANG,
+                )
+            };
+            if tid > 0 {
+                self.handle(tid as u32, status, &mut events);
+                if !events.is_empty() {
+                    return events;
+                }
+                continue;
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return events;
+            }
+            if tid < 0 {
+                // Nothing left to trace
+                std::thread::sleep(deadline - now);
+            } else {
+                std::thread::sleep(PTRACE_IDLE_SLEEP.min(deadline - now));
+            }
+        }
+    }
+
+    /// Let go of the tracee held since the last poll
+    fn release(&mut self) {
+        let Some((tid, release)) = self.held.take() else {
+            return;
+        };
+        let (request, signal) = match release {
+            Release::Continue(signal) => (libc::PTRACE_CONT, signal),
+            Release::Detach => (libc::PTRACE_DETACH, 0),
+            Release::Listen => (libc::PTRACE_LISTEN, 0),
+        };
+        // SAFETY: these requests read no memory; the signal goes in `data`
+        unsafe {
+            libc::ptrace(
+                request as _,
+                tid as libc::pid_t,
+                std::ptr::null_mut::<libc::c_void>(),
+                signal as usize as *mut libc::c_void,
+            );
+        }
+    }
+
+    /// Act on one wait status and decide how to let the tracee go
+    fn handle(&mut self, tid: u32, status: libc::c_int, events: &mut Vec<ProcEvent>) {
+        if libc::WIFEXITED(status) || libc::WIFSIGNALED(status) {
+            if self.threads.remove(&tid) == Some(tid) {
+                events.push(ProcEvent::Exit {
+                    pid: tid,
+                    exit_code: Some(crate::execmon::decode_wait_status(status)),
+                });
+            }
+            return;
+        }
+        if !libc::WIFSTOPPED(status) {
+            return;
+        }
+
+        if !self.threads.contains_key(&tid) {
+            self.adopt(tid, events);
+        }
+        let tgid = self.threads.get(&tid).copied().unwrap_or(tid);
+        let signal = libc::WSTOPSIG(status);
+        match status >> 16 {
+            libc::PTRACE_EVENT_FORK | libc::PTRACE_EVENT_VFORK | libc::PTRACE_EVENT_CLONE => {
+                let child = event_msg(tid).map(|msg| msg as u32);
+                if let Some(child) = child.filter(|child| !self.adopted.remove(child)) {
+                    let child_tgid = read_tgid(child).unwrap_or(child);
+                    self.threads.insert(child, child_tgid);
+                    if child_tgid == child {
+                        events.push(ProcEvent::Fork {
+                            ppid: tgid,
+                            pid: child,
+                        });
+                    }
+                }
+                self.held = Some((tid, Release::Continue(0)));
+            }
+            libc::PTRACE_EVENT_EXEC => {
+                // The thread that called exec now runs under the process ID
+                if let Some(former) = event_msg(tid).map(|msg| msg as u32) {
+                    if former != tid {
+                        self.threads.remove(&former);
+                    }
+                }
+                self.threads.insert(tid, tid);
+                events.push(ProcEvent::Exec { pid: tid });
+                self.held = Some((tid, Release::Continue(0)));
+            }
+            libc::PTRACE_EVENT_EXIT => {
+                if tgid == tid {
+                    events.push(ProcEvent::Exit {
+                        pid: tid,
+                        exit_code: event_msg(tid)
+                            .map(|msg| crate::execmon::decode_wait_status(msg as i32)),
+                    });
+                }
+                // Let it go so its parent can reap it as usual
+                self.threads.remove(&tid);
+                self.held = Some((tid, Release::Detach));
+            }
+            libc::PTRACE_EVENT_STOP => {
+                let group_stop = matches!(
+                    signal,
+                    libc::SIGSTOP | libc::SIGTSTP | libc::SIGTTIN | libc::SIGTTOU
+                );
+                let release = if group_stop {
+                    Release::Listen
+                } else {
+                    Release::Continue(0)
+                };
+                self.held = Some((tid, release));
+            }
+            // A signal on its way to the tracee; deliver it
+            _ => self.held = Some((tid, Release::Continue(signal))),
+        }
+    }
+
+    /// Start following a new tracee whose first stop arrived before its
+    /// parent's fork event, reporting the fork first
+    fn adopt(&mut self, tid: u32, events: &mut Vec<ProcEvent>) {
+        let tgid = read_tgid(tid).unwrap_or(tid);
+        self.threads.insert(tid, tgid);
+        self.adopted.insert(tid);
+        if tgid != tid {
+            return;
+        }
+        let parent = std::fs::read_to_string(format!("/proc/{tid}/stat"))
+            .ok()
+            .and_then(|stat| crate::execmon::parse_proc_stat(tid, &stat))
+            .map(|entry| entry.ppid);
+        if let Some(ppid) = parent {
+            events.push(ProcEvent::Fork { ppid, pid: tid });
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for PtraceSession {
+    fn drop(&mut self) {
+        // The rest are released when the tracing thread exits
+        self.release();
+    }
+}
+
+/// Message attached to the ptrace event the tracee is stopped at
+#[cfg(target_os = "linux")]
+fn event_msg(tid: u32) -> Option<libc::c_ulong> {
+    let mut msg: libc::c_ulong = 0;
+    // SAFETY: `msg` is a valid, writable c_ulong
+    let rc = unsafe {
+        libc::ptrace(
+            libc::PTRACE_GETEVENTMSG as _,
+            tid as libc::pid_t,
+            std::ptr::null_mut::<libc::c_void>(),
+            &mut msg as *mut libc::c_ulong as *mut libc::c_void,
+        )
+    };
+    (rc == 0).then_some(msg)
+}
+
+/// Process a thread belongs to, from `/proc/<tid>/status`
+#[cfg(target_os = "linux")]
+fn read_tgid(tid: u32) -> Option<u32> {
+    std::fs::read_to_string(format!("/proc/{tid}/status"))
+        .ok()?
+        .lines()
+        .find_map(|line| line.strip_prefix("Tgid:")?.trim().parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One connector datagram carrying `event_data` words for `what`
+    fn message(what: u32, data: &[u32]) -> Vec<u8> {
+        let mut payload = Vec::new();
+        payload.extend_from_slice(&what.to_ne_bytes());
+        payload.extend_from_slice(&0u32.to_ne_bytes()); // cpu
+        payload.extend_from_slice(&0u64.to_ne_bytes()); // timestamp_ns
+        for word in data {
+            payload.extend_from_slice(&word.to_ne_bytes());
+        }
+
+        let len = NLMSG_HDRLEN + CN_MSG_LEN + payload.len();
+        let mut msg = Vec::new();
+        msg.extend_from_slice(&(len as u32).to_ne_bytes());
+        msg.extend_from_slice(&NLMSG_DONE.to_ne_bytes());
+        msg.extend_from_slice(&[0; 10]); // flags, seq, pid
+        msg.extend_from_slice(&CN_IDX_PROC.to_ne_bytes());
+        msg.extend_from_slice(&CN_VAL_PROC.to_ne_bytes());
+        msg.extend_from_slice(&[0; 8]); // seq, ack
+        msg.extend_from_slice(&(payload.len() as u16).to_ne_bytes());
+        msg.extend_from_slice(&0u16.to_ne_bytes());
+        msg.extend_from_slice(&payload);
+        while msg.len() % 4 != 0 {
+            msg.push(0);
+        }
+        msg
+    }
+
+    #[test]
+    fn test_parse_fork_exec_exit() {
+        let mut buf = message(PROC_EVENT_FORK, &[100, 100, 200, 200]);
+        buf.extend(message(PROC_EVENT_EXEC, &[200, 200]));
+        buf.extend(message(PROC_EVENT_EXIT, &[200, 200, 3 << 8, 17]));
+
+        assert_eq!(
+            parse_connector_message(&buf),
+            vec![
+                ProcEvent::Fork {
+                    ppid: 100,
+                    pid: 200
+                },
+                ProcEvent::Exec { pid: 200 },
+                ProcEvent::Exit {
+                    pid: 200,
+                    exit_code: Some(3)
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_skips_threads() {
+        // A new thread 201 in process 200, then that thread exiting
+        let mut buf = message(PROC_EVENT_FORK, &[200, 200, 201, 200]);
+        buf.extend(message(PROC_EVENT_EXIT, &[201, 200, 0, 0]));
+        assert!(parse_connector_message(&buf).is_empty());
+    }
+
+    #[test]
+    fn test_parse_exit_by_signal() {
+        // Killed by SIGKILL
+        let buf = message(PROC_EVENT_EXIT, &[300, 300, 9, 17]);
+        assert_eq!(
+            parse_connector_message(&buf),
+            vec![ProcEvent::Exit {
+                pid: 300,
+                exit_code: Some(128 + 9)
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_ignores_truncated_and_foreign_messages() {
+        let buf = message(PROC_EVENT_FORK, &[100, 100, 200, 200]);
+        assert!(parse_connector_message(&buf[..buf.len() - 8]).is_empty());
+        assert!(parse_connector_message(&[]).is_empty());
+
+        // Same layout addressed to another connector
+        let mut foreign = buf.clone();
+        foreign[NLMSG_HDRLEN..NLMSG_HDRLEN + 4].copy_from_slice(&7u32.to_ne_bytes());
+        assert!(parse_connector_message(&foreign).is_empty());
+    }
+
+    #[test]
+    fn test_listen_ack() {
+        assert_eq!(listen_ack(&message(PROC_EVENT_NONE, &[0])), Some(0));
+        assert_eq!(listen_ack(&message(PROC_EVENT_NONE, &[1])), Some(1));
+        assert_eq!(listen_ack(&message(PROC_EVENT_EXEC, &[1, 1])), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_ptrace_refuses_own_child() {
+        let mut child = std::process::Command::new("sleep")
+            .arg("5")
+            .spawn()
+            .expect("Failed to spawn sleep");
+        let result = PtraceSession::attach(child.id());
+        let _ = child.kill();
+        let _ = child.wait();
+
+        let err = result.err().expect("tracing our own child must be refused");
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+        assert!(PtraceSession::attach(std::process::id()).is_err());
+    }
+}
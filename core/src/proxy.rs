@@ -0,0 +1,1230 @@
+//! Egress proxy module
+//!
+//! A local HTTP proxy the wrapped agent is pointed at through `HTTP_PROXY`,
+//! `HTTPS_PROXY` and `ALL_PROXY`. Socket polling only sees IP addresses, and
+//! only those still open at a poll; the proxy sees the hostname of every
+//! request made by clients that honour those variables, and can refuse hosts
+//! that are not in the network whitelist.
+//!
+//! Plain HTTP requests are forwarded one per client connection. `CONNECT`
+//! opens a tunnel; when the client starts a TLS handshake through it, the
+//! server name (SNI) it asks for is reported, and must pass the whitelist as
+//! well as the host named in the request. The proxy waits for the whole
+//! ClientHello record, however many reads it takes, and refuses a tunnel
+//! whose handshake it cannot read rather than trusting the `CONNECT` host.
+
+use crate::bus::{EventBus, SubscribeOptions};
+use crate::detector::{Detector, NetworkConnection, NetworkWhitelist, ProviderCatalogue};
+use crate::error::CoreError;
+use crate::event::{Event, EventType};
+use crate::types::{RiskLevel, Swappable};
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Receiver;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Largest request line plus headers accepted from a client
+const MAX_HEAD_BYTES: usize = 64 * 1024;
+/// How long a new tunnel waits for the client to start a TLS handshake
+const SNI_WAIT: Duration = Duration::from_millis(500);
+/// How long a client that has started a TLS handshake has to finish sending
+/// its ClientHello
+const HELLO_WAIT: Duration = Duration::from_secs(5);
+/// Largest TLS record accepted as a ClientHello: a 5-byte header and the
+/// protocol's 2^14-byte limit
+const MAX_HELLO_BYTES: usize = 5 + 16 * 1024;
+/// How often the listener checks the stop flag when no client is waiting
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// Variables pointing clients at the proxy, set in both cases since tools
+/// differ in which one they read
+const PROXY_VARS: [&str; 3] = ["HTTP_PROXY", "HTTPS_PROXY", "ALL_PROXY"];
+
+/// Configuration for the egress proxy
+#[derive(Debug, Clone)]
+pub struct ProxyConfig {
+    /// Address to listen on. Port 0 picks a free port.
+    pub listen_addr: SocketAddr,
+    /// Refuse requests to hosts that are not in the network whitelist
+    pub block_unlisted: bool,
+    /// Hosts clients reach directly, passed on in `NO_PROXY`
+    pub no_proxy: Vec<String>,
+    /// How long to wait for a destination to accept a connection
+    pub connect_timeout: Duration,
+}
+
+impl Default for ProxyConfig {
+    fn default() -> Self {
+        Self {
+            listen_addr: SocketAddr::from(([127, 0, 0, 1], 0)),
+            block_unlisted: false,
+            no_proxy: vec![
+                "localhost".to_string(),
+                "127.0.0.1".to_string(),
+                "::1".to_string(),
+            ],
+            connect_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+impl ProxyConfig {
+    /// Set the address to listen on
+    pub fn listen_addr(mut self, addr: SocketAddr) -> Self {
+        self.listen_addr = addr;
+        self
+    }
+
+    /// Refuse requests to hosts that are not in the network whitelist
+    pub fn block_unlisted(mut self, block: bool) -> Self {
+        self.block_unlisted = block;
+        self
+    }
+
+    /// Set the hosts clients reach without the proxy
+    pub fn no_proxy(mut self, hosts: Vec<String>) -> Self {
+        self.no_proxy = hosts;
+        self
+    }
+
+    /// Set how long to wait for a destination to accept a connection
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+}
+
+/// HTTP proxy reporting and filtering the destinations of a process tree
+pub struct EgressProxy {
+    config: ProxyConfig,
+    listener: TcpListener,
+    local_addr: SocketAddr,
+    whitelist: Swappable<NetworkWhitelist>,
+    events: EventBus<Event>,
+    /// Process requests are attributed to
+    pid: u32,
+    process: String,
+    stop_flag: Arc<AtomicBool>,
+    accept_thread: Option<JoinHandle<()>>,
+}
+
+impl EgressProxy {
+    /// Bind the proxy's listening socket. Clients can connect as soon as
+    /// this returns; their requests are served once the proxy is started.
+    pub fn new(config: ProxyConfig) -> std::result::Result<Self, CoreError> {
+        let listener = TcpListener::bind(config.listen_addr).map_err(|e| {
+            CoreError::Proxy(format!("Failed to listen on {}: {}", config.listen_addr, e))
+        })?;
+        let local_addr = listener.local_addr()?;
+
+        Ok(Self {
+            config,
+            listener,
+            local_addr,
+            whitelist: Swappable::default(),
+            events: EventBus::new(),
+            pid: 0,
+            process: String::new(),
+            stop_flag: Arc::new(AtomicBool::new(false)),
+            accept_thread: None,
+        })
+    }
+
+    /// Create with custom whitelist
+    pub fn with_whitelist(mut self, whitelist: NetworkWhitelist) -> Self {
+        self.whitelist = Swappable::new(whitelist);
+        self
+    }
+
+    /// Share a whitelist handle that can be swapped while the proxy runs
+    pub fn with_shared_whitelist(mut self, whitelist: Swappable<NetworkWhitelist>) -> Self {
+        self.whitelist = whitelist;
+        self
+    }
+
+    /// Attribute requests to a process in the events reported
+    pub fn with_process(mut self, pid: u32, name: impl Into<String>) -> Self {
+        self.pid = pid;
+        self.process = name.into();
+        self
+    }
+
+    /// Replace the whitelist used to check subsequent requests
+    pub fn set_whitelist(&self, whitelist: NetworkWhitelist) {
+        self.whitelist.store(whitelist);
+    }
+
+    /// Address the proxy listens on
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Point clients started with `env` at the proxy, replacing proxy
+    /// variables already there
+    pub fn inject(&self, env: &mut Vec<(String, String)>) {
+        let url = format!("http://{}", self.local_addr);
+        let no_proxy = self.config.no_proxy.join(",");
+        env.retain(|(key, _)| {
+            !PROXY_VARS
+                .iter()
+                .chain(&["NO_PROXY"])
+                .any(|var| key.eq_ignore_ascii_case(var))
+        });
+        for var in PROXY_VARS {
+            env.push((var.to_string(), url.clone()));
+            env.push((var.to_ascii_lowercase(), url.clone()));
+        }
+        env.push(("NO_PROXY".to_string(), no_proxy.clone()));
+        env.push(("no_proxy".to_string(), no_proxy));
+    }
+
+    /// Subscribe to request events
+    pub fn subscribe(&self) -> Receiver<Event> {
+        self.events.subscribe()
+    }
+
+    /// Subscribe with a custom buffer, lag policy and filter
+    pub fn subscribe_with(&self, options: SubscribeOptions) -> Receiver<Event> {
+        self.events.subscribe_with(options)
+    }
+
+    /// Check if running
+    pub fn is_running(&self) -> bool {
+        self.accept_thread.is_some() && !self.stop_flag.load(Ordering::Relaxed)
+    }
+
+    /// Start serving clients
+    pub fn start(&mut self) -> std::result::Result<(), CoreError> {
+        if self.accept_thread.is_some() {
+            return Ok(());
+        }
+        let listener = self.listener.try_clone()?;
+        // Non-blocking so the accept loop can notice the stop flag
+        listener.set_nonblocking(true)?;
+        self.stop_flag.store(false, Ordering::Relaxed);
+
+        let handler = Arc::new(RequestHandler {
+            whitelist: self.whitelist.clone(),
+            events: self.events.clone(),
+            block_unlisted: self.config.block_unlisted,
+            connect_timeout: self.config.connect_timeout,
+            pid: self.pid,
+            process: self.process.clone(),
+        });
+        let stop_flag = Arc::clone(&self.stop_flag);
+
+        let handle = thread::spawn(move || {
+            while !stop_flag.load(Ordering::Relaxed) {
+                match listener.accept() {
+                    Ok((client, _)) => {
+                        let handler = Arc::clone(&handler);
+                        thread::spawn(move || {
+                            if client.set_nonblocking(false).is_ok() {
+                                let _ = handler.serve(client);
+                            }
+                        });
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                        thread::sleep(ACCEPT_POLL_INTERVAL)
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                    Err(e) => {
+                        eprintln!("[agent-watch] Warning: Egress proxy failed to accept: {e}");
+                        thread::sleep(ACCEPT_POLL_INTERVAL);
+                    }
+                }
+            }
+        });
+
+        self.accept_thread = Some(handle);
+        Ok(())
+    }
+
+    /// Stop accepting clients. Tunnels already open are left to finish.
+    pub fn stop(&mut self) {
+        self.stop_flag.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.accept_thread.take() {
+            let _ = handle.join();
+        }
+        // Subscribers see a disconnect once no more events can arrive
+        self.events.close();
+    }
+
+    /// Signal the proxy to stop without waiting for the listener to finish
+    pub fn signal_stop(&self) {
+        self.stop_flag.store(true, Ordering::Relaxed);
+    }
+}
+
+impl crate::types::MonitoringSubsystem for EgressProxy {
+    fn start(&mut self) -> std::result::Result<(), CoreError> {
+        EgressProxy::start(self)
+    }
+
+    fn stop(&mut self) {
+        EgressProxy::stop(self)
+    }
+
+    fn signal_stop(&self) {
+        EgressProxy::signal_stop(self)
+    }
+
+    fn is_running(&self) -> bool {
+        EgressProxy::is_running(self)
+    }
+}
+
+impl Drop for EgressProxy {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Where a client asked the proxy to send it
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Target {
+    /// `CONNECT host:port`
+    Tunnel { host: String, port: u16 },
+    /// `GET http://host:port/path` and other methods with an absolute URL
+    Http {
+        host: String,
+        port: u16,
+        /// Path and query sent on to the destination
+        path: String,
+    },
+}
+
+/// Request line and headers sent to the proxy
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RequestHead {
+    method: String,
+    target: String,
+    version: String,
+    headers: Vec<(String, String)>,
+}
+
+impl RequestHead {
+    /// Parse the request line and headers, without the blank line ending them
+    fn parse(head: &str) -> Option<Self> {
+        let mut lines = head.split("\r\n");
+        let mut request_line = lines.next()?.split(' ');
+        let method = request_line.next()?.to_string();
+        let target = request_line.next()?.to_string();
+        let version = request_line.next()?.to_string();
+        if request_line.next().is_some() || !version.starts_with("HTTP/") {
+            return None;
+        }
+        let headers = lines
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                let (name, value) = line.split_once(':')?;
+                Some((name.trim().to_string(), value.trim().to_string()))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(Self {
+            method,
+            target,
+            version,
+            headers,
+        })
+    }
+
+    /// Destination of the request. Only `CONNECT` and absolute `http://`
+    /// URLs can be proxied.
+    fn destination(&self) -> Option<Target> {
+        if self.method.eq_ignore_ascii_case("CONNECT") {
+            let (host, port) = split_host_port(&self.target, None)?;
+            return Some(Target::Tunnel { host, port });
+        }
+        let scheme = self.target.get(..7)?;
+        if !scheme.eq_ignore_ascii_case("http://") {
+            return None;
+        }
+        let rest = &self.target[7..];
+        let end = rest.find(['/', '?']).unwrap_or(rest.len());
+        // Credentials in the URL are not part of the host
+        let authority = rest[..end].rsplit('@').next()?;
+        let (host, port) = split_host_port(authority, Some(80))?;
+        let path = match &rest[end..] {
+            "" => "/".to_string(),
+            path if path.starts_with('?') => format!("/{}", path),
+            path => path.to_string(),
+        };
+        Some(Target::Http { host, port, path })
+    }
+
+    /// Request head to send to the destination: origin-form target, proxy
+    /// headers dropped and the connection closed after one response
+    fn forwarded(&self, path: &str, authority: &str) -> Vec<u8> {
+        let mut head = format!("{} {} {}\r\n", self.method, path, self.version);
+        let mut has_host = false;
+        for (name, value) in &self.headers {
+            let hop_by_hop = [
+                "Proxy-Connection",
+                "Proxy-Authorization",
+                "Connection",
+                "Keep-Alive",
+            ]
+            .iter()
+            .any(|hop| name.eq_ignore_ascii_case(hop));
+            if hop_by_hop {
+                continue;
+            }
+            has_host |= name.eq_ignore_ascii_case("Host");
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        if !has_host {
+            head.push_str(&format!("Host: {}\r\n", authority));
+        }
+        head.push_str("Connection: close\r\n\r\n");
+        head.into_bytes()
+    }
+}
+
+/// Split `host:port` or `[v6]:port`, using `default_port` when none is given
+fn split_host_port(authority: &str, default_port: Option<u16>) -> Option<(String, u16)> {
+    let (host, port) = if let Some(rest) = authority.strip_prefix('[') {
+        let (host, after) = rest.split_once(']')?;
+        match after.strip_prefix(':') {
+            Some(port) => (host, Some(port)),
+            None if after.is_empty() => (host, None),
+            None => return None,
+        }
+    } else {
+        match authority.rsplit_once(':') {
+            // A bare IPv6 address has colons but no port
+            Some((host, port)) if !host.contains(':') => (host, Some(port)),
+            _ => (authority, None),
+        }
+    };
+    let port = match port {
+        Some(port) => port.parse().ok()?,
+        None => default_port?,
+    };
+    if host.is_empty() {
+        return None;
+    }
+    Some((host.trim_end_matches('.').to_ascii_lowercase(), port))
+}
+
+/// State shared by the threads serving clients
+struct RequestHandler {
+    whitelist: Swappable<NetworkWhitelist>,
+    events: EventBus<Event>,
+    block_unlisted: bool,
+    connect_timeout: Duration,
+    pid: u32,
+    process: String,
+}
+
+impl RequestHandler {
+    /// Serve one client connection
+    fn serve(&self, mut client: TcpStream) -> io::Result<()> {
+        let (head, rest) = read_head(&mut client)?;
+        let Some(request) = std::str::from_utf8(&head).ok().and_then(RequestHead::parse) else {
+            return respond(&mut client, "400 Bad Request", "malformed request");
+        };
+        match request.destination() {
+            Some(Target::Tunnel { host, port }) => self.tunnel(client, &host, port, rest),
+            Some(Target::Http { host, port, path }) => {
+                self.forward(client, &request, &host, port, &path, rest)
+            }
+            None => respond(
+                &mut client,
+                "400 Bad Request",
+                "only CONNECT and absolute http:// URLs can be proxied",
+            ),
+        }
+    }
+
+    /// Forward a plain HTTP request and relay the response
+    fn forward(
+        &self,
+        mut client: TcpStream,
+        request: &RequestHead,
+        host: &str,
+        port: u16,
+        path: &str,
+        body: Vec<u8>,
+    ) -> io::Result<()> {
+        let allowed = is_allowed(&self.whitelist.load(), host, port);
+        let blocked = self.block_unlisted && !allowed;
+        self.report(&request.method, host, port, allowed, blocked);
+        if blocked {
+            return refuse(&mut client, host, port);
+        }
+
+        let mut upstream = match connect(host, port, self.connect_timeout) {
+            Ok(upstream) => upstream,
+            Err(e) => return unreachable(&mut client, host, port, &e),
+        };
+        let authority = if port == 80 {
+            host.to_string()
+        } else {
+            format!("{}:{}", host, port)
+        };
+        upstream.write_all(&request.forwarded(path, &authority))?;
+        upstream.write_all(&body)?;
+        relay(client, upstream)
+    }
+
+    /// Open a tunnel, checking the TLS server name the client asks for
+    fn tunnel(
+        &self,
+        mut client: TcpStream,
+        host: &str,
+        port: u16,
+        initial: Vec<u8>,
+    ) -> io::Result<()> {
+        let whitelist = self.whitelist.load();
+        let host_allowed = is_allowed(&whitelist, host, port);
+        if self.block_unlisted && !host_allowed {
+            self.report("CONNECT", host, port, false, true);
+            return refuse(&mut client, host, port);
+        }
+
+        let mut upstream = match connect(host, port, self.connect_timeout) {
+            Ok(upstream) => upstream,
+            Err(e) => {
+                self.report("CONNECT", host, port, host_allowed, false);
+                return unreachable(&mut client, host, port, &e);
+            }
+        };
+        client.write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n")?;
+
+        let initial = read_initial(&mut client, initial)?;
+        // A tunnel to an allowed host must not carry a handshake for another,
+        // and one whose handshake can't be read can't be checked
+        let (name, allowed) = match inspect_handshake(&initial) {
+            Handshake::Tls(Some(sni)) if sni != host => {
+                let allowed = host_allowed && is_allowed(&whitelist, &sni, port);
+                (sni, allowed)
+            }
+            Handshake::Malformed => (host.to_string(), false),
+            _ => (host.to_string(), host_allowed),
+        };
+        let blocked = self.block_unlisted && !allowed;
+        self.report("CONNECT", &name, port, allowed, blocked);
+        if blocked {
+            let _ = client.shutdown(Shutdown::Both);
+            return Ok(());
+        }
+
+        upstream.write_all(&initial)?;
+        relay(client, upstream)
+    }
+
+    fn report(&self, method: &str, host: &str, port: u16, allowed: bool, blocked: bool) {
+        let risk_level = if allowed {
+            RiskLevel::Medium
+        } else {
+            self.whitelist.load().risk_level(&NetworkConnection {
+                host: host.to_string(),
+                port,
+                protocol: "tcp".to_string(),
+            })
+        };
+        self.events.publish(
+            Event::new(
+                EventType::ProxyRequest {
+                    method: method.to_string(),
+                    host: host.to_string(),
+                    port,
+                    blocked,
+                    provider: None,
+                },
+                self.process.clone(),
+                self.pid,
+                risk_level,
+            )
+            .with_provider(ProviderCatalogue::builtin()),
+        );
+    }
+}
+
+/// Whether the whitelist allows both the host and the port
+fn is_allowed(whitelist: &NetworkWhitelist, host: &str, port: u16) -> bool {
+    whitelist.is_host_allowed(host) && whitelist.is_port_allowed(port)
+}
+
+/// Read up to the blank line ending the request head. Returns the head and
+/// whatever the client sent after it.
+fn read_head(client: &mut TcpStream) -> io::Result<(Vec<u8>, Vec<u8>)> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+    loop {
+        if let Some(end) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+            let rest = buffer.split_off(end + 4);
+            buffer.truncate(end);
+            return Ok((buffer, rest));
+        }
+        if buffer.len() > MAX_HEAD_BYTES {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "request head too large",
+            ));
+        }
+        match client.read(&mut chunk)? {
+            0 => return Err(io::ErrorKind::UnexpectedEof.into()),
+            n => buffer.extend_from_slice(&chunk[..n]),
+        }
+    }
+}
+
+/// First bytes a client sends through a new tunnel, after any it sent with
+/// the request head. A TLS handshake is read until its first record is
+/// complete, up to `MAX_HELLO_BYTES` and `HELLO_WAIT`; nothing is returned
+/// if the client waits for the server to speak first.
+fn read_initial(client: &mut TcpStream, mut buffer: Vec<u8>) -> io::Result<Vec<u8>> {
+    let started = Instant::now();
+    let mut chunk = [0u8; 4096];
+    loop {
+        let wanted = match (buffer.first(), buffer.get(3..5)) {
+            (None, _) => 1,
+            (Some(22), Some(len)) => 5 + u16::from_be_bytes([len[0], len[1]]) as usize,
+            (Some(22), None) => 5,
+            (Some(_), _) => return Ok(buffer),
+        };
+        if buffer.len() >= wanted.min(MAX_HELLO_BYTES) {
+            return Ok(buffer);
+        }
+        let limit = if buffer.is_empty() {
+            SNI_WAIT
+        } else {
+            HELLO_WAIT
+        };
+        let remaining = limit.saturating_sub(started.elapsed());
+        if remaining.is_zero() {
+            return Ok(buffer);
+        }
+
+        client.set_read_timeout(Some(remaining))?;
+        let read = client.read(&mut chunk);
+        client.set_read_timeout(None)?;
+        match read {
+            Ok(0) => return Ok(buffer),
+            Ok(n) => buffer.extend_from_slice(&chunk[..n]),
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                return Ok(buffer)
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Connect to the first address of `host` that accepts
+fn connect(host: &str, port: u16, timeout: Duration) -> io::Result<TcpStream> {
+    let mut last_error = io::Error::new(io::ErrorKind::NotFound, "no addresses found");
+    for addr in (host, port).to_socket_addrs()? {
+        match TcpStream::connect_timeout(&addr, timeout) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_error = e,
+        }
+    }
+    Err(last_error)
+}
+
+/// Copy data both ways until the destination closes its side
+fn relay(client: TcpStream, upstream: TcpStream) -> io::Result<()> {
+    let mut client_reader = client.try_clone()?;
+    let mut upstream_writer = upstream.try_clone()?;
+    let upload = thread::spawn(move || {
+        let _ = io::copy(&mut client_reader, &mut upstream_writer);
+        let _ = upstream_writer.shutdown(Shutdown::Write);
+    });
+
+    let (mut upstream, mut client) = (upstream, client);
+    let _ = io::copy(&mut upstream, &mut client);
+    // Also ends the upload if the client keeps its side open
+    let _ = client.shutdown(Shutdown::Both);
+    let _ = upload.join();
+    Ok(())
+}
+
+fn refuse(client: &mut TcpStream, host: &str, port: u16) -> io::Result<()> {
+    respond(
+        client,
+        "403 Forbidden",
+        &format!(
+            "{}:{} is not in the agent-watch network whitelist",
+            host, port
+        ),
+    )
+}
+
+fn unreachable(client: &mut TcpStream, host: &str, port: u16, error: &io::Error) -> io::Result<()> {
+    respond(
+        client,
+        "502 Bad Gateway",
+        &format!("cannot reach {}:{}: {}", host, port, error),
+    )
+}
+
+/// Answer the client with a plain-text response and close the connection
+fn respond(client: &mut TcpStream, status: &str, message: &str) -> io::Result<()> {
+    let body = format!("agent-watch: {}\n", message);
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    client.write_all(response.as_bytes())?;
+    let _ = client.shutdown(Shutdown::Both);
+    Ok(())
+}
+
+/// Cursor over a TLS message
+struct ByteReader<'a>(&'a [u8]);
+
+impl<'a> ByteReader<'a> {
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        if self.0.len() < n {
+            return None;
+        }
+        let (head, tail) = self.0.split_at(n);
+        self.0 = tail;
+        Some(head)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.take(1).map(|b| b[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.take(2).map(|b| u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    /// Take a block prefixed with its one-byte length
+    fn block8(&mut self) -> Option<&'a [u8]> {
+        let len = self.u8()? as usize;
+        self.take(len)
+    }
+
+    /// Take a block prefixed with its two-byte length
+    fn block16(&mut self) -> Option<&'a [u8]> {
+        let len = self.u16()? as usize;
+        self.take(len)
+    }
+}
+
+/// What the first bytes through a tunnel say about its destination
+#[derive(Debug, PartialEq, Eq)]
+enum Handshake {
+    /// Not TLS, or nothing sent yet
+    None,
+    /// A TLS ClientHello, with the server name it asks for if any
+    Tls(Option<String>),
+    /// Starts a TLS handshake that isn't a complete, well-formed ClientHello
+    Malformed,
+}
+
+fn inspect_handshake(data: &[u8]) -> Handshake {
+    match data.first() {
+        Some(22) => parse_client_hello(data).map_or(Handshake::Malformed, Handshake::Tls),
+        _ => Handshake::None,
+    }
+}
+
+/// Server name from the SNI extension of the TLS ClientHello in the first
+/// record of `data`. `None` if the record is incomplete or malformed.
+fn parse_client_hello(data: &[u8]) -> Option<Option<String>> {
+    // Record header: content type 22 (handshake), version, length
+    let mut header = ByteReader(data);
+    if header.u8()? != 22 {
+        return None;
+    }
+    header.take(2)?;
+    let mut record = ByteReader(header.block16()?);
+    // Handshake header: type 1 (ClientHello), 24-bit length
+    if record.u8()? != 1 {
+        return None;
+    }
+    record.take(3)?;
+    // Client version and random
+    record.take(2 + 32)?;
+    record.block8()?; // session ID
+    record.block16()?; // cipher suites
+    record.block8()?; // compression methods
+    if record.0.is_empty() {
+        // No extensions at all
+        return Some(None);
+    }
+
+    let mut extensions = ByteReader(record.block16()?);
+    while let Some(kind) = extensions.u16() {
+        let body = extensions.block16()?;
+        // server_name
+        if kind != 0 {
+            continue;
+        }
+        let mut names = ByteReader(ByteReader(body).block16()?);
+        while let Some(name_type) = names.u8() {
+            let name = names.block16()?;
+            // host_name
+            if name_type == 0 {
+                return std::str::from_utf8(name)
+                    .ok()
+                    .map(|name| Some(name.to_ascii_lowercase()));
+            }
+        }
+        return Some(None);
+    }
+    Some(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Minimal TLS ClientHello asking for `name`
+    fn client_hello(name: &str) -> Vec<u8> {
+        let mut sni = Vec::new();
+        sni.extend_from_slice(&((name.len() + 3) as u16).to_be_bytes());
+        sni.push(0);
+        sni.extend_from_slice(&(name.len() as u16).to_be_bytes());
+        sni.extend_from_slice(name.as_bytes());
+
+        let mut extensions = Vec::new();
+        // An unrelated extension first (supported_versions)
+        extensions.extend_from_slice(&[0x00, 0x2b, 0x00, 0x03, 0x02, 0x03, 0x04]);
+        extensions.extend_from_slice(&[0x00, 0x00]);
+        extensions.extend_from_slice(&(sni.len() as u16).to_be_bytes());
+        extensions.extend_from_slice(&sni);
+
+        let mut hello = vec![0x03, 0x03];
+        hello.extend_from_slice(&[0u8; 32]);
+        hello.push(0); // session ID
+        hello.extend_from_slice(&[0x00, 0x02, 0x13, 0x01]); // one cipher suite
+        hello.extend_from_slice(&[0x01, 0x00]); // null compression
+        hello.extend_from_slice(&(extensions.len() as u16).to_be_bytes());
+        hello.extend_from_slice(&extensions);
+
+        let mut handshake = vec![0x01];
+        handshake.extend_from_slice(&(hello.len() as u32).to_be_bytes()[1..]);
+        handshake.extend_from_slice(&hello);
+
+        let mut record = vec![0x16, 0x03, 0x01];
+        record.extend_from_slice(&(handshake.len() as u16).to_be_bytes());
+        record.extend_from_slice(&handshake);
+        record
+    }
+
+    /// Read a whole response from a connection the other side closes
+    fn read_all(stream: &mut TcpStream) -> String {
+        let mut response = String::new();
+        let _ = stream.read_to_string(&mut response);
+        response
+    }
+
+    /// HTTP server answering every request with `hello` and the request head
+    /// it received, until `requests` have been served
+    fn http_server(requests: usize) -> (SocketAddr, JoinHandle<Vec<String>>) {
+        let server = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap();
+        let handle = thread::spawn(move || {
+            let mut heads = Vec::new();
+            for _ in 0..requests {
+                let (mut conn, _) = server.accept().unwrap();
+                let (head, _) = read_head(&mut conn).unwrap();
+                heads.push(String::from_utf8(head).unwrap());
+                conn.write_all(
+                    b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\nConnection: close\r\n\r\nhello",
+                )
+                .unwrap();
+            }
+            heads
+        });
+        (addr, handle)
+    }
+
+    fn start_proxy(block: bool, hosts: &[&str]) -> (EgressProxy, Receiver<Event>) {
+        let whitelist =
+            NetworkWhitelist::new(hosts.iter().map(|h| h.to_string()).collect(), vec![]);
+        let mut proxy = EgressProxy::new(ProxyConfig::default().block_unlisted(block))
+            .unwrap()
+            .with_whitelist(whitelist)
+            .with_process(4321, "agent");
+        let rx = proxy.subscribe();
+        proxy.start().unwrap();
+        (proxy, rx)
+    }
+
+    fn next_request(rx: &Receiver<Event>) -> (String, String, u16, bool, RiskLevel) {
+        let event = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(event.process, "agent");
+        assert_eq!(event.pid, 4321);
+        match event.event_type {
+            EventType::ProxyRequest {
+                method,
+                host,
+                port,
+                blocked,
+                ..
+            } => (method, host, port, blocked, event.risk_level),
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_request_head() {
+        let head = RequestHead::parse(
+            "GET http://user:pw@Example.COM:8080/a/b?c=1 HTTP/1.1\r\nHost: example.com:8080\r\nProxy-Connection: keep-alive",
+        )
+        .unwrap();
+        assert_eq!(head.method, "GET");
+        assert_eq!(head.headers.len(), 2);
+        assert_eq!(
+            head.destination(),
+            Some(Target::Http {
+                host: "example.com".to_string(),
+                port: 8080,
+                path: "/a/b?c=1".to_string(),
+            })
+        );
+        let forwarded = String::from_utf8(head.forwarded("/a/b?c=1", "example.com:8080")).unwrap();
+        assert_eq!(
+            forwarded,
+            "GET /a/b?c=1 HTTP/1.1\r\nHost: example.com:8080\r\nConnection: close\r\n\r\n"
+        );
+
+        let head = RequestHead::parse("CONNECT github.com:443 HTTP/1.1\r\n").unwrap();
+        assert_eq!(
+            head.destination(),
+            Some(Target::Tunnel {
+                host: "github.com".to_string(),
+                port: 443,
+            })
+        );
+
+        let head = RequestHead::parse("GET http://example.com HTTP/1.0").unwrap();
+        assert!(
+            matches!(head.destination(), Some(Target::Http { port: 80, ref path, .. }) if path == "/")
+        );
+
+        // Origin-form and missing tunnel ports cannot be proxied
+        let head = RequestHead::parse("GET /index.html HTTP/1.1").unwrap();
+        assert_eq!(head.destination(), None);
+        let head = RequestHead::parse("CONNECT github.com HTTP/1.1").unwrap();
+        assert_eq!(head.destination(), None);
+        assert!(RequestHead::parse("garbage").is_none());
+    }
+
+    #[test]
+    fn test_split_host_port() {
+        assert_eq!(
+            split_host_port("[::1]:8443", None),
+            Some(("::1".to_string(), 8443))
+        );
+        assert_eq!(
+            split_host_port("[2001:db8::1]", Some(80)),
+            Some(("2001:db8::1".to_string(), 80))
+        );
+        assert_eq!(
+            split_host_port("API.example.com.:443", None),
+            Some(("api.example.com".to_string(), 443))
+        );
+        assert_eq!(split_host_port("example.com:http", None), None);
+        assert_eq!(split_host_port(":443", None), None);
+    }
+
+    #[test]
+    fn test_inspect_handshake() {
+        assert_eq!(
+            inspect_handshake(&client_hello("API.Example.com")),
+            Handshake::Tls(Some("api.example.com".to_string()))
+        );
+        let hello = client_hello("example.com");
+        assert_eq!(
+            inspect_handshake(&hello[..hello.len() - 4]),
+            Handshake::Malformed
+        );
+        assert_eq!(inspect_handshake(&hello[..3]), Handshake::Malformed);
+        assert_eq!(
+            inspect_handshake(b"SSH-2.0-OpenSSH_9.6\r\n"),
+            Handshake::None
+        );
+        assert_eq!(inspect_handshake(&[]), Handshake::None);
+    }
+
+    #[test]
+    fn test_inject_env() {
+        let proxy = EgressProxy::new(ProxyConfig::default()).unwrap();
+        let mut env = vec![
+            (
+                "https_proxy".to_string(),
+                "http://elsewhere:3128".to_string(),
+            ),
+            ("TERM".to_string(), "xterm".to_string()),
+        ];
+        proxy.inject(&mut env);
+
+        let url = format!("http://{}", proxy.local_addr());
+        let get = |key: &str| {
+            env.iter()
+                .filter(|(k, _)| k == key)
+                .map(|(_, v)| v.as_str())
+                .collect::<Vec<_>>()
+        };
+        for key in [
+            "HTTP_PROXY",
+            "HTTPS_PROXY",
+            "ALL_PROXY",
+            "http_proxy",
+            "https_proxy",
+        ] {
+            assert_eq!(get(key), vec![url.as_str()], "{key}");
+        }
+        assert_eq!(get("NO_PROXY"), vec!["localhost,127.0.0.1,::1"]);
+        assert_eq!(get("TERM"), vec!["xterm"]);
+    }
+
+    #[test]
+    fn test_bind_failure() {
+        let taken = TcpListener::bind("127.0.0.1:0").unwrap();
+        let config = ProxyConfig::default().listen_addr(taken.local_addr().unwrap());
+        assert!(matches!(EgressProxy::new(config), Err(CoreError::Proxy(_))));
+    }
+
+    #[test]
+    fn test_proxy_forwards_http_request() {
+        let (server, server_handle) = http_server(1);
+        let (mut proxy, rx) = start_proxy(false, &["127.0.0.1"]);
+
+        let mut client = TcpStream::connect(proxy.local_addr()).unwrap();
+        write!(
+            client,
+            "GET http://{}/path?q=1 HTTP/1.1\r\nHost: {}\r\nProxy-Connection: keep-alive\r\n\r\n",
+            server, server
+        )
+        .unwrap();
+        let response = read_all(&mut client);
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+        assert!(response.ends_with("hello"));
+
+        let heads = server_handle.join().unwrap();
+        assert!(
+            heads[0].starts_with("GET /path?q=1 HTTP/1.1\r\n"),
+            "{}",
+            heads[0]
+        );
+        assert!(!heads[0].contains("Proxy-Connection"));
+
+        let (method, host, port, blocked, risk) = next_request(&rx);
+        assert_eq!(method, "GET");
+        assert_eq!(host, "127.0.0.1");
+        assert_eq!(port, server.port());
+        assert!(!blocked);
+        assert_eq!(risk, RiskLevel::Medium);
+        proxy.stop();
+    }
+
+    #[test]
+    fn test_proxy_blocks_unlisted_host() {
+        let (mut proxy, rx) = start_proxy(true, &["github.com"]);
+
+        let mut client = TcpStream::connect(proxy.local_addr()).unwrap();
+        client
+            .write_all(b"GET http://127.0.0.1:9/ HTTP/1.1\r\nHost: 127.0.0.1:9\r\n\r\n")
+            .unwrap();
+        let response = read_all(&mut client);
+        assert!(response.starts_with("HTTP/1.1 403 Forbidden"), "{response}");
+
+        let mut client = TcpStream::connect(proxy.local_addr()).unwrap();
+        client
+            .write_all(b"CONNECT 127.0.0.1:9 HTTP/1.1\r\n\r\n")
+            .unwrap();
+        let response = read_all(&mut client);
+        assert!(response.starts_with("HTTP/1.1 403 Forbidden"), "{response}");
+
+        for method in ["GET", "CONNECT"] {
+            let (reported, host, port, blocked, risk) = next_request(&rx);
+            assert_eq!(reported, method);
+            assert_eq!((host.as_str(), port), ("127.0.0.1", 9));
+            assert!(blocked);
+            assert_eq!(risk, RiskLevel::High);
+        }
+        proxy.stop();
+    }
+
+    #[test]
+    fn test_proxy_reports_unlisted_host_without_blocking() {
+        let (server, server_handle) = http_server(1);
+        let (mut proxy, rx) = start_proxy(false, &["github.com"]);
+
+        let mut client = TcpStream::connect(proxy.local_addr()).unwrap();
+        write!(client, "GET http://{}/ HTTP/1.1\r\n\r\n", server).unwrap();
+        assert!(read_all(&mut client).ends_with("hello"));
+        // The Host header is filled in when the client leaves it out
+        assert!(server_handle.join().unwrap()[0].contains(&format!("Host: {}", server)));
+
+        let (_, _, _, blocked, risk) = next_request(&rx);
+        assert!(!blocked);
+        assert_eq!(risk, RiskLevel::High);
+        proxy.stop();
+    }
+
+    #[test]
+    fn test_proxy_tunnel_reports_sni() {
+        // Echo server standing in for a TLS server
+        let server = TcpListener::bind("127.0.0.1:0").unwrap();
+        let server_addr = server.local_addr().unwrap();
+        let server_handle = thread::spawn(move || {
+            let (mut conn, _) = server.accept().unwrap();
+            let mut received = Vec::new();
+            let _ = conn.read_to_end(&mut received);
+            conn.write_all(&received).unwrap();
+            received
+        });
+        let (mut proxy, rx) = start_proxy(false, &["127.0.0.1"]);
+
+        let mut client = TcpStream::connect(proxy.local_addr()).unwrap();
+        write!(client, "CONNECT {} HTTP/1.1\r\n\r\n", server_addr).unwrap();
+        let mut established = [0u8; 39];
+        client.read_exact(&mut established).unwrap();
+        assert_eq!(&established, b"HTTP/1.1 200 Connection Established\r\n\r\n");
+
+        let hello = client_hello("api.example.com");
+        client.write_all(&hello).unwrap();
+        client.shutdown(Shutdown::Write).unwrap();
+        let mut echoed = Vec::new();
+        client.read_to_end(&mut echoed).unwrap();
+        assert_eq!(echoed, hello);
+        assert_eq!(server_handle.join().unwrap(), hello);
+
+        let (method, host, port, blocked, _) = next_request(&rx);
+        assert_eq!(method, "CONNECT");
+        assert_eq!(host, "api.example.com");
+        assert_eq!(port, server_addr.port());
+        assert!(!blocked);
+        proxy.stop();
+    }
+
+    #[test]
+    fn test_proxy_blocks_tunnel_to_unlisted_sni() {
+        let server = TcpListener::bind("127.0.0.1:0").unwrap();
+        let server_addr = server.local_addr().unwrap();
+        let (mut proxy, rx) = start_proxy(true, &["127.0.0.1"]);
+
+        let mut client = TcpStream::connect(proxy.local_addr()).unwrap();
+        write!(client, "CONNECT {} HTTP/1.1\r\n\r\n", server_addr).unwrap();
+        client
+            .write_all(&client_hello("exfil.example.net"))
+            .unwrap();
+        // The tunnel is accepted, then closed once the handshake names a
+        // host outside the whitelist
+        let response = read_all(&mut client);
+        assert_eq!(response, "HTTP/1.1 200 Connection Established\r\n\r\n");
+
+        let (_, host, _, blocked, risk) = next_request(&rx);
+        assert_eq!(host, "exfil.example.net");
+        assert!(blocked);
+        assert_eq!(risk, RiskLevel::High);
+        proxy.stop();
+    }
+
+    #[test]
+    fn test_proxy_checks_split_client_hello() {
+        let server = TcpListener::bind("127.0.0.1:0").unwrap();
+        let server_addr = server.local_addr().unwrap();
+        let (mut proxy, rx) = start_proxy(true, &["127.0.0.1"]);
+
+        let mut client = TcpStream::connect(proxy.local_addr()).unwrap();
+        client.set_nodelay(true).unwrap();
+        // Part of the record header arrives with the request, the rest of
+        // the ClientHello in two later writes
+        let hello = client_hello("exfil.example.net");
+        let mut request = format!("CONNECT {} HTTP/1.1\r\n\r\n", server_addr).into_bytes();
+        request.extend_from_slice(&hello[..3]);
+        client.write_all(&request).unwrap();
+        for part in [&hello[3..20], &hello[20..]] {
+            thread::sleep(Duration::from_millis(100));
+            client.write_all(part).unwrap();
+        }
+        let response = read_all(&mut client);
+        assert_eq!(response, "HTTP/1.1 200 Connection Established\r\n\r\n");
+
+        let (_, host, _, blocked, _) = next_request(&rx);
+        assert_eq!(host, "exfil.example.net");
+        assert!(blocked);
+        drop(server);
+        proxy.stop();
+    }
+
+    #[test]
+    fn test_proxy_blocks_unreadable_client_hello() {
+        let server = TcpListener::bind("127.0.0.1:0").unwrap();
+        let server_addr = server.local_addr().unwrap();
+        let (mut proxy, rx) = start_proxy(true, &["127.0.0.1"]);
+
+        let mut client = TcpStream::connect(proxy.local_addr()).unwrap();
+        write!(client, "CONNECT {} HTTP/1.1\r\n\r\n", server_addr).unwrap();
+        // A truncated handshake, then the client gives up sending
+        let hello = client_hello("exfil.example.net");
+        client.write_all(&hello[..hello.len() / 2]).unwrap();
+        client.shutdown(Shutdown::Write).unwrap();
+        let response = read_all(&mut client);
+        assert_eq!(response, "HTTP/1.1 200 Connection Established\r\n\r\n");
+
+        // The CONNECT host is allowed, but can't vouch for the handshake
+        let (_, host, _, blocked, _) = next_request(&rx);
+        assert_eq!(host, "127.0.0.1");
+        assert!(blocked);
+        drop(server);
+        proxy.stop();
+    }
+
+    #[test]
+    fn test_proxy_reports_unreachable_destination() {
+        // Bound but never accepted from, then closed
+        let closed = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let (mut proxy, rx) = start_proxy(false, &["127.0.0.1"]);
+
+        let mut client = TcpStream::connect(proxy.local_addr()).unwrap();
+        write!(client, "GET http://{}/ HTTP/1.1\r\n\r\n", closed).unwrap();
+        let response = read_all(&mut client);
+        assert!(
+            response.starts_with("HTTP/1.1 502 Bad Gateway"),
+            "{response}"
+        );
+
+        let (_, host, _, blocked, _) = next_request(&rx);
+        assert_eq!(host, "127.0.0.1");
+        assert!(!blocked);
+        proxy.stop();
+    }
+
+    #[test]
+    fn test_proxy_rejects_origin_form_request() {
+        let (mut proxy, rx) = start_proxy(false, &[]);
+        let mut client = TcpStream::connect(proxy.local_addr()).unwrap();
+        client
+            .write_all(b"GET / HTTP/1.1\r\nHost: x\r\n\r\n")
+            .unwrap();
+        assert!(read_all(&mut client).starts_with("HTTP/1.1 400 Bad Request"));
+        assert!(rx.recv_timeout(Duration::from_millis(200)).is_err());
+        proxy.stop();
+    }
+
+    #[test]
+    fn test_stop_closes_subscription() {
+        let (mut proxy, rx) = start_proxy(false, &[]);
+        assert!(proxy.is_running());
+        proxy.stop();
+        assert!(!proxy.is_running());
+        assert!(matches!(
+            rx.recv_timeout(Duration::from_secs(1)),
+            Err(std::sync::mpsc::RecvTimeoutError::Disconnected)
+        ));
+    }
+}
@@ -0,0 +1,521 @@
+//! Configuration hot reload for MacAgentWatch
+//!
+//! Watches the config file during a running session and swaps the detection
+//! rules used by the monitoring subsystems without restarting them.
+
+use crate::bus::{BusEvent, EventBus, SubscribeOptions};
+use crate::config::{Config, ConfigChange};
+use crate::detector::{NetworkWhitelist, SensitiveFileDetector};
+use crate::error::CoreError;
+use crate::event::{Event, RiskLevel};
+use crate::risk::RiskScorer;
+use crate::sanitize;
+use crate::types::Swappable;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Receiver;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Detection rules shared by running subsystems.
+///
+/// Cloning shares the underlying handles, so a single [`LiveRules::apply`]
+/// updates every tracker, watcher and monitor built from the same instance.
+#[derive(Clone)]
+pub struct LiveRules {
+    /// Command risk scorer used by process trackers
+    pub risk_scorer: Swappable<RiskScorer>,
+    /// Sensitive file detector used by file system watchers
+    pub file_detector: Swappable<SensitiveFileDetector>,
+    /// Network whitelist used by network monitors
+    pub network_whitelist: Swappable<NetworkWhitelist>,
+}
+
+impl Default for LiveRules {
+    fn default() -> Self {
+        Self::new(
+            RiskScorer::new(),
+            SensitiveFileDetector::default(),
+            NetworkWhitelist::default(),
+        )
+    }
+}
+
+impl LiveRules {
+    /// Create rules from explicit components
+    pub fn new(
+        risk_scorer: RiskScorer,
+        file_detector: SensitiveFileDetector,
+        network_whitelist: NetworkWhitelist,
+    ) -> Self {
+        Self {
+            risk_scorer: Swappable::new(risk_scorer),
+            file_detector: Swappable::new(file_detector),
+            network_whitelist: Swappable::new(network_whitelist),
+        }
+    }
+
+    /// Build rules from the monitoring and alert sections of a config
+    pub fn from_config(config: &Config) -> Self {
+        let (scorer, detector, whitelist) = Self::build(config);
+        Self::new(scorer, detector, whitelist)
+    }
+
+    /// Atomically replace all rules with ones built from `config`.
+    ///
+    /// The process-wide sanitizer is rebuilt from the `[sanitize]` section
+    /// as well; see [`crate::sanitize::configure`].
+    pub fn apply(&self, config: &Config) {
+        let (scorer, detector, whitelist) = Self::build(config);
+        self.risk_scorer.store(scorer);
+        self.file_detector.store(detector);
+        self.network_whitelist.store(whitelist);
+        if let Err(e) = sanitize::configure(&config.sanitize) {
+            eprintln!("[agent-watch] Warning: Keeping previous sanitizer rules: {e}");
+        }
+    }
+
+    fn build(config: &Config) -> (RiskScorer, SensitiveFileDetector, NetworkWhitelist) {
+        let mut scorer = RiskScorer::new();
+        scorer.add_custom_high_risk(config.alerts.custom_high_risk.clone());
+        let detector = SensitiveFileDetector::new(config.monitoring.sensitive_patterns.clone());
+        let whitelist = NetworkWhitelist::new(config.monitoring.network_whitelist.clone(), vec![]);
+        (scorer, detector, whitelist)
+    }
+}
+
+/// A validated config change picked up by the [`ConfigWatcher`]
+#[derive(Debug, Clone)]
+pub struct ConfigReload {
+    /// Config file that changed
+    pub path: PathBuf,
+    /// The newly loaded config
+    pub config: Config,
+    /// Settings that differ from the previously active config
+    pub changes: Vec<ConfigChange>,
+    /// Risk of the change: High when it weakens detection, Medium otherwise
+    pub risk_level: RiskLevel,
+}
+
+impl ConfigReload {
+    /// Convert into a monitoring event for logging
+    pub fn to_event(&self) -> Event {
+        Event::config_reload(self.path.clone(), self.changes.clone(), self.risk_level)
+    }
+}
+
+impl BusEvent for ConfigReload {
+    fn kind(&self) -> &'static str {
+        "config_reload"
+    }
+
+    fn risk_level(&self) -> Option<RiskLevel> {
+        Some(self.risk_level)
+    }
+}
+
+/// Risk of switching from `old` to `new`.
+///
+/// A running agent can edit the config file itself, so changes that widen the
+/// network whitelist or drop sensitive patterns / custom high-risk commands /
+/// sanitizer rules are flagged High.
+fn reload_risk(old: &Config, new: &Config) -> RiskLevel {
+    let added_hosts = new
+        .monitoring
+        .network_whitelist
+        .iter()
+        .any(|h| !old.monitoring.network_whitelist.contains(h));
+    let removed_patterns = old
+        .monitoring
+        .sensitive_patterns
+        .iter()
+        .any(|p| !new.monitoring.sensitive_patterns.contains(p));
+    let removed_commands = old
+        .alerts
+        .custom_high_risk
+        .iter()
+        .any(|c| !new.alerts.custom_high_risk.contains(c));
+
+    let (old_rules, new_rules) = (&old.sanitize, &new.sanitize);
+    let weakened_sanitizer = old_rules
+        .extra_flags
+        .iter()
+        .any(|f| !new_rules.extra_flags.contains(f))
+        || old_rules
+            .extra_env
+            .iter()
+            .any(|e| !new_rules.extra_env.contains(e))
+        || old_rules
+            .patterns
+            .iter()
+            .any(|p| !new_rules.patterns.contains(p))
+        || new_rules
+            .allowlist
+            .iter()
+            .any(|a| !old_rules.allowlist.contains(a));
+
+    if added_hosts || removed_patterns || removed_commands || weakened_sanitizer {
+        RiskLevel::High
+    } else {
+        RiskLevel::Medium
+    }
+}
+
+/// Config watcher configuration
+#[derive(Debug, Clone)]
+pub struct ConfigWatchConfig {
+    /// Config file to watch
+    pub path: PathBuf,
+    /// How often to check the file for changes
+    pub poll_interval: Duration,
+}
+
+impl ConfigWatchConfig {
+    /// Create a new config for a config file path
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            poll_interval: Duration::from_secs(1),
+        }
+    }
+
+    /// Set the polling interval
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+}
+
+/// Polls the config file and emits a [`ConfigReload`] for each valid change.
+///
+/// Edits that fail to parse or fail validation are reported on stderr and
+/// ignored, leaving the previous config in effect.
+pub struct ConfigWatcher {
+    config: ConfigWatchConfig,
+    /// Subscribers to reload events
+    events: EventBus<ConfigReload>,
+    stop_flag: Arc<AtomicBool>,
+    watch_thread: Option<JoinHandle<()>>,
+}
+
+impl ConfigWatcher {
+    /// Create a new config watcher
+    pub fn new(config: ConfigWatchConfig) -> Self {
+        Self {
+            config,
+            events: EventBus::new(),
+            stop_flag: Arc::new(AtomicBool::new(false)),
+            watch_thread: None,
+        }
+    }
+
+    /// Subscribe to config reload events
+    pub fn subscribe(&self) -> Receiver<ConfigReload> {
+        self.events.subscribe()
+    }
+
+    /// Subscribe with a custom buffer, lag policy and filter
+    pub fn subscribe_with(&self, options: SubscribeOptions) -> Receiver<ConfigReload> {
+        self.events.subscribe_with(options)
+    }
+
+    /// Check if the watcher is running
+    pub fn is_running(&self) -> bool {
+        self.watch_thread.is_some() && !self.stop_flag.load(Ordering::Relaxed)
+    }
+
+    /// Start watching. The config currently on disk becomes the baseline.
+    pub fn start(&mut self) -> std::result::Result<(), CoreError> {
+        self.stop_flag.store(false, Ordering::Relaxed);
+
+        let path = self.config.path.clone();
+        let poll_interval = self.config.poll_interval;
+        let events = self.events.clone();
+        let stop_flag = Arc::clone(&self.stop_flag);
+
+        let content = std::fs::read_to_string(&path).ok();
+        let current = content
+            .as_deref()
+            .and_then(|c| Config::from_toml(c).ok())
+            .unwrap_or_default();
+
+        let handle = thread::spawn(move || {
+            Self::watch_loop(path, poll_interval, content, current, events, stop_flag);
+        });
+
+        self.watch_thread = Some(handle);
+        Ok(())
+    }
+
+    /// Stop watching. Subscribers see a disconnect once the watch thread
+    /// has exited.
+    pub fn stop(&mut self) {
+        self.stop_flag.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.watch_thread.take() {
+            let _ = handle.join();
+        }
+        self.events.close();
+    }
+
+    /// Signal the watcher to stop without waiting for the thread to finish.
+    /// Used by MonitoringOrchestrator for two-phase shutdown.
+    pub fn signal_stop(&self) {
+        self.stop_flag.store(true, Ordering::Relaxed);
+    }
+
+    fn watch_loop(
+        path: PathBuf,
+        poll_interval: Duration,
+        mut last_content: Option<String>,
+        mut current: Config,
+        events: EventBus<ConfigReload>,
+        stop_flag: Arc<AtomicBool>,
+    ) {
+        while !stop_flag.load(Ordering::Relaxed) {
+            thread::sleep(poll_interval);
+
+            // A missing file (e.g. mid-save by an editor) keeps the current config
+            let Ok(content) = std::fs::read_to_string(&path) else {
+                continue;
+            };
+            if last_content.as_deref() == Some(content.as_str()) {
+                continue;
+            }
+            let reload = Self::check(&path, &content, &current);
+            last_content = Some(content);
+
+            if let Some(reload) = reload {
+                current = reload.config.clone();
+                events.publish(reload);
+            }
+        }
+    }
+
+    /// Parse and validate new file content against the active config
+    fn check(path: &Path, content: &str, current: &Config) -> Option<ConfigReload> {
+        let new = match Config::from_toml(content) {
+            Ok(config) => config,
+            Err(e) => {
+                eprintln!(
+                    "[agent-watch] Warning: Ignoring config change in {}: {}",
+                    path.display(),
+                    e
+                );
+                return None;
+            }
+        };
+
+        if let Some(diag) = new.validate().into_iter().find(|d| d.is_error()) {
+            eprintln!(
+                "[agent-watch] Warning: Ignoring invalid config change in {}: {}",
+                path.display(),
+                diag
+            );
+            return None;
+        }
+
+        let changes = current.diff(&new);
+        if changes.is_empty() {
+            return None;
+        }
+
+        Some(ConfigReload {
+            path: path.to_path_buf(),
+            risk_level: reload_risk(current, &new),
+            config: new,
+            changes,
+        })
+    }
+}
+
+impl crate::types::MonitoringSubsystem for ConfigWatcher {
+    fn start(&mut self) -> std::result::Result<(), CoreError> {
+        ConfigWatcher::start(self)
+    }
+
+    fn stop(&mut self) {
+        ConfigWatcher::stop(self)
+    }
+
+    fn signal_stop(&self) {
+        ConfigWatcher::signal_stop(self)
+    }
+
+    fn is_running(&self) -> bool {
+        ConfigWatcher::is_running(self)
+    }
+}
+
+impl Drop for ConfigWatcher {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detector::{Detector, NetworkConnection};
+    use crate::event::EventType;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_live_rules_apply_swaps_all_handles() {
+        let rules = LiveRules::from_config(&Config::default());
+        let shared = rules.clone();
+
+        let mut config = Config::default();
+        config.monitoring.network_whitelist = vec!["example.com".to_string()];
+        config.monitoring.sensitive_patterns = vec!["*.draft".to_string()];
+        config.alerts.custom_high_risk = vec!["deploy".to_string()];
+        rules.apply(&config);
+
+        assert!(shared
+            .network_whitelist
+            .load()
+            .is_host_allowed("example.com"));
+        assert!(!shared
+            .network_whitelist
+            .load()
+            .is_host_allowed("github.com"));
+        assert!(shared
+            .file_detector
+            .load()
+            .is_sensitive(&PathBuf::from("/tmp/plan.draft")));
+        assert_eq!(
+            shared.risk_scorer.load().score("deploy", &[]).0,
+            RiskLevel::High
+        );
+    }
+
+    #[test]
+    fn test_reload_risk() {
+        let old = Config::default();
+
+        let mut tightened = Config::default();
+        tightened.monitoring.network_whitelist.pop();
+        assert_eq!(reload_risk(&old, &tightened), RiskLevel::Medium);
+
+        let mut widened = Config::default();
+        widened
+            .monitoring
+            .network_whitelist
+            .push("evil.example".to_string());
+        assert_eq!(reload_risk(&old, &widened), RiskLevel::High);
+
+        let mut fewer_patterns = Config::default();
+        fewer_patterns.monitoring.sensitive_patterns.clear();
+        assert_eq!(reload_risk(&old, &fewer_patterns), RiskLevel::High);
+
+        let mut more_flags = Config::default();
+        more_flags
+            .sanitize
+            .extra_flags
+            .push("--vault-token".to_string());
+        assert_eq!(reload_risk(&old, &more_flags), RiskLevel::Medium);
+        assert_eq!(reload_risk(&more_flags, &old), RiskLevel::High);
+
+        let mut allowlisted = Config::default();
+        allowlisted.sanitize.allowlist.push(".*".to_string());
+        assert_eq!(reload_risk(&old, &allowlisted), RiskLevel::High);
+    }
+
+    #[test]
+    fn test_check_rejects_invalid_and_unchanged() {
+        let path = PathBuf::from("/tmp/config.toml");
+        let current = Config::default();
+
+        assert!(ConfigWatcher::check(&path, "not = [valid", &current).is_none());
+        assert!(
+            ConfigWatcher::check(&path, "[alerts]\nmin_level = \"severe\"\n", &current).is_none()
+        );
+        assert!(ConfigWatcher::check(&path, "# only a comment\n", &current).is_none());
+
+        let reload =
+            ConfigWatcher::check(&path, "[alerts]\nmin_level = \"medium\"\n", &current).unwrap();
+        assert_eq!(reload.changes.len(), 1);
+        assert_eq!(reload.changes[0].key, "alerts.min_level");
+        assert_eq!(reload.risk_level, RiskLevel::Medium);
+    }
+
+    #[test]
+    fn test_config_reload_event() {
+        let reload = ConfigReload {
+            path: PathBuf::from("/tmp/config.toml"),
+            config: Config::default(),
+            changes: vec![ConfigChange {
+                key: "monitoring.network_whitelist".to_string(),
+                old: Some("[]".to_string()),
+                new: Some("[\"example.com\"]".to_string()),
+            }],
+            risk_level: RiskLevel::High,
+        };
+
+        let event = reload.to_event();
+        assert!(event.alert);
+        match event.event_type {
+            EventType::ConfigReload { changes, .. } => assert_eq!(changes.len(), 1),
+            other => panic!("unexpected event type: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_watcher_emits_reload_on_file_change() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("config.toml");
+        std::fs::write(
+            &path,
+            "[monitoring]\nnetwork_whitelist = [\"github.com\"]\n",
+        )
+        .unwrap();
+
+        let mut watcher = ConfigWatcher::new(
+            ConfigWatchConfig::new(path.clone()).poll_interval(Duration::from_millis(20)),
+        );
+        let rx = watcher.subscribe();
+        watcher.start().unwrap();
+        assert!(watcher.is_running());
+
+        std::fs::write(
+            &path,
+            "[monitoring]\nnetwork_whitelist = [\"github.com\", \"example.com\"]\n",
+        )
+        .unwrap();
+
+        let reload = rx
+            .recv_timeout(Duration::from_secs(5))
+            .expect("expected a reload event");
+        watcher.stop();
+
+        assert_eq!(reload.changes.len(), 1);
+        assert_eq!(reload.changes[0].key, "monitoring.network_whitelist");
+        assert_eq!(reload.risk_level, RiskLevel::High);
+
+        let rules = LiveRules::default();
+        rules.apply(&reload.config);
+        let conn = NetworkConnection {
+            host: "example.com".to_string(),
+            port: 443,
+            protocol: "tcp".to_string(),
+        };
+        assert_eq!(
+            rules.network_whitelist.load().risk_level(&conn),
+            RiskLevel::Medium
+        );
+    }
+
+    #[test]
+    fn test_watcher_subscribe() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut watcher =
+            ConfigWatcher::new(ConfigWatchConfig::new(temp_dir.path().join("config.toml")));
+        let first = watcher.subscribe();
+        let second = watcher.subscribe();
+        assert_eq!(watcher.events.subscriber_count(), 2);
+
+        watcher.stop();
+        assert!(first.recv().is_err());
+        assert!(second.recv().is_err());
+    }
+}
@@ -0,0 +1,880 @@
+//! Resource usage monitoring module
+//!
+//! Samples CPU, resident memory, thread count, open file descriptors and
+//! process count across the wrapped process tree, and reports when a total
+//! crosses a limit. Two rates are tracked as well: processes spawned and
+//! memory gained within a sliding window, which catch fork bombs and runaway
+//! allocations before the absolute limits are reached.
+//!
+//! Each limit is reported once when it is crossed and again only after the
+//! value has dropped back under it. Processes that start and exit between
+//! two samples are not seen, so the spawn rate is a lower bound.
+
+use crate::bus::{EventBus, SubscribeOptions};
+use crate::error::CoreError;
+use crate::event::{Event, ResourceMetric, RiskLevel};
+use crate::execmon::{list_processes, process_tree};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+#[cfg(target_os = "macos")]
+use libproc::file_info::ListFDs;
+#[cfg(target_os = "macos")]
+use libproc::proc_pid::{listpidinfo, pidinfo};
+#[cfg(target_os = "macos")]
+use libproc::task_info::TaskAllInfo;
+
+/// Limits for a process tree. `None` disables a check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResourceLimits {
+    /// Live processes in the tree
+    pub max_processes: Option<u64>,
+    /// Processes spawned within `rate_window`
+    pub max_spawns: Option<u64>,
+    /// CPU usage in percent of one core, sustained for `cpu_sustain`
+    pub max_cpu_percent: Option<u64>,
+    /// How long CPU usage must stay over its limit before it is reported
+    pub cpu_sustain: Duration,
+    /// Total resident memory in bytes
+    pub max_rss_bytes: Option<u64>,
+    /// Resident memory gained within `rate_window`, in bytes
+    pub max_rss_growth: Option<u64>,
+    /// Total thread count
+    pub max_threads: Option<u64>,
+    /// Total open file descriptors
+    pub max_open_fds: Option<u64>,
+    /// Sliding window for the spawn and memory growth rates
+    pub rate_window: Duration,
+}
+
+impl Default for ResourceLimits {
+    fn default() -> Self {
+        // A miner keeps every core busy; a build briefly does too
+        let cores = thread::available_parallelism().map_or(1, |n| n.get() as u64);
+        Self {
+            max_processes: Some(256),
+            max_spawns: Some(200),
+            max_cpu_percent: Some(cores * 90),
+            cpu_sustain: Duration::from_secs(60),
+            max_rss_bytes: Some(8 * 1024 * 1024 * 1024),
+            max_rss_growth: Some(2 * 1024 * 1024 * 1024),
+            max_threads: Some(4096),
+            max_open_fds: Some(4096),
+            rate_window: Duration::from_secs(10),
+        }
+    }
+}
+
+impl ResourceLimits {
+    /// Set the process count limit
+    pub fn max_processes(mut self, limit: Option<u64>) -> Self {
+        self.max_processes = limit;
+        self
+    }
+
+    /// Set the limit on processes spawned within the rate window
+    pub fn max_spawns(mut self, limit: Option<u64>) -> Self {
+        self.max_spawns = limit;
+        self
+    }
+
+    /// Set the CPU limit in percent of one core
+    pub fn max_cpu_percent(mut self, limit: Option<u64>) -> Self {
+        self.max_cpu_percent = limit;
+        self
+    }
+
+    /// Set how long CPU usage must stay over its limit
+    pub fn cpu_sustain(mut self, duration: Duration) -> Self {
+        self.cpu_sustain = duration;
+        self
+    }
+
+    /// Set the resident memory limit in bytes
+    pub fn max_rss_bytes(mut self, limit: Option<u64>) -> Self {
+        self.max_rss_bytes = limit;
+        self
+    }
+
+    /// Set the limit on memory gained within the rate window
+    pub fn max_rss_growth(mut self, limit: Option<u64>) -> Self {
+        self.max_rss_growth = limit;
+        self
+    }
+
+    /// Set the thread count limit
+    pub fn max_threads(mut self, limit: Option<u64>) -> Self {
+        self.max_threads = limit;
+        self
+    }
+
+    /// Set the open file descriptor limit
+    pub fn max_open_fds(mut self, limit: Option<u64>) -> Self {
+        self.max_open_fds = limit;
+        self
+    }
+
+    /// Set the sliding window for the spawn and memory growth rates
+    pub fn rate_window(mut self, window: Duration) -> Self {
+        self.rate_window = window;
+        self
+    }
+}
+
+/// Configuration for the resource monitor
+#[derive(Debug, Clone)]
+pub struct ResMonConfig {
+    /// Root process ID of the tree to sample
+    pub root_pid: u32,
+    /// Name reported as the process of resource events
+    pub process_name: String,
+    /// Polling interval
+    pub poll_interval: Duration,
+    /// Maximum tree depth (None for unlimited)
+    pub max_depth: Option<usize>,
+    /// Limits to report
+    pub limits: ResourceLimits,
+}
+
+impl Default for ResMonConfig {
+    fn default() -> Self {
+        Self {
+            root_pid: 0,
+            process_name: "agent".to_string(),
+            poll_interval: Duration::from_millis(500),
+            max_depth: Some(10),
+            limits: ResourceLimits::default(),
+        }
+    }
+}
+
+impl ResMonConfig {
+    /// Create a new resource monitor config for a root process
+    pub fn new(root_pid: u32) -> Self {
+        Self {
+            root_pid,
+            ..Default::default()
+        }
+    }
+
+    /// Set the name reported as the process of resource events
+    pub fn process_name(mut self, name: impl Into<String>) -> Self {
+        self.process_name = name.into();
+        self
+    }
+
+    /// Set the polling interval
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Set the maximum tree depth
+    pub fn max_depth(mut self, depth: Option<usize>) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Set the limits to report
+    pub fn limits(mut self, limits: ResourceLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+}
+
+/// Totals for the tree at the latest sample
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResourceUsage {
+    /// Live processes, including the root
+    pub processes: u64,
+    /// Processes spawned within the rate window
+    pub spawned: u64,
+    /// CPU usage since the previous sample, in percent of one core
+    pub cpu_percent: u64,
+    /// Resident memory in bytes
+    pub rss_bytes: u64,
+    /// Resident memory gained within the rate window, in bytes
+    pub rss_growth: u64,
+    /// Threads
+    pub threads: u64,
+    /// Open file descriptors
+    pub open_fds: u64,
+}
+
+/// Usage of one process
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct ProcUsage {
+    /// User plus system CPU time
+    cpu_time: Duration,
+    rss_bytes: u64,
+    threads: u64,
+    open_fds: u64,
+}
+
+/// One process in a tree sample
+#[derive(Debug, Clone, Copy)]
+struct ProcSample {
+    pid: u32,
+    start_time: u64,
+    usage: ProcUsage,
+}
+
+/// A limit the tree exceeded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Breach {
+    metric: ResourceMetric,
+    value: u64,
+    limit: u64,
+}
+
+/// What the monitor remembers between samples
+#[derive(Debug, Default)]
+struct SamplerState {
+    /// CPU time per process at the last sample, keyed by PID and start time
+    cpu: HashMap<(u32, u64), Duration>,
+    last_at: Option<Instant>,
+    /// Spawn counts per sample within the rate window
+    spawns: VecDeque<(Instant, u64)>,
+    /// Resident memory per sample within the rate window
+    memory: VecDeque<(Instant, u64)>,
+    /// When CPU usage went over its limit
+    cpu_over_since: Option<Instant>,
+    /// Metrics already reported and still over their limit
+    over: HashSet<ResourceMetric>,
+}
+
+impl SamplerState {
+    /// Fold one tree sample into the state and return its totals along with
+    /// any newly crossed limits
+    fn observe(
+        &mut self,
+        at: Instant,
+        procs: &[ProcSample],
+        limits: &ResourceLimits,
+    ) -> (ResourceUsage, Vec<Breach>) {
+        // Processes already present at the first sample are not spawns
+        let first = self.last_at.is_none();
+        let mut cpu = HashMap::with_capacity(procs.len());
+        let mut cpu_used = Duration::ZERO;
+        let mut spawned = 0;
+        let mut usage = ResourceUsage {
+            processes: procs.len() as u64,
+            ..Default::default()
+        };
+
+        for proc in procs {
+            let key = (proc.pid, proc.start_time);
+            match self.cpu.get(&key) {
+                Some(prev) => cpu_used += proc.usage.cpu_time.saturating_sub(*prev),
+                None if !first => {
+                    spawned += 1;
+                    cpu_used += proc.usage.cpu_time;
+                }
+                None => {}
+            }
+            cpu.insert(key, proc.usage.cpu_time);
+            usage.rss_bytes += proc.usage.rss_bytes;
+            usage.threads += proc.usage.threads;
+            usage.open_fds += proc.usage.open_fds;
+        }
+        self.cpu = cpu;
+
+        if let Some(last) = self.last_at {
+            let elapsed = at.saturating_duration_since(last).as_secs_f64();
+            if elapsed > 0.0 {
+                usage.cpu_percent = (cpu_used.as_secs_f64() / elapsed * 100.0).round() as u64;
+            }
+        }
+        self.last_at = Some(at);
+
+        let window = limits.rate_window;
+        self.spawns.push_back((at, spawned));
+        while self.spawns.front().is_some_and(|(t, _)| *t + window < at) {
+            self.spawns.pop_front();
+        }
+        usage.spawned = self.spawns.iter().map(|(_, n)| n).sum();
+
+        self.memory.push_back((at, usage.rss_bytes));
+        while self.memory.front().is_some_and(|(t, _)| *t + window < at) {
+            self.memory.pop_front();
+        }
+        let low = self.memory.iter().map(|(_, rss)| *rss).min().unwrap_or(0);
+        usage.rss_growth = usage.rss_bytes.saturating_sub(low);
+
+        let mut breaches = Vec::new();
+        let checks = [
+            (
+                ResourceMetric::Processes,
+                usage.processes,
+                limits.max_processes,
+            ),
+            (ResourceMetric::SpawnRate, usage.spawned, limits.max_spawns),
+            (
+                ResourceMetric::Cpu,
+                usage.cpu_percent,
+                limits.max_cpu_percent,
+            ),
+            (
+                ResourceMetric::Memory,
+                usage.rss_bytes,
+                limits.max_rss_bytes,
+            ),
+            (
+                ResourceMetric::MemoryGrowth,
+                usage.rss_growth,
+                limits.max_rss_growth,
+            ),
+            (ResourceMetric::Threads, usage.threads, limits.max_threads),
+            (
+                ResourceMetric::OpenFiles,
+                usage.open_fds,
+                limits.max_open_fds,
+            ),
+        ];
+        for (metric, value, limit) in checks {
+            let Some(limit) = limit else {
+                continue;
+            };
+            if value <= limit {
+                self.over.remove(&metric);
+                if metric == ResourceMetric::Cpu {
+                    self.cpu_over_since = None;
+                }
+                continue;
+            }
+            // Short bursts such as a compile are normal; only sustained load counts
+            if metric == ResourceMetric::Cpu {
+                let since = *self.cpu_over_since.get_or_insert(at);
+                if at.saturating_duration_since(since) < limits.cpu_sustain {
+                    continue;
+                }
+            }
+            if self.over.insert(metric) {
+                breaches.push(Breach {
+                    metric,
+                    value,
+                    limit,
+                });
+            }
+        }
+
+        (usage, breaches)
+    }
+}
+
+/// Risk level of crossing a limit. Runaway process creation is how fork
+/// bombs take a machine down, so it ranks above heavy but bounded usage.
+fn risk_level(metric: ResourceMetric) -> RiskLevel {
+    match metric {
+        ResourceMetric::Processes | ResourceMetric::SpawnRate => RiskLevel::Critical,
+        _ => RiskLevel::High,
+    }
+}
+
+/// Samples resource usage of a process tree
+pub struct ResourceMonitor {
+    config: ResMonConfig,
+    /// Totals at the latest sample
+    usage: Arc<Mutex<Option<ResourceUsage>>>,
+    /// Subscribers to resource events
+    events: EventBus<Event>,
+    /// Stop flag
+    stop_flag: Arc<AtomicBool>,
+    /// Worker thread handle
+    thread_handle: Option<JoinHandle<()>>,
+}
+
+impl ResourceMonitor {
+    /// Create a new resource monitor
+    pub fn new(config: ResMonConfig) -> Self {
+        Self {
+            config,
+            usage: Arc::new(Mutex::new(None)),
+            events: EventBus::new(),
+            stop_flag: Arc::new(AtomicBool::new(false)),
+            thread_handle: None,
+        }
+    }
+
+    /// Whether resource sampling works on this platform
+    pub fn is_supported() -> bool {
+        cfg!(any(target_os = "linux", target_os = "macos"))
+    }
+
+    /// Subscribe to resource events
+    pub fn subscribe(&self) -> Receiver<Event> {
+        self.events.subscribe()
+    }
+
+    /// Subscribe with a custom buffer, lag policy and filter
+    pub fn subscribe_with(&self, options: SubscribeOptions) -> Receiver<Event> {
+        self.events.subscribe_with(options)
+    }
+
+    /// Totals at the latest sample, if one has been taken
+    pub fn usage(&self) -> Option<ResourceUsage> {
+        self.usage.lock().ok().and_then(|usage| *usage)
+    }
+
+    /// Start the sampling thread
+    pub fn start(&mut self) -> std::result::Result<(), CoreError> {
+        if !Self::is_supported() {
+            return Err(CoreError::Wrapper(
+                "resource monitoring is not supported on this platform".to_string(),
+            ));
+        }
+        if self.thread_handle.is_some() {
+            return Ok(());
+        }
+        self.stop_flag.store(false, Ordering::Relaxed);
+
+        let config = self.config.clone();
+        let usage = Arc::clone(&self.usage);
+        let stop_flag = Arc::clone(&self.stop_flag);
+        let events = self.events.clone();
+
+        let handle = thread::spawn(move || {
+            let mut state = SamplerState::default();
+            while !stop_flag.load(Ordering::Relaxed) {
+                Self::sample(&config, &mut state, &usage, &events);
+                thread::sleep(config.poll_interval);
+            }
+        });
+
+        self.thread_handle = Some(handle);
+        Ok(())
+    }
+
+    /// Stop the sampling thread. Subscribers see a disconnect.
+    pub fn stop(&mut self) {
+        self.stop_flag.store(true, Ordering::Relaxed);
+
+        if let Some(handle) = self.thread_handle.take() {
+            let _ = handle.join();
+        }
+        self.events.close();
+    }
+
+    /// Signal the monitor to stop without waiting for the thread to finish
+    pub fn signal_stop(&self) {
+        self.stop_flag.store(true, Ordering::Relaxed);
+    }
+
+    /// Check if the monitor is running
+    pub fn is_running(&self) -> bool {
+        self.thread_handle.is_some() && !self.stop_flag.load(Ordering::Relaxed)
+    }
+
+    /// Sample the tree once and emit events for newly crossed limits
+    fn sample(
+        config: &ResMonConfig,
+        state: &mut SamplerState,
+        usage: &Arc<Mutex<Option<ResourceUsage>>>,
+        events: &EventBus<Event>,
+    ) {
+        let processes = list_processes();
+        let procs: Vec<ProcSample> =
+            process_tree(&processes, config.root_pid, config.max_depth, true)
+                .into_iter()
+                // Exited processes no longer hold resources
+                .filter(|entry| entry.exited.is_none())
+                .filter_map(|entry| {
+                    Some(ProcSample {
+                        pid: entry.pid,
+                        start_time: entry.start_time,
+                        usage: read_usage(entry.pid)?,
+                    })
+                })
+                .collect();
+
+        let (totals, breaches) = state.observe(Instant::now(), &procs, &config.limits);
+        if let Ok(mut usage) = usage.lock() {
+            *usage = Some(totals);
+        }
+        for breach in breaches {
+            let event = Event::resource(
+                config.process_name.clone(),
+                config.root_pid,
+                breach.metric,
+                breach.value,
+                breach.limit,
+                risk_level(breach.metric),
+            );
+            events.publish(event);
+        }
+    }
+}
+
+/// Read the usage of one process
+#[cfg(target_os = "linux")]
+fn read_usage(pid: u32) -> Option<ProcUsage> {
+    // SAFETY: sysconf has no preconditions
+    let ticks_per_sec = match unsafe { libc::sysconf(libc::_SC_CLK_TCK) } {
+        n if n > 0 => n as u64,
+        _ => 100,
+    };
+    // SAFETY: sysconf has no preconditions
+    let page_size = match unsafe { libc::sysconf(libc::_SC_PAGESIZE) } {
+        n if n > 0 => n as u64,
+        _ => 4096,
+    };
+    let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+    let (cpu_ticks, threads, rss_pages) = parse_stat_usage(&stat)?;
+    // Other users' descriptors are unreadable; count them as none
+    let open_fds = std::fs::read_dir(format!("/proc/{pid}/fd")).map_or(0, |dir| dir.count() as u64);
+    Some(ProcUsage {
+        cpu_time: Duration::from_millis(cpu_ticks * 1000 / ticks_per_sec),
+        rss_bytes: rss_pages * page_size,
+        threads,
+        open_fds,
+    })
+}
+
+/// CPU ticks (user plus system), thread count and resident pages from
+/// `/proc/<pid>/stat`
+#[cfg(any(target_os = "linux", test))]
+fn parse_stat_usage(stat: &str) -> Option<(u64, u64, u64)> {
+    let rest = &stat[stat.rfind(')')? + 1..];
+    let fields: Vec<&str> = rest.split_whitespace().collect();
+    // Fields are numbered from 1 in proc(5); the state (field 3) is first here
+    let field = |n: usize| fields.get(n - 3)?.parse::<u64>().ok();
+    Some((field(14)? + field(15)?, field(20)?, field(24)?))
+}
+
+/// Read the usage of one process
+#[cfg(target_os = "macos")]
+fn read_usage(pid: u32) -> Option<ProcUsage> {
+    let info = pidinfo::<TaskAllInfo>(pid as i32, 0).ok()?;
+    let open_fds = listpidinfo::<ListFDs>(pid as i32, info.pbsd.pbi_nfiles as usize)
+        .map_or(0, |fds| fds.len() as u64);
+    Some(ProcUsage {
+        cpu_time: crate::process_tracker::mach_ticks_to_duration(
+            info.ptinfo.pti_total_user + info.ptinfo.pti_total_system,
+        ),
+        rss_bytes: info.ptinfo.pti_resident_size,
+        threads: info.ptinfo.pti_threadnum as u64,
+        open_fds,
+    })
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn read_usage(_pid: u32) -> Option<ProcUsage> {
+    None
+}
+
+impl crate::types::MonitoringSubsystem for ResourceMonitor {
+    fn start(&mut self) -> std::result::Result<(), CoreError> {
+        ResourceMonitor::start(self)
+    }
+
+    fn stop(&mut self) {
+        ResourceMonitor::stop(self)
+    }
+
+    fn signal_stop(&self) {
+        ResourceMonitor::signal_stop(self)
+    }
+
+    fn is_running(&self) -> bool {
+        ResourceMonitor::is_running(self)
+    }
+}
+
+impl Drop for ResourceMonitor {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::EventType;
+
+    const MB: u64 = 1024 * 1024;
+
+    fn proc(pid: u32, cpu_ms: u64, rss_bytes: u64) -> ProcSample {
+        ProcSample {
+            pid,
+            start_time: u64::from(pid),
+            usage: ProcUsage {
+                cpu_time: Duration::from_millis(cpu_ms),
+                rss_bytes,
+                threads: 1,
+                open_fds: 3,
+            },
+        }
+    }
+
+    fn no_limits() -> ResourceLimits {
+        ResourceLimits::default()
+            .max_processes(None)
+            .max_spawns(None)
+            .max_cpu_percent(None)
+            .max_rss_bytes(None)
+            .max_rss_growth(None)
+            .max_threads(None)
+            .max_open_fds(None)
+    }
+
+    fn metrics(breaches: &[Breach]) -> Vec<ResourceMetric> {
+        breaches.iter().map(|b| b.metric).collect()
+    }
+
+    #[test]
+    fn test_resmon_config_builder() {
+        let config = ResMonConfig::new(1234)
+            .process_name("claude")
+            .poll_interval(Duration::from_secs(1))
+            .max_depth(None)
+            .limits(ResourceLimits::default().max_spawns(Some(50)));
+        assert_eq!(config.root_pid, 1234);
+        assert_eq!(config.process_name, "claude");
+        assert_eq!(config.poll_interval, Duration::from_secs(1));
+        assert_eq!(config.max_depth, None);
+        assert_eq!(config.limits.max_spawns, Some(50));
+    }
+
+    #[test]
+    fn test_resource_limits_default() {
+        let limits = ResourceLimits::default();
+        assert_eq!(limits.max_spawns, Some(200));
+        assert_eq!(limits.rate_window, Duration::from_secs(10));
+        assert!(limits.max_cpu_percent.is_some_and(|cpu| cpu >= 90));
+    }
+
+    #[test]
+    fn test_observe_totals() {
+        let mut state = SamplerState::default();
+        let t0 = Instant::now();
+        let limits = no_limits();
+
+        state.observe(t0, &[proc(1, 0, 10 * MB), proc(2, 0, 5 * MB)], &limits);
+        let (usage, breaches) = state.observe(
+            t0 + Duration::from_secs(1),
+            &[proc(1, 500, 10 * MB), proc(2, 250, 20 * MB)],
+            &limits,
+        );
+        assert!(breaches.is_empty());
+        assert_eq!(usage.processes, 2);
+        assert_eq!(usage.spawned, 0);
+        assert_eq!(usage.cpu_percent, 75);
+        assert_eq!(usage.rss_bytes, 30 * MB);
+        assert_eq!(usage.rss_growth, 15 * MB);
+        assert_eq!(usage.threads, 2);
+        assert_eq!(usage.open_fds, 6);
+    }
+
+    #[test]
+    fn test_observe_spawn_rate() {
+        let mut state = SamplerState::default();
+        let t0 = Instant::now();
+        let limits = no_limits().max_spawns(Some(200));
+
+        // Processes present at the first sample are not spawns
+        let initial: Vec<ProcSample> = (1..=300).map(|pid| proc(pid, 0, 0)).collect();
+        let (usage, breaches) = state.observe(t0, &initial, &limits);
+        assert_eq!(usage.spawned, 0);
+        assert!(breaches.is_empty());
+
+        let burst: Vec<ProcSample> = (1000..1150).map(|pid| proc(pid, 0, 0)).collect();
+        let (_, breaches) = state.observe(t0 + Duration::from_secs(2), &burst, &limits);
+        assert!(breaches.is_empty());
+
+        let burst: Vec<ProcSample> = (2000..2100).map(|pid| proc(pid, 0, 0)).collect();
+        let (usage, breaches) = state.observe(t0 + Duration::from_secs(4), &burst, &limits);
+        assert_eq!(usage.spawned, 250);
+        assert_eq!(
+            breaches,
+            vec![Breach {
+                metric: ResourceMetric::SpawnRate,
+                value: 250,
+                limit: 200,
+            }]
+        );
+
+        // Reported once while still over the limit
+        let burst: Vec<ProcSample> = (3000..3010).map(|pid| proc(pid, 0, 0)).collect();
+        let (_, breaches) = state.observe(t0 + Duration::from_secs(6), &burst, &limits);
+        assert!(breaches.is_empty());
+
+        // The burst leaves the window, then a new one is reported again
+        let (usage, _) = state.observe(t0 + Duration::from_secs(20), &burst, &limits);
+        assert_eq!(usage.spawned, 0);
+        let burst: Vec<ProcSample> = (4000..4201).map(|pid| proc(pid, 0, 0)).collect();
+        let (_, breaches) = state.observe(t0 + Duration::from_secs(21), &burst, &limits);
+        assert_eq!(metrics(&breaches), vec![ResourceMetric::SpawnRate]);
+    }
+
+    #[test]
+    fn test_observe_pid_reuse_counts_as_spawn() {
+        let mut state = SamplerState::default();
+        let t0 = Instant::now();
+        let limits = no_limits().max_spawns(Some(0));
+
+        state.observe(t0, &[proc(7, 0, 0)], &limits);
+        let mut reused = proc(7, 0, 0);
+        reused.start_time += 1;
+        let (usage, breaches) = state.observe(t0 + Duration::from_secs(1), &[reused], &limits);
+        assert_eq!(usage.spawned, 1);
+        assert_eq!(metrics(&breaches), vec![ResourceMetric::SpawnRate]);
+    }
+
+    #[test]
+    fn test_observe_cpu_must_be_sustained() {
+        let mut state = SamplerState::default();
+        let t0 = Instant::now();
+        let limits = no_limits()
+            .max_cpu_percent(Some(90))
+            .cpu_sustain(Duration::from_secs(30));
+
+        let at = |secs: u64| t0 + Duration::from_secs(secs);
+        state.observe(at(0), &[proc(1, 0, 0)], &limits);
+        let (usage, breaches) = state.observe(at(10), &[proc(1, 10_000, 0)], &limits);
+        assert_eq!(usage.cpu_percent, 100);
+        assert!(breaches.is_empty());
+
+        // Dropping under the limit restarts the clock
+        let (_, breaches) = state.observe(at(20), &[proc(1, 11_000, 0)], &limits);
+        assert!(breaches.is_empty());
+        let mut cpu_ms = 11_000;
+        for secs in (30..=60).step_by(10) {
+            cpu_ms += 10_000;
+            let (_, breaches) = state.observe(at(secs), &[proc(1, cpu_ms, 0)], &limits);
+            if secs < 60 {
+                assert!(breaches.is_empty(), "reported after {secs}s");
+            } else {
+                assert_eq!(metrics(&breaches), vec![ResourceMetric::Cpu]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_observe_memory_growth() {
+        let mut state = SamplerState::default();
+        let t0 = Instant::now();
+        let limits = no_limits()
+            .max_rss_growth(Some(1024 * MB))
+            .max_rss_bytes(Some(4096 * MB));
+
+        state.observe(t0, &[proc(1, 0, 100 * MB)], &limits);
+        let (_, breaches) = state.observe(
+            t0 + Duration::from_secs(5),
+            &[proc(1, 0, 1200 * MB)],
+            &limits,
+        );
+        assert_eq!(metrics(&breaches), vec![ResourceMetric::MemoryGrowth]);
+
+        // Slow growth stays under the rate limit until the absolute one
+        let mut state = SamplerState::default();
+        for step in 0..10u64 {
+            let (_, breaches) = state.observe(
+                t0 + Duration::from_secs(step * 20),
+                &[proc(1, 0, (step + 1) * 500 * MB)],
+                &limits,
+            );
+            if step < 8 {
+                assert!(breaches.is_empty());
+            } else if step == 8 {
+                assert_eq!(metrics(&breaches), vec![ResourceMetric::Memory]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_observe_count_limits() {
+        let mut state = SamplerState::default();
+        let limits = no_limits()
+            .max_processes(Some(2))
+            .max_threads(Some(2))
+            .max_open_fds(Some(8));
+
+        let procs = [proc(1, 0, 0), proc(2, 0, 0), proc(3, 0, 0)];
+        let (_, breaches) = state.observe(Instant::now(), &procs, &limits);
+        assert_eq!(
+            metrics(&breaches),
+            vec![
+                ResourceMetric::Processes,
+                ResourceMetric::Threads,
+                ResourceMetric::OpenFiles,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_risk_levels() {
+        assert_eq!(risk_level(ResourceMetric::SpawnRate), RiskLevel::Critical);
+        assert_eq!(risk_level(ResourceMetric::Processes), RiskLevel::Critical);
+        assert_eq!(risk_level(ResourceMetric::Memory), RiskLevel::High);
+        assert_eq!(risk_level(ResourceMetric::Cpu), RiskLevel::High);
+    }
+
+    #[test]
+    fn test_parse_stat_usage() {
+        let stat = "4321 (my (odd) cmd) S 1 4321 4321 0 -1 4194560 900 0 0 0 \
+                    150 50 0 0 20 0 6 0 12345 104857600 2560 18446744073709551615";
+        assert_eq!(parse_stat_usage(stat), Some((200, 6, 2560)));
+        assert_eq!(parse_stat_usage("garbage"), None);
+    }
+
+    #[test]
+    fn test_resmon_start_stop() {
+        let mut monitor = ResourceMonitor::new(ResMonConfig::new(std::process::id()));
+        if !ResourceMonitor::is_supported() {
+            assert!(monitor.start().is_err());
+            return;
+        }
+        monitor.start().unwrap();
+        assert!(monitor.is_running());
+        monitor.stop();
+        assert!(!monitor.is_running());
+    }
+
+    #[test]
+    fn test_resmon_subscribe() {
+        let mut monitor = ResourceMonitor::new(ResMonConfig::new(std::process::id()));
+        let first = monitor.subscribe();
+        let second = monitor.subscribe();
+        assert_eq!(monitor.events.subscriber_count(), 2);
+
+        monitor.stop();
+        assert!(first.recv().is_err());
+        assert!(second.recv().is_err());
+    }
+
+    #[test]
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    fn test_resmon_reports_limit_for_live_tree() {
+        let config = ResMonConfig::new(std::process::id())
+            .process_name("test-agent")
+            .poll_interval(Duration::from_millis(50))
+            .limits(no_limits().max_threads(Some(1)));
+        let mut monitor = ResourceMonitor::new(config);
+        let rx = monitor.subscribe();
+        monitor.start().unwrap();
+
+        // The test harness runs more than one thread
+        let event = rx
+            .recv_timeout(Duration::from_secs(5))
+            .expect("no resource event");
+        assert_eq!(event.process, "test-agent");
+        assert_eq!(event.pid, std::process::id());
+        assert_eq!(event.risk_level, RiskLevel::High);
+        assert!(matches!(
+            event.event_type,
+            EventType::Resource {
+                metric: ResourceMetric::Threads,
+                limit: 1,
+                ..
+            }
+        ));
+        assert!(monitor.usage().is_some_and(|u| u.processes >= 1));
+
+        monitor.stop();
+        assert!(rx.recv().is_err());
+    }
+}
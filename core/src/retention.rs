@@ -0,0 +1,838 @@
+//! Retention, size caps and compression for stored sessions
+//!
+//! Applies the `[logging]` limits to the log directory and the SQLite
+//! database: sessions older than `retention_days` are deleted, ended
+//! sessions are compressed, and the oldest ended sessions are removed while
+//! the log directory, or the database, exceeds `max_total_mb`. The session
+//! currently being recorded is never touched.
+
+use crate::config::{Compression, LoggingConfig};
+use crate::error::{CoreError, StorageError};
+use crate::event::{Event, EventType, SessionAction};
+use crate::snapshot::WorkspaceChanges;
+use crate::sqlite_storage::SqliteStorage;
+use crate::storage::{session_segments, SegmentName};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime};
+
+const MB: u64 = 1024 * 1024;
+
+/// How far back from the end of a log to look for its final line
+const LAST_LINE_WINDOW: u64 = 64 * 1024;
+
+/// Limits applied by [`enforce`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetentionPolicy {
+    /// Delete sessions older than this many days (0 = keep forever)
+    pub retention_days: u32,
+    /// Roll a session log over to a new segment at this size (0 = no limit)
+    pub max_segment_bytes: u64,
+    /// Size cap applied to the log directory and, separately, to the
+    /// SQLite database (0 = no limit)
+    pub max_total_bytes: u64,
+    /// Compression for segments of ended sessions
+    pub compression: Compression,
+    /// A session log without a session end record counts as ended once
+    /// none of its files changed for this long (e.g. after a crash)
+    pub idle_after: Duration,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self::from_config(&LoggingConfig::default())
+    }
+}
+
+impl RetentionPolicy {
+    /// Build a policy from the `[logging]` config section
+    pub fn from_config(logging: &LoggingConfig) -> Self {
+        Self {
+            retention_days: logging.retention_days,
+            max_segment_bytes: logging.max_session_mb.saturating_mul(MB),
+            max_total_bytes: logging.max_total_mb.saturating_mul(MB),
+            compression: logging.compression,
+            idle_after: Duration::from_secs(24 * 60 * 60),
+        }
+    }
+
+    /// Set how long an unterminated session must be idle to count as ended
+    pub fn with_idle_after(mut self, idle_after: Duration) -> Self {
+        self.idle_after = idle_after;
+        self
+    }
+
+    /// Sessions whose last write is before this time have expired
+    fn cutoff(&self) -> Option<DateTime<Utc>> {
+        (self.retention_days > 0)
+            .then(|| Utc::now() - chrono::Duration::days(self.retention_days as i64))
+    }
+}
+
+/// Outcome of a single [`enforce`] pass
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RetentionReport {
+    /// Sessions deleted because they were older than `retention_days`
+    pub expired_sessions: usize,
+    /// Sessions deleted to get under `max_total_bytes`
+    pub evicted_sessions: usize,
+    /// Segment files compressed
+    pub compressed_segments: usize,
+    /// Bytes freed in the log directory by deletion and compression
+    pub freed_bytes: u64,
+    /// Events deleted from the SQLite database
+    pub pruned_events: usize,
+    /// Sessions deleted from the SQLite database to get under `max_total_bytes`
+    pub evicted_db_sessions: usize,
+    /// Files that could not be deleted or compressed, with the reason
+    pub failed: Vec<(PathBuf, String)>,
+}
+
+/// Compress one segment file, replacing it with `{name}.zst` or `{name}.gz`.
+///
+/// The compressed file is written under a temporary name and renamed into
+/// place before the original is removed, so readers always find a complete
+/// copy. Returns the path of the compressed file (or `path` itself for
+/// [`Compression::None`] and already compressed segments).
+pub fn compress_segment(path: &Path, compression: Compression) -> Result<PathBuf, CoreError> {
+    let Some(ext) = compression.extension() else {
+        return Ok(path.to_path_buf());
+    };
+    let already_compressed = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .and_then(Compression::from_extension)
+        .is_some();
+    if already_compressed {
+        return Ok(path.to_path_buf());
+    }
+
+    let mut target = path.as_os_str().to_owned();
+    target.push(".");
+    target.push(ext);
+    let target = PathBuf::from(target);
+    let mut tmp = target.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+
+    let mut input = File::open(path).map_err(|e| StorageError::OpenFile {
+        path: path.to_path_buf(),
+        source: e,
+    })?;
+    let output = File::create(&tmp).map_err(|e| StorageError::OpenFile {
+        path: tmp.clone(),
+        source: e,
+    })?;
+    let written = match compression {
+        Compression::Gzip => {
+            let mut encoder = flate2::write::GzEncoder::new(output, flate2::Compression::default());
+            std::io::copy(&mut input, &mut encoder)
+                .and_then(|_| encoder.finish())
+                .and_then(|file| file.sync_all())
+        }
+        Compression::Zstd => zstd::Encoder::new(output, 0).and_then(|mut encoder| {
+            std::io::copy(&mut input, &mut encoder)?;
+            encoder.finish()?.sync_all()
+        }),
+        Compression::None => unreachable!("handled above"),
+    };
+    if let Err(e) = written.and_then(|_| std::fs::rename(&tmp, &target)) {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(StorageError::Write(e).into());
+    }
+    std::fs::remove_file(path)?;
+    Ok(target)
+}
+
+/// Compress every uncompressed segment of the session `path` belongs to.
+/// Returns the number of segments compressed.
+pub fn compress_session(path: &Path, compression: Compression) -> Result<usize, CoreError> {
+    if compression == Compression::None {
+        return Ok(0);
+    }
+    let mut compressed = 0;
+    for segment in session_segments(path) {
+        if compress_segment(&segment, compression)? != segment {
+            compressed += 1;
+        }
+    }
+    Ok(compressed)
+}
+
+/// Session log files in the log directory, grouped by session
+struct StoredSession {
+    id: String,
+    files: Vec<(PathBuf, SegmentName, u64)>,
+    modified: SystemTime,
+}
+
+impl StoredSession {
+    fn size(&self) -> u64 {
+        self.files.iter().map(|(_, _, size)| size).sum()
+    }
+
+    /// Whether the session has ended: its log ends with a session end record,
+    /// it was already compressed, or it has been idle for `idle_after`
+    fn is_closed(&self, idle_after: Duration) -> bool {
+        if self
+            .files
+            .iter()
+            .any(|(_, name, _)| name.compression != Compression::None)
+        {
+            return true;
+        }
+        let idle = self
+            .modified
+            .elapsed()
+            .is_ok_and(|elapsed| elapsed >= idle_after);
+        idle || self
+            .files
+            .iter()
+            .max_by_key(|(_, name, _)| name.index)
+            .and_then(|(path, _, _)| last_line(path))
+            .is_some_and(|line| is_session_end(&line))
+    }
+
+    /// Delete all files of the session, including its workspace changes,
+    /// returning the bytes freed
+    fn remove(&self, failed: &mut Vec<(PathBuf, String)>) -> u64 {
+        let mut freed = 0;
+        for (path, _, size) in &self.files {
+            match std::fs::remove_file(path) {
+                Ok(()) => freed += size,
+                Err(e) => failed.push((path.clone(), e.to_string())),
+            }
+        }
+        if let Some(log_dir) = self.files.first().and_then(|(path, _, _)| path.parent()) {
+            let (list, patch) = WorkspaceChanges::artifact_paths(log_dir, &self.id);
+            for path in [list, patch] {
+                let Ok(metadata) = std::fs::metadata(&path) else {
+                    continue;
+                };
+                match std::fs::remove_file(&path) {
+                    Ok(()) => freed += metadata.len(),
+                    Err(e) => failed.push((path, e.to_string())),
+                }
+            }
+        }
+        freed
+    }
+}
+
+/// Group the session logs in `log_dir`, oldest first
+fn scan_sessions(log_dir: &Path) -> Result<Vec<StoredSession>, CoreError> {
+    let mut sessions: HashMap<String, StoredSession> = HashMap::new();
+    for entry in std::fs::read_dir(log_dir)? {
+        let entry = entry?;
+        let Some(name) = entry.file_name().to_str().and_then(SegmentName::parse) else {
+            continue;
+        };
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+        let session = sessions
+            .entry(name.session_id.clone())
+            .or_insert_with(|| StoredSession {
+                id: name.session_id.clone(),
+                files: Vec::new(),
+                modified,
+            });
+        session.modified = session.modified.max(modified);
+        session.files.push((entry.path(), name, metadata.len()));
+    }
+
+    let mut sessions: Vec<StoredSession> = sessions.into_values().collect();
+    sessions.sort_by(|a, b| a.modified.cmp(&b.modified).then(a.id.cmp(&b.id)));
+    Ok(sessions)
+}
+
+/// Last non-empty line of a plain text file
+fn last_line(path: &Path) -> Option<String> {
+    let mut file = File::open(path).ok()?;
+    let len = file.metadata().ok()?.len();
+    file.seek(SeekFrom::Start(len.saturating_sub(LAST_LINE_WINDOW)))
+        .ok()?;
+    let mut tail = Vec::new();
+    file.read_to_end(&mut tail).ok()?;
+    String::from_utf8_lossy(&tail)
+        .lines()
+        .rev()
+        .find(|line| !line.trim().is_empty())
+        .map(String::from)
+}
+
+/// A `session_end` footer or a session end event
+fn is_session_end(line: &str) -> bool {
+    if let Ok(event) = serde_json::from_str::<Event>(line) {
+        return matches!(
+            event.event_type,
+            EventType::Session {
+                action: SessionAction::End
+            }
+        );
+    }
+    serde_json::from_str::<serde_json::Value>(line)
+        .is_ok_and(|value| value.get("type").and_then(|t| t.as_str()) == Some("session_end"))
+}
+
+/// Apply `policy` to the logs in `log_dir` and, if given, the SQLite
+/// database at `db_path`. The session `active_session` is left alone.
+pub fn enforce(
+    policy: &RetentionPolicy,
+    log_dir: &Path,
+    db_path: Option<&Path>,
+    active_session: Option<&str>,
+) -> Result<RetentionReport, CoreError> {
+    let mut report = RetentionReport::default();
+    let cutoff = policy.cutoff();
+
+    if log_dir.exists() {
+        let (active, mut sessions): (Vec<_>, Vec<_>) = scan_sessions(log_dir)?
+            .into_iter()
+            .partition(|s| Some(s.id.as_str()) == active_session);
+
+        // Expired sessions
+        if let Some(cutoff) = cutoff {
+            let cutoff: SystemTime = cutoff.into();
+            sessions.retain(|session| {
+                if session.modified >= cutoff {
+                    return true;
+                }
+                report.freed_bytes += session.remove(&mut report.failed);
+                report.expired_sessions += 1;
+                false
+            });
+        }
+
+        // Compress ended sessions; only those may be evicted below
+        let mut closed: Vec<StoredSession> = Vec::new();
+        let mut total: u64 = active.iter().map(StoredSession::size).sum();
+        for mut session in sessions {
+            if !session.is_closed(policy.idle_after) {
+                total += session.size();
+                continue;
+            }
+            for (path, name, size) in &mut session.files {
+                if name.compression != Compression::None {
+                    continue;
+                }
+                match compress_segment(path, policy.compression) {
+                    Ok(target) if target != *path => {
+                        let new_size = std::fs::metadata(&target).map(|m| m.len()).unwrap_or(0);
+                        report.freed_bytes += size.saturating_sub(new_size);
+                        report.compressed_segments += 1;
+                        name.compression = policy.compression;
+                        *path = target;
+                        *size = new_size;
+                    }
+                    Ok(_) => {}
+                    Err(e) => report.failed.push((path.clone(), e.to_string())),
+                }
+            }
+            total += session.size();
+            closed.push(session);
+        }
+
+        // Total size cap, oldest ended sessions first
+        if policy.max_total_bytes > 0 {
+            for session in &closed {
+                if total <= policy.max_total_bytes {
+                    break;
+                }
+                let freed = session.remove(&mut report.failed);
+                total = total.saturating_sub(session.size());
+                report.freed_bytes += freed;
+                report.evicted_sessions += 1;
+            }
+        }
+    }
+
+    if let Some(db_path) = db_path.filter(|path| path.exists()) {
+        let mut storage = SqliteStorage::new(&db_path.to_path_buf())?;
+        if let Some(cutoff) = cutoff {
+            report.pruned_events = storage.prune_before(cutoff)?;
+        }
+
+        // The database gets the same total cap as the log directory. Each
+        // round deletes the oldest ended sessions until their share of the
+        // event data covers the excess, then vacuums to measure again.
+        let mut vacuumed = false;
+        if policy.max_total_bytes > 0 {
+            loop {
+                let used = storage.used_bytes()?;
+                let total = storage.event_data_bytes()?;
+                if used <= policy.max_total_bytes || total == 0 {
+                    break;
+                }
+                let excess = used - policy.max_total_bytes;
+                let idle_before = chrono::Duration::from_std(policy.idle_after)
+                    .ok()
+                    .and_then(|idle| Utc::now().checked_sub_signed(idle))
+                    .unwrap_or(DateTime::<Utc>::MIN_UTC);
+                let mut freed = 0u64;
+                let mut deleted = false;
+                for (session_id, bytes) in storage.ended_sessions(idle_before)? {
+                    if freed >= excess {
+                        break;
+                    }
+                    if Some(session_id.as_str()) == active_session {
+                        continue;
+                    }
+                    report.pruned_events += storage.delete_session(&session_id)?;
+                    report.evicted_db_sessions += 1;
+                    freed += (bytes as u128 * used as u128 / total as u128) as u64;
+                    deleted = true;
+                }
+                if !deleted {
+                    break;
+                }
+                storage.vacuum()?;
+                vacuumed = true;
+            }
+        }
+
+        if report.pruned_events > 0 && !vacuumed {
+            storage.vacuum()?;
+        }
+    }
+
+    Ok(report)
+}
+
+/// Runs [`enforce`] on a background thread when started and then
+/// periodically while a session is recorded.
+pub struct RetentionManager {
+    policy: RetentionPolicy,
+    log_dir: PathBuf,
+    db_path: Option<PathBuf>,
+    active_session: Option<String>,
+    interval: Duration,
+    stop_flag: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl RetentionManager {
+    /// Create a manager for the logs in `log_dir`
+    pub fn new(policy: RetentionPolicy, log_dir: PathBuf) -> Self {
+        Self {
+            policy,
+            log_dir,
+            db_path: None,
+            active_session: None,
+            interval: Duration::from_secs(60 * 60),
+            stop_flag: Arc::new(AtomicBool::new(false)),
+            thread: None,
+        }
+    }
+
+    /// Also prune the SQLite database at `path`
+    pub fn with_db_path(mut self, path: PathBuf) -> Self {
+        self.db_path = Some(path);
+        self
+    }
+
+    /// Never touch the logs of this session
+    pub fn with_active_session(mut self, session_id: impl Into<String>) -> Self {
+        self.active_session = Some(session_id.into());
+        self
+    }
+
+    /// Time between enforcement passes (default: 1 hour)
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Check if the manager is running
+    pub fn is_running(&self) -> bool {
+        self.thread.is_some() && !self.stop_flag.load(Ordering::Relaxed)
+    }
+
+    /// Start the background thread. The first pass runs immediately.
+    pub fn start(&mut self) -> std::result::Result<(), CoreError> {
+        self.stop_flag.store(false, Ordering::Relaxed);
+
+        let policy = self.policy.clone();
+        let log_dir = self.log_dir.clone();
+        let db_path = self.db_path.clone();
+        let active_session = self.active_session.clone();
+        let interval = self.interval;
+        let stop_flag = Arc::clone(&self.stop_flag);
+
+        let handle = thread::spawn(move || {
+            while !stop_flag.load(Ordering::Relaxed) {
+                match enforce(
+                    &policy,
+                    &log_dir,
+                    db_path.as_deref(),
+                    active_session.as_deref(),
+                ) {
+                    Ok(report) => {
+                        for (path, error) in report.failed {
+                            eprintln!(
+                                "[agent-watch] Warning: Retention failed for {}: {}",
+                                path.display(),
+                                error
+                            );
+                        }
+                    }
+                    Err(e) => eprintln!("[agent-watch] Warning: Retention pass failed: {e}"),
+                }
+                Self::sleep(interval, &stop_flag);
+            }
+        });
+
+        self.thread = Some(handle);
+        Ok(())
+    }
+
+    /// Sleep for `interval`, waking early when stopped
+    fn sleep(interval: Duration, stop_flag: &AtomicBool) {
+        let step = Duration::from_millis(100);
+        let mut slept = Duration::ZERO;
+        while slept < interval && !stop_flag.load(Ordering::Relaxed) {
+            let nap = step.min(interval - slept);
+            thread::sleep(nap);
+            slept += nap;
+        }
+    }
+
+    /// Stop the background thread, waiting for a running pass to finish
+    pub fn stop(&mut self) {
+        self.stop_flag.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.thread.take() {
+            let _ = handle.join();
+        }
+    }
+
+    /// Signal the manager to stop without waiting for the thread to finish.
+    pub fn signal_stop(&self) {
+        self.stop_flag.store(true, Ordering::Relaxed);
+    }
+}
+
+impl crate::types::MonitoringSubsystem for RetentionManager {
+    fn start(&mut self) -> std::result::Result<(), CoreError> {
+        RetentionManager::start(self)
+    }
+
+    fn stop(&mut self) {
+        RetentionManager::stop(self)
+    }
+
+    fn signal_stop(&self) {
+        RetentionManager::signal_stop(self)
+    }
+
+    fn is_running(&self) -> bool {
+        RetentionManager::is_running(self)
+    }
+}
+
+impl Drop for RetentionManager {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::RiskLevel;
+    use crate::storage::{open_session_log, EventStorage, SessionLogger};
+    use std::io::BufRead;
+    use tempfile::TempDir;
+
+    fn set_mtime(path: &Path, age: Duration) {
+        let file = std::fs::OpenOptions::new().write(true).open(path).unwrap();
+        file.set_modified(SystemTime::now() - age).unwrap();
+    }
+
+    fn ended_session(dir: &Path, id: &str, events: usize) -> PathBuf {
+        let mut logger = SessionLogger::new(&dir.to_path_buf(), Some(id.to_string())).unwrap();
+        logger.write_session_header("bash", 1, None).unwrap();
+        for i in 0..events {
+            let event =
+                Event::command(format!("cmd-{i}"), vec![], "bash".into(), 1, RiskLevel::Low);
+            logger.write_event(&event).unwrap();
+        }
+        logger.write_session_footer(Some(0)).unwrap();
+        logger.session_path()
+    }
+
+    fn read_commands(path: &Path) -> usize {
+        open_session_log(path)
+            .unwrap()
+            .lines()
+            .filter(|l| {
+                serde_json::from_str::<Event>(l.as_ref().unwrap())
+                    .is_ok_and(|e| matches!(e.event_type, EventType::Command { .. }))
+            })
+            .count()
+    }
+
+    fn policy() -> RetentionPolicy {
+        RetentionPolicy {
+            retention_days: 30,
+            max_segment_bytes: 0,
+            max_total_bytes: 0,
+            compression: Compression::Zstd,
+            idle_after: Duration::from_secs(3600),
+        }
+    }
+
+    #[test]
+    fn test_policy_from_config() {
+        let logging = LoggingConfig {
+            max_session_mb: 2,
+            max_total_mb: 0,
+            compression: Compression::Gzip,
+            ..LoggingConfig::default()
+        };
+        let policy = RetentionPolicy::from_config(&logging);
+        assert_eq!(policy.max_segment_bytes, 2 * MB);
+        assert_eq!(policy.max_total_bytes, 0);
+        assert_eq!(policy.compression, Compression::Gzip);
+        assert_eq!(policy.retention_days, 30);
+    }
+
+    #[test]
+    fn test_compress_segment_roundtrip() {
+        for compression in [Compression::Zstd, Compression::Gzip] {
+            let dir = TempDir::new().unwrap();
+            let path = ended_session(dir.path(), "zip", 50);
+            let plain_len = std::fs::metadata(&path).unwrap().len();
+
+            let target = compress_segment(&path, compression).unwrap();
+            assert!(!path.exists());
+            assert!(target
+                .to_str()
+                .unwrap()
+                .ends_with(compression.extension().unwrap()));
+            assert!(std::fs::metadata(&target).unwrap().len() < plain_len);
+
+            // Readable through the logical path
+            assert_eq!(read_commands(&path), 50);
+        }
+    }
+
+    #[test]
+    fn test_compress_segment_none_is_noop() {
+        let dir = TempDir::new().unwrap();
+        let path = ended_session(dir.path(), "plain", 1);
+        assert_eq!(compress_segment(&path, Compression::None).unwrap(), path);
+        assert!(path.exists());
+    }
+
+    #[test]
+    fn test_enforce_compresses_ended_sessions_only() {
+        let dir = TempDir::new().unwrap();
+        let ended = ended_session(dir.path(), "ended", 5);
+
+        let running = dir.path().join("session-running.jsonl");
+        std::fs::write(
+            &running,
+            "{\"type\":\"session_start\",\"session_id\":\"running\"}\n",
+        )
+        .unwrap();
+
+        let report = enforce(&policy(), dir.path(), None, None).unwrap();
+        assert_eq!(report.compressed_segments, 1);
+        assert!(!ended.exists());
+        assert!(dir.path().join("session-ended.jsonl.zst").exists());
+        assert!(running.exists());
+        assert_eq!(read_commands(&ended), 5);
+    }
+
+    #[test]
+    fn test_enforce_treats_idle_sessions_as_ended() {
+        let dir = TempDir::new().unwrap();
+        let crashed = dir.path().join("session-crashed.jsonl");
+        std::fs::write(
+            &crashed,
+            "{\"type\":\"session_start\",\"session_id\":\"crashed\"}\n",
+        )
+        .unwrap();
+        set_mtime(&crashed, Duration::from_secs(2 * 3600));
+
+        let report = enforce(&policy(), dir.path(), None, None).unwrap();
+        assert_eq!(report.compressed_segments, 1);
+        assert!(!crashed.exists());
+    }
+
+    #[test]
+    fn test_enforce_deletes_expired_sessions() {
+        let dir = TempDir::new().unwrap();
+        let old = ended_session(dir.path(), "old", 1);
+        set_mtime(&old, Duration::from_secs(40 * 24 * 3600));
+        let recent = ended_session(dir.path(), "recent", 1);
+
+        let report = enforce(&policy(), dir.path(), None, None).unwrap();
+        assert_eq!(report.expired_sessions, 1);
+        assert!(session_segments(&old).is_empty());
+        assert_eq!(session_segments(&recent).len(), 1);
+    }
+
+    #[test]
+    fn test_enforce_deletes_workspace_changes_with_session() {
+        let dir = TempDir::new().unwrap();
+        let old = ended_session(dir.path(), "old", 1);
+        set_mtime(&old, Duration::from_secs(40 * 24 * 3600));
+        ended_session(dir.path(), "recent", 1);
+        for id in ["old", "recent"] {
+            WorkspaceChanges::default().save(dir.path(), id).unwrap();
+        }
+
+        enforce(&policy(), dir.path(), None, None).unwrap();
+        let (list, patch) = WorkspaceChanges::artifact_paths(dir.path(), "old");
+        assert!(!list.exists());
+        assert!(!patch.exists());
+        assert!(WorkspaceChanges::load(dir.path(), "recent").is_ok());
+    }
+
+    #[test]
+    fn test_enforce_total_cap_evicts_oldest_but_not_active() {
+        let dir = TempDir::new().unwrap();
+        let policy = RetentionPolicy {
+            compression: Compression::None,
+            ..policy()
+        };
+        let oldest = ended_session(dir.path(), "a-oldest", 20);
+        set_mtime(&oldest, Duration::from_secs(3 * 3600));
+        let middle = ended_session(dir.path(), "b-middle", 20);
+        set_mtime(&middle, Duration::from_secs(2 * 3600));
+        let active = ended_session(dir.path(), "c-active", 20);
+        set_mtime(&active, Duration::from_secs(4 * 3600));
+
+        // Room for exactly the two newest. Timestamps are written with 6 or 9
+        // fractional digits, so sessions of the same events can differ by a
+        // few bytes and twice one of them isn't always enough.
+        let size = |path: &Path| std::fs::metadata(path).unwrap().len();
+        let policy = RetentionPolicy {
+            max_total_bytes: size(&middle) + size(&active),
+            ..policy
+        };
+        let report = enforce(&policy, dir.path(), None, Some("c-active")).unwrap();
+        assert_eq!(report.evicted_sessions, 1);
+        assert!(!oldest.exists());
+        assert!(middle.exists());
+        assert!(active.exists());
+    }
+
+    #[test]
+    fn test_enforce_never_touches_active_session() {
+        let dir = TempDir::new().unwrap();
+        let active = ended_session(dir.path(), "active", 1);
+        set_mtime(&active, Duration::from_secs(40 * 24 * 3600));
+
+        let report = enforce(&policy(), dir.path(), None, Some("active")).unwrap();
+        assert_eq!(report, RetentionReport::default());
+        assert!(active.exists());
+    }
+
+    #[test]
+    fn test_enforce_prunes_sqlite() {
+        let dir = TempDir::new().unwrap();
+        let db_path = dir.path().join("events.db");
+        {
+            let mut storage = SqliteStorage::new(&db_path).unwrap().with_session_id("old");
+            let start = Utc::now() - chrono::Duration::days(60);
+            storage
+                .write_session_header("old", "bash", 1, &start, None)
+                .unwrap();
+            let mut event = Event::command("ls".into(), vec![], "bash".into(), 1, RiskLevel::Low);
+            event.timestamp = start;
+            storage.write_event(&event).unwrap();
+
+            let mut storage = storage.with_session_id("new");
+            storage
+                .write_session_header("new", "bash", 1, &Utc::now(), None)
+                .unwrap();
+            let event = Event::command("pwd".into(), vec![], "bash".into(), 1, RiskLevel::Low);
+            storage.write_event(&event).unwrap();
+        }
+
+        let logs = dir.path().join("logs");
+        let report = enforce(&policy(), &logs, Some(&db_path), None).unwrap();
+        assert_eq!(report.pruned_events, 1);
+
+        let storage = SqliteStorage::new(&db_path).unwrap();
+        assert!(!storage.has_session("old").unwrap());
+        assert!(storage.has_session("new").unwrap());
+    }
+
+    #[test]
+    fn test_enforce_total_cap_evicts_oldest_db_sessions() {
+        let dir = TempDir::new().unwrap();
+        let db_path = dir.path().join("events.db");
+        {
+            let mut storage = SqliteStorage::new(&db_path).unwrap();
+            for (i, id) in ["oldest", "older", "ended", "active"].iter().enumerate() {
+                storage = storage.with_session_id(*id);
+                let start = Utc::now() - chrono::Duration::minutes(10 - i as i64);
+                storage
+                    .write_session_header(id, "bash", 1, &start, None)
+                    .unwrap();
+                for _ in 0..50 {
+                    let mut event = Event::command(
+                        "echo".into(),
+                        vec!["x".repeat(4096)],
+                        "bash".into(),
+                        1,
+                        RiskLevel::Low,
+                    );
+                    event.timestamp = start;
+                    storage.write_event(&event).unwrap();
+                }
+                if *id != "active" {
+                    storage.write_session_footer(id, &start).unwrap();
+                }
+            }
+        }
+        let used = SqliteStorage::new(&db_path).unwrap().used_bytes().unwrap();
+
+        let policy = RetentionPolicy {
+            max_total_bytes: used * 3 / 5,
+            ..policy()
+        };
+        let logs = dir.path().join("logs");
+        let report = enforce(&policy, &logs, Some(&db_path), Some("active")).unwrap();
+        assert_eq!(report.evicted_db_sessions, 2);
+        assert_eq!(report.pruned_events, 100);
+
+        let storage = SqliteStorage::new(&db_path).unwrap();
+        assert!(!storage.has_session("oldest").unwrap());
+        assert!(!storage.has_session("older").unwrap());
+        assert!(storage.has_session("ended").unwrap());
+        assert!(storage.has_session("active").unwrap());
+        assert!(std::fs::metadata(&db_path).unwrap().len() <= policy.max_total_bytes);
+    }
+
+    #[test]
+    fn test_retention_manager_runs_on_start() {
+        let dir = TempDir::new().unwrap();
+        ended_session(dir.path(), "managed", 1);
+
+        let mut manager = RetentionManager::new(policy(), dir.path().to_path_buf());
+        manager.start().unwrap();
+        assert!(manager.is_running());
+
+        let compressed = dir.path().join("session-managed.jsonl.zst");
+        for _ in 0..50 {
+            if compressed.exists() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+        manager.stop();
+        assert!(!manager.is_running());
+        assert!(compressed.exists());
+    }
+}
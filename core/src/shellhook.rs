@@ -0,0 +1,718 @@
+//! Shell hook module
+//!
+//! Injects preexec/precmd hooks into a wrapped bash, zsh or fish shell. The
+//! hooks report every command line together with its working directory,
+//! start time, duration and exit code, which prompt scraping cannot see.
+//!
+//! Records travel over a FIFO in a private per-session directory, out of band
+//! from the PTY. Each record is a set of fields separated by `\x1f` and
+//! terminated by `\x1e`:
+//!
+//! ```text
+//! exit_code \x1f start_us \x1f duration_ms \x1f cwd \x1f command \x1e
+//! ```
+//!
+//! `start_us` (microseconds since the epoch) and `duration_ms` are empty when
+//! the shell cannot measure them, e.g. bash before 5.0.
+
+use crate::bus::{BusEvent, EventBus, SubscribeOptions};
+use crate::error::CoreError;
+use chrono::{DateTime, Utc};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::Receiver;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Separates the fields of a record
+const FIELD_SEP: char = '\x1f';
+/// Terminates a record
+const RECORD_SEP: u8 = 0x1e;
+/// How often the FIFO is polled when no data is waiting
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Disambiguates hook directories created by the same process
+static NEXT_DIR: AtomicU64 = AtomicU64::new(0);
+
+/// Shells that hooks can be injected into
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShellKind {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl ShellKind {
+    /// Recognise a shell from the command used to start it
+    pub fn detect(command: &str) -> Option<Self> {
+        let name = Path::new(command).file_name()?.to_str()?;
+        // Login shells are started as "-bash" and the like
+        match name.trim_start_matches('-') {
+            "bash" => Some(Self::Bash),
+            "zsh" => Some(Self::Zsh),
+            "fish" => Some(Self::Fish),
+            _ => None,
+        }
+    }
+}
+
+/// A command reported by a shell hook
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HookRecord {
+    /// Command line as typed
+    pub command: String,
+    /// Working directory the command ran in
+    pub cwd: PathBuf,
+    /// When the command started (when it was reported if unknown)
+    pub started_at: DateTime<Utc>,
+    /// How long the command ran, if the shell could measure it
+    pub duration: Option<Duration>,
+    /// Exit code of the command
+    pub exit_code: i32,
+}
+
+impl BusEvent for HookRecord {
+    fn kind(&self) -> &'static str {
+        "shell_command"
+    }
+}
+
+/// Hooks for one wrapped shell and the listener receiving their records
+pub struct ShellHooks {
+    shell: ShellKind,
+    /// Private directory holding the FIFO and the generated scripts
+    dir: PathBuf,
+    fifo: PathBuf,
+    /// Subscribers to hook records
+    events: EventBus<HookRecord>,
+    /// Stop flag
+    stop_flag: Arc<AtomicBool>,
+    /// Listener thread handle
+    thread_handle: Option<JoinHandle<()>>,
+}
+
+impl ShellHooks {
+    /// Create the FIFO and hook scripts for a shell
+    pub fn new(shell: ShellKind) -> std::result::Result<Self, CoreError> {
+        let dir = std::env::temp_dir().join(format!(
+            "agent-watch-hooks-{}-{}",
+            std::process::id(),
+            NEXT_DIR.fetch_add(1, Ordering::Relaxed)
+        ));
+        create_private_dir(&dir)?;
+
+        let hooks = Self {
+            shell,
+            fifo: dir.join("events"),
+            dir,
+            events: EventBus::new(),
+            stop_flag: Arc::new(AtomicBool::new(false)),
+            thread_handle: None,
+        };
+        make_fifo(&hooks.fifo)?;
+        hooks.write_scripts()?;
+        Ok(hooks)
+    }
+
+    /// Shell the hooks were generated for
+    pub fn shell(&self) -> ShellKind {
+        self.shell
+    }
+
+    /// Path of the FIFO the hooks write to
+    pub fn fifo_path(&self) -> &Path {
+        &self.fifo
+    }
+
+    /// Subscribe to hook records
+    pub fn subscribe(&self) -> Receiver<HookRecord> {
+        self.events.subscribe()
+    }
+
+    /// Subscribe with a custom buffer, lag policy and filter
+    pub fn subscribe_with(&self, options: SubscribeOptions) -> Receiver<HookRecord> {
+        self.events.subscribe_with(options)
+    }
+
+    /// Add the arguments and environment that make the shell load the hooks.
+    ///
+    /// Returns `false` if the hooks cannot be injected. Bash only reads an
+    /// rc file when started interactively, so it is hooked only without
+    /// arguments; zsh and fish hooks are installed regardless and stay
+    /// inactive in non-interactive shells.
+    pub fn inject(&self, args: &mut Vec<String>, env: &mut Vec<(String, String)>) -> bool {
+        match self.shell {
+            ShellKind::Bash => {
+                if !args.is_empty() {
+                    return false;
+                }
+                *args = vec![
+                    "--rcfile".to_string(),
+                    self.dir.join("bashrc").to_string_lossy().into_owned(),
+                    "-i".to_string(),
+                ];
+            }
+            ShellKind::Zsh => {
+                env.retain(|(key, _)| key != "ZDOTDIR");
+                env.push((
+                    "ZDOTDIR".to_string(),
+                    self.dir.to_string_lossy().into_owned(),
+                ));
+            }
+            ShellKind::Fish => {
+                let script = self.dir.join("hooks.fish");
+                args.splice(
+                    0..0,
+                    [
+                        "--init-command".to_string(),
+                        format!("source {}", quote_fish(&script.to_string_lossy())),
+                    ],
+                );
+            }
+        }
+        true
+    }
+
+    /// Start listening for records
+    pub fn start(&mut self) -> std::result::Result<(), CoreError> {
+        if self.thread_handle.is_some() {
+            return Ok(());
+        }
+        // Opening read-write keeps a writer attached, so the FIFO never
+        // reports EOF between commands and opening it never blocks
+        let mut fifo = open_fifo(&self.fifo)?;
+        self.stop_flag.store(false, Ordering::Relaxed);
+
+        let stop_flag = Arc::clone(&self.stop_flag);
+        let events = self.events.clone();
+
+        let handle = thread::spawn(move || {
+            let mut buffer = [0u8; 4096];
+            let mut pending: Vec<u8> = Vec::new();
+            loop {
+                let idle = match fifo.read(&mut buffer) {
+                    Ok(0) => true,
+                    Ok(n) => {
+                        pending.extend_from_slice(&buffer[..n]);
+                        while let Some(end) = pending.iter().position(|&b| b == RECORD_SEP) {
+                            let raw: Vec<u8> = pending.drain(..=end).collect();
+                            let raw = String::from_utf8_lossy(&raw[..end]);
+                            if let Some(record) = parse_record(&raw, Utc::now()) {
+                                events.publish(record);
+                            }
+                        }
+                        false
+                    }
+                    Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => true,
+                    Err(e) if e.kind() == std::io::ErrorKind::Interrupted => false,
+                    Err(e) => {
+                        eprintln!("[agent-watch] Warning: Failed to read shell hooks: {e}");
+                        break;
+                    }
+                };
+                // Only stop once the FIFO is drained
+                if idle {
+                    if stop_flag.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    thread::sleep(POLL_INTERVAL);
+                }
+            }
+        });
+
+        self.thread_handle = Some(handle);
+        Ok(())
+    }
+
+    /// Stop listening. Subscribers see a disconnect once the listener has
+    /// drained the FIFO.
+    pub fn stop(&mut self) {
+        self.stop_flag.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.thread_handle.take() {
+            let _ = handle.join();
+        }
+        self.events.close();
+    }
+
+    /// Signal the listener to stop without waiting for it to finish
+    pub fn signal_stop(&self) {
+        self.stop_flag.store(true, Ordering::Relaxed);
+    }
+
+    /// Check if the listener is running
+    pub fn is_running(&self) -> bool {
+        self.thread_handle.is_some() && !self.stop_flag.load(Ordering::Relaxed)
+    }
+
+    fn write_scripts(&self) -> std::result::Result<(), CoreError> {
+        let fifo = self.fifo.to_string_lossy();
+        match self.shell {
+            ShellKind::Bash => std::fs::write(self.dir.join("bashrc"), bash_script(&fifo))?,
+            ShellKind::Zsh => {
+                let home = std::env::var("ZDOTDIR")
+                    .ok()
+                    .filter(|dir| !dir.is_empty())
+                    .or_else(|| dirs::home_dir().map(|h| h.to_string_lossy().into_owned()))
+                    .unwrap_or_default();
+                let dir = self.dir.to_string_lossy();
+                for file in [".zshenv", ".zprofile"] {
+                    std::fs::write(self.dir.join(file), zsh_forward(file, &home, &dir))?;
+                }
+                std::fs::write(self.dir.join(".zshrc"), zsh_script(&fifo, &home, &dir))?;
+            }
+            ShellKind::Fish => std::fs::write(self.dir.join("hooks.fish"), fish_script(&fifo))?,
+        }
+        Ok(())
+    }
+}
+
+impl crate::types::MonitoringSubsystem for ShellHooks {
+    fn start(&mut self) -> std::result::Result<(), CoreError> {
+        ShellHooks::start(self)
+    }
+
+    fn stop(&mut self) {
+        ShellHooks::stop(self)
+    }
+
+    fn signal_stop(&self) {
+        ShellHooks::signal_stop(self)
+    }
+
+    fn is_running(&self) -> bool {
+        ShellHooks::is_running(self)
+    }
+}
+
+impl Drop for ShellHooks {
+    fn drop(&mut self) {
+        self.stop();
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Parse one record, without its terminator
+fn parse_record(raw: &str, received: DateTime<Utc>) -> Option<HookRecord> {
+    let mut fields = raw.splitn(5, FIELD_SEP);
+    let exit_code = fields.next()?.trim().parse().ok()?;
+    let start_us: Option<i64> = fields.next()?.trim().parse().ok();
+    let duration = fields
+        .next()?
+        .trim()
+        .parse()
+        .ok()
+        .map(Duration::from_millis);
+    let cwd = PathBuf::from(fields.next()?);
+    let command = fields.next()?.trim_end_matches('\n').to_string();
+    if command.trim().is_empty() {
+        return None;
+    }
+
+    let started_at = start_us
+        .and_then(DateTime::from_timestamp_micros)
+        .or_else(|| {
+            duration
+                .and_then(|d| chrono::Duration::from_std(d).ok())
+                .map(|d| received - d)
+        })
+        .unwrap_or(received);
+
+    Some(HookRecord {
+        command,
+        cwd,
+        started_at,
+        duration,
+        exit_code,
+    })
+}
+
+/// Quote a string for bash and zsh
+fn quote_sh(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// Quote a string for fish
+fn quote_fish(s: &str) -> String {
+    format!("'{}'", s.replace('\\', r"\\").replace('\'', r"\'"))
+}
+
+/// Bash rc file: loads the user's bashrc, then hooks `PROMPT_COMMAND` and
+/// the DEBUG trap
+fn bash_script(fifo: &str) -> String {
+    format!(
+        r#"# agent-watch shell hooks for bash, generated for one session
+if [ -f "$HOME/.bashrc" ]; then . "$HOME/.bashrc"; fi
+
+__aw_fifo={fifo}
+__aw_ready=0
+__aw_ran=0
+__aw_start=
+__aw_last=
+__aw_hist=
+
+__aw_histline() {{ HISTTIMEFORMAT= builtin history 1; }}
+
+# DEBUG trap: the first command after a prompt starts a command line
+__aw_preexec() {{
+    [ "$__aw_ready" = 1 ] || return 0
+    case "$BASH_COMMAND" in __aw_precmd*) return 0 ;; esac
+    __aw_ready=0
+    __aw_ran=1
+    __aw_last=$BASH_COMMAND
+    __aw_start=${{EPOCHREALTIME:-}}
+}}
+
+__aw_precmd() {{
+    local status=$? line cmd start= duration= now
+    __aw_ready=0
+    [ "$__aw_ran" = 1 ] || return "$status"
+    __aw_ran=0
+    # Prefer the full line from history; fall back to the first simple
+    # command when the line was not added (ignorespace, ignoredups)
+    line=$(__aw_histline)
+    if [[ -n $line && $line != "$__aw_hist" && $line =~ ^[[:space:]]*[0-9]+[*]?[[:space:]]+(.*)$ ]]; then
+        cmd=${{BASH_REMATCH[1]}}
+    else
+        cmd=$__aw_last
+    fi
+    now=${{EPOCHREALTIME:-}}
+    if [ -n "$__aw_start" ] && [ -n "$now" ]; then
+        start=${{__aw_start/[.,]/}}
+        now=${{now/[.,]/}}
+        duration=$(( (now - start) / 1000 ))
+    fi
+    [ -p "$__aw_fifo" ] && printf '%s\037%s\037%s\037%s\037%s\036' \
+        "$status" "$start" "$duration" "$PWD" "$cmd" >> "$__aw_fifo"
+    return "$status"
+}}
+
+__aw_arm() {{
+    local status=$?
+    __aw_hist=$(__aw_histline)
+    __aw_ready=1
+    return "$status"
+}}
+
+if [[ "$(declare -p PROMPT_COMMAND 2>/dev/null)" == "declare -a"* ]]; then
+    PROMPT_COMMAND=(__aw_precmd "${{PROMPT_COMMAND[@]}}" __aw_arm)
+else
+    PROMPT_COMMAND="__aw_precmd${{PROMPT_COMMAND:+; $PROMPT_COMMAND}}; __aw_arm"
+fi
+trap '__aw_preexec' DEBUG
+"#,
+        fifo = quote_sh(fifo)
+    )
+}
+
+/// Zsh startup file that loads the user's file of the same name. ZDOTDIR
+/// points at the hook directory until `.zshrc` restores it.
+fn zsh_forward(file: &str, home: &str, dir: &str) -> String {
+    format!(
+        r#"# agent-watch shell hooks for zsh, generated for one session
+__aw_home=${{__aw_home:-{home}}}
+if [[ -f $__aw_home/{file} ]]; then
+    ZDOTDIR=$__aw_home
+    source $__aw_home/{file}
+    __aw_home=$ZDOTDIR
+    ZDOTDIR={dir}
+fi
+"#,
+        home = quote_sh(home),
+        dir = quote_sh(dir)
+    )
+}
+
+/// Zsh rc file: loads the user's zshrc, restores ZDOTDIR and installs
+/// preexec/precmd hooks
+fn zsh_script(fifo: &str, home: &str, dir: &str) -> String {
+    format!(
+        r#"{forward}ZDOTDIR=$__aw_home
+unset __aw_home
+
+zmodload zsh/datetime 2>/dev/null
+__aw_fifo={fifo}
+__aw_ran=0
+
+__aw_preexec() {{
+    __aw_cmd=$1
+    __aw_start=${{EPOCHREALTIME:-}}
+    __aw_ran=1
+}}
+
+__aw_precmd() {{
+    local exit_status=$? start= duration=
+    (( __aw_ran )) || return $exit_status
+    __aw_ran=0
+    if [[ -n $__aw_start && -n $EPOCHREALTIME ]]; then
+        integer start_us=$(( __aw_start * 1000000 ))
+        integer elapsed_ms=$(( (EPOCHREALTIME - __aw_start) * 1000 ))
+        start=$start_us
+        duration=$elapsed_ms
+    fi
+    [[ -p $__aw_fifo ]] && printf '%s\037%s\037%s\037%s\037%s\036' \
+        $exit_status "$start" "$duration" "$PWD" "$__aw_cmd" >> $__aw_fifo
+    return $exit_status
+}}
+
+# precmd runs first so it sees the command's exit status
+autoload -Uz add-zsh-hook
+add-zsh-hook preexec __aw_preexec
+precmd_functions=(__aw_precmd ${{precmd_functions:#__aw_precmd}})
+"#,
+        forward = zsh_forward(".zshrc", home, dir),
+        fifo = quote_sh(fifo)
+    )
+}
+
+/// Fish init script hooking `fish_postexec`
+fn fish_script(fifo: &str) -> String {
+    format!(
+        r#"# agent-watch shell hooks for fish, generated for one session
+set -g __aw_fifo {fifo}
+
+function __aw_postexec --on-event fish_postexec
+    set -l exit_status $status
+    string length -q -- (string trim -- "$argv"); or return $exit_status
+    test -p $__aw_fifo; or return $exit_status
+    printf '%s\037%s\037%s\037%s\037%s\036' \
+        $exit_status '' $CMD_DURATION $PWD "$argv" >> $__aw_fifo
+    return $exit_status
+end
+"#,
+        fifo = quote_fish(fifo)
+    )
+}
+
+#[cfg(unix)]
+fn create_private_dir(dir: &Path) -> std::result::Result<(), CoreError> {
+    use std::os::unix::fs::DirBuilderExt;
+    std::fs::DirBuilder::new().mode(0o700).create(dir)?;
+    Ok(())
+}
+
+#[cfg(unix)]
+fn make_fifo(path: &Path) -> std::result::Result<(), CoreError> {
+    use std::os::unix::ffi::OsStrExt;
+    let c_path = std::ffi::CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| CoreError::Wrapper(format!("Invalid shell hook path: {e}")))?;
+    // SAFETY: c_path is a valid NUL-terminated string
+    if unsafe { libc::mkfifo(c_path.as_ptr(), 0o600) } != 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn open_fifo(path: &Path) -> std::result::Result<std::fs::File, CoreError> {
+    use std::os::unix::fs::OpenOptionsExt;
+    Ok(std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .custom_flags(libc::O_NONBLOCK)
+        .open(path)?)
+}
+
+#[cfg(not(unix))]
+fn create_private_dir(_dir: &Path) -> std::result::Result<(), CoreError> {
+    Err(CoreError::Wrapper(
+        "shell hooks are not supported on this platform".to_string(),
+    ))
+}
+
+#[cfg(not(unix))]
+fn make_fifo(_path: &Path) -> std::result::Result<(), CoreError> {
+    Err(CoreError::Wrapper(
+        "shell hooks are not supported on this platform".to_string(),
+    ))
+}
+
+#[cfg(not(unix))]
+fn open_fifo(_path: &Path) -> std::result::Result<std::fs::File, CoreError> {
+    Err(CoreError::Wrapper(
+        "shell hooks are not supported on this platform".to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(fields: &[&str]) -> String {
+        fields.join(&FIELD_SEP.to_string())
+    }
+
+    #[test]
+    fn test_detect_shell() {
+        assert_eq!(ShellKind::detect("bash"), Some(ShellKind::Bash));
+        assert_eq!(ShellKind::detect("/bin/zsh"), Some(ShellKind::Zsh));
+        assert_eq!(ShellKind::detect("-bash"), Some(ShellKind::Bash));
+        assert_eq!(
+            ShellKind::detect("/opt/homebrew/bin/fish"),
+            Some(ShellKind::Fish)
+        );
+        assert_eq!(ShellKind::detect("claude"), None);
+        assert_eq!(ShellKind::detect("bashful"), None);
+    }
+
+    #[test]
+    fn test_parse_record_full() {
+        let received = Utc::now();
+        let raw = record(&["2", "1700000000123456", "1500", "/tmp/work", "make test"]);
+        let parsed = parse_record(&raw, received).unwrap();
+        assert_eq!(parsed.command, "make test");
+        assert_eq!(parsed.cwd, PathBuf::from("/tmp/work"));
+        assert_eq!(parsed.exit_code, 2);
+        assert_eq!(parsed.duration, Some(Duration::from_millis(1500)));
+        assert_eq!(parsed.started_at.timestamp_micros(), 1_700_000_000_123_456);
+    }
+
+    #[test]
+    fn test_parse_record_derives_start_from_duration() {
+        let received = Utc::now();
+        let raw = record(&["0", "", "250", "/", "ls"]);
+        let parsed = parse_record(&raw, received).unwrap();
+        assert_eq!(
+            parsed.started_at,
+            received - chrono::Duration::milliseconds(250)
+        );
+
+        let raw = record(&["0", "", "", "/", "ls"]);
+        let parsed = parse_record(&raw, received).unwrap();
+        assert_eq!(parsed.started_at, received);
+        assert_eq!(parsed.duration, None);
+    }
+
+    #[test]
+    fn test_parse_record_keeps_separators_in_command() {
+        let raw = record(&["0", "", "", "/", "printf 'a\x1fb'\necho done\n"]);
+        let parsed = parse_record(&raw, Utc::now()).unwrap();
+        assert_eq!(parsed.command, "printf 'a\x1fb'\necho done");
+    }
+
+    #[test]
+    fn test_parse_record_rejects_malformed() {
+        let now = Utc::now();
+        assert!(parse_record("", now).is_none());
+        assert!(parse_record(&record(&["x", "", "", "/", "ls"]), now).is_none());
+        assert!(parse_record(&record(&["0", "", "", "/"]), now).is_none());
+        assert!(parse_record(&record(&["0", "", "", "/", "  "]), now).is_none());
+    }
+
+    #[test]
+    fn test_quoting() {
+        assert_eq!(quote_sh("/tmp/it's"), r"'/tmp/it'\''s'");
+        assert_eq!(quote_fish(r"/tmp/it's\x"), r"'/tmp/it\'s\\x'");
+    }
+
+    #[test]
+    fn test_inject() {
+        let bash = ShellHooks::new(ShellKind::Bash).unwrap();
+        let mut args = Vec::new();
+        let mut env = Vec::new();
+        assert!(bash.inject(&mut args, &mut env));
+        assert_eq!(args[0], "--rcfile");
+        assert!(Path::new(&args[1]).is_file());
+        let mut script_args = vec!["script.sh".to_string()];
+        assert!(!bash.inject(&mut script_args, &mut env));
+        assert_eq!(script_args, vec!["script.sh"]);
+
+        let zsh = ShellHooks::new(ShellKind::Zsh).unwrap();
+        let mut env = vec![("ZDOTDIR".to_string(), "/elsewhere".to_string())];
+        assert!(zsh.inject(&mut Vec::new(), &mut env));
+        assert_eq!(env.len(), 1);
+        assert!(Path::new(&env[0].1).join(".zshrc").is_file());
+
+        let fish = ShellHooks::new(ShellKind::Fish).unwrap();
+        let mut args = vec!["-l".to_string()];
+        assert!(fish.inject(&mut args, &mut Vec::new()));
+        assert_eq!(args[0], "--init-command");
+        assert_eq!(args[2], "-l");
+    }
+
+    #[test]
+    fn test_drop_removes_directory() {
+        let hooks = ShellHooks::new(ShellKind::Bash).unwrap();
+        let dir = hooks.fifo_path().parent().unwrap().to_path_buf();
+        assert!(dir.is_dir());
+        drop(hooks);
+        assert!(!dir.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_listener_reads_fifo() {
+        use std::io::Write;
+
+        let mut hooks = ShellHooks::new(ShellKind::Zsh).unwrap();
+        let rx = hooks.subscribe();
+        let other_rx = hooks.subscribe();
+        hooks.start().unwrap();
+        assert!(hooks.is_running());
+
+        let mut writer = std::fs::OpenOptions::new()
+            .append(true)
+            .open(hooks.fifo_path())
+            .unwrap();
+        let first = record(&["0", "", "12", "/tmp", "git status"]);
+        let second = record(&["1", "", "", "/tmp", "false"]);
+        // A record split across writes is reassembled
+        write!(writer, "{first}\x1e{}", &second[..3]).unwrap();
+        writer.flush().unwrap();
+        thread::sleep(Duration::from_millis(100));
+        write!(writer, "{}\x1e", &second[3..]).unwrap();
+        drop(writer);
+
+        hooks.stop();
+        let records: Vec<HookRecord> = rx.iter().collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].command, "git status");
+        assert_eq!(records[1].command, "false");
+        assert_eq!(records[1].exit_code, 1);
+        // Every subscriber gets each record
+        assert_eq!(other_rx.iter().collect::<Vec<_>>(), records);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_bash_hooks_report_commands() {
+        use std::process::{Command, Stdio};
+
+        if Command::new("bash").arg("--version").output().is_err() {
+            return;
+        }
+        let mut hooks = ShellHooks::new(ShellKind::Bash).unwrap();
+        let rx = hooks.subscribe();
+        hooks.start().unwrap();
+        let mut args = Vec::new();
+        hooks.inject(&mut args, &mut Vec::new());
+
+        let home = tempfile::TempDir::new().unwrap();
+        let mut child = Command::new("bash")
+            .args(&args)
+            .env("HOME", home.path())
+            .env_remove("PROMPT_COMMAND")
+            .current_dir(home.path())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+        {
+            use std::io::Write;
+            let stdin = child.stdin.as_mut().unwrap();
+            stdin.write_all(b"true\n\nsh -c 'exit 3'\nexit\n").unwrap();
+        }
+        child.wait().unwrap();
+        hooks.stop();
+
+        let records: Vec<HookRecord> = rx.iter().collect();
+        let commands: Vec<(&str, i32)> = records
+            .iter()
+            .map(|r| (r.command.as_str(), r.exit_code))
+            .collect();
+        assert_eq!(commands, vec![("true", 0), ("sh -c 'exit 3'", 3)]);
+        assert_eq!(records[0].cwd, home.path());
+    }
+}
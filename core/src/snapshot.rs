@@ -0,0 +1,768 @@
+//! Workspace snapshots
+//!
+//! Records the files under the watched paths when a session starts and
+//! compares them with what is there when it ends, giving a reviewable record
+//! of what the agent changed: the added, deleted and modified files, and a
+//! unified diff of the text among them.
+//!
+//! Files are compared by length and content hash. Text files up to
+//! [`SnapshotConfig::max_text_bytes`] also keep their content, with secrets
+//! masked, so they can be diffed. Binary files, larger files and files
+//! matching a sensitive file pattern are only reported as changed.
+
+use crate::detector::{Detector, SensitiveFileDetector};
+use crate::error::{CoreError, StorageError};
+use crate::sanitize::redact_secrets;
+use chrono::{DateTime, Utc};
+use glob::Pattern;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs::File;
+use std::hash::Hasher;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Directories never walked, whatever the ignore globs say
+const ALWAYS_IGNORED: &[&str] = &[".git", ".hg", ".svn"];
+
+/// Lines of context around each hunk of the patch
+const CONTEXT_LINES: usize = 3;
+
+/// Edit distance beyond which a file's changed lines are shown as one
+/// replacement instead of a minimal diff
+const MAX_EDIT_DISTANCE: usize = 4000;
+
+/// What to snapshot
+#[derive(Debug, Clone)]
+pub struct SnapshotConfig {
+    /// Directories (or single files) to record
+    pub roots: Vec<PathBuf>,
+    /// Globs for paths to skip, matched against the full path, the file
+    /// name and each directory name
+    pub ignore: Vec<String>,
+    /// Text files up to this size keep their content for the diff
+    pub max_text_bytes: u64,
+    /// Files recorded at most; further files are left out
+    pub max_files: usize,
+    /// Files whose content is never recorded
+    pub sensitive_detector: SensitiveFileDetector,
+}
+
+impl Default for SnapshotConfig {
+    fn default() -> Self {
+        Self {
+            roots: Vec::new(),
+            ignore: Vec::new(),
+            max_text_bytes: 256 * 1024,
+            max_files: 20_000,
+            sensitive_detector: SensitiveFileDetector::default(),
+        }
+    }
+}
+
+impl SnapshotConfig {
+    /// Create a config for the given roots
+    pub fn new(roots: Vec<PathBuf>) -> Self {
+        Self {
+            roots,
+            ..Default::default()
+        }
+    }
+
+    /// Set the ignore globs
+    pub fn ignore(mut self, globs: Vec<String>) -> Self {
+        self.ignore = globs;
+        self
+    }
+
+    /// Set the largest text file whose content is recorded
+    pub fn max_text_bytes(mut self, bytes: u64) -> Self {
+        self.max_text_bytes = bytes;
+        self
+    }
+
+    /// Set the most files recorded
+    pub fn max_files(mut self, files: usize) -> Self {
+        self.max_files = files;
+        self
+    }
+
+    /// Set the detector for files whose content is never recorded
+    pub fn sensitive_detector(mut self, detector: SensitiveFileDetector) -> Self {
+        self.sensitive_detector = detector;
+        self
+    }
+}
+
+/// One recorded file
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FileState {
+    len: u64,
+    hash: u64,
+    /// Content with secrets masked; `None` for binary, large and sensitive
+    /// files
+    text: Option<String>,
+}
+
+/// The files under a set of roots at one point in time
+#[derive(Debug, Clone)]
+pub struct WorkspaceSnapshot {
+    taken_at: DateTime<Utc>,
+    roots: Vec<PathBuf>,
+    files: BTreeMap<PathBuf, FileState>,
+    truncated: bool,
+}
+
+impl WorkspaceSnapshot {
+    /// Record the files under the configured roots. Unreadable files and
+    /// directories are skipped.
+    pub fn capture(config: &SnapshotConfig) -> Self {
+        let ignore: Vec<Pattern> = config
+            .ignore
+            .iter()
+            .filter_map(|glob| Pattern::new(glob).ok())
+            .collect();
+        let mut snapshot = Self {
+            taken_at: Utc::now(),
+            roots: config.roots.clone(),
+            files: BTreeMap::new(),
+            truncated: false,
+        };
+
+        let mut pending: Vec<PathBuf> = config.roots.iter().rev().cloned().collect();
+        while let Some(path) = pending.pop() {
+            let Ok(metadata) = std::fs::symlink_metadata(&path) else {
+                continue;
+            };
+            if is_ignored(&path, &ignore) {
+                continue;
+            }
+            if metadata.is_dir() {
+                let Ok(entries) = std::fs::read_dir(&path) else {
+                    continue;
+                };
+                let mut children: Vec<PathBuf> = entries
+                    .filter_map(|entry| entry.ok().map(|e| e.path()))
+                    .collect();
+                // Walk in name order, so a capped snapshot covers the same
+                // files each time
+                children.sort_unstable_by(|a, b| b.cmp(a));
+                pending.extend(children);
+            } else if metadata.is_file() {
+                if snapshot.files.len() >= config.max_files {
+                    snapshot.truncated = true;
+                    break;
+                }
+                if let Some(state) = read_file(&path, metadata.len(), config) {
+                    snapshot.files.insert(path, state);
+                }
+            }
+        }
+        snapshot
+    }
+
+    /// When the snapshot was taken
+    pub fn taken_at(&self) -> DateTime<Utc> {
+        self.taken_at
+    }
+
+    /// Number of files recorded
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Whether no files were recorded
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Whether files were left out because of [`SnapshotConfig::max_files`]
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// What changed between this snapshot and a later one of the same roots
+    pub fn changes(&self, after: &WorkspaceSnapshot) -> WorkspaceChanges {
+        let mut changes = WorkspaceChanges {
+            started: self.taken_at,
+            ended: after.taken_at,
+            roots: self.roots.clone(),
+            truncated: self.truncated || after.truncated,
+            ..Default::default()
+        };
+
+        let mut paths: Vec<&PathBuf> = self.files.keys().chain(after.files.keys()).collect();
+        paths.sort();
+        paths.dedup();
+        for path in paths {
+            let before = self.files.get(path);
+            let now = after.files.get(path);
+            let label = self.label(path);
+            match (before, now) {
+                (None, Some(now)) => {
+                    changes.added.push(path.clone());
+                    file_patch(&mut changes.patch, &label, None, Some(now));
+                }
+                (Some(before), None) => {
+                    changes.deleted.push(path.clone());
+                    file_patch(&mut changes.patch, &label, Some(before), None);
+                }
+                (Some(before), Some(now)) if before.len != now.len || before.hash != now.hash => {
+                    if before.text.is_some() && now.text.is_some() {
+                        changes.modified.push(path.clone());
+                    } else {
+                        changes.binary_changed.push(path.clone());
+                    }
+                    file_patch(&mut changes.patch, &label, Some(before), Some(now));
+                }
+                _ => {}
+            }
+        }
+        changes
+    }
+
+    /// Name of `path` in the patch: relative to its root, and prefixed with
+    /// the root's name if there are several roots
+    fn label(&self, path: &Path) -> String {
+        let Some(root) = self.roots.iter().find(|root| path.starts_with(root)) else {
+            return path.to_string_lossy().into_owned();
+        };
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        let label = if relative.as_os_str().is_empty() {
+            // A root that is a file itself
+            PathBuf::from(root.file_name().unwrap_or(root.as_os_str()))
+        } else if self.roots.len() > 1 {
+            root.file_name().map_or_else(
+                || relative.to_path_buf(),
+                |name| Path::new(name).join(relative),
+            )
+        } else {
+            relative.to_path_buf()
+        };
+        label.to_string_lossy().into_owned()
+    }
+}
+
+fn is_ignored(path: &Path, ignore: &[Pattern]) -> bool {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy())
+        .unwrap_or_default();
+    if ALWAYS_IGNORED.contains(&name.as_ref()) {
+        return true;
+    }
+    ignore.iter().any(|pattern| {
+        pattern.matches_path(path)
+            || path
+                .components()
+                .any(|c| pattern.matches(&c.as_os_str().to_string_lossy()))
+    })
+}
+
+fn read_file(path: &Path, len: u64, config: &SnapshotConfig) -> Option<FileState> {
+    let mut file = File::open(path).ok()?;
+    let mut hasher = DefaultHasher::new();
+
+    if len > config.max_text_bytes {
+        let mut buf = [0u8; 64 * 1024];
+        let mut total = 0;
+        loop {
+            let n = file.read(&mut buf).ok()?;
+            if n == 0 {
+                break;
+            }
+            hasher.write(&buf[..n]);
+            total += n as u64;
+        }
+        return Some(FileState {
+            len: total,
+            hash: hasher.finish(),
+            text: None,
+        });
+    }
+
+    let mut content = Vec::with_capacity(len as usize);
+    file.read_to_end(&mut content).ok()?;
+    hasher.write(&content);
+    let text =
+        if config.sensitive_detector.is_sensitive(&path.to_path_buf()) || content.contains(&0) {
+            None
+        } else {
+            String::from_utf8(content.clone())
+                .ok()
+                .map(|text| redact_secrets(&text).into_owned())
+        };
+    Some(FileState {
+        len: content.len() as u64,
+        hash: hasher.finish(),
+        text,
+    })
+}
+
+/// Append the patch of one file. A missing side is an added or deleted file.
+fn file_patch(
+    out: &mut String,
+    label: &str,
+    before: Option<&FileState>,
+    after: Option<&FileState>,
+) {
+    let old_name = before.map_or_else(|| "/dev/null".to_string(), |_| format!("a/{}", label));
+    let new_name = after.map_or_else(|| "/dev/null".to_string(), |_| format!("b/{}", label));
+
+    let old_text = before.map(|f| f.text.as_deref());
+    let new_text = after.map(|f| f.text.as_deref());
+    let (old_text, new_text) = match (old_text, new_text) {
+        (Some(None), _) | (_, Some(None)) => {
+            let _ = writeln!(out, "Binary files {} and {} differ", old_name, new_name);
+            return;
+        }
+        (old, new) => (old.flatten().unwrap_or(""), new.flatten().unwrap_or("")),
+    };
+
+    let hunks = unified_hunks(old_text, new_text);
+    if hunks.is_empty() {
+        return;
+    }
+    let _ = writeln!(out, "--- {}", old_name);
+    let _ = writeln!(out, "+++ {}", new_name);
+    out.push_str(&hunks);
+}
+
+/// One line of the edit script between two texts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Edit {
+    /// Unchanged line: index in the old and in the new text
+    Equal(usize, usize),
+    /// Line of the old text removed
+    Delete(usize),
+    /// Line of the new text inserted
+    Insert(usize),
+}
+
+/// Hunks of a unified diff between two texts, without the file headers
+fn unified_hunks(old: &str, new: &str) -> String {
+    let a: Vec<&str> = old.split_inclusive('\n').collect();
+    let b: Vec<&str> = new.split_inclusive('\n').collect();
+    let edits = diff_lines(&a, &b);
+
+    let mut out = String::new();
+    let changed: Vec<usize> = edits
+        .iter()
+        .enumerate()
+        .filter(|(_, edit)| !matches!(edit, Edit::Equal(..)))
+        .map(|(i, _)| i)
+        .collect();
+    let mut next = 0;
+    while next < changed.len() {
+        // Changes closer than twice the context share a hunk
+        let first = changed[next];
+        let mut last = first;
+        next += 1;
+        while next < changed.len() && changed[next] - last <= 2 * CONTEXT_LINES {
+            last = changed[next];
+            next += 1;
+        }
+        let start = first.saturating_sub(CONTEXT_LINES);
+        let end = (last + CONTEXT_LINES + 1).min(edits.len());
+        write_hunk(&mut out, &edits[start..end], &a, &b, &edits[..start]);
+    }
+    out
+}
+
+fn write_hunk(out: &mut String, hunk: &[Edit], a: &[&str], b: &[&str], before: &[Edit]) {
+    // Lines of each text preceding the hunk
+    let old_before = before
+        .iter()
+        .filter(|e| !matches!(e, Edit::Insert(_)))
+        .count();
+    let new_before = before
+        .iter()
+        .filter(|e| !matches!(e, Edit::Delete(_)))
+        .count();
+    let old_count = hunk
+        .iter()
+        .filter(|e| !matches!(e, Edit::Insert(_)))
+        .count();
+    let new_count = hunk
+        .iter()
+        .filter(|e| !matches!(e, Edit::Delete(_)))
+        .count();
+    // An empty range names the line before it
+    let old_start = if old_count == 0 {
+        old_before
+    } else {
+        old_before + 1
+    };
+    let new_start = if new_count == 0 {
+        new_before
+    } else {
+        new_before + 1
+    };
+    let _ = writeln!(
+        out,
+        "@@ -{},{} +{},{} @@",
+        old_start, old_count, new_start, new_count
+    );
+
+    for edit in hunk {
+        let (prefix, line) = match *edit {
+            Edit::Equal(i, _) => (' ', a[i]),
+            Edit::Delete(i) => ('-', a[i]),
+            Edit::Insert(j) => ('+', b[j]),
+        };
+        out.push(prefix);
+        out.push_str(line);
+        if !line.ends_with('\n') {
+            out.push_str("\n\\ No newline at end of file\n");
+        }
+    }
+}
+
+/// Line edit script turning `a` into `b`
+///
+/// Myers' algorithm on what remains after the common prefix and suffix. Past
+/// [`MAX_EDIT_DISTANCE`] the differing middle is replaced as a whole.
+fn diff_lines(a: &[&str], b: &[&str]) -> Vec<Edit> {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (a_mid, b_mid) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+
+    let mut edits: Vec<Edit> = (0..prefix).map(|i| Edit::Equal(i, i)).collect();
+    match myers(a_mid, b_mid) {
+        Some(middle) => edits.extend(middle.into_iter().map(|edit| match edit {
+            Edit::Equal(i, j) => Edit::Equal(i + prefix, j + prefix),
+            Edit::Delete(i) => Edit::Delete(i + prefix),
+            Edit::Insert(j) => Edit::Insert(j + prefix),
+        })),
+        None => {
+            edits.extend((0..a_mid.len()).map(|i| Edit::Delete(i + prefix)));
+            edits.extend((0..b_mid.len()).map(|j| Edit::Insert(j + prefix)));
+        }
+    }
+    edits.extend((0..suffix).map(|i| Edit::Equal(a.len() - suffix + i, b.len() - suffix + i)));
+    edits
+}
+
+/// Shortest edit script, or `None` if it is longer than
+/// [`MAX_EDIT_DISTANCE`]
+fn myers(a: &[&str], b: &[&str]) -> Option<Vec<Edit>> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max = (a.len() + b.len()).min(MAX_EDIT_DISTANCE) as isize;
+    let offset = max + 1;
+    // Furthest x reached on each diagonal k = x - y
+    let mut v = vec![0isize; 2 * offset as usize + 1];
+    // v around the diagonals in reach before each step, for the backtrack
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+
+    for d in 0..=max {
+        trace.push(v[(offset - d - 1) as usize..=(offset + d + 1) as usize].to_vec());
+        for k in (-d..=d).step_by(2) {
+            let idx = (offset + k) as usize;
+            let mut x = if k == -d || (k != d && v[idx - 1] < v[idx + 1]) {
+                v[idx + 1]
+            } else {
+                v[idx - 1] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[idx] = x;
+            if x >= n && y >= m {
+                return Some(backtrack(&trace, n, m));
+            }
+        }
+    }
+    None
+}
+
+fn backtrack(trace: &[Vec<isize>], n: isize, m: isize) -> Vec<Edit> {
+    let mut edits = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let at = |k: isize| v[(k + d + 1) as usize];
+        let k = x - y;
+        let prev_k = if k == -d || (k != d && at(k - 1) < at(k + 1)) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let prev_x = at(prev_k);
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y {
+            x -= 1;
+            y -= 1;
+            edits.push(Edit::Equal(x as usize, y as usize));
+        }
+        if d > 0 {
+            if x == prev_x {
+                edits.push(Edit::Insert(prev_y as usize));
+            } else {
+                edits.push(Edit::Delete(prev_x as usize));
+            }
+        }
+        x = prev_x;
+        y = prev_y;
+    }
+    edits.reverse();
+    edits
+}
+
+/// What changed in the workspace over a session
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkspaceChanges {
+    pub started: DateTime<Utc>,
+    pub ended: DateTime<Utc>,
+    /// Paths that were snapshotted
+    pub roots: Vec<PathBuf>,
+    pub added: Vec<PathBuf>,
+    pub deleted: Vec<PathBuf>,
+    /// Text files changed, shown in the patch
+    pub modified: Vec<PathBuf>,
+    /// Changed files whose content was not recorded: binary, larger than
+    /// the text limit, or sensitive
+    pub binary_changed: Vec<PathBuf>,
+    /// Some files were left out of the snapshots, so changes may be missing
+    #[serde(default)]
+    pub truncated: bool,
+    /// Unified diff of the changes; stored in its own file
+    #[serde(skip)]
+    pub patch: String,
+}
+
+impl WorkspaceChanges {
+    /// Whether nothing changed
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.deleted.is_empty()
+            && self.modified.is_empty()
+            && self.binary_changed.is_empty()
+    }
+
+    /// Files the changes of a session are stored in, next to its log: the
+    /// change list and the patch
+    pub fn artifact_paths(log_dir: &Path, session_id: &str) -> (PathBuf, PathBuf) {
+        (
+            log_dir.join(format!("session-{}.changes.json", session_id)),
+            log_dir.join(format!("session-{}.patch", session_id)),
+        )
+    }
+
+    /// Store the changes of a session next to its log
+    pub fn save(&self, log_dir: &Path, session_id: &str) -> Result<(), CoreError> {
+        let (list_path, patch_path) = Self::artifact_paths(log_dir, session_id);
+        let list = serde_json::to_string_pretty(self).map_err(StorageError::Serialize)?;
+        std::fs::write(&list_path, list).map_err(StorageError::Write)?;
+        std::fs::write(&patch_path, &self.patch).map_err(StorageError::Write)?;
+        Ok(())
+    }
+
+    /// Read back the changes stored for a session
+    pub fn load(log_dir: &Path, session_id: &str) -> Result<Self, CoreError> {
+        let (list_path, patch_path) = Self::artifact_paths(log_dir, session_id);
+        let list = std::fs::read_to_string(&list_path).map_err(|e| StorageError::OpenFile {
+            path: list_path.clone(),
+            source: e,
+        })?;
+        let mut changes: Self = serde_json::from_str(&list).map_err(StorageError::Serialize)?;
+        changes.patch = std::fs::read_to_string(&patch_path).unwrap_or_default();
+        Ok(changes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(dir: &Path, name: &str, content: &[u8]) {
+        let path = dir.join(name);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_unified_hunks() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n";
+        let new = "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\nk\n";
+        assert_eq!(
+            unified_hunks(old, new),
+            "@@ -1,5 +1,5 @@\n a\n-b\n+B\n c\n d\n e\n\
+             @@ -8,3 +8,4 @@\n h\n i\n j\n+k\n"
+        );
+    }
+
+    #[test]
+    fn test_unified_hunks_new_and_deleted_file() {
+        assert_eq!(unified_hunks("", "x\ny\n"), "@@ -0,0 +1,2 @@\n+x\n+y\n");
+        assert_eq!(unified_hunks("x\n", ""), "@@ -1,1 +0,0 @@\n-x\n");
+        assert_eq!(unified_hunks("same\n", "same\n"), "");
+    }
+
+    #[test]
+    fn test_unified_hunks_missing_newline() {
+        assert_eq!(
+            unified_hunks("a\nb", "a\nb\n"),
+            "@@ -1,2 +1,2 @@\n a\n-b\n\\ No newline at end of file\n+b\n"
+        );
+    }
+
+    #[test]
+    fn test_diff_lines_is_minimal() {
+        let a = ["a\n", "b\n", "c\n", "a\n", "b\n", "b\n", "a\n"];
+        let b = ["c\n", "b\n", "a\n", "b\n", "a\n", "c\n"];
+        let edits = diff_lines(&a, &b);
+        let changes = edits
+            .iter()
+            .filter(|e| !matches!(e, Edit::Equal(..)))
+            .count();
+        assert_eq!(changes, 5);
+
+        // Applying the script to `a` gives `b`
+        let rebuilt: Vec<&str> = edits
+            .iter()
+            .filter_map(|edit| match *edit {
+                Edit::Equal(i, _) => Some(a[i]),
+                Edit::Insert(j) => Some(b[j]),
+                Edit::Delete(_) => None,
+            })
+            .collect();
+        assert_eq!(rebuilt, b);
+    }
+
+    #[test]
+    fn test_snapshot_changes() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path().to_path_buf();
+        write(
+            &root,
+            "src/main.rs",
+            b"fn main() {\n    println!(\"hi\");\n}\n",
+        );
+        write(&root, "README.md", b"# Demo\n");
+        write(&root, "logo.png", &[0x89, b'P', b'N', b'G', 0, 1, 2]);
+        write(&root, "old.txt", b"bye\n");
+        write(&root, ".git/HEAD", b"ref: refs/heads/main\n");
+        let config = SnapshotConfig::new(vec![root.clone()]);
+
+        let before = WorkspaceSnapshot::capture(&config);
+        assert_eq!(before.len(), 4);
+
+        write(
+            &root,
+            "src/main.rs",
+            b"fn main() {\n    println!(\"hello\");\n}\n",
+        );
+        write(&root, "src/lib.rs", b"pub fn f() {}\n");
+        write(&root, "logo.png", &[0x89, b'P', b'N', b'G', 0, 9]);
+        std::fs::remove_file(root.join("old.txt")).unwrap();
+        write(&root, ".git/HEAD", b"ref: refs/heads/other\n");
+
+        let changes = before.changes(&WorkspaceSnapshot::capture(&config));
+        assert_eq!(changes.added, vec![root.join("src/lib.rs")]);
+        assert_eq!(changes.deleted, vec![root.join("old.txt")]);
+        assert_eq!(changes.modified, vec![root.join("src/main.rs")]);
+        assert_eq!(changes.binary_changed, vec![root.join("logo.png")]);
+        assert!(changes
+            .patch
+            .contains("Binary files a/logo.png and b/logo.png differ\n"));
+        assert!(changes
+            .patch
+            .contains("--- /dev/null\n+++ b/src/lib.rs\n@@ -0,0 +1,1 @@\n+pub fn f() {}\n"));
+        assert!(changes
+            .patch
+            .contains("--- a/old.txt\n+++ /dev/null\n@@ -1,1 +0,0 @@\n-bye\n"));
+        assert!(changes.patch.contains(
+            "--- a/src/main.rs\n+++ b/src/main.rs\n@@ -1,3 +1,3 @@\n fn main() {\n-    println!(\"hi\");\n+    println!(\"hello\");\n }\n"
+        ));
+        assert!(!changes.patch.contains("README"));
+    }
+
+    #[test]
+    fn test_snapshot_keeps_secrets_out_of_patch() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path().to_path_buf();
+        write(&root, ".env", b"API_KEY=one\n");
+        write(&root, "notes.txt", b"hello\n");
+        let config = SnapshotConfig::new(vec![root.clone()]);
+        let before = WorkspaceSnapshot::capture(&config);
+
+        write(&root, ".env", b"API_KEY=two\n");
+        write(
+            &root,
+            "notes.txt",
+            b"hello\naws_secret_access_key = wJalrXUtnFEMI/K7MDENG/bPxRfiCYEXAMPLEKEY\n",
+        );
+        let changes = before.changes(&WorkspaceSnapshot::capture(&config));
+
+        assert_eq!(changes.binary_changed, vec![root.join(".env")]);
+        assert!(!changes.patch.contains("API_KEY"));
+        assert!(!changes.patch.contains("wJalrXUtnFEMI"));
+        assert_eq!(changes.modified, vec![root.join("notes.txt")]);
+    }
+
+    #[test]
+    fn test_snapshot_ignore_and_limits() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path().to_path_buf();
+        write(&root, "node_modules/pkg/index.js", b"x\n");
+        write(&root, "big.txt", &[b'a'; 100]);
+        write(&root, "small.txt", b"a\n");
+        let config = SnapshotConfig::new(vec![root.clone()])
+            .ignore(vec!["node_modules".to_string()])
+            .max_text_bytes(10);
+        let before = WorkspaceSnapshot::capture(&config);
+        assert_eq!(before.len(), 2);
+
+        write(&root, "big.txt", &[b'b'; 100]);
+        let changes = before.changes(&WorkspaceSnapshot::capture(&config));
+        assert_eq!(changes.binary_changed, vec![root.join("big.txt")]);
+
+        let capped = WorkspaceSnapshot::capture(&config.max_files(1));
+        assert_eq!(capped.len(), 1);
+        assert!(capped.is_truncated());
+    }
+
+    #[test]
+    fn test_labels_with_several_roots() {
+        let dir = TempDir::new().unwrap();
+        let (first, second) = (dir.path().join("app"), dir.path().join("lib"));
+        write(&first, "a.txt", b"1\n");
+        write(&second, "a.txt", b"1\n");
+        let config = SnapshotConfig::new(vec![first.clone(), second.clone()]);
+        let before = WorkspaceSnapshot::capture(&config);
+
+        write(&second, "a.txt", b"2\n");
+        let changes = before.changes(&WorkspaceSnapshot::capture(&config));
+        assert_eq!(changes.modified, vec![second.join("a.txt")]);
+        assert!(changes
+            .patch
+            .starts_with("--- a/lib/a.txt\n+++ b/lib/a.txt\n"));
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = TempDir::new().unwrap();
+        let changes = WorkspaceChanges {
+            added: vec![PathBuf::from("/w/new.txt")],
+            patch: "--- /dev/null\n+++ b/new.txt\n@@ -0,0 +1,1 @@\n+x\n".to_string(),
+            ..Default::default()
+        };
+        changes.save(dir.path(), "abc").unwrap();
+
+        let (list, patch) = WorkspaceChanges::artifact_paths(dir.path(), "abc");
+        assert!(list.ends_with("session-abc.changes.json"));
+        assert!(patch.ends_with("session-abc.patch"));
+        assert_eq!(WorkspaceChanges::load(dir.path(), "abc").unwrap(), changes);
+        assert!(WorkspaceChanges::load(dir.path(), "missing").is_err());
+    }
+}
@@ -0,0 +1,365 @@
+//! Per-socket TCP traffic counters
+//!
+//! `/proc/net/tcp` lists sockets but not how much data moved over them. On
+//! Linux the `NETLINK_SOCK_DIAG` interface dumps every TCP socket in the
+//! network namespace with its `struct tcp_info`, whose `tcpi_bytes_acked`
+//! and `tcpi_bytes_received` count payload bytes sent (and acknowledged by
+//! the peer) and received. Dumping needs no privileges. Sockets are keyed
+//! by inode, which is what `/proc/<pid>/fd` links to.
+
+#[cfg(any(target_os = "linux", test))]
+use std::collections::HashMap;
+#[cfg(target_os = "linux")]
+use std::io;
+#[cfg(target_os = "linux")]
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+/// Payload bytes moved over a socket so far
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct ByteCounts {
+    /// Bytes sent and acknowledged by the peer (`tcpi_bytes_acked`). The
+    /// kernel counts sequence space, so the SYN of an outbound connection
+    /// adds one. `tcpi_bytes_sent` isn't used: it includes retransmissions,
+    /// which would inflate uploads over a lossy link.
+    pub sent: u64,
+    /// Bytes received
+    pub received: u64,
+}
+
+// Layout of sock_diag messages, from <linux/netlink.h>, <linux/sock_diag.h>,
+// <linux/inet_diag.h> and <linux/tcp.h>
+#[cfg(any(target_os = "linux", test))]
+const NLMSG_HDRLEN: usize = 16;
+#[cfg(any(target_os = "linux", test))]
+const NLMSG_ERROR: u16 = 2;
+#[cfg(any(target_os = "linux", test))]
+const NLMSG_DONE: u16 = 3;
+#[cfg(target_os = "linux")]
+const SOCK_DIAG_BY_FAMILY: u16 = 20;
+#[cfg(target_os = "linux")]
+const NLM_F_REQUEST: u16 = 0x1;
+#[cfg(target_os = "linux")]
+const NLM_F_DUMP: u16 = 0x300;
+/// Attribute carrying `struct tcp_info`
+#[cfg(any(target_os = "linux", test))]
+const INET_DIAG_INFO: u16 = 2;
+/// `struct inet_diag_req_v2`
+#[cfg(target_os = "linux")]
+const INET_DIAG_REQ_LEN: usize = 56;
+/// `struct inet_diag_msg`, followed by attributes
+#[cfg(any(target_os = "linux", test))]
+const INET_DIAG_MSG_LEN: usize = 72;
+/// Offset of `idiag_inode` within `struct inet_diag_msg`
+#[cfg(any(target_os = "linux", test))]
+const INET_DIAG_INODE: usize = 68;
+/// Offsets of `tcpi_bytes_acked` and `tcpi_bytes_received` within
+/// `struct tcp_info`; kernels before 4.2 send a shorter struct
+#[cfg(any(target_os = "linux", test))]
+const TCPI_BYTES_ACKED: usize = 120;
+#[cfg(any(target_os = "linux", test))]
+const TCPI_BYTES_RECEIVED: usize = 128;
+/// Offset of `tcpi_bytes_sent` (Linux 4.19+), which counts retransmissions
+#[cfg(test)]
+const TCPI_BYTES_SENT: usize = 200;
+/// `TCP_LISTEN` in the kernel's state numbering
+#[cfg(target_os = "linux")]
+const TCP_LISTEN: u32 = 10;
+
+/// Traffic counters of every TCP socket in our network namespace, by inode.
+/// Sockets whose kernel reports no byte counters are left out.
+#[cfg(target_os = "linux")]
+pub(crate) fn tcp_byte_counts() -> io::Result<HashMap<u64, ByteCounts>> {
+    // SAFETY: socket has no memory preconditions
+    let raw = unsafe {
+        libc::socket(
+            libc::AF_NETLINK,
+            libc::SOCK_DGRAM | libc::SOCK_CLOEXEC,
+            libc::NETLINK_SOCK_DIAG,
+        )
+    };
+    if raw < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: `raw` is a freshly opened descriptor owned by nobody else
+    let fd = unsafe { OwnedFd::from_raw_fd(raw) };
+
+    let mut counts = HashMap::new();
+    let mut buf = vec![0u8; 32 * 1024];
+    for family in [libc::AF_INET, libc::AF_INET6] {
+        send_dump_request(&fd, family as u8)?;
+        loop {
+            // SAFETY: `buf` is valid for writes of its length
+            let len = unsafe {
+                libc::recv(
+                    fd.as_raw_fd(),
+                    buf.as_mut_ptr() as *mut libc::c_void,
+                    buf.len(),
+                    0,
+                )
+            };
+            if len < 0 {
+                let err = io::Error::last_os_error();
+                if err.kind() == io::ErrorKind::Interrupted {
+                    continue;
+                }
+                return Err(err);
+            }
+            match parse_diag_messages(&buf[..len as usize], &mut counts) {
+                DumpStatus::More => {}
+                DumpStatus::Done => break,
+                DumpStatus::Error(errno) => return Err(io::Error::from_raw_os_error(errno)),
+            }
+        }
+    }
+    Ok(counts)
+}
+
+/// Ask for all non-listening TCP sockets of `family` with their `tcp_info`
+#[cfg(target_os = "linux")]
+fn send_dump_request(fd: &OwnedFd, family: u8) -> io::Result<()> {
+    let len = NLMSG_HDRLEN + INET_DIAG_REQ_LEN;
+    let mut msg = Vec::with_capacity(len);
+    // nlmsghdr: len, type, flags, seq, pid
+    msg.extend_from_slice(&(len as u32).to_ne_bytes());
+    msg.extend_from_slice(&SOCK_DIAG_BY_FAMILY.to_ne_bytes());
+    msg.extend_from_slice(&(NLM_F_REQUEST | NLM_F_DUMP).to_ne_bytes());
+    msg.extend_from_slice(&0u32.to_ne_bytes());
+    msg.extend_from_slice(&0u32.to_ne_bytes());
+    // inet_diag_req_v2: family, protocol, ext, pad, states, then a zeroed
+    // inet_diag_sockid, which matches every socket in a dump
+    msg.push(family);
+    msg.push(libc::IPPROTO_TCP as u8);
+    msg.push(1 << (INET_DIAG_INFO - 1));
+    msg.push(0);
+    msg.extend_from_slice(&(!(1u32 << TCP_LISTEN)).to_ne_bytes());
+    msg.resize(len, 0);
+
+    // SAFETY: `msg` is valid for reads of its length
+    let sent = unsafe {
+        libc::send(
+            fd.as_raw_fd(),
+            msg.as_ptr() as *const libc::c_void,
+            msg.len(),
+            0,
+        )
+    };
+    if sent < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Whether a dump continues after a datagram
+#[cfg(any(target_os = "linux", test))]
+#[derive(Debug, PartialEq, Eq)]
+enum DumpStatus {
+    More,
+    Done,
+    Error(i32),
+}
+
+/// Collect the counters of each socket in one datagram of a dump
+#[cfg(any(target_os = "linux", test))]
+fn parse_diag_messages(buf: &[u8], counts: &mut HashMap<u64, ByteCounts>) -> DumpStatus {
+    let mut offset = 0;
+    while offset + NLMSG_HDRLEN <= buf.len() {
+        let msg_len = read_u32(buf, offset) as usize;
+        let msg_type = read_u16(buf, offset + 4);
+        if msg_len < NLMSG_HDRLEN || offset + msg_len > buf.len() {
+            break;
+        }
+        let payload = &buf[offset + NLMSG_HDRLEN..offset + msg_len];
+        match msg_type {
+            NLMSG_DONE => return DumpStatus::Done,
+            NLMSG_ERROR => {
+                // nlmsgerr starts with a negative errno
+                let errno = payload
+                    .get(..4)
+                    .map(|b| i32::from_ne_bytes([b[0], b[1], b[2], b[3]]))
+                    .unwrap_or(0);
+                return DumpStatus::Error(-errno);
+            }
+            _ => {
+                if let Some((inode, bytes)) = parse_diag_msg(payload) {
+                    counts.insert(inode, bytes);
+                }
+            }
+        }
+        offset += align4(msg_len);
+    }
+    DumpStatus::More
+}
+
+/// Inode and counters from an `inet_diag_msg` and its attributes
+#[cfg(any(target_os = "linux", test))]
+fn parse_diag_msg(payload: &[u8]) -> Option<(u64, ByteCounts)> {
+    if payload.len() < INET_DIAG_MSG_LEN {
+        return None;
+    }
+    let inode = read_u32(payload, INET_DIAG_INODE) as u64;
+    let mut offset = INET_DIAG_MSG_LEN;
+    while offset + 4 <= payload.len() {
+        let attr_len = read_u16(payload, offset) as usize;
+        let attr_type = read_u16(payload, offset + 2);
+        if attr_len < 4 || offset + attr_len > payload.len() {
+            return None;
+        }
+        if attr_type == INET_DIAG_INFO {
+            let info = &payload[offset + 4..offset + attr_len];
+            if info.len() < TCPI_BYTES_RECEIVED + 8 {
+                return None;
+            }
+            return Some((
+                inode,
+                ByteCounts {
+                    sent: read_u64(info, TCPI_BYTES_ACKED),
+                    received: read_u64(info, TCPI_BYTES_RECEIVED),
+                },
+            ));
+        }
+        offset += align4(attr_len);
+    }
+    None
+}
+
+#[cfg(any(target_os = "linux", test))]
+fn align4(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+#[cfg(any(target_os = "linux", test))]
+fn read_u16(buf: &[u8], offset: usize) -> u16 {
+    u16::from_ne_bytes([buf[offset], buf[offset + 1]])
+}
+
+#[cfg(any(target_os = "linux", test))]
+fn read_u32(buf: &[u8], offset: usize) -> u32 {
+    let mut bytes = [0u8; 4];
+    bytes.copy_from_slice(&buf[offset..offset + 4]);
+    u32::from_ne_bytes(bytes)
+}
+
+#[cfg(any(target_os = "linux", test))]
+fn read_u64(buf: &[u8], offset: usize) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&buf[offset..offset + 8]);
+    u64::from_ne_bytes(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nlmsg(msg_type: u16, payload: &[u8]) -> Vec<u8> {
+        let len = NLMSG_HDRLEN + payload.len();
+        let mut msg = Vec::new();
+        msg.extend_from_slice(&(len as u32).to_ne_bytes());
+        msg.extend_from_slice(&msg_type.to_ne_bytes());
+        msg.extend_from_slice(&[0; 10]);
+        msg.extend_from_slice(payload);
+        msg.resize(align4(len), 0);
+        msg
+    }
+
+    fn diag_msg(inode: u32, info_len: usize, sent: u64, received: u64) -> Vec<u8> {
+        let mut payload = vec![0u8; INET_DIAG_MSG_LEN];
+        payload[INET_DIAG_INODE..INET_DIAG_INODE + 4].copy_from_slice(&inode.to_ne_bytes());
+        // An unrelated attribute first, with padding
+        payload.extend_from_slice(&5u16.to_ne_bytes());
+        payload.extend_from_slice(&1u16.to_ne_bytes());
+        payload.extend_from_slice(&[0; 4]);
+        let mut info = vec![0u8; info_len];
+        if info_len >= TCPI_BYTES_RECEIVED + 8 {
+            info[TCPI_BYTES_ACKED..TCPI_BYTES_ACKED + 8].copy_from_slice(&sent.to_ne_bytes());
+            info[TCPI_BYTES_RECEIVED..TCPI_BYTES_RECEIVED + 8]
+                .copy_from_slice(&received.to_ne_bytes());
+        }
+        payload.extend_from_slice(&((4 + info_len) as u16).to_ne_bytes());
+        payload.extend_from_slice(&INET_DIAG_INFO.to_ne_bytes());
+        payload.extend_from_slice(&info);
+        payload
+    }
+
+    #[test]
+    fn test_parse_diag_messages() {
+        let mut buf = nlmsg(20, &diag_msg(41001, 232, 5000, 120));
+        // Kernels before 4.2 send a tcp_info without byte counters
+        buf.extend(nlmsg(20, &diag_msg(41002, 104, 0, 0)));
+        let mut counts = HashMap::new();
+        assert_eq!(parse_diag_messages(&buf, &mut counts), DumpStatus::More);
+        assert_eq!(
+            counts,
+            HashMap::from([(
+                41001,
+                ByteCounts {
+                    sent: 5000,
+                    received: 120
+                }
+            )])
+        );
+
+        let done = nlmsg(NLMSG_DONE, &[0; 4]);
+        assert_eq!(parse_diag_messages(&done, &mut counts), DumpStatus::Done);
+
+        let error = nlmsg(NLMSG_ERROR, &(-libc::EPERM).to_ne_bytes());
+        assert_eq!(
+            parse_diag_messages(&error, &mut counts),
+            DumpStatus::Error(libc::EPERM)
+        );
+    }
+
+    #[test]
+    fn test_parse_diag_msg_ignores_retransmissions() {
+        let mut payload = diag_msg(41001, 232, 5000, 120);
+        // tcpi_bytes_sent also counts retransmitted segments
+        let info = payload.len() - 232;
+        payload[info + TCPI_BYTES_SENT..info + TCPI_BYTES_SENT + 8]
+            .copy_from_slice(&9000u64.to_ne_bytes());
+        assert_eq!(
+            parse_diag_msg(&payload),
+            Some((
+                41001,
+                ByteCounts {
+                    sent: 5000,
+                    received: 120
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_diag_messages_truncated() {
+        let mut buf = nlmsg(20, &diag_msg(41001, 232, 5000, 120));
+        buf.truncate(buf.len() - 8);
+        let mut counts = HashMap::new();
+        assert_eq!(parse_diag_messages(&buf, &mut counts), DumpStatus::More);
+        assert!(counts.is_empty());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_tcp_byte_counts_of_own_socket() {
+        use std::io::{Read, Write};
+        use std::os::unix::fs::MetadataExt;
+
+        let server = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut client = std::net::TcpStream::connect(server.local_addr().unwrap()).unwrap();
+        let (mut accepted, _) = server.accept().unwrap();
+
+        client.write_all(&[7u8; 3000]).unwrap();
+        let mut buf = [0u8; 3000];
+        accepted.read_exact(&mut buf).unwrap();
+        accepted.write_all(b"ok").unwrap();
+        let mut reply = [0u8; 2];
+        client.read_exact(&mut reply).unwrap();
+
+        // A socket fd's inode is the one /proc/<pid>/fd links to
+        let inode = std::fs::metadata(format!("/proc/self/fd/{}", client.as_raw_fd()))
+            .unwrap()
+            .ino();
+        let counts = tcp_byte_counts().unwrap();
+        let bytes = counts.get(&inode).expect("client socket listed");
+        assert!((3000..=3001).contains(&bytes.sent), "sent {}", bytes.sent);
+        assert_eq!(bytes.received, 2);
+    }
+}
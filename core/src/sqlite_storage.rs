@@ -2,19 +2,41 @@
 //!
 //! Provides a SQLite backend implementing the `EventStorage` trait,
 //! offering structured queries over events alongside the existing JSONL logger.
+//! Commands, paths and hosts are indexed with FTS5 for fast text search.
+//! Events from all sessions share one database, so queries can also span
+//! sessions: grouping by agent, host, command or path, per-agent trends, and
+//! backfilling existing JSONL session logs.
 
+use crate::baseline::SessionActivity;
 use crate::error::{CoreError, StorageError};
-use crate::event::{Event, RiskLevel};
-use crate::storage::EventStorage;
+use crate::event::{Event, EventType, RiskLevel, SessionAction};
+use crate::storage::{open_session_log, EventStorage, SegmentName};
 use chrono::{DateTime, Utc};
-use rusqlite::{params, Connection};
-use std::path::PathBuf;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Schema version stored in `PRAGMA user_version`.
+/// Version 2 added `events.timestamp_ms` and the `events_fts` index.
+/// Version 3 added `sessions.agent_name`.
+const SCHEMA_VERSION: i32 = 3;
+
+/// Agent a session belongs to: the detected agent, else the wrapped process
+const SESSION_AGENT_SQL: &str = "COALESCE(agent_name, process_name)";
+
+/// Maps `risk_level` to a number so MAX() yields the most severe level
+const RISK_RANK_SQL: &str =
+    "CASE risk_level WHEN 'critical' THEN 3 WHEN 'high' THEN 2 WHEN 'medium' THEN 1 ELSE 0 END";
 
 /// SQLite-backed event storage
 pub struct SqliteStorage {
     conn: Connection,
     db_path: PathBuf,
     event_count: usize,
+    session_id: Option<String>,
 }
 
 /// Filters for querying events
@@ -26,6 +48,157 @@ pub struct EventQuery {
     pub start_time: Option<DateTime<Utc>>,
     pub end_time: Option<DateTime<Utc>>,
     pub limit: Option<usize>,
+    /// Number of matching events to skip (for pagination)
+    pub offset: Option<usize>,
+    /// Case-insensitive substring matched against commands, paths and hosts
+    pub text: Option<String>,
+    /// Only events from sessions of this agent (case-insensitive)
+    pub agent: Option<String>,
+}
+
+/// Event counts for one time bucket, as returned by [`SqliteStorage::chart_data`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChartBucket {
+    /// Bucket start (milliseconds since the Unix epoch)
+    pub start_ms: i64,
+    pub total: u32,
+    pub critical: u32,
+    pub high: u32,
+    pub medium: u32,
+    pub low: u32,
+}
+
+/// Dimension for [`SqliteStorage::group_counts`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GroupBy {
+    /// Agent of the session the event belongs to
+    Agent,
+    /// Remote host of network events
+    Host,
+    /// Command name (without arguments) of command events
+    Command,
+    /// Path of file access events
+    Path,
+}
+
+impl GroupBy {
+    /// SQL expression yielding the group key of a row in `events`, or NULL
+    /// for events that don't belong to this dimension
+    fn key_sql(self) -> String {
+        match self {
+            GroupBy::Agent => format!(
+                "(SELECT {SESSION_AGENT_SQL} FROM sessions s WHERE s.session_id = events.session_id)"
+            ),
+            GroupBy::Host => {
+                "CASE WHEN event_type = 'network' THEN json_extract(event_data, '$.host') END"
+                    .to_string()
+            }
+            GroupBy::Command => {
+                "CASE WHEN event_type = 'command' THEN json_extract(event_data, '$.command') END"
+                    .to_string()
+            }
+            GroupBy::Path => {
+                "CASE WHEN event_type = 'file_access' THEN json_extract(event_data, '$.path') END"
+                    .to_string()
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for GroupBy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GroupBy::Agent => write!(f, "agent"),
+            GroupBy::Host => write!(f, "host"),
+            GroupBy::Command => write!(f, "command"),
+            GroupBy::Path => write!(f, "path"),
+        }
+    }
+}
+
+impl FromStr for GroupBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "agent" => Ok(GroupBy::Agent),
+            "host" => Ok(GroupBy::Host),
+            "command" => Ok(GroupBy::Command),
+            "path" => Ok(GroupBy::Path),
+            _ => Err(format!("Invalid group: {}", s)),
+        }
+    }
+}
+
+/// Event counts for one value of a [`GroupBy`] dimension
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GroupCount {
+    /// Agent name, host, command or path
+    pub key: String,
+    pub total: u32,
+    pub critical: u32,
+    pub high: u32,
+    pub medium: u32,
+    pub low: u32,
+    /// Number of distinct sessions with matching events
+    pub sessions: u32,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+}
+
+/// A session with events matching a cross-session query
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SessionMatch {
+    pub session_id: String,
+    pub agent_name: Option<String>,
+    pub process_name: Option<String>,
+    pub start_time: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
+    /// Number of events in the session that matched the query
+    pub matching_events: u32,
+    /// Highest risk level among the matching events
+    pub max_risk: RiskLevel,
+    pub first_match: DateTime<Utc>,
+    pub last_match: DateTime<Utc>,
+}
+
+/// Activity of one agent within one time bucket, as returned by
+/// [`SqliteStorage::agent_trends`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AgentTrend {
+    pub agent: String,
+    /// Bucket start (milliseconds since the Unix epoch)
+    pub start_ms: i64,
+    /// Number of distinct sessions active in the bucket
+    pub sessions: u32,
+    pub total: u32,
+    pub critical: u32,
+    pub high: u32,
+    pub medium: u32,
+    pub low: u32,
+}
+
+/// Outcome of importing a single JSONL session log
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonlImport {
+    pub session_id: String,
+    /// Number of events written to the database
+    pub events: usize,
+    /// The session was already in the database and was left untouched
+    pub skipped: bool,
+}
+
+/// Result of [`SqliteStorage::backfill_from_dir`]
+#[derive(Debug, Default)]
+pub struct BackfillResult {
+    /// Number of session logs imported
+    pub imported: usize,
+    /// Number of session logs already present in the database
+    pub skipped: usize,
+    /// Session logs that could not be read or imported
+    pub failed: Vec<(PathBuf, String)>,
+    /// Total number of events imported
+    pub events: usize,
 }
 
 impl SqliteStorage {
@@ -44,6 +217,7 @@ impl SqliteStorage {
             conn,
             db_path: db_path.clone(),
             event_count: 0,
+            session_id: None,
         };
         storage.init_schema()?;
         Ok(storage)
@@ -57,11 +231,18 @@ impl SqliteStorage {
             conn,
             db_path: PathBuf::from(":memory:"),
             event_count: 0,
+            session_id: None,
         };
         storage.init_schema()?;
         Ok(storage)
     }
 
+    /// Tag events written through [`EventStorage::write_event`] with a session ID
+    pub fn with_session_id(mut self, session_id: impl Into<String>) -> Self {
+        self.session_id = Some(session_id.into());
+        self
+    }
+
     /// Initialize the database schema (tables and indexes).
     fn init_schema(&mut self) -> Result<(), CoreError> {
         self.conn
@@ -87,9 +268,83 @@ impl SqliteStorage {
                     pid INTEGER,
                     start_time TEXT,
                     end_time TEXT
-                );",
+                );
+
+                CREATE VIRTUAL TABLE IF NOT EXISTS events_fts
+                    USING fts5(search_text, tokenize = 'trigram');",
+            )
+            .map_err(StorageError::Sqlite)?;
+        self.migrate()
+    }
+
+    /// Upgrade databases created by older versions to [`SCHEMA_VERSION`].
+    fn migrate(&mut self) -> Result<(), CoreError> {
+        let version: i32 = self
+            .conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .map_err(StorageError::Sqlite)?;
+        if version >= SCHEMA_VERSION {
+            return Ok(());
+        }
+
+        let tx = self.conn.transaction().map_err(StorageError::Sqlite)?;
+
+        let has_timestamp_ms = tx
+            .prepare("SELECT 1 FROM pragma_table_info('events') WHERE name = 'timestamp_ms'")
+            .and_then(|mut stmt| stmt.exists([]))
+            .map_err(StorageError::Sqlite)?;
+        if !has_timestamp_ms {
+            tx.execute_batch(
+                "ALTER TABLE events ADD COLUMN timestamp_ms INTEGER;
+                 UPDATE events SET timestamp_ms =
+                     CAST(ROUND((julianday(timestamp) - 2440587.5) * 86400000) AS INTEGER);",
             )
             .map_err(StorageError::Sqlite)?;
+        }
+        let has_agent_name = tx
+            .prepare("SELECT 1 FROM pragma_table_info('sessions') WHERE name = 'agent_name'")
+            .and_then(|mut stmt| stmt.exists([]))
+            .map_err(StorageError::Sqlite)?;
+        if !has_agent_name {
+            tx.execute_batch("ALTER TABLE sessions ADD COLUMN agent_name TEXT;")
+                .map_err(StorageError::Sqlite)?;
+        }
+        tx.execute_batch(
+            "CREATE INDEX IF NOT EXISTS idx_events_session_time
+                 ON events(session_id, timestamp_ms);
+             CREATE INDEX IF NOT EXISTS idx_events_time_ms ON events(timestamp_ms);",
+        )
+        .map_err(StorageError::Sqlite)?;
+
+        // Index events written before the FTS table existed
+        {
+            let mut select = tx
+                .prepare(
+                    "SELECT rowid, event_data FROM events
+                     WHERE rowid NOT IN (SELECT rowid FROM events_fts)",
+                )
+                .map_err(StorageError::Sqlite)?;
+            let rows: Vec<(i64, String)> = select
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+                .and_then(|rows| rows.collect())
+                .map_err(StorageError::Sqlite)?;
+            for (rowid, json) in rows {
+                let Ok(event) = serde_json::from_str::<Event>(&json) else {
+                    continue;
+                };
+                if let Some(text) = Self::search_text(&event) {
+                    tx.execute(
+                        "INSERT INTO events_fts (rowid, search_text) VALUES (?1, ?2)",
+                        params![rowid, text],
+                    )
+                    .map_err(StorageError::Sqlite)?;
+                }
+            }
+        }
+
+        tx.pragma_update(None, "user_version", SCHEMA_VERSION)
+            .map_err(StorageError::Sqlite)?;
+        tx.commit().map_err(StorageError::Sqlite)?;
         Ok(())
     }
 
@@ -100,13 +355,25 @@ impl SqliteStorage {
         process: &str,
         pid: u32,
         start_time: &DateTime<Utc>,
+        agent_name: Option<&str>,
     ) -> Result<(), CoreError> {
-        self.conn
-            .execute(
-                "INSERT OR REPLACE INTO sessions (session_id, process_name, pid, start_time) VALUES (?1, ?2, ?3, ?4)",
-                params![session_id, process, pid, start_time.to_rfc3339()],
-            )
-            .map_err(StorageError::Sqlite)?;
+        Self::insert_session(&self.conn, session_id, process, pid, start_time, agent_name)
+    }
+
+    fn insert_session(
+        conn: &Connection,
+        session_id: &str,
+        process: &str,
+        pid: u32,
+        start_time: &DateTime<Utc>,
+        agent_name: Option<&str>,
+    ) -> Result<(), CoreError> {
+        conn.execute(
+            "INSERT OR REPLACE INTO sessions (session_id, process_name, pid, start_time, agent_name)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![session_id, process, pid, start_time.to_rfc3339(), agent_name],
+        )
+        .map_err(StorageError::Sqlite)?;
         Ok(())
     }
 
@@ -125,56 +392,623 @@ impl SqliteStorage {
         Ok(())
     }
 
+    /// Check whether any session record or event exists for `session_id`.
+    pub fn has_session(&self, session_id: &str) -> Result<bool, CoreError> {
+        let found = self
+            .conn
+            .query_row(
+                "SELECT 1 FROM sessions WHERE session_id = ?1
+                 UNION ALL SELECT 1 FROM events WHERE session_id = ?1
+                 LIMIT 1",
+                params![session_id],
+                |_| Ok(()),
+            )
+            .optional()
+            .map_err(StorageError::Sqlite)?;
+        Ok(found.is_some())
+    }
+
     /// Query events with optional filters.
     pub fn query_events(&self, query: &EventQuery) -> Result<Vec<Event>, CoreError> {
-        let mut sql = String::from("SELECT event_data FROM events WHERE 1=1");
-        let mut param_values: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
+        let (filter, param_values) = Self::filter_clause(query);
+        let mut sql = format!(
+            "SELECT event_data FROM events WHERE {filter} ORDER BY timestamp_ms ASC, rowid ASC"
+        );
 
-        if let Some(ref session_id) = query.session_id {
-            sql.push_str(" AND session_id = ?");
-            param_values.push(Box::new(session_id.clone()));
-        }
-        if let Some(ref risk_level) = query.risk_level {
-            sql.push_str(" AND risk_level = ?");
-            param_values.push(Box::new(risk_level.to_string()));
+        if query.limit.is_some() || query.offset.is_some() {
+            let limit = query.limit.map_or(-1, |l| l as i64);
+            let offset = query.offset.unwrap_or(0);
+            sql.push_str(&format!(" LIMIT {limit} OFFSET {offset}"));
         }
-        if let Some(ref event_type) = query.event_type {
-            sql.push_str(" AND event_type = ?");
-            param_values.push(Box::new(event_type.clone()));
+
+        let params_refs: Vec<&dyn rusqlite::types::ToSql> =
+            param_values.iter().map(|p| p.as_ref()).collect();
+
+        let mut stmt = self.conn.prepare(&sql).map_err(StorageError::Sqlite)?;
+        let rows = stmt
+            .query_map(params_refs.as_slice(), |row| {
+                let json_str: String = row.get(0)?;
+                Ok(json_str)
+            })
+            .map_err(StorageError::Sqlite)?;
+
+        let mut events = Vec::new();
+        for row in rows {
+            let json_str = row.map_err(StorageError::Sqlite)?;
+            let event: Event = serde_json::from_str(&json_str).map_err(StorageError::Serialize)?;
+            events.push(event);
         }
-        if let Some(ref start_time) = query.start_time {
-            sql.push_str(" AND timestamp >= ?");
-            param_values.push(Box::new(start_time.to_rfc3339()));
+        Ok(events)
+    }
+
+    /// Count events matching the filters. `limit` and `offset` are ignored.
+    pub fn count_events(&self, query: &EventQuery) -> Result<usize, CoreError> {
+        let (filter, param_values) = Self::filter_clause(query);
+        let sql = format!("SELECT COUNT(*) FROM events WHERE {filter}");
+        let params_refs: Vec<&dyn rusqlite::types::ToSql> =
+            param_values.iter().map(|p| p.as_ref()).collect();
+
+        let count: i64 = self
+            .conn
+            .query_row(&sql, params_refs.as_slice(), |row| row.get(0))
+            .map_err(StorageError::Sqlite)?;
+        Ok(count as usize)
+    }
+
+    /// Count matching events per risk level in fixed-size time buckets.
+    ///
+    /// Buckets are aligned to multiples of `bucket_ms` since the epoch and
+    /// returned in ascending order; empty buckets are omitted.
+    pub fn chart_data(
+        &self,
+        query: &EventQuery,
+        bucket_ms: i64,
+    ) -> Result<Vec<ChartBucket>, CoreError> {
+        let bucket_ms = bucket_ms.max(1);
+        let (filter, param_values) = Self::filter_clause(query);
+        let sql = format!(
+            "SELECT (timestamp_ms / {bucket_ms}) * {bucket_ms} AS bucket,
+                    COUNT(*),
+                    SUM(risk_level = 'critical'),
+                    SUM(risk_level = 'high'),
+                    SUM(risk_level = 'medium'),
+                    SUM(risk_level = 'low')
+             FROM events WHERE {filter}
+             GROUP BY bucket ORDER BY bucket ASC"
+        );
+        let params_refs: Vec<&dyn rusqlite::types::ToSql> =
+            param_values.iter().map(|p| p.as_ref()).collect();
+
+        let mut stmt = self.conn.prepare(&sql).map_err(StorageError::Sqlite)?;
+        let rows = stmt
+            .query_map(params_refs.as_slice(), |row| {
+                Ok(ChartBucket {
+                    start_ms: row.get(0)?,
+                    total: row.get(1)?,
+                    critical: row.get(2)?,
+                    high: row.get(3)?,
+                    medium: row.get(4)?,
+                    low: row.get(5)?,
+                })
+            })
+            .map_err(StorageError::Sqlite)?;
+
+        let mut buckets = Vec::new();
+        for row in rows {
+            buckets.push(row.map_err(StorageError::Sqlite)?);
         }
-        if let Some(ref end_time) = query.end_time {
-            sql.push_str(" AND timestamp <= ?");
-            param_values.push(Box::new(end_time.to_rfc3339()));
+        Ok(buckets)
+    }
+
+    /// Sessions containing events that match the filters, most recent first.
+    /// `limit` caps the number of sessions; `offset` is ignored.
+    pub fn sessions_matching(&self, query: &EventQuery) -> Result<Vec<SessionMatch>, CoreError> {
+        let (filter, param_values) = Self::filter_clause(query);
+        let mut sql = format!(
+            "SELECT m.session_id, s.agent_name, s.process_name, s.start_time, s.end_time,
+                    m.total, m.max_rank, m.first_ms, m.last_ms
+             FROM (SELECT session_id, COUNT(*) AS total, MAX({RISK_RANK_SQL}) AS max_rank,
+                          MIN(timestamp_ms) AS first_ms, MAX(timestamp_ms) AS last_ms
+                   FROM events WHERE {filter} AND session_id IS NOT NULL
+                   GROUP BY session_id) m
+             LEFT JOIN sessions s ON s.session_id = m.session_id
+             ORDER BY m.last_ms DESC"
+        );
+        if let Some(limit) = query.limit {
+            sql.push_str(&format!(" LIMIT {limit}"));
         }
+        let params_refs: Vec<&dyn rusqlite::types::ToSql> =
+            param_values.iter().map(|p| p.as_ref()).collect();
+
+        let mut stmt = self.conn.prepare(&sql).map_err(StorageError::Sqlite)?;
+        let rows = stmt
+            .query_map(params_refs.as_slice(), |row| {
+                let start: Option<String> = row.get(3)?;
+                let end: Option<String> = row.get(4)?;
+                Ok(SessionMatch {
+                    session_id: row.get(0)?,
+                    agent_name: row.get(1)?,
+                    process_name: row.get(2)?,
+                    start_time: start.as_deref().and_then(parse_rfc3339),
+                    end_time: end.as_deref().and_then(parse_rfc3339),
+                    matching_events: row.get(5)?,
+                    max_risk: risk_from_rank(row.get(6)?),
+                    first_match: from_millis(row.get(7)?),
+                    last_match: from_millis(row.get(8)?),
+                })
+            })
+            .map_err(StorageError::Sqlite)?;
 
-        sql.push_str(" ORDER BY timestamp ASC");
+        let mut sessions = Vec::new();
+        for row in rows {
+            sessions.push(row.map_err(StorageError::Sqlite)?);
+        }
+        Ok(sessions)
+    }
 
+    /// Count matching events per agent, host, command or path, most frequent
+    /// first. Events without a value for the dimension are left out.
+    /// `limit` caps the number of groups; `offset` is ignored.
+    pub fn group_counts(
+        &self,
+        query: &EventQuery,
+        group_by: GroupBy,
+    ) -> Result<Vec<GroupCount>, CoreError> {
+        let (filter, param_values) = Self::filter_clause(query);
+        let key = group_by.key_sql();
+        let mut sql = format!(
+            "SELECT key, COUNT(*) AS total,
+                    SUM(risk_level = 'critical'),
+                    SUM(risk_level = 'high'),
+                    SUM(risk_level = 'medium'),
+                    SUM(risk_level = 'low'),
+                    COUNT(DISTINCT session_id),
+                    MIN(timestamp_ms), MAX(timestamp_ms)
+             FROM (SELECT {key} AS key, session_id, risk_level, timestamp_ms
+                   FROM events WHERE {filter})
+             WHERE key IS NOT NULL
+             GROUP BY key ORDER BY total DESC, key ASC"
+        );
         if let Some(limit) = query.limit {
             sql.push_str(&format!(" LIMIT {limit}"));
         }
+        let params_refs: Vec<&dyn rusqlite::types::ToSql> =
+            param_values.iter().map(|p| p.as_ref()).collect();
+
+        let mut stmt = self.conn.prepare(&sql).map_err(StorageError::Sqlite)?;
+        let rows = stmt
+            .query_map(params_refs.as_slice(), |row| {
+                Ok(GroupCount {
+                    key: row.get(0)?,
+                    total: row.get(1)?,
+                    critical: row.get(2)?,
+                    high: row.get(3)?,
+                    medium: row.get(4)?,
+                    low: row.get(5)?,
+                    sessions: row.get(6)?,
+                    first_seen: from_millis(row.get(7)?),
+                    last_seen: from_millis(row.get(8)?),
+                })
+            })
+            .map_err(StorageError::Sqlite)?;
+
+        let mut groups = Vec::new();
+        for row in rows {
+            groups.push(row.map_err(StorageError::Sqlite)?);
+        }
+        Ok(groups)
+    }
 
+    /// Per-agent activity in fixed-size time buckets, ordered by agent and
+    /// then time. Buckets are aligned like [`Self::chart_data`] and empty
+    /// buckets are omitted.
+    pub fn agent_trends(
+        &self,
+        query: &EventQuery,
+        bucket_ms: i64,
+    ) -> Result<Vec<AgentTrend>, CoreError> {
+        let bucket_ms = bucket_ms.max(1);
+        let (filter, param_values) = Self::filter_clause(query);
+        let key = GroupBy::Agent.key_sql();
+        let sql = format!(
+            "SELECT agent, (timestamp_ms / {bucket_ms}) * {bucket_ms} AS bucket,
+                    COUNT(DISTINCT session_id), COUNT(*),
+                    SUM(risk_level = 'critical'),
+                    SUM(risk_level = 'high'),
+                    SUM(risk_level = 'medium'),
+                    SUM(risk_level = 'low')
+             FROM (SELECT {key} AS agent, session_id, risk_level, timestamp_ms
+                   FROM events WHERE {filter})
+             WHERE agent IS NOT NULL
+             GROUP BY agent, bucket ORDER BY agent ASC, bucket ASC"
+        );
         let params_refs: Vec<&dyn rusqlite::types::ToSql> =
             param_values.iter().map(|p| p.as_ref()).collect();
 
         let mut stmt = self.conn.prepare(&sql).map_err(StorageError::Sqlite)?;
         let rows = stmt
             .query_map(params_refs.as_slice(), |row| {
-                let json_str: String = row.get(0)?;
-                Ok(json_str)
+                Ok(AgentTrend {
+                    agent: row.get(0)?,
+                    start_ms: row.get(1)?,
+                    sessions: row.get(2)?,
+                    total: row.get(3)?,
+                    critical: row.get(4)?,
+                    high: row.get(5)?,
+                    medium: row.get(6)?,
+                    low: row.get(7)?,
+                })
             })
             .map_err(StorageError::Sqlite)?;
 
-        let mut events = Vec::new();
+        let mut trends = Vec::new();
         for row in rows {
-            let json_str = row.map_err(StorageError::Sqlite)?;
-            let event: Event = serde_json::from_str(&json_str).map_err(StorageError::Serialize)?;
-            events.push(event);
+            trends.push(row.map_err(StorageError::Sqlite)?);
         }
-        Ok(events)
+        Ok(trends)
+    }
+
+    /// What the most recent `limit` sessions of `agent` did, most recent
+    /// first, for learning an [`crate::AgentProfile`]. A session's length
+    /// comes from its recorded end, else from its first and last events.
+    pub fn session_activity(
+        &self,
+        agent: &str,
+        limit: usize,
+    ) -> Result<Vec<SessionActivity>, CoreError> {
+        let sql = format!(
+            "SELECT session_id, start_time, end_time FROM sessions
+             WHERE {SESSION_AGENT_SQL} = ?1 COLLATE NOCASE
+             ORDER BY start_time DESC LIMIT ?2"
+        );
+        let mut stmt = self.conn.prepare(&sql).map_err(StorageError::Sqlite)?;
+        let rows = stmt
+            .query_map(params![agent, limit as i64], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, Option<String>>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                ))
+            })
+            .map_err(StorageError::Sqlite)?;
+        let mut sessions = Vec::new();
+        for row in rows {
+            sessions.push(row.map_err(StorageError::Sqlite)?);
+        }
+
+        let distinct = |session_id: &str, event_type: &str, field: &str| {
+            let mut stmt = self
+                .conn
+                .prepare_cached(&format!(
+                    "SELECT DISTINCT json_extract(event_data, '$.{field}') FROM events
+                     WHERE session_id = ?1 AND event_type = ?2"
+                ))
+                .map_err(StorageError::Sqlite)?;
+            let values = stmt
+                .query_map(params![session_id, event_type], |row| {
+                    row.get::<_, Option<String>>(0)
+                })
+                .map_err(StorageError::Sqlite)?
+                .filter_map(|value| value.transpose())
+                .collect::<Result<BTreeSet<String>, _>>()
+                .map_err(StorageError::Sqlite)?;
+            Ok::<_, CoreError>(values)
+        };
+
+        let mut activity = Vec::with_capacity(sessions.len());
+        for (session_id, start, end) in sessions {
+            let (peak_file_rate, first_ms, last_ms): (u32, Option<i64>, Option<i64>) = self
+                .conn
+                .query_row(
+                    "SELECT
+                         (SELECT COALESCE(MAX(n), 0) FROM
+                             (SELECT SUM(CASE WHEN event_type = 'file_summary'
+                                         THEN json_extract(event_data, '$.count')
+                                         ELSE 1 END) AS n FROM events
+                              WHERE session_id = ?1
+                                AND event_type IN ('file_access', 'file_summary')
+                              GROUP BY timestamp_ms / 60000)),
+                         MIN(timestamp_ms), MAX(timestamp_ms)
+                     FROM events WHERE session_id = ?1",
+                    params![session_id],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+                )
+                .map_err(StorageError::Sqlite)?;
+
+            let recorded = start
+                .as_deref()
+                .and_then(parse_rfc3339)
+                .zip(end.as_deref().and_then(parse_rfc3339))
+                .map(|(start, end)| end - start);
+            let observed = first_ms
+                .zip(last_ms)
+                .map(|(first, last)| chrono::TimeDelta::milliseconds(last - first));
+            let duration = recorded.or(observed).and_then(|d| d.to_std().ok());
+
+            activity.push(SessionActivity {
+                hosts: distinct(&session_id, "network", "host")?,
+                commands: distinct(&session_id, "command", "command")?,
+                session_id,
+                peak_file_rate,
+                duration,
+            });
+        }
+        Ok(activity)
+    }
+
+    /// Import a JSONL session log written by [`crate::SessionLogger`].
+    ///
+    /// The session ID and agent come from the `session_start` header. Without
+    /// one, the session ID comes from the file name and the process from the
+    /// session start event. Sessions already present in
+    /// the database are skipped, so importing the same log twice is harmless.
+    /// Lines that aren't valid events are ignored, as when reading the log.
+    pub fn import_jsonl(&mut self, path: &Path) -> Result<JsonlImport, CoreError> {
+        let reader = open_session_log(path)?;
+
+        let mut header: Option<serde_json::Value> = None;
+        let mut footer: Option<serde_json::Value> = None;
+        let mut events = Vec::new();
+        for line in reader.lines() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Ok(event) = serde_json::from_str::<Event>(line) {
+                events.push(event);
+                continue;
+            }
+            if let Ok(value) = serde_json::from_str::<serde_json::Value>(line) {
+                match value.get("type").and_then(|t| t.as_str()) {
+                    Some("session_start") if header.is_none() => header = Some(value),
+                    Some("session_end") => footer = Some(value),
+                    _ => {}
+                }
+            }
+        }
+
+        let field = |value: &Option<serde_json::Value>, key: &str| {
+            value
+                .as_ref()
+                .and_then(|v| v.get(key))
+                .and_then(|v| v.as_str())
+                .map(String::from)
+        };
+        let session_id = field(&header, "session_id")
+            .or_else(|| SegmentName::parse(path.file_name()?.to_str()?).map(|name| name.session_id))
+            .ok_or_else(|| StorageError::MissingSessionId {
+                path: path.to_path_buf(),
+            })?;
+
+        if self.has_session(&session_id)? {
+            return Ok(JsonlImport {
+                session_id,
+                events: 0,
+                skipped: true,
+            });
+        }
+
+        let start_time = field(&header, "session_start")
+            .as_deref()
+            .and_then(parse_rfc3339)
+            .or_else(|| events.first().map(|e| e.timestamp))
+            .unwrap_or_else(Utc::now);
+        // Logs written by the wrapper have no header, only a session start event
+        let session_start = events.iter().find(|e| {
+            matches!(
+                e.event_type,
+                EventType::Session {
+                    action: SessionAction::Start
+                }
+            )
+        });
+        let process = field(&header, "process")
+            .or_else(|| session_start.map(|e| e.process.clone()))
+            .unwrap_or_default();
+        let pid = header
+            .as_ref()
+            .and_then(|h| h.get("pid"))
+            .and_then(|p| p.as_u64())
+            .map(|pid| pid as u32)
+            .or_else(|| session_start.map(|e| e.pid))
+            .unwrap_or(0);
+        let agent_name = field(&header, "agent_name");
+        let end_time = field(&footer, "session_end");
+
+        let tx = self.conn.transaction().map_err(StorageError::Sqlite)?;
+        Self::insert_session(
+            &tx,
+            &session_id,
+            &process,
+            pid,
+            &start_time,
+            agent_name.as_deref(),
+        )?;
+        if let Some(end_time) = end_time {
+            tx.execute(
+                "UPDATE sessions SET end_time = ?1 WHERE session_id = ?2",
+                params![end_time, session_id],
+            )
+            .map_err(StorageError::Sqlite)?;
+        }
+        for event in &events {
+            Self::insert_event(&tx, Some(&session_id), event)?;
+        }
+        tx.commit().map_err(StorageError::Sqlite)?;
+
+        Ok(JsonlImport {
+            session_id,
+            events: events.len(),
+            skipped: false,
+        })
+    }
+
+    /// Import every `session-*.jsonl` log in `log_dir` with [`Self::import_jsonl`],
+    /// including rolled-over and compressed segments.
+    /// A log that fails to import is recorded in the result and doesn't stop
+    /// the others.
+    pub fn backfill_from_dir(&mut self, log_dir: &Path) -> Result<BackfillResult, CoreError> {
+        let mut result = BackfillResult::default();
+        if !log_dir.exists() {
+            return Ok(result);
+        }
+
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(log_dir)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|n| n.to_str())
+                    .and_then(SegmentName::parse)
+                    .is_some_and(|name| name.index == 0)
+            })
+            .collect();
+        paths.sort();
+        // A session being compressed briefly has both a plain and a
+        // compressed first segment
+        paths.dedup_by(|a, b| {
+            let id = |p: &Path| {
+                p.file_name()
+                    .and_then(|n| n.to_str())
+                    .and_then(SegmentName::parse)
+                    .map(|name| name.session_id)
+            };
+            id(a) == id(b)
+        });
+
+        for path in paths {
+            match self.import_jsonl(&path) {
+                Ok(import) if import.skipped => result.skipped += 1,
+                Ok(import) => {
+                    result.imported += 1;
+                    result.events += import.events;
+                }
+                Err(e) => result.failed.push((path, e.to_string())),
+            }
+        }
+        Ok(result)
+    }
+
+    /// Delete events older than `cutoff`, along with their search index
+    /// entries and any session left without events that started before
+    /// `cutoff`. Returns the number of events deleted.
+    pub fn prune_before(&mut self, cutoff: DateTime<Utc>) -> Result<usize, CoreError> {
+        let cutoff_ms = cutoff.timestamp_millis();
+        let tx = self.conn.transaction().map_err(StorageError::Sqlite)?;
+        tx.execute(
+            "DELETE FROM events_fts WHERE rowid IN
+                 (SELECT rowid FROM events WHERE timestamp_ms < ?1)",
+            params![cutoff_ms],
+        )
+        .map_err(StorageError::Sqlite)?;
+        let deleted = tx
+            .execute(
+                "DELETE FROM events WHERE timestamp_ms < ?1",
+                params![cutoff_ms],
+            )
+            .map_err(StorageError::Sqlite)?;
+        tx.execute(
+            "DELETE FROM sessions
+             WHERE julianday(start_time) < julianday(?1)
+               AND session_id NOT IN
+                   (SELECT DISTINCT session_id FROM events WHERE session_id IS NOT NULL)",
+            params![cutoff.to_rfc3339()],
+        )
+        .map_err(StorageError::Sqlite)?;
+        tx.commit().map_err(StorageError::Sqlite)?;
+        Ok(deleted)
+    }
+
+    /// Sessions that have ended, or whose last event is before
+    /// `idle_before`, with the size of their event data, ordered by their
+    /// last event, oldest first.
+    pub fn ended_sessions(
+        &self,
+        idle_before: DateTime<Utc>,
+    ) -> Result<Vec<(String, u64)>, CoreError> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT e.session_id, SUM(length(e.event_data)) FROM events e
+                 LEFT JOIN sessions s ON s.session_id = e.session_id
+                 WHERE e.session_id IS NOT NULL
+                 GROUP BY e.session_id
+                 HAVING MAX(s.end_time) IS NOT NULL OR MAX(e.timestamp_ms) < ?1
+                 ORDER BY MAX(e.timestamp_ms)",
+            )
+            .map_err(StorageError::Sqlite)?;
+        let sessions = stmt
+            .query_map(params![idle_before.timestamp_millis()], |row| {
+                Ok((row.get(0)?, row.get::<_, i64>(1)?.max(0) as u64))
+            })
+            .map_err(StorageError::Sqlite)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(StorageError::Sqlite)?;
+        Ok(sessions)
+    }
+
+    /// Total size of the event data of all events
+    pub fn event_data_bytes(&self) -> Result<u64, CoreError> {
+        let bytes: i64 = self
+            .conn
+            .query_row(
+                "SELECT COALESCE(SUM(length(event_data)), 0) FROM events",
+                [],
+                |row| row.get(0),
+            )
+            .map_err(StorageError::Sqlite)?;
+        Ok(bytes.max(0) as u64)
+    }
+
+    /// Delete a session record with all its events and their search index
+    /// entries. Returns the number of events deleted.
+    pub fn delete_session(&mut self, session_id: &str) -> Result<usize, CoreError> {
+        let tx = self.conn.transaction().map_err(StorageError::Sqlite)?;
+        tx.execute(
+            "DELETE FROM events_fts WHERE rowid IN
+                 (SELECT rowid FROM events WHERE session_id = ?1)",
+            params![session_id],
+        )
+        .map_err(StorageError::Sqlite)?;
+        let deleted = tx
+            .execute(
+                "DELETE FROM events WHERE session_id = ?1",
+                params![session_id],
+            )
+            .map_err(StorageError::Sqlite)?;
+        tx.execute(
+            "DELETE FROM sessions WHERE session_id = ?1",
+            params![session_id],
+        )
+        .map_err(StorageError::Sqlite)?;
+        tx.commit().map_err(StorageError::Sqlite)?;
+        Ok(deleted)
+    }
+
+    /// Bytes of the database file not on the freelist; after a `VACUUM`
+    /// this is the file size.
+    pub fn used_bytes(&self) -> Result<u64, CoreError> {
+        let pragma = |name: &str| -> Result<u64, CoreError> {
+            let value: i64 = self
+                .conn
+                .query_row(&format!("PRAGMA {name}"), [], |row| row.get(0))
+                .map_err(StorageError::Sqlite)?;
+            Ok(value.max(0) as u64)
+        };
+        let pages = pragma("page_count")?.saturating_sub(pragma("freelist_count")?);
+        Ok(pages * pragma("page_size")?)
+    }
+
+    /// Reclaim space freed by deletions. The search index is merged first,
+    /// since it only drops deleted entries when its segments are merged.
+    pub fn vacuum(&self) -> Result<(), CoreError> {
+        self.conn
+            .execute_batch(
+                "INSERT INTO events_fts(events_fts) VALUES('optimize');
+                 VACUUM",
+            )
+            .map_err(StorageError::Sqlite)?;
+        Ok(())
     }
 
     /// Get the number of events written in this session.
@@ -182,40 +1016,165 @@ impl SqliteStorage {
         self.event_count
     }
 
+    /// Build the WHERE clause (without the keyword) and its parameters.
+    fn filter_clause(query: &EventQuery) -> (String, Vec<Box<dyn rusqlite::types::ToSql>>) {
+        let mut sql = String::from("1=1");
+        let mut param_values: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
+
+        if let Some(ref session_id) = query.session_id {
+            sql.push_str(" AND session_id = ?");
+            param_values.push(Box::new(session_id.clone()));
+        }
+        if let Some(ref risk_level) = query.risk_level {
+            sql.push_str(" AND risk_level = ?");
+            param_values.push(Box::new(risk_level.to_string()));
+        }
+        if let Some(ref event_type) = query.event_type {
+            sql.push_str(" AND event_type = ?");
+            param_values.push(Box::new(event_type.clone()));
+        }
+        if let Some(ref start_time) = query.start_time {
+            sql.push_str(" AND timestamp_ms >= ?");
+            param_values.push(Box::new(start_time.timestamp_millis()));
+        }
+        if let Some(ref end_time) = query.end_time {
+            sql.push_str(" AND timestamp_ms <= ?");
+            param_values.push(Box::new(end_time.timestamp_millis()));
+        }
+        if let Some(ref agent) = query.agent {
+            sql.push_str(&format!(
+                " AND session_id IN (SELECT session_id FROM sessions
+                                     WHERE {SESSION_AGENT_SQL} = ? COLLATE NOCASE)"
+            ));
+            param_values.push(Box::new(agent.clone()));
+        }
+        if let Some(text) = query.text.as_deref().filter(|t| !t.is_empty()) {
+            // The trigram tokenizer can only MATCH terms of 3+ characters;
+            // shorter terms fall back to LIKE over the indexed text.
+            if text.chars().count() >= 3 {
+                sql.push_str(
+                    " AND rowid IN (SELECT rowid FROM events_fts WHERE events_fts MATCH ?)",
+                );
+                param_values.push(Box::new(format!("\"{}\"", text.replace('"', "\"\""))));
+            } else {
+                sql.push_str(
+                    " AND rowid IN (SELECT rowid FROM events_fts WHERE search_text LIKE ? ESCAPE '\\')",
+                );
+                let escaped = text
+                    .replace('\\', "\\\\")
+                    .replace('%', "\\%")
+                    .replace('_', "\\_");
+                param_values.push(Box::new(format!("%{escaped}%")));
+            }
+        }
+
+        (sql, param_values)
+    }
+
+    /// Text indexed for full-text search: commands with their arguments,
+    /// file paths, hosts, listening addresses, changed config keys and
+    /// anomaly explanations.
+    fn search_text(event: &Event) -> Option<String> {
+        match &event.event_type {
+            EventType::Command { command, args, .. } => {
+                let mut text = command.clone();
+                for arg in args {
+                    text.push(' ');
+                    text.push_str(arg);
+                }
+                Some(text)
+            }
+            EventType::FileAccess { path, .. } | EventType::FileSummary { path, .. } => {
+                Some(path.to_string_lossy().to_string())
+            }
+            EventType::Network { host, .. }
+            | EventType::NetworkClose { host, .. }
+            | EventType::ReverseShell { host, .. }
+            | EventType::ProxyRequest { host, .. } => Some(host.clone()),
+            EventType::Listen { address, port, .. } => Some(format!("{} {}", address, port)),
+            EventType::ConfigReload { changes, .. } => Some(
+                changes
+                    .iter()
+                    .map(|c| c.key.as_str())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            ),
+            EventType::Anomaly { explanation, .. } => Some(explanation.clone()),
+            EventType::Process { .. } | EventType::Session { .. } | EventType::Resource { .. } => {
+                None
+            }
+        }
+    }
+
     /// Extract the event type tag from an Event for indexing.
     fn event_type_tag(event: &Event) -> &'static str {
-        match &event.event_type {
-            crate::event::EventType::Command { .. } => "command",
-            crate::event::EventType::FileAccess { .. } => "file_access",
-            crate::event::EventType::Network { .. } => "network",
-            crate::event::EventType::Process { .. } => "process",
-            crate::event::EventType::Session { .. } => "session",
+        event.event_type.tag()
+    }
+
+    /// Insert an event and its full-text index row. Callers wrap this in a
+    /// transaction so both rows are written together.
+    fn insert_event(
+        conn: &Connection,
+        session_id: Option<&str>,
+        event: &Event,
+    ) -> Result<(), CoreError> {
+        let event_data = serde_json::to_string(event).map_err(StorageError::Serialize)?;
+        conn.execute(
+            "INSERT INTO events (id, session_id, timestamp, timestamp_ms, event_type, event_data, process, pid, risk_level, alert)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                event.id.to_string(),
+                session_id,
+                event.timestamp.to_rfc3339(),
+                event.timestamp.timestamp_millis(),
+                Self::event_type_tag(event),
+                event_data,
+                event.process,
+                event.pid,
+                event.risk_level.to_string(),
+                event.alert as i32,
+            ],
+        )
+        .map_err(StorageError::Sqlite)?;
+        if let Some(text) = Self::search_text(event) {
+            conn.execute(
+                "INSERT INTO events_fts (rowid, search_text) VALUES (last_insert_rowid(), ?1)",
+                params![text],
+            )
+            .map_err(StorageError::Sqlite)?;
         }
+        Ok(())
+    }
+}
+
+fn parse_rfc3339(s: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(s)
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+fn from_millis(ms: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp_millis(ms).unwrap_or_default()
+}
+
+fn risk_from_rank(rank: i64) -> RiskLevel {
+    match rank {
+        3 => RiskLevel::Critical,
+        2 => RiskLevel::High,
+        1 => RiskLevel::Medium,
+        _ => RiskLevel::Low,
     }
 }
 
 impl EventStorage for SqliteStorage {
     fn write_event(&mut self, event: &Event) -> Result<(), CoreError> {
-        let event_data = serde_json::to_string(event).map_err(StorageError::Serialize)?;
-        let event_type_tag = Self::event_type_tag(event);
-
-        self.conn
-            .execute(
-                "INSERT INTO events (id, session_id, timestamp, event_type, event_data, process, pid, risk_level, alert)
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
-                params![
-                    event.id.to_string(),
-                    Option::<String>::None,
-                    event.timestamp.to_rfc3339(),
-                    event_type_tag,
-                    event_data,
-                    event.process,
-                    event.pid,
-                    event.risk_level.to_string(),
-                    event.alert as i32,
-                ],
-            )
+        let tx = self
+            .conn
+            .unchecked_transaction()
             .map_err(StorageError::Sqlite)?;
+        Self::insert_event(&tx, self.session_id.as_deref(), event)?;
+        tx.commit().map_err(StorageError::Sqlite)?;
+
         self.event_count += 1;
         Ok(())
     }
@@ -234,6 +1193,7 @@ impl EventStorage for SqliteStorage {
 mod tests {
     use super::*;
     use crate::event::{EventType, FileAction, RiskLevel};
+    use crate::storage::SessionLogger;
     use tempfile::TempDir;
 
     fn create_test_event() -> Event {
@@ -417,7 +1377,7 @@ mod tests {
         let now = Utc::now();
 
         storage
-            .write_session_header("sess-001", "claude-code", 5678, &now)
+            .write_session_header("sess-001", "claude-code", 5678, &now, None)
             .unwrap();
 
         // Verify session was inserted
@@ -515,4 +1475,460 @@ mod tests {
 
         assert_eq!(storage.event_count(), 1);
     }
+
+    fn write_search_fixtures(storage: &mut SqliteStorage) {
+        storage
+            .write_event(&Event::command(
+                "git".into(),
+                vec!["push".into(), "origin".into()],
+                "bash".into(),
+                1,
+                RiskLevel::Low,
+            ))
+            .unwrap();
+        storage
+            .write_event(&Event::new(
+                EventType::FileAccess {
+                    path: PathBuf::from("/Users/dev/.ssh/id_rsa"),
+                    action: FileAction::Read,
+                },
+                "cat".into(),
+                2,
+                RiskLevel::Critical,
+            ))
+            .unwrap();
+        storage
+            .write_event(&Event::new(
+                EventType::Network {
+                    host: "api.GitHub.com".into(),
+                    port: 443,
+                    protocol: "tcp".into(),
+                    provider: None,
+                },
+                "curl".into(),
+                3,
+                RiskLevel::Medium,
+            ))
+            .unwrap();
+    }
+
+    fn text_query(text: &str) -> EventQuery {
+        EventQuery {
+            text: Some(text.into()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_sqlite_full_text_search() {
+        let mut storage = SqliteStorage::in_memory().unwrap();
+        write_search_fixtures(&mut storage);
+
+        // Substring across command args, paths and hosts, case-insensitive
+        assert_eq!(
+            storage.query_events(&text_query("origin")).unwrap().len(),
+            1
+        );
+        assert_eq!(storage.query_events(&text_query(".ssh/")).unwrap().len(), 1);
+        assert_eq!(
+            storage.query_events(&text_query("github")).unwrap().len(),
+            1
+        );
+        assert_eq!(storage.query_events(&text_query("GIT")).unwrap().len(), 2);
+        // Terms shorter than a trigram use the LIKE fallback
+        assert_eq!(storage.query_events(&text_query("rs")).unwrap().len(), 1);
+        assert_eq!(storage.query_events(&text_query("%")).unwrap().len(), 0);
+        assert!(storage
+            .query_events(&text_query("nothing-matches"))
+            .unwrap()
+            .is_empty());
+        assert_eq!(storage.count_events(&text_query("git")).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_sqlite_query_with_offset() {
+        let mut storage = SqliteStorage::in_memory().unwrap();
+        for i in 0..5 {
+            storage
+                .write_event(&Event::command(
+                    format!("cmd{i}"),
+                    vec![],
+                    "bash".into(),
+                    1,
+                    RiskLevel::Low,
+                ))
+                .unwrap();
+        }
+
+        let query = EventQuery {
+            offset: Some(3),
+            ..Default::default()
+        };
+        let events = storage.query_events(&query).unwrap();
+        assert_eq!(events.len(), 2);
+        assert!(matches!(
+            &events[0].event_type,
+            EventType::Command { command, .. } if command == "cmd3"
+        ));
+
+        let query = EventQuery {
+            offset: Some(1),
+            limit: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(storage.query_events(&query).unwrap().len(), 2);
+        assert_eq!(storage.count_events(&query).unwrap(), 5);
+    }
+
+    #[test]
+    fn test_sqlite_chart_data() {
+        let mut storage = SqliteStorage::in_memory().unwrap();
+        let base = DateTime::parse_from_rfc3339("2026-01-01T10:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        for (minutes, level) in [
+            (0, RiskLevel::Low),
+            (10, RiskLevel::High),
+            (59, RiskLevel::Critical),
+            (61, RiskLevel::Medium),
+        ] {
+            let mut event = Event::command("ls".into(), vec![], "bash".into(), 1, level);
+            event.timestamp = base + chrono::Duration::minutes(minutes);
+            storage.write_event(&event).unwrap();
+        }
+
+        let hour_ms = 60 * 60 * 1000;
+        let buckets = storage.chart_data(&EventQuery::default(), hour_ms).unwrap();
+        assert_eq!(buckets.len(), 2);
+        assert_eq!(buckets[0].start_ms, base.timestamp_millis());
+        assert_eq!(buckets[0].total, 3);
+        assert_eq!(
+            (buckets[0].critical, buckets[0].high, buckets[0].low),
+            (1, 1, 1)
+        );
+        assert_eq!(buckets[1].start_ms, base.timestamp_millis() + hour_ms);
+        assert_eq!(buckets[1].medium, 1);
+    }
+
+    #[test]
+    fn test_sqlite_session_id_tagging() {
+        let mut storage = SqliteStorage::in_memory()
+            .unwrap()
+            .with_session_id("sess-a");
+        storage.write_event(&create_test_event()).unwrap();
+
+        assert!(storage.has_session("sess-a").unwrap());
+        assert!(!storage.has_session("sess-b").unwrap());
+
+        let query = EventQuery {
+            session_id: Some("sess-a".into()),
+            ..Default::default()
+        };
+        assert_eq!(storage.query_events(&query).unwrap().len(), 1);
+
+        storage
+            .write_session_header("sess-b", "cursor", 1, &Utc::now(), Some("Cursor"))
+            .unwrap();
+        assert!(storage.has_session("sess-b").unwrap());
+    }
+
+    #[test]
+    fn test_sqlite_migrates_v1_schema() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("old.db");
+
+        // Database as written by the original schema (no timestamp_ms, no FTS)
+        let event = Event::command(
+            "terraform".into(),
+            vec!["apply".into()],
+            "bash".into(),
+            1,
+            RiskLevel::High,
+        );
+        {
+            let conn = Connection::open(&db_path).unwrap();
+            conn.execute_batch(
+                "CREATE TABLE events (
+                    id TEXT PRIMARY KEY, session_id TEXT, timestamp TEXT NOT NULL,
+                    event_type TEXT NOT NULL, event_data TEXT NOT NULL, process TEXT NOT NULL,
+                    pid INTEGER NOT NULL, risk_level TEXT NOT NULL, alert INTEGER NOT NULL
+                );",
+            )
+            .unwrap();
+            conn.execute(
+                "INSERT INTO events VALUES (?1, 'old', ?2, 'command', ?3, 'bash', 1, 'high', 1)",
+                params![
+                    event.id.to_string(),
+                    event.timestamp.to_rfc3339(),
+                    serde_json::to_string(&event).unwrap()
+                ],
+            )
+            .unwrap();
+        }
+
+        let storage = SqliteStorage::new(&db_path).unwrap();
+        assert_eq!(
+            storage
+                .query_events(&text_query("terraform"))
+                .unwrap()
+                .len(),
+            1
+        );
+
+        let query = EventQuery {
+            start_time: Some(event.timestamp - chrono::Duration::seconds(1)),
+            end_time: Some(event.timestamp + chrono::Duration::seconds(1)),
+            ..Default::default()
+        };
+        assert_eq!(storage.query_events(&query).unwrap().len(), 1);
+
+        // Reopening an up-to-date database must not duplicate the FTS rows
+        drop(storage);
+        let storage = SqliteStorage::new(&db_path).unwrap();
+        assert_eq!(storage.count_events(&text_query("terraform")).unwrap(), 1);
+    }
+
+    /// Two Claude sessions and one Cursor session sharing a database
+    fn write_cross_session_fixtures(db_path: &PathBuf) {
+        let now = Utc::now();
+        for (session, agent) in [("s1", "Claude"), ("s2", "Claude"), ("s3", "Cursor")] {
+            let mut storage = SqliteStorage::new(db_path)
+                .unwrap()
+                .with_session_id(session);
+            storage
+                .write_session_header(session, "MacAgentWatch", 1, &now, Some(agent))
+                .unwrap();
+            write_search_fixtures(&mut storage);
+            if session == "s1" {
+                storage
+                    .write_event(&Event::new(
+                        EventType::Network {
+                            host: "pastebin.com".into(),
+                            port: 443,
+                            protocol: "tcp".into(),
+                            provider: None,
+                        },
+                        "curl".into(),
+                        4,
+                        RiskLevel::High,
+                    ))
+                    .unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_sqlite_group_counts() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("events.db");
+        write_cross_session_fixtures(&db_path);
+        let storage = SqliteStorage::new(&db_path).unwrap();
+
+        let hosts = storage
+            .group_counts(&EventQuery::default(), GroupBy::Host)
+            .unwrap();
+        assert_eq!(hosts.len(), 2);
+        assert_eq!(hosts[0].key, "api.GitHub.com");
+        assert_eq!(hosts[0].total, 3);
+        assert_eq!(hosts[0].sessions, 3);
+        assert_eq!(hosts[1].key, "pastebin.com");
+        assert_eq!(hosts[1].high, 1);
+
+        let query = EventQuery {
+            agent: Some("cursor".into()),
+            ..Default::default()
+        };
+        let hosts = storage.group_counts(&query, GroupBy::Host).unwrap();
+        assert_eq!(hosts.len(), 1);
+        assert_eq!(hosts[0].total, 1);
+
+        let agents = storage
+            .group_counts(&EventQuery::default(), GroupBy::Agent)
+            .unwrap();
+        let agents: Vec<(&str, u32, u32)> = agents
+            .iter()
+            .map(|g| (g.key.as_str(), g.total, g.sessions))
+            .collect();
+        assert_eq!(agents, vec![("Claude", 7, 2), ("Cursor", 3, 1)]);
+
+        let commands = storage
+            .group_counts(&EventQuery::default(), GroupBy::Command)
+            .unwrap();
+        assert_eq!(commands.len(), 1);
+        assert_eq!(commands[0].key, "git");
+
+        let query = EventQuery {
+            limit: Some(1),
+            ..Default::default()
+        };
+        let paths = storage.group_counts(&query, GroupBy::Path).unwrap();
+        assert_eq!(paths.len(), 1);
+        assert_eq!(paths[0].key, "/Users/dev/.ssh/id_rsa");
+        assert_eq!(paths[0].critical, 3);
+    }
+
+    #[test]
+    fn test_sqlite_sessions_matching() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("events.db");
+        write_cross_session_fixtures(&db_path);
+        let storage = SqliteStorage::new(&db_path).unwrap();
+
+        let query = EventQuery {
+            text: Some("/.ssh".into()),
+            event_type: Some("file_access".into()),
+            ..Default::default()
+        };
+        let sessions = storage.sessions_matching(&query).unwrap();
+        assert_eq!(sessions.len(), 3);
+        assert!(sessions.iter().all(|s| s.matching_events == 1));
+        assert!(sessions.iter().all(|s| s.max_risk == RiskLevel::Critical));
+
+        let query = EventQuery {
+            agent: Some("Claude".into()),
+            text: Some("pastebin".into()),
+            ..Default::default()
+        };
+        let sessions = storage.sessions_matching(&query).unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].session_id, "s1");
+        assert_eq!(sessions[0].agent_name.as_deref(), Some("Claude"));
+        assert_eq!(sessions[0].max_risk, RiskLevel::High);
+        assert!(sessions[0].start_time.is_some());
+    }
+
+    #[test]
+    fn test_sqlite_agent_trends() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("events.db");
+        write_cross_session_fixtures(&db_path);
+        let storage = SqliteStorage::new(&db_path).unwrap();
+
+        let trends = storage
+            .agent_trends(&EventQuery::default(), i64::MAX)
+            .unwrap();
+        assert_eq!(trends.len(), 2);
+        assert_eq!(trends[0].agent, "Claude");
+        assert_eq!(trends[0].sessions, 2);
+        assert_eq!(trends[0].total, 7);
+        assert_eq!(trends[0].critical, 2);
+        assert_eq!(trends[1].agent, "Cursor");
+        assert_eq!(trends[1].sessions, 1);
+    }
+
+    #[test]
+    fn test_sqlite_session_activity() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("events.db");
+        write_cross_session_fixtures(&db_path);
+        SqliteStorage::new(&db_path)
+            .unwrap()
+            .with_session_id("s2")
+            .write_event(&Event::file_summary(
+                PathBuf::from("/work/node_modules"),
+                FileAction::Create,
+                40,
+                "fswatch".into(),
+                1,
+            ))
+            .unwrap();
+        let storage = SqliteStorage::new(&db_path).unwrap();
+
+        let mut activity = storage.session_activity("claude", 10).unwrap();
+        assert_eq!(activity.len(), 2);
+        activity.sort_by(|a, b| a.session_id.cmp(&b.session_id));
+        let hosts: Vec<&str> = activity[0].hosts.iter().map(String::as_str).collect();
+        assert_eq!(hosts, vec!["api.GitHub.com", "pastebin.com"]);
+        assert_eq!(activity[1].hosts.len(), 1);
+        assert!(activity[0].commands.contains("git"));
+        assert_eq!(activity[0].peak_file_rate, 1);
+        assert!(activity[1].peak_file_rate >= 40);
+        assert!(activity[0].duration.is_some());
+
+        assert_eq!(storage.session_activity("claude", 1).unwrap().len(), 1);
+        assert!(storage.session_activity("aider", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_sqlite_import_jsonl() {
+        let temp_dir = TempDir::new().unwrap();
+        let log_dir = temp_dir.path().join("logs");
+        let log_path = {
+            let mut logger = SessionLogger::new(&log_dir, Some("imported".into())).unwrap();
+            logger
+                .write_session_header("claude", 42, Some("Claude Code"))
+                .unwrap();
+            logger.write_event(&create_test_event()).unwrap();
+            logger.write_event(&create_test_event()).unwrap();
+            logger.write_session_footer(Some(0)).unwrap();
+            logger.path().clone()
+        };
+        std::fs::write(log_dir.join("notes.jsonl"), "not a session\n").unwrap();
+
+        let mut storage = SqliteStorage::new(&temp_dir.path().join("events.db")).unwrap();
+        let result = storage.backfill_from_dir(&log_dir).unwrap();
+        assert_eq!(result.imported, 1);
+        assert_eq!(result.events, 2);
+        assert!(result.failed.is_empty());
+
+        let query = EventQuery {
+            agent: Some("claude code".into()),
+            ..Default::default()
+        };
+        assert_eq!(storage.count_events(&query).unwrap(), 2);
+        let sessions = storage.sessions_matching(&query).unwrap();
+        assert_eq!(sessions[0].session_id, "imported");
+        assert_eq!(sessions[0].process_name.as_deref(), Some("claude"));
+        assert!(sessions[0].end_time.is_some());
+
+        // Importing again leaves the session untouched
+        let again = storage.import_jsonl(&log_path).unwrap();
+        assert!(again.skipped);
+        let result = storage.backfill_from_dir(&log_dir).unwrap();
+        assert_eq!((result.imported, result.skipped), (0, 1));
+        assert_eq!(storage.count_events(&EventQuery::default()).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_sqlite_import_jsonl_without_header() {
+        let temp_dir = TempDir::new().unwrap();
+        let log_path = {
+            let mut logger =
+                SessionLogger::new(&temp_dir.path().to_path_buf(), Some("wrapped".into())).unwrap();
+            logger
+                .write_event(&Event::session_start("aider".into(), 77))
+                .unwrap();
+            logger.write_event(&create_test_event()).unwrap();
+            logger.flush().unwrap();
+            logger.path().clone()
+        };
+
+        let mut storage = SqliteStorage::in_memory().unwrap();
+        storage.import_jsonl(&log_path).unwrap();
+        let query = EventQuery {
+            agent: Some("aider".into()),
+            ..Default::default()
+        };
+        let sessions = storage.sessions_matching(&query).unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].session_id, "wrapped");
+    }
+
+    #[test]
+    fn test_sqlite_import_jsonl_without_session_id() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("events.jsonl");
+        std::fs::write(&path, "").unwrap();
+
+        let mut storage = SqliteStorage::in_memory().unwrap();
+        assert!(storage.import_jsonl(&path).is_err());
+    }
+
+    #[test]
+    fn test_group_by_from_str() {
+        assert_eq!("Host".parse::<GroupBy>().unwrap(), GroupBy::Host);
+        assert_eq!(GroupBy::Path.to_string(), "path");
+        assert!("user".parse::<GroupBy>().is_err());
+    }
 }
@@ -1,14 +1,19 @@
 //! Storage module for MacAgentWatch
 //!
 //! Handles session-based log file storage with JSON Lines format.
-//! Each monitoring session creates a new log file.
+//! Each monitoring session creates a new log file. A session that outgrows
+//! its size cap continues in numbered segment files, and segments of ended
+//! sessions may be compressed; [`open_session_log`] reads all of them back
+//! as one stream.
 
+use crate::config::Compression;
 use crate::error::{CoreError, StorageError};
 use crate::event::Event;
+use crate::traffic::{TrafficStats, TrafficSummary};
 use chrono::{DateTime, Utc};
 use std::fs::{File, OpenOptions};
-use std::io::{BufWriter, Write};
-use std::path::PathBuf;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
 
 /// Trait for event storage implementations
 pub trait EventStorage: Send {
@@ -20,17 +25,159 @@ pub trait EventStorage: Send {
     fn path(&self) -> &PathBuf;
 }
 
+/// File name of one segment of a session log.
+///
+/// The first segment is `session-{id}.jsonl`; later segments are
+/// `session-{id}.{n}.jsonl`. Compressed segments carry an extra `.zst` or
+/// `.gz` suffix.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SegmentName {
+    pub session_id: String,
+    /// Segment number, 0 for the first segment
+    pub index: u32,
+    pub compression: Compression,
+}
+
+impl SegmentName {
+    /// Parse a segment file name; returns `None` for other files
+    pub fn parse(file_name: &str) -> Option<Self> {
+        let rest = file_name.strip_prefix("session-")?;
+        let (rest, compression) = match rest.rsplit_once('.') {
+            Some((stem, ext)) => match Compression::from_extension(ext) {
+                Some(compression) => (stem, compression),
+                None => (rest, Compression::None),
+            },
+            None => (rest, Compression::None),
+        };
+        let stem = rest.strip_suffix(".jsonl")?;
+        let (session_id, index) = match stem.rsplit_once('.') {
+            Some((id, n)) if !id.is_empty() && n.bytes().all(|b| b.is_ascii_digit()) => {
+                (id, n.parse().ok()?)
+            }
+            _ => (stem, 0),
+        };
+        if session_id.is_empty() {
+            return None;
+        }
+        Some(Self {
+            session_id: session_id.to_string(),
+            index,
+            compression,
+        })
+    }
+
+    /// File name for this segment
+    pub fn file_name(&self) -> String {
+        let mut name = if self.index == 0 {
+            format!("session-{}.jsonl", self.session_id)
+        } else {
+            format!("session-{}.{}.jsonl", self.session_id, self.index)
+        };
+        if let Some(ext) = self.compression.extension() {
+            name.push('.');
+            name.push_str(ext);
+        }
+        name
+    }
+}
+
+/// All existing segment files of the session that `path` belongs to, in
+/// order. `path` may name any segment, compressed or not, including one that
+/// no longer exists (e.g. `session-{id}.jsonl` after compression). If a
+/// segment exists both plain and compressed (compression in progress), the
+/// plain file is used.
+pub fn session_segments(path: &Path) -> Vec<PathBuf> {
+    let Some(name) = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .and_then(SegmentName::parse)
+    else {
+        return if path.exists() {
+            vec![path.to_path_buf()]
+        } else {
+            Vec::new()
+        };
+    };
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut segments: Vec<(u32, bool, PathBuf)> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let segment = SegmentName::parse(entry.file_name().to_str()?)?;
+            (segment.session_id == name.session_id).then(|| {
+                (
+                    segment.index,
+                    segment.compression != Compression::None,
+                    entry.path(),
+                )
+            })
+        })
+        .collect();
+    segments.sort();
+    segments.dedup_by_key(|(index, _, _)| *index);
+    segments.into_iter().map(|(_, _, path)| path).collect()
+}
+
+/// Open a single segment file, decompressing it if needed
+pub fn open_segment(path: &Path) -> Result<Box<dyn Read + Send>, CoreError> {
+    let file = File::open(path).map_err(|e| StorageError::OpenFile {
+        path: path.to_path_buf(),
+        source: e,
+    })?;
+    let compression = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .and_then(Compression::from_extension)
+        .unwrap_or(Compression::None);
+    Ok(match compression {
+        Compression::None => Box::new(file),
+        Compression::Gzip => Box::new(flate2::read::GzDecoder::new(file)),
+        Compression::Zstd => Box::new(zstd::Decoder::new(file)?),
+    })
+}
+
+/// Open all segments of a session log as one line-oriented stream
+pub fn open_session_log(path: &Path) -> Result<Box<dyn BufRead + Send>, CoreError> {
+    let segments = session_segments(path);
+    if segments.is_empty() {
+        return Err(StorageError::OpenFile {
+            path: path.to_path_buf(),
+            source: std::io::Error::from(std::io::ErrorKind::NotFound),
+        }
+        .into());
+    }
+    let mut reader: Box<dyn Read + Send> = Box::new(std::io::empty());
+    for segment in &segments {
+        reader = Box::new(reader.chain(open_segment(segment)?));
+    }
+    Ok(Box::new(BufReader::new(reader)))
+}
+
 /// Session-based log file writer
 ///
 /// Creates a new log file for each monitoring session.
 /// Format: `session-{timestamp}-{uuid}.jsonl`
+///
+/// With [`SessionLogger::with_max_segment_bytes`] the log rolls over to
+/// `session-{id}.{n}.jsonl` once the current file reaches the cap. Each new
+/// segment starts with a copy of the session header.
 pub struct SessionLogger {
     session_id: String,
     session_start: DateTime<Utc>,
+    log_dir: PathBuf,
     file_path: PathBuf,
     writer: BufWriter<File>,
     event_count: usize,
     auto_flush_interval: usize,
+    segment_index: u32,
+    segment_bytes: u64,
+    max_segment_bytes: u64,
+    header: Option<serde_json::Value>,
+    /// Network traffic among the written events, summarised in the footer
+    traffic: TrafficStats,
 }
 
 impl SessionLogger {
@@ -57,30 +204,108 @@ impl SessionLogger {
             )
         });
 
-        let filename = format!("session-{}.jsonl", session_id);
-        let file_path = log_dir.join(&filename);
-
-        let file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&file_path)
-            .map_err(|e| StorageError::OpenFile {
-                path: file_path.clone(),
-                source: e,
-            })?;
-
-        let writer = BufWriter::with_capacity(65536, file);
+        let file_path = log_dir.join(
+            SegmentName {
+                session_id: session_id.clone(),
+                index: 0,
+                compression: Compression::None,
+            }
+            .file_name(),
+        );
+        let (writer, segment_bytes) = Self::open_segment_writer(&file_path)?;
 
         Ok(Self {
             session_id,
             session_start,
+            log_dir: log_dir.clone(),
             file_path,
             writer,
             event_count: 0,
             auto_flush_interval: 10,
+            segment_index: 0,
+            segment_bytes,
+            max_segment_bytes: 0,
+            header: None,
+            traffic: TrafficStats::new(),
         })
     }
 
+    /// Roll over to a new segment file once the current one reaches
+    /// `bytes` (0 = never)
+    pub fn with_max_segment_bytes(mut self, bytes: u64) -> Self {
+        self.max_segment_bytes = bytes;
+        self
+    }
+
+    fn open_segment_writer(path: &Path) -> Result<(BufWriter<File>, u64), CoreError> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| StorageError::OpenFile {
+                path: path.to_path_buf(),
+                source: e,
+            })?;
+        let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+        Ok((BufWriter::with_capacity(65536, file), len))
+    }
+
+    /// Path of the first segment, which identifies the session log
+    pub fn session_path(&self) -> PathBuf {
+        self.log_dir.join(
+            SegmentName {
+                session_id: self.session_id.clone(),
+                index: 0,
+                compression: Compression::None,
+            }
+            .file_name(),
+        )
+    }
+
+    /// Number of the segment currently written to (0 for the first)
+    pub fn segment_index(&self) -> u32 {
+        self.segment_index
+    }
+
+    /// Write one line, rolling over first if it would overflow the segment
+    fn write_line(&mut self, line: &str) -> Result<(), CoreError> {
+        let len = line.len() as u64 + 1;
+        if self.max_segment_bytes > 0
+            && self.segment_bytes > 0
+            && self.segment_bytes + len > self.max_segment_bytes
+        {
+            self.roll_over()?;
+        }
+        writeln!(self.writer, "{}", line).map_err(StorageError::Write)?;
+        self.segment_bytes += len;
+        Ok(())
+    }
+
+    /// Close the current segment and continue in the next one
+    fn roll_over(&mut self) -> Result<(), CoreError> {
+        self.flush()?;
+        self.segment_index += 1;
+        self.file_path = self.log_dir.join(
+            SegmentName {
+                session_id: self.session_id.clone(),
+                index: self.segment_index,
+                compression: Compression::None,
+            }
+            .file_name(),
+        );
+        let (writer, segment_bytes) = Self::open_segment_writer(&self.file_path)?;
+        self.writer = writer;
+        self.segment_bytes = segment_bytes;
+
+        if let Some(mut header) = self.header.clone() {
+            header["segment"] = serde_json::Value::from(self.segment_index);
+            let line = header.to_string();
+            writeln!(self.writer, "{}", line).map_err(StorageError::Write)?;
+            self.segment_bytes += line.len() as u64 + 1;
+        }
+        Ok(())
+    }
+
     /// Get session ID
     pub fn session_id(&self) -> &str {
         &self.session_id
@@ -96,6 +321,11 @@ impl SessionLogger {
         self.event_count
     }
 
+    /// Network traffic among the events written so far, by provider category
+    pub fn traffic_summary(&self) -> TrafficSummary {
+        self.traffic.summary()
+    }
+
     /// Write session metadata as first line
     pub fn write_session_header(
         &mut self,
@@ -113,21 +343,26 @@ impl SessionLogger {
         if let Some(name) = agent_name {
             header["agent_name"] = serde_json::Value::String(name.to_string());
         }
-        writeln!(self.writer, "{}", header).map_err(StorageError::Write)?;
+        self.write_line(&header.to_string())?;
+        self.header = Some(header);
         self.flush()?;
         Ok(())
     }
 
     /// Write session end marker
     pub fn write_session_footer(&mut self, exit_code: Option<i32>) -> Result<(), CoreError> {
-        let footer = serde_json::json!({
+        let mut footer = serde_json::json!({
             "session_id": self.session_id,
             "session_end": Utc::now().to_rfc3339(),
             "event_count": self.event_count,
             "exit_code": exit_code,
             "type": "session_end"
         });
-        writeln!(self.writer, "{}", footer).map_err(StorageError::Write)?;
+        if !self.traffic.is_empty() {
+            footer["traffic"] =
+                serde_json::to_value(self.traffic_summary()).map_err(StorageError::Serialize)?;
+        }
+        self.write_line(&footer.to_string())?;
         self.flush()?;
         Ok(())
     }
@@ -136,8 +371,9 @@ impl SessionLogger {
 impl EventStorage for SessionLogger {
     fn write_event(&mut self, event: &Event) -> Result<(), CoreError> {
         let json = serde_json::to_string(event).map_err(StorageError::Serialize)?;
-        writeln!(self.writer, "{}", json).map_err(StorageError::Write)?;
+        self.write_line(&json)?;
         self.event_count += 1;
+        self.traffic.observe(event);
         if self.event_count.is_multiple_of(self.auto_flush_interval) {
             self.flush()?;
         }
@@ -197,7 +433,12 @@ pub fn cleanup_old_logs(
         let entry = entry?;
         let path = entry.path();
 
-        if path.extension().and_then(|e| e.to_str()) == Some("jsonl") {
+        let is_log = path.extension().and_then(|e| e.to_str()) == Some("jsonl")
+            || path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| SegmentName::parse(n).is_some());
+        if is_log {
             if let Ok(metadata) = entry.metadata() {
                 if let Ok(modified) = metadata.modified() {
                     let modified: DateTime<Utc> = modified.into();
@@ -329,6 +570,54 @@ mod tests {
         assert!(lines[2].contains("\"exit_code\":0"));
     }
 
+    #[test]
+    fn test_session_footer_summarizes_traffic() {
+        let temp_dir = TempDir::new().unwrap();
+        let log_dir = temp_dir.path().to_path_buf();
+
+        let mut logger = SessionLogger::new(&log_dir, Some("traffic-test".to_string())).unwrap();
+        logger.write_event(&create_test_event()).unwrap();
+        for host in ["api.anthropic.com", "api.anthropic.com", "pypi.org"] {
+            let event = Event::new(
+                crate::event::EventType::NetworkClose {
+                    host: host.to_string(),
+                    port: 443,
+                    protocol: "tcp".to_string(),
+                    duration_ms: 100,
+                    bytes_sent: None,
+                    bytes_received: None,
+                    provider: None,
+                },
+                "agent".to_string(),
+                1,
+                RiskLevel::Low,
+            );
+            logger.write_event(&event).unwrap();
+        }
+        logger.write_session_footer(Some(0)).unwrap();
+
+        let content = std::fs::read_to_string(logger.path()).unwrap();
+        let footer: serde_json::Value =
+            serde_json::from_str(content.lines().last().unwrap()).unwrap();
+        assert_eq!(footer["traffic"]["llm"]["requests"], 2);
+        assert_eq!(footer["traffic"]["llm"]["providers"]["Anthropic"], 2);
+        assert_eq!(footer["traffic"]["package_registry"]["requests"], 1);
+        assert!(footer["traffic"].get("vcs").is_none());
+    }
+
+    #[test]
+    fn test_session_footer_omits_traffic_without_network_events() {
+        let temp_dir = TempDir::new().unwrap();
+        let log_dir = temp_dir.path().to_path_buf();
+
+        let mut logger = SessionLogger::new(&log_dir, Some("quiet-test".to_string())).unwrap();
+        logger.write_event(&create_test_event()).unwrap();
+        logger.write_session_footer(None).unwrap();
+
+        let content = std::fs::read_to_string(logger.path()).unwrap();
+        assert!(!content.lines().last().unwrap().contains("traffic"));
+    }
+
     #[test]
     fn test_creates_log_directory() {
         let temp_dir = TempDir::new().unwrap();
@@ -480,4 +769,98 @@ mod tests {
         let lines: Vec<&str> = content.lines().collect();
         assert_eq!(lines.len(), 10);
     }
+
+    #[test]
+    fn test_segment_name_parse() {
+        let first = SegmentName::parse("session-20240101-abc.jsonl").unwrap();
+        assert_eq!(first.session_id, "20240101-abc");
+        assert_eq!(first.index, 0);
+        assert_eq!(first.compression, Compression::None);
+
+        let later = SegmentName::parse("session-20240101-abc.3.jsonl.zst").unwrap();
+        assert_eq!(later.session_id, "20240101-abc");
+        assert_eq!(later.index, 3);
+        assert_eq!(later.compression, Compression::Zstd);
+        assert_eq!(later.file_name(), "session-20240101-abc.3.jsonl.zst");
+
+        let gz = SegmentName::parse("session-x.jsonl.gz").unwrap();
+        assert_eq!(gz.compression, Compression::Gzip);
+        assert_eq!(gz.file_name(), "session-x.jsonl.gz");
+
+        assert!(SegmentName::parse("session-x.jsonl.zst.tmp").is_none());
+        assert!(SegmentName::parse("notes.jsonl").is_none());
+        assert!(SegmentName::parse("session-.jsonl").is_none());
+    }
+
+    #[test]
+    fn test_session_logger_rolls_over_segments() {
+        let temp_dir = TempDir::new().unwrap();
+        let log_dir = temp_dir.path().to_path_buf();
+
+        let mut logger = SessionLogger::new(&log_dir, Some("roll".to_string()))
+            .unwrap()
+            .with_max_segment_bytes(1024);
+        logger
+            .write_session_header("bash", 1, Some("Claude Code"))
+            .unwrap();
+        for _ in 0..30 {
+            logger.write_event(&create_test_event()).unwrap();
+        }
+        logger.write_session_footer(Some(0)).unwrap();
+        assert!(logger.segment_index() > 0);
+        assert_eq!(logger.session_path(), log_dir.join("session-roll.jsonl"));
+
+        let segments = session_segments(&logger.session_path());
+        assert_eq!(segments.len(), logger.segment_index() as usize + 1);
+        for (i, segment) in segments.iter().enumerate() {
+            // Lines are never split, so only a single oversized line could
+            // push a segment past the cap
+            assert!(std::fs::metadata(segment).unwrap().len() <= 1024);
+            let content = std::fs::read_to_string(segment).unwrap();
+            let header: serde_json::Value =
+                serde_json::from_str(content.lines().next().unwrap()).unwrap();
+            if i > 0 {
+                assert_eq!(header["type"], "session_start");
+                assert_eq!(header["segment"], i as u64);
+                assert_eq!(header["agent_name"], "Claude Code");
+            }
+        }
+
+        let events = open_session_log(&logger.session_path())
+            .unwrap()
+            .lines()
+            .filter(|l| serde_json::from_str::<Event>(l.as_ref().unwrap()).is_ok())
+            .count();
+        assert_eq!(events, 30);
+    }
+
+    #[test]
+    fn test_session_segments_order_and_compression() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        for name in [
+            "session-s.10.jsonl",
+            "session-s.2.jsonl.gz",
+            "session-s.jsonl.zst",
+            "session-s.2.jsonl",
+            "session-other.jsonl",
+        ] {
+            std::fs::write(dir.join(name), "").unwrap();
+        }
+
+        let segments: Vec<String> = session_segments(&dir.join("session-s.jsonl"))
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(
+            segments,
+            vec![
+                "session-s.jsonl.zst",
+                "session-s.2.jsonl",
+                "session-s.10.jsonl"
+            ]
+        );
+        assert!(session_segments(&dir.join("session-missing.jsonl")).is_empty());
+        assert!(open_session_log(&dir.join("session-missing.jsonl")).is_err());
+    }
 }
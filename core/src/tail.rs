@@ -0,0 +1,423 @@
+//! Incremental reader for JSON Lines session logs
+//!
+//! Remembers how far into a session file it has read so that polling a
+//! growing log only parses newly appended lines. When the logger rolls
+//! over to a new segment file, the tail follows it.
+
+use crate::config::Compression;
+use crate::error::{CoreError, StorageError};
+use crate::event::Event;
+use crate::storage::SegmentName;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// Identity of a file on disk, used to detect log rotation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileId {
+    dev: u64,
+    ino: u64,
+}
+
+impl FileId {
+    #[cfg(unix)]
+    fn of(metadata: &std::fs::Metadata) -> Option<Self> {
+        use std::os::unix::fs::MetadataExt;
+        Some(Self {
+            dev: metadata.dev(),
+            ino: metadata.ino(),
+        })
+    }
+
+    #[cfg(not(unix))]
+    fn of(_metadata: &std::fs::Metadata) -> Option<Self> {
+        None
+    }
+}
+
+/// Events read by a single [`SessionTail::poll`]
+#[derive(Debug, Default)]
+pub struct TailUpdate {
+    /// Newly parsed events, in file order
+    pub events: Vec<Event>,
+    /// Index of the first event in `events` within the session
+    pub first_index: usize,
+    /// The file was truncated or replaced since the previous poll, so all
+    /// previously returned events are stale and indexing restarted at zero
+    pub reset: bool,
+}
+
+/// Tail reader for a single session log.
+///
+/// Only complete (newline-terminated) lines are consumed; a partially
+/// written trailing line is picked up by the next poll. Session metadata
+/// lines and invalid lines are skipped, matching `read_session_log`.
+///
+/// Once the current file is exhausted and the next uncompressed segment
+/// (`session-{id}.{n}.jsonl`) exists, reading continues there.
+#[derive(Debug)]
+pub struct SessionTail {
+    path: PathBuf,
+    /// Segment currently read, 0 for `path` itself
+    segment: u32,
+    /// Byte offset just past the last complete line read in `segment`
+    offset: u64,
+    file_id: Option<FileId>,
+    /// Segment and byte offset of each parsed event line, indexed by event
+    /// number
+    event_offsets: Vec<(u32, u64)>,
+}
+
+impl SessionTail {
+    /// Create a tail reader positioned at the start of `path`
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            segment: 0,
+            offset: 0,
+            file_id: None,
+            event_offsets: Vec::new(),
+        }
+    }
+
+    /// Path of the tailed file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Number of events read so far
+    pub fn event_count(&self) -> usize {
+        self.event_offsets.len()
+    }
+
+    /// Byte offset up to which the current segment has been consumed
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Segment currently read, 0 for the first file
+    pub fn segment(&self) -> u32 {
+        self.segment
+    }
+
+    /// Read lines appended since the last poll.
+    ///
+    /// If the file shrank below the current offset (truncation) or was
+    /// replaced by a different file (rotation), reading restarts from the
+    /// beginning and [`TailUpdate::reset`] is set.
+    pub fn poll(&mut self) -> Result<TailUpdate, CoreError> {
+        let mut file = self.open(self.segment)?;
+        let metadata = file.metadata()?;
+        let file_id = FileId::of(&metadata);
+
+        let rotated = self.file_id.is_some() && file_id != self.file_id;
+        let truncated = metadata.len() < self.offset;
+        let reset = rotated || truncated;
+        self.file_id = file_id;
+        if reset {
+            if self.segment != 0 {
+                file = self.open(0)?;
+                self.file_id = FileId::of(&file.metadata()?);
+            }
+            self.segment = 0;
+            self.offset = 0;
+            self.event_offsets.clear();
+        }
+
+        let first_index = self.event_offsets.len();
+        let mut events = Vec::new();
+        loop {
+            let segment = self.segment;
+            file.seek(SeekFrom::Start(self.offset))?;
+            let end = read_lines(file, self.offset, None, |offset, event| {
+                self.event_offsets.push((segment, offset));
+                events.push(event);
+            })?;
+            self.offset = end;
+
+            // The logger flushes a segment before starting the next one, so
+            // once the next file exists the current one is complete
+            let Some(next) = self.segment_path(segment + 1).filter(|p| p.exists()) else {
+                break;
+            };
+            file = File::open(&next).map_err(|e| StorageError::OpenFile {
+                path: next,
+                source: e,
+            })?;
+            self.segment = segment + 1;
+            self.offset = 0;
+            self.file_id = FileId::of(&file.metadata()?);
+        }
+
+        Ok(TailUpdate {
+            events,
+            first_index,
+            reset,
+        })
+    }
+
+    /// Poll the file, then return all events from `index` onwards.
+    ///
+    /// Earlier events are located through their recorded byte offsets, so
+    /// only the requested range of the file is parsed again.
+    pub fn events_since(&mut self, index: usize) -> Result<Vec<Event>, CoreError> {
+        let update = self.poll()?;
+        if index >= self.event_offsets.len() {
+            return Ok(Vec::new());
+        }
+        if index >= update.first_index {
+            return Ok(update
+                .events
+                .into_iter()
+                .skip(index - update.first_index)
+                .collect());
+        }
+
+        let (start_segment, start) = self.event_offsets[index];
+        let (end_segment, end) = self
+            .event_offsets
+            .get(update.first_index)
+            .copied()
+            .unwrap_or((self.segment, self.offset));
+
+        let mut events = Vec::with_capacity(self.event_offsets.len() - index);
+        for segment in start_segment..=end_segment {
+            let from = if segment == start_segment { start } else { 0 };
+            let to = (segment == end_segment).then_some(end);
+            let mut file = self.open(segment)?;
+            file.seek(SeekFrom::Start(from))?;
+            read_lines(file, from, to, |_, event| events.push(event))?;
+        }
+        events.extend(update.events);
+        Ok(events)
+    }
+
+    /// Path of segment `index`, or `None` if `path` is not a session log
+    /// name and so has no further segments
+    fn segment_path(&self, index: u32) -> Option<PathBuf> {
+        if index == 0 {
+            return Some(self.path.clone());
+        }
+        let name = SegmentName::parse(self.path.file_name()?.to_str()?)?;
+        let name = SegmentName {
+            index,
+            compression: Compression::None,
+            ..name
+        };
+        Some(self.path.with_file_name(name.file_name()))
+    }
+
+    fn open(&self, segment: u32) -> Result<File, CoreError> {
+        let path = self
+            .segment_path(segment)
+            .unwrap_or_else(|| self.path.clone());
+        File::open(&path).map_err(|e| StorageError::OpenFile { path, source: e }.into())
+    }
+}
+
+/// Parse complete lines starting at byte `start` until EOF or `end`,
+/// calling `on_event` with each event's byte offset. Returns the offset
+/// just past the last complete line.
+fn read_lines(
+    file: File,
+    start: u64,
+    end: Option<u64>,
+    mut on_event: impl FnMut(u64, Event),
+) -> Result<u64, CoreError> {
+    let mut reader = BufReader::new(file);
+    let mut offset = start;
+    let mut line = Vec::new();
+
+    while end.is_none_or(|end| offset < end) {
+        line.clear();
+        let n = reader.read_until(b'\n', &mut line)?;
+        if n == 0 || line.last() != Some(&b'\n') {
+            // EOF or a line still being written
+            break;
+        }
+        if let Ok(event) = serde_json::from_slice::<Event>(line.trim_ascii()) {
+            on_event(offset, event);
+        }
+        offset += n as u64;
+    }
+
+    Ok(offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::RiskLevel;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    fn event_line(command: &str) -> String {
+        let event = Event::command(
+            command.to_string(),
+            vec![],
+            "bash".to_string(),
+            1,
+            RiskLevel::Low,
+        );
+        format!("{}\n", serde_json::to_string(&event).unwrap())
+    }
+
+    fn append(path: &Path, content: &str) {
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+    }
+
+    fn commands(events: &[Event]) -> Vec<String> {
+        events
+            .iter()
+            .map(|e| match &e.event_type {
+                crate::event::EventType::Command { command, .. } => command.clone(),
+                other => panic!("unexpected event type: {:?}", other),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_poll_reads_only_appended_lines() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("session.jsonl");
+        append(&path, r#"{"type":"session_start","session_id":"abc"}"#);
+        append(&path, "\n");
+        append(&path, &event_line("ls"));
+
+        let mut tail = SessionTail::new(&path);
+        let update = tail.poll().unwrap();
+        assert_eq!(commands(&update.events), vec!["ls"]);
+        assert_eq!(update.first_index, 0);
+        assert!(!update.reset);
+
+        let offset = tail.offset();
+        assert!(tail.poll().unwrap().events.is_empty());
+        assert_eq!(tail.offset(), offset);
+
+        append(&path, &event_line("pwd"));
+        let update = tail.poll().unwrap();
+        assert_eq!(commands(&update.events), vec!["pwd"]);
+        assert_eq!(update.first_index, 1);
+        assert_eq!(tail.event_count(), 2);
+    }
+
+    #[test]
+    fn test_poll_waits_for_complete_line() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("session.jsonl");
+        let line = event_line("echo");
+        let (head, rest) = line.split_at(20);
+
+        append(&path, head);
+        let mut tail = SessionTail::new(&path);
+        assert!(tail.poll().unwrap().events.is_empty());
+        assert_eq!(tail.offset(), 0);
+
+        append(&path, rest);
+        assert_eq!(commands(&tail.poll().unwrap().events), vec!["echo"]);
+    }
+
+    #[test]
+    fn test_poll_detects_truncation() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("session.jsonl");
+        append(&path, &event_line("ls"));
+        append(&path, &event_line("pwd"));
+
+        let mut tail = SessionTail::new(&path);
+        assert_eq!(tail.poll().unwrap().events.len(), 2);
+
+        std::fs::write(&path, event_line("whoami")).unwrap();
+        let update = tail.poll().unwrap();
+        assert!(update.reset);
+        assert_eq!(update.first_index, 0);
+        assert_eq!(commands(&update.events), vec!["whoami"]);
+        assert_eq!(tail.event_count(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_poll_detects_rotation() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("session.jsonl");
+        append(&path, &event_line("ls"));
+
+        let mut tail = SessionTail::new(&path);
+        assert_eq!(tail.poll().unwrap().events.len(), 1);
+
+        // Replace the file with a longer one so only the inode reveals it
+        std::fs::rename(&path, dir.path().join("session.jsonl.1")).unwrap();
+        append(&path, &event_line("cat"));
+        append(&path, &event_line("grep"));
+
+        let update = tail.poll().unwrap();
+        assert!(update.reset);
+        assert_eq!(commands(&update.events), vec!["cat", "grep"]);
+    }
+
+    #[test]
+    fn test_events_since_uses_recorded_offsets() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("session.jsonl");
+        for cmd in ["a", "b", "c"] {
+            append(&path, &event_line(cmd));
+        }
+
+        let mut tail = SessionTail::new(&path);
+        assert_eq!(
+            commands(&tail.events_since(0).unwrap()),
+            vec!["a", "b", "c"]
+        );
+
+        append(&path, &event_line("d"));
+        assert_eq!(
+            commands(&tail.events_since(1).unwrap()),
+            vec!["b", "c", "d"]
+        );
+        assert_eq!(commands(&tail.events_since(3).unwrap()), vec!["d"]);
+        assert!(tail.events_since(10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_poll_follows_segments() {
+        let dir = TempDir::new().unwrap();
+        let first = dir.path().join("session-seg.jsonl");
+        append(&first, &event_line("a"));
+
+        let mut tail = SessionTail::new(&first);
+        assert_eq!(commands(&tail.poll().unwrap().events), vec!["a"]);
+
+        append(&first, &event_line("b"));
+        let second = dir.path().join("session-seg.1.jsonl");
+        append(
+            &second,
+            r#"{"type":"session_start","session_id":"seg","segment":1}"#,
+        );
+        append(&second, "\n");
+        append(&second, &event_line("c"));
+
+        let update = tail.poll().unwrap();
+        assert!(!update.reset);
+        assert_eq!(update.first_index, 1);
+        assert_eq!(commands(&update.events), vec!["b", "c"]);
+        assert_eq!(tail.segment(), 1);
+
+        append(&second, &event_line("d"));
+        assert_eq!(
+            commands(&tail.events_since(0).unwrap()),
+            vec!["a", "b", "c", "d"]
+        );
+        assert_eq!(commands(&tail.events_since(2).unwrap()), vec!["c", "d"]);
+    }
+
+    #[test]
+    fn test_poll_missing_file() {
+        let mut tail = SessionTail::new("/nonexistent/session.jsonl");
+        assert!(tail.poll().is_err());
+    }
+}
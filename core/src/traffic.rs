@@ -0,0 +1,416 @@
+//! Per-session network traffic by provider
+//!
+//! Tallies a session's connections and proxied requests by destination host,
+//! then groups the tallies by [`ProviderCategory`] so sessions can be compared
+//! by how often, when and for how long they talked to LLM APIs, package
+//! registries and code hosts.
+//!
+//! The provider comes from the events, which are classified when they are
+//! recorded: by the proxy's hostname, or by the addresses the provider hosts
+//! resolved to at the time. Events recorded without one fall back to
+//! matching their host by name against the built-in catalogue, so a summary
+//! never depends on DNS answers at the time it is made.
+//!
+//! Proxied requests are counted from `proxy_request` events and socket
+//! connections from `network_close` events, which the network monitor reports
+//! once per socket (and for every socket still open when it stops). Its
+//! `network` events only mark when a destination was first seen: they are
+//! reported once per process, address and port, so repeated connections to
+//! the same endpoint would be counted once. Durations and byte counts come
+//! from `network_close` events too.
+
+use crate::detector::{ProviderCatalogue, ProviderCategory};
+use crate::event::{Event, EventType, ProviderTag};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Distinct hosts tracked per session; further hosts share one tally
+const MAX_TRACKED_HOSTS: usize = 4096;
+
+/// Host key for traffic beyond [`MAX_TRACKED_HOSTS`]
+const OVERFLOW_HOST: &str = "*";
+
+#[derive(Debug, Clone, Default)]
+struct HostTraffic {
+    requests: u64,
+    closed: u64,
+    duration_ms: u64,
+    bytes_sent: u64,
+    bytes_received: u64,
+    first: Option<DateTime<Utc>>,
+    last: Option<DateTime<Utc>>,
+}
+
+impl HostTraffic {
+    fn touch(&mut self, at: DateTime<Utc>) {
+        self.first = Some(self.first.map_or(at, |first| first.min(at)));
+        self.last = Some(self.last.map_or(at, |last| last.max(at)));
+    }
+}
+
+/// Running per-host traffic tallies for one session
+#[derive(Debug, Clone, Default)]
+pub struct TrafficStats {
+    hosts: HashMap<(String, Option<ProviderTag>), HostTraffic>,
+}
+
+impl TrafficStats {
+    /// Create empty tallies
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count an event if it is network traffic. Returns whether it was.
+    pub fn observe(&mut self, event: &Event) -> bool {
+        match &event.event_type {
+            EventType::Network { host, provider, .. } => {
+                self.record_open(host, provider.as_ref(), event.timestamp)
+            }
+            EventType::ProxyRequest { host, provider, .. } => {
+                self.record_request(host, provider.as_ref(), event.timestamp)
+            }
+            EventType::NetworkClose {
+                host,
+                duration_ms,
+                bytes_sent,
+                bytes_received,
+                provider,
+                ..
+            } => {
+                self.record_close(
+                    host,
+                    provider.as_ref(),
+                    event.timestamp,
+                    *duration_ms,
+                    bytes_sent.unwrap_or(0),
+                    bytes_received.unwrap_or(0),
+                );
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// Count a request made to `host` through the egress proxy
+    pub fn record_request(
+        &mut self,
+        host: &str,
+        provider: Option<&ProviderTag>,
+        at: DateTime<Utc>,
+    ) {
+        let entry = self.entry(host, provider);
+        entry.requests += 1;
+        entry.touch(at);
+    }
+
+    /// Note that a connection to `host` was seen opening. It is counted by
+    /// [`Self::record_close`], once per socket.
+    pub fn record_open(&mut self, host: &str, provider: Option<&ProviderTag>, at: DateTime<Utc>) {
+        self.entry(host, provider).touch(at);
+    }
+
+    /// Count a connection to `host` that closed after `duration_ms`
+    pub fn record_close(
+        &mut self,
+        host: &str,
+        provider: Option<&ProviderTag>,
+        at: DateTime<Utc>,
+        duration_ms: u64,
+        bytes_sent: u64,
+        bytes_received: u64,
+    ) {
+        let entry = self.entry(host, provider);
+        entry.requests += 1;
+        entry.closed += 1;
+        entry.duration_ms = entry.duration_ms.saturating_add(duration_ms);
+        entry.bytes_sent = entry.bytes_sent.saturating_add(bytes_sent);
+        entry.bytes_received = entry.bytes_received.saturating_add(bytes_received);
+        entry.touch(at);
+    }
+
+    /// Whether no traffic has been seen
+    pub fn is_empty(&self) -> bool {
+        self.hosts.is_empty()
+    }
+
+    /// Group the tallies by provider category
+    pub fn summary(&self) -> TrafficSummary {
+        let mut categories: BTreeMap<ProviderCategory, CategoryTraffic> = BTreeMap::new();
+        for ((host, provider), traffic) in &self.hosts {
+            let provider = provider.clone().or_else(|| {
+                ProviderCatalogue::builtin()
+                    .classify(host)
+                    .map(ProviderTag::from)
+            });
+            let category = provider
+                .as_ref()
+                .map(|p| p.category)
+                .unwrap_or(ProviderCategory::Other);
+            let summary = categories.entry(category).or_default();
+            summary.requests += traffic.requests;
+            summary.closed += traffic.closed;
+            summary.duration_ms = summary.duration_ms.saturating_add(traffic.duration_ms);
+            summary.bytes_sent = summary.bytes_sent.saturating_add(traffic.bytes_sent);
+            summary.bytes_received = summary
+                .bytes_received
+                .saturating_add(traffic.bytes_received);
+            summary.first = min_time(summary.first, traffic.first);
+            summary.last = max_time(summary.last, traffic.last);
+            if let Some(provider) = provider {
+                *summary.providers.entry(provider.name).or_default() += traffic.requests;
+            }
+        }
+        TrafficSummary(categories)
+    }
+
+    fn entry(&mut self, host: &str, provider: Option<&ProviderTag>) -> &mut HostTraffic {
+        let mut key = (host.to_string(), provider.cloned());
+        if self.hosts.len() >= MAX_TRACKED_HOSTS && !self.hosts.contains_key(&key) {
+            key.0 = OVERFLOW_HOST.to_string();
+        }
+        self.hosts.entry(key).or_default()
+    }
+}
+
+fn min_time(a: Option<DateTime<Utc>>, b: Option<DateTime<Utc>>) -> Option<DateTime<Utc>> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+fn max_time(a: Option<DateTime<Utc>>, b: Option<DateTime<Utc>>) -> Option<DateTime<Utc>> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.max(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Traffic to one provider category over a session
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CategoryTraffic {
+    /// Connections made and requests sent through the egress proxy
+    pub requests: u64,
+    /// Connections seen closing
+    pub closed: u64,
+    /// Total time the closed connections were open
+    pub duration_ms: u64,
+    /// Bytes sent over the closed connections
+    pub bytes_sent: u64,
+    /// Bytes received over the closed connections
+    pub bytes_received: u64,
+    /// First time traffic was seen
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first: Option<DateTime<Utc>>,
+    /// Last time traffic was seen
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last: Option<DateTime<Utc>>,
+    /// Requests per provider name; empty for [`ProviderCategory::Other`]
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub providers: BTreeMap<String, u64>,
+}
+
+impl CategoryTraffic {
+    /// Mean time a closed connection was open
+    pub fn mean_duration_ms(&self) -> Option<u64> {
+        (self.closed > 0).then(|| self.duration_ms / self.closed)
+    }
+}
+
+/// A session's traffic grouped by provider category, in the order of
+/// [`ProviderCategory::ALL`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct TrafficSummary(pub BTreeMap<ProviderCategory, CategoryTraffic>);
+
+impl TrafficSummary {
+    /// Traffic for one category, if any was seen
+    pub fn get(&self, category: ProviderCategory) -> Option<&CategoryTraffic> {
+        self.0.get(&category)
+    }
+
+    /// Whether no traffic was seen
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Categories with traffic
+    pub fn iter(&self) -> impl Iterator<Item = (ProviderCategory, &CategoryTraffic)> {
+        self.0
+            .iter()
+            .map(|(category, traffic)| (*category, traffic))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::RiskLevel;
+    use chrono::TimeZone;
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        Utc.timestamp_opt(1_700_000_000 + secs, 0).unwrap()
+    }
+
+    fn network(host: &str, secs: i64) -> Event {
+        let mut event = Event::new(
+            EventType::Network {
+                host: host.to_string(),
+                port: 443,
+                protocol: "tcp".to_string(),
+                provider: None,
+            },
+            "agent".to_string(),
+            1,
+            RiskLevel::Medium,
+        );
+        event.timestamp = at(secs);
+        event
+    }
+
+    fn close(host: &str, secs: i64, duration_ms: u64) -> Event {
+        let mut event = Event::new(
+            EventType::NetworkClose {
+                host: host.to_string(),
+                port: 443,
+                protocol: "tcp".to_string(),
+                duration_ms,
+                bytes_sent: Some(100),
+                bytes_received: Some(1000),
+                provider: None,
+            },
+            "agent".to_string(),
+            1,
+            RiskLevel::Low,
+        );
+        event.timestamp = at(secs);
+        event
+    }
+
+    #[test]
+    fn test_summary_groups_by_category() {
+        let mut stats = TrafficStats::new();
+        assert!(stats.observe(&network("api.anthropic.com", 0)));
+        assert!(stats.observe(&network("api.openai.com", 5)));
+        assert!(stats.observe(&network("registry.npmjs.org", 3)));
+        assert!(stats.observe(&close("api.anthropic.com", 12, 2000)));
+        assert!(stats.observe(&close("api.anthropic.com", 20, 4000)));
+        assert!(stats.observe(&close("api.openai.com", 8, 0)));
+        assert!(stats.observe(&close("registry.npmjs.org", 4, 0)));
+        assert!(stats.observe(&close("example.com", 4, 0)));
+        assert!(!stats.observe(&Event::command(
+            "ls".to_string(),
+            vec![],
+            "agent".to_string(),
+            1,
+            RiskLevel::Low
+        )));
+
+        let summary = stats.summary();
+        let llm = summary.get(ProviderCategory::Llm).unwrap();
+        assert_eq!(llm.requests, 3);
+        assert_eq!(llm.closed, 3);
+        assert_eq!(llm.duration_ms, 6000);
+        assert_eq!(llm.mean_duration_ms(), Some(2000));
+        assert_eq!(llm.bytes_sent, 300);
+        assert_eq!(llm.bytes_received, 3000);
+        assert_eq!(llm.first, Some(at(0)));
+        assert_eq!(llm.last, Some(at(20)));
+        assert_eq!(llm.providers.get("Anthropic"), Some(&2));
+        assert_eq!(llm.providers.get("OpenAI"), Some(&1));
+
+        let registry = summary.get(ProviderCategory::PackageRegistry).unwrap();
+        assert_eq!(registry.requests, 1);
+        assert_eq!(registry.first, Some(at(3)));
+
+        let other = summary.get(ProviderCategory::Other).unwrap();
+        assert_eq!(other.requests, 1);
+        assert!(other.providers.is_empty());
+        assert!(summary.get(ProviderCategory::Vcs).is_none());
+    }
+
+    #[test]
+    fn test_open_connections_are_not_requests() {
+        let mut stats = TrafficStats::new();
+        assert!(stats.observe(&network("api.anthropic.com", 0)));
+
+        let summary = stats.summary();
+        let llm = summary.get(ProviderCategory::Llm).unwrap();
+        assert_eq!(llm.requests, 0);
+        assert_eq!(llm.first, Some(at(0)));
+        assert_eq!(llm.mean_duration_ms(), None);
+    }
+
+    #[test]
+    fn test_proxy_requests_are_counted() {
+        let mut stats = TrafficStats::new();
+        let event = Event::new(
+            EventType::ProxyRequest {
+                method: "CONNECT".to_string(),
+                host: "api.openai.com".to_string(),
+                port: 443,
+                blocked: false,
+                provider: None,
+            },
+            "agent".to_string(),
+            1,
+            RiskLevel::Medium,
+        );
+        assert!(stats.observe(&event));
+
+        let summary = stats.summary();
+        assert_eq!(summary.get(ProviderCategory::Llm).unwrap().requests, 1);
+    }
+
+    #[test]
+    fn test_summary_uses_provider_recorded_on_event() {
+        let tag = ProviderTag {
+            name: "OpenAI".to_string(),
+            category: ProviderCategory::Llm,
+        };
+        let mut stats = TrafficStats::new();
+        let mut tagged = close("203.0.113.9", 1, 100);
+        if let EventType::NetworkClose { provider, .. } = &mut tagged.event_type {
+            *provider = Some(tag);
+        }
+        assert!(stats.observe(&tagged));
+        // An address without a recorded provider is not looked up again
+        assert!(stats.observe(&close("203.0.113.10", 2, 100)));
+
+        let summary = stats.summary();
+        let llm = summary.get(ProviderCategory::Llm).unwrap();
+        assert_eq!(llm.requests, 1);
+        assert_eq!(llm.providers.get("OpenAI"), Some(&1));
+        assert_eq!(summary.get(ProviderCategory::Other).unwrap().requests, 1);
+    }
+
+    #[test]
+    fn test_host_count_is_capped() {
+        let mut stats = TrafficStats::new();
+        for i in 0..MAX_TRACKED_HOSTS + 10 {
+            stats.record_request(&format!("host{}.example.com", i), None, at(0));
+        }
+        assert_eq!(stats.hosts.len(), MAX_TRACKED_HOSTS + 1);
+
+        let summary = stats.summary();
+        assert_eq!(
+            summary.get(ProviderCategory::Other).unwrap().requests,
+            (MAX_TRACKED_HOSTS + 10) as u64
+        );
+    }
+
+    #[test]
+    fn test_summary_serializes_by_category() {
+        let mut stats = TrafficStats::new();
+        stats.record_request("github.com", None, at(0));
+        let summary = stats.summary();
+
+        let json = serde_json::to_value(&summary).unwrap();
+        assert_eq!(json["vcs"]["requests"], 1);
+        assert_eq!(json["vcs"]["providers"]["GitHub"], 1);
+
+        let parsed: TrafficSummary = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, summary);
+    }
+}
@@ -3,6 +3,7 @@
 //! Contains common types used across multiple modules.
 
 use serde::{Deserialize, Serialize};
+use std::sync::{Arc, RwLock};
 
 /// Risk level for categorizing event severity
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
@@ -62,8 +63,26 @@ impl std::fmt::Display for RiskLevel {
     }
 }
 
+impl std::str::FromStr for RiskLevel {
+    type Err = String;
+
+    /// Parse a lowercase risk level name as used in config files
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "low" => Ok(RiskLevel::Low),
+            "medium" => Ok(RiskLevel::Medium),
+            "high" => Ok(RiskLevel::High),
+            "critical" => Ok(RiskLevel::Critical),
+            other => Err(format!(
+                "unknown risk level '{}' (expected low, medium, high or critical)",
+                other
+            )),
+        }
+    }
+}
+
 /// File system action types
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum FileAction {
     Read,
@@ -92,6 +111,8 @@ pub enum ProcessAction {
     Start,
     Exit,
     Fork,
+    /// Left the monitored process tree, e.g. by daemonizing
+    Detach,
 }
 
 /// Session action types
@@ -102,6 +123,101 @@ pub enum SessionAction {
     End,
 }
 
+/// Resource measured across a monitored process tree
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResourceMetric {
+    /// Number of live processes in the tree
+    Processes,
+    /// Processes spawned within the rate window
+    SpawnRate,
+    /// CPU usage in percent of one core
+    Cpu,
+    /// Total resident memory in bytes
+    Memory,
+    /// Resident memory gained within the rate window, in bytes
+    MemoryGrowth,
+    /// Total thread count
+    Threads,
+    /// Total open file descriptors
+    OpenFiles,
+}
+
+impl ResourceMetric {
+    /// Format a value of this metric with its unit
+    pub fn format_value(&self, value: u64) -> String {
+        match self {
+            ResourceMetric::Cpu => format!("{}%", value),
+            ResourceMetric::Memory | ResourceMetric::MemoryGrowth => {
+                format!("{:.1}MB", value as f64 / (1024.0 * 1024.0))
+            }
+            _ => value.to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for ResourceMetric {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResourceMetric::Processes => write!(f, "processes"),
+            ResourceMetric::SpawnRate => write!(f, "spawn_rate"),
+            ResourceMetric::Cpu => write!(f, "cpu"),
+            ResourceMetric::Memory => write!(f, "memory"),
+            ResourceMetric::MemoryGrowth => write!(f, "memory_growth"),
+            ResourceMetric::Threads => write!(f, "threads"),
+            ResourceMetric::OpenFiles => write!(f, "open_files"),
+        }
+    }
+}
+
+/// Behaviour that deviated from an agent's learned baseline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnomalyKind {
+    /// Contacted hosts not seen in earlier sessions
+    NewHosts,
+    /// Ran commands not seen in earlier sessions
+    NewCommands,
+    /// Touched files faster than usual
+    FileRate,
+    /// Session running longer than usual
+    SessionLength,
+}
+
+impl std::fmt::Display for AnomalyKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AnomalyKind::NewHosts => write!(f, "new_hosts"),
+            AnomalyKind::NewCommands => write!(f, "new_commands"),
+            AnomalyKind::FileRate => write!(f, "file_rate"),
+            AnomalyKind::SessionLength => write!(f, "session_length"),
+        }
+    }
+}
+
+/// Mechanism the process tracker used to observe a process
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrackerBackend {
+    /// Linux netlink proc connector, pushed by the kernel on every
+    /// fork, exec and exit
+    ProcConnector,
+    /// ptrace with fork, clone and exec tracing
+    Ptrace,
+    /// Periodic scan of the process table
+    Polling,
+}
+
+impl std::fmt::Display for TrackerBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TrackerBackend::ProcConnector => write!(f, "proc_connector"),
+            TrackerBackend::Ptrace => write!(f, "ptrace"),
+            TrackerBackend::Polling => write!(f, "polling"),
+        }
+    }
+}
+
 /// Trait for monitoring subsystem lifecycle management.
 ///
 /// Implemented by `FileSystemWatcher`, `NetworkMonitor`, and `ProcessTracker`
@@ -116,3 +232,48 @@ pub trait MonitoringSubsystem: Send {
     /// Check if the subsystem is currently running
     fn is_running(&self) -> bool;
 }
+
+/// A value shared with running subsystem threads that can be replaced atomically.
+///
+/// Readers take a cheap snapshot with [`Swappable::load`] and keep using it for
+/// the current unit of work, so a concurrent [`Swappable::store`] never
+/// exposes a half-updated value.
+#[derive(Debug)]
+pub struct Swappable<T> {
+    inner: Arc<RwLock<Arc<T>>>,
+}
+
+impl<T> Clone for Swappable<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+impl<T: Default> Default for Swappable<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T> Swappable<T> {
+    /// Wrap a value in a new handle
+    pub fn new(value: T) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(Arc::new(value))),
+        }
+    }
+
+    /// Snapshot of the current value
+    pub fn load(&self) -> Arc<T> {
+        let guard = self.inner.read().unwrap_or_else(|p| p.into_inner());
+        Arc::clone(&guard)
+    }
+
+    /// Replace the value seen by all clones of this handle
+    pub fn store(&self, value: T) {
+        let mut guard = self.inner.write().unwrap_or_else(|p| p.into_inner());
+        *guard = Arc::new(value);
+    }
+}